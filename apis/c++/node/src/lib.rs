@@ -6,7 +6,13 @@ use chrono::DateTime;
 use dora_node_api::{
     self, Event, EventStream, Metadata as DoraMetadata,
     MetadataParameters as DoraMetadataParameters, Parameter as DoraParameter,
-    arrow::array::{AsArray, UInt8Array},
+    arrow::{
+        array::{AsArray, UInt8Array},
+        datatypes::{
+            ArrowPrimitiveType, Float32Type, Float64Type, Int8Type, Int16Type, Int32Type,
+            Int64Type, UInt8Type, UInt16Type, UInt32Type, UInt64Type,
+        },
+    },
     merged::{MergeExternal, MergedEvent},
 };
 use eyre::{Result as EyreResult, bail, eyre};
@@ -64,6 +70,19 @@ mod ffi {
         Timestamp,
     }
 
+    enum LoanedDataType {
+        UInt8,
+        UInt16,
+        UInt32,
+        UInt64,
+        Int8,
+        Int16,
+        Int32,
+        Int64,
+        Float32,
+        Float64,
+    }
+
     pub struct CombinedEvents {
         events: Box<MergedEvents>,
     }
@@ -80,6 +99,7 @@ mod ffi {
         type MergedDoraEvent;
         type Metadata;
         type DataSampleHandle;
+        type LoanedOutput;
 
         fn init_dora_node() -> Result<DoraNode>;
         fn init_dora_node_from_id(node_id: String) -> Result<DoraNode>;
@@ -181,6 +201,34 @@ mod ffi {
             sample: Box<DataSampleHandle>,
             metadata: Box<Metadata>,
         ) -> DoraResult;
+
+        // Loaned output API
+        fn loan_output(
+            output_sender: &mut Box<OutputSender>,
+            id: String,
+            data_type: LoanedDataType,
+            len: usize,
+        ) -> Result<Box<LoanedOutput>>;
+        fn loaned_output_bytes(loan: &mut Box<LoanedOutput>) -> &mut [u8];
+        fn loaned_output_as_u8(loan: &mut Box<LoanedOutput>) -> Result<&mut [u8]>;
+        fn loaned_output_as_u16(loan: &mut Box<LoanedOutput>) -> Result<&mut [u16]>;
+        fn loaned_output_as_u32(loan: &mut Box<LoanedOutput>) -> Result<&mut [u32]>;
+        fn loaned_output_as_u64(loan: &mut Box<LoanedOutput>) -> Result<&mut [u64]>;
+        fn loaned_output_as_i8(loan: &mut Box<LoanedOutput>) -> Result<&mut [i8]>;
+        fn loaned_output_as_i16(loan: &mut Box<LoanedOutput>) -> Result<&mut [i16]>;
+        fn loaned_output_as_i32(loan: &mut Box<LoanedOutput>) -> Result<&mut [i32]>;
+        fn loaned_output_as_i64(loan: &mut Box<LoanedOutput>) -> Result<&mut [i64]>;
+        fn loaned_output_as_f32(loan: &mut Box<LoanedOutput>) -> Result<&mut [f32]>;
+        fn loaned_output_as_f64(loan: &mut Box<LoanedOutput>) -> Result<&mut [f64]>;
+        fn send_loaned_output(
+            output_sender: &mut Box<OutputSender>,
+            loan: Box<LoanedOutput>,
+        ) -> DoraResult;
+        fn send_loaned_output_with_metadata(
+            output_sender: &mut Box<OutputSender>,
+            loan: Box<LoanedOutput>,
+            metadata: Box<Metadata>,
+        ) -> DoraResult;
     }
 }
 
@@ -782,6 +830,102 @@ fn send_data_sample_internal(
     ffi::DoraResult { error }
 }
 
+/// Opaque handle to a buffer for `len` values of a primitive type.
+///
+/// Unlike [`DataSampleHandle`], the data is sent as a typed Arrow array. Write
+/// the values through one of the `loaned_output_as_*` spans, then pass the
+/// handle to [`send_loaned_output`]. The spans are invalid after sending.
+pub struct LoanedOutput(dora_node_api::LoanedOutput);
+
+/// Loan a buffer for `len` values of the given type.
+///
+/// Uses shared memory when the buffer is at least 4096 bytes, so sending it
+/// does not copy the data.
+fn loan_output(
+    sender: &mut Box<OutputSender>,
+    id: String,
+    data_type: ffi::LoanedDataType,
+    len: usize,
+) -> eyre::Result<Box<LoanedOutput>> {
+    use dora_node_api::arrow::datatypes::DataType;
+    let data_type = match data_type {
+        ffi::LoanedDataType::UInt8 => DataType::UInt8,
+        ffi::LoanedDataType::UInt16 => DataType::UInt16,
+        ffi::LoanedDataType::UInt32 => DataType::UInt32,
+        ffi::LoanedDataType::UInt64 => DataType::UInt64,
+        ffi::LoanedDataType::Int8 => DataType::Int8,
+        ffi::LoanedDataType::Int16 => DataType::Int16,
+        ffi::LoanedDataType::Int32 => DataType::Int32,
+        ffi::LoanedDataType::Int64 => DataType::Int64,
+        ffi::LoanedDataType::Float32 => DataType::Float32,
+        ffi::LoanedDataType::Float64 => DataType::Float64,
+        _ => bail!("unknown loaned data type"),
+    };
+    let loan = sender.0.loan_output(id.into(), data_type, len)?;
+    Ok(Box::new(LoanedOutput(loan)))
+}
+
+/// Returns the raw bytes of the loaned buffer.
+fn loaned_output_bytes(loan: &mut Box<LoanedOutput>) -> &mut [u8] {
+    &mut loan.0
+}
+
+macro_rules! loaned_output_as {
+    ($($name:ident: $arrow_type:ident,)*) => {
+        $(
+            /// Returns the values of the loaned buffer.
+            ///
+            /// Fails if the buffer was loaned for a different type.
+            fn $name(
+                loan: &mut Box<LoanedOutput>,
+            ) -> eyre::Result<&mut [<$arrow_type as ArrowPrimitiveType>::Native]> {
+                loan.0.values_mut::<$arrow_type>()
+            }
+        )*
+    };
+}
+
+loaned_output_as! {
+    loaned_output_as_u8: UInt8Type,
+    loaned_output_as_u16: UInt16Type,
+    loaned_output_as_u32: UInt32Type,
+    loaned_output_as_u64: UInt64Type,
+    loaned_output_as_i8: Int8Type,
+    loaned_output_as_i16: Int16Type,
+    loaned_output_as_i32: Int32Type,
+    loaned_output_as_i64: Int64Type,
+    loaned_output_as_f32: Float32Type,
+    loaned_output_as_f64: Float64Type,
+}
+
+/// Send a loaned buffer as output without copying it.
+fn send_loaned_output(sender: &mut Box<OutputSender>, loan: Box<LoanedOutput>) -> ffi::DoraResult {
+    send_loaned_output_internal(sender, loan.0, Default::default())
+}
+
+fn send_loaned_output_with_metadata(
+    sender: &mut Box<OutputSender>,
+    loan: Box<LoanedOutput>,
+    metadata: Box<Metadata>,
+) -> ffi::DoraResult {
+    let metadata = *metadata;
+    let parameters = metadata.into_parameters();
+    send_loaned_output_internal(sender, loan.0, parameters)
+}
+
+fn send_loaned_output_internal(
+    sender: &mut Box<OutputSender>,
+    loan: dora_node_api::LoanedOutput,
+    metadata: DoraMetadataParameters,
+) -> ffi::DoraResult {
+    let result = sender.0.send_loaned_output(loan, metadata);
+    let error = match result {
+        Ok(()) => String::new(),
+        Err(err) => format!("{err:?}"),
+    };
+    ffi::DoraResult { error }
+}
+
 pub struct MergedEvents {
     events: Option<Box<dyn Stream<Item = MergedEvent<ExternalEvent>> + Unpin>>,
     next_id: u32,
@@ -65,6 +65,42 @@ class Enum:
     def __len__() -> int:
         """Return the number of members (no aliases)"""
 
+@typing.final
+class GoalHandle:
+    """Identifies an action goal when sending feedback or the result through
+    `node.send_feedback` and `node.finish_goal`.
+    """
+
+@typing.final
+class LoanedOutput:
+    """A buffer for an output message, created through `node.loan_output`.
+
+    Its content is written through views, e.g. `as_numpy()`. The views are
+    released when the buffer is sent, so they can't be used afterwards.
+    """
+
+    def as_arrow(self) -> pyarrow.Buffer:
+        """Returns a mutable `pyarrow.Buffer` that views the buffer.
+
+        The buffer must be deleted before sending the loaned output.
+        """
+
+    def as_memoryview(self) -> memoryview:
+        """Returns a writable `memoryview` of the raw bytes of the buffer."""
+
+    def as_numpy(self) -> typing.Any:
+        """Returns a writable numpy array that views the buffer.
+
+        The array must be deleted before sending the buffer.
+        """
+
+    @property
+    def data_type(self) -> pyarrow.DataType:
+        """The Arrow data type of the values."""
+
+    def __len__(self) -> int:
+        """Return len(self)."""
+
 @typing.final
 class Node:
     """The custom node API lets you integrate `dora` into your application.
@@ -92,6 +128,44 @@ class Node:
         ```
         """
 
+    def call(
+        self, service: str, data: pyarrow.Array, timeout: float, metadata: dict = None
+    ) -> int:
+        """`call` sends a request to a service of another node.
+
+        The service is given as `<node>/<service>` and must be listed in the `calls`
+        field of this node. The reply is received as `REPLY` event with the returned
+        request ID. If no reply arrives within `timeout` seconds, the `REPLY` event
+        contains an `error` instead of a `value`.
+
+        ```python
+        Args:
+        service: str,
+        data: pyarrow.Array,
+        timeout: float,
+        metadata: Option[Dict],
+        ```
+
+        ex:
+
+        ```python
+        request_id = node.call("planner/plan_path", pa.array([1.0, 2.5]), timeout=1.0)
+        ```
+        """
+
+    def cancel_goal(self, action: str, goal_id: int) -> None:
+        """`cancel_goal` asks the server of an action to cancel a goal.
+
+        The goal still finishes with a `GOAL_RESULT` event, which has the status
+        `"canceled"` if the server stopped working on it.
+
+        ```python
+        Args:
+        action: str,
+        goal_id: int,
+        ```
+        """
+
     def dataflow_descriptor(self) -> dict:
         """Returns the full dataflow descriptor that this node is part of.
 
@@ -101,6 +175,82 @@ class Node:
     def dataflow_id(self) -> str:
         """Returns the dataflow id."""
 
+    def extend_stop_timeout(self, duration: float) -> None:
+        """`extend_stop_timeout` requests more time to exit after this node received a `STOP` event.
+
+        The daemon terminates nodes that don't exit within their `stop_timeout`. This
+        restarts the timeout with the given duration in seconds, e.g. to finish
+        flushing files.
+
+        ```python
+        node.extend_stop_timeout(5.0)
+        ```
+        """
+
+    def finish_goal(
+        self,
+        goal: dora.GoalHandle,
+        status: str,
+        data: pyarrow.Array,
+        metadata: dict = None,
+    ) -> None:
+        """`finish_goal` sends the final result of a goal that was received as `GOAL` event.
+
+        The status is one of `"succeeded"`, `"aborted"`, or `"canceled"`.
+
+        ```python
+        Args:
+        goal: GoalHandle,
+        status: str,
+        data: pyarrow.Array,
+        metadata: Option[Dict],
+        ```
+
+        ex:
+
+        ```python
+        node.finish_goal(goal, "succeeded", pa.array([4.0, 2.0]))
+        ```
+        """
+
+    def loan_output(
+        self, output_id: str, data_type: pyarrow.DataType, length: int
+    ) -> dora.LoanedOutput:
+        """`loan_output` allocates a buffer for `length` values of the given type,
+        which is sent without copying through `send_loaned_output`.
+
+        The buffer is placed in shared memory if it's large enough. Fill it through
+        a writable view, e.g. `as_numpy()`, before sending it.
+
+        ```python
+        Args:
+        output_id: str,
+        data_type: pyarrow.DataType,
+        length: int,
+        ```
+
+        ex:
+
+        ```python
+        loan = node.loan_output("image", pa.uint8(), height * width * 3)
+        frame = loan.as_numpy().reshape((height, width, 3))
+        frame[:] = 255
+        del frame
+        node.send_loaned_output(loan)
+        ```
+        """
+
+    def mark_ready(self) -> None:
+        """`mark_ready` reports that this node finished its initialization.
+
+        Nodes that list this node with the `ready` condition in their `depends_on`
+        field are only spawned after this call.
+
+        ```python
+        node.mark_ready()
+        ```
+        """
+
     def merge_external_events(self, subscription: dora.Ros2Subscription) -> None:
         """Merge an external event stream with dora main loop.
         This currently only work with ROS2.
@@ -147,6 +297,84 @@ class Node:
         You can also iterate over the event stream with a loop
         """
 
+    def reply(
+        self, reply_token: dora.ReplyToken, data: pyarrow.Array, metadata: dict = None
+    ) -> None:
+        """`reply` answers a service request that was received as `REQUEST` event.
+
+        ```python
+        Args:
+        reply_token: ReplyToken,
+        data: pyarrow.Array,
+        metadata: Option[Dict],
+        ```
+
+        ex:
+
+        ```python
+        if event["type"] == "REQUEST":
+            node.reply(event["reply_token"], pa.array([0.0, 1.0, 2.5]))
+        ```
+        """
+
+    def send_feedback(
+        self, goal: dora.GoalHandle, data: pyarrow.Array, metadata: dict = None
+    ) -> None:
+        """`send_feedback` reports progress on a goal that was received as `GOAL` event.
+
+        ```python
+        Args:
+        goal: GoalHandle,
+        data: pyarrow.Array,
+        metadata: Option[Dict],
+        ```
+
+        ex:
+
+        ```python
+        if event["type"] == "GOAL":
+            node.send_feedback(event["goal"], pa.array([0.5]))
+        ```
+        """
+
+    def send_goal(
+        self, action: str, data: pyarrow.Array, metadata: dict = None
+    ) -> int:
+        """`send_goal` sends a goal to an action of another node and returns the goal ID.
+
+        The action must be listed in the `calls` of this node as `<node>/<action>`.
+        Feedback arrives as `FEEDBACK` events and the final result as `GOAL_RESULT`
+        event, both with a `goal_id` field.
+
+        ```python
+        Args:
+        action: str,
+        data: pyarrow.Array,
+        metadata: Option[Dict],
+        ```
+
+        ex:
+
+        ```python
+        goal_id = node.send_goal("navigator/navigate_to", pa.array([4.0, 2.0]))
+        ```
+        """
+
+    def send_loaned_output(
+        self, loan: dora.LoanedOutput, metadata: dict = None
+    ) -> None:
+        """`send_loaned_output` sends a buffer from `loan_output` on its output.
+
+        Fails if a numpy array or pyarrow buffer that views the loaned output is
+        still alive, so delete them first. Views can't be used after sending.
+
+        ```python
+        Args:
+        loan: dora.LoanedOutput,
+        metadata: Option[Dict],
+        ```
+        """
+
     def send_output(
         self, output_id: str, data: pyarrow.Array, metadata: dict = None
     ) -> None:
@@ -178,6 +406,10 @@ class Node:
     def __str__(self) -> str:
         """Return str(self)."""
 
+@typing.final
+class ReplyToken:
+    """Identifies a service request when replying to it through `node.reply`."""
+
 @typing.final
 class Ros2Context:
     """ROS2 Context holding all messages definition for receiving and sending messages to ROS2.
@@ -12,11 +12,14 @@ use dora_node_api::dora_core::config::NodeId;
 use dora_node_api::dora_core::descriptor::source_is_url;
 use dora_node_api::merged::{MergeExternalSend, MergedEvent};
 use dora_node_api::{DataflowId, DoraNode, EventStream, TryRecvError, init_tracing};
-use dora_operator_api_python::{DelayedCleanup, NodeCleanupHandle, PyEvent, pydict_to_metadata};
+use dora_operator_api_python::{
+    DelayedCleanup, NodeCleanupHandle, PyEvent, PyGoalHandle, PyReplyToken, pydict_to_metadata,
+};
 use dora_ros2_bridge_python::Ros2Subscription;
 use eyre::{Context, ContextCompat};
 
 use futures::{Stream, StreamExt};
+use pyo3::exceptions::PyValueError;
 use pyo3::prelude::*;
 use pyo3::types::{PyBytes, PyDict};
 use pyo3_special_method_derive::{Dict, Dir, Repr, Str};
@@ -108,6 +111,126 @@ def basicConfig(*pargs, **kwargs):
 
     Ok(())
 }
+/// A buffer for an output message, created through `node.loan_output`.
+///
+/// Its content is written through views, e.g. `as_numpy()`. The views are
+/// released when the buffer is sent, so they can't be used afterwards.
+#[pyclass(name = "LoanedOutput")]
+pub struct PyLoanedOutput {
+    loan: Option<dora_node_api::LoanedOutput>,
+    /// The `memoryview`s that were handed out for the buffer.
+    views: Vec<PyObject>,
+}
+
+#[pymethods]
+impl PyLoanedOutput {
+    /// The Arrow data type of the values.
+    ///
+    /// :rtype: pyarrow.DataType
+    #[getter]
+    fn data_type(&self, py: Python) -> PyResult<PyObject> {
+        self.loan()?.data_type().to_pyarrow(py)
+    }
+
+    /// Returns the number of values in the buffer.
+    fn __len__(&self) -> PyResult<usize> {
+        Ok(self.loan()?.len())
+    }
+
+    /// Returns a writable `memoryview` of the raw bytes of the buffer.
+    ///
+    /// :rtype: memoryview
+    fn as_memoryview(&mut self, py: Python) -> PyResult<PyObject> {
+        /// Flag for a writable memoryview, see `PyBUF_WRITE` in CPython.
+        const PYBUF_WRITE: std::os::raw::c_int = 0x200;
+
+        let data: &mut [u8] = self
+            .loan
+            .as_mut()
+            .ok_or_else(|| PyValueError::new_err("loaned output was already sent"))?;
+        // SAFETY: the buffer outlives the view because the view is released before the
+        // buffer is sent or dropped
+        let view = unsafe {
+            let ptr = pyo3::ffi::PyMemoryView_FromMemory(
+                data.as_mut_ptr().cast(),
+                data.len() as pyo3::ffi::Py_ssize_t,
+                PYBUF_WRITE,
+            );
+            Bound::from_owned_ptr_or_err(py, ptr)?
+        };
+        self.views.push(view.clone().unbind());
+        Ok(view.unbind())
+    }
+
+    /// Returns a writable numpy array that views the buffer.
+    ///
+    /// The array must be deleted before sending the buffer.
+    ///
+    /// :rtype: numpy.ndarray
+    fn as_numpy(&mut self, py: Python) -> PyResult<PyObject> {
+        let dtype = self
+            .loan()?
+            .data_type()
+            .to_pyarrow(py)?
+            .call_method0(py, "to_pandas_dtype")?;
+        let view = self.as_memoryview(py)?;
+        let array = py
+            .import("numpy")?
+            .call_method1("frombuffer", (view, dtype))?;
+        Ok(array.unbind())
+    }
+
+    /// Returns a mutable `pyarrow.Buffer` that views the buffer.
+    ///
+    /// The buffer must be deleted before sending the loaned output.
+    ///
+    /// :rtype: pyarrow.Buffer
+    fn as_arrow(&mut self, py: Python) -> PyResult<PyObject> {
+        let view = self.as_memoryview(py)?;
+        let buffer = py.import("pyarrow")?.call_method1("py_buffer", (view,))?;
+        Ok(buffer.unbind())
+    }
+}
+
+impl PyLoanedOutput {
+    fn loan(&self) -> PyResult<&dora_node_api::LoanedOutput> {
+        self.loan
+            .as_ref()
+            .ok_or_else(|| PyValueError::new_err("loaned output was already sent"))
+    }
+
+    /// Takes the buffer for sending after releasing all views of it.
+    ///
+    /// Fails if a view is still in use, e.g. by a numpy array.
+    fn take_for_sending(&mut self, py: Python) -> eyre::Result<dora_node_api::LoanedOutput> {
+        self.release_views(py)?;
+        self.loan.take().context("loaned output was already sent")
+    }
+
+    fn release_views(&mut self, py: Python) -> eyre::Result<()> {
+        while let Some(view) = self.views.last() {
+            view.call_method0(py, "release").map_err(|err| {
+                eyre::eyre!("loaned output is still in use, delete all views of it first ({err})")
+            })?;
+            self.views.pop();
+        }
+        Ok(())
+    }
+}
+
+impl Drop for PyLoanedOutput {
+    fn drop(&mut self) {
+        if self.views.is_empty() {
+            return;
+        }
+        let released = Python::with_gil(|py| self.release_views(py).is_ok());
+        if !released {
+            // a view might still access the buffer, so we must not free it
+            std::mem::forget(self.loan.take());
+        }
+    }
+}
+
 /// The custom node API lets you integrate `dora` into your application.
 /// It allows you to retrieve input and send output in any fashion you want.
 ///
@@ -381,6 +504,333 @@ impl Node {
         Ok(())
     }
 
+    /// `loan_output` allocates a buffer for `length` values of the given type,
+    /// which is sent without copying through `send_loaned_output`.
+    ///
+    /// The buffer is placed in shared memory if it's large enough. Fill it through
+    /// a writable view, e.g. `as_numpy()`, before sending it.
+    ///
+    /// ```python
+    /// Args:
+    ///    output_id: str,
+    ///    data_type: pyarrow.DataType,
+    ///    length: int,
+    /// ```
+    ///
+    /// ex:
+    ///
+    /// ```python
+    /// loan = node.loan_output("image", pa.uint8(), height * width * 3)
+    /// frame = loan.as_numpy().reshape((height, width, 3))
+    /// frame[:] = 255
+    /// del frame
+    /// node.send_loaned_output(loan)
+    /// ```
+    ///
+    /// :type output_id: str
+    /// :type data_type: pyarrow.DataType
+    /// :type length: int
+    /// :rtype: dora.LoanedOutput
+    pub fn loan_output(
+        &self,
+        output_id: String,
+        data_type: Bound<'_, PyAny>,
+        length: usize,
+    ) -> eyre::Result<PyLoanedOutput> {
+        let data_type = arrow::datatypes::DataType::from_pyarrow_bound(&data_type)
+            .context("invalid `data_type`, must be a `pyarrow.DataType`")?;
+        let loan = self
+            .node
+            .get_mut()
+            .loan_output(output_id.into(), data_type, length)?;
+        Ok(PyLoanedOutput {
+            loan: Some(loan),
+            views: Vec::new(),
+        })
+    }
+
+    /// `send_loaned_output` sends a buffer from `loan_output` on its output.
+    ///
+    /// Fails if a numpy array or pyarrow buffer that views the loaned output is
+    /// still alive, so delete them first. Views can't be used after sending.
+    ///
+    /// ```python
+    /// Args:
+    ///    loan: dora.LoanedOutput,
+    ///    metadata: Option[Dict],
+    /// ```
+    ///
+    /// :type loan: dora.LoanedOutput
+    /// :type metadata: dict, optional
+    /// :rtype: None
+    #[pyo3(signature = (loan, metadata=None))]
+    pub fn send_loaned_output(
+        &self,
+        mut loan: PyRefMut<'_, PyLoanedOutput>,
+        metadata: Option<Bound<'_, PyDict>>,
+        py: Python,
+    ) -> eyre::Result<()> {
+        let parameters = pydict_to_metadata(metadata)?;
+        let loan = loan.take_for_sending(py)?;
+        self.node
+            .get_mut()
+            .send_loaned_output(loan, parameters)
+            .wrap_err("failed to send loaned output")
+    }
+
+    /// `call` sends a request to a service of another node.
+    ///
+    /// The service is given as `<node>/<service>` and must be listed in the `calls`
+    /// field of this node. The reply is received as `REPLY` event with the returned
+    /// request ID. If no reply arrives within `timeout` seconds, the `REPLY` event
+    /// contains an `error` instead of a `value`.
+    ///
+    /// ```python
+    /// Args:
+    ///    service: str,
+    ///    data: pyarrow.Array,
+    ///    timeout: float,
+    ///    metadata: Option[Dict],
+    /// ```
+    ///
+    /// ex:
+    ///
+    /// ```python
+    /// request_id = node.call("planner/plan_path", pa.array([1.0, 2.5]), timeout=1.0)
+    /// ```
+    ///
+    /// :type service: str
+    /// :type data: pyarrow.Array
+    /// :type timeout: float
+    /// :type metadata: dict, optional
+    /// :rtype: int
+    #[pyo3(signature = (service, data, timeout, metadata=None))]
+    pub fn call(
+        &self,
+        service: String,
+        data: PyObject,
+        timeout: f64,
+        metadata: Option<Bound<'_, PyDict>>,
+        py: Python,
+    ) -> eyre::Result<u64> {
+        let parameters = pydict_to_metadata(metadata)?;
+        let timeout = Duration::try_from_secs_f64(timeout).context("invalid `timeout`")?;
+        let data = arrow::array::ArrayData::from_pyarrow_bound(data.bind(py))
+            .context("invalid `data` type, must be an arrow array")?;
+        self.node.get_mut().call(
+            &service,
+            parameters,
+            arrow::array::make_array(data),
+            timeout,
+        )
+    }
+
+    /// `reply` answers a service request that was received as `REQUEST` event.
+    ///
+    /// ```python
+    /// Args:
+    ///    reply_token: ReplyToken,
+    ///    data: pyarrow.Array,
+    ///    metadata: Option[Dict],
+    /// ```
+    ///
+    /// ex:
+    ///
+    /// ```python
+    /// if event["type"] == "REQUEST":
+    ///     node.reply(event["reply_token"], pa.array([0.0, 1.0, 2.5]))
+    /// ```
+    ///
+    /// :type reply_token: ReplyToken
+    /// :type data: pyarrow.Array
+    /// :type metadata: dict, optional
+    /// :rtype: None
+    #[pyo3(signature = (reply_token, data, metadata=None))]
+    pub fn reply(
+        &self,
+        reply_token: PyRef<'_, PyReplyToken>,
+        data: PyObject,
+        metadata: Option<Bound<'_, PyDict>>,
+        py: Python,
+    ) -> eyre::Result<()> {
+        let parameters = pydict_to_metadata(metadata)?;
+        let data = arrow::array::ArrayData::from_pyarrow_bound(data.bind(py))
+            .context("invalid `data` type, must be an arrow array")?;
+        self.node.get_mut().reply(
+            reply_token.0.clone(),
+            parameters,
+            arrow::array::make_array(data),
+        )
+    }
+
+    /// `send_goal` sends a goal to an action of another node and returns the goal ID.
+    ///
+    /// The action must be listed in the `calls` of this node as `<node>/<action>`.
+    /// Feedback arrives as `FEEDBACK` events and the final result as `GOAL_RESULT`
+    /// event, both with a `goal_id` field.
+    ///
+    /// ```python
+    /// Args:
+    ///    action: str,
+    ///    data: pyarrow.Array,
+    ///    metadata: Option[Dict],
+    /// ```
+    ///
+    /// ex:
+    ///
+    /// ```python
+    /// goal_id = node.send_goal("navigator/navigate_to", pa.array([4.0, 2.0]))
+    /// ```
+    ///
+    /// :type action: str
+    /// :type data: pyarrow.Array
+    /// :type metadata: dict, optional
+    /// :rtype: int
+    #[pyo3(signature = (action, data, metadata=None))]
+    pub fn send_goal(
+        &self,
+        action: String,
+        data: PyObject,
+        metadata: Option<Bound<'_, PyDict>>,
+        py: Python,
+    ) -> eyre::Result<u64> {
+        let parameters = pydict_to_metadata(metadata)?;
+        let data = arrow::array::ArrayData::from_pyarrow_bound(data.bind(py))
+            .context("invalid `data` type, must be an arrow array")?;
+        self.node
+            .get_mut()
+            .send_goal(&action, parameters, arrow::array::make_array(data))
+    }
+
+    /// `cancel_goal` asks the server of an action to cancel a goal.
+    ///
+    /// The goal still finishes with a `GOAL_RESULT` event, which has the status
+    /// `"canceled"` if the server stopped working on it.
+    ///
+    /// ```python
+    /// Args:
+    ///    action: str,
+    ///    goal_id: int,
+    /// ```
+    ///
+    /// :type action: str
+    /// :type goal_id: int
+    /// :rtype: None
+    pub fn cancel_goal(&self, action: String, goal_id: u64) -> eyre::Result<()> {
+        self.node.get_mut().cancel_goal(&action, goal_id)
+    }
+
+    /// `send_feedback` reports progress on a goal that was received as `GOAL` event.
+    ///
+    /// ```python
+    /// Args:
+    ///    goal: GoalHandle,
+    ///    data: pyarrow.Array,
+    ///    metadata: Option[Dict],
+    /// ```
+    ///
+    /// ex:
+    ///
+    /// ```python
+    /// if event["type"] == "GOAL":
+    ///     node.send_feedback(event["goal"], pa.array([0.5]))
+    /// ```
+    ///
+    /// :type goal: GoalHandle
+    /// :type data: pyarrow.Array
+    /// :type metadata: dict, optional
+    /// :rtype: None
+    #[pyo3(signature = (goal, data, metadata=None))]
+    pub fn send_feedback(
+        &self,
+        goal: PyRef<'_, PyGoalHandle>,
+        data: PyObject,
+        metadata: Option<Bound<'_, PyDict>>,
+        py: Python,
+    ) -> eyre::Result<()> {
+        let parameters = pydict_to_metadata(metadata)?;
+        let data = arrow::array::ArrayData::from_pyarrow_bound(data.bind(py))
+            .context("invalid `data` type, must be an arrow array")?;
+        self.node
+            .get_mut()
+            .send_feedback(&goal.0, parameters, arrow::array::make_array(data))
+    }
+
+    /// `finish_goal` sends the final result of a goal that was received as `GOAL` event.
+    ///
+    /// The status is one of `"succeeded"`, `"aborted"`, or `"canceled"`.
+    ///
+    /// ```python
+    /// Args:
+    ///    goal: GoalHandle,
+    ///    status: str,
+    ///    data: pyarrow.Array,
+    ///    metadata: Option[Dict],
+    /// ```
+    ///
+    /// ex:
+    ///
+    /// ```python
+    /// node.finish_goal(goal, "succeeded", pa.array([4.0, 2.0]))
+    /// ```
+    ///
+    /// :type goal: GoalHandle
+    /// :type status: str
+    /// :type data: pyarrow.Array
+    /// :type metadata: dict, optional
+    /// :rtype: None
+    #[pyo3(signature = (goal, status, data, metadata=None))]
+    pub fn finish_goal(
+        &self,
+        goal: PyRef<'_, PyGoalHandle>,
+        status: String,
+        data: PyObject,
+        metadata: Option<Bound<'_, PyDict>>,
+        py: Python,
+    ) -> eyre::Result<()> {
+        let status = status.parse().map_err(|err: String| eyre::eyre!(err))?;
+        let parameters = pydict_to_metadata(metadata)?;
+        let data = arrow::array::ArrayData::from_pyarrow_bound(data.bind(py))
+            .context("invalid `data` type, must be an arrow array")?;
+        self.node.get_mut().finish_goal(
+            goal.0.clone(),
+            status,
+            parameters,
+            arrow::array::make_array(data),
+        )
+    }
+
+    /// `mark_ready` reports that this node finished its initialization.
+    ///
+    /// Nodes that list this node with the `ready` condition in their `depends_on`
+    /// field are only spawned after this call.
+    ///
+    /// ```python
+    /// node.mark_ready()
+    /// ```
+    ///
+    /// :rtype: None
+    pub fn mark_ready(&self) -> eyre::Result<()> {
+        self.node.get_mut().mark_ready()
+    }
+
+    /// `extend_stop_timeout` requests more time to exit after this node received a `STOP` event.
+    ///
+    /// The daemon terminates nodes that don't exit within their `stop_timeout`. This
+    /// restarts the timeout with the given duration in seconds, e.g. to finish
+    /// flushing files.
+    ///
+    /// ```python
+    /// node.extend_stop_timeout(5.0)
+    /// ```
+    ///
+    /// :type duration: float
+    /// :rtype: None
+    pub fn extend_stop_timeout(&self, duration: f64) -> eyre::Result<()> {
+        let duration = Duration::try_from_secs_f64(duration).context("invalid `duration`")?;
+        self.node.get_mut().extend_stop_timeout(duration)
+    }
+
     /// Returns the full dataflow descriptor that this node is part of.
     ///
     /// This method returns the parsed dataflow YAML file.
@@ -660,6 +1110,9 @@ fn dora(_py: Python, m: Bound<'_, PyModule>) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(run, &m)?)?;
     m.add_function(wrap_pyfunction!(build, &m)?)?;
     m.add_class::<Node>()?;
+    m.add_class::<PyReplyToken>()?;
+    m.add_class::<PyGoalHandle>()?;
+    m.add_class::<PyLoanedOutput>()?;
     m.setattr("__version__", env!("CARGO_PKG_VERSION"))?;
     m.setattr("__author__", "Dora-rs Authors")?;
 
@@ -6,7 +6,8 @@ use std::{
 use arrow::pyarrow::ToPyArrow;
 use chrono::{DateTime, Utc};
 use dora_node_api::{
-    DoraNode, Event, EventStream, Metadata, MetadataParameters, Parameter, StopCause,
+    DoraNode, Event, EventStream, GoalHandle, Metadata, MetadataParameters, Parameter, ReplyToken,
+    StopCause,
     merged::{MergeExternalSend, MergedEvent},
 };
 use eyre::{Context, Result};
@@ -29,6 +30,16 @@ fn cached_datetime_module<'py>(py: Python<'py>) -> PyResult<&'py Bound<'py, PyMo
         .bind(py))
 }
 
+/// Identifies a service request when replying to it through `node.reply`.
+#[pyclass(name = "ReplyToken")]
+pub struct PyReplyToken(pub ReplyToken);
+
+/// Identifies an action goal when sending feedback or the result through
+/// `node.send_feedback` and `node.finish_goal`.
+#[pyclass(name = "GoalHandle", eq, hash, frozen)]
+#[derive(PartialEq, Eq, Hash)]
+pub struct PyGoalHandle(pub GoalHandle);
+
 /// Dora Event
 pub struct PyEvent {
     pub event: MergedEvent<PyObject>,
@@ -145,6 +156,98 @@ impl PyEvent {
                             .into(),
                     );
                 }
+                match event {
+                    Event::Request {
+                        client,
+                        reply_token,
+                        ..
+                    } => {
+                        pydict.insert(
+                            "client",
+                            client
+                                .as_ref()
+                                .into_pyobject(py)
+                                .context("Failed to create client pyobject")?
+                                .unbind()
+                                .into(),
+                        );
+                        pydict.insert(
+                            "reply_token",
+                            Py::new(py, PyReplyToken(reply_token.clone()))?.into_any(),
+                        );
+                    }
+                    Event::Reply {
+                        request_id,
+                        service,
+                        ..
+                    } => {
+                        pydict.insert(
+                            "request_id",
+                            request_id
+                                .into_pyobject(py)
+                                .context("Failed to create request_id pyobject")?
+                                .unbind()
+                                .into(),
+                        );
+                        pydict.insert(
+                            "service",
+                            service
+                                .to_string()
+                                .into_pyobject(py)
+                                .context("Failed to create service pyobject")?
+                                .unbind()
+                                .into(),
+                        );
+                    }
+                    Event::Goal { client, goal, .. } | Event::CancelGoal { client, goal, .. } => {
+                        pydict.insert(
+                            "client",
+                            client
+                                .as_ref()
+                                .into_pyobject(py)
+                                .context("Failed to create client pyobject")?
+                                .unbind()
+                                .into(),
+                        );
+                        pydict.insert("goal", Py::new(py, PyGoalHandle(goal.clone()))?.into_any());
+                    }
+                    Event::Feedback {
+                        goal_id, action, ..
+                    }
+                    | Event::GoalResult {
+                        goal_id, action, ..
+                    } => {
+                        pydict.insert(
+                            "goal_id",
+                            goal_id
+                                .into_pyobject(py)
+                                .context("Failed to create goal_id pyobject")?
+                                .unbind()
+                                .into(),
+                        );
+                        pydict.insert(
+                            "action",
+                            action
+                                .to_string()
+                                .into_pyobject(py)
+                                .context("Failed to create action pyobject")?
+                                .unbind()
+                                .into(),
+                        );
+                        if let Event::GoalResult { status, .. } = event {
+                            pydict.insert(
+                                "status",
+                                status
+                                    .as_str()
+                                    .into_pyobject(py)
+                                    .context("Failed to create status pyobject")?
+                                    .unbind()
+                                    .into(),
+                            );
+                        }
+                    }
+                    _ => {}
+                }
             }
             MergedEvent::External(event) => {
                 pydict.insert("value", event.clone_ref(py));
@@ -162,6 +265,12 @@ impl PyEvent {
             Event::Stop(_) => "STOP",
             Event::Input { .. } => "INPUT",
             Event::InputClosed { .. } => "INPUT_CLOSED",
+            Event::Request { .. } => "REQUEST",
+            Event::Reply { .. } => "REPLY",
+            Event::Goal { .. } => "GOAL",
+            Event::CancelGoal { .. } => "CANCEL_GOAL",
+            Event::Feedback { .. } => "FEEDBACK",
+            Event::GoalResult { .. } => "GOAL_RESULT",
             Event::Error(_) => "ERROR",
             _other => "UNKNOWN",
         }
@@ -171,6 +280,8 @@ impl PyEvent {
         match event {
             Event::Input { id, .. } => Some(id),
             Event::InputClosed { id } => Some(id),
+            Event::Request { id, .. } => Some(id),
+            Event::Goal { id, .. } | Event::CancelGoal { id, .. } => Some(id),
             Event::Stop(reason) => match reason {
                 StopCause::Manual => Some("MANUAL"),
                 StopCause::AllInputsClosed => Some("ALL_INPUTS_CLOSED"),
@@ -184,7 +295,16 @@ impl PyEvent {
     /// Returns the payload of an input event as an arrow array (if any).
     fn value(&self, py: Python<'_>) -> PyResult<Option<PyObject>> {
         match &self.event {
-            MergedEvent::Dora(Event::Input { data, .. }) => {
+            MergedEvent::Dora(Event::Input { data, .. })
+            | MergedEvent::Dora(Event::Request { data, .. })
+            | MergedEvent::Dora(Event::Reply {
+                result: Ok(data), ..
+            })
+            | MergedEvent::Dora(Event::Goal { data, .. })
+            | MergedEvent::Dora(Event::Feedback { data, .. })
+            | MergedEvent::Dora(Event::GoalResult {
+                result: Ok(data), ..
+            }) => {
                 // TODO: Does this call leak data?&
                 let array_data = data.to_data().to_pyarrow(py)?;
                 Ok(Some(array_data))
@@ -195,7 +315,12 @@ impl PyEvent {
 
     fn metadata(event: &Event, py: Python<'_>) -> Result<Option<PyObject>> {
         match event {
-            Event::Input { metadata, .. } => Ok(Some(
+            Event::Input { metadata, .. }
+            | Event::Request { metadata, .. }
+            | Event::Reply { metadata, .. }
+            | Event::Goal { metadata, .. }
+            | Event::Feedback { metadata, .. }
+            | Event::GoalResult { metadata, .. } => Ok(Some(
                 metadata_to_pydict(metadata, py)
                     .context("Issue deserializing metadata")?
                     .into_pyobject(py)
@@ -210,6 +335,12 @@ impl PyEvent {
     fn error(event: &Event) -> Option<&str> {
         match event {
             Event::Error(error) => Some(error),
+            Event::Reply {
+                result: Err(error), ..
+            }
+            | Event::GoalResult {
+                result: Err(error), ..
+            } => Some(error),
             _other => None,
         }
     }
@@ -70,10 +70,29 @@ impl InteractiveEvents {
                 println!("{}", "node reports OutputsDone".blue());
                 DaemonReply::Result(Ok(()))
             }
+            DaemonRequest::MarkReady => {
+                println!("{}", "node reports ready".blue());
+                DaemonReply::Result(Ok(()))
+            }
+            DaemonRequest::ExtendStopTimeout { duration } => {
+                println!(
+                    "{}",
+                    format!("node requests {duration:?} more time to stop").blue()
+                );
+                DaemonReply::Result(Ok(()))
+            }
             DaemonRequest::ReportDropTokens { drop_tokens } => {
                 println!("{} {drop_tokens:?}", "node reports drop tokens".blue());
                 DaemonReply::Empty
             }
+            DaemonRequest::ReportIdle { .. } => {
+                // nodes only report idle state when following a simulated clock
+                DaemonReply::Empty
+            }
+            DaemonRequest::Heartbeat => {
+                // there is no liveness monitoring without a daemon
+                DaemonReply::Empty
+            }
             DaemonRequest::NextFinishedDropTokens => {
                 // interactive nodes don't use shared memory -> no drop tokens
                 DaemonReply::NextDropEvents(vec![])
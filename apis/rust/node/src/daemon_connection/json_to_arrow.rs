@@ -1,11 +1,17 @@
+//! Conversion of JSON data to Apache Arrow arrays.
+
 use std::{
     io::{BufRead, Read},
     sync::Arc,
 };
 
 use arrow::array::{Array, ArrayData};
+use arrow_schema::{DataType, Field, Schema};
 use eyre::{Context, ContextCompat};
 
+/// Parses the given bytes as JSON and converts them to an arrow array.
+///
+/// Bytes that are not valid JSON are treated as a string.
 pub fn read_json_bytes_as_arrow(data: &[u8]) -> eyre::Result<ArrayData> {
     match arrow_json::reader::infer_json_schema(wrapped(data), None) {
         Ok((schema, _)) => read_from_json_with_schema(wrapped(data), schema),
@@ -62,3 +68,48 @@ pub fn read_json_value_as_arrow(
         .context("no record batch in JSON")?;
     Ok(batch.column(0).to_data())
 }
+
+/// Converts the given JSON value to the closest arrow representation.
+///
+/// JSON arrays are converted to an arrow array with one item per element. Other
+/// values are wrapped into a single-item array. The data type is inferred from
+/// the value if not given.
+pub fn json_value_to_arrow(
+    value: serde_json::Value,
+    data_type: Option<DataType>,
+) -> eyre::Result<ArrayData> {
+    let array = json_value_to_list(value);
+    let schema = match data_type {
+        Some(ty) => Schema::new([Arc::new(Field::new("inner", ty, true))]),
+        None => arrow_json::reader::infer_json_schema_from_iterator(array.iter().map(Ok))?,
+    };
+    let schema = Arc::new(schema);
+    read_json_value_as_arrow(&array, schema.clone()).with_context(|| {
+        format!(
+            "failed to decode JSON value for data type {}",
+            schema
+                .fields()
+                .first()
+                .map(|f| f.data_type())
+                .unwrap_or(&DataType::Null)
+        )
+    })
+}
+
+fn json_value_to_list(value: serde_json::Value) -> Vec<serde_json::Value> {
+    match value {
+        serde_json::Value::Array(inner) => inner.into_iter().map(wrap_value_into_object).collect(),
+        _ => {
+            // wrap into object to allow bare values
+            let object = wrap_value_into_object(value);
+            vec![object]
+        }
+    }
+}
+
+fn wrap_value_into_object(value: serde_json::Value) -> serde_json::Value {
+    let mut map = serde_json::Map::new();
+    map.insert("inner".into(), value);
+
+    serde_json::Value::Object(map)
+}
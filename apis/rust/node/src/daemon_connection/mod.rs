@@ -1,23 +1,36 @@
 use crate::daemon_connection::interactive::InteractiveEvents;
+#[cfg(target_os = "linux")]
+use communication_layer_shared_memory::ShmemChannel;
 use dora_core::{config::NodeId, uhlc::Timestamp};
 use dora_message::{
     DataflowId,
-    daemon_to_node::DaemonReply,
+    daemon_to_node::{DaemonCommunication, DaemonReply},
     node_to_daemon::{DaemonRequest, NodeRegisterRequest, Timestamped},
 };
 use eyre::{Context, bail, eyre};
 pub use node_integration_testing::IntegrationTestingEvents;
-use std::net::{SocketAddr, TcpStream};
+#[cfg(unix)]
+use std::os::unix::net::UnixStream;
+use std::{
+    net::{SocketAddr, TcpStream},
+    path::Path,
+};
 use tokio::sync::oneshot;
 
 mod interactive;
 pub(crate) mod node_integration_testing;
+#[cfg(target_os = "linux")]
+mod shmem;
 mod tcp;
 
-mod json_to_arrow;
+pub mod json_to_arrow;
 
 pub enum DaemonChannel {
     Tcp(TcpStream),
+    #[cfg(unix)]
+    UnixDomain(UnixStream),
+    #[cfg(target_os = "linux")]
+    SharedMemory(ShmemChannel),
     Interactive(InteractiveEvents),
     IntegrationTestChannel(
         tokio::sync::mpsc::Sender<(
@@ -35,6 +48,54 @@ impl DaemonChannel {
         Ok(DaemonChannel::Tcp(stream))
     }
 
+    #[cfg(unix)]
+    #[tracing::instrument(level = "trace")]
+    pub fn new_unix_socket(path: &Path) -> eyre::Result<Self> {
+        let stream = UnixStream::connect(path).wrap_err_with(|| {
+            format!(
+                "failed to connect to Unix domain socket `{}`",
+                path.display()
+            )
+        })?;
+        Ok(DaemonChannel::UnixDomain(stream))
+    }
+
+    #[cfg(not(unix))]
+    pub fn new_unix_socket(path: &Path) -> eyre::Result<Self> {
+        bail!(
+            "failed to connect to `{}`: Unix domain sockets are not supported on this platform",
+            path.display()
+        )
+    }
+
+    /// Opens the first of the given shared memory regions that is not used by
+    /// another connection yet.
+    #[cfg(target_os = "linux")]
+    #[tracing::instrument(level = "trace")]
+    pub fn new_shmem(region_ids: &[String]) -> eyre::Result<Self> {
+        for region_id in region_ids {
+            if let Some(channel) = ShmemChannel::connect(region_id)? {
+                return Ok(DaemonChannel::SharedMemory(channel));
+            }
+        }
+        bail!("all shared memory channels to the daemon are already in use")
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub fn new_shmem(_region_ids: &[String]) -> eyre::Result<Self> {
+        bail!("shared memory channels are not supported on this platform")
+    }
+
+    /// Connects to the daemon through the given transport.
+    pub fn connect(daemon_communication: &DaemonCommunication) -> eyre::Result<Self> {
+        match daemon_communication {
+            DaemonCommunication::Tcp { socket_addr } => Self::new_tcp(*socket_addr),
+            DaemonCommunication::UnixDomain { path } => Self::new_unix_socket(path),
+            DaemonCommunication::SharedMemory { region_ids } => Self::new_shmem(region_ids),
+            DaemonCommunication::Interactive => Ok(DaemonChannel::Interactive(Default::default())),
+        }
+    }
+
     pub fn register(
         &mut self,
         dataflow_id: DataflowId,
@@ -61,6 +122,11 @@ impl DaemonChannel {
     pub fn request(&mut self, request: &Timestamped<DaemonRequest>) -> eyre::Result<DaemonReply> {
         match self {
             DaemonChannel::Tcp(stream) => tcp::request(stream, request),
+            // Unix domain sockets use the same message framing as TCP
+            #[cfg(unix)]
+            DaemonChannel::UnixDomain(stream) => tcp::request(stream, request),
+            #[cfg(target_os = "linux")]
+            DaemonChannel::SharedMemory(channel) => shmem::request(channel, request),
             DaemonChannel::Interactive(events) => events.request(request),
             DaemonChannel::IntegrationTestChannel(channel) => {
                 let (reply_tx, reply) = oneshot::channel();
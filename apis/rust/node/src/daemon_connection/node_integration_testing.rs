@@ -1,12 +1,11 @@
 use std::{
     fs::File,
     io::Write,
-    sync::Arc,
     time::{Duration, Instant},
 };
 
 use arrow::array::{Array, RecordBatch, StructArray};
-use arrow_schema::{DataType, Field, Schema};
+use arrow_schema::DataType;
 use colored::Colorize;
 use dora_core::{
     metadata::ArrowTypeInfoExt,
@@ -25,7 +24,7 @@ use eyre::{Context, ContextCompat};
 
 use crate::{
     arrow_utils::{copy_array_into_sample, required_data_size},
-    daemon_connection::json_to_arrow::read_json_value_as_arrow,
+    daemon_connection::json_to_arrow::json_value_to_arrow,
     event_stream::data_to_arrow_array,
     integration_testing::{TestingInput, TestingOptions, TestingOutput},
 };
@@ -107,10 +106,29 @@ impl IntegrationTestingEvents {
                 println!("{}", "node reports OutputsDone".blue());
                 DaemonReply::Result(Ok(()))
             }
+            DaemonRequest::MarkReady => {
+                println!("{}", "node reports ready".blue());
+                DaemonReply::Result(Ok(()))
+            }
+            DaemonRequest::ExtendStopTimeout { duration } => {
+                println!(
+                    "{}",
+                    format!("node requests {duration:?} more time to stop").blue()
+                );
+                DaemonReply::Result(Ok(()))
+            }
             DaemonRequest::ReportDropTokens { drop_tokens } => {
                 println!("{} {drop_tokens:?}", "node reports drop tokens".blue());
                 DaemonReply::Empty
             }
+            DaemonRequest::ReportIdle { .. } => {
+                // nodes only report idle state when following a simulated clock
+                DaemonReply::Empty
+            }
+            DaemonRequest::Heartbeat => {
+                // there is no liveness monitoring without a daemon
+                DaemonReply::Empty
+            }
             DaemonRequest::NextFinishedDropTokens => {
                 // interactive nodes don't use shared memory -> no drop tokens
                 DaemonReply::NextDropEvents(vec![])
@@ -270,22 +288,11 @@ fn read_input_data(data: InputData) -> eyre::Result<arrow::array::ArrayData> {
     Ok(match data {
         InputData::JsonObject { data, data_type } => {
             // input is JSON data
-            let array = json_value_to_list(data);
-            let schema = match data_type {
-                Some(ty) => data_type_to_schema(ty)?,
-                None => arrow_json::reader::infer_json_schema_from_iterator(array.iter().map(Ok))?,
-            };
-            let schema = Arc::new(schema);
-            read_json_value_as_arrow(&array, schema.clone()).with_context(|| {
-                format!(
-                    "failed to decode JSON value for data type {}",
-                    schema
-                        .fields()
-                        .first()
-                        .map(|f| f.data_type())
-                        .unwrap_or(&DataType::Null)
-                )
-            })?
+            let data_type = data_type
+                .map(serde_json::from_value::<DataType>)
+                .transpose()
+                .context("failed to deserialize `type` field of input data")?;
+            json_value_to_arrow(data, data_type)?
         }
         InputData::ArrowFile {
             path,
@@ -324,27 +331,3 @@ fn read_input_data(data: InputData) -> eyre::Result<arrow::array::ArrayData> {
         }
     })
 }
-
-fn json_value_to_list(value: serde_json::Value) -> Vec<serde_json::Value> {
-    match value {
-        serde_json::Value::Array(inner) => inner.into_iter().map(wrap_value_into_object).collect(),
-        _ => {
-            // wrap into object to allow bare values
-            let object = wrap_value_into_object(value);
-            vec![object]
-        }
-    }
-}
-
-fn data_type_to_schema(ty: serde_json::Value) -> eyre::Result<Schema> {
-    let ty = serde_json::from_value::<DataType>(ty)
-        .context("failed to deserialize `type` field of input data")?;
-    Ok(Schema::new([Arc::new(Field::new("inner", ty, true))]))
-}
-
-fn wrap_value_into_object(value: serde_json::Value) -> serde_json::Value {
-    let mut map = serde_json::Map::new();
-    map.insert("inner".into(), value);
-
-    serde_json::Value::Object(map)
-}
@@ -0,0 +1,26 @@
+use communication_layer_shared_memory::ShmemChannel;
+use dora_message::{
+    daemon_to_node::DaemonReply,
+    node_to_daemon::{DaemonRequest, Timestamped},
+};
+use eyre::{Context, eyre};
+
+pub fn request(
+    channel: &mut ShmemChannel,
+    request: &Timestamped<DaemonRequest>,
+) -> eyre::Result<DaemonReply> {
+    let serialized = bincode::serialize(request).wrap_err("failed to serialize DaemonRequest")?;
+    channel
+        .send(&serialized)
+        .wrap_err("failed to send DaemonRequest")?;
+    // the daemon only sends JSON replies over TCP
+    if request.inner.expects_tcp_bincode_reply() {
+        let raw = channel
+            .receive()
+            .wrap_err("failed to receive DaemonReply")?
+            .ok_or_else(|| eyre!("server disconnected unexpectedly"))?;
+        bincode::deserialize(&raw).wrap_err("failed to deserialize DaemonReply")
+    } else {
+        Ok(DaemonReply::Empty)
+    }
+}
@@ -3,17 +3,14 @@ use dora_message::{
     node_to_daemon::{DaemonRequest, Timestamped},
 };
 use eyre::{Context, eyre};
-use std::{
-    io::{Read, Write},
-    net::TcpStream,
-};
+use std::io::{Read, Write};
 
 enum Serializer {
     Bincode,
     SerdeJson,
 }
 pub fn request(
-    connection: &mut TcpStream,
+    connection: &mut (impl Read + Write),
     request: &Timestamped<DaemonRequest>,
 ) -> eyre::Result<DaemonReply> {
     send_message(connection, request)?;
@@ -30,7 +27,7 @@ pub fn request(
 }
 
 fn send_message(
-    connection: &mut TcpStream,
+    connection: &mut impl Write,
     message: &Timestamped<DaemonRequest>,
 ) -> eyre::Result<()> {
     let serialized = bincode::serialize(&message).wrap_err("failed to serialize DaemonRequest")?;
@@ -39,7 +36,7 @@ fn send_message(
 }
 
 fn receive_reply(
-    connection: &mut TcpStream,
+    connection: &mut impl Read,
     serializer: Serializer,
 ) -> eyre::Result<Option<DaemonReply>> {
     let raw =
@@ -66,7 +63,7 @@ fn receive_reply(
     }
 }
 
-fn tcp_send(connection: &mut (impl Write + Unpin), message: &[u8]) -> std::io::Result<()> {
+fn tcp_send(connection: &mut impl Write, message: &[u8]) -> std::io::Result<()> {
     let len_raw = (message.len() as u64).to_le_bytes();
     connection.write_all(&len_raw)?;
     connection.write_all(message)?;
@@ -74,7 +71,7 @@ fn tcp_send(connection: &mut (impl Write + Unpin), message: &[u8]) -> std::io::R
     Ok(())
 }
 
-fn tcp_receive(connection: &mut (impl Read + Unpin)) -> std::io::Result<Vec<u8>> {
+fn tcp_receive(connection: &mut impl Read) -> std::io::Result<Vec<u8>> {
     let reply_len = {
         let mut raw = [0; 8];
         connection.read_exact(&mut raw)?;
@@ -1,7 +1,10 @@
 use dora_arrow_convert::ArrowData;
 use dora_core::config::{DataId, NodeId, OperatorId};
 pub use dora_message::daemon_to_node::StopCause;
-use dora_message::metadata::Metadata;
+use dora_message::{
+    metadata::Metadata,
+    service::{GoalStatus, ServiceCall},
+};
 
 /// Represents an incoming Dora event.
 ///
@@ -75,8 +78,115 @@ pub enum Event {
         /// There is currently no case where `operator_id` is `None`.
         operator_id: Option<OperatorId>,
     },
+    /// A service request was received from another node.
+    ///
+    /// This event corresponds to one of the `services` of the node as specified
+    /// in the dataflow YAML file. The request should be answered by passing the
+    /// `reply_token` to [`DoraNode::reply`](crate::DoraNode::reply).
+    Request {
+        /// The service ID, as specified in the YAML file.
+        id: DataId,
+        /// The node that sent the request.
+        client: NodeId,
+        /// Meta information about this request, e.g. the timestamp.
+        metadata: Metadata,
+        /// The request data in the Apache Arrow data format.
+        data: ArrowData,
+        /// Identifies the request when replying to it.
+        reply_token: ReplyToken,
+    },
+    /// The reply to a request sent through [`DoraNode::call`](crate::DoraNode::call).
+    Reply {
+        /// The ID that was returned by [`DoraNode::call`](crate::DoraNode::call).
+        request_id: u64,
+        /// The called service, as specified in the `calls` field of the YAML file.
+        service: ServiceCall,
+        /// Meta information about this reply, e.g. the timestamp.
+        metadata: Metadata,
+        /// The reply data, or an error if the request failed or timed out.
+        result: Result<ArrowData, String>,
+    },
+    /// An action goal was received from another node.
+    ///
+    /// This event corresponds to one of the `actions` of the node as specified
+    /// in the dataflow YAML file. Progress can be reported through
+    /// [`DoraNode::send_feedback`](crate::DoraNode::send_feedback). Every goal
+    /// must be finished through [`DoraNode::finish_goal`](crate::DoraNode::finish_goal).
+    Goal {
+        /// The action ID, as specified in the YAML file.
+        id: DataId,
+        /// The node that sent the goal.
+        client: NodeId,
+        /// Meta information about this goal, e.g. the timestamp.
+        metadata: Metadata,
+        /// The goal data in the Apache Arrow data format.
+        data: ArrowData,
+        /// Identifies the goal when sending feedback or the result.
+        goal: GoalHandle,
+    },
+    /// The client of a goal asked to cancel it.
+    ///
+    /// The `goal` handle is equal to the handle of the corresponding
+    /// [`Event::Goal`]. The node should stop working on the goal and finish it
+    /// with [`GoalStatus::Canceled`], or ignore the request if the goal cannot
+    /// be canceled anymore.
+    CancelGoal {
+        /// The action ID, as specified in the YAML file.
+        id: DataId,
+        /// The node that sent the goal.
+        client: NodeId,
+        /// Identifies the goal that should be canceled.
+        goal: GoalHandle,
+    },
+    /// Feedback on a goal sent through [`DoraNode::send_goal`](crate::DoraNode::send_goal).
+    Feedback {
+        /// The ID that was returned by [`DoraNode::send_goal`](crate::DoraNode::send_goal).
+        goal_id: u64,
+        /// The action, as specified in the `calls` field of the YAML file.
+        action: ServiceCall,
+        /// Meta information about this feedback, e.g. the timestamp.
+        metadata: Metadata,
+        /// The feedback data in the Apache Arrow data format.
+        data: ArrowData,
+    },
+    /// The final result of a goal sent through [`DoraNode::send_goal`](crate::DoraNode::send_goal).
+    ///
+    /// No further feedback is delivered for the goal after this event.
+    GoalResult {
+        /// The ID that was returned by [`DoraNode::send_goal`](crate::DoraNode::send_goal).
+        goal_id: u64,
+        /// The action, as specified in the `calls` field of the YAML file.
+        action: ServiceCall,
+        /// Whether the goal succeeded, was aborted, or was canceled.
+        status: GoalStatus,
+        /// Meta information about this result, e.g. the timestamp.
+        metadata: Metadata,
+        /// The result data, or an error if the server stopped before finishing the goal.
+        result: Result<ArrowData, String>,
+    },
     /// Notifies the node about an unexpected error that happened inside Dora.
     ///
     /// It's a good idea to output or log this error for debugging.
     Error(String),
 }
+
+/// Identifies a service request that should be answered.
+///
+/// Passed to [`DoraNode::reply`](crate::DoraNode::reply) to send the reply
+/// back to the node that sent the request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplyToken {
+    pub(crate) output_id: DataId,
+    pub(crate) request_id: i64,
+}
+
+/// Identifies an action goal that is executed by this node.
+///
+/// Passed to [`DoraNode::send_feedback`](crate::DoraNode::send_feedback) and
+/// [`DoraNode::finish_goal`](crate::DoraNode::finish_goal) to send messages
+/// back to the node that sent the goal.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct GoalHandle {
+    pub(crate) output_id: DataId,
+    pub(crate) goal_id: i64,
+}
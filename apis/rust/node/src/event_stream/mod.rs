@@ -1,19 +1,27 @@
 use std::{
     collections::{BTreeMap, HashMap, VecDeque},
+    future::poll_fn,
     path::PathBuf,
     pin::pin,
-    sync::Arc,
-    task::Poll,
+    sync::{Arc, atomic::AtomicU64},
+    task::{self, Poll},
     time::Duration,
 };
 
+use dora_arrow_convert::ArrowData;
 use dora_message::{
     DataflowId,
-    daemon_to_node::{DaemonCommunication, DaemonReply, DataMessage, NodeEvent},
+    daemon_to_node::{DaemonReply, DataMessage, NodeEvent},
     id::DataId,
+    metadata::{Metadata, Parameter},
     node_to_daemon::{DaemonRequest, Timestamped},
+    service::{
+        self, ACTION_MESSAGE_PARAMETER, ActionMessage, GOAL_STATUS_PARAMETER, REPLY_PREFIX,
+        REQUEST_ID_PARAMETER, REQUEST_PREFIX, SERVICE_ERROR_PARAMETER, ServiceCall,
+        parse_hidden_input,
+    },
 };
-pub use event::{Event, StopCause};
+pub use event::{Event, GoalHandle, ReplyToken, StopCause};
 use futures::{
     FutureExt, Stream,
     future::{Either, select},
@@ -21,7 +29,7 @@ use futures::{
 use futures_timer::Delay;
 use scheduler::{NON_INPUT_EVENT, Scheduler};
 
-use self::thread::{EventItem, EventStreamThreadHandle};
+use self::thread::{Demand, EventItem, EventStreamThreadHandle};
 use crate::{
     DaemonCommunicationWrapper,
     daemon_connection::{DaemonChannel, node_integration_testing::convert_output_to_json},
@@ -63,8 +71,15 @@ mod thread;
 pub struct EventStream {
     node_id: NodeId,
     receiver: tokio::sync::mpsc::UnboundedReceiver<EventItem>,
+    /// Set if the node follows the simulated clock of the daemon.
+    ///
+    /// Declared before the thread handle so that the event stream thread stops
+    /// waiting for demand before it is joined.
+    simulated_clock: Option<SimulatedClock>,
     _thread_handle: EventStreamThreadHandle,
     close_channel: DaemonChannel,
+    /// Set if the daemon monitors the liveness of the node.
+    heartbeat: Option<Heartbeat>,
     clock: Arc<uhlc::HLC>,
     scheduler: Scheduler,
     write_events_to: Option<WriteEventsTo>,
@@ -75,6 +90,7 @@ pub struct EventStream {
 
 impl EventStream {
     #[tracing::instrument(level = "trace", skip(clock))]
+    #[allow(clippy::too_many_arguments)]
     pub(crate) fn init(
         dataflow_id: DataflowId,
         node_id: &NodeId,
@@ -82,19 +98,14 @@ impl EventStream {
         input_config: BTreeMap<DataId, Input>,
         clock: Arc<uhlc::HLC>,
         write_events_to: Option<PathBuf>,
+        sent_outputs: Option<Arc<AtomicU64>>,
+        liveness_timeout: Option<Duration>,
     ) -> eyre::Result<Self> {
         let channel = match daemon_communication {
             DaemonCommunicationWrapper::Standard(daemon_communication) => {
-                match daemon_communication {
-                    DaemonCommunication::Tcp { socket_addr } => {
-                        DaemonChannel::new_tcp(*socket_addr).wrap_err_with(|| {
-                            format!("failed to connect event stream for node `{node_id}`")
-                        })?
-                    }
-                    DaemonCommunication::Interactive => {
-                        DaemonChannel::Interactive(Default::default())
-                    }
-                }
+                DaemonChannel::connect(daemon_communication).wrap_err_with(|| {
+                    format!("failed to connect event stream for node `{node_id}`")
+                })?
             }
 
             DaemonCommunicationWrapper::Testing { channel } => {
@@ -104,16 +115,9 @@ impl EventStream {
 
         let close_channel = match daemon_communication {
             DaemonCommunicationWrapper::Standard(daemon_communication) => {
-                match daemon_communication {
-                    DaemonCommunication::Tcp { socket_addr } => {
-                        DaemonChannel::new_tcp(*socket_addr).wrap_err_with(|| {
-                            format!("failed to connect event close channel for node `{node_id}`")
-                        })?
-                    }
-                    DaemonCommunication::Interactive => {
-                        DaemonChannel::Interactive(Default::default())
-                    }
-                }
+                DaemonChannel::connect(daemon_communication).wrap_err_with(|| {
+                    format!("failed to connect event close channel for node `{node_id}`")
+                })?
             }
             DaemonCommunicationWrapper::Testing { channel } => {
                 DaemonChannel::IntegrationTestChannel(channel.clone())
@@ -174,9 +178,12 @@ impl EventStream {
             clock,
             scheduler,
             write_events_to,
+            sent_outputs,
+            liveness_timeout,
         )
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub(crate) fn init_on_channel(
         dataflow_id: DataflowId,
         node_id: &NodeId,
@@ -185,6 +192,8 @@ impl EventStream {
         clock: Arc<uhlc::HLC>,
         scheduler: Scheduler,
         write_events_to: Option<WriteEventsTo>,
+        sent_outputs: Option<Arc<AtomicU64>>,
+        liveness_timeout: Option<Duration>,
     ) -> eyre::Result<Self> {
         channel.register(dataflow_id, node_id.clone(), clock.new_timestamp())?;
         let reply = channel
@@ -220,13 +229,28 @@ impl EventStream {
             _ => true,
         };
 
-        let thread_handle = thread::init(node_id.clone(), tx, channel, clock.clone())?;
+        let (simulated_clock, demand) = match sent_outputs {
+            Some(sent_outputs) => {
+                let (demand_tx, demand_rx) = flume::unbounded();
+                let simulated_clock = SimulatedClock {
+                    demand: demand_tx,
+                    received: 0,
+                    deadline: None,
+                };
+                (Some(simulated_clock), Some((demand_rx, sent_outputs)))
+            }
+            None => (None, None),
+        };
+
+        let thread_handle = thread::init(node_id.clone(), tx, channel, clock.clone(), demand)?;
 
         Ok(EventStream {
             node_id: node_id.clone(),
             receiver: rx,
+            simulated_clock,
             _thread_handle: thread_handle,
             close_channel,
+            heartbeat: liveness_timeout.map(Heartbeat::new),
             start_timestamp: clock.new_timestamp(),
             clock,
             scheduler,
@@ -310,17 +334,23 @@ impl EventStream {
     /// it has returned `None` once. Use [`is_closed`][Self::is_closed] to check if the stream
     /// is closed.
     pub async fn recv_async(&mut self) -> Option<Event> {
+        self.recv_async_until(None).await
+    }
+
+    /// Receives the next event, requesting a wake-up at the given simulated time.
+    async fn recv_async_until(&mut self, wake_at: Option<uhlc::NTP64>) -> Option<Event> {
         assert!(
             !self.closed,
             "receive function called after None was returned"
         );
 
         if !self.use_scheduler {
-            return self.receiver.recv().await.map(Self::convert_event_item);
+            return self.next_item().await.map(Self::convert_event_item);
         }
         loop {
             if self.scheduler.is_empty() {
-                if let Some(event) = self.receiver.recv().await {
+                self.request_events(wake_at);
+                if let Some(event) = self.next_item().await {
                     self.add_event(event);
                 } else {
                     break;
@@ -340,6 +370,28 @@ impl EventStream {
         event.map(Self::convert_event_item)
     }
 
+    /// Receives the next item from the event stream thread.
+    async fn next_item(&mut self) -> Option<EventItem> {
+        poll_fn(|cx| self.poll_item(cx)).await
+    }
+
+    /// Polls the next item from the event stream thread.
+    ///
+    /// Also sends a heartbeat to the daemon if one is due, including while the
+    /// node waits for new events.
+    fn poll_item(&mut self, cx: &mut task::Context<'_>) -> Poll<Option<EventItem>> {
+        if self.heartbeat.as_mut().is_some_and(|h| h.poll_due(cx)) {
+            let request = Timestamped {
+                inner: DaemonRequest::Heartbeat,
+                timestamp: self.clock.new_timestamp(),
+            };
+            if let Err(err) = self.close_channel.request(&request) {
+                tracing::warn!("failed to send heartbeat to dora-daemon: {err:?}");
+            }
+        }
+        self.receiver.poll_recv(cx)
+    }
+
     /// Check if there are any buffered events in the scheduler or the receiver.
     pub fn is_empty(&self) -> bool {
         self.scheduler.is_empty() && self.receiver.is_empty()
@@ -353,7 +405,38 @@ impl EventStream {
         self.closed
     }
 
-    fn add_event(&mut self, event: EventItem) {
+    /// Asks the event stream thread for new events if the node follows a
+    /// simulated clock and all received events were handled.
+    fn request_events(&mut self, wake_at: Option<uhlc::NTP64>) {
+        if let Some(simulated_clock) = &self.simulated_clock {
+            if self.receiver.is_empty() {
+                let demand = Demand {
+                    received: simulated_clock.received,
+                    wake_at,
+                };
+                if simulated_clock.demand.send(demand).is_err() {
+                    tracing::debug!("event stream thread is no longer waiting for demand");
+                }
+            }
+        }
+    }
+
+    fn add_event(&mut self, mut event: EventItem) {
+        if let Some(simulated_clock) = &mut self.simulated_clock {
+            simulated_clock.received += 1;
+            if let EventItem::NodeEvent {
+                event: NodeEvent::SimulatedTimeout { deadline },
+                ..
+            } = &event
+            {
+                if simulated_clock.deadline != Some(*deadline) {
+                    // outdated wake-up for an earlier `recv_timeout` call
+                    return;
+                }
+                event = EventItem::TimeoutError(eyre!("Receiver timed out"));
+            }
+        }
+
         // Event recording failure should not prevent event scheduling.
         // If writing to the event log file fails, log a warning but continue
         // processing events. Observability should never break the main logic.
@@ -382,7 +465,7 @@ impl EventStream {
                         });
                         Some(event_json)
                     }
-                    NodeEvent::Reload { .. } => None,
+                    NodeEvent::Reload { .. } | NodeEvent::SimulatedTimeout { .. } => None,
                     NodeEvent::Input { id, metadata, data } => {
                         let mut event_json = convert_output_to_json(
                             id,
@@ -513,6 +596,19 @@ impl EventStream {
     /// [`StreamExt::next`] method with a custom timeout future instead
     /// ([`EventStream`] implements the [`Stream`] trait).
     pub async fn recv_async_timeout(&mut self, dur: Duration) -> Option<Event> {
+        if self.simulated_clock.is_some() {
+            // the daemon wakes us up once the deadline is reached in simulated time
+            let deadline = self.clock.new_timestamp().get_time().to_duration() + dur;
+            let deadline = uhlc::NTP64::from(deadline);
+            if let Some(simulated_clock) = &mut self.simulated_clock {
+                simulated_clock.deadline = Some(deadline);
+            }
+            let event = self.recv_async_until(Some(deadline)).await;
+            if let Some(simulated_clock) = &mut self.simulated_clock {
+                simulated_clock.deadline = None;
+            }
+            return event;
+        }
         match select(Delay::new(dur), pin!(self.recv_async())).await {
             Either::Left((_elapsed, _)) => Some(Self::convert_event_item(EventItem::TimeoutError(
                 eyre!("Receiver timed out"),
@@ -539,15 +635,14 @@ impl EventStream {
                 NodeEvent::Input { id, metadata, data } => {
                     let data = data_to_arrow_array(data, &metadata, ack_channel);
                     match data {
-                        Ok(data) => Event::Input {
-                            id,
-                            metadata,
-                            data: data.into(),
-                        },
+                        Ok(data) => convert_input(id, metadata, data.into()),
                         Err(err) => Event::Error(format!("{err:?}")),
                     }
                 }
                 NodeEvent::AllInputsClosed => Event::Stop(StopCause::AllInputsClosed),
+                NodeEvent::SimulatedTimeout { .. } => {
+                    Event::Error("Timeout event stream error: Receiver timed out".into())
+                }
             },
 
             EventItem::FatalError(err) => {
@@ -560,6 +655,108 @@ impl EventStream {
     }
 }
 
+/// Converts messages on the hidden inputs of services and actions into request,
+/// reply, and goal events.
+fn convert_input(id: DataId, metadata: Metadata, data: ArrowData) -> Event {
+    let request_id = match metadata.parameters.get(REQUEST_ID_PARAMETER) {
+        Some(Parameter::Integer(request_id)) => Some(*request_id),
+        _ => None,
+    };
+    let action_message = match metadata.parameters.get(ACTION_MESSAGE_PARAMETER) {
+        Some(Parameter::String(message)) => match message.parse::<ActionMessage>() {
+            Ok(message) => Some(message),
+            Err(err) => return Event::Error(format!("invalid message on `{id}`: {err}")),
+        },
+        _ => None,
+    };
+    if let Some((client, service)) = parse_hidden_input(REQUEST_PREFIX, &id) {
+        let Some(request_id) = request_id else {
+            return Event::Error(format!("service request on `{id}` has no request ID"));
+        };
+        let output_id = service::reply_output(&client, &service);
+        match action_message {
+            None => Event::Request {
+                id: service,
+                client,
+                metadata,
+                data,
+                reply_token: ReplyToken {
+                    output_id,
+                    request_id,
+                },
+            },
+            Some(ActionMessage::Goal) => Event::Goal {
+                id: service,
+                client,
+                metadata,
+                data,
+                goal: GoalHandle {
+                    output_id,
+                    goal_id: request_id,
+                },
+            },
+            Some(ActionMessage::Cancel) => Event::CancelGoal {
+                id: service,
+                client,
+                goal: GoalHandle {
+                    output_id,
+                    goal_id: request_id,
+                },
+            },
+            Some(other) => {
+                Event::Error(format!("unexpected `{}` message on `{id}`", other.as_str()))
+            }
+        }
+    } else if let Some((server, service)) = parse_hidden_input(REPLY_PREFIX, &id) {
+        let Some(request_id) = request_id.and_then(|id| u64::try_from(id).ok()) else {
+            return Event::Error(format!("service reply on `{id}` has no request ID"));
+        };
+        let result = match metadata.parameters.get(SERVICE_ERROR_PARAMETER) {
+            Some(Parameter::String(error)) => Err(error.clone()),
+            _ => Ok(data),
+        };
+        let call = ServiceCall { server, service };
+        match action_message {
+            None => Event::Reply {
+                request_id,
+                service: call,
+                metadata,
+                result,
+            },
+            Some(ActionMessage::Feedback) => match result {
+                Ok(data) => Event::Feedback {
+                    goal_id: request_id,
+                    action: call,
+                    metadata,
+                    data,
+                },
+                Err(err) => Event::Error(format!("error feedback on `{id}`: {err}")),
+            },
+            Some(ActionMessage::Result) => {
+                let status = match metadata.parameters.get(GOAL_STATUS_PARAMETER) {
+                    Some(Parameter::String(status)) => status.parse().ok(),
+                    _ => None,
+                };
+                let Some(status) = status else {
+                    return Event::Error(format!("goal result on `{id}` has no valid status"));
+                };
+                Event::GoalResult {
+                    goal_id: request_id,
+                    action: call,
+                    status,
+                    metadata,
+                    result,
+                }
+            }
+            Some(other) => {
+                Event::Error(format!("unexpected `{}` message on `{id}`", other.as_str()))
+            }
+        }
+    } else {
+        Event::Input { id, metadata, data }
+    }
+}
+
 /// No event is available right now or the event stream has been closed.
 #[derive(Debug)]
 pub enum TryRecvError {
@@ -606,9 +803,13 @@ impl Stream for EventStream {
         mut self: std::pin::Pin<&mut Self>,
         cx: &mut std::task::Context<'_>,
     ) -> Poll<Option<Self::Item>> {
-        self.receiver
-            .poll_recv(cx)
-            .map(|item| item.map(Self::convert_event_item))
+        let poll = self.poll_item(cx);
+        match (&poll, &mut self.simulated_clock) {
+            (Poll::Ready(Some(_)), Some(simulated_clock)) => simulated_clock.received += 1,
+            (Poll::Pending, Some(_)) => self.request_events(None),
+            _ => {}
+        }
+        poll.map(|item| item.map(Self::convert_event_item))
     }
 }
 
@@ -643,6 +844,46 @@ impl Drop for EventStream {
     }
 }
 
+/// Sends heartbeats for the liveness monitoring of the daemon.
+struct Heartbeat {
+    interval: Duration,
+    /// Fires when the next heartbeat is due.
+    timer: Delay,
+}
+
+impl Heartbeat {
+    fn new(liveness_timeout: Duration) -> Self {
+        // send a heartbeat immediately, then several times per timeout to allow
+        // some time for handling events between two `recv` calls
+        Self {
+            interval: liveness_timeout / 4,
+            timer: Delay::new(Duration::ZERO),
+        }
+    }
+
+    /// Returns `true` if a heartbeat should be sent now.
+    ///
+    /// Wakes up the given context when the next heartbeat is due.
+    fn poll_due(&mut self, cx: &mut task::Context<'_>) -> bool {
+        if self.timer.poll_unpin(cx).is_pending() {
+            return false;
+        }
+        self.timer.reset(self.interval);
+        let _ = self.timer.poll_unpin(cx);
+        true
+    }
+}
+
+/// State for following the simulated clock of the daemon.
+struct SimulatedClock {
+    /// Used to request new events from the event stream thread.
+    demand: flume::Sender<Demand>,
+    /// Number of events that were received from the event stream thread.
+    received: u64,
+    /// Simulated deadline of the current `recv_timeout` call.
+    deadline: Option<uhlc::NTP64>,
+}
+
 pub(crate) struct WriteEventsTo {
     node_id: NodeId,
     file: std::fs::File,
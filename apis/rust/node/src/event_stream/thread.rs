@@ -9,7 +9,10 @@ use dora_message::{
 use eyre::{Context, eyre};
 use flume::RecvTimeoutError;
 use std::{
-    sync::Arc,
+    sync::{
+        Arc,
+        atomic::{self, AtomicU64},
+    },
     time::{Duration, Instant},
 };
 
@@ -20,12 +23,27 @@ pub fn init(
     tx: tokio::sync::mpsc::UnboundedSender<EventItem>,
     channel: DaemonChannel,
     clock: Arc<uhlc::HLC>,
+    demand: Option<(flume::Receiver<Demand>, Arc<AtomicU64>)>,
 ) -> eyre::Result<EventStreamThreadHandle> {
     let node_id_cloned = node_id.clone();
-    let join_handle = std::thread::spawn(|| event_stream_loop(node_id_cloned, tx, channel, clock));
+    let join_handle =
+        std::thread::spawn(|| event_stream_loop(node_id_cloned, tx, channel, clock, demand));
     Ok(EventStreamThreadHandle::new(join_handle))
 }
 
+/// Signals that the node waits for new events.
+///
+/// Only used if the node follows the simulated clock of the daemon. In this
+/// mode, the event stream thread requests new events only on demand, so that
+/// the daemon knows when the node is idle.
+#[derive(Debug)]
+pub struct Demand {
+    /// Number of events that the node received from this thread so far.
+    pub received: u64,
+    /// Simulated time at which the node wants to be woken up.
+    pub wake_at: Option<uhlc::NTP64>,
+}
+
 #[derive(Debug)]
 #[allow(clippy::large_enum_variant)]
 pub enum EventItem {
@@ -80,23 +98,71 @@ impl Drop for EventStreamThreadHandle {
     }
 }
 
-#[tracing::instrument(skip(tx, channel, clock))]
+#[tracing::instrument(skip(tx, channel, clock, demand))]
 fn event_stream_loop(
     node_id: NodeId,
     tx: tokio::sync::mpsc::UnboundedSender<EventItem>,
     mut channel: DaemonChannel,
     clock: Arc<uhlc::HLC>,
+    demand: Option<(flume::Receiver<Demand>, Arc<AtomicU64>)>,
 ) {
     let mut tx = Some(tx);
     let mut close_tx = false;
     let mut pending_drop_tokens: Vec<(DropToken, flume::Receiver<()>, Instant, u64)> = Vec::new();
     let mut drop_tokens = Vec::new();
+    let mut pushed = 0;
 
     let result = 'outer: loop {
         if let Err(err) = handle_pending_drop_tokens(&mut pending_drop_tokens, &mut drop_tokens) {
             break 'outer Err(err);
         }
 
+        if let (Some((demand, sent_outputs)), Some(_)) = (&demand, &tx) {
+            // wait until the node handled all events that we pushed so far
+            let wake_at = loop {
+                match demand.recv_timeout(Duration::from_millis(100)) {
+                    Ok(demand) if demand.received == pushed => break demand.wake_at,
+                    Ok(_) => {
+                        // outdated demand, the node received new events since
+                    }
+                    Err(RecvTimeoutError::Timeout) => {
+                        // keep reporting drop tokens while the node is busy
+                        let result =
+                            handle_pending_drop_tokens(&mut pending_drop_tokens, &mut drop_tokens)
+                                .and_then(|()| {
+                                    report_drop_tokens(
+                                        &mut drop_tokens,
+                                        &mut channel,
+                                        clock.new_timestamp(),
+                                    )
+                                });
+                        if let Err(err) = result {
+                            break 'outer Err(err);
+                        }
+                    }
+                    Err(RecvTimeoutError::Disconnected) => break 'outer Ok(()),
+                }
+            };
+            let daemon_request = Timestamped {
+                inner: DaemonRequest::ReportIdle {
+                    sent_outputs: sent_outputs.load(atomic::Ordering::SeqCst),
+                    wake_at,
+                },
+                timestamp: clock.new_timestamp(),
+            };
+            match channel.request(&daemon_request) {
+                Ok(DaemonReply::Empty) => {}
+                Ok(other) => {
+                    let err = eyre!("unexpected ReportIdle reply: {other:?}");
+                    tracing::warn!("{err:?}");
+                }
+                Err(err) => {
+                    let err = err.wrap_err("failed to report idle state");
+                    break 'outer Err(err);
+                }
+            }
+        }
+
         let daemon_request = Timestamped {
             inner: DaemonRequest::NextEvent {
                 drop_tokens: std::mem::take(&mut drop_tokens),
@@ -154,7 +220,7 @@ fn event_stream_loop(
                     event: *inner,
                     ack_channel: drop_tx,
                 }) {
-                    Ok(()) => {}
+                    Ok(()) => pushed += 1,
                     Err(send_error) => {
                         let event = send_error.0;
                         tracing::warn!(
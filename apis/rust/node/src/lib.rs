@@ -92,19 +92,25 @@ pub use dora_core::{self, uhlc};
 pub use dora_message::{
     DataflowId,
     metadata::{Metadata, MetadataParameters, Parameter},
+    service::{GoalStatus, ServiceCall},
 };
 use dora_message::{
     common::Timestamped,
     daemon_to_node::{DaemonCommunication, DaemonReply},
     node_to_daemon::DaemonRequest,
 };
-pub use event_stream::{Event, EventScheduler, EventStream, StopCause, TryRecvError, merged};
+pub use daemon_connection::json_to_arrow;
+pub use event_stream::{
+    Event, EventScheduler, EventStream, GoalHandle, ReplyToken, StopCause, TryRecvError, merged,
+};
 pub use flume;
 pub use flume::Receiver;
 pub use futures;
 #[cfg(feature = "tracing")]
 pub use node::init_tracing;
-pub use node::{DataSample, DoraNode, ZERO_COPY_THRESHOLD, arrow_utils};
+pub use node::{
+    DataSample, DoraNode, LoanedArrayBuilder, LoanedOutput, ZERO_COPY_THRESHOLD, arrow_utils,
+};
 
 pub use serde_json;
 use tokio::sync::oneshot;
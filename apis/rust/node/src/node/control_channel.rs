@@ -1,4 +1,4 @@
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
 use crate::{DaemonCommunicationWrapper, daemon_connection::DaemonChannel};
 use dora_core::{
@@ -7,7 +7,7 @@ use dora_core::{
 };
 use dora_message::{
     DataflowId,
-    daemon_to_node::{DaemonCommunication, DaemonReply},
+    daemon_to_node::DaemonReply,
     metadata::Metadata,
     node_to_daemon::{DaemonRequest, DataMessage, Timestamped},
 };
@@ -28,15 +28,8 @@ impl ControlChannel {
     ) -> eyre::Result<Self> {
         let channel = match daemon_communication {
             DaemonCommunicationWrapper::Standard(daemon_communication) => {
-                match daemon_communication {
-                    DaemonCommunication::Tcp { socket_addr } => {
-                        DaemonChannel::new_tcp(*socket_addr)
-                            .wrap_err("failed to connect control channel")?
-                    }
-                    DaemonCommunication::Interactive => {
-                        DaemonChannel::Interactive(Default::default())
-                    }
-                }
+                DaemonChannel::connect(daemon_communication)
+                    .wrap_err("failed to connect control channel")?
             }
             DaemonCommunicationWrapper::Testing { channel } => {
                 DaemonChannel::IntegrationTestChannel(channel.clone())
@@ -92,6 +85,40 @@ impl ControlChannel {
         Ok(())
     }
 
+    pub fn report_ready(&mut self) -> eyre::Result<()> {
+        let reply = self
+            .channel
+            .request(&Timestamped {
+                inner: DaemonRequest::MarkReady,
+                timestamp: self.clock.new_timestamp(),
+            })
+            .wrap_err("failed to report ready to dora-daemon")?;
+        match reply {
+            DaemonReply::Result(result) => result
+                .map_err(|e| eyre!(e))
+                .wrap_err("failed to receive ready reply from dora-daemon")?,
+            other => bail!("unexpected ready reply: {other:?}"),
+        }
+        Ok(())
+    }
+
+    pub fn extend_stop_timeout(&mut self, duration: Duration) -> eyre::Result<()> {
+        let reply = self
+            .channel
+            .request(&Timestamped {
+                inner: DaemonRequest::ExtendStopTimeout { duration },
+                timestamp: self.clock.new_timestamp(),
+            })
+            .wrap_err("failed to request stop timeout extension from dora-daemon")?;
+        match reply {
+            DaemonReply::Result(result) => result
+                .map_err(|e| eyre!(e))
+                .wrap_err("failed to extend stop timeout")?,
+            other => bail!("unexpected ExtendStopTimeout reply: {other:?}"),
+        }
+        Ok(())
+    }
+
     pub fn send_message(
         &mut self,
         output_id: DataId,
@@ -4,7 +4,7 @@ use crate::{DaemonCommunicationWrapper, daemon_connection::DaemonChannel};
 use dora_core::{config::NodeId, uhlc};
 use dora_message::{
     DataflowId,
-    daemon_to_node::{DaemonCommunication, DaemonReply, NodeDropEvent},
+    daemon_to_node::{DaemonReply, NodeDropEvent},
     node_to_daemon::{DaemonRequest, DropToken, Timestamped},
 };
 use eyre::{Context, eyre};
@@ -25,16 +25,9 @@ impl DropStream {
     ) -> eyre::Result<Self> {
         let channel = match daemon_communication {
             DaemonCommunicationWrapper::Standard(daemon_communication) => {
-                match daemon_communication {
-                    DaemonCommunication::Tcp { socket_addr } => {
-                        DaemonChannel::new_tcp(*socket_addr).wrap_err_with(|| {
-                            format!("failed to connect drop stream for node `{node_id}`")
-                        })?
-                    }
-                    DaemonCommunication::Interactive => {
-                        DaemonChannel::Interactive(Default::default())
-                    }
-                }
+                DaemonChannel::connect(daemon_communication).wrap_err_with(|| {
+                    format!("failed to connect drop stream for node `{node_id}`")
+                })?
             }
             DaemonCommunicationWrapper::Testing { channel } => {
                 DaemonChannel::IntegrationTestChannel(channel.clone())
@@ -0,0 +1,340 @@
+//! Output buffers that are written in place before they are sent.
+//!
+use std::{
+    marker::PhantomData,
+    ops::{Deref, DerefMut},
+};
+
+use arrow::datatypes::{ArrowPrimitiveType, DataType};
+use dora_core::config::DataId;
+use dora_message::metadata::{ArrowTypeInfo, BufferOffset};
+
+use super::{DataSample, DataSampleInner};
+
+/// A buffer for an output message of a fixed-width primitive type.
+///
+/// Created through [`DoraNode::loan_output`][super::DoraNode::loan_output]. The buffer is
+/// placed in shared memory if it's large enough, so sending it through
+/// [`DoraNode::send_loaned_output`][super::DoraNode::send_loaned_output] does not copy
+/// the data.
+///
+/// Dereferences to the raw bytes of the values. The buffer is aligned for all
+/// primitive types, but its initial content is unspecified.
+pub struct LoanedOutput {
+    pub(super) output_id: DataId,
+    data_type: DataType,
+    len: usize,
+    pub(super) sample: DataSample,
+}
+
+impl LoanedOutput {
+    pub(super) fn new(
+        output_id: DataId,
+        data_type: DataType,
+        len: usize,
+        sample: DataSample,
+    ) -> Self {
+        Self {
+            output_id,
+            data_type,
+            len,
+            sample,
+        }
+    }
+
+    /// The output that this buffer will be sent on.
+    pub fn output_id(&self) -> &DataId {
+        &self.output_id
+    }
+
+    /// The Arrow data type of the values.
+    pub fn data_type(&self) -> &DataType {
+        &self.data_type
+    }
+
+    /// The number of values in the buffer.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the buffer holds no values.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Shortens the buffer to the first `len` values.
+    ///
+    /// Has no effect if `len` is not smaller than the current length.
+    pub fn truncate(&mut self, len: usize) {
+        if len >= self.len {
+            return;
+        }
+        let byte_len = len * self.value_width();
+        self.sample.len = byte_len;
+        if let DataSampleInner::Vec(data) = &mut self.sample.inner {
+            data.truncate(byte_len);
+        }
+        self.len = len;
+    }
+
+    /// The values of the buffer.
+    ///
+    /// Fails if `T` does not match the data type of the buffer.
+    pub fn values<T: ArrowPrimitiveType>(&self) -> eyre::Result<&[T::Native]> {
+        self.check_type::<T>()?;
+        let bytes: &[u8] = &self.sample;
+        // SAFETY: the buffer is aligned to at least 128 bytes, its length matches the
+        // value width of `T`, and every bit pattern is a valid value of a native Arrow type
+        Ok(unsafe { std::slice::from_raw_parts(bytes.as_ptr().cast(), self.len) })
+    }
+
+    /// Mutable access to the values of the buffer.
+    ///
+    /// Fails if `T` does not match the data type of the buffer.
+    pub fn values_mut<T: ArrowPrimitiveType>(&mut self) -> eyre::Result<&mut [T::Native]> {
+        self.check_type::<T>()?;
+        let len = self.len;
+        let bytes: &mut [u8] = &mut self.sample;
+        // SAFETY: see `values`
+        Ok(unsafe { std::slice::from_raw_parts_mut(bytes.as_mut_ptr().cast(), len) })
+    }
+
+    fn check_type<T: ArrowPrimitiveType>(&self) -> eyre::Result<()> {
+        if self.data_type != T::DATA_TYPE {
+            eyre::bail!(
+                "cannot access loaned output of type `{}` as `{}`",
+                self.data_type,
+                T::DATA_TYPE
+            );
+        }
+        Ok(())
+    }
+
+    pub(super) fn type_info(&self) -> ArrowTypeInfo {
+        ArrowTypeInfo {
+            data_type: self.data_type.clone(),
+            len: self.len,
+            null_count: 0,
+            validity: None,
+            offset: 0,
+            buffer_offsets: vec![BufferOffset {
+                offset: 0,
+                len: self.sample.len,
+            }],
+            child_data: Vec::new(),
+        }
+    }
+
+    fn value_width(&self) -> usize {
+        self.data_type
+            .primitive_width()
+            .expect("loaned outputs are only created for primitive types")
+    }
+}
+
+impl Deref for LoanedOutput {
+    type Target = [u8];
+
+    fn deref(&self) -> &Self::Target {
+        &self.sample
+    }
+}
+
+impl DerefMut for LoanedOutput {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.sample
+    }
+}
+
+impl std::fmt::Debug for LoanedOutput {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LoanedOutput")
+            .field("output_id", &self.output_id)
+            .field("data_type", &self.data_type)
+            .field("len", &self.len)
+            .field("sample", &self.sample)
+            .finish()
+    }
+}
+
+/// Builds a primitive Arrow array directly in the buffer of an output message.
+///
+/// Created through [`DoraNode::loan_array_builder`][super::DoraNode::loan_array_builder]
+/// with a fixed capacity. Appending more values than the capacity panics. Only the appended
+/// values are sent on [`DoraNode::send_loaned_array`][super::DoraNode::send_loaned_array].
+pub struct LoanedArrayBuilder<T: ArrowPrimitiveType> {
+    loan: LoanedOutput,
+    len: usize,
+    _type: PhantomData<T>,
+}
+
+impl<T: ArrowPrimitiveType> LoanedArrayBuilder<T> {
+    pub(super) fn new(loan: LoanedOutput) -> Self {
+        Self {
+            loan,
+            len: 0,
+            _type: PhantomData,
+        }
+    }
+
+    /// The number of values that fit into the buffer.
+    pub fn capacity(&self) -> usize {
+        self.loan.len
+    }
+
+    /// The number of values that were appended so far.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if no values were appended yet.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Appends a single value.
+    ///
+    /// Panics if the builder is full.
+    pub fn append_value(&mut self, value: T::Native) {
+        self.append_slice(&[value]);
+    }
+
+    /// Appends all given values.
+    ///
+    /// Panics if the values don't fit into the remaining capacity.
+    pub fn append_slice(&mut self, values: &[T::Native]) {
+        self.spare_capacity_mut()[..values.len()].copy_from_slice(values);
+        self.len += values.len();
+    }
+
+    /// The values that were appended so far.
+    pub fn values_slice(&self) -> &[T::Native] {
+        &self.all_values()[..self.len]
+    }
+
+    /// Mutable access to the values that were appended so far.
+    pub fn values_slice_mut(&mut self) -> &mut [T::Native] {
+        let len = self.len;
+        &mut self.all_values_mut()[..len]
+    }
+
+    /// The remaining capacity of the buffer.
+    ///
+    /// Values written to this slice are only sent after they are committed through
+    /// [`advance`][Self::advance]. This allows filling the buffer directly, e.g. by
+    /// decoding an image into it.
+    pub fn spare_capacity_mut(&mut self) -> &mut [T::Native] {
+        let len = self.len;
+        &mut self.all_values_mut()[len..]
+    }
+
+    /// Marks the next `additional` values of the spare capacity as appended.
+    ///
+    /// Panics if this exceeds the capacity.
+    pub fn advance(&mut self, additional: usize) {
+        let new_len = self.len + additional;
+        assert!(
+            new_len <= self.capacity(),
+            "cannot advance loaned array builder to {new_len} values: capacity is {}",
+            self.capacity()
+        );
+        self.len = new_len;
+    }
+
+    /// Converts the builder into an untyped buffer that holds the appended values.
+    pub fn finish(mut self) -> LoanedOutput {
+        self.loan.truncate(self.len);
+        self.loan
+    }
+
+    fn all_values(&self) -> &[T::Native] {
+        self.loan
+            .values::<T>()
+            .expect("builder loans are created with the builder type")
+    }
+
+    fn all_values_mut(&mut self) -> &mut [T::Native] {
+        self.loan
+            .values_mut::<T>()
+            .expect("builder loans are created with the builder type")
+    }
+}
+
+impl<T: ArrowPrimitiveType> std::fmt::Debug for LoanedArrayBuilder<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LoanedArrayBuilder")
+            .field("loan", &self.loan)
+            .field("len", &self.len)
+            .finish()
+    }
+}
+
+/// Checks that values of the given type can be loaned and returns their width in bytes.
+pub(super) fn value_width(data_type: &DataType) -> eyre::Result<usize> {
+    data_type.primitive_width().ok_or_else(|| {
+        eyre::eyre!("cannot loan output buffer for non-primitive type `{data_type}`")
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow::datatypes::{DataType, Float32Type, UInt32Type};
+
+    use super::{LoanedOutput, value_width};
+    use crate::{
+        DataSample, DoraNode,
+        integration_testing::{IntegrationTestInput, TestingInput, TestingOptions, TestingOutput},
+    };
+
+    fn loan(data_type: DataType, len: usize) -> LoanedOutput {
+        let width = value_width(&data_type).unwrap();
+        let sample: DataSample = aligned_vec::AVec::__from_elem(128, 0, len * width).into();
+        LoanedOutput::new("out".to_owned().into(), data_type, len, sample)
+    }
+
+    #[test]
+    fn builder_sends_appended_values() -> eyre::Result<()> {
+        let inputs = TestingInput::Input(IntegrationTestInput::new("node".parse()?, Vec::new()));
+        let (tx, rx) = flume::unbounded();
+        let options = TestingOptions {
+            skip_output_time_offsets: true,
+        };
+        let (mut node, _events) =
+            DoraNode::init_testing(inputs, TestingOutput::ToChannel(tx), options)?;
+
+        let mut builder = node.loan_array_builder::<Float32Type>("out".into(), 8)?;
+        builder.append_value(1.0);
+        builder.append_slice(&[2.0, 3.0]);
+        builder.spare_capacity_mut()[0] = 4.0;
+        builder.advance(1);
+        assert_eq!(builder.values_slice(), &[1.0, 2.0, 3.0, 4.0]);
+        node.send_loaned_array(builder, Default::default())?;
+        std::mem::drop(node);
+
+        let outputs = rx.try_iter().collect::<Vec<_>>();
+        let expected = serde_json::json!({
+            "id": "out",
+            "data_type": "Float32",
+            "data": [1.0, 2.0, 3.0, 4.0]
+        });
+        assert_eq!(outputs, [expected.as_object().unwrap().clone()]);
+        Ok(())
+    }
+
+    #[test]
+    fn values_check_data_type() {
+        let mut loan = loan(DataType::Float32, 2);
+        loan.values_mut::<Float32Type>()
+            .unwrap()
+            .copy_from_slice(&[0.5, 1.5]);
+        assert_eq!(loan.values::<Float32Type>().unwrap(), &[0.5, 1.5]);
+        assert!(loan.values::<UInt32Type>().is_err());
+    }
+
+    #[test]
+    fn non_primitive_types_are_rejected() {
+        assert!(value_width(&DataType::Utf8).is_err());
+        assert!(value_width(&DataType::Boolean).is_err());
+        assert_eq!(value_width(&DataType::UInt16).unwrap(), 2);
+    }
+}
@@ -1,5 +1,5 @@
 use crate::{
-    DaemonCommunicationWrapper, EventStream,
+    DaemonCommunicationWrapper, EventStream, GoalHandle, ReplyToken,
     daemon_connection::{DaemonChannel, IntegrationTestingEvents},
     integration_testing::{
         TestingCommunication, TestingInput, TestingOptions, TestingOutput,
@@ -13,7 +13,10 @@ use self::{
     drop_stream::DropStream,
 };
 use aligned_vec::{AVec, ConstAlign};
-use arrow::array::Array;
+use arrow::{
+    array::Array,
+    datatypes::{ArrowPrimitiveType, DataType},
+};
 use colored::Colorize;
 use dora_core::{
     config::{DataId, NodeId, NodeRunConfig},
@@ -25,8 +28,12 @@ use dora_core::{
 use dora_message::{
     DataflowId,
     daemon_to_node::{DaemonCommunication, DaemonReply, NodeConfig},
-    metadata::{ArrowTypeInfo, Metadata, MetadataParameters},
+    metadata::{ArrowTypeInfo, Metadata, MetadataParameters, Parameter},
     node_to_daemon::{DaemonRequest, DataMessage, DropToken, Timestamped},
+    service::{
+        ACTION_MESSAGE_PARAMETER, ActionMessage, GOAL_STATUS_PARAMETER, GoalStatus,
+        REQUEST_ID_PARAMETER, REQUEST_TIMEOUT_PARAMETER, ServiceCall,
+    },
 };
 use eyre::{WrapErr, bail};
 use is_terminal::IsTerminal;
@@ -37,7 +44,10 @@ use std::{
     collections::{BTreeSet, HashMap, VecDeque},
     ops::{Deref, DerefMut},
     path::PathBuf,
-    sync::Arc,
+    sync::{
+        Arc,
+        atomic::{self, AtomicU64},
+    },
     time::Duration,
 };
 use tokio::runtime::Handle;
@@ -49,6 +59,9 @@ use tracing::{info, warn};
 pub mod arrow_utils;
 mod control_channel;
 mod drop_stream;
+mod loan;
+
+pub use self::loan::{LoanedArrayBuilder, LoanedOutput};
 
 /// The data size threshold at which we start using shared memory.
 ///
@@ -76,6 +89,8 @@ pub struct DoraNode {
     node_config: NodeRunConfig,
     control_channel: ControlChannel,
     clock: Arc<uhlc::HLC>,
+    /// Number of sent outputs, reported to the daemon when using a simulated clock.
+    sent_outputs: Arc<AtomicU64>,
 
     sent_out_shared_memory: HashMap<DropToken, ShmemHandle>,
     drop_stream: DropStream,
@@ -83,6 +98,7 @@ pub struct DoraNode {
 
     dataflow_descriptor: serde_yaml::Result<Descriptor>,
     warned_unknown_output: BTreeSet<DataId>,
+    next_request_id: u64,
     interactive: bool,
 }
 
@@ -364,6 +380,8 @@ impl DoraNode {
             dataflow_descriptor: serde_yaml::Value::Null,
             dynamic: false,
             write_events_to: None,
+            simulated_clock: false,
+            liveness_timeout: None,
         };
         let (mut node, events) = Self::init(node_config)?;
         node.interactive = true;
@@ -393,6 +411,8 @@ impl DoraNode {
             dataflow_descriptor: serde_yaml::Value::Null,
             dynamic: false,
             write_events_to: None,
+            simulated_clock: false,
+            liveness_timeout: None,
         };
         let testing_comm = TestingCommunication {
             input,
@@ -424,8 +444,20 @@ impl DoraNode {
             dataflow_descriptor,
             dynamic,
             write_events_to,
+            simulated_clock,
+            liveness_timeout,
         } = node_config;
-        let clock = Arc::new(uhlc::HLC::default());
+        let clock = if simulated_clock {
+            // follow the timestamps of the received events only
+            uhlc::HLCBuilder::new()
+                .with_clock(|| uhlc::NTP64(0))
+                .with_max_delta(Duration::from_secs(u32::MAX.into()))
+                .build()
+        } else {
+            uhlc::HLC::default()
+        };
+        let clock = Arc::new(clock);
+        let sent_outputs = Arc::new(AtomicU64::new(0));
         let input_config = run_config.inputs.clone();
 
         let daemon_communication = match daemon_communication {
@@ -466,6 +498,8 @@ impl DoraNode {
             input_config,
             clock.clone(),
             write_events_to,
+            simulated_clock.then(|| sent_outputs.clone()),
+            liveness_timeout,
         )
         .wrap_err("failed to init event stream")?;
         let drop_stream =
@@ -480,11 +514,13 @@ impl DoraNode {
             node_config: run_config.clone(),
             control_channel,
             clock,
+            sent_outputs,
             sent_out_shared_memory: HashMap::new(),
             drop_stream,
             cache: VecDeque::new(),
             dataflow_descriptor: serde_yaml::from_value(dataflow_descriptor),
             warned_unknown_output: BTreeSet::new(),
+            next_request_id: 0,
             interactive: false,
         };
 
@@ -680,6 +716,7 @@ impl DoraNode {
         self.control_channel
             .send_message(output_id.clone(), metadata, data)
             .wrap_err_with(|| format!("failed to send output {output_id}"))?;
+        self.sent_outputs.fetch_add(1, atomic::Ordering::SeqCst);
 
         if let Some((shared_memory, drop_token)) = shmem {
             self.sent_out_shared_memory
@@ -689,6 +726,72 @@ impl DoraNode {
         Ok(())
     }
 
+    /// Loans a buffer for `len` values of the given primitive type.
+    ///
+    /// The buffer is placed in shared memory if it's large enough, so nodes can write
+    /// e.g. images or point clouds directly into the message without copying. Send it
+    /// through [`send_loaned_output`][Self::send_loaned_output] once it is filled.
+    ///
+    /// Fails if `data_type` is not a fixed-width primitive type.
+    pub fn loan_output(
+        &mut self,
+        output_id: DataId,
+        data_type: DataType,
+        len: usize,
+    ) -> eyre::Result<LoanedOutput> {
+        let width = loan::value_width(&data_type)?;
+        let data_len = len
+            .checked_mul(width)
+            .ok_or_else(|| eyre::eyre!("loaned output of {len} values is too large"))?;
+        let sample = self.allocate_data_sample(data_len)?;
+        Ok(LoanedOutput::new(output_id, data_type, len, sample))
+    }
+
+    /// Loans a buffer for up to `capacity` values and returns a builder that appends to it.
+    ///
+    /// The typed counterpart of [`loan_output`][Self::loan_output]. Send the built array
+    /// through [`send_loaned_array`][Self::send_loaned_array].
+    pub fn loan_array_builder<T: ArrowPrimitiveType>(
+        &mut self,
+        output_id: DataId,
+        capacity: usize,
+    ) -> eyre::Result<LoanedArrayBuilder<T>> {
+        let loan = self.loan_output(output_id, T::DATA_TYPE, capacity)?;
+        Ok(LoanedArrayBuilder::new(loan))
+    }
+
+    /// Sends the given loaned buffer as an Arrow array on its output.
+    ///
+    /// Does not copy the data.
+    ///
+    /// Ignores the output if its `output_id` is not specified as node output in the dataflow
+    /// configuration file.
+    pub fn send_loaned_output(
+        &mut self,
+        loan: LoanedOutput,
+        parameters: MetadataParameters,
+    ) -> eyre::Result<()> {
+        if !self.validate_output(&loan.output_id) {
+            return Ok(());
+        };
+        let type_info = loan.type_info();
+        self.send_output_sample(loan.output_id, type_info, parameters, Some(loan.sample))
+    }
+
+    /// Sends the values appended to the given builder as an Arrow array.
+    ///
+    /// Does not copy the data.
+    ///
+    /// Ignores the output if its `output_id` is not specified as node output in the dataflow
+    /// configuration file.
+    pub fn send_loaned_array<T: ArrowPrimitiveType>(
+        &mut self,
+        builder: LoanedArrayBuilder<T>,
+        parameters: MetadataParameters,
+    ) -> eyre::Result<()> {
+        self.send_loaned_output(builder.finish(), parameters)
+    }
+
     /// Report the given outputs IDs as closed.
     ///
     /// The node is not allowed to send more outputs with the closed IDs.
@@ -708,6 +811,212 @@ impl DoraNode {
         Ok(())
     }
 
+    /// Reports that this node finished its initialization.
+    ///
+    /// Nodes that list this node with the `ready` condition in their `depends_on`
+    /// field are only spawned after this call. Calling it again has no effect.
+    pub fn mark_ready(&mut self) -> eyre::Result<()> {
+        self.control_channel
+            .report_ready()
+            .wrap_err("failed to mark node as ready")
+    }
+
+    /// Requests more time to exit after this node received a stop event.
+    ///
+    /// The daemon terminates nodes that don't exit within their `stop_timeout`
+    /// after the [`Stop`](crate::Event::Stop) event. This function restarts the
+    /// timeout with the given `duration`, e.g. to finish flushing files. Fails if
+    /// the node was not asked to stop yet.
+    pub fn extend_stop_timeout(&mut self, duration: Duration) -> eyre::Result<()> {
+        self.control_channel
+            .extend_stop_timeout(duration)
+            .wrap_err("failed to extend stop timeout")
+    }
+
+    /// Sends a request to a service of another node.
+    ///
+    /// The `service` is given as `<node>/<service>` and must be listed in the `calls`
+    /// field of this node in the dataflow configuration file. The reply is delivered
+    /// as [`Event::Reply`](crate::Event::Reply) with the returned request ID. If no
+    /// reply arrives within the given `timeout`, the reply contains an error instead.
+    ///
+    /// ```no_run
+    /// use dora_node_api::{DoraNode, MetadataParameters, arrow::array::Float32Array};
+    /// use std::time::Duration;
+    ///
+    /// let (mut node, mut events) = DoraNode::init_from_env().expect("Could not init node.");
+    ///
+    /// let goal = Float32Array::from(vec![1.0, 2.5]);
+    /// let request_id = node
+    ///     .call("planner/plan_path", MetadataParameters::default(), goal, Duration::from_secs(1))
+    ///     .expect("Could not call service");
+    /// ```
+    pub fn call(
+        &mut self,
+        service: &str,
+        mut parameters: MetadataParameters,
+        data: impl Array,
+        timeout: Duration,
+    ) -> eyre::Result<u64> {
+        let call: ServiceCall = service.parse().map_err(|err: String| eyre::eyre!(err))?;
+        let output_id = call.request_output();
+        if !self.node_config.outputs.contains(&output_id) && !self.interactive {
+            bail!(
+                "service `{service}` is not listed in the `calls` of node `{}`",
+                self.id
+            );
+        }
+
+        let request_id = self.next_request_id;
+        self.next_request_id += 1;
+        parameters.insert(
+            REQUEST_ID_PARAMETER.into(),
+            Parameter::Integer(request_id as i64),
+        );
+        let timeout_ms = timeout.as_millis().try_into().unwrap_or(i64::MAX);
+        parameters.insert(
+            REQUEST_TIMEOUT_PARAMETER.into(),
+            Parameter::Integer(timeout_ms),
+        );
+        self.send_output(output_id, parameters, data)
+            .wrap_err_with(|| format!("failed to call service `{service}`"))?;
+        Ok(request_id)
+    }
+
+    /// Answers a service request that was received as [`Event::Request`](crate::Event::Request).
+    pub fn reply(
+        &mut self,
+        reply_token: ReplyToken,
+        mut parameters: MetadataParameters,
+        data: impl Array,
+    ) -> eyre::Result<()> {
+        parameters.insert(
+            REQUEST_ID_PARAMETER.into(),
+            Parameter::Integer(reply_token.request_id),
+        );
+        self.send_output(reply_token.output_id, parameters, data)
+            .wrap_err("failed to send reply")
+    }
+
+    /// Sends a goal to an action of another node.
+    ///
+    /// The `action` is given as `<node>/<action>` and must be listed in the `calls`
+    /// field of this node in the dataflow configuration file. Feedback on the goal is
+    /// delivered as [`Event::Feedback`](crate::Event::Feedback) and the final result as
+    /// [`Event::GoalResult`](crate::Event::GoalResult), both with the returned goal ID.
+    ///
+    /// ```no_run
+    /// use dora_node_api::{DoraNode, MetadataParameters, arrow::array::Float32Array};
+    ///
+    /// let (mut node, mut events) = DoraNode::init_from_env().expect("Could not init node.");
+    ///
+    /// let target = Float32Array::from(vec![4.0, 2.0]);
+    /// let goal_id = node
+    ///     .send_goal("navigator/navigate_to", MetadataParameters::default(), target)
+    ///     .expect("Could not send goal");
+    /// ```
+    pub fn send_goal(
+        &mut self,
+        action: &str,
+        mut parameters: MetadataParameters,
+        data: impl Array,
+    ) -> eyre::Result<u64> {
+        let call = self.action_call(action)?;
+        let goal_id = self.next_request_id;
+        self.next_request_id += 1;
+        parameters.insert(
+            REQUEST_ID_PARAMETER.into(),
+            Parameter::Integer(goal_id as i64),
+        );
+        parameters.insert(
+            ACTION_MESSAGE_PARAMETER.into(),
+            Parameter::String(ActionMessage::Goal.as_str().into()),
+        );
+        self.send_output(call.request_output(), parameters, data)
+            .wrap_err_with(|| format!("failed to send goal to action `{action}`"))?;
+        Ok(goal_id)
+    }
+
+    /// Asks the server of an action to cancel a goal sent through [`send_goal`](Self::send_goal).
+    ///
+    /// The goal is still finished through an [`Event::GoalResult`](crate::Event::GoalResult),
+    /// which has the status [`GoalStatus::Canceled`] if the server stopped working on it.
+    /// Cancel requests for goals that are already finished are ignored.
+    pub fn cancel_goal(&mut self, action: &str, goal_id: u64) -> eyre::Result<()> {
+        let call = self.action_call(action)?;
+        let mut parameters = MetadataParameters::default();
+        parameters.insert(
+            REQUEST_ID_PARAMETER.into(),
+            Parameter::Integer(goal_id as i64),
+        );
+        parameters.insert(
+            ACTION_MESSAGE_PARAMETER.into(),
+            Parameter::String(ActionMessage::Cancel.as_str().into()),
+        );
+        self.send_output(
+            call.request_output(),
+            parameters,
+            arrow::array::NullArray::new(0),
+        )
+        .wrap_err_with(|| format!("failed to cancel goal of action `{action}`"))
+    }
+
+    fn action_call(&self, action: &str) -> eyre::Result<ServiceCall> {
+        let call: ServiceCall = action.parse().map_err(|err: String| eyre::eyre!(err))?;
+        if !self.node_config.outputs.contains(&call.request_output()) && !self.interactive {
+            bail!(
+                "action `{action}` is not listed in the `calls` of node `{}`",
+                self.id
+            );
+        }
+        Ok(call)
+    }
+
+    /// Reports progress on a goal that was received as [`Event::Goal`](crate::Event::Goal).
+    pub fn send_feedback(
+        &mut self,
+        goal: &GoalHandle,
+        mut parameters: MetadataParameters,
+        data: impl Array,
+    ) -> eyre::Result<()> {
+        parameters.insert(
+            REQUEST_ID_PARAMETER.into(),
+            Parameter::Integer(goal.goal_id),
+        );
+        parameters.insert(
+            ACTION_MESSAGE_PARAMETER.into(),
+            Parameter::String(ActionMessage::Feedback.as_str().into()),
+        );
+        self.send_output(goal.output_id.clone(), parameters, data)
+            .wrap_err("failed to send feedback")
+    }
+
+    /// Sends the final result of a goal that was received as [`Event::Goal`](crate::Event::Goal).
+    ///
+    /// No feedback can be sent for the goal afterwards.
+    pub fn finish_goal(
+        &mut self,
+        goal: GoalHandle,
+        status: GoalStatus,
+        mut parameters: MetadataParameters,
+        data: impl Array,
+    ) -> eyre::Result<()> {
+        parameters.insert(
+            REQUEST_ID_PARAMETER.into(),
+            Parameter::Integer(goal.goal_id),
+        );
+        parameters.insert(
+            ACTION_MESSAGE_PARAMETER.into(),
+            Parameter::String(ActionMessage::Result.as_str().into()),
+        );
+        parameters.insert(
+            GOAL_STATUS_PARAMETER.into(),
+            Parameter::String(status.as_str().into()),
+        );
+        self.send_output(goal.output_id, parameters, data)
+            .wrap_err("failed to send goal result")
+    }
+
     /// Returns the ID of the node as specified in the dataflow configuration file.
     pub fn id(&self) -> &NodeId {
         &self.id
@@ -0,0 +1,90 @@
+use std::collections::BTreeSet;
+
+use clap::Args;
+use dora_core::descriptor::{Descriptor, DescriptorExt};
+use dora_message::{
+    cli_to_coordinator::CoordinatorControlClient, common::DataflowChanges, id::NodeId, tarpc,
+};
+use eyre::Context;
+
+use crate::{
+    command::{Executable, default_tracing},
+    common::{CoordinatorOptions, resolve_dataflow, resolve_dataflow_identifier_interactive, rpc},
+};
+
+/// Update a running dataflow to match the given dataflow file.
+///
+/// New nodes are spawned, deleted nodes are stopped, and nodes with changed
+/// input mappings are rewired without a restart. Nodes with other changes are
+/// restarted. All other nodes keep running.
+///
+/// Examples:
+///
+/// Add a node to the running dataflow:
+///   dora apply dataflow.yml
+///
+/// Update a specific dataflow:
+///   dora apply dataflow.yml --dataflow my-dataflow
+#[derive(Debug, Args)]
+#[clap(verbatim_doc_comment)]
+pub struct Apply {
+    /// Path to the updated dataflow descriptor file
+    #[clap(value_name = "PATH")]
+    dataflow_path: String,
+    /// Name or UUID of the running dataflow
+    #[clap(long, short = 'd', value_name = "NAME_OR_UUID")]
+    dataflow: Option<String>,
+
+    #[clap(flatten)]
+    coordinator: CoordinatorOptions,
+}
+
+impl Executable for Apply {
+    async fn execute(self) -> eyre::Result<()> {
+        default_tracing()?;
+
+        let dataflow_path = resolve_dataflow(self.dataflow_path)
+            .await
+            .context("could not resolve dataflow")?;
+        let working_dir = dataflow_path
+            .canonicalize()
+            .context("failed to canonicalize dataflow path")?
+            .parent()
+            .ok_or_else(|| eyre::eyre!("dataflow path has no parent dir"))?
+            .to_owned();
+        let descriptor =
+            Descriptor::blocking_read(&dataflow_path).wrap_err("Failed to read yaml dataflow")?;
+        descriptor
+            .check(&working_dir)
+            .wrap_err("could not validate yaml")?;
+
+        let client: CoordinatorControlClient = self.coordinator.connect_rpc().await?;
+        let dataflow_id =
+            resolve_dataflow_identifier_interactive(&client, self.dataflow.as_deref()).await?;
+        let changes = rpc(
+            "apply dataflow changes",
+            client.apply(tarpc::context::current(), dataflow_id, descriptor),
+        )
+        .await?;
+
+        print_changes(&changes);
+        Ok(())
+    }
+}
+
+fn print_changes(changes: &DataflowChanges) {
+    if changes.is_empty() {
+        println!("No changes");
+        return;
+    }
+    let print = |label: &str, nodes: &BTreeSet<NodeId>| {
+        if !nodes.is_empty() {
+            let nodes: Vec<_> = nodes.iter().map(|n| n.to_string()).collect();
+            println!("{label}: {}", nodes.join(", "));
+        }
+    };
+    print("Added", &changes.added);
+    print("Removed", &changes.removed);
+    print("Restarted", &changes.restarted);
+    print("Rewired", &changes.rewired);
+}
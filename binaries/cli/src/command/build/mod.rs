@@ -58,7 +58,8 @@ use std::{collections::BTreeMap, net::IpAddr};
 use super::{Executable, default_tracing};
 use crate::{
     common::{
-        ConnectAndCheckVersionError, connect_and_check_version, local_working_dir, resolve_dataflow,
+        ConnectAndCheckVersionError, CoordinatorAddrs, connect_and_check_version,
+        local_working_dir, resolve_dataflow,
     },
     session::DataflowSession,
 };
@@ -76,9 +77,10 @@ pub struct Build {
     /// Path to the dataflow descriptor file
     #[clap(value_name = "PATH")]
     dataflow: String,
-    /// Address of the dora coordinator
-    #[clap(long, value_name = "IP")]
-    coordinator_addr: Option<IpAddr>,
+    /// Address of the dora coordinator, or comma-separated addresses of a
+    /// high-availability coordinator cluster
+    #[clap(long, value_name = "IP[:PORT],...")]
+    coordinator_addr: Option<CoordinatorAddrs>,
     /// Port number of the coordinator control server
     #[clap(long, value_name = "PORT")]
     coordinator_port: Option<u16>,
@@ -93,7 +95,7 @@ pub struct Build {
 impl Executable for Build {
     async fn execute(self) -> eyre::Result<()> {
         default_tracing()?;
-        build_async(
+        build_with_coordinators(
             self.dataflow,
             self.coordinator_addr,
             self.coordinator_port,
@@ -130,6 +132,23 @@ pub async fn build_async(
     coordinator_port: Option<u16>,
     uv: bool,
     force_local: bool,
+) -> eyre::Result<()> {
+    build_with_coordinators(
+        dataflow,
+        coordinator_addr.map(CoordinatorAddrs::from),
+        coordinator_port,
+        uv,
+        force_local,
+    )
+    .await
+}
+
+async fn build_with_coordinators(
+    dataflow: String,
+    coordinator_addr: Option<CoordinatorAddrs>,
+    coordinator_port: Option<u16>,
+    uv: bool,
+    force_local: bool,
 ) -> eyre::Result<()> {
     let dataflow_path = resolve_dataflow(dataflow)
         .await
@@ -155,7 +174,8 @@ pub async fn build_async(
         }
     }
 
-    let session = || connect_to_coordinator_rpc_with_defaults(coordinator_addr, coordinator_port);
+    let session =
+        || connect_to_coordinator_rpc_with_defaults(coordinator_addr.as_ref(), coordinator_port);
 
     let build_kind = if force_local {
         tracing::info!("Building locally, as requested through `--force-local`");
@@ -228,7 +248,9 @@ pub async fn build_async(
                 &dataflow_session,
                 local_working_dir,
                 uv,
-                coordinator_addr.unwrap_or(LOCALHOST),
+                coordinator_addr
+                    .as_ref()
+                    .map_or(LOCALHOST, |addr| addr.ip()),
                 log::LevelFilter::Info,
             )
             .await?;
@@ -253,10 +275,12 @@ enum BuildKind {
 }
 
 async fn connect_to_coordinator_rpc_with_defaults(
-    coordinator_addr: Option<std::net::IpAddr>,
+    coordinator_addr: Option<&CoordinatorAddrs>,
     coordinator_port: Option<u16>,
 ) -> Result<CoordinatorControlClient, ConnectAndCheckVersionError> {
-    let addr = coordinator_addr.unwrap_or(LOCALHOST);
+    let addr = coordinator_addr
+        .cloned()
+        .unwrap_or_else(|| LOCALHOST.into());
     let control_port = coordinator_port.unwrap_or(DORA_COORDINATOR_PORT_CONTROL_DEFAULT);
-    connect_and_check_version(addr, control_port).await
+    connect_and_check_version(&addr, control_port).await
 }
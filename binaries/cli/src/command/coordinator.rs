@@ -25,6 +25,14 @@ pub struct Coordinator {
     /// Port number to bind to for control communication
     #[clap(long, default_value_t = DORA_COORDINATOR_PORT_CONTROL_DEFAULT)]
     control_port: u16,
+    /// Run as a member of a high-availability coordinator cluster, using the
+    /// given address for the Raft communication with the other members
+    #[clap(long, value_name = "IP:PORT", requires = "ha_peer")]
+    ha_addr: Option<SocketAddr>,
+    /// Raft address of another member of the high-availability cluster
+    /// (can be repeated)
+    #[clap(long, value_name = "IP:PORT", requires = "ha_addr")]
+    ha_peer: Vec<SocketAddr>,
     /// Suppresses all log output to stdout.
     #[clap(long)]
     quiet: bool,
@@ -47,6 +55,24 @@ impl Executable for Coordinator {
 
         let bind = SocketAddr::new(self.interface, self.port);
         let bind_control = SocketAddr::new(self.control_interface, self.control_port);
+        if let Some(addr) = self.ha_addr {
+            let config = dora_coordinator::HaConfig {
+                addr,
+                peers: self.ha_peer,
+            };
+            let task = dora_coordinator::start_ha(
+                bind,
+                bind_control,
+                config,
+                futures::stream::empty::<Event>(),
+            )
+            .await?;
+            if !self.quiet {
+                println!("Waiting for election as leader of the coordinator cluster on {addr}");
+            }
+            return task.await.context("failed to run dora-coordinator");
+        }
+
         let (port, task) =
             dora_coordinator::start(bind, bind_control, futures::stream::empty::<Event>()).await?;
         if !self.quiet {
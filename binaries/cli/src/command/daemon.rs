@@ -1,15 +1,18 @@
-use super::Executable;
-use crate::{common::handle_dataflow_result, session::DataflowSession};
+use super::{Executable, run::NodeCommunicationKind};
+use crate::{
+    common::{CoordinatorAddrs, handle_dataflow_result},
+    session::DataflowSession,
+};
 use dora_core::topics::{
     DORA_COORDINATOR_PORT_DEFAULT, DORA_DAEMON_LOCAL_LISTEN_PORT_DEFAULT, LOCALHOST,
 };
 
 use dora_daemon::LogDestination;
-use eyre::Context;
-use std::{
-    net::{IpAddr, SocketAddr},
-    path::PathBuf,
+use dora_message::{
+    config::parse_memory_size, daemon_to_coordinator::DaemonPlacement, descriptor::Capacity,
 };
+use eyre::Context;
+use std::path::PathBuf;
 use tracing::level_filters::LevelFilter;
 
 #[derive(Debug, clap::Args)]
@@ -21,9 +24,10 @@ pub struct Daemon {
     /// Local listen port for event such as dynamic node.
     #[clap(long, default_value_t = DORA_DAEMON_LOCAL_LISTEN_PORT_DEFAULT)]
     local_listen_port: u16,
-    /// Address and port number of the dora coordinator
-    #[clap(long, short, default_value_t = LOCALHOST)]
-    coordinator_addr: IpAddr,
+    /// Address of the dora coordinator, or comma-separated addresses of a
+    /// high-availability coordinator cluster
+    #[clap(long, short, value_name = "IP[:PORT],...", default_value_t = LOCALHOST.into())]
+    coordinator_addr: CoordinatorAddrs,
     /// Port number of the coordinator control server
     #[clap(long, default_value_t = DORA_COORDINATOR_PORT_DEFAULT)]
     coordinator_port: u16,
@@ -32,6 +36,31 @@ pub struct Daemon {
     /// Suppresses all log output to stdout.
     #[clap(long)]
     quiet: bool,
+    /// Label for label-based node placement, e.g. `arch=arm64` (can be given multiple times)
+    #[clap(long = "label", value_name = "KEY=VALUE", value_parser = parse_label)]
+    labels: Vec<(String, String)>,
+    /// Number of CPU cores that nodes can allocate on this machine [default: all cores]
+    #[clap(long)]
+    cpus: Option<f64>,
+    /// Memory that nodes can allocate on this machine, e.g. `16G` [default: all memory]
+    #[clap(long, value_parser = parse_memory_size)]
+    memory: Option<u64>,
+    /// Transport that nodes use to connect to this daemon
+    ///
+    /// Unix domain sockets have a lower latency than TCP, and shared memory
+    /// (Linux only) is the fastest. Nodes fall back to TCP on platforms that
+    /// don't support the selected transport and if they run in a `sandbox`.
+    #[clap(long, value_enum, default_value_t = NodeCommunicationKind::Tcp)]
+    node_communication: NodeCommunicationKind,
+}
+
+fn parse_label(label: &str) -> Result<(String, String), String> {
+    match label.split_once('=') {
+        Some((key, value)) if !key.trim().is_empty() => {
+            Ok((key.trim().to_owned(), value.trim().to_owned()))
+        }
+        _ => Err(format!("label must have the form `KEY=VALUE` (got `{label}`)")),
+    }
 }
 
 impl Executable for Daemon {
@@ -64,7 +93,7 @@ impl Executable for Daemon {
             match self.run_dataflow {
                 Some(dataflow_path) => {
                     tracing::info!("Starting dataflow `{}`", dataflow_path.display());
-                    if self.coordinator_addr != LOCALHOST {
+                    if self.coordinator_addr != CoordinatorAddrs::from(LOCALHOST) {
                         tracing::info!(
                             "Not using coordinator addr {} as `run_dataflow` is for local dataflow only. Please use the `start` command for remote coordinator",
                             self.coordinator_addr
@@ -75,12 +104,23 @@ impl Executable for Daemon {
 
                     let result = dora_daemon::Daemon::run_dataflow(&dataflow_path,
                         dataflow_session.build_id, dataflow_session.local_build, dataflow_session.session_id, false,
-                        LogDestination::Tracing, None, None, false,
+                        LogDestination::Tracing, None, None, false, false, Default::default(),
+                        self.node_communication.into(),
                     ).await?;
                     handle_dataflow_result(result, None)
                 }
                 None => {
-                    dora_daemon::Daemon::run(SocketAddr::new(self.coordinator_addr, self.coordinator_port), self.machine_id, self.local_listen_port).await
+                    if self.cpus.is_some_and(|cpus| !(cpus.is_finite() && cpus > 0.0)) {
+                        eyre::bail!("`--cpus` must be a positive number of CPU cores");
+                    }
+                    let placement = DaemonPlacement {
+                        labels: self.labels.into_iter().collect(),
+                        capacity: Capacity {
+                            cpus: self.cpus,
+                            memory: self.memory,
+                        },
+                    };
+                    dora_daemon::Daemon::run(self.coordinator_addr.socket_addrs(self.coordinator_port), self.machine_id, self.local_listen_port, placement, self.node_communication.into()).await
                 }
             }
         }
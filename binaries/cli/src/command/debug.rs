@@ -0,0 +1,266 @@
+use std::io::Write;
+
+use clap::Args;
+use dora_core::config::InputMapping;
+use dora_message::{
+    cli_to_coordinator::CoordinatorControlClient,
+    debug::{Breakpoint, DebugCommand, DebugStatus},
+    id::NodeId,
+    metadata::{MetadataParameters, Parameter},
+    tarpc,
+};
+use eyre::bail;
+use tabwriter::TabWriter;
+
+use crate::{
+    command::{Executable, default_tracing},
+    common::{CoordinatorOptions, resolve_dataflow_identifier_interactive, rpc},
+    formatting::OutputFormat,
+};
+
+/// Pause, single-step, and set breakpoints on message delivery.
+///
+/// While a node is paused, the daemon holds back all input messages for it.
+/// The held back messages can be inspected with `dora debug queue` and
+/// delivered one by one with `dora debug step`. At most `queue_size` messages
+/// (10 by default) are held back per input; older messages are dropped.
+///
+/// Examples:
+///
+/// Pause the whole dataflow and deliver the next message:
+///   dora debug pause
+///   dora debug step
+///
+/// Pause when the detector finds a person:
+///   dora debug break detector/boxes --if "parameters.label == 'person'"
+#[derive(Debug, clap::Subcommand)]
+#[clap(verbatim_doc_comment)]
+pub enum Debug {
+    Pause(Pause),
+    Resume(Resume),
+    Step(Step),
+    Break(Break),
+    Queue(Queue),
+}
+
+impl Executable for Debug {
+    async fn execute(self) -> eyre::Result<()> {
+        default_tracing()?;
+
+        match self {
+            Debug::Pause(cmd) => {
+                let command = DebugCommand::Pause { node: cmd.node };
+                run(cmd.target, command).await
+            }
+            Debug::Resume(cmd) => {
+                let command = DebugCommand::Resume { node: cmd.node };
+                run(cmd.target, command).await
+            }
+            Debug::Step(cmd) => {
+                let command = DebugCommand::Step {
+                    node: cmd.node,
+                    count: cmd.count,
+                };
+                run(cmd.target, command).await
+            }
+            Debug::Break(cmd) => {
+                let command = match (cmd.clear, cmd.topic) {
+                    (true, _) => DebugCommand::ClearBreakpoints,
+                    (false, Some(topic)) => {
+                        DebugCommand::SetBreakpoint(breakpoint(&topic, cmd.condition)?)
+                    }
+                    (false, None) => bail!("specify a topic such as `node/output` or `--clear`"),
+                };
+                run(cmd.target, command).await
+            }
+            Debug::Queue(cmd) => {
+                let status = send(&cmd.target, DebugCommand::Status).await?;
+                match cmd.format {
+                    OutputFormat::Table => print_queue(&status),
+                    OutputFormat::Json => {
+                        println!("{}", serde_json::to_string_pretty(&status)?);
+                        Ok(())
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct Target {
+    /// Name or UUID of the dataflow
+    #[clap(long, short = 'd', value_name = "NAME_OR_UUID")]
+    dataflow: Option<String>,
+
+    #[clap(flatten)]
+    coordinator: CoordinatorOptions,
+}
+
+/// Hold back all messages to a node, or to all nodes of the dataflow.
+#[derive(Debug, Args)]
+pub struct Pause {
+    /// Only pause message delivery to this node
+    #[clap(long, value_name = "NODE")]
+    node: Option<NodeId>,
+    #[clap(flatten)]
+    target: Target,
+}
+
+/// Deliver all held back messages and continue normally.
+#[derive(Debug, Args)]
+pub struct Resume {
+    /// Only resume message delivery to this node
+    #[clap(long, value_name = "NODE")]
+    node: Option<NodeId>,
+    #[clap(flatten)]
+    target: Target,
+}
+
+/// Deliver the next held back messages and stay paused.
+#[derive(Debug, Args)]
+pub struct Step {
+    /// Number of messages to deliver
+    #[clap(default_value_t = 1)]
+    count: usize,
+    /// Only deliver messages to this node
+    #[clap(long, value_name = "NODE")]
+    node: Option<NodeId>,
+    #[clap(flatten)]
+    target: Target,
+}
+
+/// Pause the dataflow when a message is sent on a topic.
+#[derive(Debug, Args)]
+pub struct Break {
+    /// Topic to break on, e.g. `node_id/output_id`
+    #[clap(value_name = "TOPIC", required_unless_present = "clear")]
+    topic: Option<String>,
+    /// Only break on messages whose metadata matches the condition, e.g. `parameters.score > 0.5`
+    #[clap(long = "if", value_name = "CONDITION")]
+    condition: Option<String>,
+    /// Remove all breakpoints
+    #[clap(long, conflicts_with_all = ["topic", "condition"])]
+    clear: bool,
+    #[clap(flatten)]
+    target: Target,
+}
+
+/// List the held back messages.
+#[derive(Debug, Args)]
+pub struct Queue {
+    /// Output format
+    #[clap(long, value_name = "FORMAT", default_value_t = OutputFormat::Table)]
+    format: OutputFormat,
+    #[clap(flatten)]
+    target: Target,
+}
+
+fn breakpoint(topic: &str, condition: Option<String>) -> eyre::Result<Breakpoint> {
+    let (node, output) = match topic.parse() {
+        Ok(InputMapping::User(user)) if !user.output.is_empty() => (user.source, user.output),
+        _ => bail!("invalid topic `{topic}`, expected `node_id/output_id`"),
+    };
+    let condition = condition
+        .map(|c| c.parse())
+        .transpose()
+        .map_err(|err: String| eyre::eyre!(err))?;
+    Ok(Breakpoint {
+        node,
+        output,
+        condition,
+    })
+}
+
+async fn send(target: &Target, command: DebugCommand) -> eyre::Result<DebugStatus> {
+    let client: CoordinatorControlClient = target.coordinator.connect_rpc().await?;
+    let dataflow_id =
+        resolve_dataflow_identifier_interactive(&client, target.dataflow.as_deref()).await?;
+    rpc(
+        "debug dataflow",
+        client.debug(tarpc::context::current(), dataflow_id, command),
+    )
+    .await
+}
+
+async fn run(target: Target, command: DebugCommand) -> eyre::Result<()> {
+    let status = send(&target, command).await?;
+    if status.released > 0 {
+        println!("Delivered {} message(s)", status.released);
+    }
+    print_summary(&status);
+    Ok(())
+}
+
+fn print_summary(status: &DebugStatus) {
+    if status.dataflow_paused {
+        println!("Dataflow is paused");
+    } else if !status.paused_nodes.is_empty() {
+        let nodes: Vec<_> = status.paused_nodes.iter().map(|n| n.to_string()).collect();
+        println!("Paused nodes: {}", nodes.join(", "));
+    } else {
+        println!("Dataflow is running");
+    }
+    for breakpoint in &status.breakpoints {
+        match &breakpoint.condition {
+            Some(condition) => println!(
+                "Breakpoint: {}/{} if {condition}",
+                breakpoint.node, breakpoint.output
+            ),
+            None => println!("Breakpoint: {}/{}", breakpoint.node, breakpoint.output),
+        }
+    }
+    println!("{} message(s) held back", status.queued.len());
+}
+
+fn print_queue(status: &DebugStatus) -> eyre::Result<()> {
+    print_summary(status);
+    if status.queued.is_empty() {
+        return Ok(());
+    }
+    println!();
+    let mut tw = TabWriter::new(std::io::stdout().lock());
+    writeln!(tw, "#\tRECEIVER\tSOURCE\tTIME\tSIZE\tPARAMETERS")?;
+    for (i, message) in status.queued.iter().enumerate() {
+        let time: chrono::DateTime<chrono::Utc> =
+            message.timestamp.get_time().to_system_time().into();
+        writeln!(
+            tw,
+            "{}{}\t{}/{}\t{}/{}\t{}\t{}\t{}",
+            i + 1,
+            if message.breakpoint { "*" } else { "" },
+            message.receiver,
+            message.input,
+            message.source_node,
+            message.source_output,
+            time.format("%H:%M:%S%.3f"),
+            message
+                .len
+                .map(|len| format!("{len} B"))
+                .unwrap_or_else(|| "-".into()),
+            format_parameters(&message.parameters),
+        )?;
+    }
+    tw.flush()?;
+    Ok(())
+}
+
+fn format_parameters(parameters: &MetadataParameters) -> String {
+    parameters
+        .iter()
+        .map(|(key, value)| {
+            let value = match value {
+                Parameter::Bool(v) => v.to_string(),
+                Parameter::Integer(v) => v.to_string(),
+                Parameter::Float(v) => v.to_string(),
+                Parameter::String(v) => format!("{v:?}"),
+                Parameter::ListInt(v) => format!("{v:?}"),
+                Parameter::ListFloat(v) => format!("{v:?}"),
+                Parameter::ListString(v) => format!("{v:?}"),
+                Parameter::Timestamp(v) => v.to_rfc3339(),
+            };
+            format!("{key}={value}")
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
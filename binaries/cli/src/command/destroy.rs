@@ -1,6 +1,6 @@
 use super::{Executable, default_tracing, up};
+use crate::common::CoordinatorAddrs;
 use dora_core::topics::{DORA_COORDINATOR_PORT_CONTROL_DEFAULT, LOCALHOST};
-use std::net::IpAddr;
 use std::path::PathBuf;
 
 #[derive(Debug, clap::Args)]
@@ -9,9 +9,10 @@ pub struct Destroy {
     /// Use a custom configuration
     #[clap(long, hide = true)]
     config: Option<PathBuf>,
-    /// Address of the dora coordinator
-    #[clap(long, value_name = "IP", default_value_t = LOCALHOST)]
-    coordinator_addr: IpAddr,
+    /// Address of the dora coordinator, or comma-separated addresses of a
+    /// high-availability coordinator cluster
+    #[clap(long, value_name = "IP[:PORT],...", default_value_t = LOCALHOST.into())]
+    coordinator_addr: CoordinatorAddrs,
     /// Port number of the coordinator control server
     #[clap(long, value_name = "PORT", default_value_t = DORA_COORDINATOR_PORT_CONTROL_DEFAULT)]
     coordinator_port: u16,
@@ -22,7 +23,8 @@ impl Executable for Destroy {
         default_tracing()?;
         up::destroy(
             self.config.as_deref(),
-            (self.coordinator_addr, self.coordinator_port).into(),
+            &self.coordinator_addr,
+            self.coordinator_port,
         )
         .await
     }
@@ -15,7 +15,7 @@ use dora_core::topics::DORA_COORDINATOR_PORT_CONTROL_DEFAULT;
 use dora_message::{coordinator_to_cli::NodeInfo, id::NodeId, tarpc};
 use eyre::Context;
 
-use crate::common::{connect_and_check_version, rpc};
+use crate::common::{CoordinatorAddrs, connect_and_check_version, rpc};
 use ratatui::{
     Frame, Terminal,
     backend::{Backend, CrosstermBackend},
@@ -40,9 +40,10 @@ use super::super::{Executable, default_tracing};
 /// - Nodes can run on different machines with potentially different CPUs, so percentages are not comparable across machines
 #[derive(Debug, Args)]
 pub struct Top {
-    /// Address of the dora coordinator
-    #[clap(long, value_name = "IP", default_value_t = LOCALHOST)]
-    pub coordinator_addr: std::net::IpAddr,
+    /// Address of the dora coordinator, or comma-separated addresses of a
+    /// high-availability coordinator cluster
+    #[clap(long, value_name = "IP[:PORT],...", default_value_t = LOCALHOST.into())]
+    pub coordinator_addr: CoordinatorAddrs,
     /// Port number of the coordinator control server
     #[clap(long, value_name = "PORT", default_value_t = DORA_COORDINATOR_PORT_CONTROL_DEFAULT)]
     pub coordinator_port: u16,
@@ -66,7 +67,7 @@ impl Executable for Top {
         let refresh_duration = Duration::from_secs(self.refresh_interval);
         let res = run_app(
             &mut terminal,
-            self.coordinator_addr,
+            &self.coordinator_addr,
             self.coordinator_port,
             refresh_duration,
         )
@@ -251,7 +252,7 @@ impl App {
 
 async fn run_app<B: Backend>(
     terminal: &mut Terminal<B>,
-    coordinator_addr: std::net::IpAddr,
+    coordinator_addr: &CoordinatorAddrs,
     coordinator_port: u16,
     refresh_duration: Duration,
 ) -> eyre::Result<()> {
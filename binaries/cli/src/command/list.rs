@@ -3,7 +3,7 @@ use std::io::Write;
 use super::{Executable, default_tracing};
 use crate::{
     LOCALHOST,
-    common::{connect_and_check_version, query_running_dataflows, rpc},
+    common::{CoordinatorAddrs, connect_and_check_version, query_running_dataflows, rpc},
     formatting::OutputFormat,
 };
 use clap::Args;
@@ -19,9 +19,10 @@ use uuid::Uuid;
 #[derive(Debug, Args)]
 /// List running dataflows.
 pub struct ListArgs {
-    /// Address of the dora coordinator
-    #[clap(long, value_name = "IP", default_value_t = LOCALHOST)]
-    pub coordinator_addr: std::net::IpAddr,
+    /// Address of the dora coordinator, or comma-separated addresses of a
+    /// high-availability coordinator cluster
+    #[clap(long, value_name = "IP[:PORT],...", default_value_t = LOCALHOST.into())]
+    pub coordinator_addr: CoordinatorAddrs,
     /// Port number of the coordinator control server
     #[clap(long, value_name = "PORT", default_value_t = DORA_COORDINATOR_PORT_CONTROL_DEFAULT)]
     pub coordinator_port: u16,
@@ -43,7 +44,7 @@ impl Executable for ListArgs {
     async fn execute(self) -> eyre::Result<()> {
         default_tracing()?;
 
-        let client = connect_and_check_version(self.coordinator_addr, self.coordinator_port)
+        let client = connect_and_check_version(&self.coordinator_addr, self.coordinator_port)
             .await
             .wrap_err("failed to connect to dora coordinator")?;
 
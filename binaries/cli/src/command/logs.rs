@@ -3,7 +3,8 @@ use std::io::Write;
 use super::{Executable, default_tracing};
 use crate::{
     common::{
-        connect_and_check_version, long_context, resolve_dataflow_identifier_interactive, rpc,
+        CoordinatorAddrs, connect_and_check_version, long_context,
+        resolve_dataflow_identifier_interactive, rpc,
     },
     output::subscribe_to_logs,
 };
@@ -28,9 +29,10 @@ pub struct LogsArgs {
     /// Follow log output
     #[clap(long, short)]
     pub follow: bool,
-    /// Address of the dora coordinator
-    #[clap(long, value_name = "IP", default_value_t = LOCALHOST)]
-    pub coordinator_addr: std::net::IpAddr,
+    /// Address of the dora coordinator, or comma-separated addresses of a
+    /// high-availability coordinator cluster
+    #[clap(long, value_name = "IP[:PORT],...", default_value_t = LOCALHOST.into())]
+    pub coordinator_addr: CoordinatorAddrs,
     /// Port number of the coordinator control server
     #[clap(long, value_name = "PORT", default_value_t = DORA_COORDINATOR_PORT_CONTROL_DEFAULT)]
     pub coordinator_port: u16,
@@ -40,7 +42,7 @@ impl Executable for LogsArgs {
     async fn execute(self) -> eyre::Result<()> {
         default_tracing()?;
 
-        let client = connect_and_check_version(self.coordinator_addr, self.coordinator_port)
+        let client = connect_and_check_version(&self.coordinator_addr, self.coordinator_port)
             .await
             .wrap_err("failed to connect to dora coordinator")?;
         let uuid =
@@ -51,7 +53,7 @@ impl Executable for LogsArgs {
             self.node,
             self.tail,
             self.follow,
-            self.coordinator_addr,
+            self.coordinator_addr.ip(),
         )
         .await
     }
@@ -0,0 +1,617 @@
+use super::Executable;
+use colored::Colorize;
+use dora_core::descriptor::{
+    CoreNodeKind, Descriptor, DescriptorExt, SINGLE_OPERATOR_DEFAULT_ID, source_is_url,
+};
+use eyre::{Context, OptionExt, bail};
+use serde_yaml::{Mapping, Value};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::PathBuf,
+};
+
+#[derive(Debug, clap::Args)]
+/// Rewrite a dataflow descriptor that uses deprecated syntax into the current form.
+///
+/// The migrated descriptor is written back to the given file and a diff of the changes is
+/// printed. Nodes that don't need a migration are kept as-is, including their comments.
+pub struct Migrate {
+    /// Path to the dataflow descriptor file
+    #[clap(value_name = "PATH", value_hint = clap::ValueHint::FilePath)]
+    dataflow: PathBuf,
+    /// Only print the diff and exit with an error if the descriptor needs a migration
+    ///
+    /// Useful for enforcing up-to-date descriptors in CI.
+    #[clap(long, action)]
+    check: bool,
+}
+
+impl Executable for Migrate {
+    async fn execute(self) -> eyre::Result<()> {
+        let original = std::fs::read_to_string(&self.dataflow)
+            .with_context(|| format!("failed to read dataflow at `{}`", self.dataflow.display()))?;
+        let migration = migrate(&original)
+            .with_context(|| format!("failed to migrate `{}`", self.dataflow.display()))?;
+
+        for warning in &migration.warnings {
+            eprintln!("{}: {warning}", "warning".yellow().bold());
+        }
+        if migration.changes.is_empty() {
+            println!("`{}` is up to date", self.dataflow.display());
+            return Ok(());
+        }
+
+        for change in &migration.changes {
+            println!("- {change}");
+        }
+        println!();
+        print_diff(
+            &self.dataflow.display().to_string(),
+            &original,
+            &migration.output,
+        );
+
+        if self.check {
+            bail!(
+                "`{}` uses deprecated syntax, run `dora migrate {}` to update it",
+                self.dataflow.display(),
+                self.dataflow.display()
+            );
+        }
+        std::fs::write(&self.dataflow, &migration.output).with_context(|| {
+            format!(
+                "failed to write migrated dataflow to `{}`",
+                self.dataflow.display()
+            )
+        })?;
+        println!("migrated `{}`", self.dataflow.display());
+        Ok(())
+    }
+}
+
+/// The result of migrating a dataflow descriptor.
+#[derive(Debug)]
+struct Migration {
+    /// The migrated descriptor. Equal to the input if no changes were needed.
+    output: String,
+    /// Human-readable descriptions of the applied changes.
+    changes: Vec<String>,
+    /// Deprecated syntax that can't be migrated automatically.
+    warnings: Vec<String>,
+}
+
+fn migrate(original: &str) -> eyre::Result<Migration> {
+    // legacy descriptors might not be accepted by the current descriptor model anymore
+    let resolved = resolve(original).ok();
+    let mut document: Value =
+        serde_yaml::from_str(original).context("failed to parse descriptor as YAML")?;
+
+    let mut changes = Vec::new();
+    let mut warnings = Vec::new();
+    let mut changed_nodes = BTreeSet::new();
+    // node ID -> operator ID of the former single-operator nodes
+    let mut single_operators = BTreeMap::new();
+    let nodes = document
+        .get_mut("nodes")
+        .and_then(Value::as_sequence_mut)
+        .ok_or_eyre("descriptor has no `nodes` list")?;
+    for (index, node) in nodes.iter_mut().enumerate() {
+        let node = node
+            .as_mapping_mut()
+            .ok_or_eyre("entries of the `nodes` list must be mappings")?;
+        let node_id = node
+            .get("id")
+            .and_then(Value::as_str)
+            .ok_or_eyre("node has no `id`")?
+            .to_owned();
+        let node_changes = migrate_node(node, &node_id)?;
+        if !node_changes.is_empty() {
+            changed_nodes.insert(index);
+            changes.extend(node_changes);
+        }
+        if let Some(operator_id) = migrate_single_operator(node, &node_id)? {
+            changed_nodes.insert(index);
+            changes.push(format!(
+                "node `{node_id}`: replaced `operator` by an `operators` list with operator \
+                `{operator_id}`"
+            ));
+            single_operators.insert(node_id.clone(), operator_id);
+        }
+
+        if let Some(path) = node.get("path").and_then(Value::as_str) {
+            if source_is_url(path) {
+                warnings.push(format!(
+                    "node `{node_id}` downloads its executable from a URL, which is not \
+                    recommended anymore; consider using the `git` and `build` fields instead"
+                ));
+            }
+        }
+    }
+
+    if !single_operators.is_empty() {
+        for (index, node) in nodes.iter_mut().enumerate() {
+            let node = node
+                .as_mapping_mut()
+                .ok_or_eyre("entries of the `nodes` list must be mappings")?;
+            if add_operator_to_inputs(node, &single_operators) {
+                changed_nodes.insert(index);
+            }
+        }
+    }
+
+    let output = if changed_nodes.is_empty() {
+        original.to_owned()
+    } else {
+        match splice_nodes(original, nodes, &changed_nodes) {
+            Some(output) => output,
+            None => {
+                warnings.push(
+                    "could not locate the node entries in the original file, so comments \
+                    are not preserved"
+                        .into(),
+                );
+                serde_yaml::to_string(&document).context("failed to serialize descriptor")?
+            }
+        }
+    };
+
+    // make sure that the result is accepted by the current descriptor model and that it
+    // describes the same dataflow
+    let migrated = resolve(&output).context("migrated descriptor is not valid")?;
+    if resolved.is_some_and(|resolved| resolved != migrated) {
+        bail!("migrated descriptor does not describe the same dataflow as the original");
+    }
+
+    Ok(Migration {
+        output,
+        changes,
+        warnings,
+    })
+}
+
+fn migrate_node(node: &mut Mapping, node_id: &str) -> eyre::Result<Vec<String>> {
+    let mut changes = Vec::new();
+
+    if let Some(custom) = node.get("custom") {
+        let custom = custom
+            .as_mapping()
+            .ok_or_else(|| eyre::eyre!("`custom` field of node `{node_id}` must be a mapping"))?;
+        let mut hoisted = hoist_custom_fields(custom, node_id)?;
+
+        // node-level env is applied before the deprecated `envs`, so `envs` takes precedence
+        let custom_envs = hoisted.shift_remove("env");
+        for (key, value) in node.iter() {
+            if hoisted.contains_key(key) && !is_empty(value) {
+                bail!(
+                    "node `{node_id}` sets `{}` both in its `custom` block and at the node level",
+                    key.as_str().unwrap_or_default()
+                );
+            }
+        }
+
+        let hoisted_keys: Vec<_> = hoisted.keys().cloned().collect();
+        let mut migrated = Mapping::new();
+        for (key, value) in std::mem::take(node) {
+            match key.as_str() {
+                Some("custom") => migrated.extend(std::mem::take(&mut hoisted)),
+                Some("env") => {
+                    let mut env = value;
+                    if let Some(Value::Mapping(custom_envs)) = custom_envs.clone() {
+                        let Value::Mapping(env) = &mut env else {
+                            bail!("`env` field of node `{node_id}` must be a mapping");
+                        };
+                        env.extend(custom_envs);
+                    }
+                    migrated.insert(key, env);
+                }
+                // node-level field is empty, as checked above
+                _ if hoisted_keys.contains(&key) => {}
+                _ => {
+                    migrated.insert(key, value);
+                }
+            }
+        }
+        if let Some(custom_envs) = custom_envs {
+            if !migrated.contains_key("env") {
+                migrated.insert("env".into(), custom_envs);
+            }
+        }
+        *node = migrated;
+
+        changes.push(format!(
+            "node `{node_id}`: moved fields of the deprecated `custom` block to the node level"
+        ));
+    }
+
+    Ok(changes)
+}
+
+/// Replaces a single-operator `operator` field by an `operators` list with one entry.
+///
+/// Returns the ID of the operator, which inputs from the node need to include now.
+fn migrate_single_operator(node: &mut Mapping, node_id: &str) -> eyre::Result<Option<String>> {
+    let Some(operator) = node.get("operator") else {
+        return Ok(None);
+    };
+    let mut operator = operator
+        .as_mapping()
+        .ok_or_else(|| eyre::eyre!("`operator` field of node `{node_id}` must be a mapping"))?
+        .clone();
+    let operator_id = match operator.shift_remove("id") {
+        Some(Value::String(id)) => id,
+        None | Some(Value::Null) => SINGLE_OPERATOR_DEFAULT_ID.to_owned(),
+        Some(_) => bail!("operator `id` of node `{node_id}` must be a string"),
+    };
+    let mut entry = Mapping::new();
+    entry.insert("id".into(), operator_id.clone().into());
+    entry.extend(operator);
+
+    let mut migrated = Mapping::new();
+    for (key, value) in std::mem::take(node) {
+        if key.as_str() == Some("operator") {
+            migrated.insert(
+                "operators".into(),
+                Value::Sequence(vec![Value::Mapping(entry.clone())]),
+            );
+        } else {
+            migrated.insert(key, value);
+        }
+    }
+    *node = migrated;
+    Ok(Some(operator_id))
+}
+
+/// Adds the operator ID to inputs that refer to outputs of former single-operator nodes.
+///
+/// Returns `true` if an input was changed.
+fn add_operator_to_inputs(node: &mut Mapping, single_operators: &BTreeMap<String, String>) -> bool {
+    let mut inputs = Vec::new();
+    for (key, value) in node.iter_mut() {
+        match key.as_str() {
+            Some("inputs") => inputs.extend(value.as_mapping_mut()),
+            Some("operators") => {
+                for operator in value.as_sequence_mut().into_iter().flatten() {
+                    inputs.extend(operator.get_mut("inputs").and_then(Value::as_mapping_mut));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut changed = false;
+    for (_, input) in inputs.into_iter().flat_map(|inputs| inputs.iter_mut()) {
+        let source = match input {
+            Value::Mapping(input) => input.get_mut("source"),
+            other => Some(other),
+        };
+        let Some(Value::String(source)) = source else {
+            continue;
+        };
+        let Some((source_node, output)) = source.split_once('/') else {
+            continue;
+        };
+        if let Some(operator_id) = single_operators.get(source_node) {
+            *source = format!("{source_node}/{operator_id}/{output}");
+            changed = true;
+        }
+    }
+    changed
+}
+
+/// Parses the descriptor with the full descriptor model and resolves it into its nodes.
+///
+/// The deprecated `envs` of custom nodes are merged into the node-level `env`, like the
+/// migration does, so that the results of the original and the migrated descriptor are equal.
+fn resolve(descriptor: &str) -> eyre::Result<Value> {
+    let descriptor = Descriptor::parse(descriptor.as_bytes().to_vec())?;
+    let mut nodes = descriptor.resolve_aliases_and_set_defaults()?;
+    for node in nodes.values_mut() {
+        if let CoreNodeKind::Custom(custom) = &mut node.kind {
+            #[allow(deprecated)]
+            if let Some(envs) = custom.envs.take() {
+                node.env.get_or_insert_default().extend(envs);
+            }
+        }
+    }
+    serde_yaml::to_value(nodes).context("failed to serialize resolved nodes")
+}
+
+/// Translates the fields of a deprecated `custom` block to their node-level equivalent.
+fn hoist_custom_fields(custom: &Mapping, node_id: &str) -> eyre::Result<Mapping> {
+    let mut hoisted = Mapping::new();
+    // older versions used `source` for the executable path
+    let legacy_source = !custom.contains_key("path");
+    for (key, value) in custom {
+        if value.is_null() {
+            continue;
+        }
+        let key = key
+            .as_str()
+            .ok_or_else(|| eyre::eyre!("invalid key in `custom` block of node `{node_id}`"))?;
+        match key {
+            "source" if legacy_source => {
+                hoisted.insert("path".into(), value.clone());
+            }
+            "source" => match value {
+                Value::String(s) if s == "Local" => {}
+                Value::Mapping(m) => {
+                    let git = m
+                        .get("GitBranch")
+                        .and_then(Value::as_mapping)
+                        .ok_or_else(|| {
+                            eyre::eyre!(
+                                "unsupported `source` in `custom` block of node `{node_id}`"
+                            )
+                        })?;
+                    let repo = git.get("repo").ok_or_else(|| {
+                        eyre::eyre!("git source of node `{node_id}` has no `repo`")
+                    })?;
+                    hoisted.insert("git".into(), repo.clone());
+                    if let Some(rev) = git.get("rev").and_then(Value::as_mapping) {
+                        for (kind, value) in rev {
+                            let key = match kind.as_str() {
+                                Some("Branch") => "branch",
+                                Some("Tag") => "tag",
+                                Some("Rev") => "rev",
+                                _ => bail!("unsupported git revision for node `{node_id}`"),
+                            };
+                            hoisted.insert(key.into(), value.clone());
+                        }
+                    }
+                }
+                _ => bail!("unsupported `source` in `custom` block of node `{node_id}`"),
+            },
+            "envs" => {
+                hoisted.insert("env".into(), value.clone());
+            }
+            "path" | "args" | "build" | "send_stdout_as" | "restart_policy" | "inputs"
+            | "outputs" => {
+                hoisted.insert(key.into(), value.clone());
+            }
+            other => bail!("unknown field `{other}` in `custom` block of node `{node_id}`"),
+        }
+    }
+    Ok(hoisted)
+}
+
+fn is_empty(value: &Value) -> bool {
+    match value {
+        Value::Null => true,
+        Value::Sequence(s) => s.is_empty(),
+        Value::Mapping(m) => m.is_empty(),
+        _ => false,
+    }
+}
+
+/// Replaces the text of the changed node entries in the original file.
+///
+/// Everything else, including comments and formatting, is kept as-is. Returns `None` if the
+/// node entries can't be located in the text (e.g. when the file uses flow style).
+fn splice_nodes(original: &str, nodes: &[Value], changed: &BTreeSet<usize>) -> Option<String> {
+    let lines: Vec<&str> = original.split_inclusive('\n').collect();
+    let nodes_line = lines.iter().position(|line| {
+        line.strip_prefix("nodes:")
+            .is_some_and(|rest| rest.trim().is_empty() || rest.trim().starts_with('#'))
+    })?;
+
+    // (first line, last non-comment line) of each entry
+    let mut blocks: Vec<(usize, usize)> = Vec::new();
+    let mut item_indent = None;
+    for (i, line) in lines.iter().enumerate().skip(nodes_line + 1) {
+        let trimmed = line.trim_start();
+        if trimmed.trim_end().is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        let indent = line.len() - trimmed.len();
+        let item_indent = *item_indent.get_or_insert(indent);
+        if indent == item_indent && (trimmed.starts_with("- ") || trimmed.trim_end() == "-") {
+            blocks.push((i, i));
+        } else if indent > item_indent {
+            blocks.last_mut()?.1 = i;
+        } else {
+            break;
+        }
+    }
+    if blocks.len() != nodes.len() {
+        return None;
+    }
+
+    let indent = " ".repeat(item_indent?);
+    let mut output = String::with_capacity(original.len());
+    let mut position = 0;
+    for (index, (first, last)) in blocks.into_iter().enumerate() {
+        if !changed.contains(&index) {
+            continue;
+        }
+        output.extend(lines[position..first].iter().copied());
+        let serialized = serde_yaml::to_string(&[&nodes[index]]).ok()?;
+        for line in serialized.lines() {
+            output.push_str(&indent);
+            output.push_str(line);
+            output.push('\n');
+        }
+        position = last + 1;
+    }
+    output.extend(lines[position..].iter().copied());
+    Some(output)
+}
+
+fn print_diff(name: &str, old: &str, new: &str) {
+    let diff = similar::TextDiff::from_lines(old, new);
+    let diff = diff
+        .unified_diff()
+        .header(&format!("a/{name}"), &format!("b/{name}"))
+        .to_string();
+    for line in diff.lines() {
+        if line.starts_with("+++") || line.starts_with("---") {
+            println!("{}", line.bold());
+        } else if line.starts_with('+') {
+            println!("{}", line.green());
+        } else if line.starts_with('-') {
+            println!("{}", line.red());
+        } else if line.starts_with("@@") {
+            println!("{}", line.cyan());
+        } else {
+            println!("{line}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn up_to_date_descriptor_is_unchanged() {
+        let original = "nodes:\n  # the camera\n  - id: camera\n    path: camera.py\n    outputs:\n      - image\n";
+        let migration = migrate(original).unwrap();
+        assert!(migration.changes.is_empty());
+        assert_eq!(migration.output, original);
+    }
+
+    #[test]
+    fn legacy_custom_block_is_hoisted() {
+        let original = "\
+nodes:
+  # the camera
+  - id: camera
+    path: camera.py
+    outputs:
+      - image
+
+  ## keyboard input
+  - id: keyboard
+    env:
+      A: node
+      B: node
+    custom:
+      source: keyboard_op.py
+      envs:
+        B: custom
+      inputs:
+        image: camera/image
+      outputs:
+        - buffer
+";
+        let migration = migrate(original).unwrap();
+        assert_eq!(migration.changes.len(), 1);
+        assert_eq!(
+            migration.output,
+            "\
+nodes:
+  # the camera
+  - id: camera
+    path: camera.py
+    outputs:
+      - image
+
+  ## keyboard input
+  - id: keyboard
+    env:
+      A: node
+      B: custom
+    path: keyboard_op.py
+    inputs:
+      image: camera/image
+    outputs:
+    - buffer
+"
+        );
+    }
+
+    #[test]
+    fn custom_git_source_is_hoisted() {
+        let original = "\
+nodes:
+  - id: node
+    custom:
+      path: target/debug/node
+      source:
+        GitBranch:
+          repo: https://github.com/dora-rs/dora.git
+          rev:
+            Tag: v0.3.0
+";
+        let migration = migrate(original).unwrap();
+        let node = &serde_yaml::from_str::<Value>(&migration.output).unwrap()["nodes"][0];
+        assert_eq!(node["path"].as_str(), Some("target/debug/node"));
+        assert_eq!(
+            node["git"].as_str(),
+            Some("https://github.com/dora-rs/dora.git")
+        );
+        assert_eq!(node["tag"].as_str(), Some("v0.3.0"));
+        assert!(node.get("custom").is_none());
+    }
+
+    #[test]
+    fn single_operator_is_converted_to_list() {
+        let original = "\
+nodes:
+  - id: camera
+    operator:
+      python: camera.py
+      outputs:
+        - image
+  - id: plot
+    operator:
+      id: plotter
+      python: plot.py
+      inputs:
+        image: camera/image
+        tick: dora/timer/millis/100
+  - id: recorder
+    path: recorder.py
+    inputs:
+      image:
+        source: camera/image
+        queue_size: 1
+";
+        let migration = migrate(original).unwrap();
+        assert_eq!(migration.changes.len(), 2);
+        let nodes = &serde_yaml::from_str::<Value>(&migration.output).unwrap()["nodes"];
+
+        let camera = &nodes[0]["operators"][0];
+        assert_eq!(camera["id"].as_str(), Some("op"));
+        assert_eq!(camera["python"].as_str(), Some("camera.py"));
+        let plot = &nodes[1]["operators"][0];
+        assert_eq!(plot["id"].as_str(), Some("plotter"));
+        assert_eq!(plot["inputs"]["image"].as_str(), Some("camera/op/image"));
+        assert_eq!(
+            plot["inputs"]["tick"].as_str(),
+            Some("dora/timer/millis/100")
+        );
+        assert_eq!(
+            nodes[2]["inputs"]["image"]["source"].as_str(),
+            Some("camera/op/image")
+        );
+        assert!(nodes[0].get("operator").is_none());
+    }
+
+    #[test]
+    fn operators_list_is_unchanged() {
+        let original = "\
+nodes:
+  - id: runtime
+    operators:
+      - id: camera
+        python: camera.py
+        outputs:
+          - image
+      - id: plot
+        python: plot.py
+        inputs:
+          image: runtime/camera/image
+";
+        let migration = migrate(original).unwrap();
+        assert!(migration.changes.is_empty());
+        assert_eq!(migration.output, original);
+    }
+
+    #[test]
+    fn url_path_is_reported() {
+        let original = "nodes:\n  - id: node\n    path: https://example.com/node\n";
+        let migration = migrate(original).unwrap();
+        assert!(migration.changes.is_empty());
+        assert_eq!(migration.warnings.len(), 1);
+    }
+}
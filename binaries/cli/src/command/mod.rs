@@ -1,12 +1,15 @@
+mod apply;
 mod build;
 mod completion;
 mod coordinator;
 mod daemon;
+mod debug;
 mod destroy;
 mod graph;
 mod inspect;
 mod list;
 mod logs;
+mod migrate;
 mod new;
 mod node;
 mod run;
@@ -20,18 +23,21 @@ mod up;
 mod version;
 
 pub use build::{build, build_async};
-pub use run::{Run, run, run_func};
+pub use run::{NodeCommunicationKind, Run, run, run_func};
 
+use apply::Apply;
 use build::Build;
 use completion::Completion;
 use coordinator::Coordinator;
 use daemon::Daemon;
+use debug::Debug;
 use destroy::Destroy;
 use eyre::Context;
 use graph::Graph;
 use inspect::Inspect;
 use list::ListArgs;
 use logs::LogsArgs;
+use migrate::Migrate;
 use new::NewArgs;
 use node::Node;
 use runtime::Runtime;
@@ -51,6 +57,7 @@ pub enum Command {
     /// Alias for `system status`
     Check(system::status::Status),
     Graph(Graph),
+    Migrate(Migrate),
     Build(Build),
     New(NewArgs),
     Run(Run),
@@ -58,6 +65,7 @@ pub enum Command {
     Destroy(Destroy),
     Start(Start),
     Stop(Stop),
+    Apply(Apply),
     #[clap(alias = "ps")]
     List(ListArgs),
     // Planned for future releases:
@@ -77,6 +85,8 @@ pub enum Command {
     Topic(Topic),
     #[clap(subcommand)]
     Node(Node),
+    #[clap(subcommand)]
+    Debug(Debug),
 
     Version(Version),
 
@@ -111,6 +121,7 @@ impl Executable for Command {
             Command::Check(args) => args.execute().await,
             Command::Coordinator(args) => args.execute().await,
             Command::Graph(args) => args.execute().await,
+            Command::Migrate(args) => args.execute().await,
             Command::Build(args) => args.execute().await,
             Command::New(args) => args.execute().await,
             Command::Run(args) => args.execute().await,
@@ -118,6 +129,7 @@ impl Executable for Command {
             Command::Destroy(args) => args.execute().await,
             Command::Start(args) => args.execute().await,
             Command::Stop(args) => args.execute().await,
+            Command::Apply(args) => args.execute().await,
             Command::List(args) => args.execute().await,
             Command::Logs(args) => args.execute().await,
             Command::Inspect(args) => args.execute().await,
@@ -126,6 +138,7 @@ impl Executable for Command {
             Command::Runtime(args) => args.execute().await,
             Command::Topic(args) => args.execute().await,
             Command::Node(args) => args.execute().await,
+            Command::Debug(args) => args.execute().await,
             Command::Version(args) => args.execute().await,
             Command::Completion(args) => args.execute().await,
         }
@@ -53,6 +53,8 @@ impl Executable for List {
 #[derive(Serialize)]
 struct OutputEntry {
     node: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    replica_of: Option<String>,
     status: String,
     pid: String,
     cpu: String,
@@ -98,12 +100,16 @@ async fn list(
         .into_iter()
         .map(|node| {
             let (status, pid, cpu, memory) = if let Some(metrics) = node.metrics {
-                (
-                    "Running".to_string(),
-                    metrics.pid.to_string(),
-                    format!("{:.1}%", metrics.cpu_usage),
-                    format!("{:.0} MB", metrics.memory_mb),
-                )
+                // show the usage against the resource limits of the node, if any
+                let mut cpu = format!("{:.1}%", metrics.cpu_usage);
+                if let Some(limit) = metrics.cpu_limit {
+                    cpu += &format!(" / {limit:.0}%");
+                }
+                let mut memory = format!("{:.0} MB", metrics.memory_mb);
+                if let Some(limit) = metrics.memory_limit_mb {
+                    memory += &format!(" / {limit:.0} MB");
+                }
+                ("Running".to_string(), metrics.pid.to_string(), cpu, memory)
             } else {
                 // Node exists but no metrics available (might be starting or error state)
                 (
@@ -116,6 +122,7 @@ async fn list(
 
             OutputEntry {
                 node: node.node_id.to_string(),
+                replica_of: node.replica.map(|replica| replica.node_id.to_string()),
                 status,
                 pid,
                 cpu,
@@ -144,7 +151,10 @@ async fn list(
             }
 
             // Write entries
-            for entry in entries {
+            for mut entry in entries {
+                if let Some(replica_of) = &entry.replica_of {
+                    entry.node = format!("{} (replica of {replica_of})", entry.node);
+                }
                 if let Some(ref dataflow) = entry.dataflow {
                     tw.write_all(
                         format!(
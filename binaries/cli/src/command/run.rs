@@ -11,9 +11,9 @@ use crate::{
     output::print_log_message,
     session::DataflowSession,
 };
-use dora_daemon::{Daemon, LogDestination, flume};
+use dora_daemon::{Daemon, DataflowClock, LogDestination, NodeCommunication, flume};
 use duration_str::parse as parse_duration_str;
-use eyre::Context;
+use eyre::{Context, bail};
 use std::time::Duration;
 
 #[derive(Debug, clap::Args)]
@@ -43,6 +43,63 @@ pub struct Run {
     /// Enable hot-reload: watch node binaries and restart on changes.
     #[clap(long, action)]
     pub hot_reload: bool,
+    /// Rebuild and restart nodes when their sources change
+    ///
+    /// Watches the source tree of every node that has a `build` command. On
+    /// changes, the build command is rerun and the node is restarted if the
+    /// build succeeds. If the build fails, the old node keeps running.
+    #[clap(long, action)]
+    pub watch: bool,
+    /// Clock that drives timers and message timestamps
+    ///
+    /// With `sim`, the clock starts at zero and only advances once all nodes
+    /// with inputs are idle, jumping directly to the next timer tick or
+    /// `recv_timeout` deadline. This makes runs reproducible and lets them
+    /// run faster than real time. Nodes without inputs do not hold back the
+    /// clock, and nodes that never wait for events stall it.
+    #[clap(long, value_enum, default_value_t = ClockKind::Real)]
+    pub clock: ClockKind,
+    /// Drive the simulated clock from a node output, given as `NODE/OUTPUT`
+    ///
+    /// Each message on this output sets the clock to the value of its
+    /// `sim_time_ns` metadata parameter. Requires `--clock sim`.
+    #[clap(long, value_name = "NODE/OUTPUT")]
+    pub clock_source: Option<String>,
+    /// Transport that nodes use to connect to the daemon
+    ///
+    /// Unix domain sockets have a lower latency than TCP, and shared memory
+    /// (Linux only) is the fastest. Nodes fall back to TCP on platforms that
+    /// don't support the selected transport and if they run in a `sandbox`.
+    #[clap(long, value_enum, default_value_t = NodeCommunicationKind::Tcp)]
+    pub node_communication: NodeCommunicationKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ClockKind {
+    /// Use the system clock.
+    Real,
+    /// Use a simulated clock.
+    Sim,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum NodeCommunicationKind {
+    /// Connect through loopback TCP.
+    Tcp,
+    /// Connect through Unix domain sockets.
+    Unix,
+    /// Connect through shared memory queues.
+    Shmem,
+}
+
+impl From<NodeCommunicationKind> for NodeCommunication {
+    fn from(kind: NodeCommunicationKind) -> Self {
+        match kind {
+            NodeCommunicationKind::Tcp => NodeCommunication::Tcp,
+            NodeCommunicationKind::Unix => NodeCommunication::UnixDomain,
+            NodeCommunicationKind::Shmem => NodeCommunication::SharedMemory,
+        }
+    }
 }
 
 impl Run {
@@ -52,8 +109,31 @@ impl Run {
             uv: false,
             stop_after: None,
             hot_reload: false,
+            watch: false,
+            clock: ClockKind::Real,
+            clock_source: None,
+            node_communication: NodeCommunicationKind::Tcp,
         }
     }
+
+    fn dataflow_clock(&self) -> eyre::Result<DataflowClock> {
+        let source = match &self.clock_source {
+            Some(source) => {
+                if self.clock != ClockKind::Sim {
+                    bail!("`--clock-source` requires `--clock sim`");
+                }
+                let Some((node, output)) = source.split_once('/') else {
+                    bail!("invalid clock source `{source}`: expected `NODE/OUTPUT`");
+                };
+                Some((node.to_owned().into(), output.to_owned().into()))
+            }
+            None => None,
+        };
+        Ok(match self.clock {
+            ClockKind::Real => DataflowClock::Real,
+            ClockKind::Sim => DataflowClock::Simulated { source },
+        })
+    }
 }
 
 #[deprecated(note = "use `run` instead")]
@@ -85,6 +165,7 @@ impl Executable for Run {
             .context("failed to initialize tracing")?
         };
 
+        let dataflow_clock = self.dataflow_clock()?;
         let dataflow_path = resolve_dataflow(self.dataflow)
             .await
             .context("could not resolve dataflow")?;
@@ -108,6 +189,9 @@ impl Executable for Run {
             write_events_to(),
             self.stop_after,
             self.hot_reload,
+            self.watch,
+            dataflow_clock,
+            self.node_communication.into(),
         )
         .await?;
         handle_dataflow_result(result, None)
@@ -6,8 +6,8 @@ use super::{Executable, default_tracing};
 use crate::{
     command::start::attach::attach_dataflow,
     common::{
-        connect_and_check_version, local_working_dir, long_context, resolve_dataflow, rpc,
-        write_events_to,
+        CoordinatorAddrs, connect_and_check_version, local_working_dir, long_context,
+        resolve_dataflow, rpc, write_events_to,
     },
     output::{abort_log_task_with_grace, subscribe_and_print_logs},
     session::DataflowSession,
@@ -21,10 +21,7 @@ use dora_message::{
     tarpc,
 };
 use eyre::Context;
-use std::{
-    net::{IpAddr, SocketAddr},
-    path::Path,
-};
+use std::path::Path;
 use tokio::task::JoinHandle;
 use uuid::{NoContext, Timestamp, Uuid};
 
@@ -39,9 +36,10 @@ pub struct Start {
     /// Assign a name to the dataflow
     #[clap(long)]
     name: Option<String>,
-    /// Address of the dora coordinator
-    #[clap(long, value_name = "IP", default_value_t = LOCALHOST)]
-    coordinator_addr: IpAddr,
+    /// Address of the dora coordinator, or comma-separated addresses of a
+    /// high-availability coordinator cluster
+    #[clap(long, value_name = "IP[:PORT],...", default_value_t = LOCALHOST.into())]
+    coordinator_addr: CoordinatorAddrs,
     /// Port number of the coordinator control server
     #[clap(long, value_name = "PORT", default_value_t = DORA_COORDINATOR_PORT_CONTROL_DEFAULT)]
     coordinator_port: u16,
@@ -62,8 +60,6 @@ pub struct Start {
 impl Executable for Start {
     async fn execute(self) -> eyre::Result<()> {
         default_tracing()?;
-        let coordinator_socket: SocketAddr = (self.coordinator_addr, self.coordinator_port).into();
-
         // Generate the dataflow ID on the CLI side so we can subscribe to
         // zenoh log messages *before* triggering the start RPC, ensuring
         // no early log messages are missed.
@@ -86,7 +82,7 @@ impl Executable for Start {
             Descriptor::blocking_read(&dataflow_path).wrap_err("Failed to read yaml dataflow")?;
         let dataflow_session = DataflowSession::read_session(&dataflow_path)
             .context("failed to read DataflowSession")?;
-        let client = connect_and_check_version(coordinator_socket.ip(), coordinator_socket.port())
+        let client = connect_and_check_version(&self.coordinator_addr, self.coordinator_port)
             .await
             .wrap_err("failed to connect to dora coordinator")?;
 
@@ -103,7 +99,7 @@ impl Executable for Start {
 
         // Open the zenoh session and subscribe to logs *before* the start
         // RPC so that no early log messages are missed.
-        let zenoh_session = dora_core::topics::open_zenoh_session(Some(self.coordinator_addr))
+        let zenoh_session = dora_core::topics::open_zenoh_session(Some(self.coordinator_addr.ip()))
             .await
             .wrap_err("failed to open zenoh session for log subscription")?;
         let base_log_topic = dora_core::topics::zenoh_log_base_topic_for_dataflow(dataflow_id);
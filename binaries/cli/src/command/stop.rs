@@ -1,8 +1,15 @@
 use super::{Executable, default_tracing};
-use crate::common::{
-    connect_and_check_version, handle_dataflow_result, long_context, query_running_dataflows, rpc,
+use crate::{
+    common::{
+        CoordinatorAddrs, connect_and_check_version, handle_dataflow_result, long_context,
+        query_running_dataflows, rpc,
+    },
+    output::{close_log_session_and_wait, subscribe_to_logs},
+};
+use dora_core::topics::{
+    DORA_COORDINATOR_PORT_CONTROL_DEFAULT, LOCALHOST, open_zenoh_session,
+    zenoh_log_base_topic_for_dataflow,
 };
-use dora_core::topics::{DORA_COORDINATOR_PORT_CONTROL_DEFAULT, LOCALHOST};
 use dora_message::{
     cli_to_coordinator::CoordinatorControlClient, coordinator_to_cli::StopDataflowReply,
 };
@@ -10,11 +17,15 @@ use duration_str::parse;
 use eyre::Context;
 use std::net::IpAddr;
 use std::time::Duration;
+use tokio::task::JoinHandle;
 use uuid::Uuid;
 
 #[derive(Debug, clap::Args)]
 /// Stop a running dataflow. If no id or name is provided, you will be able to choose between the running dataflows.
 ///
+/// Nodes are stopped in the order of the data flow: sources first, and every other node
+/// once all of its upstream nodes exited. The shutdown progress is printed while waiting.
+///
 /// You could specify the strategy to stop the dataflow with `--grace-duration` or `--force`.
 pub struct Stop {
     /// UUID of the dataflow that should be stopped
@@ -22,12 +33,12 @@ pub struct Stop {
     /// Name of the dataflow that should be stopped
     #[clap(long)]
     name: Option<String>,
-    /// Kill the dataflow if it doesn't stop after the given duration
+    /// Kill nodes that don't stop within the given duration
     ///
-    /// Specifically, it does the following:
-    /// 1. Sends `Event::Stop` to all nodes of the dataflow.
+    /// Used for nodes that don't set a `stop_timeout`. Specifically, for every node:
+    /// 1. Sends `Event::Stop` once all upstream nodes of the node exited.
     /// 2. After DURATION, performs a soft kill (sending SIGTERM, or Ctrl-Break on Windows).
-    /// 3. If the dataflow is still running after DURATION * 0.5, terminates all its processes.
+    /// 3. If the node is still running after DURATION * 0.5, terminates its process.
     #[clap(
         long,
         value_name = "DURATION",
@@ -39,9 +50,10 @@ pub struct Stop {
     /// Force stop the dataflow by immediately terminating all its processes
     #[clap(short, long, action, group = "strategy")]
     force: bool,
-    /// Address of the dora coordinator
-    #[clap(long, value_name = "IP", default_value_t = LOCALHOST)]
-    coordinator_addr: IpAddr,
+    /// Address of the dora coordinator, or comma-separated addresses of a
+    /// high-availability coordinator cluster
+    #[clap(long, value_name = "IP[:PORT],...", default_value_t = LOCALHOST.into())]
+    coordinator_addr: CoordinatorAddrs,
     /// Port number of the coordinator control server
     #[clap(long, value_name = "PORT", default_value_t = DORA_COORDINATOR_PORT_CONTROL_DEFAULT)]
     coordinator_port: u16,
@@ -50,16 +62,19 @@ pub struct Stop {
 impl Executable for Stop {
     async fn execute(self) -> eyre::Result<()> {
         default_tracing()?;
-        let client = connect_and_check_version(self.coordinator_addr, self.coordinator_port)
+        let client = connect_and_check_version(&self.coordinator_addr, self.coordinator_port)
             .await
             .wrap_err("could not connect to dora coordinator")?;
+        let addr = self.coordinator_addr.ip();
         match (self.uuid, self.name) {
-            (Some(uuid), _) => stop_dataflow(uuid, self.grace_duration, self.force, &client).await,
+            (Some(uuid), _) => {
+                stop_dataflow(uuid, self.grace_duration, self.force, &client, addr).await
+            }
             (None, Some(name)) => {
-                stop_dataflow_by_name(name, self.grace_duration, self.force, &client).await
+                stop_dataflow_by_name(name, self.grace_duration, self.force, &client, addr).await
             }
             (None, None) => {
-                stop_dataflow_interactive(self.grace_duration, self.force, &client).await
+                stop_dataflow_interactive(self.grace_duration, self.force, &client, addr).await
             }
         }
     }
@@ -69,6 +84,7 @@ async fn stop_dataflow_interactive(
     grace_duration: Option<Duration>,
     force: bool,
     client: &CoordinatorControlClient,
+    coordinator_addr: IpAddr,
 ) -> eyre::Result<()> {
     let list = query_running_dataflows(client)
         .await
@@ -78,7 +94,14 @@ async fn stop_dataflow_interactive(
         eprintln!("No dataflows are running");
     } else {
         let selection = inquire::Select::new("Choose dataflow to stop:", active).prompt()?;
-        stop_dataflow(selection.uuid, grace_duration, force, client).await?;
+        stop_dataflow(
+            selection.uuid,
+            grace_duration,
+            force,
+            client,
+            coordinator_addr,
+        )
+        .await?;
     }
 
     Ok(())
@@ -89,12 +112,18 @@ async fn stop_dataflow(
     grace_duration: Option<Duration>,
     force: bool,
     client: &CoordinatorControlClient,
+    coordinator_addr: IpAddr,
 ) -> Result<(), eyre::ErrReport> {
-    let StopDataflowReply { uuid, result } = rpc(
+    let progress = print_shutdown_progress(coordinator_addr, uuid).await;
+    let reply = rpc(
         "stop dataflow",
         client.stop(long_context(), uuid, grace_duration, force),
     )
-    .await?;
+    .await;
+    if let Some((zenoh_session, task)) = progress {
+        close_log_session_and_wait(zenoh_session, task).await;
+    }
+    let StopDataflowReply { uuid, result } = reply?;
     handle_dataflow_result(result, Some(uuid))
 }
 
@@ -103,11 +132,54 @@ async fn stop_dataflow_by_name(
     grace_duration: Option<Duration>,
     force: bool,
     client: &CoordinatorControlClient,
+    coordinator_addr: IpAddr,
 ) -> Result<(), eyre::ErrReport> {
-    let StopDataflowReply { uuid, result } = rpc(
+    let uuid = query_running_dataflows(client)
+        .await
+        .ok()
+        .and_then(|list| {
+            list.get_active()
+                .into_iter()
+                .find(|d| d.name.as_ref() == Some(&name))
+        })
+        .map(|d| d.uuid);
+    let progress = match uuid {
+        Some(uuid) => print_shutdown_progress(coordinator_addr, uuid).await,
+        None => None,
+    };
+    let reply = rpc(
         "stop dataflow by name",
         client.stop_by_name(long_context(), name, grace_duration, force),
     )
-    .await?;
+    .await;
+    if let Some((zenoh_session, task)) = progress {
+        close_log_session_and_wait(zenoh_session, task).await;
+    }
+    let StopDataflowReply { uuid, result } = reply?;
     handle_dataflow_result(result, Some(uuid))
 }
+
+/// Prints the shutdown progress that the daemons log while the dataflow stops.
+///
+/// Progress is best effort, so errors are only logged.
+async fn print_shutdown_progress(
+    coordinator_addr: IpAddr,
+    dataflow_id: Uuid,
+) -> Option<(zenoh::Session, JoinHandle<()>)> {
+    let result = async {
+        let zenoh_session = open_zenoh_session(Some(coordinator_addr))
+            .await
+            .wrap_err("failed to open zenoh session for log subscription")?;
+        let base_topic = zenoh_log_base_topic_for_dataflow(dataflow_id);
+        let subscription =
+            subscribe_to_logs(&zenoh_session, &base_topic, log::LevelFilter::Info).await?;
+        let task = subscription.spawn_filtered_printer(false, |message| {
+            message.target.as_deref() == Some("shutdown")
+        });
+        eyre::Ok((zenoh_session, task))
+    }
+    .await;
+    result
+        .inspect_err(|err| tracing::warn!("cannot print shutdown progress: {err:?}"))
+        .ok()
+}
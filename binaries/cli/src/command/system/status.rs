@@ -1,7 +1,7 @@
 use crate::command::{Executable, default_tracing};
 use crate::{
     LOCALHOST,
-    common::{connect_to_coordinator_rpc, rpc},
+    common::{CoordinatorAddrs, connect_to_coordinator_rpc, rpc},
 };
 use dora_core::descriptor::DescriptorExt;
 use dora_core::{descriptor::Descriptor, topics::DORA_COORDINATOR_PORT_CONTROL_DEFAULT};
@@ -11,15 +11,26 @@ use dora_message::{
     tarpc,
 };
 use eyre::{Context, bail};
-use std::{
-    io::{IsTerminal, Write},
-    net::SocketAddr,
-};
-use std::{net::IpAddr, path::PathBuf};
+use itertools::Itertools;
+use std::io::{IsTerminal, Write};
+use std::path::PathBuf;
 use termcolor::{Color, ColorChoice, ColorSpec, WriteColor};
 
-pub async fn check_environment(coordinator_addr: SocketAddr) -> eyre::Result<()> {
+/// Checks the coordinator and daemons, and whether they can run the given dataflow.
+pub async fn check_environment(
+    coordinator_addr: &CoordinatorAddrs,
+    control_port: u16,
+    dataflow: Option<&Descriptor>,
+) -> eyre::Result<()> {
     let mut error_occurred = false;
+    // warn about missing permissions if no dataflow is given
+    let needs_realtime_scheduling = dataflow.is_none_or(|dataflow| {
+        dataflow.nodes.iter().any(|node| {
+            node.scheduling
+                .as_ref()
+                .is_some_and(|scheduling| scheduling.requires_privileges())
+        })
+    });
 
     let color_choice = if std::io::stdout().is_terminal() {
         ColorChoice::Auto
@@ -29,30 +40,25 @@ pub async fn check_environment(coordinator_addr: SocketAddr) -> eyre::Result<()>
     let mut stdout = termcolor::StandardStream::stdout(color_choice);
 
     // Coordinator status
-    let client =
-        match connect_to_coordinator_rpc(coordinator_addr.ip(), coordinator_addr.port()).await {
-            Ok(client) => {
-                let _ = stdout.set_color(ColorSpec::new().set_fg(Some(Color::Green)));
-                write!(stdout, "✓ ")?;
-                let _ = stdout.reset();
-                writeln!(stdout, "Coordinator: Running")?;
-                writeln!(
-                    stdout,
-                    "  Address: {}:{}",
-                    coordinator_addr.ip(),
-                    coordinator_addr.port()
-                )?;
-                Some(client)
-            }
-            Err(_) => {
-                let _ = stdout.set_color(ColorSpec::new().set_fg(Some(Color::Red)));
-                write!(stdout, "✗ ")?;
-                let _ = stdout.reset();
-                writeln!(stdout, "Coordinator: Not running")?;
-                error_occurred = true;
-                None
-            }
-        };
+    let client = match connect_to_coordinator_rpc(coordinator_addr, control_port).await {
+        Ok(client) => {
+            let _ = stdout.set_color(ColorSpec::new().set_fg(Some(Color::Green)));
+            write!(stdout, "✓ ")?;
+            let _ = stdout.reset();
+            writeln!(stdout, "Coordinator: Running")?;
+            let addrs = coordinator_addr.socket_addrs(control_port);
+            writeln!(stdout, "  Address: {}", addrs.iter().join(", "))?;
+            Some(client)
+        }
+        Err(_) => {
+            let _ = stdout.set_color(ColorSpec::new().set_fg(Some(Color::Red)));
+            write!(stdout, "✗ ")?;
+            let _ = stdout.reset();
+            writeln!(stdout, "Coordinator: Not running")?;
+            error_occurred = true;
+            None
+        }
+    };
 
     // Daemon status
     let daemon_running_result = match client.as_ref() {
@@ -77,6 +83,27 @@ pub async fn check_environment(coordinator_addr: SocketAddr) -> eyre::Result<()>
                         "  Daemon {}  Zenoh: {} (peer: {})",
                         d.daemon_id, zenoh_mark, peer
                     )?;
+                    if !d.labels.is_empty() {
+                        let labels = d.labels.iter().map(|(k, v)| format!("{k}={v}"));
+                        writeln!(
+                            stdout,
+                            "    Labels: {}",
+                            labels.collect::<Vec<_>>().join(", ")
+                        )?;
+                    }
+                    if let Some(allocation) = format_allocation(d) {
+                        writeln!(stdout, "    Allocated: {allocation}")?;
+                    }
+                    if needs_realtime_scheduling && !d.realtime_scheduling {
+                        let _ = stdout.set_color(ColorSpec::new().set_fg(Some(Color::Yellow)));
+                        write!(stdout, "  ⚠ ")?;
+                        let _ = stdout.reset();
+                        writeln!(
+                            stdout,
+                            "Daemon {} lacks `CAP_SYS_NICE`: nodes with real-time `scheduling` or negative `nice` values will fail to spawn",
+                            d.daemon_id
+                        )?;
+                    }
                 }
             }
         }
@@ -108,6 +135,24 @@ pub async fn check_environment(coordinator_addr: SocketAddr) -> eyre::Result<()>
     Ok(())
 }
 
+/// Formats the allocated and total capacity of a daemon, e.g. `2/8 CPUs`.
+fn format_allocation(daemon: &DaemonInfo) -> Option<String> {
+    let mut parts = Vec::new();
+    if let Some(cpus) = daemon.capacity.cpus {
+        let allocated = daemon.allocated.cpus.unwrap_or_default();
+        parts.push(format!("{allocated}/{cpus} CPUs"));
+    }
+    if let Some(memory) = daemon.capacity.memory {
+        let allocated = daemon.allocated.memory.unwrap_or_default();
+        parts.push(format!(
+            "{}/{} MB memory",
+            allocated / 1_000_000,
+            memory / 1_000_000
+        ));
+    }
+    (!parts.is_empty()).then(|| parts.join(", "))
+}
+
 pub async fn daemon_running(client: &CoordinatorControlClient) -> Result<bool, eyre::ErrReport> {
     rpc(
         "check daemon connection",
@@ -142,9 +187,10 @@ pub struct Status {
     /// Path to the dataflow descriptor file (enables additional checks)
     #[clap(long, value_name = "PATH", value_hint = clap::ValueHint::FilePath)]
     dataflow: Option<PathBuf>,
-    /// Address of the dora coordinator
-    #[clap(long, value_name = "IP", default_value_t = LOCALHOST)]
-    coordinator_addr: IpAddr,
+    /// Address of the dora coordinator, or comma-separated addresses of a
+    /// high-availability coordinator cluster
+    #[clap(long, value_name = "IP[:PORT],...", default_value_t = LOCALHOST.into())]
+    coordinator_addr: CoordinatorAddrs,
     /// Port number of the coordinator control server
     #[clap(long, value_name = "PORT", default_value_t = DORA_COORDINATOR_PORT_CONTROL_DEFAULT)]
     coordinator_port: u16,
@@ -162,12 +208,16 @@ impl Executable for Status {
                     .parent()
                     .ok_or_else(|| eyre::eyre!("dataflow path has no parent dir"))?
                     .to_owned();
-                Descriptor::blocking_read(&dataflow)?.check(&working_dir)?;
-                check_environment((self.coordinator_addr, self.coordinator_port).into()).await?
-            }
-            None => {
-                check_environment((self.coordinator_addr, self.coordinator_port).into()).await?
+                let descriptor = Descriptor::blocking_read(&dataflow)?;
+                descriptor.check(&working_dir)?;
+                check_environment(
+                    &self.coordinator_addr,
+                    self.coordinator_port,
+                    Some(&descriptor),
+                )
+                .await?
             }
+            None => check_environment(&self.coordinator_addr, self.coordinator_port, None).await?,
         }
 
         Ok(())
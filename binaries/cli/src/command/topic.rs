@@ -1,12 +1,13 @@
 use crate::command::{
     Executable,
-    topic::{echo::Echo, hz::Hz, info::Info, list::List},
+    topic::{echo::Echo, hz::Hz, info::Info, list::List, publish::Publish},
 };
 
 mod echo;
 mod hz;
 mod info;
 mod list;
+mod publish;
 mod selector;
 
 /// Manage and inspect dataflow topics.
@@ -16,6 +17,8 @@ pub enum Topic {
     Echo(Echo),
     Hz(Hz),
     Info(Info),
+    #[clap(name = "pub")]
+    Pub(Publish),
 }
 
 impl Executable for Topic {
@@ -25,6 +28,7 @@ impl Executable for Topic {
             Topic::Echo(cmd) => cmd.execute().await,
             Topic::Hz(cmd) => cmd.execute().await,
             Topic::Info(cmd) => cmd.execute().await,
+            Topic::Pub(cmd) => cmd.execute().await,
         }
     }
 }
@@ -1,9 +1,13 @@
 use std::{ptr::NonNull, sync::Arc, time::SystemTime};
 
+use aligned_vec::AVec;
 use arrow::{buffer::OffsetBuffer, datatypes::Field};
 use clap::Args;
 use colored::Colorize;
-use dora_core::topics::{open_zenoh_session, zenoh_output_publish_topic};
+use dora_core::{
+    compression,
+    topics::{open_zenoh_session_for_dataflow, zenoh_output_publish_topic},
+};
 use dora_message::{
     common::Timestamped,
     daemon_to_daemon::InterDaemonEvent,
@@ -74,16 +78,18 @@ async fn inspect(
     format: OutputFormat,
 ) -> eyre::Result<()> {
     let client = coordinator.connect_rpc().await?;
-    let (dataflow_id, topics) = selector.resolve(&client).await?;
+    let (dataflow_id, remote, topics) = selector.resolve(&client).await?;
 
-    let zenoh_session = open_zenoh_session(Some(coordinator.coordinator_addr))
-        .await
-        .context("failed to open zenoh session")?;
+    let zenoh_session =
+        open_zenoh_session_for_dataflow(Some(coordinator.coordinator_addr.ip()), &remote)
+            .await
+            .context("failed to open zenoh session")?;
 
     let mut join_set = JoinSet::new();
     for TopicIdentifier { node_id, data_id } in topics {
         join_set.spawn(log_to_terminal(
             zenoh_session.clone(),
+            remote.zenoh_topic_prefix().to_owned(),
             dataflow_id,
             node_id,
             data_id,
@@ -139,12 +145,13 @@ fn buffer_into_arrow_array(
 
 async fn log_to_terminal(
     zenoh_session: zenoh::Session,
+    prefix: String,
     dataflow_id: Uuid,
     node_id: NodeId,
     output_id: DataId,
     format: OutputFormat,
 ) -> eyre::Result<()> {
-    let subscribe_topic = zenoh_output_publish_topic(dataflow_id, &node_id, &output_id);
+    let subscribe_topic = zenoh_output_publish_topic(&prefix, dataflow_id, &node_id, &output_id);
     let output_name = format!("{node_id}/{output_id}");
     let subscriber = zenoh_session
         .declare_subscriber(subscribe_topic)
@@ -167,9 +174,24 @@ async fn log_to_terminal(
             }
         };
         match event.inner {
-            InterDaemonEvent::Output { metadata, data, .. } => {
+            InterDaemonEvent::Output {
+                mut metadata, data, ..
+            } => {
                 use std::fmt::Write;
 
+                // outputs with a `compression` setting are published compressed
+                let data = match &data {
+                    Some(bytes) => match compression::decompress(&mut metadata, bytes) {
+                        Ok(Some(decompressed)) => Some(AVec::from_slice(128, &decompressed)),
+                        Ok(None) => data,
+                        Err(e) => {
+                            eprintln!("invalid data: {e:?}");
+                            continue;
+                        }
+                    },
+                    None => data,
+                };
+
                 let timestamp = SystemTime::now()
                     .duration_since(SystemTime::UNIX_EPOCH)
                     .unwrap()
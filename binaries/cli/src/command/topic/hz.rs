@@ -1,5 +1,8 @@
 use crossterm::event::{Event, KeyCode, KeyModifiers};
-use dora_core::topics::{open_zenoh_session, zenoh_output_publish_topic};
+use dora_core::{
+    config::RemoteCommunicationConfig,
+    topics::{open_zenoh_session_for_dataflow, zenoh_output_publish_topic},
+};
 use dora_message::{common::Timestamped, daemon_to_daemon::InterDaemonEvent};
 use eyre::{Context, eyre};
 use itertools::Itertools;
@@ -66,15 +69,16 @@ pub struct Hz {
 impl Executable for Hz {
     async fn execute(self) -> eyre::Result<()> {
         let client = self.coordinator.connect_rpc().await?;
-        let (dataflow_id, topics) = self.selector.resolve(&client).await?;
+        let (dataflow_id, remote, topics) = self.selector.resolve(&client).await?;
 
         let terminal = ratatui::init();
         let result = run_hz(
             terminal,
             self.window,
             dataflow_id,
+            &remote,
             topics,
-            self.coordinator.coordinator_addr,
+            self.coordinator.coordinator_addr.ip(),
         )
         .await;
         result.inspect(|_| {
@@ -165,6 +169,7 @@ async fn run_hz(
     mut terminal: DefaultTerminal,
     window: usize,
     dataflow_id: Uuid,
+    remote: &RemoteCommunicationConfig,
     outputs: BTreeSet<TopicIdentifier>,
     coordinator_addr: IpAddr,
 ) -> eyre::Result<()> {
@@ -200,7 +205,7 @@ async fn run_hz(
         )
     })?;
 
-    let zenoh_session = open_zenoh_session(Some(coordinator_addr))
+    let zenoh_session = open_zenoh_session_for_dataflow(Some(coordinator_addr), remote)
         .await
         .context("failed to open zenoh session")?;
 
@@ -212,12 +217,14 @@ async fn run_hz(
             continue;
         }
         let zenoh_session = zenoh_session.clone();
+        let prefix = remote.zenoh_topic_prefix().to_owned();
         let topic = (*topic).clone();
         let hz_stats = hz_stats.clone();
         let all_stats_cloned = all_stats.clone();
         tokio::spawn(async move {
             if let Err(e) = subscribe_output(
                 zenoh_session,
+                &prefix,
                 dataflow_id,
                 &topic,
                 hz_stats,
@@ -299,12 +306,14 @@ async fn run_hz(
 
 async fn subscribe_output(
     zenoh_session: zenoh::Session,
+    prefix: &str,
     dataflow_id: Uuid,
     topic: &TopicIdentifier,
     hz_stats: Arc<HzStats>,
     aggregate: Option<Arc<HzStats>>,
 ) -> eyre::Result<()> {
-    let subscribe_topic = zenoh_output_publish_topic(dataflow_id, &topic.node_id, &topic.data_id);
+    let subscribe_topic =
+        zenoh_output_publish_topic(prefix, dataflow_id, &topic.node_id, &topic.data_id);
     let subscriber = zenoh_session
         .declare_subscriber(subscribe_topic)
         .await
@@ -6,8 +6,9 @@ use std::{
 use arrow_schema::DataType;
 use clap::Args;
 use dora_core::{
-    config::InputMapping,
-    topics::{open_zenoh_session, zenoh_output_publish_topic},
+    compression,
+    config::{Compression, InputMapping},
+    topics::{open_zenoh_session_for_dataflow, zenoh_output_publish_topic},
 };
 use dora_message::{
     common::Timestamped, daemon_to_daemon::InterDaemonEvent, metadata::ArrowTypeInfo,
@@ -22,7 +23,7 @@ use crate::{
 /// Display detailed metadata of a topic.
 ///
 /// Shows topic type, publisher, subscribers, and statistics (message count,
-/// bandwidth, publishing frequency).
+/// bandwidth, publishing frequency, compression ratio).
 ///
 /// Examples:
 ///
@@ -63,14 +64,28 @@ impl Executable for Info {
 struct TopicStats {
     message_count: Arc<Mutex<u64>>,
     total_bytes: Arc<Mutex<u64>>,
+    /// Size of the messages before compression.
+    uncompressed_bytes: Arc<Mutex<u64>>,
+    compression: Arc<Mutex<Option<Compression>>>,
     timestamps: Arc<Mutex<Vec<Instant>>>,
     data_type: Arc<Mutex<Option<ArrowTypeInfo>>>,
 }
 
 impl TopicStats {
-    fn record(&self, data_size: usize, type_info: &ArrowTypeInfo, now: Instant) {
+    fn record(
+        &self,
+        data_size: usize,
+        uncompressed_size: usize,
+        compression: Option<Compression>,
+        type_info: &ArrowTypeInfo,
+        now: Instant,
+    ) {
         *self.message_count.lock().unwrap() += 1;
         *self.total_bytes.lock().unwrap() += data_size as u64;
+        *self.uncompressed_bytes.lock().unwrap() += uncompressed_size as u64;
+        if compression.is_some() {
+            *self.compression.lock().unwrap() = compression;
+        }
         self.timestamps.lock().unwrap().push(now);
         *self.data_type.lock().unwrap() = Some(type_info.clone());
     }
@@ -114,7 +129,7 @@ async fn info(
     duration_secs: u64,
 ) -> eyre::Result<()> {
     let client = coordinator.connect_rpc().await?;
-    let (dataflow_id, topics) = selector.resolve(&client).await?;
+    let (dataflow_id, remote, topics) = selector.resolve(&client).await?;
 
     if topics.is_empty() {
         eyre::bail!("No topics specified");
@@ -144,13 +159,18 @@ async fn info(
     // Collect statistics by subscribing to messages
     let stats = TopicStats::default();
 
-    let coordinator_addr = coordinator.coordinator_addr;
+    let coordinator_addr = coordinator.coordinator_addr.ip();
 
-    let zenoh_session = open_zenoh_session(Some(coordinator_addr))
+    let zenoh_session = open_zenoh_session_for_dataflow(Some(coordinator_addr), &remote)
         .await
         .context("failed to open zenoh session")?;
 
-    let subscribe_topic = zenoh_output_publish_topic(dataflow_id, &topic.node_id, &topic.data_id);
+    let subscribe_topic = zenoh_output_publish_topic(
+        remote.zenoh_topic_prefix(),
+        dataflow_id,
+        &topic.node_id,
+        &topic.data_id,
+    );
     let subscriber = zenoh_session
         .declare_subscriber(subscribe_topic)
         .await
@@ -178,10 +198,30 @@ async fn info(
                     };
 
                 match event.inner {
-                    InterDaemonEvent::Output { metadata, data, .. } => {
+                    InterDaemonEvent::Output {
+                        mut metadata, data, ..
+                    } => {
                         let data_size = data.as_ref().map(|d| d.len()).unwrap_or(0);
+                        let codec = compression::compression_of(&metadata).ok().flatten();
+                        let uncompressed_size = match &data {
+                            Some(data) if codec.is_some() => {
+                                match compression::decompress(&mut metadata, data) {
+                                    Ok(decompressed) => {
+                                        decompressed.map_or(data.len(), |d| d.len())
+                                    }
+                                    Err(_) => continue,
+                                }
+                            }
+                            _ => data_size,
+                        };
                         let now = Instant::now();
-                        stats.record(data_size, &metadata.type_info, now);
+                        stats.record(
+                            data_size,
+                            uncompressed_size,
+                            codec,
+                            &metadata.type_info,
+                            now,
+                        );
                     }
                     InterDaemonEvent::OutputClosed { .. } => {
                         break;
@@ -199,6 +239,8 @@ async fn info(
     // Display the information
     let message_count = *stats.message_count.lock().unwrap();
     let total_bytes = *stats.total_bytes.lock().unwrap();
+    let uncompressed_bytes = *stats.uncompressed_bytes.lock().unwrap();
+    let codec = *stats.compression.lock().unwrap();
     let data_type = stats.data_type.lock().unwrap().clone();
     let hz = stats.calculate_hz(Duration::from_secs(duration_secs));
 
@@ -242,6 +284,13 @@ async fn info(
     } else {
         println!("  Bandwidth: <unknown>");
     }
+    match codec {
+        Some(codec) if total_bytes > 0 => {
+            let ratio = uncompressed_bytes as f64 / total_bytes as f64;
+            println!("  Compression: {codec} (ratio {ratio:.2})");
+        }
+        _ => println!("  Compression: <none>"),
+    }
 
     Ok(())
 }
@@ -82,7 +82,12 @@ async fn list(
                     .into_iter()
                     .map(|(node, data)| format!("{node}/{data}"))
                     .collect(),
-                zenoh_key: zenoh_output_publish_topic(dataflow_id, &node.id, output),
+                zenoh_key: zenoh_output_publish_topic(
+                    descriptor.communication.remote.zenoh_topic_prefix(),
+                    dataflow_id,
+                    &node.id,
+                    output,
+                ),
             });
         }
     }
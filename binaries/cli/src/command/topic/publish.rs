@@ -0,0 +1,312 @@
+use std::{fs::File, ops::Range, path::PathBuf, str::FromStr};
+
+use arrow::{
+    array::{Array, ArrayData, RecordBatch, StructArray},
+    datatypes::DataType,
+};
+use clap::Args;
+use dora_core::config::InputMapping;
+use dora_message::{
+    common::PublishMessage,
+    filter::Rate,
+    metadata::{MetadataParameters, Parameter},
+    tarpc,
+};
+use dora_node_api::{
+    arrow_utils::{copy_array_into_sample, required_data_size},
+    json_to_arrow::json_value_to_arrow,
+};
+use eyre::{Context, ContextCompat, bail};
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+use crate::{
+    command::{Executable, default_tracing, topic::selector::DataflowSelector},
+    common::{CoordinatorOptions, rpc},
+};
+
+/// Publish a message on a topic of a running dataflow.
+///
+/// The message is delivered to all local and remote receivers of the topic,
+/// as if the node had sent it. The data is given as JSON or read from an
+/// Arrow IPC file or a Parquet file.
+///
+/// Examples:
+///
+/// Send a single value:
+///   dora topic pub -d my-dataflow camera/trigger true
+///
+/// Send a list with an explicit data type and metadata:
+///   dora topic pub -d my-dataflow planner/goal '[1.0, 2.5]' --type '"Float32"' --param frame=map
+///
+/// Replay rows 100 to 199 of a Parquet file at 10Hz:
+///   dora topic pub -d my-dataflow lidar/points --parquet scan.parquet --rows 100..200 --rate 10Hz
+#[derive(Debug, Args)]
+#[clap(verbatim_doc_comment)]
+pub struct Publish {
+    #[clap(flatten)]
+    dataflow: DataflowSelector,
+
+    /// Topic to publish on, e.g. `node_id/output_id`
+    #[clap(value_name = "TOPIC")]
+    topic: String,
+
+    /// Message data as JSON
+    #[clap(value_name = "JSON", group = "source")]
+    data: Option<String>,
+
+    /// Arrow data type of the JSON data, e.g. `"Int64"`
+    ///
+    /// The data type is inferred from the JSON value if not given.
+    #[clap(long = "type", value_name = "DATA_TYPE", requires = "data")]
+    data_type: Option<String>,
+
+    /// Read the message data from an Arrow IPC file
+    #[clap(long, value_name = "PATH", group = "source")]
+    arrow_file: Option<PathBuf>,
+
+    /// Record batch of the Arrow IPC file to send
+    #[clap(
+        long,
+        value_name = "INDEX",
+        default_value_t = 0,
+        requires = "arrow_file"
+    )]
+    batch: usize,
+
+    /// Read the message data from a Parquet file
+    #[clap(long, value_name = "PATH", group = "source")]
+    parquet: Option<PathBuf>,
+
+    /// Rows of the Parquet file to send, e.g. `10..20` (default: all rows)
+    #[clap(long, value_name = "START..END", requires = "parquet")]
+    rows: Option<RowRange>,
+
+    /// Only send the given column of the Arrow IPC or Parquet file
+    ///
+    /// If not set, the whole record batch is sent as struct array.
+    #[clap(long, value_name = "NAME")]
+    column: Option<String>,
+
+    /// Metadata parameter of the message, e.g. `frame=map`
+    #[clap(long = "param", value_name = "KEY=VALUE")]
+    parameters: Vec<String>,
+
+    /// Repeat the message at the given rate, e.g. `10Hz`
+    #[clap(long, value_name = "RATE")]
+    rate: Option<Rate>,
+
+    /// Number of messages to send (default: 1, or unlimited with `--rate`)
+    #[clap(long, value_name = "N")]
+    count: Option<u64>,
+
+    #[clap(flatten)]
+    coordinator: CoordinatorOptions,
+}
+
+impl Executable for Publish {
+    async fn execute(self) -> eyre::Result<()> {
+        default_tracing()?;
+
+        let client = self.coordinator.connect_rpc().await?;
+        let (dataflow_id, descriptor) = self.dataflow.resolve(&client).await?;
+
+        let (node_id, output_id) = match self.topic.parse() {
+            Ok(InputMapping::User(user)) if !user.output.is_empty() => (user.source, user.output),
+            _ => bail!(
+                "invalid topic `{}`, expected `node_id/output_id`",
+                self.topic
+            ),
+        };
+        let node = descriptor
+            .nodes
+            .iter()
+            .find(|node| node.id == node_id)
+            .with_context(|| format!("Unknown node: `{node_id}`"))?;
+        if !node.outputs.contains(&output_id) {
+            bail!("Node `{node_id}` does not have output `{output_id}`");
+        }
+
+        let array = self.read_data()?;
+        let mut data = vec![0; required_data_size(&array)];
+        let type_info = copy_array_into_sample(&mut data, &array);
+        let parameters = self
+            .parameters
+            .iter()
+            .map(|p| parse_parameter(p))
+            .collect::<eyre::Result<MetadataParameters>>()?;
+        let message = PublishMessage {
+            node_id,
+            output_id,
+            type_info,
+            parameters,
+            data,
+        };
+
+        let count = self
+            .count
+            .unwrap_or(if self.rate.is_some() { u64::MAX } else { 1 });
+        let mut interval = self
+            .rate
+            .map(|rate| tokio::time::interval(rate.min_interval));
+        let mut sent = 0;
+        let publish = async {
+            while sent < count {
+                if let Some(interval) = &mut interval {
+                    interval.tick().await;
+                }
+                rpc(
+                    "publish message",
+                    client.publish(tarpc::context::current(), dataflow_id, message.clone()),
+                )
+                .await?;
+                sent += 1;
+            }
+            eyre::Ok(())
+        };
+        // unlimited publishing is stopped with ctrl-c
+        let result = tokio::select! {
+            result = publish => result,
+            _ = tokio::signal::ctrl_c() => Ok(()),
+        };
+        result.wrap_err_with(|| format!("failed after publishing {sent} message(s)"))?;
+        println!("Published {sent} message(s) on `{}`", self.topic);
+        Ok(())
+    }
+}
+
+impl Publish {
+    fn read_data(&self) -> eyre::Result<ArrayData> {
+        if let Some(path) = &self.arrow_file {
+            let file = File::open(path)
+                .with_context(|| format!("failed to open arrow file {}", path.display()))?;
+            let mut reader = arrow::ipc::reader::FileReader::try_new(file, None)
+                .context("failed to create arrow file reader")?;
+            reader.set_index(self.batch).with_context(|| {
+                format!(
+                    "failed to seek to batch index {} in arrow file {}",
+                    self.batch,
+                    path.display()
+                )
+            })?;
+            let batch = reader
+                .next()
+                .context("no batch at given index")?
+                .context("failed to read batch from arrow file")?;
+            self.select_column(batch)
+        } else if let Some(path) = &self.parquet {
+            let file = File::open(path)
+                .with_context(|| format!("failed to open parquet file {}", path.display()))?;
+            let mut builder = ParquetRecordBatchReaderBuilder::try_new(file)
+                .context("failed to create parquet file reader")?;
+            if let Some(RowRange(rows)) = &self.rows {
+                builder = builder.with_offset(rows.start).with_limit(rows.len());
+            }
+            let schema = builder.schema().clone();
+            let batches = builder
+                .build()
+                .context("failed to build parquet file reader")?
+                .collect::<Result<Vec<_>, _>>()
+                .context("failed to read parquet file")?;
+            let batch = arrow::compute::concat_batches(&schema, &batches)
+                .context("failed to concatenate record batches")?;
+            self.select_column(batch)
+        } else {
+            let value = match &self.data {
+                Some(data) => serde_json::from_str(data).context("failed to parse JSON data")?,
+                None => serde_json::Value::Null,
+            };
+            let data_type = self
+                .data_type
+                .as_deref()
+                .map(serde_json::from_str::<DataType>)
+                .transpose()
+                .context("failed to parse `--type`")?;
+            if value.is_null() && data_type.is_none() {
+                // message without data
+                return Ok(ArrayData::new_empty(&DataType::Null));
+            }
+            json_value_to_arrow(value, data_type)
+        }
+    }
+
+    fn select_column(&self, batch: RecordBatch) -> eyre::Result<ArrayData> {
+        Ok(match &self.column {
+            Some(name) => batch
+                .column_by_name(name)
+                .with_context(|| format!("no column `{name}` in record batch"))?
+                .to_data(),
+            None => StructArray::from(batch).to_data(),
+        })
+    }
+}
+
+/// Range of Parquet rows, e.g. `10..20`.
+#[derive(Debug, Clone)]
+struct RowRange(Range<usize>);
+
+impl FromStr for RowRange {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (start, end) = s
+            .split_once("..")
+            .ok_or_else(|| format!("expected a row range such as `10..20` (got `{s}`)"))?;
+        let parse = |v: &str| {
+            v.trim()
+                .parse::<usize>()
+                .map_err(|_| format!("invalid row index `{v}`"))
+        };
+        let (start, end) = (parse(start)?, parse(end)?);
+        if end <= start {
+            return Err(format!("row range `{s}` is empty"));
+        }
+        Ok(Self(start..end))
+    }
+}
+
+fn parse_parameter(s: &str) -> eyre::Result<(String, Parameter)> {
+    let Some((key, value)) = s.split_once('=') else {
+        bail!("invalid parameter `{s}`, expected `KEY=VALUE`");
+    };
+    let value = if let Ok(v) = value.parse() {
+        Parameter::Bool(v)
+    } else if let Ok(v) = value.parse() {
+        Parameter::Integer(v)
+    } else if let Ok(v) = value.parse() {
+        Parameter::Float(v)
+    } else {
+        Parameter::String(value.to_owned())
+    };
+    Ok((key.to_owned(), value))
+}
+
+#[cfg(test)]
+mod tests {
+    use dora_message::metadata::Parameter;
+
+    use super::{RowRange, parse_parameter};
+
+    #[test]
+    fn parse_parameters() {
+        assert_eq!(
+            parse_parameter("frame=map").unwrap(),
+            ("frame".into(), Parameter::String("map".into()))
+        );
+        assert_eq!(
+            parse_parameter("seq=3").unwrap(),
+            ("seq".into(), Parameter::Integer(3))
+        );
+        assert_eq!(
+            parse_parameter("score=0.5").unwrap(),
+            ("score".into(), Parameter::Float(0.5))
+        );
+        assert!(parse_parameter("flag").is_err());
+    }
+
+    #[test]
+    fn parse_row_range() {
+        assert_eq!("10..20".parse::<RowRange>().unwrap().0, 10..20);
+        assert!("20..10".parse::<RowRange>().is_err());
+        assert!("10".parse::<RowRange>().is_err());
+    }
+}
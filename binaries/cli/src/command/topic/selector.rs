@@ -5,7 +5,10 @@ use std::{
 };
 
 use crate::common::{resolve_dataflow_identifier_interactive, rpc};
-use dora_core::{config::InputMapping, descriptor::Descriptor};
+use dora_core::{
+    config::{InputMapping, RemoteCommunicationConfig},
+    descriptor::Descriptor,
+};
 use dora_message::{
     DataflowId,
     cli_to_coordinator::CoordinatorControlClient,
@@ -60,10 +63,16 @@ impl fmt::Display for TopicIdentifier {
 }
 
 impl TopicSelector {
+    /// Resolves the selected dataflow and topics, together with the remote
+    /// communication settings that determine the zenoh topics.
     pub async fn resolve(
         &self,
         client: &CoordinatorControlClient,
-    ) -> eyre::Result<(DataflowId, BTreeSet<TopicIdentifier>)> {
+    ) -> eyre::Result<(
+        DataflowId,
+        RemoteCommunicationConfig,
+        BTreeSet<TopicIdentifier>,
+    )> {
         let (dataflow_id, dataflow_descriptor) = self.dataflow.resolve(client).await?;
         if !dataflow_descriptor.debug.publish_all_messages_to_zenoh {
             bail!(
@@ -92,7 +101,7 @@ impl TopicSelector {
                     data_id: output.clone(),
                 })
             }));
-            return Ok((dataflow_id, data));
+            return Ok((dataflow_id, dataflow_descriptor.communication.remote, data));
         }
 
         for s in &self.data {
@@ -130,6 +139,6 @@ impl TopicSelector {
             }
         }
 
-        Ok((dataflow_id, data))
+        Ok((dataflow_id, dataflow_descriptor.communication.remote, data))
     }
 }
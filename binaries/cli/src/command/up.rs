@@ -2,13 +2,15 @@ use super::system::status::daemon_running;
 use super::{Executable, default_tracing};
 use crate::{
     LOCALHOST,
-    common::{connect_and_check_version, connect_to_coordinator_rpc, long_context, rpc},
+    common::{
+        CoordinatorAddrs, connect_and_check_version, connect_to_coordinator_rpc, long_context, rpc,
+    },
 };
 use dora_core::topics::DORA_COORDINATOR_PORT_CONTROL_DEFAULT;
 
 use eyre::{Context, ContextCompat, bail};
 use std::path::PathBuf;
-use std::{fs, net::SocketAddr, path::Path, process::Command, time::Duration};
+use std::{fs, path::Path, process::Command, time::Duration};
 
 #[derive(Debug, clap::Args)]
 /// Spawn coordinator and daemon in local mode (with default config)
@@ -30,15 +32,15 @@ struct UpConfig {}
 
 pub(crate) async fn up(config_path: Option<&Path>) -> eyre::Result<()> {
     let UpConfig {} = parse_dora_config(config_path)?;
-    let coordinator_addr = LOCALHOST;
+    let coordinator_addr = LOCALHOST.into();
     let control_port = DORA_COORDINATOR_PORT_CONTROL_DEFAULT;
-    let client = match connect_to_coordinator_rpc(coordinator_addr, control_port).await {
+    let client = match connect_to_coordinator_rpc(&coordinator_addr, control_port).await {
         Ok(client) => client,
         Err(_) => {
             start_coordinator().wrap_err("failed to start dora-coordinator")?;
 
             loop {
-                match connect_to_coordinator_rpc(coordinator_addr, control_port).await {
+                match connect_to_coordinator_rpc(&coordinator_addr, control_port).await {
                     Ok(client) => break client,
                     Err(_) => {
                         // sleep a bit until the coordinator accepts connections
@@ -72,10 +74,11 @@ pub(crate) async fn up(config_path: Option<&Path>) -> eyre::Result<()> {
 
 pub(crate) async fn destroy(
     config_path: Option<&Path>,
-    coordinator_addr: SocketAddr,
+    coordinator_addr: &CoordinatorAddrs,
+    control_port: u16,
 ) -> Result<(), eyre::ErrReport> {
     let UpConfig {} = parse_dora_config(config_path)?;
-    match connect_and_check_version(coordinator_addr.ip(), coordinator_addr.port()).await {
+    match connect_and_check_version(coordinator_addr, control_port).await {
         Ok(client) => {
             rpc("destroy coordinator", client.destroy(long_context())).await?;
             println!("Coordinator and daemons destroyed successfully");
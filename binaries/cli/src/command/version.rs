@@ -1,14 +1,19 @@
 use super::{Executable, default_tracing};
-use crate::{LOCALHOST, common::connect_to_coordinator_rpc, get_python_dora_version};
+use crate::{
+    LOCALHOST,
+    common::{CoordinatorAddrs, connect_to_coordinator_rpc},
+    get_python_dora_version,
+};
 use dora_core::topics::DORA_COORDINATOR_PORT_CONTROL_DEFAULT;
 use dora_message::tarpc;
 
 #[derive(Debug, clap::Args)]
 /// Show detailed version information for CLI, message format, and coordinator.
 pub struct Version {
-    /// Address of the dora coordinator
-    #[clap(long, value_name = "IP", default_value_t = LOCALHOST)]
-    coordinator_addr: std::net::IpAddr,
+    /// Address of the dora coordinator, or comma-separated addresses of a
+    /// high-availability coordinator cluster
+    #[clap(long, value_name = "IP[:PORT],...", default_value_t = LOCALHOST.into())]
+    coordinator_addr: CoordinatorAddrs,
     /// Port number of the coordinator control server
     #[clap(long, value_name = "PORT", default_value_t = DORA_COORDINATOR_PORT_CONTROL_DEFAULT)]
     coordinator_port: u16,
@@ -26,7 +31,7 @@ impl Executable for Version {
             None => println!("Python dora-rs version:     not found"),
         }
 
-        match connect_to_coordinator_rpc(self.coordinator_addr, self.coordinator_port).await {
+        match connect_to_coordinator_rpc(&self.coordinator_addr, self.coordinator_port).await {
             Ok(client) => match client.get_version(tarpc::context::current()).await {
                 Ok(info) => {
                     println!("Coordinator version:        {}", info.coordinator_version);
@@ -13,8 +13,9 @@ use eyre::{Context, ContextCompat, bail};
 use std::{
     env::current_dir,
     future::Future,
-    net::IpAddr,
+    net::{IpAddr, SocketAddr},
     path::{Path, PathBuf},
+    str::FromStr,
     time::Duration,
 };
 use uuid::Uuid;
@@ -99,9 +100,10 @@ pub(crate) async fn resolve_dataflow_identifier_interactive(
 
 #[derive(Debug, clap::Args)]
 pub(crate) struct CoordinatorOptions {
-    /// Address of the dora coordinator
-    #[clap(long, value_name = "IP", default_value_t = LOCALHOST)]
-    pub coordinator_addr: IpAddr,
+    /// Address of the dora coordinator, or comma-separated addresses of a
+    /// high-availability coordinator cluster
+    #[clap(long, value_name = "IP[:PORT],...", default_value_t = LOCALHOST.into())]
+    pub coordinator_addr: CoordinatorAddrs,
     /// Port number of the coordinator control server
     #[clap(long, value_name = "PORT", default_value_t = DORA_COORDINATOR_PORT_CONTROL_DEFAULT)]
     pub coordinator_port: u16,
@@ -109,22 +111,100 @@ pub(crate) struct CoordinatorOptions {
 
 impl CoordinatorOptions {
     pub async fn connect_rpc(&self) -> eyre::Result<CoordinatorControlClient> {
-        Ok(connect_and_check_version(self.coordinator_addr, self.coordinator_port).await?)
+        Ok(connect_and_check_version(&self.coordinator_addr, self.coordinator_port).await?)
+    }
+}
+
+/// Addresses of one or more coordinators, e.g. `10.0.0.1,10.0.0.2:7000`.
+///
+/// Multiple addresses are given for a high-availability coordinator cluster.
+/// Only the current leader of the cluster accepts connections, so the
+/// addresses are tried in order. Addresses without port use the port given
+/// through a separate argument.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct CoordinatorAddrs(Vec<(IpAddr, Option<u16>)>);
+
+impl CoordinatorAddrs {
+    /// The IP of the first coordinator, used for zenoh discovery.
+    pub fn ip(&self) -> IpAddr {
+        self.0[0].0
+    }
+
+    pub fn socket_addrs(&self, default_port: u16) -> Vec<SocketAddr> {
+        self.0
+            .iter()
+            .map(|(ip, port)| SocketAddr::new(*ip, port.unwrap_or(default_port)))
+            .collect()
+    }
+}
+
+impl From<IpAddr> for CoordinatorAddrs {
+    fn from(ip: IpAddr) -> Self {
+        Self(vec![(ip, None)])
+    }
+}
+
+impl FromStr for CoordinatorAddrs {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let addrs = s
+            .split(',')
+            .map(|addr| {
+                let addr = addr.trim();
+                if let Ok(socket_addr) = addr.parse::<SocketAddr>() {
+                    Ok((socket_addr.ip(), Some(socket_addr.port())))
+                } else {
+                    let ip = addr
+                        .parse::<IpAddr>()
+                        .map_err(|_| format!("invalid coordinator address `{addr}`"))?;
+                    Ok((ip, None))
+                }
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        Ok(Self(addrs))
+    }
+}
+
+impl std::fmt::Display for CoordinatorAddrs {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, (ip, port)) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str(",")?;
+            }
+            match port {
+                Some(port) => write!(f, "{}", SocketAddr::new(*ip, *port))?,
+                None => write!(f, "{ip}")?,
+            }
+        }
+        Ok(())
     }
 }
 
 /// Connect to the coordinator's tarpc RPC service.
+///
+/// Tries the given coordinators in order and connects to the first one that
+/// accepts the connection.
 pub(crate) async fn connect_to_coordinator_rpc(
-    addr: IpAddr,
+    addrs: &CoordinatorAddrs,
     control_port: u16,
 ) -> eyre::Result<CoordinatorControlClient> {
-    let rpc_port = dora_coordinator_port_rpc(control_port);
-    let transport =
-        tarpc::serde_transport::tcp::connect((addr, rpc_port), tokio_serde::formats::Json::default)
+    let mut last_error = None;
+    for addr in addrs.socket_addrs(control_port) {
+        let rpc_addr = (addr.ip(), dora_coordinator_port_rpc(addr.port()));
+        match tarpc::serde_transport::tcp::connect(rpc_addr, tokio_serde::formats::Json::default)
             .await
-            .context("failed to connect tarpc client to coordinator")?;
-    let client = CoordinatorControlClient::new(client::Config::default(), transport).spawn();
-    Ok(client)
+        {
+            Ok(transport) => {
+                let client =
+                    CoordinatorControlClient::new(client::Config::default(), transport).spawn();
+                return Ok(client);
+            }
+            Err(err) => last_error = Some(err),
+        }
+    }
+    let err = last_error.context("no coordinator address given")?;
+    Err(err).context("failed to connect tarpc client to coordinator")
 }
 
 pub(crate) async fn resolve_dataflow(dataflow: String) -> eyre::Result<PathBuf> {
@@ -209,10 +289,10 @@ impl std::error::Error for ConnectAndCheckVersionError {
 
 /// Connect to the coordinator and check that the message format version is compatible.
 pub(crate) async fn connect_and_check_version(
-    addr: IpAddr,
+    addrs: &CoordinatorAddrs,
     control_port: u16,
 ) -> Result<CoordinatorControlClient, ConnectAndCheckVersionError> {
-    let client = connect_to_coordinator_rpc(addr, control_port)
+    let client = connect_to_coordinator_rpc(addrs, control_port)
         .await
         .map_err(ConnectAndCheckVersionError::ConnectionFailed)?;
     check_coordinator_version(&client)
@@ -308,6 +388,22 @@ mod tests {
         assert!(!semver_compatible(&v("1.0.0"), &v("2.0.0")));
     }
 
+    #[test]
+    fn parses_multiple_coordinator_addrs() {
+        let addrs: CoordinatorAddrs = "10.0.0.1, 10.0.0.2:7000,[::1]:7001".parse().unwrap();
+        assert_eq!(
+            addrs.socket_addrs(6012),
+            vec![
+                "10.0.0.1:6012".parse::<SocketAddr>().unwrap(),
+                "10.0.0.2:7000".parse().unwrap(),
+                "[::1]:7001".parse().unwrap(),
+            ]
+        );
+        assert_eq!(addrs.ip(), "10.0.0.1".parse::<IpAddr>().unwrap());
+        assert_eq!(addrs.to_string(), "10.0.0.1,10.0.0.2:7000,[::1]:7001");
+        assert!("10.0.0.1,".parse::<CoordinatorAddrs>().is_err());
+    }
+
     #[test]
     fn preserves_connection_error_sources() {
         let err =
@@ -11,7 +11,7 @@ pub mod output;
 pub mod session;
 mod template;
 
-pub use command::{Executable, Run as RunCommand, run, run_func};
+pub use command::{Executable, NodeCommunicationKind, Run as RunCommand, run, run_func};
 pub use command::{build, build_async};
 
 const LOCALHOST: IpAddr = IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1));
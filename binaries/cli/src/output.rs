@@ -52,6 +52,27 @@ impl LogSubscription {
             }
         })
     }
+
+    /// Like [`LogSubscription::spawn_printer`], but only prints the messages
+    /// that match the given `filter`.
+    pub fn spawn_filtered_printer(
+        self,
+        print_daemon_name: bool,
+        filter: impl Fn(&LogMessage) -> bool + Send + 'static,
+    ) -> JoinHandle<()> {
+        let Self {
+            mut rx,
+            _subscribers,
+        } = self;
+        tokio::spawn(async move {
+            let _subscribers = _subscribers;
+            while let Some(log_message) = rx.recv().await {
+                if filter(&log_message) {
+                    print_log_message(log_message, false, print_daemon_name);
+                }
+            }
+        })
+    }
 }
 
 /// Subscribe to zenoh log topics for each level that passes `log_level`.
@@ -0,0 +1,264 @@
+//! Sends the changes of `dora apply` to the daemons of a running dataflow.
+//!
+//! All daemons check the changes first, so that an invalid update is rejected
+//! before any daemon applies it. If a daemon fails to apply the checked
+//! changes, the daemons that applied them already are reverted to the
+//! previous dataflow.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use dora_message::{
+    common::{DaemonId, DataflowChanges},
+    coordinator_to_daemon::ApplyDataflowChanges,
+    descriptor::{Descriptor, ResolvedNode},
+    id::NodeId,
+    tarpc,
+};
+use eyre::{ContextCompat, WrapErr, eyre};
+use uuid::Uuid;
+
+use crate::DaemonConnections;
+
+/// The `apply` request of the daemon control RPC.
+pub(crate) trait ApplyRpc {
+    async fn apply(
+        &self,
+        daemon_id: &DaemonId,
+        dataflow_id: Uuid,
+        request: ApplyDataflowChanges,
+    ) -> eyre::Result<()>;
+}
+
+impl ApplyRpc for DaemonConnections {
+    async fn apply(
+        &self,
+        daemon_id: &DaemonId,
+        dataflow_id: Uuid,
+        request: ApplyDataflowChanges,
+    ) -> eyre::Result<()> {
+        let client = self
+            .get(daemon_id)
+            .map(|c| c.client.clone())
+            .wrap_err_with(|| format!("no daemon connection to `{daemon_id}`"))?;
+        client
+            .apply(tarpc::context::current(), dataflow_id, request)
+            .await
+            .context("RPC transport error")?
+            .map_err(|e: String| eyre!(e))
+    }
+}
+
+/// The nodes of a dataflow and the daemons they run on.
+pub(crate) struct DataflowNodes {
+    pub nodes: BTreeMap<NodeId, ResolvedNode>,
+    pub descriptor: Descriptor,
+    pub node_to_daemon: BTreeMap<NodeId, DaemonId>,
+}
+
+impl DataflowNodes {
+    fn request(
+        &self,
+        daemon_id: &DaemonId,
+        changes: &DataflowChanges,
+        dry_run: bool,
+    ) -> ApplyDataflowChanges {
+        ApplyDataflowChanges {
+            nodes: self.nodes.clone(),
+            dataflow_descriptor: self.descriptor.clone(),
+            local_nodes: self
+                .node_to_daemon
+                .iter()
+                .filter(|(_, d)| *d == daemon_id)
+                .map(|(node_id, _)| node_id.clone())
+                .collect(),
+            changes: changes.clone(),
+            dry_run,
+        }
+    }
+}
+
+/// Changes the dataflow on all given daemons from `old` to `new`.
+///
+/// All daemons need the update to adjust the edges to remote nodes. On error,
+/// the dataflow runs unchanged on all daemons, as far as the rollback succeeds.
+pub(crate) async fn apply_on_daemons(
+    rpc: &impl ApplyRpc,
+    dataflow_id: Uuid,
+    daemons: &BTreeSet<DaemonId>,
+    old: &DataflowNodes,
+    new: &DataflowNodes,
+    changes: &DataflowChanges,
+) -> eyre::Result<()> {
+    for daemon_id in daemons {
+        rpc.apply(
+            daemon_id,
+            dataflow_id,
+            new.request(daemon_id, changes, true),
+        )
+        .await
+        .wrap_err_with(|| format!("cannot apply changes on daemon `{daemon_id}`"))?;
+    }
+
+    let mut applied = Vec::new();
+    for daemon_id in daemons {
+        let result = rpc
+            .apply(
+                daemon_id,
+                dataflow_id,
+                new.request(daemon_id, changes, false),
+            )
+            .await;
+        if let Err(err) = result {
+            let revert = DataflowChanges::between(&new.nodes, &old.nodes);
+            for applied_id in applied {
+                let request = old.request(applied_id, &revert, false);
+                if let Err(err) = rpc.apply(applied_id, dataflow_id, request).await {
+                    tracing::warn!(
+                        "failed to revert changes of dataflow `{dataflow_id}` on daemon \
+                        `{applied_id}`: {err:?}"
+                    );
+                }
+            }
+            return Err(err)
+                .wrap_err_with(|| format!("failed to apply changes on daemon `{daemon_id}`"));
+        }
+        applied.push(daemon_id);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::BTreeSet, sync::Mutex};
+
+    use dora_core::descriptor::{Descriptor, DescriptorExt};
+    use dora_message::{
+        common::{DaemonId, DataflowChanges},
+        coordinator_to_daemon::ApplyDataflowChanges,
+        id::NodeId,
+    };
+    use uuid::Uuid;
+
+    use super::{ApplyRpc, DataflowNodes, apply_on_daemons};
+
+    /// Records the requests and fails the requests to the given daemons.
+    #[derive(Default)]
+    struct FakeDaemons {
+        failing_checks: BTreeSet<&'static str>,
+        failing: BTreeSet<&'static str>,
+        requests: Mutex<Vec<String>>,
+    }
+
+    impl ApplyRpc for FakeDaemons {
+        async fn apply(
+            &self,
+            daemon_id: &DaemonId,
+            _dataflow_id: Uuid,
+            request: ApplyDataflowChanges,
+        ) -> eyre::Result<()> {
+            let machine_id = daemon_id.machine_id().unwrap();
+            let (kind, failing) = match request.dry_run {
+                true => ("check", &self.failing_checks),
+                false => ("apply", &self.failing),
+            };
+            let added = request.changes.added.iter().map(|n| n.to_string());
+            let removed = request.changes.removed.iter().map(|n| n.to_string());
+            self.requests.lock().unwrap().push(format!(
+                "{kind} +{} -{} on {machine_id}",
+                added.collect::<Vec<_>>().join(","),
+                removed.collect::<Vec<_>>().join(","),
+            ));
+            match failing.contains(machine_id) {
+                true => eyre::bail!("daemon `{machine_id}` failed"),
+                false => Ok(()),
+            }
+        }
+    }
+
+    /// Adds the node `logger` on daemon `b` to a dataflow that runs on the
+    /// daemons `a` and `b`.
+    fn dataflows() -> (BTreeSet<DaemonId>, DataflowNodes, DataflowNodes) {
+        let a = DaemonId::new(Some("a".to_owned()));
+        let b = DaemonId::new(Some("b".to_owned()));
+        let dataflow = |yaml: &str| {
+            let descriptor = Descriptor::parse(yaml.as_bytes().to_vec()).unwrap();
+            let nodes = descriptor.resolve_aliases_and_set_defaults().unwrap();
+            let node_to_daemon = nodes
+                .keys()
+                .map(|id| match id.as_ref() {
+                    "camera" => (id.clone(), a.clone()),
+                    _ => (id.clone(), b.clone()),
+                })
+                .collect();
+            DataflowNodes {
+                nodes,
+                descriptor,
+                node_to_daemon,
+            }
+        };
+        let old = dataflow(
+            r#"
+nodes:
+  - id: camera
+    path: shell
+    args: ./camera
+    outputs: [image]
+"#,
+        );
+        let new = dataflow(
+            r#"
+nodes:
+  - id: camera
+    path: shell
+    args: ./camera
+    outputs: [image]
+  - id: logger
+    path: shell
+    args: ./logger
+    inputs:
+      image: camera/image
+"#,
+        );
+        (BTreeSet::from([a, b]), old, new)
+    }
+
+    #[tokio::test]
+    async fn changes_are_reverted_when_a_daemon_fails() {
+        let (daemons, old, new) = dataflows();
+        let changes = DataflowChanges::between(&old.nodes, &new.nodes);
+        assert_eq!(
+            changes.added,
+            BTreeSet::from([NodeId::from("logger".to_owned())])
+        );
+
+        // failing checks don't change the dataflow on any daemon
+        let rpc = FakeDaemons {
+            failing_checks: BTreeSet::from(["b"]),
+            ..Default::default()
+        };
+        let result = apply_on_daemons(&rpc, Uuid::new_v4(), &daemons, &old, &new, &changes).await;
+        assert!(result.is_err());
+        assert_eq!(
+            rpc.requests.into_inner().unwrap(),
+            ["check +logger - on a", "check +logger - on b"]
+        );
+
+        // daemons that applied the changes already are reverted
+        let rpc = FakeDaemons {
+            failing: BTreeSet::from(["b"]),
+            ..Default::default()
+        };
+        let result = apply_on_daemons(&rpc, Uuid::new_v4(), &daemons, &old, &new, &changes).await;
+        assert!(result.is_err());
+        assert_eq!(
+            rpc.requests.into_inner().unwrap(),
+            [
+                "check +logger - on a",
+                "check +logger - on b",
+                "apply +logger - on a",
+                "apply +logger - on b",
+                "apply + -logger on a",
+            ]
+        );
+    }
+}
@@ -0,0 +1,662 @@
+//! Failover of nodes when the coordinator loses a daemon, see the `failover`
+//! field of nodes.
+//!
+//! The failover nodes of the lost daemon are placed on other daemons that
+//! match their `_unstable_deploy` settings. Daemons that run the dataflow
+//! already add the nodes through the same mechanism as `dora apply`, other
+//! daemons spawn them like on a regular start. All other nodes of the lost
+//! daemon are reported as failed, as are moved nodes that could not be
+//! started on their new daemon.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    sync::Arc,
+};
+
+use dora_core::uhlc::HLC;
+use dora_message::{
+    common::{DaemonId, DataflowChanges, NodeError, NodeErrorCause, NodeExitStatus},
+    coordinator_to_daemon::{ApplyDataflowChanges, SpawnDataflowNodes},
+    daemon_to_coordinator::DataflowDaemonResult,
+    descriptor::{Descriptor, ResolvedNode},
+    id::NodeId,
+    tarpc,
+};
+use eyre::{ContextCompat, WrapErr, eyre};
+use uuid::Uuid;
+
+use crate::{
+    DaemonConnections, apply::ApplyRpc, listener::handle_daemon_result, run::Placement,
+    state::CoordinatorState,
+};
+
+/// Moves the failover nodes of the lost daemon to other daemons and fails all
+/// other nodes of the daemon.
+///
+/// Must be called after the daemon was removed from the daemon connections.
+/// Only used for daemons that stopped sending heartbeats, daemons that exit
+/// regularly report the results of their nodes themselves.
+pub(crate) async fn handle_daemon_loss(state: Arc<CoordinatorState>, daemon_id: DaemonId) {
+    let dataflow_ids: Vec<Uuid> = state
+        .running_dataflows
+        .iter()
+        .filter(|d| d.daemons.contains(&daemon_id))
+        .map(|d| *d.key())
+        .collect();
+    for dataflow_id in dataflow_ids {
+        fail_over(&state, dataflow_id, &daemon_id).await;
+    }
+}
+
+async fn fail_over(state: &CoordinatorState, dataflow_id: Uuid, lost_daemon: &DaemonId) {
+    let mut placement = Placement::new(&state.daemon_connections, &state.running_dataflows);
+
+    let (mut node_results, update) = {
+        let Some(mut dataflow) = state.running_dataflows.get_mut(&dataflow_id) else {
+            return;
+        };
+        let FailoverPlan {
+            mut moved,
+            mut node_results,
+        } = plan_failover(
+            dataflow_id,
+            &dataflow.nodes,
+            &dataflow.node_to_daemon,
+            lost_daemon,
+            &mut placement,
+            &state.clock,
+        );
+
+        if dataflow.pending_spawn_results.remove(lost_daemon) {
+            dataflow.spawn_result.set_result(Err(eyre!(
+                "lost connection to daemon `{lost_daemon}` while spawning dataflow"
+            )));
+        }
+        dataflow.pending_daemons.remove(lost_daemon);
+
+        // the moved nodes keep the settings of the lost daemon, e.g. its
+        // working directory
+        let spawn_settings = dataflow.spawn_settings.remove(lost_daemon);
+        let update = match spawn_settings {
+            _ if moved.is_empty() => None,
+            None => {
+                tracing::error!("no spawn settings for daemon `{lost_daemon}`");
+                node_results.extend(daemon_lost(std::mem::take(&mut moved), &state.clock));
+                None
+            }
+            Some(spawn_settings) => {
+                // record the moved nodes before sending the requests, so that
+                // the dataflow is not finished while the nodes are started
+                let previous_daemons = dataflow.daemons.clone();
+                for (node_id, daemon_id) in &moved {
+                    dataflow
+                        .node_to_daemon
+                        .insert(node_id.clone(), daemon_id.clone());
+                    dataflow.daemons.insert(daemon_id.clone());
+                    dataflow
+                        .spawn_settings
+                        .entry(daemon_id.clone())
+                        .or_insert_with(|| spawn_settings.clone());
+                }
+                Some(DataflowUpdate {
+                    spawn_settings,
+                    nodes: dataflow.nodes.clone(),
+                    descriptor: dataflow.descriptor.clone(),
+                    node_to_daemon: dataflow.node_to_daemon.clone(),
+                    moved,
+                    previous_daemons,
+                })
+            }
+        };
+        (node_results, update)
+    };
+
+    if let Some(update) = update {
+        let failed = update
+            .send(&state.daemon_connections, dataflow_id, lost_daemon)
+            .await;
+        if !failed.is_empty() {
+            revert_failed_moves(state, dataflow_id, lost_daemon, &update, &failed);
+            let failed = update
+                .moved
+                .into_iter()
+                .filter(|(node_id, _)| failed.contains(node_id))
+                .collect();
+            node_results.extend(daemon_lost(failed, &state.clock));
+        }
+    }
+
+    // finishes the dataflow on the lost daemon, which archives the dataflow
+    // if no nodes were moved to other daemons
+    let result = DataflowDaemonResult {
+        timestamp: state.clock.new_timestamp(),
+        node_results,
+    };
+    handle_daemon_result(state, dataflow_id, lost_daemon.clone(), result);
+}
+
+/// Removes moved nodes that could not be started from their new daemons.
+///
+/// New daemons that run none of the moved nodes are removed from the
+/// dataflow again.
+fn revert_failed_moves(
+    state: &CoordinatorState,
+    dataflow_id: Uuid,
+    lost_daemon: &DaemonId,
+    update: &DataflowUpdate,
+    failed: &BTreeSet<NodeId>,
+) {
+    let Some(mut dataflow) = state.running_dataflows.get_mut(&dataflow_id) else {
+        return;
+    };
+    for node_id in failed {
+        dataflow
+            .node_to_daemon
+            .insert(node_id.clone(), lost_daemon.clone());
+    }
+    for daemon_id in update.new_daemons() {
+        let started = update
+            .moved
+            .iter()
+            .any(|(node_id, d)| d == daemon_id && !failed.contains(node_id));
+        if !started {
+            dataflow.daemons.remove(daemon_id);
+            dataflow.pending_daemons.remove(daemon_id);
+            dataflow.spawn_settings.remove(daemon_id);
+        }
+    }
+}
+
+fn daemon_lost(
+    nodes: BTreeMap<NodeId, DaemonId>,
+    clock: &HLC,
+) -> impl Iterator<Item = (NodeId, Result<(), NodeError>)> {
+    nodes.into_keys().map(|node_id| {
+        let error = NodeError {
+            timestamp: clock.new_timestamp(),
+            cause: NodeErrorCause::DaemonLost,
+            exit_status: NodeExitStatus::Unknown,
+        };
+        (node_id, Err(error))
+    })
+}
+
+/// The nodes of a lost daemon, split into failover nodes that are moved to
+/// other daemons and nodes that failed.
+struct FailoverPlan {
+    moved: BTreeMap<NodeId, DaemonId>,
+    node_results: BTreeMap<NodeId, Result<(), NodeError>>,
+}
+
+fn plan_failover(
+    dataflow_id: Uuid,
+    nodes: &BTreeMap<NodeId, ResolvedNode>,
+    node_to_daemon: &BTreeMap<NodeId, DaemonId>,
+    lost_daemon: &DaemonId,
+    placement: &mut Placement,
+    clock: &HLC,
+) -> FailoverPlan {
+    let mut moved = BTreeMap::new();
+    let mut failed = BTreeMap::new();
+    let lost_nodes = node_to_daemon
+        .iter()
+        .filter(|(_, daemon_id)| *daemon_id == lost_daemon)
+        .map(|(node_id, _)| node_id);
+    for node_id in lost_nodes {
+        let node = &nodes[node_id];
+        let target = if node.failover {
+            placement.place(node).map_err(|err| {
+                tracing::warn!(
+                    "cannot move node `{node_id}` of dataflow `{dataflow_id}` to \
+                    another daemon: {err}"
+                );
+            })
+        } else {
+            Err(())
+        };
+        match target {
+            Ok(daemon_id) => {
+                tracing::info!(
+                    "moving node `{node_id}` of dataflow `{dataflow_id}` from lost daemon \
+                    `{lost_daemon}` to daemon `{daemon_id}`"
+                );
+                moved.insert(node_id.clone(), daemon_id);
+            }
+            Err(()) => {
+                failed.insert(node_id.clone(), lost_daemon.clone());
+            }
+        }
+    }
+    FailoverPlan {
+        moved,
+        node_results: daemon_lost(failed, clock).collect(),
+    }
+}
+
+/// The requests that are sent to daemons on failover.
+trait FailoverRpc: ApplyRpc {
+    async fn spawn(&self, daemon_id: &DaemonId, command: SpawnDataflowNodes) -> eyre::Result<()>;
+
+    async fn stop_node(
+        &self,
+        daemon_id: &DaemonId,
+        dataflow_id: Uuid,
+        node_id: NodeId,
+    ) -> eyre::Result<()>;
+}
+
+impl FailoverRpc for DaemonConnections {
+    async fn spawn(&self, daemon_id: &DaemonId, command: SpawnDataflowNodes) -> eyre::Result<()> {
+        let client = self
+            .get(daemon_id)
+            .map(|c| c.client.clone())
+            .wrap_err_with(|| format!("no daemon connection to `{daemon_id}`"))?;
+        client
+            .spawn(tarpc::context::current(), command)
+            .await
+            .context("RPC transport error")?
+            .map_err(|e: String| eyre!(e))
+    }
+
+    async fn stop_node(
+        &self,
+        daemon_id: &DaemonId,
+        dataflow_id: Uuid,
+        node_id: NodeId,
+    ) -> eyre::Result<()> {
+        let client = self
+            .get(daemon_id)
+            .map(|c| c.client.clone())
+            .wrap_err_with(|| format!("no daemon connection to `{daemon_id}`"))?;
+        client
+            .stop_node(tarpc::context::current(), dataflow_id, node_id)
+            .await
+            .context("RPC transport error")?
+            .map_err(|e: String| eyre!(e))
+    }
+}
+
+/// The updated dataflow after moving nodes away from a lost daemon.
+struct DataflowUpdate {
+    spawn_settings: SpawnDataflowNodes,
+    nodes: BTreeMap<NodeId, ResolvedNode>,
+    descriptor: Descriptor,
+    node_to_daemon: BTreeMap<NodeId, DaemonId>,
+    /// The new daemon of each moved node.
+    moved: BTreeMap<NodeId, DaemonId>,
+    /// Daemons that ran the dataflow before the failover.
+    previous_daemons: BTreeSet<DaemonId>,
+}
+
+impl DataflowUpdate {
+    /// Daemons that did not run the dataflow before the failover.
+    fn new_daemons(&self) -> BTreeSet<&DaemonId> {
+        self.moved
+            .values()
+            .filter(|d| !self.previous_daemons.contains(*d))
+            .collect()
+    }
+
+    fn local_nodes(&self, daemon_id: &DaemonId) -> BTreeSet<NodeId> {
+        self.node_to_daemon
+            .iter()
+            .filter(|(_, d)| *d == daemon_id)
+            .map(|(node_id, _)| node_id.clone())
+            .collect()
+    }
+
+    /// Spawns the moved nodes and updates the edges to them on all daemons.
+    ///
+    /// Returns the moved nodes that could not be started. If a daemon that
+    /// runs the dataflow already fails to apply the update, its edges to the
+    /// moved nodes are not set up, so all moved nodes are stopped again.
+    async fn send(
+        &self,
+        rpc: &impl FailoverRpc,
+        dataflow_id: Uuid,
+        lost_daemon: &DaemonId,
+    ) -> BTreeSet<NodeId> {
+        // the daemons that run the dataflow already need to spawn the moved
+        // nodes or to subscribe to their outputs
+        let changes = DataflowChanges {
+            added: self.moved.keys().cloned().collect(),
+            ..Default::default()
+        };
+        let mut applied = Vec::new();
+        for daemon_id in self.previous_daemons.iter().filter(|d| *d != lost_daemon) {
+            let request = ApplyDataflowChanges {
+                nodes: self.nodes.clone(),
+                dataflow_descriptor: self.descriptor.clone(),
+                local_nodes: self.local_nodes(daemon_id),
+                changes: changes.clone(),
+                dry_run: false,
+            };
+            match rpc.apply(daemon_id, dataflow_id, request).await {
+                Ok(()) => applied.push(daemon_id),
+                Err(err) => {
+                    tracing::error!(
+                        "failed to move nodes of dataflow `{dataflow_id}`: failed to \
+                        update dataflow on daemon `{daemon_id}`: {err:?}"
+                    );
+                    self.stop_moved_nodes(rpc, dataflow_id, &applied).await;
+                    return self.moved.keys().cloned().collect();
+                }
+            }
+        }
+
+        // other daemons spawn the moved nodes like on a regular start
+        let mut failed = BTreeSet::new();
+        for daemon_id in self.new_daemons() {
+            let spawn_command = SpawnDataflowNodes {
+                // the nodes were not built on the new daemon
+                build_id: None,
+                nodes: self.nodes.clone(),
+                dataflow_descriptor: self.descriptor.clone(),
+                spawn_nodes: self.local_nodes(daemon_id),
+                ..self.spawn_settings.clone()
+            };
+            if let Err(err) = rpc.spawn(daemon_id, spawn_command).await {
+                tracing::error!(
+                    "failed to spawn moved nodes of dataflow `{dataflow_id}` on daemon \
+                    `{daemon_id}`: {err:?}"
+                );
+                failed.extend(
+                    self.moved
+                        .iter()
+                        .filter(|(_, d)| *d == daemon_id)
+                        .map(|(node_id, _)| node_id.clone()),
+                );
+            }
+        }
+        failed
+    }
+
+    /// Stops the moved nodes on the given daemons, which applied the update
+    /// already.
+    async fn stop_moved_nodes(
+        &self,
+        rpc: &impl FailoverRpc,
+        dataflow_id: Uuid,
+        daemons: &[&DaemonId],
+    ) {
+        for (node_id, daemon_id) in &self.moved {
+            if !daemons.contains(&daemon_id) {
+                continue;
+            }
+            if let Err(err) = rpc.stop_node(daemon_id, dataflow_id, node_id.clone()).await {
+                tracing::warn!(
+                    "failed to stop moved node `{node_id}` on daemon `{daemon_id}`: {err:?}"
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use dora_core::{
+        descriptor::{Descriptor, DescriptorExt},
+        uhlc::HLC,
+    };
+    use dora_message::{
+        SessionId,
+        common::{DaemonId, NodeErrorCause},
+        coordinator_to_daemon::{ApplyDataflowChanges, SpawnDataflowNodes},
+        daemon_to_coordinator::DaemonPlacement,
+        descriptor::Capacity,
+        id::NodeId,
+    };
+    use std::{collections::BTreeSet, sync::Mutex};
+    use uuid::Uuid;
+
+    use super::{DataflowUpdate, FailoverRpc, plan_failover};
+    use crate::{apply::ApplyRpc, run::Placement};
+
+    const DATAFLOW: &str = r#"
+nodes:
+  - id: detector
+    path: shell
+    args: ./detector
+    failover: true
+    _unstable_deploy:
+      selector:
+        gpu: "true"
+  - id: camera
+    path: shell
+    args: ./camera
+    _unstable_deploy:
+      selector:
+        gpu: "true"
+  - id: logger
+    path: shell
+    args: ./logger
+"#;
+
+    fn daemon(machine_id: &str, labels: &[(&str, &str)]) -> (DaemonId, DaemonPlacement, Capacity) {
+        let placement = DaemonPlacement {
+            labels: labels
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            capacity: Capacity::default(),
+        };
+        (
+            DaemonId::new(Some(machine_id.to_owned())),
+            placement,
+            Capacity::default(),
+        )
+    }
+
+    fn node_id(id: &str) -> NodeId {
+        NodeId::from(id.to_owned())
+    }
+
+    #[test]
+    fn failover_nodes_are_moved_and_other_nodes_fail() {
+        let nodes = Descriptor::parse(DATAFLOW.as_bytes().to_vec())
+            .unwrap()
+            .resolve_aliases_and_set_defaults()
+            .unwrap();
+        let lost = DaemonId::new(Some("a".to_owned()));
+        let other = DaemonId::new(None);
+        let node_to_daemon = BTreeMap::from([
+            (node_id("detector"), lost.clone()),
+            (node_id("camera"), lost.clone()),
+            (node_id("logger"), other.clone()),
+        ]);
+        // the lost daemon `a` is not connected anymore
+        let mut placement = Placement::from_daemons(vec![
+            daemon("b", &[("gpu", "false")]),
+            daemon("c", &[("gpu", "true")]),
+        ]);
+
+        let plan = plan_failover(
+            Uuid::new_v4(),
+            &nodes,
+            &node_to_daemon,
+            &lost,
+            &mut placement,
+            &HLC::default(),
+        );
+
+        let moved = plan
+            .moved
+            .iter()
+            .map(|(node_id, daemon_id)| (node_id.to_string(), daemon_id.machine_id()))
+            .collect::<Vec<_>>();
+        assert_eq!(moved, [("detector".to_owned(), Some("c"))]);
+        // `camera` matches daemon `c` too, but it is not a failover node
+        assert_eq!(plan.node_results.len(), 1);
+        let error = plan.node_results[&node_id("camera")].as_ref().unwrap_err();
+        assert!(matches!(error.cause, NodeErrorCause::DaemonLost));
+    }
+
+    #[test]
+    fn failover_nodes_without_matching_daemon_fail() {
+        let nodes = Descriptor::parse(DATAFLOW.as_bytes().to_vec())
+            .unwrap()
+            .resolve_aliases_and_set_defaults()
+            .unwrap();
+        let lost = DaemonId::new(Some("a".to_owned()));
+        let node_to_daemon = BTreeMap::from([
+            (node_id("detector"), lost.clone()),
+            (node_id("camera"), DaemonId::new(Some("c".to_owned()))),
+            (node_id("logger"), DaemonId::new(None)),
+        ]);
+        let mut placement = Placement::from_daemons(vec![daemon("b", &[("gpu", "false")])]);
+
+        let plan = plan_failover(
+            Uuid::new_v4(),
+            &nodes,
+            &node_to_daemon,
+            &lost,
+            &mut placement,
+            &HLC::default(),
+        );
+
+        assert!(plan.moved.is_empty());
+        assert_eq!(plan.node_results.len(), 1);
+        let error = plan.node_results[&node_id("detector")]
+            .as_ref()
+            .unwrap_err();
+        assert!(matches!(error.cause, NodeErrorCause::DaemonLost));
+    }
+
+    /// Records the requests and fails all requests to the given daemons.
+    #[derive(Default)]
+    struct FakeDaemons {
+        failing: BTreeSet<&'static str>,
+        requests: Mutex<Vec<String>>,
+    }
+
+    impl FakeDaemons {
+        fn request(&self, daemon_id: &DaemonId, request: String) -> eyre::Result<()> {
+            let machine_id = daemon_id.machine_id().unwrap();
+            self.requests
+                .lock()
+                .unwrap()
+                .push(format!("{request} on {machine_id}"));
+            match self.failing.contains(machine_id) {
+                true => eyre::bail!("daemon `{machine_id}` failed"),
+                false => Ok(()),
+            }
+        }
+    }
+
+    impl ApplyRpc for FakeDaemons {
+        async fn apply(
+            &self,
+            daemon_id: &DaemonId,
+            _dataflow_id: Uuid,
+            request: ApplyDataflowChanges,
+        ) -> eyre::Result<()> {
+            let local_nodes = request.local_nodes.iter().map(|n| n.to_string());
+            let request = format!("apply {}", local_nodes.collect::<Vec<_>>().join(","));
+            self.request(daemon_id, request)
+        }
+    }
+
+    impl FailoverRpc for FakeDaemons {
+        async fn spawn(
+            &self,
+            daemon_id: &DaemonId,
+            command: SpawnDataflowNodes,
+        ) -> eyre::Result<()> {
+            let nodes = command.spawn_nodes.iter().map(|n| n.to_string());
+            let request = format!("spawn {}", nodes.collect::<Vec<_>>().join(","));
+            self.request(daemon_id, request)
+        }
+
+        async fn stop_node(
+            &self,
+            daemon_id: &DaemonId,
+            _dataflow_id: Uuid,
+            node_id: NodeId,
+        ) -> eyre::Result<()> {
+            self.request(daemon_id, format!("stop {node_id}"))
+        }
+    }
+
+    /// Moves `detector` to the new daemon `c` and `camera` to daemon `b`,
+    /// which runs the dataflow already.
+    fn update(dataflow_id: Uuid, lost: &DaemonId) -> DataflowUpdate {
+        let descriptor = Descriptor::parse(DATAFLOW.as_bytes().to_vec()).unwrap();
+        let nodes = descriptor.resolve_aliases_and_set_defaults().unwrap();
+        let b = DaemonId::new(Some("b".to_owned()));
+        let c = DaemonId::new(Some("c".to_owned()));
+        let moved = BTreeMap::from([(node_id("detector"), c), (node_id("camera"), b.clone())]);
+        let mut node_to_daemon = moved.clone();
+        node_to_daemon.insert(node_id("logger"), b.clone());
+        DataflowUpdate {
+            spawn_settings: SpawnDataflowNodes {
+                build_id: None,
+                session_id: SessionId::generate(),
+                dataflow_id,
+                local_working_dir: None,
+                nodes: nodes.clone(),
+                dataflow_descriptor: descriptor.clone(),
+                spawn_nodes: BTreeSet::new(),
+                uv: false,
+                write_events_to: None,
+                hot_reload: false,
+                dataflow_path: None,
+            },
+            nodes,
+            descriptor,
+            node_to_daemon,
+            moved,
+            previous_daemons: BTreeSet::from([lost.clone(), b]),
+        }
+    }
+
+    #[tokio::test]
+    async fn nodes_fail_when_spawn_on_new_daemon_fails() {
+        let dataflow_id = Uuid::new_v4();
+        let lost = DaemonId::new(Some("a".to_owned()));
+        let daemons = FakeDaemons {
+            failing: BTreeSet::from(["c"]),
+            ..Default::default()
+        };
+
+        let failed = update(dataflow_id, &lost)
+            .send(&daemons, dataflow_id, &lost)
+            .await;
+
+        assert_eq!(failed, BTreeSet::from([node_id("detector")]));
+        assert_eq!(
+            daemons.requests.into_inner().unwrap(),
+            ["apply camera,logger on b", "spawn detector on c"]
+        );
+    }
+
+    #[tokio::test]
+    async fn all_moved_nodes_fail_when_apply_fails() {
+        let dataflow_id = Uuid::new_v4();
+        let lost = DaemonId::new(Some("a".to_owned()));
+        let d = DaemonId::new(Some("d".to_owned()));
+        let mut update = update(dataflow_id, &lost);
+        update.previous_daemons.insert(d.clone());
+        let daemons = FakeDaemons {
+            failing: BTreeSet::from(["d"]),
+            ..Default::default()
+        };
+
+        let failed = update.send(&daemons, dataflow_id, &lost).await;
+
+        // `camera` was started on `b` already, so it is stopped again
+        assert_eq!(
+            failed,
+            BTreeSet::from([node_id("camera"), node_id("detector")])
+        );
+        assert_eq!(
+            daemons.requests.into_inner().unwrap(),
+            [
+                "apply camera,logger on b",
+                "apply  on d",
+                "stop camera on b"
+            ]
+        );
+    }
+}
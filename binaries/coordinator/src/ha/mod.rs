@@ -0,0 +1,252 @@
+//! High-availability mode, in which multiple coordinators replicate their
+//! state through Raft.
+//!
+//! Only the elected leader runs the actual coordinator. It periodically
+//! proposes a snapshot of its [`ReplicatedState`] to the cluster. After a
+//! leader change, the new leader restores the latest committed snapshot
+//! before it starts listening for daemons and clients.
+
+use std::{
+    collections::BTreeMap,
+    net::SocketAddr,
+    time::{Duration, Instant},
+};
+
+use eyre::{Context, bail};
+use futures::{Future, Stream};
+use tokio::{
+    net::{TcpListener, TcpStream},
+    sync::{mpsc, watch},
+};
+
+use self::raft::{Message, PeerId, Raft};
+pub(crate) use self::state::ReplicatedState;
+use crate::{
+    Event,
+    tcp_utils::{tcp_receive, tcp_send},
+};
+
+mod raft;
+mod state;
+
+const TICK_INTERVAL: Duration = Duration::from_millis(50);
+/// Interval in which the leader replicates changes of its state.
+const SYNC_INTERVAL: Duration = Duration::from_millis(250);
+const PEER_CONNECT_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Configuration of a coordinator that is a member of a high-availability
+/// cluster.
+#[derive(Debug, Clone)]
+pub struct HaConfig {
+    /// Address to bind the Raft endpoint of this coordinator to.
+    ///
+    /// Also identifies this coordinator, so it must match the address that
+    /// the other members are configured with.
+    pub addr: SocketAddr,
+    /// Raft endpoints of all members of the cluster.
+    ///
+    /// May include [`Self::addr`]. The cluster should consist of three or
+    /// more members, it is available while the majority is reachable.
+    pub peers: Vec<SocketAddr>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct RaftStatus {
+    ready_leader: bool,
+    term: u64,
+    leader: Option<PeerId>,
+}
+
+/// Runs the Raft member in the background and waits for leadership before
+/// starting the coordinator.
+pub(crate) async fn start(
+    bind: SocketAddr,
+    bind_control: SocketAddr,
+    config: HaConfig,
+    external_events: impl Stream<Item = Event> + Unpin,
+) -> eyre::Result<impl Future<Output = eyre::Result<()>>> {
+    let listener = TcpListener::bind(config.addr)
+        .await
+        .wrap_err_with(|| format!("failed to bind Raft endpoint to `{}`", config.addr))?;
+    let (incoming_tx, incoming_rx) = mpsc::channel(100);
+    tokio::spawn(accept_peers(listener, incoming_tx));
+
+    let (status_tx, mut status) = watch::channel(RaftStatus::default());
+    let (committed_tx, committed) = watch::channel(None);
+    let (proposals_tx, proposals_rx) = mpsc::channel(10);
+    let raft = Raft::new(config.addr, config.peers.clone(), Instant::now());
+    tokio::spawn(run_raft(
+        raft,
+        config.addr,
+        incoming_rx,
+        proposals_rx,
+        status_tx,
+        committed_tx,
+    ));
+
+    Ok(async move {
+        tracing::info!("waiting for election as Raft leader");
+        let term = status
+            .wait_for(|s| s.ready_leader)
+            .await
+            .wrap_err("Raft task stopped")?
+            .term;
+        let restored = committed
+            .borrow()
+            .as_deref()
+            .map(serde_json::from_str::<ReplicatedState>)
+            .transpose()
+            .wrap_err("failed to deserialize replicated coordinator state")?;
+        tracing::info!("became coordinator leader in Raft term {term}, starting coordinator");
+
+        let (port, coordinator_state, coordinator) =
+            crate::start_with_state(bind, bind_control, external_events, restored).await?;
+        tracing::info!("Listening for incoming daemon connection on {port}");
+
+        let sync = async {
+            let mut last_proposed = None;
+            let mut interval = tokio::time::interval(SYNC_INTERVAL);
+            loop {
+                interval.tick().await;
+                let snapshot = serde_json::to_string(&ReplicatedState::capture(&coordinator_state))
+                    .wrap_err("failed to serialize coordinator state")?;
+                if last_proposed.as_ref() != Some(&snapshot) {
+                    proposals_tx
+                        .send(snapshot.clone())
+                        .await
+                        .wrap_err("Raft task stopped")?;
+                    last_proposed = Some(snapshot);
+                }
+            }
+        };
+        let leadership_lost = async {
+            status
+                .wait_for(|s| !s.ready_leader || s.term != term)
+                .await
+                .wrap_err("Raft task stopped")?;
+            bail!("lost Raft leadership in term {term}")
+        };
+
+        tokio::select! {
+            result = coordinator => result,
+            result = sync => result,
+            result = leadership_lost => result,
+        }
+    })
+}
+
+async fn run_raft(
+    mut raft: Raft,
+    id: PeerId,
+    mut incoming: mpsc::Receiver<(PeerId, Message)>,
+    mut proposals: mpsc::Receiver<String>,
+    status: watch::Sender<RaftStatus>,
+    committed: watch::Sender<Option<String>>,
+) {
+    let mut peers: BTreeMap<PeerId, mpsc::Sender<Message>> = BTreeMap::new();
+    let mut interval = tokio::time::interval(TICK_INTERVAL);
+    loop {
+        tokio::select! {
+            _ = interval.tick() => raft.tick(Instant::now()),
+            Some((from, message)) = incoming.recv() => raft.handle(from, message, Instant::now()),
+            Some(data) = proposals.recv() => {
+                if let Err(err) = raft.propose(data) {
+                    tracing::warn!("failed to replicate coordinator state: {err}");
+                }
+            }
+            else => break,
+        }
+
+        for (to, message) in raft.take_messages() {
+            let sender = peers
+                .entry(to)
+                .or_insert_with(|| spawn_peer_connection(id, to));
+            // messages are dropped if the peer is unreachable, Raft resends
+            // them as needed
+            let _ = sender.try_send(message);
+        }
+        // publish the state before the status so that a new leader sees the
+        // latest state
+        if let Some(state) = raft.take_committed_state() {
+            committed.send_replace(state);
+        }
+        status.send_if_modified(|status| {
+            let new = RaftStatus {
+                ready_leader: raft.is_ready_leader(),
+                term: raft.term(),
+                leader: raft.leader(),
+            };
+            if new.leader != status.leader {
+                if let Some(leader) = new.leader.filter(|l| *l != id) {
+                    tracing::info!("following Raft leader `{leader}` in term {}", new.term);
+                }
+            }
+            let modified = *status != new;
+            *status = new;
+            modified
+        });
+    }
+}
+
+async fn accept_peers(listener: TcpListener, incoming: mpsc::Sender<(PeerId, Message)>) {
+    loop {
+        match listener.accept().await {
+            Ok((connection, _)) => {
+                tokio::spawn(receive_from_peer(connection, incoming.clone()));
+            }
+            Err(err) => tracing::warn!("failed to accept Raft connection: {err}"),
+        }
+    }
+}
+
+async fn receive_from_peer(mut connection: TcpStream, incoming: mpsc::Sender<(PeerId, Message)>) {
+    while let Ok(raw) = tcp_receive(&mut connection).await {
+        match serde_json::from_slice(&raw) {
+            Ok(message) => {
+                if incoming.send(message).await.is_err() {
+                    break;
+                }
+            }
+            Err(err) => {
+                tracing::warn!("failed to deserialize Raft message: {err}");
+                break;
+            }
+        }
+    }
+}
+
+/// Spawns a task that sends messages to the given peer, connecting lazily.
+fn spawn_peer_connection(id: PeerId, peer: PeerId) -> mpsc::Sender<Message> {
+    let (tx, mut rx) = mpsc::channel::<Message>(64);
+    tokio::spawn(async move {
+        let mut connection = None;
+        while let Some(message) = rx.recv().await {
+            let stream = match &mut connection {
+                Some(stream) => stream,
+                None => {
+                    let connect =
+                        tokio::time::timeout(PEER_CONNECT_TIMEOUT, TcpStream::connect(peer));
+                    match connect.await {
+                        Ok(Ok(stream)) => {
+                            let _ = stream.set_nodelay(true);
+                            connection.insert(stream)
+                        }
+                        _ => continue,
+                    }
+                }
+            };
+            let raw = match serde_json::to_vec(&(id, message)) {
+                Ok(raw) => raw,
+                Err(err) => {
+                    tracing::warn!("failed to serialize Raft message: {err}");
+                    continue;
+                }
+            };
+            if let Err(err) = tcp_send(stream, &raw).await {
+                tracing::debug!("lost Raft connection to `{peer}`: {err}");
+                connection = None;
+            }
+        }
+    });
+    tx
+}
@@ -0,0 +1,733 @@
+//! A minimal implementation of the Raft consensus algorithm.
+//!
+//! The implementation does not perform any I/O. Messages for other members of
+//! the cluster are collected in an outbox and incoming messages are passed to
+//! [`Raft::handle`]. Time is passed in explicitly to make the algorithm
+//! testable.
+//!
+//! Every log entry carries a full snapshot of the replicated state, so the log
+//! is compacted to the latest applied entry. Followers that are behind the
+//! compacted log receive that snapshot instead of the missing entries.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    hash::{BuildHasher, RandomState},
+    net::SocketAddr,
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+
+/// Members of the cluster are identified by the address of their Raft endpoint.
+pub(crate) type PeerId = SocketAddr;
+
+pub(crate) const HEARTBEAT_INTERVAL: Duration = Duration::from_millis(300);
+const ELECTION_TIMEOUT_MIN: Duration = Duration::from_millis(1500);
+const ELECTION_TIMEOUT_JITTER: Duration = Duration::from_millis(1500);
+/// Maximum number of entries sent in a single append message.
+const MAX_ENTRIES_PER_MESSAGE: usize = 16;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct Entry {
+    pub term: u64,
+    /// The replicated state, `None` for the empty entry that every leader
+    /// appends at the start of its term.
+    pub data: Option<String>,
+}
+
+/// The compacted part of the log.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct Snapshot {
+    /// Index of the last entry that is contained in the snapshot.
+    pub index: u64,
+    /// Term of the last entry that is contained in the snapshot.
+    pub term: u64,
+    /// The latest state up to the last entry.
+    pub data: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) enum Message {
+    RequestVote {
+        term: u64,
+        last_log_index: u64,
+        last_log_term: u64,
+    },
+    Vote {
+        term: u64,
+        granted: bool,
+    },
+    AppendEntries {
+        term: u64,
+        prev_log_index: u64,
+        prev_log_term: u64,
+        entries: Vec<Entry>,
+        leader_commit: u64,
+    },
+    InstallSnapshot {
+        term: u64,
+        snapshot: Snapshot,
+    },
+    AppendResult {
+        term: u64,
+        success: bool,
+        /// The last index that matches the leader's log on success, the last
+        /// index of the log otherwise.
+        match_index: u64,
+    },
+}
+
+impl Message {
+    fn term(&self) -> u64 {
+        match self {
+            Message::RequestVote { term, .. }
+            | Message::Vote { term, .. }
+            | Message::AppendEntries { term, .. }
+            | Message::InstallSnapshot { term, .. }
+            | Message::AppendResult { term, .. } => *term,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Role {
+    Follower,
+    Candidate,
+    Leader,
+}
+
+pub(crate) struct Raft {
+    id: PeerId,
+    peers: Vec<PeerId>,
+    role: Role,
+    term: u64,
+    voted_for: Option<PeerId>,
+    leader: Option<PeerId>,
+    votes: BTreeSet<PeerId>,
+
+    snapshot: Snapshot,
+    /// Entries after the snapshot.
+    log: Vec<Entry>,
+    commit_index: u64,
+    /// Index of the first entry of the current term, set on leaders.
+    term_start: u64,
+    /// Set when a new state was committed that was not returned by
+    /// [`Raft::take_committed_state`] yet.
+    state_changed: bool,
+
+    next_index: BTreeMap<PeerId, u64>,
+    match_index: BTreeMap<PeerId, u64>,
+    /// Last time that each follower responded to the leader.
+    last_contact: BTreeMap<PeerId, Instant>,
+
+    election_deadline: Instant,
+    heartbeat_deadline: Instant,
+    outbox: Vec<(PeerId, Message)>,
+}
+
+impl Raft {
+    pub fn new(id: PeerId, peers: Vec<PeerId>, now: Instant) -> Self {
+        Self {
+            id,
+            peers: peers.into_iter().filter(|p| *p != id).collect(),
+            role: Role::Follower,
+            term: 0,
+            voted_for: None,
+            leader: None,
+            votes: BTreeSet::new(),
+            snapshot: Snapshot::default(),
+            log: Vec::new(),
+            commit_index: 0,
+            term_start: 0,
+            state_changed: false,
+            next_index: BTreeMap::new(),
+            match_index: BTreeMap::new(),
+            last_contact: BTreeMap::new(),
+            election_deadline: now + election_timeout(),
+            heartbeat_deadline: now,
+            outbox: Vec::new(),
+        }
+    }
+
+    #[cfg(test)]
+    pub fn role(&self) -> Role {
+        self.role
+    }
+
+    pub fn term(&self) -> u64 {
+        self.term
+    }
+
+    pub fn leader(&self) -> Option<PeerId> {
+        self.leader
+    }
+
+    /// Whether this member is the leader and committed an entry of its term.
+    ///
+    /// At this point, the leader knows the latest committed state.
+    pub fn is_ready_leader(&self) -> bool {
+        self.role == Role::Leader && self.commit_index >= self.term_start
+    }
+
+    /// Returns the messages for other members that were created since the
+    /// last call.
+    pub fn take_messages(&mut self) -> Vec<(PeerId, Message)> {
+        std::mem::take(&mut self.outbox)
+    }
+
+    /// Returns the latest committed state if it changed since the last call.
+    pub fn take_committed_state(&mut self) -> Option<Option<String>> {
+        self.compact();
+        std::mem::take(&mut self.state_changed).then(|| self.snapshot.data.clone())
+    }
+
+    /// Appends the given state to the log, only possible on the leader.
+    pub fn propose(&mut self, data: String) -> Result<u64, NotLeader> {
+        if self.role != Role::Leader {
+            return Err(NotLeader {
+                leader: self.leader,
+            });
+        }
+        self.log.push(Entry {
+            term: self.term,
+            data: Some(data),
+        });
+        self.advance_commit_index();
+        for peer in self.peers.clone() {
+            self.send_append(peer);
+        }
+        Ok(self.last_index())
+    }
+
+    pub fn tick(&mut self, now: Instant) {
+        match self.role {
+            Role::Leader => {
+                // step down if the majority is unreachable, so that a
+                // partitioned leader doesn't keep serving requests
+                let reachable = self
+                    .last_contact
+                    .values()
+                    .filter(|contact| now.duration_since(**contact) < ELECTION_TIMEOUT_MIN)
+                    .count();
+                if reachable + 1 < self.quorum() {
+                    tracing::warn!("lost contact to the majority of the Raft cluster");
+                    self.become_follower(self.term, None, now);
+                    self.leader = None;
+                    return;
+                }
+                if now >= self.heartbeat_deadline {
+                    self.heartbeat_deadline = now + HEARTBEAT_INTERVAL;
+                    for peer in self.peers.clone() {
+                        self.send_append(peer);
+                    }
+                }
+            }
+            Role::Follower | Role::Candidate => {
+                if now >= self.election_deadline {
+                    self.start_election(now);
+                }
+            }
+        }
+    }
+
+    pub fn handle(&mut self, from: PeerId, message: Message, now: Instant) {
+        if !self.peers.contains(&from) {
+            tracing::warn!("ignoring Raft message from unknown peer `{from}`");
+            return;
+        }
+        if message.term() > self.term {
+            self.become_follower(message.term(), None, now);
+        }
+        match message {
+            Message::RequestVote {
+                term,
+                last_log_index,
+                last_log_term,
+            } => {
+                let up_to_date =
+                    (last_log_term, last_log_index) >= (self.last_term(), self.last_index());
+                let granted =
+                    term == self.term && self.voted_for.is_none_or(|v| v == from) && up_to_date;
+                if granted {
+                    self.voted_for = Some(from);
+                    self.election_deadline = now + election_timeout();
+                }
+                self.send(
+                    from,
+                    Message::Vote {
+                        term: self.term,
+                        granted,
+                    },
+                );
+            }
+            Message::Vote { term, granted } => {
+                if self.role == Role::Candidate && term == self.term && granted {
+                    self.votes.insert(from);
+                    if self.votes.len() >= self.quorum() {
+                        self.become_leader(now);
+                    }
+                }
+            }
+            Message::AppendEntries {
+                term,
+                prev_log_index,
+                prev_log_term,
+                entries,
+                leader_commit,
+            } => {
+                if term < self.term {
+                    self.reject_append(from);
+                    return;
+                }
+                self.become_follower(term, Some(from), now);
+                self.append_entries(from, prev_log_index, prev_log_term, entries, leader_commit);
+            }
+            Message::InstallSnapshot { term, snapshot } => {
+                if term < self.term {
+                    self.reject_append(from);
+                    return;
+                }
+                self.become_follower(term, Some(from), now);
+                self.install_snapshot(from, snapshot);
+            }
+            Message::AppendResult {
+                term,
+                success,
+                match_index,
+            } => {
+                if self.role != Role::Leader || term != self.term {
+                    return;
+                }
+                self.last_contact.insert(from, now);
+                if success {
+                    let matched = self.match_index.entry(from).or_default();
+                    *matched = (*matched).max(match_index);
+                    self.next_index.insert(from, *matched + 1);
+                    self.advance_commit_index();
+                } else {
+                    // retry with an earlier entry, the follower reports the
+                    // end of its log to skip over missing entries
+                    let next = self.next_index.get(&from).copied().unwrap_or(1);
+                    let next = next.saturating_sub(1).min(match_index + 1).max(1);
+                    self.next_index.insert(from, next);
+                    self.send_append(from);
+                }
+            }
+        }
+    }
+
+    fn append_entries(
+        &mut self,
+        leader: PeerId,
+        prev_log_index: u64,
+        prev_log_term: u64,
+        entries: Vec<Entry>,
+        leader_commit: u64,
+    ) {
+        // entries before the snapshot are committed, so they match already
+        let skip = self.snapshot.index.saturating_sub(prev_log_index);
+        let (prev_log_index, prev_log_term, entries) = if skip == 0 {
+            (prev_log_index, prev_log_term, entries)
+        } else if let Some(skipped) = entries.get(skip as usize - 1) {
+            let term = skipped.term;
+            let entries = entries.into_iter().skip(skip as usize).collect();
+            (self.snapshot.index, term, entries)
+        } else {
+            (self.snapshot.index, self.snapshot.term, Vec::new())
+        };
+        if self.term_at(prev_log_index) != Some(prev_log_term) {
+            self.reject_append(leader);
+            return;
+        }
+
+        let mut index = prev_log_index;
+        for entry in entries {
+            index += 1;
+            match self.term_at(index) {
+                Some(term) if term == entry.term => {}
+                Some(_) => {
+                    // conflicting uncommitted entries are replaced
+                    self.log
+                        .truncate((index - self.snapshot.index - 1) as usize);
+                    self.log.push(entry);
+                }
+                None => self.log.push(entry),
+            }
+        }
+        if leader_commit > self.commit_index {
+            let commit_index = leader_commit.min(index);
+            if commit_index > self.commit_index {
+                self.commit_index = commit_index;
+                self.state_changed = true;
+            }
+        }
+        self.send(
+            leader,
+            Message::AppendResult {
+                term: self.term,
+                success: true,
+                match_index: index,
+            },
+        );
+    }
+
+    fn install_snapshot(&mut self, leader: PeerId, snapshot: Snapshot) {
+        if snapshot.index > self.snapshot.index {
+            if self.term_at(snapshot.index) == Some(snapshot.term) {
+                // keep the entries after the snapshot
+                let keep_from = (snapshot.index - self.snapshot.index) as usize;
+                self.log.drain(..keep_from);
+            } else {
+                self.log.clear();
+            }
+            self.commit_index = self.commit_index.max(snapshot.index);
+            self.snapshot = snapshot;
+            self.state_changed = true;
+        }
+        self.send(
+            leader,
+            Message::AppendResult {
+                term: self.term,
+                success: true,
+                match_index: self.snapshot.index,
+            },
+        );
+    }
+
+    fn reject_append(&mut self, to: PeerId) {
+        self.send(
+            to,
+            Message::AppendResult {
+                term: self.term,
+                success: false,
+                match_index: self.last_index(),
+            },
+        );
+    }
+
+    fn start_election(&mut self, now: Instant) {
+        self.term += 1;
+        self.role = Role::Candidate;
+        self.leader = None;
+        self.voted_for = Some(self.id);
+        self.votes = BTreeSet::from([self.id]);
+        self.election_deadline = now + election_timeout();
+        tracing::info!("starting Raft election for term {}", self.term);
+        if self.votes.len() >= self.quorum() {
+            self.become_leader(now);
+            return;
+        }
+        for peer in self.peers.clone() {
+            self.send(
+                peer,
+                Message::RequestVote {
+                    term: self.term,
+                    last_log_index: self.last_index(),
+                    last_log_term: self.last_term(),
+                },
+            );
+        }
+    }
+
+    fn become_follower(&mut self, term: u64, leader: Option<PeerId>, now: Instant) {
+        if term > self.term {
+            self.term = term;
+            self.voted_for = None;
+            self.leader = None;
+        }
+        if self.role == Role::Leader {
+            tracing::warn!("stepping down as Raft leader in term {}", self.term);
+        }
+        self.role = Role::Follower;
+        if leader.is_some() {
+            self.leader = leader;
+        }
+        self.election_deadline = now + election_timeout();
+    }
+
+    fn become_leader(&mut self, now: Instant) {
+        tracing::info!("elected as Raft leader for term {}", self.term);
+        self.role = Role::Leader;
+        self.leader = Some(self.id);
+        let next = self.last_index() + 1;
+        self.next_index = self.peers.iter().map(|p| (*p, next)).collect();
+        self.match_index = self.peers.iter().map(|p| (*p, 0)).collect();
+        self.last_contact = self.peers.iter().map(|p| (*p, now)).collect();
+        // entries of previous terms are only committed together with an
+        // entry of the current term
+        self.log.push(Entry {
+            term: self.term,
+            data: None,
+        });
+        self.term_start = self.last_index();
+        self.advance_commit_index();
+        self.heartbeat_deadline = now + HEARTBEAT_INTERVAL;
+        for peer in self.peers.clone() {
+            self.send_append(peer);
+        }
+    }
+
+    fn send_append(&mut self, peer: PeerId) {
+        let next = self
+            .next_index
+            .get(&peer)
+            .copied()
+            .unwrap_or(self.last_index() + 1);
+        let message = if next <= self.snapshot.index {
+            Message::InstallSnapshot {
+                term: self.term,
+                snapshot: self.snapshot.clone(),
+            }
+        } else {
+            let prev_log_index = next - 1;
+            let start = (next - self.snapshot.index - 1) as usize;
+            Message::AppendEntries {
+                term: self.term,
+                prev_log_index,
+                prev_log_term: self.term_at(prev_log_index).unwrap_or_default(),
+                entries: self
+                    .log
+                    .iter()
+                    .skip(start)
+                    .take(MAX_ENTRIES_PER_MESSAGE)
+                    .cloned()
+                    .collect(),
+                leader_commit: self.commit_index,
+            }
+        };
+        self.send(peer, message);
+    }
+
+    fn advance_commit_index(&mut self) {
+        let mut matched: Vec<u64> = self.match_index.values().copied().collect();
+        matched.push(self.last_index());
+        matched.sort_unstable_by(|a, b| b.cmp(a));
+        let majority_index = matched[self.quorum() - 1];
+        // only entries of the current term are committed by counting replicas
+        if majority_index > self.commit_index && self.term_at(majority_index) == Some(self.term) {
+            self.commit_index = majority_index;
+            self.state_changed = true;
+        }
+    }
+
+    /// Moves the committed entries into the snapshot.
+    fn compact(&mut self) {
+        if self.commit_index <= self.snapshot.index {
+            return;
+        }
+        let compacted = (self.commit_index - self.snapshot.index) as usize;
+        let term = self.log[compacted - 1].term;
+        let data = self
+            .log
+            .drain(..compacted)
+            .filter_map(|e| e.data)
+            .next_back();
+        self.snapshot.index = self.commit_index;
+        self.snapshot.term = term;
+        if data.is_some() {
+            self.snapshot.data = data;
+        }
+    }
+
+    fn send(&mut self, to: PeerId, message: Message) {
+        self.outbox.push((to, message));
+    }
+
+    fn quorum(&self) -> usize {
+        let members = self.peers.len() + 1;
+        members / 2 + 1
+    }
+
+    fn last_index(&self) -> u64 {
+        self.snapshot.index + self.log.len() as u64
+    }
+
+    fn last_term(&self) -> u64 {
+        self.log
+            .last()
+            .map(|e| e.term)
+            .unwrap_or(self.snapshot.term)
+    }
+
+    fn term_at(&self, index: u64) -> Option<u64> {
+        if index == self.snapshot.index {
+            Some(self.snapshot.term)
+        } else if index < self.snapshot.index {
+            None
+        } else {
+            self.log
+                .get((index - self.snapshot.index - 1) as usize)
+                .map(|e| e.term)
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct NotLeader {
+    pub leader: Option<PeerId>,
+}
+
+impl std::fmt::Display for NotLeader {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.leader {
+            Some(leader) => write!(f, "not the Raft leader (leader is `{leader}`)"),
+            None => write!(f, "not the Raft leader (no leader elected)"),
+        }
+    }
+}
+
+/// Randomized election timeout, so that members don't start elections at the
+/// same time.
+fn election_timeout() -> Duration {
+    let random = RandomState::new().hash_one(Instant::now());
+    ELECTION_TIMEOUT_MIN + ELECTION_TIMEOUT_JITTER.mul_f64((random % 1000) as f64 / 1000.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::{BTreeMap, BTreeSet},
+        net::SocketAddr,
+        time::{Duration, Instant},
+    };
+
+    use super::{PeerId, Raft, Role};
+
+    struct Cluster {
+        members: BTreeMap<PeerId, Raft>,
+        stopped: BTreeSet<PeerId>,
+        now: Instant,
+    }
+
+    impl Cluster {
+        fn new(size: u16) -> Self {
+            let ids: Vec<PeerId> = (0..size)
+                .map(|i| SocketAddr::from(([127, 0, 0, 1], 7000 + i)))
+                .collect();
+            let now = Instant::now();
+            let members = ids
+                .iter()
+                .map(|id| (*id, Raft::new(*id, ids.clone(), now)))
+                .collect();
+            Self {
+                members,
+                stopped: BTreeSet::new(),
+                now,
+            }
+        }
+
+        /// Advances the time in small steps and delivers all messages.
+        fn run(&mut self, duration: Duration) {
+            let end = self.now + duration;
+            while self.now < end {
+                self.now += Duration::from_millis(50);
+                for (id, member) in &mut self.members {
+                    if !self.stopped.contains(id) {
+                        member.tick(self.now);
+                    }
+                }
+                self.deliver();
+            }
+        }
+
+        fn deliver(&mut self) {
+            loop {
+                let mut messages = Vec::new();
+                for (id, member) in &mut self.members {
+                    for (to, message) in member.take_messages() {
+                        messages.push((*id, to, message));
+                    }
+                }
+                if messages.is_empty() {
+                    break;
+                }
+                for (from, to, message) in messages {
+                    if self.stopped.contains(&from) || self.stopped.contains(&to) {
+                        continue;
+                    }
+                    self.members
+                        .get_mut(&to)
+                        .unwrap()
+                        .handle(from, message, self.now);
+                }
+            }
+        }
+
+        fn leader(&self) -> PeerId {
+            let leaders: Vec<_> = self
+                .members
+                .iter()
+                .filter(|(id, m)| !self.stopped.contains(*id) && m.role() == Role::Leader)
+                .map(|(id, _)| *id)
+                .collect();
+            assert_eq!(leaders.len(), 1, "expected exactly one leader");
+            leaders[0]
+        }
+
+        fn state(&mut self, id: PeerId) -> Option<String> {
+            let member = self.members.get_mut(&id).unwrap();
+            member.take_committed_state();
+            member.snapshot.data.clone()
+        }
+    }
+
+    #[test]
+    fn state_survives_loss_of_leader() {
+        let mut cluster = Cluster::new(3);
+        cluster.run(Duration::from_secs(5));
+        let leader = cluster.leader();
+        assert!(cluster.members[&leader].is_ready_leader());
+
+        for i in 0..20 {
+            let member = cluster.members.get_mut(&leader).unwrap();
+            member.propose(format!("state {i}")).unwrap();
+            cluster.deliver();
+        }
+        cluster.run(Duration::from_secs(1));
+        for id in cluster.members.keys().copied().collect::<Vec<_>>() {
+            assert_eq!(cluster.state(id).as_deref(), Some("state 19"));
+        }
+
+        // a new leader is elected from the remaining members and keeps the state
+        cluster.stopped.insert(leader);
+        cluster.run(Duration::from_secs(5));
+        let new_leader = cluster.leader();
+        assert_ne!(new_leader, leader);
+        assert!(cluster.members[&new_leader].is_ready_leader());
+        cluster
+            .members
+            .get_mut(&new_leader)
+            .unwrap()
+            .propose("state 20".into())
+            .unwrap();
+        cluster.run(Duration::from_secs(1));
+        assert_eq!(cluster.state(new_leader).as_deref(), Some("state 20"));
+
+        // the old leader rejoins as follower and catches up from the snapshot
+        cluster.stopped.remove(&leader);
+        cluster.run(Duration::from_secs(2));
+        assert_eq!(cluster.leader(), new_leader);
+        assert_eq!(cluster.state(leader).as_deref(), Some("state 20"));
+    }
+
+    #[test]
+    fn no_leader_without_quorum() {
+        let mut cluster = Cluster::new(3);
+        cluster.run(Duration::from_secs(5));
+        let leader = cluster.leader();
+        let followers: Vec<_> = cluster
+            .members
+            .keys()
+            .copied()
+            .filter(|id| *id != leader)
+            .collect();
+        cluster.stopped.extend(followers);
+        cluster.run(Duration::from_secs(10));
+
+        // the isolated leader steps down and can't be re-elected
+        let remaining = &cluster.members[&leader];
+        assert_ne!(remaining.role(), Role::Leader);
+        assert_eq!(remaining.leader(), None);
+        assert!(!remaining.is_ready_leader());
+    }
+}
@@ -0,0 +1,336 @@
+//! The part of the coordinator state that is replicated to the other members
+//! of a high-availability cluster.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    time::Instant,
+};
+
+use dora_message::{
+    BuildId, DataflowId,
+    common::DaemonId,
+    coordinator_to_daemon::SpawnDataflowNodes,
+    daemon_to_coordinator::DataflowDaemonResult,
+    descriptor::{Descriptor, ResolvedNode},
+    id::NodeId,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ArchivedDataflow, BuildFinishedResult, CachedResult, RunningDataflow, state::CoordinatorState,
+};
+
+/// Snapshot of all coordinator state that control decisions depend on.
+///
+/// Connections, pending replies, and metrics are not replicated. Maps keyed by
+/// [`DaemonId`] are stored as lists because JSON only supports string keys.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub(crate) struct ReplicatedState {
+    running_dataflows: Vec<ReplicatedDataflow>,
+    archived_dataflows: Vec<(DataflowId, ArchivedDataflow)>,
+    dataflow_results: Vec<(DataflowId, Vec<(DaemonId, DataflowDaemonResult)>)>,
+    finished_builds: Vec<(BuildId, Result<(), String>)>,
+    /// Builds that were still running, they fail after a leader change.
+    interrupted_builds: Vec<BuildId>,
+    build_placements: Vec<(BuildId, BTreeMap<NodeId, DaemonId>)>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ReplicatedDataflow {
+    uuid: DataflowId,
+    name: Option<String>,
+    descriptor: Descriptor,
+    daemons: BTreeSet<DaemonId>,
+    pending_daemons: BTreeSet<DaemonId>,
+    exited_before_subscribe: Vec<NodeId>,
+    nodes: BTreeMap<NodeId, ResolvedNode>,
+    node_to_daemon: BTreeMap<NodeId, DaemonId>,
+    spawn_settings: Vec<(DaemonId, SpawnDataflowNodes)>,
+}
+
+impl ReplicatedState {
+    pub fn capture(state: &CoordinatorState) -> Self {
+        let mut running_dataflows: Vec<_> = state
+            .running_dataflows
+            .iter()
+            .map(|entry| ReplicatedDataflow::from(entry.value()))
+            .collect();
+        running_dataflows.sort_by_key(|d| d.uuid);
+
+        let mut archived_dataflows: Vec<_> = state
+            .archived_dataflows
+            .iter()
+            .map(|entry| (*entry.key(), entry.value().clone()))
+            .collect();
+        archived_dataflows.sort_by_key(|(id, _)| *id);
+
+        let mut dataflow_results: Vec<_> = state
+            .dataflow_results
+            .iter()
+            .map(|entry| {
+                let results = entry
+                    .value()
+                    .iter()
+                    .map(|(daemon, result)| (daemon.clone(), result.clone()))
+                    .collect();
+                (*entry.key(), results)
+            })
+            .collect();
+        dataflow_results.sort_by_key(|(id, _)| *id);
+
+        let mut finished_builds: Vec<_> = state
+            .finished_builds
+            .iter()
+            .filter_map(|entry| match entry.value() {
+                CachedResult::Cached { result } => {
+                    let result = match result {
+                        Ok(finished) => finished.result.clone(),
+                        Err(err) => Err(format!("{err:?}")),
+                    };
+                    Some((*entry.key(), result))
+                }
+                CachedResult::Pending { .. } => None,
+            })
+            .collect();
+        finished_builds.sort_by_key(|(id, _)| *id);
+
+        let mut interrupted_builds: Vec<_> = state
+            .running_builds
+            .iter()
+            .map(|entry| *entry.key())
+            .collect();
+        interrupted_builds.sort();
+
+        let mut build_placements: Vec<_> = state
+            .build_placements
+            .iter()
+            .map(|entry| (*entry.key(), entry.value().clone()))
+            .collect();
+        build_placements.sort_by_key(|(id, _)| *id);
+
+        Self {
+            running_dataflows,
+            archived_dataflows,
+            dataflow_results,
+            finished_builds,
+            interrupted_builds,
+            build_placements,
+        }
+    }
+
+    /// Fills the (empty) state of a new leader.
+    ///
+    /// The daemons of running dataflows are expected to reconnect, they are
+    /// treated as lost if they don't reconnect in time.
+    pub fn restore(self, state: &CoordinatorState) {
+        let now = Instant::now();
+        for dataflow in self.running_dataflows {
+            for daemon_id in &dataflow.daemons {
+                state.awaited_daemons.insert(daemon_id.clone(), now);
+            }
+            state
+                .running_dataflows
+                .insert(dataflow.uuid, dataflow.into_running());
+        }
+        for (id, dataflow) in self.archived_dataflows {
+            state.archived_dataflows.insert(id, dataflow);
+        }
+        for (id, results) in self.dataflow_results {
+            state
+                .dataflow_results
+                .insert(id, results.into_iter().collect());
+        }
+        let interrupted = self.interrupted_builds.into_iter().map(|build_id| {
+            let error = "build was interrupted by a coordinator leader change".to_owned();
+            (build_id, Err(error))
+        });
+        for (build_id, result) in self.finished_builds.into_iter().chain(interrupted) {
+            state.finished_builds.insert(
+                build_id,
+                CachedResult::Cached {
+                    result: Ok(BuildFinishedResult { build_id, result }),
+                },
+            );
+        }
+        for (id, placement) in self.build_placements {
+            state.build_placements.insert(id, placement);
+        }
+    }
+}
+
+impl From<&RunningDataflow> for ReplicatedDataflow {
+    fn from(dataflow: &RunningDataflow) -> Self {
+        Self {
+            uuid: dataflow.uuid,
+            name: dataflow.name.clone(),
+            descriptor: dataflow.descriptor.clone(),
+            daemons: dataflow.daemons.clone(),
+            pending_daemons: dataflow.pending_daemons.clone(),
+            exited_before_subscribe: dataflow.exited_before_subscribe.clone(),
+            nodes: dataflow.nodes.clone(),
+            node_to_daemon: dataflow.node_to_daemon.clone(),
+            spawn_settings: dataflow
+                .spawn_settings
+                .iter()
+                .map(|(daemon, settings)| (daemon.clone(), settings.clone()))
+                .collect(),
+        }
+    }
+}
+
+impl ReplicatedDataflow {
+    /// Restores the dataflow as spawned, replies to spawn requests that were
+    /// pending on the previous leader are lost.
+    fn into_running(self) -> RunningDataflow {
+        RunningDataflow {
+            name: self.name,
+            uuid: self.uuid,
+            descriptor: self.descriptor,
+            daemons: self.daemons,
+            pending_daemons: self.pending_daemons,
+            exited_before_subscribe: self.exited_before_subscribe,
+            nodes: self.nodes,
+            node_to_daemon: self.node_to_daemon,
+            spawn_settings: self.spawn_settings.into_iter().collect(),
+            node_metrics: BTreeMap::new(),
+            spawn_result: CachedResult::Cached {
+                result: Ok(self.uuid),
+            },
+            stop_reply_senders: Vec::new(),
+            pending_spawn_results: BTreeSet::new(),
+            buffered_dependency_statuses: Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::{BTreeMap, BTreeSet},
+        sync::Arc,
+    };
+
+    use dora_core::{descriptor::DescriptorExt, uhlc::HLC};
+    use dora_message::{
+        SessionId, common::DaemonId, coordinator_to_daemon::SpawnDataflowNodes,
+        descriptor::Descriptor, id::NodeId,
+    };
+    use uuid::Uuid;
+
+    use super::ReplicatedState;
+    use crate::{CachedResult, RunningDataflow, state::CoordinatorState};
+
+    const DATAFLOW: &str = r#"
+nodes:
+  - id: camera
+    path: shell
+    args: ./camera
+  - id: logger
+    path: shell
+    args: ./logger
+"#;
+
+    fn coordinator_state() -> CoordinatorState {
+        let (daemon_events_tx, _) = tokio::sync::mpsc::channel(1);
+        let (_, abort_handle) = futures::stream::abortable(futures::stream::empty::<()>());
+        CoordinatorState {
+            clock: Arc::new(HLC::default()),
+            running_builds: Default::default(),
+            finished_builds: Default::default(),
+            build_placements: Default::default(),
+            running_dataflows: Default::default(),
+            dataflow_results: Default::default(),
+            archived_dataflows: Default::default(),
+            daemon_connections: Default::default(),
+            awaited_daemons: Default::default(),
+            daemon_events_tx,
+            abort_handle,
+        }
+    }
+
+    #[test]
+    fn running_dataflows_are_restored_and_daemons_awaited() {
+        let descriptor = Descriptor::parse(DATAFLOW.as_bytes().to_vec()).unwrap();
+        let nodes = descriptor.resolve_aliases_and_set_defaults().unwrap();
+        let daemon = DaemonId::new(Some("a".to_owned()));
+        let uuid = Uuid::new_v4();
+        let spawn_settings = SpawnDataflowNodes {
+            build_id: None,
+            session_id: SessionId::generate(),
+            dataflow_id: uuid,
+            local_working_dir: None,
+            nodes: nodes.clone(),
+            dataflow_descriptor: descriptor.clone(),
+            spawn_nodes: nodes.keys().cloned().collect(),
+            uv: false,
+            write_events_to: None,
+            hot_reload: false,
+            dataflow_path: None,
+        };
+
+        let leader = coordinator_state();
+        leader.running_dataflows.insert(
+            uuid,
+            RunningDataflow {
+                name: Some("demo".to_owned()),
+                uuid,
+                descriptor,
+                daemons: BTreeSet::from([daemon.clone()]),
+                pending_daemons: BTreeSet::new(),
+                exited_before_subscribe: Vec::new(),
+                node_to_daemon: nodes.keys().map(|n| (n.clone(), daemon.clone())).collect(),
+                nodes,
+                spawn_settings: BTreeMap::from([(daemon.clone(), spawn_settings)]),
+                node_metrics: BTreeMap::new(),
+                spawn_result: CachedResult::default(),
+                stop_reply_senders: Vec::new(),
+                pending_spawn_results: BTreeSet::from([daemon.clone()]),
+                buffered_dependency_statuses: Vec::new(),
+            },
+        );
+
+        let replicated = serde_json::to_string(&ReplicatedState::capture(&leader)).unwrap();
+        let new_leader = coordinator_state();
+        serde_json::from_str::<ReplicatedState>(&replicated)
+            .unwrap()
+            .restore(&new_leader);
+
+        let dataflow = new_leader.running_dataflows.get(&uuid).unwrap();
+        assert_eq!(dataflow.name.as_deref(), Some("demo"));
+        assert_eq!(
+            dataflow
+                .node_to_daemon
+                .get(&NodeId::from("camera".to_owned())),
+            Some(&daemon)
+        );
+        assert!(dataflow.spawn_settings.contains_key(&daemon));
+        assert!(matches!(
+            dataflow.spawn_result,
+            CachedResult::Cached { result: Ok(id) } if id == uuid
+        ));
+        assert!(new_leader.awaited_daemons.contains_key(&daemon));
+    }
+
+    #[test]
+    fn running_builds_fail_after_restore() {
+        let leader = coordinator_state();
+        let build_id = dora_message::BuildId::generate();
+        leader.running_builds.insert(
+            build_id,
+            crate::RunningBuild {
+                errors: Vec::new(),
+                build_result: CachedResult::default(),
+                pending_build_results: BTreeSet::new(),
+            },
+        );
+
+        let new_leader = coordinator_state();
+        ReplicatedState::capture(&leader).restore(&new_leader);
+
+        let finished = new_leader.finished_builds.get(&build_id).unwrap();
+        assert!(matches!(
+            &*finished,
+            CachedResult::Cached { result: Ok(finished) } if finished.result.is_err()
+        ));
+    }
+}
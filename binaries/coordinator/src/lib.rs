@@ -14,13 +14,14 @@ use dora_message::{
         BuildRequest, CoordinatorControl, CoordinatorControlClient, CoordinatorControlRequest,
         CoordinatorControlResponse,
     },
-    common::DaemonId,
+    common::{DaemonId, DataflowChanges, DependencyStatus, PublishMessage},
     coordinator_to_cli::{DataflowResult, StopDataflowReply},
     coordinator_to_daemon::{
         BuildDataflowNodes, DaemonControlClient, DaemonControlRequest, DaemonControlResponse,
-        RegisterResult, Timestamped,
+        RegisterResult, SpawnDataflowNodes, Timestamped,
     },
-    daemon_to_coordinator::DataflowDaemonResult,
+    daemon_to_coordinator::{DaemonPlacement, DataflowDaemonResult},
+    debug::{DebugCommand, DebugStatus},
     descriptor::{Descriptor, ResolvedNode},
     tarpc::{
         self, ClientMessage, Response, Transport, client,
@@ -48,6 +49,11 @@ use tokio::{
 use tokio_stream::wrappers::ReceiverStream;
 use uuid::Uuid;
 
+pub use ha::HaConfig;
+
+mod apply;
+mod failover;
+mod ha;
 mod listener;
 mod run;
 mod server;
@@ -61,10 +67,44 @@ pub async fn start(
     bind_control: SocketAddr,
     external_events: impl Stream<Item = Event> + Unpin,
 ) -> Result<(u16, impl Future<Output = eyre::Result<()>>), eyre::ErrReport> {
+    let (daemon_port, _state, future) =
+        start_with_state(bind, bind_control, external_events, None).await?;
+    Ok((daemon_port, future))
+}
+
+/// Start the coordinator as a member of a high-availability cluster.
+///
+/// The coordinators of the cluster replicate their state through Raft. Only
+/// the elected leader binds the daemon and control ports, so daemons and
+/// clients that are given the addresses of all coordinators connect to the
+/// leader. When the leader fails, the new leader restores the replicated
+/// state and waits for the daemons to reconnect.
+///
+/// The returned future fails when this coordinator loses its leadership.
+pub async fn start_ha(
+    bind: SocketAddr,
+    bind_control: SocketAddr,
+    ha_config: HaConfig,
+    external_events: impl Stream<Item = Event> + Unpin,
+) -> Result<impl Future<Output = eyre::Result<()>>, eyre::ErrReport> {
+    ha::start(bind, bind_control, ha_config, external_events).await
+}
+
+/// Start the coordinator with the given restored state.
+async fn start_with_state(
+    bind: SocketAddr,
+    bind_control: SocketAddr,
+    external_events: impl Stream<Item = Event> + Unpin,
+    restored: Option<ha::ReplicatedState>,
+) -> Result<(
+    u16,
+    Arc<state::CoordinatorState>,
+    impl Future<Output = eyre::Result<()>>,
+)> {
     let tasks = FuturesUnordered::new();
 
     let (daemon_port, coordinator_state, future) =
-        init_coordinator(bind, external_events, tasks).await?;
+        init_coordinator(bind, external_events, tasks, restored).await?;
 
     // Bind the tarpc RPC server on the same interface
     let rpc_bind = SocketAddr::new(
@@ -76,18 +116,19 @@ pub async fn start(
             .await
             .wrap_err("failed to start tarpc server for control messages")?;
 
+    let state_for_server = coordinator_state.clone();
     let stream = listener
         // ignore connect errors
         .filter_map(|c| future::ready(c.ok()))
         .map(move |transport| {
             let client_ip = transport.peer_addr().ok().map(|addr| addr.ip());
-            serve_control_requests(transport, coordinator_state.clone(), client_ip)
+            serve_control_requests(transport, state_for_server.clone(), client_ip)
         });
     tokio::spawn(stream.for_each(|handle_connection| async {
         tokio::spawn(handle_connection);
     }));
 
-    Ok((daemon_port, future))
+    Ok((daemon_port, coordinator_state, future))
 }
 
 /// Start the coordinator with an in-process RPC server instead of a TCP listener.
@@ -108,7 +149,7 @@ pub async fn start_with_channel_rpc(
     let tasks = FuturesUnordered::new();
 
     let (_daemon_port, coordinator_state, future) =
-        init_coordinator(bind, external_events, tasks).await?;
+        init_coordinator(bind, external_events, tasks, None).await?;
 
     // Create an in-process channel-based client (no TCP overhead)
     let (client_transport, server_transport) = tarpc::transport::channel::unbounded();
@@ -128,6 +169,7 @@ async fn init_coordinator(
     bind: SocketAddr,
     external_events: impl Stream<Item = Event> + Unpin,
     mut tasks: FuturesUnordered<JoinHandle<()>>,
+    restored: Option<ha::ReplicatedState>,
 ) -> Result<(
     u16,
     Arc<state::CoordinatorState>,
@@ -164,13 +206,18 @@ async fn init_coordinator(
         clock: Arc::new(HLC::default()),
         running_builds: Default::default(),
         finished_builds: Default::default(),
+        build_placements: Default::default(),
         running_dataflows: Default::default(),
         dataflow_results: Default::default(),
         archived_dataflows: Default::default(),
         daemon_connections: Default::default(),
+        awaited_daemons: Default::default(),
         daemon_events_tx,
         abort_handle,
     });
+    if let Some(restored) = restored {
+        restored.restore(&coordinator_state);
+    }
 
     let state_for_caller = coordinator_state.clone();
 
@@ -353,26 +400,31 @@ async fn start_inner(
                     machine_id,
                     machine_uid,
                     zenoh_peer_id,
+                    realtime_scheduling,
+                    placement,
+                    daemon_id: previous_daemon_id,
                     mut connection,
                     version_check_result,
                 } => {
+                    // daemons keep their ID when reconnecting to a new leader
+                    let daemon_id = match previous_daemon_id {
+                        Some(id) if id.machine_id() == machine_id.as_deref() => id,
+                        _ => DaemonId::new(machine_id.clone()),
+                    };
+
                     let existing = match &machine_id {
                         Some(id) => coordinator_state
                             .daemon_connections
                             .get_matching_daemon_id(id),
                         None => coordinator_state.daemon_connections.unnamed().next(),
                     };
-                    let existing_result = if existing.is_some() {
-                        Err(format!(
+                    let existing_result = match existing {
+                        Some(existing) if existing != daemon_id => Err(format!(
                             "There is already a connected daemon with machine ID `{machine_id:?}`"
-                        ))
-                    } else {
-                        Ok(())
+                        )),
+                        _ => Ok(()),
                     };
 
-                    // assign a unique ID to the daemon
-                    let daemon_id = DaemonId::new(machine_id);
-
                     let reply: Timestamped<RegisterResult> = Timestamped {
                         inner: match version_check_result.as_ref().and(existing_result.as_ref()) {
                             Ok(_) => RegisterResult::Ok {
@@ -413,8 +465,11 @@ async fn start_inner(
                                     peer_addr,
                                     machine_uid,
                                     zenoh_peer_id,
+                                    realtime_scheduling,
+                                    placement,
                                 },
                             );
+                            coordinator_state.awaited_daemons.remove(&daemon_id);
                         }
                         Err(err) => {
                             tracing::warn!(
@@ -497,10 +552,29 @@ async fn start_inner(
                         }
                     });
                 }
+                // daemons of restored dataflows that didn't reconnect to this
+                // coordinator after a leader change
+                coordinator_state
+                    .awaited_daemons
+                    .retain(|daemon_id, since| {
+                        if since.elapsed() > Duration::from_secs(30) {
+                            tracing::warn!(
+                                "daemon `{daemon_id}` did not reconnect after leader change"
+                            );
+                            disconnected.insert(daemon_id.clone());
+                            false
+                        } else {
+                            true
+                        }
+                    });
                 if !disconnected.is_empty() {
                     tracing::error!("Disconnecting daemons that failed watchdog: {disconnected:?}");
                     for machine_id in disconnected {
                         coordinator_state.daemon_connections.remove(&machine_id);
+                        tokio::spawn(failover::handle_daemon_loss(
+                            coordinator_state.clone(),
+                            machine_id,
+                        ));
                     }
                 }
             }
@@ -552,6 +626,10 @@ pub(crate) struct DaemonConnection {
     /// System-level machine identifier reported by the daemon at registration.
     machine_uid: Option<String>,
     pub(crate) zenoh_peer_id: Option<String>,
+    /// Whether the daemon may apply real-time scheduling settings to nodes.
+    pub(crate) realtime_scheduling: bool,
+    /// Labels and capacity of the daemon for label-based node placement.
+    pub(crate) placement: DaemonPlacement,
 }
 
 async fn handle_destroy(
@@ -606,6 +684,9 @@ pub(crate) struct RunningDataflow {
     nodes: BTreeMap<NodeId, ResolvedNode>,
     /// Maps each node to the daemon it's running on
     node_to_daemon: BTreeMap<NodeId, DaemonId>,
+    /// Spawn command that was sent to each daemon, used to spawn the nodes of
+    /// a lost daemon on other daemons on failover.
+    spawn_settings: BTreeMap<DaemonId, SpawnDataflowNodes>,
     /// Latest metrics for each node (from daemons)
     node_metrics: BTreeMap<NodeId, dora_message::daemon_to_coordinator::NodeMetrics>,
 
@@ -614,6 +695,11 @@ pub(crate) struct RunningDataflow {
         Vec<tokio::sync::oneshot::Sender<eyre::Result<StopDataflowReply>>>,
 
     pub(crate) pending_spawn_results: BTreeSet<DaemonId>,
+    /// Startup progress of nodes that other nodes depend on, reported by the given daemon.
+    ///
+    /// Buffered until all daemons spawned their nodes, as the other daemons might not
+    /// know the dataflow before.
+    pub(crate) buffered_dependency_statuses: Vec<(DaemonId, NodeId, DependencyStatus)>,
 }
 
 pub enum CachedResult<T> {
@@ -664,9 +750,11 @@ impl<T: Clone> CachedResult<T> {
     }
 }
 
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub(crate) struct ArchivedDataflow {
     name: Option<String>,
     nodes: BTreeMap<NodeId, ResolvedNode>,
+    node_to_daemon: BTreeMap<NodeId, DaemonId>,
 }
 
 impl From<&RunningDataflow> for ArchivedDataflow {
@@ -674,6 +762,7 @@ impl From<&RunningDataflow> for ArchivedDataflow {
         ArchivedDataflow {
             name: dataflow.name.clone(),
             nodes: dataflow.nodes.clone(),
+            node_to_daemon: dataflow.node_to_daemon.clone(),
         }
     }
 }
@@ -769,6 +858,46 @@ async fn reload_dataflow(
     Ok(())
 }
 
+async fn debug_dataflow(
+    running_dataflows: &DashMap<Uuid, RunningDataflow>,
+    dataflow_id: Uuid,
+    command: DebugCommand,
+    daemon_connections: &DaemonConnections,
+) -> eyre::Result<DebugStatus> {
+    let daemon_ids: Vec<DaemonId> = {
+        let Some(dataflow) = running_dataflows.get(&dataflow_id) else {
+            bail!("No running dataflow found with UUID `{dataflow_id}`")
+        };
+        dataflow.daemons.iter().cloned().collect()
+    };
+
+    let mut status = DebugStatus::default();
+    for machine_id in &daemon_ids {
+        // a step count is shared by all daemons, so only step the remaining messages
+        let command = match &command {
+            DebugCommand::Step { node, count } => DebugCommand::Step {
+                node: node.clone(),
+                count: count.saturating_sub(status.released),
+            },
+            other => other.clone(),
+        };
+        let client = daemon_connections
+            .get(machine_id)
+            .wrap_err("no daemon connection")?
+            .client
+            .clone();
+        let daemon_status = client
+            .debug(tarpc::context::current(), dataflow_id, command)
+            .await
+            .context("RPC transport error")?
+            .map_err(|e: String| eyre!(e))
+            .wrap_err_with(|| format!("failed to debug dataflow on daemon `{machine_id}`"))?;
+        status.merge(daemon_status);
+    }
+
+    Ok(status)
+}
+
 async fn retrieve_logs(
     running_dataflows: &DashMap<Uuid, RunningDataflow>,
     archived_dataflows: &DashMap<Uuid, ArchivedDataflow>,
@@ -777,17 +906,176 @@ async fn retrieve_logs(
     daemon_connections: &DaemonConnections,
     tail: Option<usize>,
 ) -> eyre::Result<dora_message::common::LogsResponse> {
-    let nodes = if let Some(dataflow) = archived_dataflows.get(&dataflow_id) {
-        dataflow.nodes.clone()
+    let (nodes, node_to_daemon) = if let Some(dataflow) = archived_dataflows.get(&dataflow_id) {
+        (dataflow.nodes.clone(), dataflow.node_to_daemon.clone())
     } else if let Some(dataflow) = running_dataflows.get(&dataflow_id) {
-        dataflow.nodes.clone()
+        (dataflow.nodes.clone(), dataflow.node_to_daemon.clone())
     } else {
         bail!("No dataflow found with UUID `{dataflow_id}`")
     };
+    let daemon_id = node_daemon_id(
+        &nodes,
+        &node_to_daemon,
+        dataflow_id,
+        &node_id,
+        daemon_connections,
+    )?;
+    let client = daemon_connections
+        .get(&daemon_id)
+        .wrap_err_with(|| format!("no daemon connection to `{daemon_id}`"))?
+        .client
+        .clone();
+    // DashMap lock is dropped — safe to do async I/O.
+    let reply_logs = client
+        .logs(
+            tarpc::context::current(),
+            dataflow_id,
+            node_id.clone(),
+            tail,
+        )
+        .await
+        .context("RPC transport error")?;
+    tracing::info!("successfully retrieved logs for `{dataflow_id}/{node_id}`");
+
+    reply_logs.map_err(|err: String| eyre!(err))
+}
+
+async fn publish_message(
+    running_dataflows: &DashMap<Uuid, RunningDataflow>,
+    dataflow_id: Uuid,
+    message: PublishMessage,
+    daemon_connections: &DaemonConnections,
+) -> eyre::Result<()> {
+    let daemon_id = {
+        let Some(dataflow) = running_dataflows.get(&dataflow_id) else {
+            bail!("No running dataflow found with UUID `{dataflow_id}`")
+        };
+        node_daemon_id(
+            &dataflow.nodes,
+            &dataflow.node_to_daemon,
+            dataflow_id,
+            &message.node_id,
+            daemon_connections,
+        )?
+    };
+    let client = daemon_connections
+        .get(&daemon_id)
+        .wrap_err_with(|| format!("no daemon connection to `{daemon_id}`"))?
+        .client
+        .clone();
+    client
+        .publish(tarpc::context::current(), dataflow_id, message)
+        .await
+        .context("RPC transport error")?
+        .map_err(|e: String| eyre!(e))
+        .wrap_err("failed to publish message")
+}
+
+/// Adds, removes, restarts, and rewires nodes of a running dataflow to match
+/// the given descriptor.
+async fn apply_dataflow(
+    running_dataflows: &DashMap<Uuid, RunningDataflow>,
+    dataflow_id: Uuid,
+    descriptor: Descriptor,
+    daemon_connections: &DaemonConnections,
+) -> eyre::Result<DataflowChanges> {
+    let nodes = descriptor.resolve_aliases_and_set_defaults()?;
+    let mut placement = run::Placement::new(daemon_connections, running_dataflows);
+    let (changes, node_to_daemon, daemons, old) = {
+        let Some(dataflow) = running_dataflows.get(&dataflow_id) else {
+            bail!("No running dataflow found with UUID `{dataflow_id}`")
+        };
+        let changes = DataflowChanges::between(&dataflow.nodes, &nodes);
+        for node_id in changes.nodes() {
+            let replicated = |nodes: &BTreeMap<NodeId, ResolvedNode>| {
+                nodes.get(node_id).is_some_and(|n| n.replicas.is_some())
+            };
+            if replicated(&dataflow.nodes) || replicated(&nodes) {
+                bail!("cannot apply changes to replicated node `{node_id}`");
+            }
+        }
+        for node_id in changes.added.iter().chain(&changes.restarted) {
+            if nodes[node_id].has_git_source() {
+                bail!("cannot apply changes to node `{node_id}` because it has a git source");
+            }
+        }
+
+        let mut node_to_daemon = BTreeMap::new();
+        for node in nodes.values() {
+            let daemon_id = match dataflow.node_to_daemon.get(&node.id) {
+                Some(previous) if !placement.matches(node, previous) => {
+                    bail!("node `{}` cannot be moved to another machine", node.id)
+                }
+                Some(previous) => previous.clone(),
+                None => {
+                    let daemon_id = placement
+                        .place(node)
+                        .wrap_err_with(|| format!("failed to place node `{}`", node.id))?;
+                    if !dataflow.daemons.contains(&daemon_id) {
+                        bail!(
+                            "cannot add node `{}` because the dataflow does not run on machine `{daemon_id}` yet",
+                            node.id
+                        );
+                    }
+                    daemon_id
+                }
+            };
+            node_to_daemon.insert(node.id.clone(), daemon_id);
+        }
+        let old = apply::DataflowNodes {
+            nodes: dataflow.nodes.clone(),
+            descriptor: dataflow.descriptor.clone(),
+            node_to_daemon: dataflow.node_to_daemon.clone(),
+        };
+        (changes, node_to_daemon, dataflow.daemons.clone(), old)
+    };
+    if changes.is_empty() {
+        return Ok(changes);
+    }
+
+    let new = apply::DataflowNodes {
+        nodes,
+        descriptor,
+        node_to_daemon,
+    };
+    apply::apply_on_daemons(
+        daemon_connections,
+        dataflow_id,
+        &daemons,
+        &old,
+        &new,
+        &changes,
+    )
+    .await?;
+
+    if let Some(mut dataflow) = running_dataflows.get_mut(&dataflow_id) {
+        for node_id in &changes.removed {
+            dataflow.node_metrics.remove(node_id);
+        }
+        dataflow.descriptor = new.descriptor;
+        dataflow.nodes = new.nodes;
+        dataflow.node_to_daemon = new.node_to_daemon;
+    }
+    tracing::info!("applied changes to dataflow `{dataflow_id}`: {changes:?}");
+
+    Ok(changes)
+}
+
+/// Returns the ID of the daemon that runs the given node.
+fn node_daemon_id(
+    nodes: &BTreeMap<NodeId, ResolvedNode>,
+    node_to_daemon: &BTreeMap<NodeId, DaemonId>,
+    dataflow_id: Uuid,
+    node_id: &NodeId,
+    daemon_connections: &DaemonConnections,
+) -> eyre::Result<DaemonId> {
+    if let Some(daemon_id) = node_to_daemon.get(node_id) {
+        return Ok(daemon_id.clone());
+    }
 
     let machine_ids: Vec<Option<String>> = nodes
         .values()
-        .filter(|node| node.id == node_id)
+        .filter(|node| &node.id == node_id)
         .map(|node| node.deploy.as_ref().and_then(|d| d.machine.clone()))
         .collect();
 
@@ -815,32 +1103,19 @@ async fn retrieve_logs(
         [] => eyre::bail!("no matching daemon connections for machine ID `{machine_id:?}`"),
         _ => eyre::bail!("multiple matching daemon connections for machine ID `{machine_id:?}`"),
     };
-    let client = daemon_connections
-        .get(&daemon_id)
-        .wrap_err_with(|| format!("no daemon connection to `{daemon_id}`"))?
-        .client
-        .clone();
-    // DashMap lock is dropped — safe to do async I/O.
-    let reply_logs = client
-        .logs(
-            tarpc::context::current(),
-            dataflow_id,
-            node_id.clone(),
-            tail,
-        )
-        .await
-        .context("RPC transport error")?;
-    tracing::info!("successfully retrieved logs for `{dataflow_id}/{node_id}`");
-
-    reply_logs.map_err(|err: String| eyre!(err))
+    Ok(daemon_id)
 }
 
-#[tracing::instrument(skip(daemon_connections))]
+/// Triggers the build of the given dataflow on the daemons.
+///
+/// Returns the running build and the daemon that each node was placed on.
+#[tracing::instrument(skip(daemon_connections, running_dataflows))]
 async fn build_dataflow(
     build_request: BuildRequest,
     build_id: BuildId,
     daemon_connections: &DaemonConnections,
-) -> eyre::Result<RunningBuild> {
+    running_dataflows: &DashMap<Uuid, RunningDataflow>,
+) -> eyre::Result<(RunningBuild, BTreeMap<NodeId, DaemonId>)> {
     let BuildRequest {
         build_id: _, // already extracted by the RPC handler above
         session_id,
@@ -853,85 +1128,76 @@ async fn build_dataflow(
 
     let nodes = dataflow.resolve_aliases_and_set_defaults()?;
 
+    let mut placement = run::Placement::new(daemon_connections, running_dataflows);
+    let mut node_to_daemon = BTreeMap::new();
+    for node in nodes.values() {
+        let daemon_id = placement
+            .place(node)
+            .wrap_err_with(|| format!("failed to place node `{}`", node.id))?;
+        node_to_daemon.insert(node.id.clone(), daemon_id);
+    }
+
     let mut git_sources_by_daemon = git_sources
         .into_iter()
-        .into_grouping_map_by(|(id, _)| {
-            nodes
-                .get(id)
-                .and_then(|n| n.deploy.as_ref().and_then(|d| d.machine.as_ref()))
-        })
+        .into_grouping_map_by(|(id, _)| node_to_daemon.get(id))
         .collect();
     let mut prev_git_sources_by_daemon = prev_git_sources
         .into_iter()
-        .into_grouping_map_by(|(id, _)| {
-            nodes
-                .get(id)
-                .and_then(|n| n.deploy.as_ref().and_then(|d| d.machine.as_ref()))
-        })
+        .into_grouping_map_by(|(id, _)| node_to_daemon.get(id))
         .collect();
 
-    let nodes_by_daemon = nodes
-        .values()
-        .into_group_map_by(|n| n.deploy.as_ref().and_then(|d| d.machine.as_ref()));
+    let nodes_by_daemon = node_to_daemon
+        .iter()
+        .into_group_map_by(|(_, daemon_id)| *daemon_id);
 
     let mut daemons = BTreeSet::new();
-    for (machine, nodes_on_machine) in &nodes_by_daemon {
-        let nodes_on_machine = nodes_on_machine.iter().map(|n| n.id.clone()).collect();
+    for (daemon_id, nodes_on_daemon) in nodes_by_daemon {
+        let nodes_on_machine = nodes_on_daemon
+            .iter()
+            .map(|(node_id, _)| (*node_id).clone())
+            .collect();
         tracing::debug!(
-            "Running dataflow build `{build_id}` on machine `{machine:?}` (nodes: {nodes_on_machine:?})"
+            "Running dataflow build `{build_id}` on daemon `{daemon_id}` (nodes: {nodes_on_machine:?})"
         );
 
         let build_command = BuildDataflowNodes {
             build_id,
             session_id,
             local_working_dir: local_working_dir.clone(),
-            git_sources: git_sources_by_daemon.remove(machine).unwrap_or_default(),
+            git_sources: git_sources_by_daemon
+                .remove(&Some(daemon_id))
+                .unwrap_or_default(),
             prev_git_sources: prev_git_sources_by_daemon
-                .remove(machine)
+                .remove(&Some(daemon_id))
                 .unwrap_or_default(),
             dataflow_descriptor: dataflow.clone(),
             nodes_on_machine,
             uv,
         };
 
-        let daemon_id = build_dataflow_on_machine(
-            daemon_connections,
-            machine.map(|s| s.as_str()),
-            build_command,
-        )
-        .await
-        .wrap_err_with(|| format!("failed to build dataflow on machine `{machine:?}`"))?;
-        daemons.insert(daemon_id);
+        build_dataflow_on_daemon(daemon_connections, daemon_id, build_command)
+            .await
+            .wrap_err_with(|| format!("failed to build dataflow on daemon `{daemon_id}`"))?;
+        daemons.insert(daemon_id.clone());
     }
 
     tracing::info!("successfully triggered dataflow build `{build_id}`",);
 
-    Ok(RunningBuild {
+    let build = RunningBuild {
         errors: Vec::new(),
         build_result: CachedResult::default(),
         pending_build_results: daemons,
-    })
+    };
+    Ok((build, node_to_daemon))
 }
 
-async fn build_dataflow_on_machine(
+async fn build_dataflow_on_daemon(
     daemon_connections: &DaemonConnections,
-    machine: Option<&str>,
+    daemon_id: &DaemonId,
     build_command: BuildDataflowNodes,
-) -> Result<DaemonId, eyre::ErrReport> {
-    let daemon_id = match machine {
-        Some(machine) => daemon_connections
-            .get_matching_daemon_id(machine)
-            .wrap_err_with(|| format!("no matching daemon for machine id {machine:?}"))?
-            .clone(),
-        None => daemon_connections
-            .unnamed()
-            .next()
-            .wrap_err("no unnamed daemon connections")?
-            .clone(),
-    };
-
+) -> eyre::Result<()> {
     let client = daemon_connections
-        .get(&daemon_id)
+        .get(daemon_id)
         .wrap_err_with(|| format!("no daemon connection for daemon `{daemon_id}`"))?
         .client
         .clone();
@@ -942,7 +1208,7 @@ async fn build_dataflow_on_machine(
         .context("RPC transport error")?
         .map_err(|e: String| eyre!(e))
         .wrap_err("daemon returned an error")?;
-    Ok(daemon_id)
+    Ok(())
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -955,6 +1221,7 @@ async fn start_dataflow(
     name: Option<String>,
     daemon_connections: &DaemonConnections,
     running_dataflows: &DashMap<Uuid, RunningDataflow>,
+    build_placement: &BTreeMap<NodeId, DaemonId>,
     uv: bool,
     write_events_to: Option<PathBuf>,
     hot_reload: bool,
@@ -966,6 +1233,8 @@ async fn start_dataflow(
         &dataflow,
         local_working_dir,
         daemon_connections,
+        running_dataflows,
+        build_placement,
         uv,
         write_events_to,
         hot_reload,
@@ -987,6 +1256,7 @@ async fn start_dataflow(
     // and sends the SpawnResult back before start_dataflow returns — if the
     // entry isn't in the map yet, the coordinator event loop would discard
     // the result and `wait_for_spawn` would time out.
+    let spawn_settings = daemon_spawn_commands.iter().cloned().collect();
     running_dataflows.insert(
         uuid,
         RunningDataflow {
@@ -1002,10 +1272,12 @@ async fn start_dataflow(
             daemons: daemons.clone(),
             nodes,
             node_to_daemon,
+            spawn_settings,
             node_metrics: BTreeMap::new(),
             spawn_result: CachedResult::default(),
             stop_reply_senders: Vec::new(),
             pending_spawn_results: daemons,
+            buffered_dependency_statuses: Vec::new(),
         },
     );
 
@@ -1094,6 +1366,10 @@ pub enum DaemonRequest {
         machine_id: Option<String>,
         machine_uid: Option<String>,
         zenoh_peer_id: Option<String>,
+        realtime_scheduling: bool,
+        placement: DaemonPlacement,
+        /// ID assigned by a previous coordinator leader.
+        daemon_id: Option<DaemonId>,
         connection: TcpStream,
         version_check_result: Result<(), String>,
     },
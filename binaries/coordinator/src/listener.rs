@@ -4,11 +4,12 @@ use crate::{
 };
 use dora_core::uhlc::HLC;
 use dora_message::{
-    common::DaemonId,
+    common::{DaemonId, DependencyStatus},
     coordinator_to_cli::{DataflowResult, StopDataflowReply},
     daemon_to_coordinator::{
         CoordinatorNotify, CoordinatorRequest, DataflowDaemonResult, NodeMetrics, Timestamped,
     },
+    id::NodeId,
     tarpc,
 };
 use eyre::Context;
@@ -79,6 +80,9 @@ pub async fn handle_connection(
                     machine_id: register_request.machine_id,
                     machine_uid: register_request.machine_uid,
                     zenoh_peer_id: register_request.zenoh_peer_id,
+                    realtime_scheduling: register_request.realtime_scheduling,
+                    placement: register_request.placement,
+                    daemon_id: register_request.daemon_id,
                 };
                 let _ = events_tx.send(Event::Daemon(event)).await;
                 break;
@@ -173,52 +177,7 @@ impl CoordinatorNotify for CoordinatorNotifyServer {
         tracing::debug!(
             "coordinator received DataflowFinishedOnDaemon ({daemon_id:?}, result: {result:?})"
         );
-        match self.coordinator_state.running_dataflows.entry(dataflow_id) {
-            dashmap::Entry::Occupied(mut entry) => {
-                let dataflow = entry.get_mut();
-                dataflow.daemons.remove(&daemon_id);
-                tracing::info!(
-                    "removed machine id: {daemon_id} from dataflow: {:#?}",
-                    dataflow.uuid
-                );
-                self.coordinator_state
-                    .dataflow_results
-                    .entry(dataflow_id)
-                    .or_default()
-                    .insert(daemon_id, result);
-
-                if dataflow.daemons.is_empty() {
-                    // Archive finished dataflow
-                    self.coordinator_state
-                        .archived_dataflows
-                        .entry(dataflow_id)
-                        .or_insert_with(|| ArchivedDataflow::from(entry.get()));
-                    let finished_dataflow = entry.remove();
-                    let clock = &self.coordinator_state.clock;
-
-                    let reply = StopDataflowReply {
-                        uuid: dataflow_id,
-                        result: self
-                            .coordinator_state
-                            .dataflow_results
-                            .get(&dataflow_id)
-                            .map(|r| dataflow_result(r.value(), dataflow_id, clock))
-                            .unwrap_or_else(|| {
-                                DataflowResult::ok_empty(dataflow_id, clock.new_timestamp())
-                            }),
-                    };
-                    for sender in finished_dataflow.stop_reply_senders {
-                        let _ = sender.send(Ok(reply.clone()));
-                    }
-                    if !matches!(finished_dataflow.spawn_result, CachedResult::Cached { .. }) {
-                        log::error!("pending spawn result on dataflow finish");
-                    }
-                }
-            }
-            dashmap::Entry::Vacant(_) => {
-                tracing::warn!("dataflow not running on DataflowFinishedOnDaemon");
-            }
-        }
+        handle_daemon_result(&self.coordinator_state, dataflow_id, daemon_id, result);
     }
 
     async fn heartbeat(self, _ctx: tarpc::context::Context) {
@@ -304,7 +263,7 @@ impl CoordinatorNotify for CoordinatorNotifyServer {
         result: Result<(), String>,
     ) {
         let result = result.map_err(|err| eyre::eyre!(err));
-        match self
+        let buffered_statuses = match self
             .coordinator_state
             .running_dataflows
             .get_mut(&dataflow_id)
@@ -323,12 +282,157 @@ impl CoordinatorNotify for CoordinatorNotifyServer {
                         dataflow.spawn_result.set_result(Err(err));
                     }
                 }
+                if dataflow.pending_spawn_results.is_empty() {
+                    std::mem::take(&mut dataflow.buffered_dependency_statuses)
+                } else {
+                    Vec::new()
+                }
             }
             None => {
                 tracing::warn!(
                     "received spawn result, but no matching dataflow \
                      `{dataflow_id}` in `running_dataflows` map"
                 );
+                Vec::new()
+            }
+        };
+        // DashMap lock is now dropped — safe to do async I/O.
+        for (daemon_id, node_id, status) in buffered_statuses {
+            self.forward_dependency_status(dataflow_id, daemon_id, node_id, status)
+                .await;
+        }
+    }
+
+    async fn dependency_status(
+        self,
+        _ctx: tarpc::context::Context,
+        dataflow_id: Uuid,
+        node_id: NodeId,
+        status: DependencyStatus,
+    ) {
+        match self
+            .coordinator_state
+            .running_dataflows
+            .get_mut(&dataflow_id)
+        {
+            Some(mut dataflow) if !dataflow.pending_spawn_results.is_empty() => {
+                // other daemons might not know the dataflow yet
+                dataflow.buffered_dependency_statuses.push((
+                    self.daemon_id.clone(),
+                    node_id,
+                    status,
+                ));
+                return;
+            }
+            Some(_) => {}
+            None => {
+                tracing::warn!("dataflow not running on DependencyStatus");
+                return;
+            }
+        }
+        // DashMap lock is now dropped — safe to do async I/O.
+        let daemon_id = self.daemon_id.clone();
+        self.forward_dependency_status(dataflow_id, daemon_id, node_id, status)
+            .await;
+    }
+}
+
+/// Records the result of a dataflow on a daemon, e.g. when all nodes of the
+/// daemon finished.
+///
+/// Archives the dataflow when it finished on all of its daemons.
+pub(crate) fn handle_daemon_result(
+    coordinator_state: &state::CoordinatorState,
+    dataflow_id: Uuid,
+    daemon_id: DaemonId,
+    result: DataflowDaemonResult,
+) {
+    match coordinator_state.running_dataflows.entry(dataflow_id) {
+        dashmap::Entry::Occupied(mut entry) => {
+            let dataflow = entry.get_mut();
+            dataflow.daemons.remove(&daemon_id);
+            tracing::info!(
+                "removed machine id: {daemon_id} from dataflow: {:#?}",
+                dataflow.uuid
+            );
+            coordinator_state
+                .dataflow_results
+                .entry(dataflow_id)
+                .or_default()
+                .insert(daemon_id, result);
+
+            if dataflow.daemons.is_empty() {
+                // Archive finished dataflow
+                coordinator_state
+                    .archived_dataflows
+                    .entry(dataflow_id)
+                    .or_insert_with(|| ArchivedDataflow::from(entry.get()));
+                let finished_dataflow = entry.remove();
+                let clock = &coordinator_state.clock;
+
+                let reply = StopDataflowReply {
+                    uuid: dataflow_id,
+                    result: coordinator_state
+                        .dataflow_results
+                        .get(&dataflow_id)
+                        .map(|r| dataflow_result(r.value(), dataflow_id, clock))
+                        .unwrap_or_else(|| {
+                            DataflowResult::ok_empty(dataflow_id, clock.new_timestamp())
+                        }),
+                };
+                for sender in finished_dataflow.stop_reply_senders {
+                    let _ = sender.send(Ok(reply.clone()));
+                }
+                if !matches!(finished_dataflow.spawn_result, CachedResult::Cached { .. }) {
+                    log::error!("pending spawn result on dataflow finish");
+                }
+            }
+        }
+        dashmap::Entry::Vacant(_) => {
+            tracing::warn!("dataflow not running on DataflowFinishedOnDaemon");
+        }
+    }
+}
+
+impl CoordinatorNotifyServer {
+    /// Forwards the startup progress of a node to the other daemons of the dataflow.
+    async fn forward_dependency_status(
+        &self,
+        dataflow_id: Uuid,
+        from_daemon: DaemonId,
+        node_id: NodeId,
+        status: DependencyStatus,
+    ) {
+        let Some(daemons) = self
+            .coordinator_state
+            .running_dataflows
+            .get(&dataflow_id)
+            .map(|dataflow| dataflow.daemons.clone())
+        else {
+            return;
+        };
+        for daemon_id in daemons.iter().filter(|d| **d != from_daemon) {
+            let client = match self.coordinator_state.daemon_connections.get(daemon_id) {
+                Some(connection) => connection.client.clone(),
+                None => {
+                    tracing::warn!("no daemon connection found for machine `{daemon_id}`");
+                    continue;
+                }
+            };
+            // DashMap lock is dropped — safe to do async I/O.
+            if let Err(err) = client
+                .dependency_status(
+                    tarpc::context::current(),
+                    dataflow_id,
+                    node_id.clone(),
+                    status.clone(),
+                )
+                .await
+            {
+                tracing::error!(
+                    "failed to forward dependency status of `{node_id}` \
+                     to machine {daemon_id}: {err:?}"
+                );
             }
         }
     }
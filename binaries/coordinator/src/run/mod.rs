@@ -1,5 +1,6 @@
-use crate::DaemonConnections;
+use crate::{DaemonConnections, RunningDataflow};
 
+use dashmap::DashMap;
 use dora_core::descriptor::DescriptorExt;
 use dora_message::{
     BuildId, SessionId,
@@ -17,11 +18,18 @@ use std::{
 };
 use uuid::{NoContext, Timestamp, Uuid};
 
+pub(crate) use self::placement::{Placement, allocated_capacity};
+
+mod placement;
+
 /// Plan a dataflow spawn without sending any commands to daemons yet.
 ///
 /// Resolves nodes, generates a UUID, and determines which daemon handles
 /// each node group. The actual spawn commands are prepared but not sent.
-#[tracing::instrument(skip(daemon_connections))]
+///
+/// Nodes of a previous build stay on the daemon that built them, as long as
+/// that daemon is still connected.
+#[tracing::instrument(skip(daemon_connections, running_dataflows, build_placement))]
 pub(super) fn plan_dataflow(
     dataflow_id: Option<Uuid>,
     build_id: Option<BuildId>,
@@ -29,6 +37,8 @@ pub(super) fn plan_dataflow(
     dataflow: &Descriptor,
     local_working_dir: Option<PathBuf>,
     daemon_connections: &DaemonConnections,
+    running_dataflows: &DashMap<Uuid, RunningDataflow>,
+    build_placement: &BTreeMap<NodeId, DaemonId>,
     uv: bool,
     write_events_to: Option<PathBuf>,
     hot_reload: bool,
@@ -36,23 +46,36 @@ pub(super) fn plan_dataflow(
     let nodes = dataflow.resolve_aliases_and_set_defaults()?;
     let uuid = dataflow_id.unwrap_or_else(|| Uuid::new_v7(Timestamp::now(NoContext)));
 
-    let nodes_by_daemon = nodes
-        .values()
-        .into_group_map_by(|n| n.deploy.as_ref().and_then(|d| d.machine.as_ref()));
+    let mut placement = Placement::new(daemon_connections, running_dataflows);
+    let mut node_to_daemon = BTreeMap::new();
+    for node in nodes.values() {
+        let daemon_id = match build_placement.get(&node.id) {
+            Some(daemon_id) if daemon_connections.get(daemon_id).is_some() => {
+                placement.assign(node, daemon_id);
+                daemon_id.clone()
+            }
+            _ => placement
+                .place(node)
+                .wrap_err_with(|| format!("failed to place node `{}`", node.id))?,
+        };
+        node_to_daemon.insert(node.id.clone(), daemon_id);
+    }
+    let nodes_by_daemon = node_to_daemon
+        .iter()
+        .into_group_map_by(|(_, daemon_id)| (*daemon_id).clone());
 
     let mut daemons = BTreeSet::new();
-    let mut node_to_daemon = BTreeMap::new();
     let mut daemon_spawn_commands: Vec<(DaemonId, SpawnDataflowNodes)> = Vec::new();
 
-    for (machine, nodes_on_machine) in &nodes_by_daemon {
-        let spawn_nodes = nodes_on_machine.iter().map(|n| n.id.clone()).collect();
+    for (daemon_id, nodes_on_daemon) in nodes_by_daemon {
+        let spawn_nodes = nodes_on_daemon
+            .iter()
+            .map(|(node_id, _)| (*node_id).clone())
+            .collect();
         tracing::debug!(
-            "Spawning dataflow `{uuid}` on machine `{machine:?}` (nodes: {spawn_nodes:?})"
+            "Spawning dataflow `{uuid}` on daemon `{daemon_id}` (nodes: {spawn_nodes:?})"
         );
 
-        let daemon_id = resolve_daemon_for_machine(daemon_connections, machine.map(|m| m.as_str()))
-            .wrap_err_with(|| format!("failed to resolve daemon for machine `{machine:?}`"))?;
-
         let spawn_command = SpawnDataflowNodes {
             build_id,
             session_id,
@@ -68,12 +91,7 @@ pub(super) fn plan_dataflow(
         };
 
         daemon_spawn_commands.push((daemon_id.clone(), spawn_command));
-        daemons.insert(daemon_id.clone());
-
-        // Map each node on this machine to its daemon
-        for node in nodes_on_machine {
-            node_to_daemon.insert(node.id.clone(), daemon_id.clone());
-        }
+        daemons.insert(daemon_id);
     }
 
     Ok(DataflowPlan {
@@ -111,24 +129,6 @@ pub(super) async fn execute_dataflow_plan(
     Ok(())
 }
 
-fn resolve_daemon_for_machine(
-    daemon_connections: &DaemonConnections,
-    machine: Option<&str>,
-) -> Result<DaemonId, eyre::ErrReport> {
-    let daemon_id = match machine {
-        Some(machine) => daemon_connections
-            .get_matching_daemon_id(machine)
-            .wrap_err_with(|| format!("no matching daemon for machine id {machine:?}"))?
-            .clone(),
-        None => daemon_connections
-            .unnamed()
-            .next()
-            .wrap_err("no unnamed daemon connections")?
-            .clone(),
-    };
-    Ok(daemon_id)
-}
-
 pub struct DataflowPlan {
     pub uuid: Uuid,
     pub daemons: BTreeSet<DaemonId>,
@@ -0,0 +1,372 @@
+//! Placement of nodes on daemons, based on the `_unstable_deploy` section of the nodes.
+
+use std::collections::BTreeMap;
+
+use dashmap::DashMap;
+use dora_message::{
+    common::DaemonId,
+    daemon_to_coordinator::DaemonPlacement,
+    descriptor::{Capacity, Deploy, ResolvedNode},
+};
+use eyre::{ContextCompat, bail};
+use itertools::Itertools;
+use uuid::Uuid;
+
+use crate::{DaemonConnections, RunningDataflow};
+
+/// Tolerance for rounding errors when adding up fractional CPU requirements.
+const CPU_EPSILON: f64 = 1e-9;
+
+/// Assigns nodes to daemons and keeps track of the capacity that they allocate.
+///
+/// - Nodes without a `selector` or `requires` field are placed on the daemon
+///   with the given `machine` ID, or on the unnamed daemon.
+/// - Other nodes are placed on a daemon that has all labels of the `selector`
+///   (and the given `machine` ID, if any) and enough unallocated capacity. If
+///   multiple daemons match, the one with the largest share of unallocated
+///   capacity after placing the node wins, so that load is spread evenly.
+pub(crate) struct Placement {
+    daemons: Vec<Candidate>,
+}
+
+struct Candidate {
+    id: DaemonId,
+    placement: DaemonPlacement,
+    allocated: Capacity,
+}
+
+impl Placement {
+    /// Prepares the placement on the connected daemons, taking the capacity
+    /// allocated by running dataflows into account.
+    pub fn new(
+        daemon_connections: &DaemonConnections,
+        running_dataflows: &DashMap<Uuid, RunningDataflow>,
+    ) -> Self {
+        let mut allocated = allocated_capacity(running_dataflows);
+        let daemons = daemon_connections
+            .iter()
+            .map(|r| (r.key().clone(), r.value().placement.clone()))
+            .collect::<Vec<_>>();
+        Self::from_daemons(
+            daemons
+                .into_iter()
+                .map(|(id, placement)| {
+                    let allocated = allocated.remove(&id).unwrap_or_default();
+                    (id, placement, allocated)
+                })
+                .collect(),
+        )
+    }
+
+    pub(crate) fn from_daemons(daemons: Vec<(DaemonId, DaemonPlacement, Capacity)>) -> Self {
+        let daemons = daemons
+            .into_iter()
+            .map(|(id, placement, allocated)| Candidate {
+                id,
+                placement,
+                allocated,
+            })
+            .sorted_by(|a, b| a.id.cmp(&b.id))
+            .collect();
+        Self { daemons }
+    }
+
+    /// Picks the daemon for the given node and allocates the capacity that the
+    /// node requires on it.
+    pub fn place(&mut self, node: &ResolvedNode) -> eyre::Result<DaemonId> {
+        self.place_deploy(node.deploy.as_ref(), required_capacity(node))
+    }
+
+    fn place_deploy(
+        &mut self,
+        deploy: Option<&Deploy>,
+        required: Capacity,
+    ) -> eyre::Result<DaemonId> {
+        let Some(deploy) = deploy.filter(|d| d.label_based()) else {
+            let machine = deploy.and_then(|d| d.machine.as_deref());
+            let daemon = self
+                .daemons
+                .iter()
+                .find(|d| eligible(d, deploy))
+                .wrap_err_with(|| match machine {
+                    Some(machine) => format!("no matching daemon for machine id {machine:?}"),
+                    None => "no unnamed daemon connections".to_owned(),
+                })?;
+            return Ok(daemon.id.clone());
+        };
+
+        let matching = self
+            .daemons
+            .iter_mut()
+            .filter(|d| eligible(d, Some(deploy)))
+            .collect::<Vec<_>>();
+        if matching.is_empty() {
+            bail!(
+                "no connected daemon matches the selector {}",
+                format_labels(&deploy.selector)
+            );
+        }
+        let Some(best) = matching
+            .into_iter()
+            .filter(|d| fits(&d.placement.capacity, &d.allocated, &required))
+            .min_by(|a, b| {
+                // `min_by` keeps the first of equal elements, i.e. the lowest daemon ID
+                score(b, &required).total_cmp(&score(a, &required))
+            })
+        else {
+            bail!(
+                "no daemon matching the selector {} has enough unallocated capacity for {}",
+                format_labels(&deploy.selector),
+                format_capacity(&required)
+            );
+        };
+        add(&mut best.allocated, &required);
+        Ok(best.id.clone())
+    }
+
+    /// Whether the given daemon matches the `machine` and `selector` fields of
+    /// the node, without checking its capacity.
+    pub fn matches(&self, node: &ResolvedNode, daemon_id: &DaemonId) -> bool {
+        self.daemons
+            .iter()
+            .any(|d| &d.id == daemon_id && eligible(d, node.deploy.as_ref()))
+    }
+
+    /// Allocates the capacity that the given node requires on the given daemon,
+    /// e.g. for nodes that keep their previous placement.
+    pub fn assign(&mut self, node: &ResolvedNode, daemon_id: &DaemonId) {
+        if let Some(daemon) = self.daemons.iter_mut().find(|d| &d.id == daemon_id) {
+            add(&mut daemon.allocated, &required_capacity(node));
+        }
+    }
+}
+
+/// Whether the daemon matches the `machine` ID and `selector` of a node.
+///
+/// Nodes without machine ID and without selector run on unnamed daemons.
+fn eligible(daemon: &Candidate, deploy: Option<&Deploy>) -> bool {
+    let machine = deploy.and_then(|d| d.machine.as_deref());
+    match deploy.filter(|d| d.label_based()) {
+        None => match machine {
+            Some(machine) => daemon.id.matches_machine_id(machine),
+            None => daemon.id.machine_id().is_none(),
+        },
+        Some(deploy) => {
+            machine.is_none_or(|machine| daemon.id.matches_machine_id(machine))
+                && deploy
+                    .selector
+                    .iter()
+                    .all(|(key, value)| daemon.placement.labels.get(key) == Some(value))
+        }
+    }
+}
+
+/// Capacity that the nodes of the running dataflows allocate on each daemon.
+pub(crate) fn allocated_capacity(
+    running_dataflows: &DashMap<Uuid, RunningDataflow>,
+) -> BTreeMap<DaemonId, Capacity> {
+    let mut allocated = BTreeMap::<DaemonId, Capacity>::new();
+    for dataflow in running_dataflows.iter() {
+        for (node_id, node) in &dataflow.nodes {
+            if let Some(daemon_id) = dataflow.node_to_daemon.get(node_id) {
+                add(
+                    allocated.entry(daemon_id.clone()).or_default(),
+                    &required_capacity(node),
+                );
+            }
+        }
+    }
+    allocated
+}
+
+/// The capacity required by all instances of the given node.
+fn required_capacity(node: &ResolvedNode) -> Capacity {
+    let Some(requires) = node.deploy.as_ref().and_then(|d| d.requires.as_ref()) else {
+        return Capacity::default();
+    };
+    let instances = node.replicas.as_ref().map(|r| r.count).unwrap_or(1);
+    Capacity {
+        cpus: requires.cpus.map(|cpus| cpus * instances as f64),
+        memory: requires.memory.map(|memory| memory * instances as u64),
+    }
+}
+
+fn add(capacity: &mut Capacity, other: &Capacity) {
+    if let Some(cpus) = other.cpus {
+        *capacity.cpus.get_or_insert(0.0) += cpus;
+    }
+    if let Some(memory) = other.memory {
+        *capacity.memory.get_or_insert(0) += memory;
+    }
+}
+
+/// Whether the `required` capacity is still available on a daemon.
+///
+/// Daemons that don't report a capacity can't satisfy requirements for it.
+fn fits(capacity: &Capacity, allocated: &Capacity, required: &Capacity) -> bool {
+    let cpus_fit = required.cpus.is_none_or(|required| {
+        capacity.cpus.is_some_and(|capacity| {
+            allocated.cpus.unwrap_or_default() + required <= capacity + CPU_EPSILON
+        })
+    });
+    let memory_fits = required.memory.is_none_or(|required| {
+        capacity
+            .memory
+            .is_some_and(|capacity| allocated.memory.unwrap_or_default() + required <= capacity)
+    });
+    cpus_fit && memory_fits
+}
+
+/// The average share of unallocated capacity of the daemon after placing a
+/// node with the given requirements.
+fn score(daemon: &Candidate, required: &Capacity) -> f64 {
+    let capacity = &daemon.placement.capacity;
+    let mut shares = Vec::new();
+    if let Some(cpus) = capacity.cpus.filter(|cpus| *cpus > 0.0) {
+        let used = daemon.allocated.cpus.unwrap_or_default() + required.cpus.unwrap_or_default();
+        shares.push(1.0 - used / cpus);
+    }
+    if let Some(memory) = capacity.memory.filter(|memory| *memory > 0) {
+        let used =
+            daemon.allocated.memory.unwrap_or_default() + required.memory.unwrap_or_default();
+        shares.push(1.0 - used as f64 / memory as f64);
+    }
+    if shares.is_empty() {
+        0.0
+    } else {
+        shares.iter().sum::<f64>() / shares.len() as f64
+    }
+}
+
+fn format_labels(labels: &BTreeMap<String, String>) -> String {
+    let labels = labels.iter().map(|(k, v)| format!("{k}={v}")).join(", ");
+    format!("`{{{labels}}}`")
+}
+
+fn format_capacity(capacity: &Capacity) -> String {
+    let mut parts = Vec::new();
+    if let Some(cpus) = capacity.cpus {
+        parts.push(format!("{cpus} CPUs"));
+    }
+    if let Some(memory) = capacity.memory {
+        parts.push(format!("{} MB of memory", memory / 1_000_000));
+    }
+    match parts.is_empty() {
+        true => "the node".to_owned(),
+        false => parts.join(" and "),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use dora_message::{
+        common::DaemonId,
+        daemon_to_coordinator::DaemonPlacement,
+        descriptor::{Capacity, Deploy},
+    };
+
+    use super::Placement;
+
+    fn daemon(
+        machine_id: &str,
+        labels: &[(&str, &str)],
+        cpus: f64,
+    ) -> (DaemonId, DaemonPlacement, Capacity) {
+        let placement = DaemonPlacement {
+            labels: labels
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            capacity: Capacity {
+                cpus: Some(cpus),
+                memory: Some(16 << 30),
+            },
+        };
+        (
+            DaemonId::new(Some(machine_id.to_owned())),
+            placement,
+            Capacity::default(),
+        )
+    }
+
+    fn deploy(selector: &[(&str, &str)]) -> Deploy {
+        Deploy {
+            machine: None,
+            working_dir: None,
+            selector: selector
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            requires: None,
+        }
+    }
+
+    fn cpus(cpus: f64) -> Capacity {
+        Capacity {
+            cpus: Some(cpus),
+            memory: None,
+        }
+    }
+
+    #[test]
+    fn nodes_are_placed_by_labels_and_capacity() {
+        let mut placement = Placement::from_daemons(vec![
+            daemon("a", &[("camera", "front")], 4.0),
+            daemon("b", &[("camera", "front")], 8.0),
+            daemon("c", &[("camera", "rear")], 16.0),
+        ]);
+        let front = deploy(&[("camera", "front")]);
+
+        // the daemon with the largest share of free capacity wins, ties go to
+        // the first daemon
+        let placed = (0..6)
+            .map(|_| {
+                let daemon_id = placement.place_deploy(Some(&front), cpus(2.0)).unwrap();
+                daemon_id.machine_id().unwrap().to_owned()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(placed, ["b", "a", "b", "b", "a", "b"]);
+
+        // all daemons with the `camera=front` label are fully allocated now
+        let err = placement
+            .place_deploy(Some(&front), cpus(2.0))
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("enough unallocated capacity"), "{err}");
+
+        let err = placement
+            .place_deploy(Some(&deploy(&[("camera", "top")])), cpus(1.0))
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("no connected daemon matches"), "{err}");
+    }
+
+    #[test]
+    fn nodes_without_selector_use_machine_id() {
+        let mut placement = Placement::from_daemons(vec![
+            daemon("a", &[], 4.0),
+            (DaemonId::new(None), Default::default(), Default::default()),
+        ]);
+        let unnamed = placement.place_deploy(None, Capacity::default()).unwrap();
+        assert_eq!(unnamed.machine_id(), None);
+
+        let pinned = Deploy {
+            machine: Some("a".into()),
+            ..deploy(&[])
+        };
+        let daemon_id = placement
+            .place_deploy(Some(&pinned), Capacity::default())
+            .unwrap();
+        assert_eq!(daemon_id.machine_id(), Some("a"));
+
+        // daemons without reported capacity can't satisfy requirements
+        let requires_cpus = Deploy {
+            requires: Some(cpus(1.0)),
+            ..deploy(&[])
+        };
+        let daemon_id = placement
+            .place_deploy(Some(&requires_cpus), cpus(1.0))
+            .unwrap();
+        assert_eq!(daemon_id.machine_id(), Some("a"));
+    }
+}
@@ -4,12 +4,14 @@ use dora_core::config::{NodeId, OperatorId};
 use dora_message::{
     BuildId,
     cli_to_coordinator::{BuildRequest, CoordinatorControl, StartRequest},
-    common::DaemonId,
+    common::{DaemonId, DataflowChanges, PublishMessage},
     coordinator_to_cli::{
         CheckDataflowReply, DaemonInfo, DataflowIdAndName, DataflowInfo, DataflowList,
-        DataflowListEntry, DataflowResult, DataflowStatus, NodeInfo, NodeMetricsInfo,
+        DataflowListEntry, DataflowResult, DataflowStatus, NodeInfo, NodeMetricsInfo, ReplicaInfo,
         StopDataflowReply, VersionInfo,
     },
+    debug::{DebugCommand, DebugStatus},
+    descriptor::Descriptor,
     tarpc::context::Context,
 };
 use eyre::eyre;
@@ -18,8 +20,9 @@ use tokio::sync::oneshot;
 use uuid::Uuid;
 
 use crate::{
-    build_dataflow, dataflow_result, handle_destroy, reload_dataflow, resolve_name, retrieve_logs,
-    start_dataflow, state::CoordinatorState, stop_dataflow,
+    apply_dataflow, build_dataflow, dataflow_result, debug_dataflow, handle_destroy,
+    publish_message, reload_dataflow, resolve_name, retrieve_logs, run, start_dataflow,
+    state::CoordinatorState, stop_dataflow,
 };
 
 /// Helper to convert eyre errors to strings for tarpc.
@@ -81,10 +84,17 @@ impl CoordinatorControl for CoordinatorControlServer {
             return Err(format!("duplicate build id {build_id}"));
         }
 
-        let result = build_dataflow(request, build_id, &self.state.daemon_connections).await;
+        let result = build_dataflow(
+            request,
+            build_id,
+            &self.state.daemon_connections,
+            &self.state.running_dataflows,
+        )
+        .await;
         match result {
-            Ok(build) => {
+            Ok((build, node_to_daemon)) => {
                 self.state.running_builds.insert(build_id, build);
+                self.state.build_placements.insert(build_id, node_to_daemon);
                 Ok(build_id)
             }
             Err(err) => Err(err_to_string(err)),
@@ -122,6 +132,10 @@ impl CoordinatorControl for CoordinatorControlServer {
         } = request;
 
         let name = name.or_else(|| petname(2, "-"));
+        let build_placement = build_id
+            .and_then(|build_id| self.state.build_placements.get(&build_id))
+            .map(|placement| placement.clone())
+            .unwrap_or_default();
 
         if let Some(name) = name.as_deref() {
             // check that name is unique
@@ -145,6 +159,7 @@ impl CoordinatorControl for CoordinatorControlServer {
             name,
             &self.state.daemon_connections,
             &self.state.running_dataflows,
+            &build_placement,
             uv,
             write_events_to,
             hot_reload,
@@ -273,6 +288,54 @@ impl CoordinatorControl for CoordinatorControlServer {
         .map_err(err_to_string)
     }
 
+    async fn debug(
+        self,
+        _context: Context,
+        dataflow_uuid: Uuid,
+        command: DebugCommand,
+    ) -> Result<DebugStatus, String> {
+        debug_dataflow(
+            &self.state.running_dataflows,
+            dataflow_uuid,
+            command,
+            &self.state.daemon_connections,
+        )
+        .await
+        .map_err(err_to_string)
+    }
+
+    async fn publish(
+        self,
+        _context: Context,
+        dataflow_uuid: Uuid,
+        message: PublishMessage,
+    ) -> Result<(), String> {
+        publish_message(
+            &self.state.running_dataflows,
+            dataflow_uuid,
+            message,
+            &self.state.daemon_connections,
+        )
+        .await
+        .map_err(err_to_string)
+    }
+
+    async fn apply(
+        self,
+        _context: Context,
+        dataflow_uuid: Uuid,
+        dataflow: Descriptor,
+    ) -> Result<DataflowChanges, String> {
+        apply_dataflow(
+            &self.state.running_dataflows,
+            dataflow_uuid,
+            dataflow,
+            &self.state.daemon_connections,
+        )
+        .await
+        .map_err(err_to_string)
+    }
+
     async fn destroy(self, _context: Context) -> Result<(), String> {
         tracing::info!("Received destroy command");
 
@@ -384,28 +447,58 @@ impl CoordinatorControl for CoordinatorControlServer {
         let mut node_infos = Vec::new();
         for r in self.state.running_dataflows.iter() {
             let dataflow = r.value();
-            for node_id in dataflow.nodes.keys() {
+            for (node_id, node) in &dataflow.nodes {
                 // Get the specific daemon this node is running on
                 if let Some(daemon_id) = dataflow.node_to_daemon.get(node_id) {
-                    // Get metrics if available
-                    let metrics = dataflow.node_metrics.get(node_id).map(|m| {
-                        NodeMetricsInfo {
-                            pid: m.pid,
-                            cpu_usage: m.cpu_usage,
-                            // Use 1000 for MB (megabytes) instead of 1024 (mebibytes)
-                            memory_mb: m.memory_bytes as f64 / 1000.0 / 1000.0,
-                            disk_read_mb_s: m.disk_read_bytes.map(|b| b as f64 / 1000.0 / 1000.0),
-                            disk_write_mb_s: m.disk_write_bytes.map(|b| b as f64 / 1000.0 / 1000.0),
-                        }
-                    });
-
-                    node_infos.push(NodeInfo {
-                        dataflow_id: dataflow.uuid,
-                        dataflow_name: dataflow.name.clone(),
-                        node_id: node_id.clone(),
-                        daemon_id: daemon_id.clone(),
-                        metrics,
-                    });
+                    // Replicated nodes are listed once per replica instance
+                    let instances = match &node.replicas {
+                        Some(replicas) => replicas
+                            .instance_ids(node_id)
+                            .into_iter()
+                            .enumerate()
+                            .map(|(index, instance_id)| {
+                                let replica = ReplicaInfo {
+                                    node_id: node_id.clone(),
+                                    index,
+                                    count: replicas.count,
+                                };
+                                (instance_id, Some(replica))
+                            })
+                            .collect(),
+                        None => vec![(node_id.clone(), None)],
+                    };
+                    for (instance_id, replica) in instances {
+                        // Get metrics if available
+                        let metrics = dataflow.node_metrics.get(&instance_id).map(|m| {
+                            NodeMetricsInfo {
+                                pid: m.pid,
+                                cpu_usage: m.cpu_usage,
+                                // Use 1000 for MB (megabytes) instead of 1024 (mebibytes)
+                                memory_mb: m.memory_bytes as f64 / 1000.0 / 1000.0,
+                                disk_read_mb_s: m
+                                    .disk_read_bytes
+                                    .map(|b| b as f64 / 1000.0 / 1000.0),
+                                disk_write_mb_s: m
+                                    .disk_write_bytes
+                                    .map(|b| b as f64 / 1000.0 / 1000.0),
+                                cpu_limit: m.cpu_limit,
+                                memory_limit_mb: m
+                                    .memory_limit_bytes
+                                    .map(|b| b as f64 / 1000.0 / 1000.0),
+                                pids: m.pids,
+                                pids_limit: m.pids_limit,
+                            }
+                        });
+
+                        node_infos.push(NodeInfo {
+                            dataflow_id: dataflow.uuid,
+                            dataflow_name: dataflow.name.clone(),
+                            node_id: instance_id,
+                            daemon_id: daemon_id.clone(),
+                            metrics,
+                            replica,
+                        });
+                    }
                 }
             }
         }
@@ -413,6 +506,7 @@ impl CoordinatorControl for CoordinatorControlServer {
     }
 
     async fn list_daemons(self, _ctx: Context) -> Result<Vec<DaemonInfo>, String> {
+        let mut allocated = run::allocated_capacity(&self.state.running_dataflows);
         Ok(self
             .state
             .daemon_connections
@@ -421,6 +515,10 @@ impl CoordinatorControl for CoordinatorControlServer {
                 daemon_id: r.key().clone(),
                 zenoh_ready: r.value().zenoh_peer_id.is_some(),
                 zenoh_peer_id: r.value().zenoh_peer_id.clone(),
+                realtime_scheduling: r.value().realtime_scheduling,
+                labels: r.value().placement.labels.clone(),
+                capacity: r.value().placement.capacity.clone(),
+                allocated: allocated.remove(r.key()).unwrap_or_default(),
             })
             .collect())
     }
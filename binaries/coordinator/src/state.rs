@@ -1,9 +1,9 @@
-use std::{collections::BTreeMap, sync::Arc};
+use std::{collections::BTreeMap, sync::Arc, time::Instant};
 
 use dashmap::DashMap;
 use dora_core::uhlc::HLC;
 use dora_message::{
-    BuildId, DataflowId, common::DaemonId, daemon_to_coordinator::DataflowDaemonResult,
+    BuildId, DataflowId, common::DaemonId, daemon_to_coordinator::DataflowDaemonResult, id::NodeId,
 };
 use tokio::sync::mpsc;
 
@@ -16,10 +16,17 @@ pub struct CoordinatorState {
     pub clock: Arc<HLC>,
     pub running_builds: DashMap<BuildId, RunningBuild>,
     pub finished_builds: DashMap<BuildId, CachedResult<BuildFinishedResult>>,
+    /// The daemon that each node of a build was placed on.
+    ///
+    /// Dataflows started from a build are spawned on the same daemons.
+    pub build_placements: DashMap<BuildId, BTreeMap<NodeId, DaemonId>>,
     pub running_dataflows: DashMap<DataflowId, RunningDataflow>,
     pub dataflow_results: DashMap<DataflowId, BTreeMap<DaemonId, DataflowDaemonResult>>,
     pub archived_dataflows: DashMap<DataflowId, ArchivedDataflow>,
     pub daemon_connections: DaemonConnections,
+    /// Daemons of restored dataflows that did not reconnect yet after a
+    /// leader change, with the time when the state was restored.
+    pub awaited_daemons: DashMap<DaemonId, Instant>,
     pub daemon_events_tx: mpsc::Sender<Event>,
     pub abort_handle: futures::stream::AbortHandle,
 }
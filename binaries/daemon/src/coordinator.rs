@@ -1,19 +1,19 @@
 use crate::{
-    Event, log, read_last_n_lines, send_with_timestamp,
+    DataflowClock, Event, log, read_last_n_lines, send_with_timestamp,
     socket_stream_utils::{socket_stream_receive, socket_stream_send},
     state::DaemonState,
 };
 use dora_core::uhlc::HLC;
 use dora_message::{
     DataflowId,
-    common::{DaemonId, Timestamped},
+    common::{DaemonId, DependencyStatus, Timestamped},
     coordinator_to_daemon::{
-        BuildDataflowNodes, DaemonControl, DaemonControlRequest, DaemonControlResponse,
-        RegisterResult, SpawnDataflowNodes,
+        ApplyDataflowChanges, BuildDataflowNodes, DaemonControl, DaemonControlRequest,
+        DaemonControlResponse, RegisterResult, SpawnDataflowNodes,
     },
     daemon_to_coordinator::{
         CoordinatorNotifyClient, CoordinatorNotifyRequest, CoordinatorNotifyResponse,
-        CoordinatorRequest, DaemonRegisterRequest,
+        CoordinatorRequest, DaemonPlacement, DaemonRegisterRequest,
     },
     daemon_to_node::NodeEvent,
     id::{NodeId, OperatorId},
@@ -25,7 +25,11 @@ use dora_message::{
 };
 use eyre::Context;
 use futures::StreamExt;
-use std::{net::SocketAddr, sync::Arc, time::Duration};
+use std::{
+    net::SocketAddr,
+    sync::{Arc, atomic::Ordering},
+    time::{Duration, Instant},
+};
 use tokio::{io::AsyncReadExt, net::TcpStream, task::JoinHandle, time::sleep};
 use tracing::warn;
 
@@ -41,34 +45,50 @@ pub struct DaemonRegistration {
     pub coordinator_client: CoordinatorNotifyClient,
 }
 
+/// Settings for reconnecting to a high-availability coordinator cluster.
+pub(crate) struct ReconnectSettings {
+    pub addrs: Vec<SocketAddr>,
+    pub machine_id: Option<String>,
+    pub placement: DaemonPlacement,
+}
+
 /// Connect to the coordinator, register, set up bidirectional tarpc channels.
 ///
 /// 1. Opens a TCP connection, sends `Register`, receives `DaemonId`.
 /// 2. Converts that connection into a tarpc server (coordinator→daemon `DaemonControl`).
 /// 3. Opens a **second** TCP connection, sends `RegisterNotificationChannel`, and
 ///    creates a tarpc client (daemon→coordinator `CoordinatorNotify`).
+///
+/// The given addresses are tried in turn until one accepts the connection. Only
+/// the leader of a high-availability coordinator cluster accepts connections.
 pub async fn register(
-    addr: SocketAddr,
+    addrs: &[SocketAddr],
     machine_id: Option<String>,
+    previous_daemon_id: Option<DaemonId>,
+    placement: DaemonPlacement,
     clock: &Arc<HLC>,
     state: Arc<DaemonState>,
 ) -> eyre::Result<DaemonRegistration> {
+    if addrs.is_empty() {
+        eyre::bail!("no coordinator address given");
+    }
     // --- First connection: registration + coordinator→daemon RPC ---
-    let mut stream = loop {
-        match TcpStream::connect(addr)
-            .await
-            .wrap_err("failed to connect to dora-coordinator")
-        {
-            Err(err) => {
-                warn!(
-                    "Could not connect to: {addr}, with error: {err}. Retrying in {DAEMON_COORDINATOR_RETRY_INTERVAL:#?}.."
-                );
-                sleep(DAEMON_COORDINATOR_RETRY_INTERVAL).await;
-            }
-            Ok(stream) => {
-                break stream;
-            }
-        };
+    let (addr, mut stream) = 'connect: loop {
+        for &addr in addrs {
+            match TcpStream::connect(addr)
+                .await
+                .wrap_err("failed to connect to dora-coordinator")
+            {
+                Err(err) => {
+                    warn!("Could not connect to: {addr}, with error: {err}.");
+                }
+                Ok(stream) => {
+                    break 'connect (addr, stream);
+                }
+            };
+        }
+        warn!("Retrying in {DAEMON_COORDINATOR_RETRY_INTERVAL:#?}..");
+        sleep(DAEMON_COORDINATOR_RETRY_INTERVAL).await;
     };
     stream
         .set_nodelay(true)
@@ -77,8 +97,15 @@ pub async fn register(
     let zenoh_peer_id = state.zenoh_session.as_ref().map(|s| s.zid().to_string());
 
     // Registration handshake (raw length-prefixed JSON)
+    let mut register_request = DaemonRegisterRequest::new(
+        machine_id,
+        zenoh_peer_id,
+        crate::spawn::realtime_scheduling_allowed(),
+        placement,
+    );
+    register_request.daemon_id = previous_daemon_id;
     let register = serde_json::to_vec(&Timestamped {
-        inner: CoordinatorRequest::Register(DaemonRegisterRequest::new(machine_id, zenoh_peer_id)),
+        inner: CoordinatorRequest::Register(register_request),
         timestamp: clock.new_timestamp(),
     })?;
     socket_stream_send(&mut stream, &register)
@@ -149,6 +176,70 @@ pub async fn register(
     })
 }
 
+/// Reconnects to the current leader of a high-availability coordinator
+/// cluster in the background.
+///
+/// Does nothing if only a single coordinator is configured or if a reconnect
+/// is in progress already.
+pub(crate) fn reconnect(state: Arc<DaemonState>) {
+    let Some(settings) = state.reconnect.get() else {
+        return;
+    };
+    if state.reconnecting.swap(true, Ordering::AcqRel) {
+        return;
+    }
+    let addrs = settings.addrs.clone();
+    let machine_id = settings.machine_id.clone();
+    let placement = settings.placement.clone();
+    tokio::spawn(async move {
+        warn!("lost connection to dora-coordinator, reconnecting to the current leader");
+        let clock = state.clock.clone();
+        let daemon_id = state.try_daemon_id().cloned();
+        let result = register(
+            &addrs,
+            machine_id,
+            daemon_id.clone(),
+            placement,
+            &clock,
+            state.clone(),
+        )
+        .await;
+        match result {
+            Ok(registration) => {
+                if Some(&registration.daemon_id) != daemon_id.as_ref() {
+                    warn!(
+                        "dora-coordinator assigned the new ID `{}` on reconnect",
+                        registration.daemon_id
+                    );
+                }
+                state.set_coordinator_client(registration.coordinator_client);
+                *state.last_coordinator_heartbeat.lock().await = Instant::now();
+                let rpc_server_handle = registration.rpc_server_handle;
+                tokio::spawn(async move {
+                    if let Err(err) = rpc_server_handle.await {
+                        tracing::error!("coordinator RPC server task panicked: {err}");
+                    }
+                });
+
+                // results that the previous leader did not receive
+                let unreported: Vec<_> = state
+                    .unreported_results
+                    .iter()
+                    .map(|entry| (*entry.key(), entry.value().clone()))
+                    .collect();
+                for (dataflow_id, result) in unreported {
+                    state.report_dataflow_result(dataflow_id, result);
+                }
+                tracing::info!("reconnected to dora-coordinator");
+            }
+            Err(err) => {
+                tracing::error!("failed to reconnect to dora-coordinator: {err:?}");
+            }
+        }
+        state.reconnecting.store(false, Ordering::Release);
+    });
+}
+
 /// tarpc server that handles coordinator→daemon RPC calls directly using
 /// shared `DaemonState`.
 #[derive(Clone)]
@@ -262,6 +353,7 @@ impl DaemonControl for DaemonControlServer {
                 write_events_to,
                 hot_reload,
                 dataflow_path,
+                clock: DataflowClock::Real,
                 reply_tx: result_tx,
             },
             timestamp: clock.new_timestamp(),
@@ -318,6 +410,7 @@ impl DaemonControl for DaemonControlServer {
                 {
                     tracing::error!("failed to start dataflow: {err:?}");
                 }
+                dataflow.dataflow_started = true;
             }
         }
     }
@@ -362,51 +455,14 @@ impl DaemonControl for DaemonControlServer {
         node_id: NodeId,
         operator_id: Option<OperatorId>,
     ) -> Result<(), String> {
-        use dora_message::daemon_to_node::StopCause;
         let mut dataflow =
             self.state.running.get_mut(&dataflow_id).ok_or_else(|| {
                 format!("Reload failed: no running dataflow with ID `{dataflow_id}`")
             })?;
 
         // If operator_id is None, this is a custom node reload (hot-reload of binary).
-        // Send a Stop event with HotReload reason so the node can save state and exit gracefully.
-        // A fallback SIGTERM is sent after 2 seconds in case the node doesn't respond.
         if operator_id.is_none() {
-            if dataflow.running_nodes.contains_key(&node_id) {
-                tracing::info!(
-                    "Hot-reload: sending Stop(HotReload) to custom node `{}` for restart",
-                    node_id
-                );
-
-                // Set pending_hot_reload flag before sending the stop event to avoid races
-                let running_node = dataflow.running_nodes.get(&node_id).unwrap();
-                running_node
-                    .pending_hot_reload
-                    .store(true, std::sync::atomic::Ordering::Release);
-
-                // Send Stop event with HotReload reason
-                if let Some(channel) = dataflow.subscribe_channels.get(&node_id) {
-                    let _ = send_with_timestamp(
-                        channel,
-                        NodeEvent::Stop {
-                            reason: Some(StopCause::HotReload),
-                        },
-                        &self.state.clock,
-                    );
-                }
-
-                // Spawn a fallback task: if the node doesn't exit within 2 seconds, send SIGTERM
-                if let Some(process) = &running_node.process {
-                    let sender = process.clone_sender();
-                    let pending_flag = running_node.pending_hot_reload.clone();
-                    tokio::spawn(async move {
-                        tokio::time::sleep(std::time::Duration::from_secs(2)).await;
-                        if pending_flag.load(std::sync::atomic::Ordering::Acquire) {
-                            let _ = sender.send(crate::ProcessOperation::SoftKill);
-                        }
-                    });
-                }
-            } else {
+            if !dataflow.hot_reload_node(&node_id, &self.state.clock) {
                 tracing::info!(
                     "Hot-reload: node `{}` is not running, cannot reload",
                     node_id
@@ -416,7 +472,12 @@ impl DaemonControl for DaemonControlServer {
             // For Python operators in Runtime nodes, send the reload event
             let event = NodeEvent::Reload { operator_id };
             if let Some(channel) = dataflow.subscribe_channels.get(&node_id) {
-                match send_with_timestamp(channel, event, &self.state.clock) {
+                match send_with_timestamp(
+                    channel,
+                    event,
+                    &self.state.clock,
+                    dataflow.sim_clock.as_ref(),
+                ) {
                     Ok(()) => {}
                     Err(_) => {
                         dataflow.subscribe_channels.remove(&node_id);
@@ -427,6 +488,53 @@ impl DaemonControl for DaemonControlServer {
         Ok(())
     }
 
+    async fn apply(
+        self,
+        _ctx: tarpc::context::Context,
+        dataflow_id: DataflowId,
+        request: ApplyDataflowChanges,
+    ) -> Result<(), String> {
+        // Route through the event loop, which owns the routing tables and node spawning.
+        let (result_tx, result_rx) = tokio::sync::oneshot::channel();
+        let event = Timestamped {
+            inner: Event::ApplyRequest {
+                dataflow_id,
+                request,
+                reply_tx: result_tx,
+            },
+            timestamp: self.state.clock.new_timestamp(),
+        };
+        self.state
+            .events_tx
+            .send(event)
+            .await
+            .map_err(|_| "daemon event loop closed".to_string())?;
+
+        result_rx
+            .await
+            .map_err(|_| "daemon dropped apply reply channel".to_string())?
+    }
+
+    async fn dependency_status(
+        self,
+        _ctx: tarpc::context::Context,
+        dataflow_id: DataflowId,
+        node_id: NodeId,
+        status: DependencyStatus,
+    ) {
+        let event = Timestamped {
+            inner: Event::DependencyStatus {
+                dataflow_id,
+                node_id,
+                status,
+            },
+            timestamp: self.state.clock.new_timestamp(),
+        };
+        if self.state.events_tx.send(event).await.is_err() {
+            warn!("failed to forward dependency status: daemon event loop closed");
+        }
+    }
+
     async fn stop_node(
         self,
         _ctx: tarpc::context::Context,
@@ -508,6 +616,59 @@ impl DaemonControl for DaemonControlServer {
         .map_err(|err| format!("{err:?}"))
     }
 
+    async fn debug(
+        self,
+        _ctx: tarpc::context::Context,
+        dataflow_id: DataflowId,
+        command: dora_message::debug::DebugCommand,
+    ) -> Result<dora_message::debug::DebugStatus, String> {
+        // Route through the event loop, which owns the input delivery.
+        let (result_tx, result_rx) = tokio::sync::oneshot::channel();
+        let event = Timestamped {
+            inner: Event::DebugRequest {
+                dataflow_id,
+                command,
+                reply_tx: result_tx,
+            },
+            timestamp: self.state.clock.new_timestamp(),
+        };
+        self.state
+            .events_tx
+            .send(event)
+            .await
+            .map_err(|_| "daemon event loop closed".to_string())?;
+
+        result_rx
+            .await
+            .map_err(|_| "daemon dropped debug reply channel".to_string())?
+    }
+
+    async fn publish(
+        self,
+        _ctx: tarpc::context::Context,
+        dataflow_id: DataflowId,
+        message: dora_message::common::PublishMessage,
+    ) -> Result<(), String> {
+        let (result_tx, result_rx) = tokio::sync::oneshot::channel();
+        let event = Timestamped {
+            inner: Event::PublishRequest {
+                dataflow_id,
+                message,
+                reply_tx: result_tx,
+            },
+            timestamp: self.state.clock.new_timestamp(),
+        };
+        self.state
+            .events_tx
+            .send(event)
+            .await
+            .map_err(|_| "daemon event loop closed".to_string())?;
+
+        result_rx
+            .await
+            .map_err(|_| "daemon dropped publish reply channel".to_string())?
+    }
+
     async fn destroy(self, _ctx: tarpc::context::Context) -> Result<(), String> {
         tracing::info!("received destroy command -> exiting");
         // Send a Destroy event to the event loop to trigger shutdown
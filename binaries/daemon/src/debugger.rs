@@ -0,0 +1,412 @@
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+use dora_core::config::{DataId, NodeId};
+use dora_message::{
+    common::DataMessage,
+    daemon_to_node::NodeEvent,
+    debug::{Breakpoint, DebugCommand, DebugStatus, QueuedMessage},
+    node_to_daemon::Timestamped,
+};
+
+use crate::OutputId;
+
+/// Number of held back messages per input if the input has no `queue_size`.
+///
+/// Same default as for the queue sizes that are passed to the node listeners.
+const DEFAULT_QUEUE_SIZE: usize = 10;
+
+/// Holds back input messages of paused nodes for `dora debug`.
+#[derive(Default)]
+pub struct Debugger {
+    /// Set if delivery to all nodes is paused, e.g. after a breakpoint hit.
+    paused_all: bool,
+    paused: BTreeSet<NodeId>,
+    breakpoints: Vec<Breakpoint>,
+    /// Held back messages in the order they were sent.
+    queue: VecDeque<Queued>,
+    /// The `queue_size` of local inputs, which limits the held back messages per input.
+    queue_sizes: BTreeMap<(NodeId, DataId), usize>,
+}
+
+struct Queued {
+    receiver: NodeId,
+    event: Timestamped<NodeEvent>,
+    /// Set for input messages, `None` for other events that were queued behind them.
+    info: Option<QueuedMessage>,
+}
+
+/// Result of [`Debugger::intercept`].
+pub enum Intercepted {
+    /// The message should be delivered now.
+    Deliver(Timestamped<NodeEvent>),
+    /// The message is held back.
+    HeldBack {
+        /// The oldest held back message of the same input, which was dropped
+        /// because the queue of the input was full.
+        dropped: Option<Timestamped<NodeEvent>>,
+    },
+}
+
+impl Debugger {
+    fn is_active(&self) -> bool {
+        self.paused_all
+            || !self.paused.is_empty()
+            || !self.breakpoints.is_empty()
+            || !self.queue.is_empty()
+    }
+
+    /// Sets the `queue_size` of a local input.
+    pub fn set_queue_size(
+        &mut self,
+        receiver: NodeId,
+        input_id: DataId,
+        queue_size: Option<usize>,
+    ) {
+        let queue_size = queue_size.unwrap_or(DEFAULT_QUEUE_SIZE);
+        self.queue_sizes.insert((receiver, input_id), queue_size);
+    }
+
+    pub fn remove_queue_size(&mut self, receiver: &NodeId, input_id: &DataId) {
+        self.queue_sizes
+            .remove(&(receiver.clone(), input_id.clone()));
+    }
+
+    /// Checks whether an input message for `receiver` should be delivered now.
+    ///
+    /// Messages to nodes that have held back messages are queued too, to keep
+    /// the delivery order. If more messages than the `queue_size` of the input
+    /// are held back, the oldest one is dropped.
+    pub fn intercept(
+        &mut self,
+        receiver: &NodeId,
+        source: &OutputId,
+        event: Timestamped<NodeEvent>,
+    ) -> Intercepted {
+        if !self.is_active() {
+            return Intercepted::Deliver(event);
+        }
+        let NodeEvent::Input { id, metadata, data } = &event.inner else {
+            return Intercepted::Deliver(event);
+        };
+        let hit = self.breakpoints.iter().any(|b| {
+            b.node == source.0
+                && b.output == source.1
+                && b.condition
+                    .as_ref()
+                    .is_none_or(|c| c.matches(&metadata.parameters))
+        });
+        if hit && !self.paused_all {
+            tracing::info!(
+                "breakpoint hit on `{}/{}` -> pausing dataflow",
+                source.0,
+                source.1
+            );
+            self.paused_all = true;
+        }
+        let held_back = self.paused_all
+            || self.paused.contains(receiver)
+            || self.queue.iter().any(|q| &q.receiver == receiver);
+        if !held_back {
+            return Intercepted::Deliver(event);
+        }
+        let input_id = id.clone();
+        let info = QueuedMessage {
+            receiver: receiver.clone(),
+            input: id.clone(),
+            source_node: source.0.clone(),
+            source_output: source.1.clone(),
+            timestamp: metadata.timestamp(),
+            parameters: metadata.parameters.clone(),
+            len: data.as_ref().map(|data| match data {
+                DataMessage::Vec(v) => v.len(),
+                DataMessage::SharedMemory { len, .. } => *len,
+            }),
+            breakpoint: hit,
+        };
+        self.queue.push_back(Queued {
+            receiver: receiver.clone(),
+            event,
+            info: Some(info),
+        });
+
+        let queue_size = self
+            .queue_sizes
+            .get(&(receiver.clone(), input_id.clone()))
+            .copied()
+            .unwrap_or(DEFAULT_QUEUE_SIZE);
+        let is_same_input = |q: &Queued| {
+            &q.receiver == receiver && q.info.as_ref().is_some_and(|i| i.input == input_id)
+        };
+        let dropped = if self.queue.iter().filter(|q| is_same_input(q)).count() > queue_size {
+            let oldest = self.queue.iter().position(is_same_input);
+            oldest
+                .and_then(|index| self.queue.remove(index))
+                .map(|q| q.event)
+        } else {
+            None
+        };
+        Intercepted::HeldBack { dropped }
+    }
+
+    /// Checks whether a non-input event for `receiver` should be delivered now.
+    ///
+    /// Events like `InputClosed` are queued behind the held back messages of
+    /// the node, so that the node does not see them before earlier inputs.
+    pub fn hold_back(
+        &mut self,
+        receiver: &NodeId,
+        event: Timestamped<NodeEvent>,
+    ) -> Option<Timestamped<NodeEvent>> {
+        if !self.queue.iter().any(|q| &q.receiver == receiver) {
+            return Some(event);
+        }
+        self.queue.push_back(Queued {
+            receiver: receiver.clone(),
+            event,
+            info: None,
+        });
+        None
+    }
+
+    /// Removes all held back events of the given node, e.g. because it is stopped.
+    pub fn take_node(&mut self, node: &NodeId) -> Vec<Timestamped<NodeEvent>> {
+        self.release(Some(node), usize::MAX)
+            .into_iter()
+            .map(|(_, event)| event)
+            .collect()
+    }
+
+    /// Applies the given command and returns the messages that should be delivered now.
+    pub fn apply(&mut self, command: DebugCommand) -> Vec<(NodeId, Timestamped<NodeEvent>)> {
+        match command {
+            DebugCommand::Pause { node: None } => self.paused_all = true,
+            DebugCommand::Pause { node: Some(node) } => {
+                self.paused.insert(node);
+            }
+            DebugCommand::Resume { node: None } => {
+                self.paused_all = false;
+                self.paused.clear();
+                return self.release(None, usize::MAX);
+            }
+            DebugCommand::Resume { node: Some(node) } => {
+                self.paused.remove(&node);
+                if !self.paused_all {
+                    return self.release(Some(&node), usize::MAX);
+                }
+            }
+            DebugCommand::Step { node, count } => return self.release(node.as_ref(), count),
+            DebugCommand::SetBreakpoint(breakpoint) => {
+                if !self.breakpoints.contains(&breakpoint) {
+                    self.breakpoints.push(breakpoint);
+                }
+            }
+            DebugCommand::ClearBreakpoints => self.breakpoints.clear(),
+            DebugCommand::Status => {}
+        }
+        Vec::new()
+    }
+
+    /// Removes up to `count` held back messages, optionally only for the given node.
+    ///
+    /// Other events that were queued behind the messages are released with them.
+    fn release(
+        &mut self,
+        node: Option<&NodeId>,
+        count: usize,
+    ) -> Vec<(NodeId, Timestamped<NodeEvent>)> {
+        let mut released = Vec::new();
+        let mut released_inputs = 0;
+        // nodes that still have held back messages, whose later events must wait too
+        let mut blocked = BTreeSet::new();
+        let mut remaining = VecDeque::with_capacity(self.queue.len());
+        for queued in self.queue.drain(..) {
+            let release = node.is_none_or(|n| n == &queued.receiver)
+                && !blocked.contains(&queued.receiver)
+                && (queued.info.is_none() || released_inputs < count);
+            if release {
+                if queued.info.is_some() {
+                    released_inputs += 1;
+                }
+                released.push((queued.receiver, queued.event));
+            } else {
+                blocked.insert(queued.receiver.clone());
+                remaining.push_back(queued);
+            }
+        }
+        self.queue = remaining;
+        released
+    }
+
+    pub fn status(&self) -> DebugStatus {
+        DebugStatus {
+            dataflow_paused: self.paused_all,
+            paused_nodes: self.paused.iter().cloned().collect(),
+            breakpoints: self.breakpoints.clone(),
+            queued: self.queue.iter().filter_map(|q| q.info.clone()).collect(),
+            released: 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use dora_core::{
+        config::{DataId, NodeId},
+        uhlc::HLC,
+    };
+    use dora_message::{
+        daemon_to_node::NodeEvent,
+        debug::{Breakpoint, DebugCommand},
+        metadata::{Metadata, Parameter},
+        node_to_daemon::Timestamped,
+    };
+
+    use super::{Debugger, Intercepted};
+    use crate::OutputId;
+
+    fn node(id: &str) -> NodeId {
+        id.to_string().into()
+    }
+
+    fn source() -> OutputId {
+        OutputId(node("detector"), DataId::from("boxes".to_string()))
+    }
+
+    fn input(label: &str) -> Timestamped<NodeEvent> {
+        let clock = HLC::default();
+        let metadata = Metadata::from_parameters(
+            clock.new_timestamp(),
+            crate::empty_type_info(),
+            [("label".to_string(), Parameter::String(label.into()))]
+                .into_iter()
+                .collect(),
+        );
+        Timestamped {
+            inner: NodeEvent::Input {
+                id: "boxes".to_string().into(),
+                metadata,
+                data: None,
+            },
+            timestamp: clock.new_timestamp(),
+        }
+    }
+
+    /// Intercepts an input message to `receiver` and returns whether it is delivered.
+    fn deliver(debugger: &mut Debugger, receiver: &str, label: &str) -> bool {
+        let intercepted = debugger.intercept(&node(receiver), &source(), input(label));
+        matches!(intercepted, Intercepted::Deliver(_))
+    }
+
+    fn label(event: &Timestamped<NodeEvent>) -> &Parameter {
+        match &event.inner {
+            NodeEvent::Input { metadata, .. } => &metadata.parameters["label"],
+            other => panic!("expected input, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn pause_and_step_node() {
+        let mut debugger = Debugger::default();
+        debugger.apply(DebugCommand::Pause {
+            node: Some(node("plot")),
+        });
+        assert!(deliver(&mut debugger, "tracker", "car"));
+        for label in ["person", "car", "dog"] {
+            assert!(!deliver(&mut debugger, "plot", label));
+        }
+        assert_eq!(debugger.status().queued.len(), 3);
+
+        let released = debugger.apply(DebugCommand::Step {
+            node: None,
+            count: 2,
+        });
+        assert_eq!(released.len(), 2);
+        assert_eq!(debugger.status().queued.len(), 1);
+
+        let released = debugger.apply(DebugCommand::Resume {
+            node: Some(node("plot")),
+        });
+        assert_eq!(released.len(), 1);
+        assert!(deliver(&mut debugger, "plot", "person"));
+    }
+
+    #[test]
+    fn breakpoint_pauses_dataflow() {
+        let mut debugger = Debugger::default();
+        debugger.apply(DebugCommand::SetBreakpoint(Breakpoint {
+            node: node("detector"),
+            output: DataId::from("boxes".to_string()),
+            condition: Some("parameters.label == 'person'".parse().unwrap()),
+        }));
+        assert!(deliver(&mut debugger, "plot", "car"));
+        assert!(!deliver(&mut debugger, "plot", "person"));
+        // all nodes are paused after the hit
+        assert!(!deliver(&mut debugger, "tracker", "car"));
+        let status = debugger.status();
+        assert!(status.dataflow_paused);
+        assert!(status.queued[0].breakpoint);
+        assert!(!status.queued[1].breakpoint);
+
+        assert_eq!(debugger.apply(DebugCommand::Resume { node: None }).len(), 2);
+        assert!(!debugger.status().dataflow_paused);
+    }
+
+    #[test]
+    fn held_back_messages_are_limited_by_queue_size() {
+        let mut debugger = Debugger::default();
+        debugger.set_queue_size(node("plot"), "boxes".to_string().into(), Some(2));
+        debugger.apply(DebugCommand::Pause { node: None });
+
+        let mut dropped = Vec::new();
+        for label in ["a", "b", "c", "d"] {
+            match debugger.intercept(&node("plot"), &source(), input(label)) {
+                Intercepted::Deliver(_) => panic!("message should be held back"),
+                Intercepted::HeldBack { dropped: event } => dropped.extend(event),
+            }
+        }
+        assert_eq!(
+            dropped.iter().map(label).collect::<Vec<_>>(),
+            [
+                &Parameter::String("a".into()),
+                &Parameter::String("b".into())
+            ]
+        );
+        let queued = debugger.status().queued;
+        assert_eq!(queued.len(), 2);
+        assert_eq!(queued[0].parameters["label"], Parameter::String("c".into()));
+    }
+
+    #[test]
+    fn other_events_are_queued_behind_held_back_messages() {
+        let mut debugger = Debugger::default();
+        let closed = |id: &str| Timestamped {
+            inner: NodeEvent::InputClosed {
+                id: id.to_string().into(),
+            },
+            timestamp: HLC::default().new_timestamp(),
+        };
+        // nothing is held back yet
+        assert!(debugger.hold_back(&node("plot"), closed("other")).is_some());
+
+        debugger.apply(DebugCommand::Pause {
+            node: Some(node("plot")),
+        });
+        assert!(!deliver(&mut debugger, "plot", "a"));
+        assert!(!deliver(&mut debugger, "plot", "b"));
+        assert!(debugger.hold_back(&node("plot"), closed("boxes")).is_none());
+        assert_eq!(debugger.status().queued.len(), 2);
+
+        // the close event is released together with the last message before it
+        let released = debugger.apply(DebugCommand::Step {
+            node: None,
+            count: 1,
+        });
+        assert_eq!(released.len(), 1);
+        let released = debugger.apply(DebugCommand::Step {
+            node: None,
+            count: 1,
+        });
+        assert_eq!(released.len(), 2);
+        assert!(matches!(released[1].1.inner, NodeEvent::InputClosed { .. }));
+    }
+}
@@ -0,0 +1,455 @@
+//! Ordered node startup through the `depends_on` field of the dataflow.
+//!
+//! Nodes with dependencies are not spawned together with the other nodes.
+//! The daemon keeps them back until every dependency reached its
+//! [`Readiness`] condition. Conditions of local dependencies are observed by
+//! this daemon, conditions of remote dependencies are forwarded by the
+//! coordinator.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::{Path, PathBuf},
+    process::Stdio,
+    sync::Arc,
+    time::Duration,
+};
+
+use dora_core::{descriptor::ResolvedNode, uhlc::HLC};
+use dora_message::{
+    DataflowId,
+    common::Timestamped,
+    descriptor::{EnvValue, Readiness},
+    id::{DataId, NodeId},
+};
+use futures::{FutureExt, future::RemoteHandle};
+use tokio::{process::Command, sync::mpsc};
+
+use crate::Event;
+
+/// Time to wait for the `depends_on` conditions if no `startup_timeout` is set.
+pub const DEFAULT_STARTUP_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Pause between two runs of a failing exec probe.
+const PROBE_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Maximum run time of a single exec probe.
+const PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Startup dependencies of the local nodes of a dataflow.
+#[derive(Default)]
+pub struct StartupDependencies {
+    /// Local nodes that wait for their dependencies before they are spawned.
+    deferred: BTreeMap<NodeId, DeferredNode>,
+    /// Deferred nodes that are spawning, but didn't report their spawn result yet.
+    starting: BTreeSet<NodeId>,
+    /// Conditions of local nodes that local or remote nodes wait for.
+    awaited: BTreeMap<NodeId, BTreeSet<Readiness>>,
+    /// Local nodes that nodes on other machines depend on.
+    ///
+    /// The startup progress of these nodes is reported to the coordinator.
+    remote_dependencies: BTreeSet<NodeId>,
+    /// Exec probes of local nodes, started once the node is spawned.
+    probes: BTreeMap<NodeId, Probe>,
+    /// Running probe tasks, which are canceled when dropped.
+    probe_tasks: BTreeMap<NodeId, RemoteHandle<()>>,
+}
+
+struct DeferredNode {
+    node: ResolvedNode,
+    /// Dependencies whose condition doesn't hold yet.
+    unmet: BTreeMap<NodeId, Readiness>,
+    timeout: Duration,
+    /// Fails the node once the timeout passed, canceled when dropped.
+    _timeout_task: RemoteHandle<()>,
+}
+
+impl StartupDependencies {
+    pub fn new(nodes: &BTreeMap<NodeId, ResolvedNode>, local_nodes: &BTreeSet<NodeId>) -> Self {
+        let mut dependencies = Self::default();
+        for node in nodes.values() {
+            for (dependency, readiness) in &node.depends_on {
+                if !local_nodes.contains(dependency) {
+                    continue;
+                }
+                dependencies
+                    .awaited
+                    .entry(dependency.clone())
+                    .or_default()
+                    .insert(readiness.clone());
+                if !local_nodes.contains(&node.id) {
+                    dependencies.remote_dependencies.insert(dependency.clone());
+                }
+            }
+        }
+        dependencies
+    }
+
+    /// Prepares the exec probes that other nodes wait for on the given local node.
+    pub fn add_probes(&mut self, node: &ResolvedNode, working_dir: &Path) {
+        let commands: BTreeSet<_> = self
+            .awaited
+            .get(&node.id)
+            .into_iter()
+            .flatten()
+            .filter_map(|readiness| match readiness {
+                Readiness::Exec(command) => Some(command.clone()),
+                _ => None,
+            })
+            .collect();
+        if !commands.is_empty() {
+            self.probes.insert(
+                node.id.clone(),
+                Probe {
+                    commands,
+                    working_dir: working_dir.to_owned(),
+                    env: node.env.clone(),
+                },
+            );
+        }
+    }
+
+    /// Keeps the given node back until its dependencies are ready.
+    ///
+    /// Sends an [`Event::StartupTimeout`] if the dependencies are not ready in time.
+    pub fn defer(
+        &mut self,
+        dataflow_id: DataflowId,
+        node: ResolvedNode,
+        events_tx: mpsc::Sender<Timestamped<Event>>,
+        clock: Arc<HLC>,
+    ) {
+        let node_id = node.id.clone();
+        let timeout = node.startup_timeout.unwrap_or(DEFAULT_STARTUP_TIMEOUT);
+        let task = {
+            let node_id = node_id.clone();
+            async move {
+                tokio::time::sleep(timeout).await;
+                let event = Timestamped {
+                    inner: Event::StartupTimeout {
+                        dataflow_id,
+                        node_id,
+                    },
+                    timestamp: clock.new_timestamp(),
+                };
+                let _ = events_tx.send(event).await;
+            }
+        };
+        let (task, handle) = task.remote_handle();
+        tokio::spawn(task);
+        self.deferred.insert(
+            node_id,
+            DeferredNode {
+                unmet: node.depends_on.clone(),
+                node,
+                timeout,
+                _timeout_task: handle,
+            },
+        );
+    }
+
+    pub fn has_deferred(&self) -> bool {
+        !self.deferred.is_empty()
+    }
+
+    /// Whether some deferred nodes are not running yet.
+    pub fn pending(&self) -> bool {
+        !self.deferred.is_empty() || !self.starting.is_empty()
+    }
+
+    /// Whether other nodes wait for the first message on the given output.
+    pub fn awaits_output(&self, node_id: &NodeId, output_id: &DataId) -> bool {
+        self.awaited.get(node_id).is_some_and(|conditions| {
+            conditions
+                .iter()
+                .any(|r| matches!(r, Readiness::Output(output) if output == output_id))
+        })
+    }
+
+    /// Records that a local node reached the given condition.
+    ///
+    /// Returns `false` if no node waits for this condition, or if it was
+    /// reached before.
+    pub fn local_condition_reached(&mut self, node_id: &NodeId, readiness: &Readiness) -> bool {
+        let Some(conditions) = self.awaited.get_mut(node_id) else {
+            return false;
+        };
+        let reached = conditions.remove(readiness);
+        if conditions.is_empty() {
+            self.awaited.remove(node_id);
+        }
+        reached
+    }
+
+    /// Whether the startup progress of the given local node needs to be
+    /// reported to other machines.
+    pub fn has_remote_dependents(&self, node_id: &NodeId) -> bool {
+        self.remote_dependencies.contains(node_id)
+    }
+
+    /// Marks the given condition as met for all deferred nodes.
+    ///
+    /// Returns the nodes whose conditions all hold now, so they can be spawned.
+    pub fn condition_reached(
+        &mut self,
+        dependency: &NodeId,
+        readiness: &Readiness,
+    ) -> Vec<ResolvedNode> {
+        let mut ready = Vec::new();
+        for (node_id, deferred) in &mut self.deferred {
+            if deferred.unmet.get(dependency) == Some(readiness) {
+                deferred.unmet.remove(dependency);
+                if deferred.unmet.is_empty() {
+                    ready.push(node_id.clone());
+                }
+            }
+        }
+        ready
+            .into_iter()
+            .filter_map(|node_id| self.deferred.remove(&node_id))
+            .map(|deferred| {
+                self.starting.insert(deferred.node.id.clone());
+                deferred.node
+            })
+            .collect()
+    }
+
+    /// Handles the exit of a node, which can't reach any further conditions.
+    ///
+    /// Returns the deferred nodes that can't be started anymore, together with
+    /// the error reason. Nodes that depend on these nodes fail too.
+    pub fn dependency_stopped(&mut self, node_id: &NodeId) -> Vec<(NodeId, String)> {
+        self.awaited.remove(node_id);
+        self.probes.remove(node_id);
+        self.probe_tasks.remove(node_id);
+
+        let stopped: Vec<_> = self
+            .deferred
+            .iter()
+            .filter_map(|(id, deferred)| {
+                let readiness = deferred.unmet.get(node_id)?;
+                let reason = format!(
+                    "dependency `{node_id}` stopped before its `depends_on` \
+                    condition was met ({readiness})"
+                );
+                Some((id.clone(), reason))
+            })
+            .collect();
+        self.fail(stopped)
+    }
+
+    /// Handles the startup timeout of the given node.
+    ///
+    /// Returns the failed nodes, which are empty if the node was already started.
+    pub fn timed_out(&mut self, node_id: &NodeId) -> Vec<(NodeId, String)> {
+        let Some(deferred) = self.deferred.get(node_id) else {
+            return Vec::new();
+        };
+        let unmet: Vec<_> = deferred
+            .unmet
+            .iter()
+            .map(|(dependency, readiness)| format!("`{dependency}` ({readiness})"))
+            .collect();
+        let reason = format!(
+            "`depends_on` conditions were not met within {:?}: {}",
+            deferred.timeout,
+            unmet.join(", ")
+        );
+        self.fail(vec![(node_id.clone(), reason)])
+    }
+
+    /// Removes the given deferred nodes and all nodes that depend on them.
+    fn fail(&mut self, mut failed: Vec<(NodeId, String)>) -> Vec<(NodeId, String)> {
+        let mut index = 0;
+        while let Some((node_id, _)) = failed.get(index) {
+            let node_id = node_id.clone();
+            self.deferred.remove(&node_id);
+            for (id, deferred) in &self.deferred {
+                if deferred.unmet.contains_key(&node_id) && !failed.iter().any(|(f, _)| f == id) {
+                    let reason = format!("dependency `{node_id}` failed to start");
+                    failed.push((id.clone(), reason));
+                }
+            }
+            index += 1;
+        }
+        failed
+    }
+
+    /// Removes the given node from the starting nodes once its spawn finished.
+    ///
+    /// Returns the exec probes to run for the node.
+    pub fn spawn_finished(&mut self, node_id: &NodeId) -> Option<Probe> {
+        self.starting.remove(node_id);
+        self.probes.remove(node_id)
+    }
+
+    pub fn add_probe_task(&mut self, node_id: NodeId, task: RemoteHandle<()>) {
+        self.probe_tasks.insert(node_id, task);
+    }
+
+    /// Drops all deferred nodes, e.g. because the dataflow is stopped.
+    pub fn cancel(&mut self) {
+        self.deferred.clear();
+        self.probe_tasks.clear();
+    }
+}
+
+/// Exec probes that other nodes wait for.
+pub struct Probe {
+    commands: BTreeSet<String>,
+    working_dir: PathBuf,
+    env: Option<BTreeMap<String, EnvValue>>,
+}
+
+impl Probe {
+    /// Reruns the probe commands until each of them succeeded once.
+    ///
+    /// Sends an [`Event::NodeReadiness`] for every successful command.
+    pub async fn run(
+        mut self,
+        dataflow_id: DataflowId,
+        node_id: NodeId,
+        events_tx: mpsc::Sender<Timestamped<Event>>,
+        clock: Arc<HLC>,
+    ) {
+        while !self.commands.is_empty() {
+            for command in self.commands.clone() {
+                if !self.probe(&command).await {
+                    continue;
+                }
+                self.commands.remove(&command);
+                let event = Timestamped {
+                    inner: Event::NodeReadiness {
+                        dataflow_id,
+                        node_id: node_id.clone(),
+                        readiness: Readiness::Exec(command),
+                    },
+                    timestamp: clock.new_timestamp(),
+                };
+                if events_tx.send(event).await.is_err() {
+                    return;
+                }
+            }
+            tokio::time::sleep(PROBE_INTERVAL).await;
+        }
+    }
+
+    async fn probe(&self, command: &str) -> bool {
+        // run through the shell, like nodes with `path: shell`
+        let mut cmd = if cfg!(target_os = "windows") {
+            let mut cmd = Command::new("cmd");
+            cmd.args(["/C", command]);
+            cmd
+        } else {
+            let mut cmd = Command::new("sh");
+            cmd.args(["-c", command]);
+            cmd
+        };
+        if let Some(env) = &self.env {
+            for (key, value) in env {
+                cmd.env(key, value.to_string());
+            }
+        }
+        cmd.current_dir(dunce::simplified(&self.working_dir));
+        cmd.stdin(Stdio::null());
+        cmd.stdout(Stdio::null());
+        cmd.stderr(Stdio::null());
+        cmd.kill_on_drop(true);
+
+        match tokio::time::timeout(PROBE_TIMEOUT, cmd.status()).await {
+            Ok(Ok(status)) => status.success(),
+            Ok(Err(err)) => {
+                tracing::debug!("failed to run exec probe `{command}`: {err}");
+                false
+            }
+            Err(_) => {
+                tracing::debug!("exec probe `{command}` timed out");
+                false
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeMap, BTreeSet};
+
+    use dora_core::descriptor::{Descriptor, DescriptorExt};
+    use dora_message::{descriptor::Readiness, id::NodeId};
+
+    use super::{Probe, StartupDependencies};
+
+    fn id(id: &str) -> NodeId {
+        NodeId::from(id.to_owned())
+    }
+
+    #[tokio::test]
+    async fn deferred_nodes_are_released_and_failed() {
+        let yaml = r#"
+nodes:
+  - id: driver
+    path: dynamic
+    outputs: [status]
+  - id: camera
+    path: dynamic
+  - id: planner
+    path: shell
+    depends_on:
+      driver: ready
+      camera: subscribed
+  - id: controller
+    path: shell
+    depends_on:
+      planner: subscribed
+"#;
+        let descriptor = Descriptor::parse(yaml.as_bytes().to_vec()).unwrap();
+        let mut nodes = descriptor.resolve_aliases_and_set_defaults().unwrap();
+        let local: BTreeSet<_> = nodes
+            .keys()
+            .filter(|n| **n != id("camera"))
+            .cloned()
+            .collect();
+        let mut dependencies = StartupDependencies::new(&nodes, &local);
+        assert!(!dependencies.has_remote_dependents(&id("driver")));
+
+        let (events_tx, _events_rx) = tokio::sync::mpsc::channel(1);
+        let clock = std::sync::Arc::new(dora_core::uhlc::HLC::default());
+        for node_id in ["planner", "controller"] {
+            let node = nodes.remove(&id(node_id)).unwrap();
+            dependencies.defer(Default::default(), node, events_tx.clone(), clock.clone());
+        }
+
+        // remote condition of `camera` and local condition of `driver`
+        assert!(
+            dependencies
+                .condition_reached(&id("camera"), &Readiness::Subscribed)
+                .is_empty()
+        );
+        assert!(!dependencies.local_condition_reached(&id("driver"), &Readiness::Subscribed));
+        assert!(dependencies.local_condition_reached(&id("driver"), &Readiness::Ready));
+        let released = dependencies.condition_reached(&id("driver"), &Readiness::Ready);
+        assert_eq!(released.len(), 1);
+        assert_eq!(released[0].id, id("planner"));
+        assert!(dependencies.pending());
+
+        // `controller` can't start if `planner` exits before subscribing
+        let failed: BTreeMap<_, _> = dependencies
+            .dependency_stopped(&id("planner"))
+            .into_iter()
+            .collect();
+        assert!(failed[&id("controller")].contains("dependency `planner` stopped"));
+        dependencies.spawn_finished(&id("planner"));
+        assert!(!dependencies.pending());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn probes_run_through_the_shell() {
+        let probe = Probe {
+            commands: BTreeSet::new(),
+            working_dir: std::env::temp_dir(),
+            env: None,
+        };
+        assert!(probe.probe("test -d . && exit 0").await);
+        assert!(!probe.probe("true && false").await);
+    }
+}
@@ -3,6 +3,7 @@ use dora_core::config::{NodeId, OperatorId};
 use dora_core::descriptor::{
     CoreNodeKind, Descriptor, DescriptorExt, OperatorSource, ResolvedNode,
 };
+use dora_message::common::DataflowChanges;
 use eyre::{Context, Result};
 use notify::{Config, Event as NotifyEvent, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
 use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
@@ -67,9 +68,6 @@ pub fn setup_daemon_watcher(
     // Optionally track the dataflow YAML path
     let dataflow_path_canonical = dataflow_path.and_then(|p| p.canonicalize().ok());
 
-    let current_node_ids = std::sync::Arc::new(std::sync::RwLock::new(
-        nodes.keys().cloned().collect::<BTreeSet<NodeId>>(),
-    ));
     let current_nodes = std::sync::Arc::new(std::sync::RwLock::new(nodes.clone()));
 
     info!(
@@ -128,49 +126,41 @@ pub fn setup_daemon_watcher(
                             Ok(new_descriptor) => {
                                 match new_descriptor.resolve_aliases_and_set_defaults() {
                                     Ok(new_nodes) => {
-                                        let changes = detect_changes(
-                                            &current_node_ids.read().unwrap(),
+                                        let changes = DataflowChanges::between(
                                             &current_nodes.read().unwrap(),
                                             &new_nodes,
                                         );
 
                                         if !changes.is_empty() {
                                             info!(
-                                                "Hot-reload: detected {} changes in dataflow YAML",
-                                                changes.len()
+                                                "Hot-reload: detected changes in dataflow YAML: {changes:?}"
                                             );
 
-                                            *current_node_ids.write().unwrap() =
-                                                new_nodes.keys().cloned().collect();
                                             *current_nodes.write().unwrap() = new_nodes.clone();
 
-                                            for change in changes {
-                                                let event = match change {
-                                                    DataflowChangeEvent::NodeAdded { node_id } => {
-                                                        if let Some(node) = new_nodes.get(&node_id)
-                                                        {
-                                                            DaemonHotReloadEvent::SpawnNode {
-                                                                node_id,
-                                                                node: node.clone(),
-                                                                new_descriptor: new_descriptor
-                                                                    .clone(),
-                                                            }
-                                                        } else {
-                                                            continue;
-                                                        }
-                                                    }
-                                                    DataflowChangeEvent::NodeRemoved {
-                                                        node_id,
-                                                    } => DaemonHotReloadEvent::StopNode { node_id },
-                                                    DataflowChangeEvent::NodeChanged {
-                                                        node_id,
-                                                        new_node,
-                                                    } => DaemonHotReloadEvent::RestartNode {
-                                                        node_id,
-                                                        new_node,
-                                                        new_descriptor: new_descriptor.clone(),
-                                                    },
-                                                };
+                                            // nodes are restarted on input changes too, hot-reload
+                                            // doesn't rewire running nodes
+                                            let added = changes.added.into_iter().map(|node_id| {
+                                                DaemonHotReloadEvent::SpawnNode {
+                                                    node: new_nodes[&node_id].clone(),
+                                                    node_id,
+                                                    new_descriptor: new_descriptor.clone(),
+                                                }
+                                            });
+                                            let removed =
+                                                changes.removed.into_iter().map(|node_id| {
+                                                    DaemonHotReloadEvent::StopNode { node_id }
+                                                });
+                                            let changed = changes
+                                                .restarted
+                                                .into_iter()
+                                                .chain(changes.rewired)
+                                                .map(|node_id| DaemonHotReloadEvent::RestartNode {
+                                                    new_node: new_nodes[&node_id].clone(),
+                                                    node_id,
+                                                    new_descriptor: new_descriptor.clone(),
+                                                });
+                                            for event in added.chain(removed).chain(changed) {
                                                 if tx.blocking_send(event).is_err() {
                                                     break;
                                                 }
@@ -233,61 +223,3 @@ pub fn setup_daemon_watcher(
 
     Ok(watcher)
 }
-
-enum DataflowChangeEvent {
-    NodeAdded {
-        node_id: NodeId,
-    },
-    NodeRemoved {
-        node_id: NodeId,
-    },
-    NodeChanged {
-        node_id: NodeId,
-        new_node: ResolvedNode,
-    },
-}
-
-fn detect_changes(
-    old_node_ids: &BTreeSet<NodeId>,
-    old_nodes: &BTreeMap<NodeId, ResolvedNode>,
-    new_nodes: &BTreeMap<NodeId, ResolvedNode>,
-) -> Vec<DataflowChangeEvent> {
-    let mut changes = Vec::new();
-    let new_node_ids: BTreeSet<NodeId> = new_nodes.keys().cloned().collect();
-
-    for node_id in new_node_ids.difference(old_node_ids) {
-        info!("Hot-reload: detected added node '{}'", node_id);
-        changes.push(DataflowChangeEvent::NodeAdded {
-            node_id: node_id.clone(),
-        });
-    }
-
-    for node_id in old_node_ids.difference(&new_node_ids) {
-        info!("Hot-reload: detected removed node '{}'", node_id);
-        changes.push(DataflowChangeEvent::NodeRemoved {
-            node_id: node_id.clone(),
-        });
-    }
-
-    for node_id in old_node_ids.intersection(&new_node_ids) {
-        let old_node = old_nodes.get(node_id);
-        let new_node = new_nodes.get(node_id);
-        if let (Some(old), Some(new)) = (old_node, new_node) {
-            if node_config_changed(old, new) {
-                info!("Hot-reload: detected changed node '{}'", node_id);
-                changes.push(DataflowChangeEvent::NodeChanged {
-                    node_id: node_id.clone(),
-                    new_node: new.clone(),
-                });
-            }
-        }
-    }
-
-    changes
-}
-
-fn node_config_changed(old: &ResolvedNode, new: &ResolvedNode) -> bool {
-    let old_json = serde_json::to_string(old).unwrap_or_default();
-    let new_json = serde_json::to_string(new).unwrap_or_default();
-    old_json != new_json
-}
@@ -0,0 +1,183 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    num::NonZeroU64,
+    time::Duration,
+};
+
+use dora_core::config::{Input, InputFilter};
+use dora_message::metadata::Metadata;
+
+use crate::{InputId, replicas::ReplicaSets};
+
+/// Applies the `max_rate`, `every`, and `filter` options of local inputs.
+#[derive(Default)]
+pub struct InputGates {
+    gates: HashMap<InputId, InputGate>,
+}
+
+struct InputGate {
+    min_interval: Option<Duration>,
+    every: Option<NonZeroU64>,
+    filter: Option<InputFilter>,
+    /// Number of messages that passed the filter so far.
+    seen: u64,
+    /// Timestamp of the last delivered message.
+    last_delivery: Option<Duration>,
+}
+
+impl InputGates {
+    /// Registers the edge options of the given input, if it has any.
+    ///
+    /// For replicated nodes, `input_id` must refer to the replicated node, not
+    /// to one of its instances.
+    pub fn insert(&mut self, input_id: InputId, input: &Input) {
+        if input.has_edge_options() {
+            self.gates.entry(input_id).or_insert_with(|| InputGate {
+                min_interval: input.max_rate.map(|r| r.min_interval),
+                every: input.every,
+                filter: input.filter.clone(),
+                seen: 0,
+                last_delivery: None,
+            });
+        }
+    }
+
+    /// Removes the edge options of the given input, e.g. because it was rewired.
+    pub fn remove(&mut self, input_id: &InputId) {
+        self.gates.remove(input_id);
+    }
+
+    /// Returns the receivers that should get the given message.
+    ///
+    /// The options of each input are evaluated only once per message, even if
+    /// the input belongs to multiple replica instances.
+    pub fn admit<'a>(
+        &mut self,
+        receivers: &'a BTreeSet<InputId>,
+        metadata: &Metadata,
+        replicas: &ReplicaSets,
+    ) -> Vec<&'a InputId> {
+        if self.gates.is_empty() {
+            return receivers.iter().collect();
+        }
+        let mut decisions: HashMap<InputId, bool> = HashMap::new();
+        receivers
+            .iter()
+            .filter(|(node_id, input_id)| {
+                let key = (replicas.node_id(node_id).clone(), input_id.clone());
+                if let Some(admitted) = decisions.get(&key) {
+                    return *admitted;
+                }
+                let admitted = self
+                    .gates
+                    .get_mut(&key)
+                    .map(|gate| gate.admit(metadata))
+                    .unwrap_or(true);
+                decisions.insert(key, admitted);
+                admitted
+            })
+            .collect()
+    }
+}
+
+impl InputGate {
+    fn admit(&mut self, metadata: &Metadata) -> bool {
+        if let Some(filter) = &self.filter {
+            if !filter.matches(&metadata.parameters) {
+                return false;
+            }
+        }
+        self.seen += 1;
+        if let Some(every) = self.every {
+            if (self.seen - 1) % every.get() != 0 {
+                return false;
+            }
+        }
+        if let Some(min_interval) = self.min_interval {
+            let time = metadata.timestamp().get_time().to_duration();
+            if let Some(last) = self.last_delivery {
+                if time.saturating_sub(last) < min_interval {
+                    return false;
+                }
+            }
+            self.last_delivery = Some(time);
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::BTreeSet, time::Duration};
+
+    use dora_core::{config::Input, uhlc};
+    use dora_message::metadata::{Metadata, Parameter};
+
+    use super::InputGates;
+    use crate::{InputId, replicas::ReplicaSets};
+
+    fn input(yaml: &str) -> Input {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    fn receivers() -> BTreeSet<InputId> {
+        [("sink".to_string().into(), "image".to_string().into())]
+            .into_iter()
+            .collect()
+    }
+
+    fn metadata(millis: u64, label: &str) -> Metadata {
+        let time = uhlc::NTP64::from(Duration::from_millis(millis));
+        let timestamp = uhlc::Timestamp::new(time, uhlc::HLC::default().get_id().to_owned());
+        Metadata::from_parameters(
+            timestamp,
+            crate::empty_type_info(),
+            [("label".to_string(), Parameter::String(label.into()))]
+                .into_iter()
+                .collect(),
+        )
+    }
+
+    fn delivered(gates: &mut InputGates, messages: impl Iterator<Item = Metadata>) -> usize {
+        let receivers = receivers();
+        let replicas = ReplicaSets::default();
+        messages
+            .filter(|m| !gates.admit(&receivers, m, &replicas).is_empty())
+            .count()
+    }
+
+    #[test]
+    fn every_nth_message() {
+        let mut gates = InputGates::default();
+        let input = input("{source: camera/image, every: 10}");
+        gates.insert(receivers().pop_first().unwrap(), &input);
+        assert_eq!(
+            delivered(&mut gates, (0..100).map(|i| metadata(i, "person"))),
+            10
+        );
+    }
+
+    #[test]
+    fn max_rate_uses_message_timestamps() {
+        let mut gates = InputGates::default();
+        let input = input("{source: camera/image, max_rate: 5Hz}");
+        gates.insert(receivers().pop_first().unwrap(), &input);
+        // one message every 10ms for one second
+        assert_eq!(
+            delivered(&mut gates, (0..100).map(|i| metadata(i * 10, "person"))),
+            5
+        );
+    }
+
+    #[test]
+    fn filter_on_metadata() {
+        let mut gates = InputGates::default();
+        let input = input("{source: detector/boxes, filter: \"parameters.label == 'person'\"}");
+        gates.insert(receivers().pop_first().unwrap(), &input);
+        let labels = ["person", "car", "person", "dog"];
+        assert_eq!(
+            delivered(&mut gates, labels.iter().map(|l| metadata(0, l))),
+            2
+        );
+    }
+}
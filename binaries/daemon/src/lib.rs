@@ -1,43 +1,58 @@
 use aligned_vec::{AVec, ConstAlign};
 use crossbeam::queue::ArrayQueue;
+use debugger::{Debugger, Intercepted};
+use dependencies::StartupDependencies;
 use dora_core::{
     build::{self, BuildInfo, PrevGitSource},
-    config::{DataId, Input, InputMapping, NodeId, NodeRunConfig},
+    compression,
+    config::{Compression, DataId, Input, InputFilter, InputMapping, NodeId, NodeRunConfig, Timer},
     descriptor::{
         CoreNodeKind, DYNAMIC_SOURCE, Descriptor, DescriptorExt, ResolvedNode, RuntimeNode,
         read_as_descriptor,
     },
     topics::{
-        DORA_DAEMON_LOCAL_LISTEN_PORT_DEFAULT, LOCALHOST, open_zenoh_session,
-        open_zenoh_session_as_daemon, zenoh_output_publish_topic,
+        DORA_DAEMON_LOCAL_LISTEN_PORT_DEFAULT, LOCALHOST, open_dataflow_zenoh_session,
+        open_zenoh_session, open_zenoh_session_as_daemon, zenoh_output_publish_topic,
+        zenoh_priority,
     },
     uhlc::HLC,
 };
 use dora_message::{
     BuildId, DataflowId, SessionId,
     common::{
-        DaemonId, DataMessage, DropToken, GitSource, LogLevel, NodeError, NodeErrorCause,
-        NodeExitStatus,
+        DaemonId, DataMessage, DependencyStatus, DropToken, GitSource, LogLevel, NodeError,
+        NodeErrorCause, NodeExitStatus, PublishMessage,
     },
     coordinator_to_cli::DataflowResult,
-    coordinator_to_daemon::SpawnDataflowNodes,
-    daemon_to_coordinator::DataflowDaemonResult,
+    coordinator_to_daemon::{ApplyDataflowChanges, SpawnDataflowNodes},
+    daemon_to_coordinator::{DaemonPlacement, DataflowDaemonResult},
     daemon_to_daemon::InterDaemonEvent,
-    daemon_to_node::{DaemonReply, NodeConfig, NodeDropEvent, NodeEvent},
-    descriptor::{NodeSource, RestartPolicy},
+    daemon_to_node::{DaemonReply, NodeConfig, NodeDropEvent, NodeEvent, StopCause},
+    debug::{DebugCommand, DebugStatus},
+    descriptor::{Liveness, NodeSource, Readiness, RestartPolicy},
     metadata::{self, ArrowTypeInfo},
     node_to_daemon::{DynamicNodeEvent, Timestamped},
+    service::{
+        ACTION_MESSAGE_PARAMETER, ActionMessage, GOAL_STATUS_PARAMETER, GoalStatus, REPLY_PREFIX,
+        REQUEST_ID_PARAMETER, REQUEST_PREFIX, SERVICE_ERROR_PARAMETER,
+    },
     tarpc,
 };
 use dora_node_api::{Parameter, arrow::datatypes::DataType};
 use eyre::{Context, ContextCompat, Result, bail, eyre};
 use futures::{FutureExt, TryFutureExt, future, stream};
 use futures_concurrency::stream::Merge;
+use input_gate::InputGates;
+use liveness::LivenessMonitors;
 use local_listener::DynamicNodeEventWrapper;
 use log::{DaemonLogger, DataflowLogger, Logger};
 use pending::PendingNodes;
 use process_wrap::tokio::TokioChildWrapper;
+use replicas::ReplicaSets;
+use services::{PendingRequests, UnansweredRequest};
 use shared_memory_extended::ShmemConf;
+use shutdown::Shutdown;
+use sim_clock::SimClock;
 use spawn::Spawner;
 use std::{
     collections::{BTreeMap, BTreeSet, HashMap, VecDeque},
@@ -51,7 +66,7 @@ use std::{
         Arc,
         atomic::{self, AtomicBool, AtomicU32},
     },
-    time::{Duration, Instant},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 use tokio::{
     fs::File,
@@ -65,19 +80,32 @@ use tokio::{
 use tokio_stream::{Stream, StreamExt, wrappers::ReceiverStream};
 use tracing::{error, warn};
 use uuid::{NoContext, Timestamp, Uuid};
+use watch::WatchedNode;
 
 pub use flume;
 pub use log::LogDestination;
+pub use node_communication::NodeCommunication;
+pub use sim_clock::DataflowClock;
 
 mod coordinator;
+mod debugger;
+mod dependencies;
 mod extract_err_from_stderr;
+mod input_gate;
+mod liveness;
 mod local_listener;
 mod log;
 mod node_communication;
 mod pending;
+mod replicas;
+mod services;
+mod shutdown;
+mod sim_clock;
 mod socket_stream_utils;
 mod spawn;
 pub(crate) mod state;
+mod timer;
+mod watch;
 
 #[cfg(feature = "telemetry")]
 use dora_tracing::telemetry::serialize_context;
@@ -113,20 +141,40 @@ struct NodeBuildTask<F> {
 }
 
 impl Daemon {
+    /// Runs the daemon and connects it to the coordinator.
+    ///
+    /// Multiple coordinator addresses can be given for a high-availability
+    /// coordinator cluster. The daemon connects to the current leader and
+    /// reconnects to the new leader if the connection is lost.
     pub async fn run(
-        coordinator_addr: SocketAddr,
+        coordinator_addrs: Vec<SocketAddr>,
         machine_id: Option<String>,
         local_listen_port: u16,
+        mut placement: DaemonPlacement,
+        node_communication: NodeCommunication,
     ) -> eyre::Result<()> {
         let clock = Arc::new(HLC::default());
 
+        // offer the whole machine for label-based node placement by default
+        if placement.capacity.cpus.is_none() {
+            placement.capacity.cpus = std::thread::available_parallelism()
+                .ok()
+                .map(|cpus| cpus.get() as f64);
+        }
+        if placement.capacity.memory.is_none() {
+            let mut system = sysinfo::System::new();
+            system.refresh_memory();
+            placement.capacity.memory = Some(system.total_memory());
+        }
+
         let mut ctrlc_events = set_up_ctrlc_handler(clock.clone())?;
         let (remote_daemon_events_tx, remote_daemon_events_rx) = flume::bounded(10);
 
         // Use a large channel capacity to prevent deadlock
         let (dora_events_tx, dora_events_rx) = mpsc::channel(1000);
 
-        let zenoh_session = open_zenoh_session_as_daemon(Some(coordinator_addr.ip()))
+        let coordinator_ip = coordinator_addrs.first().map(|addr| addr.ip());
+        let zenoh_session = open_zenoh_session_as_daemon(coordinator_ip)
             .await
             .wrap_err("failed to open zenoh session")?;
 
@@ -137,16 +185,25 @@ impl Daemon {
             dora_events_tx,
             Some(zenoh_session),
             Some(remote_daemon_events_tx),
+            node_communication,
         ));
+        if coordinator_addrs.len() > 1 {
+            let _ = daemon_state.reconnect.set(coordinator::ReconnectSettings {
+                addrs: coordinator_addrs.clone(),
+                machine_id: machine_id.clone(),
+                placement: placement.clone(),
+            });
+        }
 
         let ((daemon_id, coordinator_client), incoming_events) = {
             let incoming_events = set_up_event_stream(
-                coordinator_addr,
+                &coordinator_addrs,
                 &machine_id,
                 &clock,
                 daemon_state.clone(),
                 remote_daemon_events_rx,
                 local_listen_port,
+                placement,
             );
 
             // finish early if ctrl-c is pressed during event stream setup
@@ -198,6 +255,9 @@ impl Daemon {
         write_events_to: Option<PathBuf>,
         stop_after: Option<Duration>,
         hot_reload: bool,
+        watch: bool,
+        dataflow_clock: DataflowClock,
+        node_communication: NodeCommunication,
     ) -> eyre::Result<DataflowResult> {
         let working_dir = dataflow_path
             .canonicalize()
@@ -237,6 +297,11 @@ impl Daemon {
         });
 
         let dataflow_id = Uuid::new_v7(Timestamp::now(NoContext));
+        let watched_nodes = if watch {
+            watch::watched_nodes(&nodes, &working_dir, local_build.as_ref())
+        } else {
+            BTreeMap::new()
+        };
         let spawn_command = SpawnDataflowNodes {
             build_id,
             session_id,
@@ -251,10 +316,35 @@ impl Daemon {
             dataflow_path: Some(dataflow_path.to_path_buf()),
         };
 
-        let clock = Arc::new(HLC::default());
+        let clock = match &dataflow_clock {
+            DataflowClock::Real => Arc::new(HLC::default()),
+            DataflowClock::Simulated { .. } => {
+                // the HLC is moved forward to the simulated time on each clock step,
+                // which can jump arbitrarily far ahead of the clocks of the nodes
+                let max_delta = Duration::from_secs(u32::MAX.into());
+                Arc::new(
+                    dora_core::uhlc::HLCBuilder::new()
+                        .with_clock(|| dora_core::uhlc::NTP64(0))
+                        .with_max_delta(max_delta)
+                        .build(),
+                )
+            }
+        };
 
         let ctrlc_events = ReceiverStream::new(set_up_ctrlc_handler(clock.clone())?);
 
+        // Keep the watcher alive until the dataflow is done
+        let (_source_watcher, source_events) = if watched_nodes.is_empty() {
+            if watch {
+                tracing::warn!("`--watch` has no effect: no node has a `build` command");
+            }
+            (None, ReceiverStream::new(tokio::sync::mpsc::channel(1).1))
+        } else {
+            let (watcher, events) =
+                watch::watch_node_sources(dataflow_id, watched_nodes, clock.clone())?;
+            (Some(watcher), events)
+        };
+
         // Set up optional timeout for --stop-after
         let timeout_events = if let Some(duration) = stop_after {
             let clock = clock.clone();
@@ -300,6 +390,7 @@ impl Daemon {
                     write_events_to: spawn_command.write_events_to,
                     hot_reload: spawn_command.hot_reload,
                     dataflow_path: spawn_command.dataflow_path,
+                    clock: dataflow_clock,
                     reply_tx,
                 },
                 timestamp: spawn_event_clock.new_timestamp(),
@@ -310,6 +401,7 @@ impl Daemon {
             ctrlc_events,
             timeout_events,
             dynamic_node_events,
+            source_events,
         )
             .merge();
 
@@ -335,6 +427,7 @@ impl Daemon {
             dora_events_tx,
             zenoh_session,
             builds_map,
+            node_communication,
         ));
 
         let run_result = Self::run_general(
@@ -452,18 +545,26 @@ impl Daemon {
                 } => self.handle_node_event(event, dataflow, node_id).await?,
                 Event::Dora(event) => self.handle_dora_event(event).await?,
                 Event::DynamicNode(event) => self.handle_dynamic_node_event(event).await?,
+                Event::SimulatedClockIdle => {
+                    // the clock is advanced below
+                }
                 Event::HeartbeatInterval => {
                     if let Some(client) = self.state.coordinator_client() {
                         // Fire-and-forget: notify the coordinator we're alive.
                         // Don't block the event loop waiting for the RPC response.
-                        let client = client.clone();
+                        let state = self.state.clone();
                         tokio::spawn(async move {
-                            let _ = client.heartbeat(tarpc::context::current()).await;
+                            if client.heartbeat(tarpc::context::current()).await.is_err() {
+                                coordinator::reconnect(state);
+                            }
                         });
 
                         let last_hb = self.state.last_coordinator_heartbeat.lock().await;
                         if last_hb.elapsed() > Duration::from_secs(20) {
-                            bail!("lost connection to coordinator")
+                            if self.state.reconnect.get().is_none() {
+                                bail!("lost connection to coordinator")
+                            }
+                            coordinator::reconnect(self.state.clone());
                         }
                     }
                 }
@@ -482,6 +583,7 @@ impl Daemon {
                     write_events_to,
                     hot_reload,
                     dataflow_path,
+                    clock,
                     reply_tx,
                 } => {
                     let result = self
@@ -496,10 +598,129 @@ impl Daemon {
                             write_events_to,
                             hot_reload,
                             dataflow_path,
+                            clock,
                         )
                         .await;
                     let _ = reply_tx.send(result);
                 }
+                Event::PublishRequest {
+                    dataflow_id,
+                    message,
+                    reply_tx,
+                } => {
+                    let result = self
+                        .publish(dataflow_id, message)
+                        .await
+                        .map_err(|err| format!("{err:?}"));
+                    let _ = reply_tx.send(result);
+                }
+                Event::ApplyRequest {
+                    dataflow_id,
+                    request,
+                    reply_tx,
+                } => {
+                    let result = self
+                        .apply_changes(dataflow_id, request)
+                        .await
+                        .map_err(|err| format!("{err:?}"));
+                    let _ = reply_tx.send(result);
+                }
+                Event::DebugRequest {
+                    dataflow_id,
+                    command,
+                    reply_tx,
+                } => {
+                    let result = match self.state.running.get_mut(&dataflow_id) {
+                        Some(mut dataflow) => {
+                            let released = dataflow.debugger.apply(command);
+                            let count = released.len();
+                            deliver_held_back_messages(&mut dataflow, released, &self.state.clock)
+                                .await
+                                .map(|()| DebugStatus {
+                                    released: count,
+                                    ..dataflow.debugger.status()
+                                })
+                                .map_err(|err| format!("{err:?}"))
+                        }
+                        None => Err(format!("no running dataflow with ID `{dataflow_id}`")),
+                    };
+                    let _ = reply_tx.send(result);
+                }
+                Event::ServiceRequestTimeout {
+                    dataflow_id,
+                    request_id,
+                } => self.handle_service_request_timeout(dataflow_id, request_id),
+                Event::NodeSourceChanged {
+                    dataflow_id,
+                    node_id,
+                    node,
+                } => {
+                    if let Err(err) = self
+                        .handle_node_source_changed(dataflow_id, node_id, node)
+                        .await
+                    {
+                        tracing::error!("failed to rebuild node: {err:?}");
+                    }
+                }
+                Event::NodeRebuilt {
+                    dataflow_id,
+                    node_id,
+                    node,
+                    result,
+                } => {
+                    if let Err(err) = self
+                        .handle_node_rebuilt(dataflow_id, node_id, node, result)
+                        .await
+                    {
+                        tracing::error!("failed to rebuild node: {err:?}");
+                    }
+                }
+                Event::NodeReadiness {
+                    dataflow_id,
+                    node_id,
+                    readiness,
+                } => {
+                    self.handle_local_readiness(dataflow_id, node_id, readiness)
+                        .await?
+                }
+                Event::DependencyStatus {
+                    dataflow_id,
+                    node_id,
+                    status,
+                } => match status {
+                    DependencyStatus::Reached(readiness) => {
+                        self.handle_dependency_reached(dataflow_id, &node_id, &readiness)
+                            .await?
+                    }
+                    DependencyStatus::Stopped => {
+                        self.handle_dependency_stopped(dataflow_id, &node_id)
+                            .await?;
+                        self.finish_dataflow_if_done(dataflow_id).await?;
+                    }
+                },
+                Event::StartupTimeout {
+                    dataflow_id,
+                    node_id,
+                } => {
+                    let failed = match self.state.running.get_mut(&dataflow_id) {
+                        Some(mut dataflow) => dataflow.dependencies.timed_out(&node_id),
+                        None => Vec::new(),
+                    };
+                    self.fail_deferred_nodes(dataflow_id, failed).await?;
+                    self.finish_dataflow_if_done(dataflow_id).await?;
+                }
+                Event::ShutdownDeadline { dataflow_id } => {
+                    if let Some(mut dataflow) = self.state.running.get_mut(&dataflow_id) {
+                        if let Some(shutdown) = &mut dataflow.shutdown {
+                            shutdown.deadline_passed();
+                        }
+                    }
+                    self.continue_shutdown(dataflow_id).await;
+                }
+                Event::LivenessCheck {
+                    dataflow_id,
+                    node_id,
+                } => self.check_liveness(dataflow_id, node_id).await,
                 Event::StopDataflowRequest {
                     dataflow_id,
                     grace_duration,
@@ -518,7 +739,7 @@ impl Daemon {
                             let df = &mut *dataflow;
                             df.pending_nodes
                                 .handle_dataflow_stop(
-                                    &self.state.coordinator_client().cloned(),
+                                    &self.state.coordinator_client(),
                                     &self.state.clock,
                                     &mut df.cascading_error_causes,
                                     &df.dynamic_nodes,
@@ -537,6 +758,7 @@ impl Daemon {
                                 .stop_all_after_pending(&self.state, grace_duration, force)
                                 .map_err(|err| format!("{err:?}"))?
                         };
+                        self.continue_shutdown(dataflow_id).await;
                         // DashMap guard is dropped — safe to call finish_dataflow.
                         if matches!(finish_when, FinishDataflowWhen::Now) {
                             self.state
@@ -586,6 +808,20 @@ impl Daemon {
                                     ._listener_tasks
                                     .push(ListenerTask(abort_handle.clone()));
                             }
+                            if let Some(probe) = dataflow.dependencies.spawn_finished(&node_id) {
+                                let (task, handle) = probe
+                                    .run(
+                                        dataflow_id,
+                                        node_id.clone(),
+                                        self.state.events_tx.clone(),
+                                        self.state.clock.clone(),
+                                    )
+                                    .remote_handle();
+                                tokio::spawn(task);
+                                dataflow
+                                    .dependencies
+                                    .add_probe_task(node_id.clone(), handle);
+                            }
                             dataflow.running_nodes.insert(node_id, running_node);
                         } else {
                             tracing::error!(
@@ -594,6 +830,9 @@ impl Daemon {
                         }
                     }
                     Err(error) => {
+                        if let Some(mut dataflow) = self.state.running.get_mut(&dataflow_id) {
+                            dataflow.dependencies.spawn_finished(&node_id);
+                        }
                         self.state
                             .dataflow_node_results
                             .entry(dataflow_id)
@@ -608,7 +847,6 @@ impl Daemon {
                     result,
                 } => {
                     if let Some(client) = self.state.coordinator_client() {
-                        let client = client.clone();
                         let result = result.map_err(|err| format!("{err:?}"));
                         tokio::spawn(async move {
                             if let Err(err) = client
@@ -644,6 +882,8 @@ impl Daemon {
                 }
             }
 
+            self.advance_simulated_clock().await?;
+
             // warn if event handling took too long -> the main loop should never be blocked for too long
             let elapsed = start.elapsed();
             if elapsed > Duration::from_millis(100) {
@@ -672,6 +912,80 @@ impl Daemon {
             .collect())
     }
 
+    /// Advances the simulated clock by one step if all nodes are idle.
+    async fn advance_simulated_clock(&mut self) -> eyre::Result<()> {
+        let steps: Vec<_> = self
+            .state
+            .running
+            .iter()
+            .filter_map(|dataflow| {
+                let sim_clock = dataflow.sim_clock.clone()?;
+                let step = sim_clock.advance()?;
+                Some((dataflow.id, sim_clock, step))
+            })
+            .collect();
+        for (dataflow_id, sim_clock, step) in steps {
+            let sent_before = sim_clock.events_sent();
+            self.handle_simulated_clock_step(dataflow_id, step).await?;
+            if sim_clock.events_sent() == sent_before {
+                // nothing happened at this time -> continue with the next step,
+                // but give other events a chance to be handled first
+                let event = Timestamped {
+                    inner: Event::SimulatedClockIdle,
+                    timestamp: self.state.clock.new_timestamp(),
+                };
+                let _ = self.state.events_tx.try_send(event);
+            }
+        }
+        Ok(())
+    }
+
+    /// Sends the timer ticks and wake-ups that are due at a new simulated time.
+    async fn handle_simulated_clock_step(
+        &mut self,
+        dataflow_id: DataflowId,
+        step: sim_clock::Step,
+    ) -> eyre::Result<()> {
+        // timestamps of the sent events must not be behind the new simulated time
+        let time = dora_core::uhlc::Timestamp::new(step.time, *self.state.clock.get_id());
+        if let Err(err) = self.state.clock.update_with_timestamp(&time) {
+            tracing::warn!("failed to move HLC to simulated time: {err}");
+        }
+        if let Some(dataflow) = self.state.running.get(&dataflow_id) {
+            for (node_id, deadline) in step.wake {
+                if let Some(channel) = dataflow.subscribe_channels.get(&node_id) {
+                    let event = NodeEvent::SimulatedTimeout { deadline };
+                    let _ = send_with_timestamp(
+                        channel,
+                        event,
+                        &self.state.clock,
+                        dataflow.sim_clock.as_ref(),
+                    );
+                }
+            }
+        }
+        for timer in step.timers {
+            let mut parameters = BTreeMap::new();
+            timer::add_tick_parameters(
+                &mut parameters,
+                UNIX_EPOCH + step.time.to_duration(),
+                Duration::ZERO,
+            );
+            let metadata = metadata::Metadata::from_parameters(
+                self.state.clock.new_timestamp(),
+                empty_type_info(),
+                parameters,
+            );
+            self.handle_dora_event(DoraEvent::Timer {
+                dataflow_id,
+                timer,
+                metadata,
+            })
+            .await?;
+        }
+        Ok(())
+    }
+
     async fn trigger_manual_stop(&mut self) -> eyre::Result<()> {
         // Collect dataflow IDs first, then process one at a time to avoid
         // holding DashMap guards across `.await` points.
@@ -693,6 +1007,7 @@ impl Daemon {
                     dataflows_to_finish.push(dataflow_id);
                 }
             }
+            self.continue_shutdown(dataflow_id).await;
         }
 
         // DashMap guards are dropped — safe to call finish_dataflow.
@@ -717,6 +1032,7 @@ impl Daemon {
         write_events_to: Option<PathBuf>,
         hot_reload: bool,
         dataflow_path: Option<PathBuf>,
+        clock: DataflowClock,
     ) -> Result<(), String> {
         // Resolve base working dir — for spawn we use the daemon's working dir
         let base_working_dir = match local_working_dir {
@@ -749,6 +1065,7 @@ impl Daemon {
                 write_events_to,
                 hot_reload,
                 dataflow_path,
+                clock,
             )
             .await;
         let (trigger_result, result_task) = match result {
@@ -781,114 +1098,745 @@ impl Daemon {
         trigger_result
     }
 
-    async fn collect_and_send_metrics(&mut self) -> eyre::Result<()> {
-        use dora_message::daemon_to_coordinator::NodeMetrics;
-        use sysinfo::{Pid, ProcessRefreshKind, ProcessesToUpdate};
-
-        if self.state.coordinator_client().is_none() {
-            return Ok(());
-        }
-
-        // Reuse system instance for metrics collection
-        let system = &mut self.metrics_system;
-
-        // Metrics are collected every 2 seconds (metrics_interval)
-        const METRICS_INTERVAL_SECS: f64 = 2.0;
-
-        // Collect metrics for all running dataflows
-        for entry in self.state.running.iter() {
-            let (dataflow_id, dataflow) = (entry.key(), entry.value());
-            let mut metrics = BTreeMap::new();
-
-            // Collect all PIDs for this dataflow
-            let pids: Vec<Pid> = dataflow
-                .running_nodes
-                .values()
-                .filter_map(|node| {
-                    node.pid
-                        .as_ref()
-                        .map(|pid| Pid::from_u32(pid.load(atomic::Ordering::Acquire)))
-                })
-                .collect();
+    /// Applies the node changes of `dora apply` to a running dataflow.
+    ///
+    /// Untouched nodes keep running. Removed and restarted nodes are stopped,
+    /// rewired nodes get their changed inputs replaced. With `dry_run`, only
+    /// the checks are run.
+    async fn apply_changes(
+        &mut self,
+        dataflow_id: DataflowId,
+        request: ApplyDataflowChanges,
+    ) -> eyre::Result<()> {
+        let ApplyDataflowChanges {
+            nodes,
+            dataflow_descriptor,
+            local_nodes,
+            changes,
+            dry_run,
+        } = request;
+        let clock = self.state.clock.clone();
+        let mut to_spawn = Vec::new();
+        {
+            let mut dataflow = self
+                .state
+                .running
+                .get_mut(&dataflow_id)
+                .wrap_err_with(|| format!("no running dataflow with ID `{dataflow_id}`"))?;
+            if !dataflow.dataflow_started {
+                bail!("dataflow `{dataflow_id}` is not started yet");
+            }
+            if dataflow.stop_sent {
+                bail!("dataflow `{dataflow_id}` is stopping");
+            }
+            let old_nodes = dataflow.descriptor.resolve_aliases_and_set_defaults()?;
+            if let Some(node_id) = local_nodes.iter().find(|id| !nodes.contains_key(*id)) {
+                bail!("local node `{node_id}` is not part of the updated dataflow");
+            }
+            let spawns_nodes = changes
+                .added
+                .iter()
+                .chain(&changes.restarted)
+                .any(|id| local_nodes.contains(id));
+            if spawns_nodes && !self.state.working_dir.contains_key(&dataflow_id) {
+                bail!("no working dir for dataflow `{dataflow_id}`");
+            }
+            if dry_run {
+                return Ok(());
+            }
+            let node_inputs_of = |nodes: &BTreeMap<NodeId, ResolvedNode>, node_id: &NodeId| {
+                nodes.get(node_id).map(node_inputs).unwrap_or_default()
+            };
 
-            if !pids.is_empty() {
-                // Refresh process metrics (cpu, memory, disk)
-                let refresh_kind = ProcessRefreshKind::nothing()
-                    .with_cpu()
-                    .with_memory()
-                    .with_disk_usage();
-                system.refresh_processes_specifics(
-                    ProcessesToUpdate::Some(&pids),
-                    true,
-                    refresh_kind,
-                );
+            // stop removed and restarted nodes and remove their inputs
+            for node_id in changes.removed.iter().chain(&changes.restarted) {
+                for (input_id, input) in node_inputs_of(&old_nodes, node_id) {
+                    dataflow.remove_input_route(node_id, &input_id, &input);
+                }
+                dataflow.open_inputs.remove(node_id);
+                let reason = if changes.restarted.contains(node_id) {
+                    StopCause::HotReload
+                } else {
+                    StopCause::Manual
+                };
+                let wait_for_exit = dataflow.stop_node_for_apply(node_id, reason, &clock);
+                if let Some(node) = nodes.get(node_id).filter(|_| local_nodes.contains(node_id)) {
+                    if wait_for_exit {
+                        dataflow
+                            .pending_respawns
+                            .insert(node_id.clone(), node.clone());
+                    } else {
+                        to_spawn.push(node.clone());
+                    }
+                }
+            }
 
-                // Collect metrics for each node
-                for (node_id, running_node) in &dataflow.running_nodes {
-                    if let Some(pid) = running_node.pid.as_ref() {
-                        let pid = pid.load(atomic::Ordering::Acquire);
-                        let sys_pid = Pid::from_u32(pid);
-                        if let Some(process) = system.process(sys_pid) {
-                            let disk_usage = process.disk_usage();
-                            // Divide by metrics_interval to get per-second averages
-                            metrics.insert(
-                                node_id.clone(),
-                                NodeMetrics {
-                                    pid,
-                                    cpu_usage: process.cpu_usage(),
-                                    memory_bytes: process.memory(),
-                                    disk_read_bytes: Some(
-                                        (disk_usage.read_bytes as f64 / METRICS_INTERVAL_SECS)
-                                            as u64,
-                                    ),
-                                    disk_write_bytes: Some(
-                                        (disk_usage.written_bytes as f64 / METRICS_INTERVAL_SECS)
-                                            as u64,
-                                    ),
-                                },
-                            );
-                        }
+            // replace the changed inputs of rewired nodes
+            for node_id in changes
+                .rewired
+                .iter()
+                .filter(|id| local_nodes.contains(*id))
+            {
+                let old_inputs = node_inputs_of(&old_nodes, node_id);
+                let new_inputs = node_inputs_of(&nodes, node_id);
+                for (input_id, input) in &old_inputs {
+                    if new_inputs.get(input_id) == Some(input) {
+                        continue;
+                    }
+                    if !new_inputs.contains_key(input_id) {
+                        close_input(&mut dataflow, node_id, input_id, &clock);
+                    }
+                    dataflow.remove_input_route(node_id, input_id, input);
+                }
+                for (input_id, input) in new_inputs {
+                    if old_inputs.get(&input_id) != Some(&input) {
+                        dataflow.add_input_route(node_id, input_id, input);
                     }
                 }
             }
 
-            // Send metrics to coordinator if we have any (fire-and-forget).
-            if !metrics.is_empty() {
-                if let Some(client) = self.state.coordinator_client() {
-                    let client = client.clone();
-                    let dataflow_id = *dataflow_id;
-                    tokio::spawn(async move {
-                        let _ = client
-                            .node_metrics(tarpc::context::current(), dataflow_id, metrics)
-                            .await;
-                    });
+            // route the inputs of added and restarted nodes
+            for node_id in changes.added.iter().chain(&changes.restarted) {
+                if !local_nodes.contains(node_id) {
+                    continue;
+                }
+                for (input_id, input) in node_inputs_of(&nodes, node_id) {
+                    dataflow.add_input_route(node_id, input_id, input);
                 }
+                if changes.added.contains(node_id) {
+                    to_spawn.extend(nodes.get(node_id).cloned());
+                }
+            }
+
+            // update the edges to remote nodes
+            dataflow.open_external_mappings.clear();
+            dataflow.external_input_filters.clear();
+            for node in nodes.values().filter(|n| !local_nodes.contains(&n.id)) {
+                for (_, input) in node_inputs(node) {
+                    if let InputMapping::User(mapping) = &input.mapping {
+                        let output_id = OutputId(mapping.source.clone(), mapping.output.clone());
+                        dataflow.add_remote_input(output_id, input);
+                    }
+                }
+            }
+            let remote_outputs: Vec<_> = dataflow
+                .mappings
+                .keys()
+                .filter(|o| !local_nodes.contains(&o.0))
+                .cloned()
+                .collect();
+            for output_id in remote_outputs {
+                Self::subscribe_to_remote_output(&self.state, &mut dataflow, output_id).await?;
             }
+
+            dataflow.descriptor = dataflow_descriptor;
+            // start the timers of new inputs
+            dataflow.start(&self.state.events_tx, &clock).await?;
         }
 
-        Ok(())
+        self.spawn_additional_nodes(dataflow_id, to_spawn).await
     }
 
-    async fn handle_inter_daemon_event(&mut self, event: InterDaemonEvent) -> eyre::Result<()> {
-        match event {
-            InterDaemonEvent::Output {
-                dataflow_id,
-                node_id,
-                output_id,
-                metadata,
-                data,
-            } => {
-                let inner = async {
-                    let mut dataflow =
-                        self.state.running.get_mut(&dataflow_id).wrap_err_with(|| {
-                            format!("send out failed: no running dataflow with ID `{dataflow_id}`")
-                        })?;
-                    send_output_to_local_receivers(
-                        node_id.clone(),
-                        output_id.clone(),
-                        &mut dataflow,
-                        &metadata,
-                        data.map(DataMessage::Vec),
+    /// Spawns nodes after the dataflow was set up, e.g. nodes that were added or
+    /// restarted by `dora apply` or nodes whose `depends_on` conditions are met.
+    async fn spawn_additional_nodes(
+        &mut self,
+        dataflow_id: DataflowId,
+        nodes: Vec<ResolvedNode>,
+    ) -> eyre::Result<()> {
+        if nodes.is_empty() {
+            return Ok(());
+        }
+        let mut logger = self
+            .logger
+            .for_dataflow(dataflow_id)
+            .try_clone()
+            .await
+            .context("failed to clone logger")?;
+        let base_working_dir = self
+            .state
+            .working_dir
+            .get(&dataflow_id)
+            .map(|entry| entry.clone())
+            .wrap_err_with(|| format!("no working dir for dataflow `{dataflow_id}`"))?;
+
+        let mut tasks = Vec::new();
+        let mut failed = Vec::new();
+        {
+            let mut dataflow = self
+                .state
+                .running
+                .get_mut(&dataflow_id)
+                .wrap_err_with(|| format!("no running dataflow with ID `{dataflow_id}`"))?;
+            let spawner = Spawner {
+                dataflow_id,
+                daemon_tx: self.state.events_tx.clone(),
+                dataflow_descriptor: dataflow.descriptor.clone(),
+                clock: self.state.clock.clone(),
+                uv: dataflow.uv,
+                node_communication: self.state.node_communication,
+                sim_clock: dataflow.sim_clock.clone(),
+            };
+            for node in nodes {
+                let node_id = node.id.clone();
+                let dynamic_node = node.kind.dynamic();
+                if dynamic_node {
+                    dataflow.dynamic_nodes.insert(node_id.clone());
+                }
+                let node_stderr_most_recent = dataflow
+                    .node_stderr_most_recent
+                    .entry(node_id.clone())
+                    .or_insert_with(|| Arc::new(ArrayQueue::new(STDERR_LOG_LINES_MAX)))
+                    .clone();
+                let node_working_dir = dataflow
+                    .build_working_dirs
+                    .get(dataflow.replicas.node_id(&node_id))
+                    .cloned()
+                    .or_else(|| {
+                        node.deploy
+                            .as_ref()
+                            .and_then(|d| d.working_dir.as_ref().map(|d| base_working_dir.join(d)))
+                    })
+                    .unwrap_or(base_working_dir.clone());
+                let node_write_events_to = dataflow
+                    .write_events_to
+                    .as_ref()
+                    .map(|p| p.join(format!("inputs-{node_id}.json")));
+                let result = spawner
+                    .clone()
+                    .spawn_node(
+                        node,
+                        node_working_dir,
+                        node_stderr_most_recent,
+                        node_write_events_to,
+                        &mut logger.reborrow().for_node(node_id.clone()),
+                    )
+                    .await
+                    .wrap_err_with(|| format!("failed to spawn node `{node_id}`"));
+                match result {
+                    Ok(task) => tasks.push(NodeBuildTask {
+                        node_id,
+                        task,
+                        dynamic_node,
+                    }),
+                    Err(err) => failed.push((node_id, dynamic_node, err)),
+                }
+            }
+        }
+        for (node_id, dynamic_node, err) in failed {
+            logger
+                .log(
+                    LogLevel::Error,
+                    Some(node_id.clone()),
+                    Some("daemon".into()),
+                    format!("{err:?}"),
+                )
+                .await;
+            let event = Event::SpawnNodeResult {
+                dataflow_id,
+                node_id,
+                dynamic_node,
+                result: Err(NodeError {
+                    timestamp: self.state.clock.new_timestamp(),
+                    cause: NodeErrorCause::FailedToSpawn(format!("{err:?}")),
+                    exit_status: NodeExitStatus::Unknown,
+                }),
+            };
+            let _ = self
+                .state
+                .events_tx
+                .send(Timestamped {
+                    inner: event,
+                    timestamp: self.state.clock.new_timestamp(),
+                })
+                .await;
+        }
+
+        let spawn_result = Self::spawn_prepared_nodes(
+            dataflow_id,
+            logger,
+            tasks,
+            self.state.events_tx.clone(),
+            self.state.clock.clone(),
+        );
+        tokio::spawn(async move {
+            if let Err(err) = spawn_result.await {
+                tracing::warn!("failed to spawn nodes of dataflow `{dataflow_id}`: {err:?}");
+            }
+        });
+        Ok(())
+    }
+
+    /// Handles the exit of a node that was stopped by `dora apply` and spawns
+    /// the node again if it was restarted.
+    ///
+    /// Returns `false` if the node wasn't stopped by `dora apply`.
+    async fn handle_applied_node_stop(
+        &mut self,
+        dataflow_id: DataflowId,
+        node_id: &NodeId,
+    ) -> eyre::Result<bool> {
+        let respawn = {
+            let Some(mut dataflow) = self.state.running.get_mut(&dataflow_id) else {
+                return Ok(false);
+            };
+            if !dataflow.stopped_by_apply.remove(node_id) {
+                return Ok(false);
+            }
+            dataflow.running_nodes.remove(node_id);
+            dataflow.drop_channels.remove(node_id);
+            dataflow.pending_respawns.remove(node_id)
+        };
+
+        match respawn {
+            Some(node) => {
+                self.logger
+                    .for_dataflow(dataflow_id)
+                    .for_node(node_id.clone())
+                    .log(
+                        LogLevel::Info,
+                        Some("daemon".into()),
+                        "restarting node with updated configuration",
+                    )
+                    .await;
+                if let Err(err) = self.spawn_additional_nodes(dataflow_id, vec![node]).await {
+                    self.logger
+                        .for_dataflow(dataflow_id)
+                        .for_node(node_id.clone())
+                        .log(LogLevel::Error, Some("daemon".into()), format!("{err:?}"))
+                        .await;
+                }
+            }
+            None => {
+                self.logger
+                    .for_dataflow(dataflow_id)
+                    .for_node(node_id.clone())
+                    .log(
+                        LogLevel::Info,
+                        Some("daemon".into()),
+                        "node was removed from the dataflow",
+                    )
+                    .await;
+                let should_finish = self
+                    .state
+                    .running
+                    .get(&dataflow_id)
+                    .is_some_and(|d| d.stopped_by_apply.is_empty() && d.all_nodes_finished());
+                if should_finish {
+                    self.finish_dataflow(dataflow_id).await?;
+                }
+            }
+        }
+        Ok(true)
+    }
+
+    /// Handles a local node that reached a condition that other nodes might wait for.
+    async fn handle_local_readiness(
+        &mut self,
+        dataflow_id: DataflowId,
+        node_id: NodeId,
+        readiness: Readiness,
+    ) -> eyre::Result<()> {
+        let report = match self.state.running.get_mut(&dataflow_id) {
+            Some(mut dataflow) => {
+                if !dataflow
+                    .dependencies
+                    .local_condition_reached(&node_id, &readiness)
+                {
+                    return Ok(());
+                }
+                dataflow.dependencies.has_remote_dependents(&node_id)
+            }
+            None => return Ok(()),
+        };
+        self.logger
+            .for_dataflow(dataflow_id)
+            .for_node(node_id.clone())
+            .log(
+                LogLevel::Debug,
+                Some("daemon".into()),
+                format!("reached startup condition ({readiness})"),
+            )
+            .await;
+        if report {
+            self.report_dependency_status(
+                dataflow_id,
+                node_id.clone(),
+                DependencyStatus::Reached(readiness.clone()),
+            );
+        }
+        self.handle_dependency_reached(dataflow_id, &node_id, &readiness)
+            .await
+    }
+
+    /// Spawns the deferred nodes whose `depends_on` conditions all hold now.
+    async fn handle_dependency_reached(
+        &mut self,
+        dataflow_id: DataflowId,
+        dependency: &NodeId,
+        readiness: &Readiness,
+    ) -> eyre::Result<()> {
+        let ready = match self.state.running.get_mut(&dataflow_id) {
+            Some(mut dataflow) => dataflow
+                .dependencies
+                .condition_reached(dependency, readiness),
+            None => return Ok(()),
+        };
+        for node in &ready {
+            self.logger
+                .for_dataflow(dataflow_id)
+                .for_node(node.id.clone())
+                .log(
+                    LogLevel::Info,
+                    Some("daemon".into()),
+                    "`depends_on` conditions are met, spawning node",
+                )
+                .await;
+        }
+        self.spawn_additional_nodes(dataflow_id, ready).await
+    }
+
+    /// Fails the deferred nodes that wait for a condition of the given node, which stopped.
+    async fn handle_dependency_stopped(
+        &mut self,
+        dataflow_id: DataflowId,
+        node_id: &NodeId,
+    ) -> eyre::Result<()> {
+        let (failed, report) = match self.state.running.get_mut(&dataflow_id) {
+            Some(mut dataflow) => (
+                dataflow.dependencies.dependency_stopped(node_id),
+                dataflow.dependencies.has_remote_dependents(node_id),
+            ),
+            None => return Ok(()),
+        };
+        if report {
+            self.report_dependency_status(dataflow_id, node_id.clone(), DependencyStatus::Stopped);
+        }
+        self.fail_deferred_nodes(dataflow_id, failed).await
+    }
+
+    /// Records the given deferred nodes as failed to spawn.
+    async fn fail_deferred_nodes(
+        &mut self,
+        dataflow_id: DataflowId,
+        failed: Vec<(NodeId, String)>,
+    ) -> eyre::Result<()> {
+        for (node_id, reason) in failed {
+            self.logger
+                .for_dataflow(dataflow_id)
+                .for_node(node_id.clone())
+                .log(
+                    LogLevel::Error,
+                    Some("daemon".into()),
+                    format!("failed to start node: {reason}"),
+                )
+                .await;
+            self.state
+                .dataflow_node_results
+                .entry(dataflow_id)
+                .or_default()
+                .insert(
+                    node_id.clone(),
+                    Err(NodeError {
+                        timestamp: self.state.clock.new_timestamp(),
+                        cause: NodeErrorCause::FailedToSpawn(reason),
+                        exit_status: NodeExitStatus::Unknown,
+                    }),
+                );
+            let report = self
+                .state
+                .running
+                .get(&dataflow_id)
+                .is_some_and(|d| d.dependencies.has_remote_dependents(&node_id));
+            if report {
+                self.report_dependency_status(
+                    dataflow_id,
+                    node_id.clone(),
+                    DependencyStatus::Stopped,
+                );
+            }
+            // the node won't send any outputs -> notify downstream nodes
+            self.handle_outputs_done(dataflow_id, &node_id, false)
+                .await?;
+            let _ = self
+                .state
+                .events_tx
+                .send(Timestamped {
+                    inner: Event::NodeStopped {
+                        dataflow_id,
+                        node_id,
+                    },
+                    timestamp: self.state.clock.new_timestamp(),
+                })
+                .await;
+        }
+        Ok(())
+    }
+
+    /// Reports the startup progress of a local node to the daemons of its remote dependents.
+    fn report_dependency_status(
+        &self,
+        dataflow_id: DataflowId,
+        node_id: NodeId,
+        status: DependencyStatus,
+    ) {
+        let Some(client) = self.state.coordinator_client() else {
+            return;
+        };
+        tokio::spawn(async move {
+            if let Err(err) = client
+                .dependency_status(tarpc::context::current(), dataflow_id, node_id, status)
+                .await
+            {
+                tracing::error!(
+                    ?err,
+                    "failed to send dependency_status notification to coordinator"
+                );
+            }
+        });
+    }
+
+    /// Sends stop events to the local nodes of a stopped dataflow whose upstream
+    /// nodes all exited.
+    async fn continue_shutdown(&mut self, dataflow_id: DataflowId) {
+        loop {
+            let next = match self.state.running.get_mut(&dataflow_id) {
+                Some(mut dataflow) => dataflow.stop_next_nodes(&self.state),
+                None => return,
+            };
+            let Some((stage, nodes)) = next else {
+                return;
+            };
+            for (node_id, timeout) in nodes {
+                self.logger
+                    .for_dataflow(dataflow_id)
+                    .for_node(node_id)
+                    .log(
+                        LogLevel::Info,
+                        Some("shutdown".into()),
+                        format!("stopping node (stage {stage}, stop timeout: {timeout:?})"),
+                    )
+                    .await;
+            }
+        }
+    }
+
+    /// Continues the shutdown of a stopped dataflow after one of its nodes exited.
+    async fn handle_stopped_node_exit(&mut self, dataflow_id: DataflowId, node_id: &NodeId) {
+        let remaining = match self.state.running.get_mut(&dataflow_id) {
+            Some(mut dataflow) => match &mut dataflow.shutdown {
+                Some(shutdown) => {
+                    shutdown.node_exited(node_id);
+                    shutdown.remaining()
+                }
+                None => return,
+            },
+            None => return,
+        };
+        self.logger
+            .for_dataflow(dataflow_id)
+            .for_node(node_id.clone())
+            .log(
+                LogLevel::Info,
+                Some("shutdown".into()),
+                format!("node exited ({remaining} local nodes remaining)"),
+            )
+            .await;
+        self.continue_shutdown(dataflow_id).await;
+    }
+
+    /// Kills the given node if it didn't send a heartbeat within its liveness timeout.
+    ///
+    /// The node is reported as failed once it exited, so its restart policy applies.
+    async fn check_liveness(&mut self, dataflow_id: DataflowId, node_id: NodeId) {
+        let (liveness, process) = {
+            let Some(mut dataflow) = self.state.running.get_mut(&dataflow_id) else {
+                return;
+            };
+            let Some(liveness) = dataflow.liveness.check(
+                dataflow_id,
+                &node_id,
+                &self.state.events_tx,
+                &self.state.clock,
+            ) else {
+                return;
+            };
+            let process = dataflow
+                .running_nodes
+                .get(&node_id)
+                .and_then(|node| Some((node.process.as_ref()?.clone_sender(), node.pid.clone()?)));
+            (liveness, process)
+        };
+
+        let mut logger = self.logger.for_dataflow(dataflow_id).for_node(node_id);
+        let Some((process, pid)) = process else {
+            logger
+                .log(
+                    LogLevel::Error,
+                    Some("liveness".into()),
+                    format!(
+                        "node is unresponsive: no heartbeat within {:?}",
+                        liveness.timeout
+                    ),
+                )
+                .await;
+            return;
+        };
+        let action = if liveness.stack_dump && cfg!(unix) {
+            "sending SIGQUIT for a stack dump before killing it"
+        } else {
+            "killing it"
+        };
+        logger
+            .log(
+                LogLevel::Error,
+                Some("liveness".into()),
+                format!(
+                    "node is unresponsive: no heartbeat within {:?}, {action}",
+                    liveness.timeout
+                ),
+            )
+            .await;
+        liveness::kill_unresponsive(process, pid, liveness.stack_dump && cfg!(unix));
+    }
+
+    async fn finish_dataflow_if_done(&mut self, dataflow_id: DataflowId) -> eyre::Result<()> {
+        let done = self
+            .state
+            .running
+            .get(&dataflow_id)
+            .is_some_and(|d| d.all_nodes_finished());
+        if done {
+            self.finish_dataflow(dataflow_id).await?;
+        }
+        Ok(())
+    }
+
+    async fn collect_and_send_metrics(&mut self) -> eyre::Result<()> {
+        use dora_message::daemon_to_coordinator::NodeMetrics;
+        use sysinfo::{Pid, ProcessRefreshKind, ProcessesToUpdate};
+
+        if self.state.coordinator_client().is_none() {
+            return Ok(());
+        }
+
+        // Reuse system instance for metrics collection
+        let system = &mut self.metrics_system;
+
+        // Metrics are collected every 2 seconds (metrics_interval)
+        const METRICS_INTERVAL_SECS: f64 = 2.0;
+
+        // Collect metrics for all running dataflows
+        for entry in self.state.running.iter() {
+            let (dataflow_id, dataflow) = (entry.key(), entry.value());
+            let mut metrics = BTreeMap::new();
+
+            // Collect all PIDs for this dataflow
+            let pids: Vec<Pid> = dataflow
+                .running_nodes
+                .values()
+                .filter_map(|node| {
+                    node.pid
+                        .as_ref()
+                        .map(|pid| Pid::from_u32(pid.load(atomic::Ordering::Acquire)))
+                })
+                .collect();
+
+            if !pids.is_empty() {
+                // Refresh process metrics (cpu, memory, disk)
+                let refresh_kind = ProcessRefreshKind::nothing()
+                    .with_cpu()
+                    .with_memory()
+                    .with_disk_usage();
+                system.refresh_processes_specifics(
+                    ProcessesToUpdate::Some(&pids),
+                    true,
+                    refresh_kind,
+                );
+
+                // Collect metrics for each node
+                for (node_id, running_node) in &dataflow.running_nodes {
+                    if let Some(pid) = running_node.pid.as_ref() {
+                        let pid = pid.load(atomic::Ordering::Acquire);
+                        let sys_pid = Pid::from_u32(pid);
+                        if let Some(process) = system.process(sys_pid) {
+                            let disk_usage = process.disk_usage();
+                            let cgroup_usage = running_node
+                                .cgroup
+                                .as_ref()
+                                .map(|cgroup| cgroup.usage())
+                                .unwrap_or_default();
+                            let resources = running_node
+                                .cgroup
+                                .as_ref()
+                                .map(|cgroup| cgroup.resources());
+                            // Divide by metrics_interval to get per-second averages
+                            metrics.insert(
+                                node_id.clone(),
+                                NodeMetrics {
+                                    pid,
+                                    cpu_usage: process.cpu_usage(),
+                                    memory_bytes: cgroup_usage
+                                        .memory_bytes
+                                        .unwrap_or_else(|| process.memory()),
+                                    disk_read_bytes: Some(
+                                        (disk_usage.read_bytes as f64 / METRICS_INTERVAL_SECS)
+                                            as u64,
+                                    ),
+                                    disk_write_bytes: Some(
+                                        (disk_usage.written_bytes as f64 / METRICS_INTERVAL_SECS)
+                                            as u64,
+                                    ),
+                                    cpu_limit: resources
+                                        .and_then(|r| r.cpu)
+                                        .map(|cpus| (cpus * 100.0) as f32),
+                                    memory_limit_bytes: resources.and_then(|r| r.memory),
+                                    pids: cgroup_usage.pids,
+                                    pids_limit: resources.and_then(|r| r.pids),
+                                },
+                            );
+                        }
+                    }
+                }
+            }
+
+            // Send metrics to coordinator if we have any (fire-and-forget).
+            if !metrics.is_empty() {
+                if let Some(client) = self.state.coordinator_client() {
+                    let dataflow_id = *dataflow_id;
+                    tokio::spawn(async move {
+                        let _ = client
+                            .node_metrics(tarpc::context::current(), dataflow_id, metrics)
+                            .await;
+                    });
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn handle_inter_daemon_event(&mut self, event: InterDaemonEvent) -> eyre::Result<()> {
+        match event {
+            InterDaemonEvent::Output {
+                dataflow_id,
+                node_id,
+                output_id,
+                metadata,
+                data,
+            } => {
+                let inner = async {
+                    let (metadata, data) = decompress_remote_output(metadata, data)?;
+                    let mut dataflow =
+                        self.state.running.get_mut(&dataflow_id).wrap_err_with(|| {
+                            format!("send out failed: no running dataflow with ID `{dataflow_id}`")
+                        })?;
+                    send_output_to_local_receivers(
+                        node_id.clone(),
+                        output_id.clone(),
+                        &mut dataflow,
+                        &metadata,
+                        data.map(DataMessage::Vec),
                         &self.state.clock,
                     )
                     .await?;
@@ -948,6 +1896,8 @@ impl Daemon {
                         .log(LogLevel::Warn, Some("daemon".into()), format!("{err:?}"))
                         .await;
                 }
+                // local nodes of a stopped dataflow might wait for the remote node
+                self.continue_shutdown(dataflow_id).await;
                 Ok(())
             }
             InterDaemonEvent::NodeFailed {
@@ -995,13 +1945,14 @@ impl Daemon {
         build_id: Option<BuildId>,
         dataflow_id: DataflowId,
         base_working_dir: PathBuf,
-        nodes: BTreeMap<NodeId, ResolvedNode>,
+        mut nodes: BTreeMap<NodeId, ResolvedNode>,
         dataflow_descriptor: Descriptor,
-        spawn_nodes: BTreeSet<NodeId>,
+        mut spawn_nodes: BTreeSet<NodeId>,
         uv: bool,
         write_events_to: Option<PathBuf>,
         hot_reload: bool,
         dataflow_path: Option<PathBuf>,
+        clock: DataflowClock,
     ) -> eyre::Result<impl Future<Output = eyre::Result<()>> + use<>> {
         let mut logger = self
             .logger
@@ -1009,13 +1960,24 @@ impl Daemon {
             .try_clone()
             .await
             .context("failed to clone logger")?;
-        let dataflow = RunningDataflow::new(
+        let mut dataflow = RunningDataflow::new(
             dataflow_id,
             self.state.daemon_id().clone(),
             dataflow_descriptor.clone(),
             self.state.events_tx.clone(),
             self.state.clock.clone(),
         );
+        if let DataflowClock::Simulated { source } = clock {
+            dataflow.sim_clock = Some(SimClock::new(source));
+        }
+        if let Some(settings) = dataflow_descriptor
+            .communication
+            .remote
+            .zenoh_session_settings()
+        {
+            let session = open_dataflow_zenoh_session(settings, true).await?;
+            dataflow.zenoh_session = Some(session);
+        }
         let mut dataflow = match self.state.running.entry(dataflow_id) {
             dashmap::Entry::Vacant(entry) => {
                 self.state
@@ -1046,6 +2008,24 @@ impl Daemon {
             .map(|info| info.node_working_dirs.clone())
             .unwrap_or_default();
 
+        // replace replicated local nodes by their instances
+        let replicated: Vec<_> = nodes
+            .values()
+            .filter(|n| spawn_nodes.contains(&n.id))
+            .filter_map(|n| Some((n.id.clone(), n.replicas.clone()?)))
+            .collect();
+        for (node_id, replicas) in replicated {
+            let Some(node) = nodes.remove(&node_id) else {
+                continue;
+            };
+            spawn_nodes.remove(&node_id);
+            let instance_ids = dataflow.replicas.insert(node_id, &replicas);
+            for instance in Spawner::replica_instances(node, &instance_ids) {
+                spawn_nodes.insert(instance.id.clone());
+                nodes.insert(instance.id.clone(), instance);
+            }
+        }
+
         // calculate info about mappings
         for node in nodes.values() {
             let local = spawn_nodes.contains(&node.id);
@@ -1053,34 +2033,17 @@ impl Daemon {
             let inputs = node_inputs(node);
             for (input_id, input) in inputs {
                 if local {
-                    dataflow
-                        .open_inputs
-                        .entry(node.id.clone())
-                        .or_default()
-                        .insert(input_id.clone());
-                    match input.mapping {
-                        InputMapping::User(mapping) => {
-                            dataflow
-                                .mappings
-                                .entry(OutputId(mapping.source, mapping.output))
-                                .or_default()
-                                .insert((node.id.clone(), input_id));
-                        }
-                        InputMapping::Timer { interval } => {
-                            dataflow
-                                .timers
-                                .entry(interval)
-                                .or_default()
-                                .insert((node.id.clone(), input_id));
-                        }
-                    }
-                } else if let InputMapping::User(mapping) = input.mapping {
-                    dataflow
-                        .open_external_mappings
-                        .insert(OutputId(mapping.source, mapping.output));
+                    dataflow.add_input_route(&node.id, input_id, input);
+                } else if let InputMapping::User(mapping) = &input.mapping {
+                    let output_id = OutputId(mapping.source.clone(), mapping.output.clone());
+                    dataflow.add_remote_input(output_id, input);
                 }
             }
         }
+        dataflow.uv = uv;
+        dataflow.write_events_to = write_events_to.clone();
+        dataflow.dependencies = StartupDependencies::new(&nodes, &spawn_nodes);
+        dataflow.build_working_dirs = node_working_dirs.clone();
 
         let spawner = Spawner {
             dataflow_id,
@@ -1088,6 +2051,8 @@ impl Daemon {
             dataflow_descriptor,
             clock: self.state.clock.clone(),
             uv,
+            node_communication: self.state.node_communication,
+            sim_clock: dataflow.sim_clock.clone(),
         };
 
         let mut tasks = Vec::new();
@@ -1097,21 +2062,10 @@ impl Daemon {
             let mut logger = logger.reborrow().for_node(node.id.clone());
             let local = spawn_nodes.contains(&node.id);
             if local {
-                let dynamic_node = node.kind.dynamic();
-                if dynamic_node {
-                    dataflow.dynamic_nodes.insert(node.id.clone());
-                } else {
-                    dataflow.pending_nodes.insert(node.id.clone());
-                }
-
                 let node_id = node.id.clone();
-                let node_stderr_most_recent = dataflow
-                    .node_stderr_most_recent
-                    .entry(node.id.clone())
-                    .or_insert_with(|| Arc::new(ArrayQueue::new(STDERR_LOG_LINES_MAX)))
-                    .clone();
-
-                let configured_node_working_dir = node_working_dirs.get(&node_id).cloned();
+                let configured_node_working_dir = node_working_dirs
+                    .get(dataflow.replicas.node_id(&node_id))
+                    .cloned();
                 if configured_node_working_dir.is_none() && node.has_git_source() {
                     eyre::bail!(
                         "node {} has git source, but no git clone directory was found for it\n\n\
@@ -1127,6 +2081,44 @@ impl Daemon {
                     })
                     .unwrap_or(base_working_dir.clone())
                     .clone();
+                dataflow.dependencies.add_probes(&node, &node_working_dir);
+
+                if !node.depends_on.is_empty() {
+                    let conditions: Vec<_> = node
+                        .depends_on
+                        .iter()
+                        .map(|(dep, readiness)| format!("`{dep}` ({readiness})"))
+                        .collect();
+                    logger
+                        .log(
+                            LogLevel::Info,
+                            Some("daemon".into()),
+                            format!(
+                                "waiting for `depends_on` conditions: {}",
+                                conditions.join(", ")
+                            ),
+                        )
+                        .await;
+                    dataflow.dependencies.defer(
+                        dataflow_id,
+                        node,
+                        self.state.events_tx.clone(),
+                        self.state.clock.clone(),
+                    );
+                    continue;
+                }
+
+                let dynamic_node = node.kind.dynamic();
+                if dynamic_node {
+                    dataflow.dynamic_nodes.insert(node.id.clone());
+                } else {
+                    dataflow.pending_nodes.insert(node.id.clone());
+                }
+                let node_stderr_most_recent = dataflow
+                    .node_stderr_most_recent
+                    .entry(node.id.clone())
+                    .or_insert_with(|| Arc::new(ArrayQueue::new(STDERR_LOG_LINES_MAX)))
+                    .clone();
                 let node_write_events_to = write_events_to
                     .as_ref()
                     .map(|p| p.join(format!("inputs-{}.json", node.id)));
@@ -1173,69 +2165,27 @@ impl Daemon {
                 dataflow.pending_nodes.set_external_nodes(true);
 
                 // subscribe to all node outputs that are mapped to some local inputs
-                for output_id in dataflow.mappings.keys().filter(|o| o.0 == node.id) {
-                    let tx = self
-                        .state
-                        .remote_daemon_events_tx
-                        .clone()
-                        .wrap_err("no remote_daemon_events_tx channel")?;
-                    let mut finished_rx = dataflow.finished_tx.subscribe();
-                    let subscribe_topic =
-                        zenoh_output_publish_topic(dataflow.id, &output_id.0, &output_id.1);
-                    tracing::debug!("declaring subscriber on {subscribe_topic}");
-                    let zenoh = self
-                        .state
-                        .zenoh_session
-                        .as_ref()
-                        .wrap_err("no zenoh session")?;
-                    let subscriber = zenoh
-                        .declare_subscriber(subscribe_topic)
-                        .await
-                        .map_err(|e| eyre!(e))
-                        .wrap_err_with(|| format!("failed to subscribe to {output_id:?}"))?;
-                    tokio::spawn(async move {
-                        let mut finished = pin!(finished_rx.recv());
-                        loop {
-                            let finished_or_next =
-                                futures::future::select(finished, subscriber.recv_async());
-                            match finished_or_next.await {
-                                future::Either::Left((finished, _)) => match finished {
-                                    Err(broadcast::error::RecvError::Closed) => {
-                                        tracing::debug!(
-                                            "dataflow finished, breaking from zenoh subscribe task"
-                                        );
-                                        break;
-                                    }
-                                    other => {
-                                        tracing::warn!(
-                                            "unexpected return value of dataflow finished_rx channel: {other:?}"
-                                        );
-                                        break;
-                                    }
-                                },
-                                future::Either::Right((sample, f)) => {
-                                    finished = f;
-                                    let event = sample.map_err(|e| eyre!(e)).and_then(|s| {
-                                        Timestamped::deserialize_inter_daemon_event(
-                                            &s.payload().to_bytes(),
-                                        )
-                                    });
-                                    if tx.send_async(event).await.is_err() {
-                                        // daemon finished
-                                        break;
-                                    }
-                                }
-                            }
-                        }
-                    });
+                let outputs: Vec<_> = dataflow
+                    .mappings
+                    .keys()
+                    .filter(|o| o.0 == node.id)
+                    .cloned()
+                    .collect();
+                for output_id in outputs {
+                    Self::subscribe_to_remote_output(&self.state, &mut dataflow, output_id).await?;
                 }
             }
         }
+        let has_deferred = dataflow.dependencies.has_deferred();
         drop(dataflow);
         for (node_id, dynamic) in stopped {
             self.handle_node_stop(dataflow_id, &node_id, dynamic)
                 .await?;
         }
+        if has_deferred {
+            // deferred nodes are not part of the start barrier -> report if no other local nodes
+            self.report_local_nodes_ready(dataflow_id).await;
+        }
 
         let spawn_result = Self::spawn_prepared_nodes(
             dataflow_id,
@@ -1248,6 +2198,80 @@ impl Daemon {
         Ok(spawn_result)
     }
 
+    /// Subscribes to the zenoh topic of an output of a remote node, forwarding
+    /// its messages to the local receivers.
+    ///
+    /// Does nothing if the output is already subscribed.
+    async fn subscribe_to_remote_output(
+        state: &state::DaemonState,
+        dataflow: &mut RunningDataflow,
+        output_id: OutputId,
+    ) -> eyre::Result<()> {
+        if dataflow.remote_subscriptions.contains(&output_id) {
+            return Ok(());
+        }
+        let tx = state
+            .remote_daemon_events_tx
+            .clone()
+            .wrap_err("no remote_daemon_events_tx channel")?;
+        let mut finished_rx = dataflow.finished_tx.subscribe();
+        let subscribe_topic = zenoh_output_publish_topic(
+            dataflow
+                .descriptor
+                .communication
+                .remote
+                .zenoh_topic_prefix(),
+            dataflow.id,
+            &output_id.0,
+            &output_id.1,
+        );
+        tracing::debug!("declaring subscriber on {subscribe_topic}");
+        let zenoh = dataflow
+            .zenoh_session
+            .as_ref()
+            .or(state.zenoh_session.as_ref())
+            .wrap_err("no zenoh session")?;
+        let subscriber = zenoh
+            .declare_subscriber(subscribe_topic)
+            .await
+            .map_err(|e| eyre!(e))
+            .wrap_err_with(|| format!("failed to subscribe to {output_id:?}"))?;
+        tokio::spawn(async move {
+            let mut finished = pin!(finished_rx.recv());
+            loop {
+                let finished_or_next = futures::future::select(finished, subscriber.recv_async());
+                match finished_or_next.await {
+                    future::Either::Left((finished, _)) => match finished {
+                        Err(broadcast::error::RecvError::Closed) => {
+                            tracing::debug!(
+                                "dataflow finished, breaking from zenoh subscribe task"
+                            );
+                            break;
+                        }
+                        other => {
+                            tracing::warn!(
+                                "unexpected return value of dataflow finished_rx channel: {other:?}"
+                            );
+                            break;
+                        }
+                    },
+                    future::Either::Right((sample, f)) => {
+                        finished = f;
+                        let event = sample.map_err(|e| eyre!(e)).and_then(|s| {
+                            Timestamped::deserialize_inter_daemon_event(&s.payload().to_bytes())
+                        });
+                        if tx.send_async(event).await.is_err() {
+                            // daemon finished
+                            break;
+                        }
+                    }
+                }
+            }
+        });
+        dataflow.remote_subscriptions.insert(output_id);
+        Ok(())
+    }
+
     async fn spawn_prepared_nodes(
         dataflow_id: Uuid,
         mut logger: DataflowLogger<'_>,
@@ -1438,7 +2462,14 @@ impl Daemon {
     ) -> eyre::Result<()> {
         let might_restart = || {
             let dataflow = self.state.running.get(&dataflow_id)?;
+            if dataflow.stopped_by_apply.contains(&node_id) {
+                // the outputs are either rewired or taken over by the restarted node
+                return Some(true);
+            }
             let node = dataflow.running_nodes.get(&node_id)?;
+            if node.pending_hot_reload.load(atomic::Ordering::Acquire) {
+                return Some(true);
+            }
             Some(match node.restart_policy {
                 RestartPolicy::Never => false,
                 _ if node.restarts_disabled() => false,
@@ -1459,6 +2490,9 @@ impl Daemon {
                         "node is ready",
                     )
                     .await;
+                self.handle_local_readiness(dataflow_id, node_id.clone(), Readiness::Subscribed)
+                    .await?;
+                let mut logger = self.logger.for_dataflow(dataflow_id);
 
                 let dataflow = self.state.running.get_mut(&dataflow_id).ok_or_else(|| {
                     format!("subscribe failed: no running dataflow with ID `{dataflow_id}`")
@@ -1477,13 +2511,19 @@ impl Daemon {
                         )
                         .await;
 
+                        // restarted and added nodes don't need to wait for other nodes
+                        if dataflow.dataflow_started {
+                            let _ = reply_sender.send(DaemonReply::Result(Ok(())));
+                            return Ok(());
+                        }
+
                         let df = &mut *dataflow;
                         let status = df
                             .pending_nodes
                             .handle_node_subscription(
                                 node_id.clone(),
                                 reply_sender,
-                                &self.state.coordinator_client().cloned(),
+                                &self.state.coordinator_client(),
                                 &self.state.clock,
                                 &mut df.cascading_error_causes,
                                 &mut logger,
@@ -1564,10 +2604,72 @@ impl Daemon {
                 output_id,
                 metadata,
                 data,
-            } => self
-                .send_out(dataflow_id, node_id, output_id, metadata, data)
-                .await
-                .context("failed to send out")?,
+            } => {
+                // the first message on an output might be a startup condition of other nodes
+                let awaited = self
+                    .state
+                    .running
+                    .get(&dataflow_id)
+                    .is_some_and(|d| d.dependencies.awaits_output(&node_id, &output_id));
+                if awaited {
+                    let readiness = Readiness::Output(output_id.clone());
+                    self.send_out(dataflow_id, node_id.clone(), output_id, metadata, data)
+                        .await
+                        .context("failed to send out")?;
+                    self.handle_local_readiness(dataflow_id, node_id, readiness)
+                        .await?;
+                } else {
+                    self.send_out(dataflow_id, node_id, output_id, metadata, data)
+                        .await
+                        .context("failed to send out")?;
+                }
+            }
+            DaemonNodeEvent::ExtendStopTimeout {
+                duration,
+                reply_sender,
+            } => {
+                let result = match self.state.running.get_mut(&dataflow_id) {
+                    Some(mut dataflow) => match &mut dataflow.shutdown {
+                        Some(shutdown) => shutdown.extend(&node_id, duration),
+                        None => Err(eyre!("node was not asked to stop")),
+                    },
+                    None => Err(eyre!("no running dataflow with ID `{dataflow_id}`")),
+                };
+                if result.is_ok() {
+                    self.logger
+                        .for_dataflow(dataflow_id)
+                        .for_node(node_id.clone())
+                        .log(
+                            LogLevel::Info,
+                            Some("shutdown".into()),
+                            format!("node requested {duration:?} more time to stop"),
+                        )
+                        .await;
+                }
+                let _ = reply_sender.send(DaemonReply::Result(result.map_err(|e| format!("{e}"))));
+            }
+            DaemonNodeEvent::Heartbeat => {
+                if let Some(mut dataflow) = self.state.running.get_mut(&dataflow_id) {
+                    let liveness = dataflow
+                        .running_nodes
+                        .get(&node_id)
+                        .and_then(|node| node.liveness.clone());
+                    if let Some(liveness) = liveness {
+                        dataflow.liveness.heartbeat(
+                            dataflow_id,
+                            node_id,
+                            &liveness,
+                            &self.state.events_tx,
+                            &self.state.clock,
+                        );
+                    }
+                }
+            }
+            DaemonNodeEvent::MarkReady { reply_sender } => {
+                let _ = reply_sender.send(DaemonReply::Result(Ok(())));
+                self.handle_local_readiness(dataflow_id, node_id, Readiness::Ready)
+                    .await?;
+            }
             DaemonNodeEvent::ReportDrop { tokens } => {
                 let dataflow = self.state.running.get_mut(&dataflow_id).wrap_err_with(|| {
                     format!(
@@ -1600,6 +2702,7 @@ impl Daemon {
                 let reply = match self.state.running.get_mut(&dataflow_id) {
                     Some(mut dataflow) => {
                         dataflow.subscribe_channels.remove(&node_id);
+                        dataflow.liveness.stop_monitoring(&node_id);
                         Ok(())
                     }
                     None => Err(format!("no running dataflow with ID `{dataflow_id}`")),
@@ -1610,14 +2713,70 @@ impl Daemon {
         Ok(())
     }
 
-    async fn send_out(
+    async fn send_out(
+        &mut self,
+        dataflow_id: Uuid,
+        node_id: NodeId,
+        output_id: DataId,
+        mut metadata: dora_message::metadata::Metadata,
+        data: Option<DataMessage>,
+    ) -> Result<(), eyre::ErrReport> {
+        let mut dataflow = self.state.running.get_mut(&dataflow_id).wrap_err_with(|| {
+            format!("send out failed: no running dataflow with ID `{dataflow_id}`")
+        })?;
+        if output_id.starts_with(REQUEST_PREFIX) {
+            match dataflow
+                .service_requests
+                .register(&node_id, &output_id, &mut metadata.parameters)
+            {
+                Ok((request_id, timeout)) => {
+                    if let Some(timeout) = timeout {
+                        self.schedule_service_request_timeout(dataflow_id, request_id, timeout);
+                    }
+                }
+                Err(err) => {
+                    warn!("dropping service request of node `{node_id}`: {err}");
+                    return Ok(());
+                }
+            }
+        } else if output_id.starts_with(REPLY_PREFIX) {
+            dataflow
+                .service_requests
+                .goal_finished(&output_id, &metadata.parameters);
+        }
+        let clock_step = dataflow.sim_clock.as_ref().and_then(|sim_clock| {
+            sim_clock.output_received(&node_id);
+            sim_clock
+                .source()
+                .filter(|(source_node, source_output)| {
+                    source_node == &node_id && source_output == &output_id
+                })
+                .and_then(|_| sim_clock.set_time(&metadata.parameters))
+        });
+        dataflow.replicas.output_sent(&node_id);
+        drop(dataflow);
+        self.deliver_output(dataflow_id, node_id, output_id, metadata, data)
+            .await?;
+        if let Some(step) = clock_step {
+            self.handle_simulated_clock_step(dataflow_id, step).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Delivers an output message to the local and remote receivers.
+    ///
+    /// Unlike [`Self::send_out`], this does not record the message as an output
+    /// of the sending node, so service requests, replica load, simulated clock
+    /// and startup conditions are not affected.
+    async fn deliver_output(
         &mut self,
         dataflow_id: Uuid,
         node_id: NodeId,
         output_id: DataId,
         metadata: dora_message::metadata::Metadata,
         data: Option<DataMessage>,
-    ) -> Result<(), eyre::ErrReport> {
+    ) -> eyre::Result<()> {
         let mut dataflow = self.state.running.get_mut(&dataflow_id).wrap_err_with(|| {
             format!("send out failed: no running dataflow with ID `{dataflow_id}`")
         })?;
@@ -1631,17 +2790,36 @@ impl Daemon {
         )
         .await?;
 
-        let output_id = OutputId(node_id, output_id);
-        let remote_receivers = dataflow.open_external_mappings.contains(&output_id)
+        let output_id = OutputId(dataflow.replicas.node_id(&node_id).clone(), output_id);
+        // skip the zenoh put if the message is rejected by the filters of all remote inputs
+        let remote_receivers = (dataflow.open_external_mappings.contains(&output_id)
+            && dataflow
+                .external_input_filters
+                .get(&output_id)
+                .is_none_or(|filters| {
+                    filters.iter().any(|filter| {
+                        filter
+                            .as_ref()
+                            .is_none_or(|f| f.matches(&metadata.parameters))
+                    })
+                }))
             || dataflow.publish_all_messages_to_zenoh;
+        let compression = dataflow.output_compression(&output_id);
         drop(dataflow);
         if remote_receivers {
+            let (metadata, data) = compress_remote_output(compression, metadata, data_bytes)
+                .wrap_err_with(|| {
+                    format!(
+                        "failed to compress output `{}/{}`",
+                        output_id.0, output_id.1
+                    )
+                })?;
             let event = InterDaemonEvent::Output {
                 dataflow_id,
                 node_id: output_id.0.clone(),
                 output_id: output_id.1.clone(),
                 metadata,
-                data: data_bytes,
+                data,
             };
             self.send_to_remote_receivers(dataflow_id, &output_id, event)
                 .await?;
@@ -1650,6 +2828,201 @@ impl Daemon {
         Ok(())
     }
 
+    /// Delivers a message injected by `dora topic pub` to the receivers of the given output.
+    async fn publish(&mut self, dataflow_id: Uuid, message: PublishMessage) -> eyre::Result<()> {
+        let PublishMessage {
+            node_id,
+            output_id,
+            type_info,
+            parameters,
+            data,
+        } = message;
+        {
+            let dataflow = self
+                .state
+                .running
+                .get(&dataflow_id)
+                .wrap_err_with(|| format!("no running dataflow with ID `{dataflow_id}`"))?;
+            if !dataflow.descriptor.nodes.iter().any(|n| n.id == node_id) {
+                bail!("dataflow `{dataflow_id}` has no node `{node_id}`");
+            }
+        }
+        // injected messages bypass the request bookkeeping, so they could never be answered
+        if output_id.starts_with(REQUEST_PREFIX) || output_id.starts_with(REPLY_PREFIX) {
+            bail!("cannot publish to service output `{node_id}/{output_id}`");
+        }
+        let metadata = metadata::Metadata::from_parameters(
+            self.state.clock.new_timestamp(),
+            type_info,
+            parameters,
+        );
+        let data = (!data.is_empty()).then(|| DataMessage::Vec(AVec::from_slice(128, &data)));
+        self.deliver_output(dataflow_id, node_id, output_id, metadata, data)
+            .await
+    }
+
+    fn schedule_service_request_timeout(
+        &self,
+        dataflow_id: DataflowId,
+        request_id: i64,
+        timeout: Duration,
+    ) {
+        let events_tx = self.state.events_tx.clone();
+        let clock = self.state.clock.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(timeout).await;
+            let event = Timestamped {
+                inner: Event::ServiceRequestTimeout {
+                    dataflow_id,
+                    request_id,
+                },
+                timestamp: clock.new_timestamp(),
+            };
+            let _ = events_tx.send(event).await;
+        });
+    }
+
+    /// Sends an error reply to the client if the request is still unanswered.
+    fn handle_service_request_timeout(&mut self, dataflow_id: DataflowId, request_id: i64) {
+        let Some(mut dataflow) = self.state.running.get_mut(&dataflow_id) else {
+            return;
+        };
+        let Some(request) = dataflow.service_requests.time_out(request_id) else {
+            return;
+        };
+        send_unanswered_reply(
+            &mut dataflow,
+            request,
+            "request timed out",
+            &self.state.clock,
+        );
+    }
+
+    /// Starts a rebuild of the given watched node.
+    ///
+    /// If a rebuild of the node is already running, it is rerun once it finishes.
+    async fn handle_node_source_changed(
+        &mut self,
+        dataflow_id: DataflowId,
+        node_id: NodeId,
+        node: WatchedNode,
+    ) -> eyre::Result<()> {
+        let uv = {
+            let Some(mut dataflow) = self.state.running.get_mut(&dataflow_id) else {
+                return Ok(());
+            };
+            if dataflow.stop_sent {
+                return Ok(());
+            }
+            if let Some(rebuild) = dataflow.rebuilds.get_mut(&node_id) {
+                rebuild.source_changed();
+                return Ok(());
+            }
+            dataflow
+                .rebuilds
+                .insert(node_id.clone(), watch::Rebuild::new());
+            dataflow.uv
+        };
+
+        let logger = self
+            .logger
+            .for_dataflow(dataflow_id)
+            .for_node(node_id.clone())
+            .try_clone()
+            .await
+            .context("failed to clone logger")?;
+        let events_tx = self.state.events_tx.clone();
+        let clock = self.state.clock.clone();
+        tokio::spawn(async move {
+            let result = watch::rebuild_node(node.clone(), uv, logger).await;
+            let event = Timestamped {
+                inner: Event::NodeRebuilt {
+                    dataflow_id,
+                    node_id,
+                    node,
+                    result,
+                },
+                timestamp: clock.new_timestamp(),
+            };
+            let _ = events_tx.send(event).await;
+        });
+        Ok(())
+    }
+
+    /// Restarts a watched node after a successful rebuild.
+    ///
+    /// If the build failed, the old process keeps running.
+    async fn handle_node_rebuilt(
+        &mut self,
+        dataflow_id: DataflowId,
+        node_id: NodeId,
+        node: WatchedNode,
+        result: eyre::Result<bool>,
+    ) -> eyre::Result<()> {
+        let (rerun, restarted) = {
+            let Some(mut dataflow) = self.state.running.get_mut(&dataflow_id) else {
+                return Ok(());
+            };
+            let rerun = dataflow
+                .rebuilds
+                .remove(&node_id)
+                .is_some_and(|r| r.needs_rerun());
+            let restarted = matches!(result, Ok(true))
+                && !dataflow.stop_sent
+                && dataflow.hot_reload_node(&node_id, &self.state.clock);
+            (rerun, restarted)
+        };
+
+        let mut logger = self
+            .logger
+            .for_dataflow(dataflow_id)
+            .for_node(node_id.clone());
+        match result {
+            Ok(true) if restarted => {
+                logger
+                    .log(
+                        LogLevel::Info,
+                        Some("watch".into()),
+                        "build succeeded, restarting node",
+                    )
+                    .await
+            }
+            Ok(true) => {
+                logger
+                    .log(
+                        LogLevel::Info,
+                        Some("watch".into()),
+                        "build succeeded, but node is not running anymore",
+                    )
+                    .await
+            }
+            Ok(false) => {
+                logger
+                    .log(
+                        LogLevel::Info,
+                        Some("watch".into()),
+                        "build did not change the node executable, not restarting",
+                    )
+                    .await
+            }
+            Err(err) => {
+                logger
+                    .log(
+                        LogLevel::Error,
+                        Some("watch".into()),
+                        format!("build failed, keeping the old node running: {err:?}"),
+                    )
+                    .await
+            }
+        }
+
+        if rerun {
+            self.handle_node_source_changed(dataflow_id, node_id, node)
+                .await?;
+        }
+        Ok(())
+    }
+
     /// Find all receivers affected by a node failure and send NodeFailed events to local ones.
     ///
     /// Returns the list of outputs and a set of remote receiver node IDs (nodes not on this daemon).
@@ -1659,6 +3032,17 @@ impl Daemon {
         error_message: &str,
         clock: &HLC,
     ) -> (Vec<DataId>, BTreeSet<NodeId>) {
+        // A failed replica does not affect downstream nodes as long as other
+        // replicas are still running and producing the merged outputs.
+        if dataflow
+            .replicas
+            .siblings(source_node_id)
+            .any(|sibling| dataflow.running_nodes.contains_key(sibling))
+        {
+            return (Vec::new(), BTreeSet::new());
+        }
+        let source_node_id = &dataflow.replicas.node_id(source_node_id).clone();
+
         // Get all outputs of the failed node
         let outputs: Vec<DataId> = dataflow
             .mappings
@@ -1698,7 +3082,7 @@ impl Daemon {
                     error: error_message.to_string(),
                     source_node_id: source_node_id.clone(),
                 };
-                let _ = send_with_timestamp(channel, event, clock);
+                let _ = send_with_timestamp(channel, event, clock, dataflow.sim_clock.as_ref());
             }
         }
 
@@ -1735,6 +3119,8 @@ impl Daemon {
             &self.state.clock,
         );
 
+        let source_node_id = dataflow.replicas.node_id(&source_node_id).clone();
+
         // Drop the DashMap lock before doing async I/O via send_to_remote_receivers.
         drop(dataflow);
 
@@ -1786,21 +3172,33 @@ impl Daemon {
                 .map(|d| d.publishers.contains_key(output_id))
                 .unwrap_or(false);
             if !has_publisher {
-                let publish_topic = {
+                let (publish_topic, priority, dataflow_session) = {
                     let dataflow = self.state.running.get(&dataflow_id).wrap_err_with(|| {
                         format!("send out failed: no running dataflow with ID `{dataflow_id}`")
                     })?;
-                    zenoh_output_publish_topic(dataflow.id, &output_id.0, &output_id.1)
+                    let remote = &dataflow.descriptor.communication.remote;
+                    let topic = zenoh_output_publish_topic(
+                        remote.zenoh_topic_prefix(),
+                        dataflow.id,
+                        &output_id.0,
+                        &output_id.1,
+                    );
+                    let priority = remote
+                        .zenoh()
+                        .and_then(|zenoh| zenoh.priority(&output_id.0, &output_id.1));
+                    (topic, priority, dataflow.zenoh_session.clone())
                 };
                 // DashMap lock dropped — safe to do async I/O.
                 tracing::debug!("declaring publisher on {publish_topic}");
-                let zenoh = self
-                    .state
-                    .zenoh_session
+                let zenoh = dataflow_session
                     .as_ref()
+                    .or(self.state.zenoh_session.as_ref())
                     .wrap_err("no zenoh session")?;
-                let publisher = zenoh
-                    .declare_publisher(publish_topic)
+                let mut publisher = zenoh.declare_publisher(publish_topic);
+                if let Some(priority) = priority {
+                    publisher = publisher.priority(zenoh_priority(priority));
+                }
+                let publisher = publisher
                     .await
                     .map_err(|e| eyre!(e))
                     .context("failed to create zenoh publisher")?;
@@ -1847,6 +3245,9 @@ impl Daemon {
                 .running
                 .get_mut(&dataflow_id)
                 .wrap_err_with(|| format!("no running dataflow with ID `{dataflow_id}`"))?;
+            // outputs of replicated nodes stay open until all replicas closed them
+            let outputs = dataflow.replicas.close_outputs(&node_id, outputs);
+            let node_id = dataflow.replicas.node_id(&node_id).clone();
             let local_node_inputs: BTreeSet<_> = dataflow
                 .mappings
                 .iter()
@@ -1886,6 +3287,8 @@ impl Daemon {
         event_sender: UnboundedSender<Timestamped<NodeEvent>>,
         clock: &HLC,
     ) {
+        dataflow.liveness.node_subscribed(&node_id);
+
         // some inputs might have been closed already -> report those events
         let closed_inputs = dataflow
             .mappings
@@ -1907,36 +3310,66 @@ impl Daemon {
                     id: input_id.clone(),
                 },
                 clock,
+                dataflow.sim_clock.as_ref(),
             );
         }
         if dataflow.open_inputs(&node_id).is_empty() {
             if let Some(node) = dataflow.running_nodes.get_mut(&node_id) {
                 node.disable_restart();
             }
-            if let Some(node) = dataflow.descriptor.nodes.iter().find(|n| n.id == node_id) {
+            let descriptor_id = dataflow.replicas.node_id(&node_id);
+            if let Some(node) = dataflow
+                .descriptor
+                .nodes
+                .iter()
+                .find(|n| &n.id == descriptor_id)
+            {
                 if node.inputs.is_empty() {
                     // do not send AllInputsClosed for source nodes
                 } else {
-                    let _ = send_with_timestamp(&event_sender, NodeEvent::AllInputsClosed, clock);
+                    dataflow.liveness.stop_monitoring(&node_id);
+                    let _ = send_with_timestamp(
+                        &event_sender,
+                        NodeEvent::AllInputsClosed,
+                        clock,
+                        dataflow.sim_clock.as_ref(),
+                    );
                 }
             }
         }
 
         // if a stop event was already sent for the dataflow, send it to
-        // the newly connected node too
-        if dataflow.stop_sent {
+        // the newly connected node too, unless it waits for its upstream nodes
+        let stop_released = dataflow
+            .shutdown
+            .as_ref()
+            .is_none_or(|shutdown| shutdown.stop_sent(&node_id));
+        if dataflow.stop_sent && stop_released {
             if let Some(node) = dataflow.running_nodes.get_mut(&node_id) {
                 node.disable_restart();
             }
+            dataflow.liveness.stop_monitoring(&node_id);
             let _ = send_with_timestamp(
                 &event_sender,
                 NodeEvent::Stop {
                     reason: Some(dora_message::daemon_to_node::StopCause::Manual),
                 },
                 clock,
+                dataflow.sim_clock.as_ref(),
             );
         }
 
+        // nodes with inputs need to be idle before the simulated clock can advance
+        if dataflow
+            .open_inputs
+            .get(&node_id)
+            .is_some_and(|inputs| !inputs.is_empty())
+        {
+            if let Some(sim_clock) = &dataflow.sim_clock {
+                sim_clock.add_node(node_id.clone());
+            }
+        }
+
         dataflow.subscribe_channels.insert(node_id, event_sender);
     }
 
@@ -1953,6 +3386,7 @@ impl Daemon {
                 .running
                 .get(&dataflow_id)
                 .ok_or_else(|| eyre!("no running dataflow with ID `{dataflow_id}`"))?;
+            let node_id = dataflow.replicas.node_id(node_id);
             dataflow
                 .mappings
                 .keys()
@@ -2067,8 +3501,45 @@ impl Daemon {
                 .await;
         }
         // DashMap guard is dropped — safe to do async I/O (RPC to coordinator).
-        // Check if all local nodes are ready and report to coordinator if needed.
-        // We must not hold the DashMap guard during the RPC call.
+        self.report_local_nodes_ready(dataflow_id).await;
+
+        // When hot-reload is active, the node might be re-spawned after the user fixes
+        // the file. Keep outputs open so downstream nodes don't receive InputClosed events.
+        let hot_reload_active = self
+            .state
+            .running
+            .get(&dataflow_id)
+            .map(|d| d._hot_reload_watcher.is_some())
+            .unwrap_or(false);
+        let might_restart = hot_reload_active;
+
+        self.handle_outputs_done(dataflow_id, node_id, might_restart)
+            .await?;
+        self.handle_dependency_stopped(dataflow_id, node_id).await?;
+        self.handle_stopped_node_exit(dataflow_id, node_id).await;
+
+        let should_finish = {
+            let mut dataflow = self.state.running.get_mut(&dataflow_id).wrap_err_with(|| {
+                format!(
+                    "failed to get downstream nodes: no running dataflow with ID `{dataflow_id}`"
+                )
+            })?;
+            dataflow.running_nodes.remove(node_id);
+            dataflow.all_nodes_finished()
+        };
+
+        if should_finish {
+            self.finish_dataflow(dataflow_id).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Checks if all local nodes are ready and reports to the coordinator if needed.
+    ///
+    /// We must not hold the DashMap guard during the RPC call.
+    async fn report_local_nodes_ready(&mut self, dataflow_id: Uuid) {
+        let mut logger = self.logger.for_dataflow(dataflow_id);
         let needs_report = self
             .state
             .running
@@ -2119,40 +3590,6 @@ impl Daemon {
                 }
             }
         }
-
-        // When hot-reload is active, the node might be re-spawned after the user fixes
-        // the file. Keep outputs open so downstream nodes don't receive InputClosed events.
-        let hot_reload_active = self
-            .state
-            .running
-            .get(&dataflow_id)
-            .map(|d| d._hot_reload_watcher.is_some())
-            .unwrap_or(false);
-        let might_restart = hot_reload_active;
-
-        self.handle_outputs_done(dataflow_id, node_id, might_restart)
-            .await?;
-
-        let should_finish = {
-            let mut dataflow = self.state.running.get_mut(&dataflow_id).wrap_err_with(|| {
-                format!(
-                    "failed to get downstream nodes: no running dataflow with ID `{dataflow_id}`"
-                )
-            })?;
-            dataflow.running_nodes.remove(node_id);
-            // Check if all remaining nodes are dynamic (won't send SpawnedNodeResult)
-            !dataflow.pending_nodes.local_nodes_pending()
-                && dataflow
-                    .running_nodes
-                    .iter()
-                    .all(|(_id, n)| n.node_config.dynamic)
-        };
-
-        if should_finish {
-            self.finish_dataflow(dataflow_id).await?;
-        }
-
-        Ok(())
     }
 
     /// Mark a dataflow as finished and perform cleanup.
@@ -2193,20 +3630,7 @@ impl Daemon {
             )
             .await;
 
-        if let Some(client) = self.state.coordinator_client() {
-            let client = client.clone();
-            tokio::spawn(async move {
-                if let Err(err) = client
-                    .all_nodes_finished(tarpc::context::current(), dataflow_id, result)
-                    .await
-                {
-                    tracing::error!(
-                        ?err,
-                        "failed to send all_nodes_finished notification to coordinator"
-                    );
-                }
-            });
-        }
+        self.state.report_dataflow_result(dataflow_id, result);
         self.state.running.remove(&dataflow_id);
 
         Ok(())
@@ -2216,7 +3640,7 @@ impl Daemon {
         match event {
             DoraEvent::Timer {
                 dataflow_id,
-                interval,
+                timer,
                 metadata,
             } => {
                 let Some(mut dataflow) = self.state.running.get_mut(&dataflow_id) else {
@@ -2224,27 +3648,35 @@ impl Daemon {
                     return Ok(());
                 };
 
-                let Some(subscribers) = dataflow.timers.get(&interval).cloned() else {
+                let Some(subscribers) = dataflow.timers.get(&timer).cloned() else {
                     return Ok(());
                 };
+                let dataflow = &mut *dataflow;
 
+                // ticks for paused nodes are held back by `dora debug` too
+                let source = OutputId("dora".to_string().into(), format!("timer/{timer}").into());
                 let mut closed = Vec::new();
                 for (receiver_id, input_id) in &subscribers {
                     let Some(channel) = dataflow.subscribe_channels.get(receiver_id) else {
                         continue;
                     };
 
-                    let send_result = send_with_timestamp(
-                        channel,
-                        NodeEvent::Input {
+                    let event = Timestamped {
+                        inner: NodeEvent::Input {
                             id: input_id.clone(),
                             metadata: metadata.clone(),
                             data: None,
                         },
-                        &self.state.clock,
-                    );
-                    match send_result {
-                        Ok(()) => {}
+                        timestamp: self.state.clock.new_timestamp(),
+                    };
+                    // ticks have no data, so dropped ticks need no cleanup
+                    let Intercepted::Deliver(event) =
+                        dataflow.debugger.intercept(receiver_id, &source, event)
+                    else {
+                        continue;
+                    };
+                    match channel.send(event) {
+                        Ok(()) => dataflow.event_sent(),
                         Err(_) => {
                             closed.push(receiver_id.clone());
                         }
@@ -2289,6 +3721,7 @@ impl Daemon {
                             data: Some(message.clone()),
                         },
                         &self.state.clock,
+                        dataflow.sim_clock.as_ref(),
                     );
                     match send_result {
                         Ok(()) => {}
@@ -2306,8 +3739,12 @@ impl Daemon {
                 node_id,
                 dynamic_node,
                 exit_status,
+                memory_limit_exceeded,
                 restart,
             } => {
+                if self.handle_applied_node_stop(dataflow_id, &node_id).await? {
+                    return Ok(());
+                }
                 let mut logger = self
                     .logger
                     .for_dataflow(dataflow_id)
@@ -2320,6 +3757,14 @@ impl Daemon {
                     )
                     .await;
 
+                let unresponsive = match self.state.running.get_mut(&dataflow_id) {
+                    Some(mut dataflow) => {
+                        dataflow.liveness.stop_monitoring(&node_id);
+                        dataflow.liveness.take_unresponsive(&node_id)
+                    }
+                    None => None,
+                };
+
                 let node_result = match exit_status {
                     NodeExitStatus::Success => Ok(()),
                     exit_status => {
@@ -2348,8 +3793,8 @@ impl Daemon {
                                 (None, false, None)
                             };
 
-                        let cause = match caused_by_node {
-                            Some(caused_by_node) => {
+                        let cause = match (caused_by_node, unresponsive) {
+                            (Some(caused_by_node), _) => {
                                 logger
                                 .log(
                                     LogLevel::Info,
@@ -2360,8 +3805,12 @@ impl Daemon {
 
                                 NodeErrorCause::Cascading { caused_by_node }
                             }
-                            None if grace_duration_kill => NodeErrorCause::GraceDuration,
-                            None => {
+                            (None, _) if memory_limit_exceeded => {
+                                NodeErrorCause::MemoryLimitExceeded
+                            }
+                            (None, Some(timeout)) => NodeErrorCause::Unresponsive { timeout },
+                            (None, None) if grace_duration_kill => NodeErrorCause::GraceDuration,
+                            (None, None) => {
                                 let cause = stderr_lines
                                     .map(extract_err_from_stderr)
                                     .unwrap_or_default();
@@ -2394,7 +3843,8 @@ impl Daemon {
 
                 drop(logger);
 
-                // Propagate error to downstream nodes only for genuine user-code failures,
+                // Propagate error to downstream nodes only for genuine user-code failures
+                // (including hangs detected through `liveness` and exceeded memory limits),
                 // even if the node will be restarted (downstream should know about failures).
                 // Cascading errors (caused by a previously failed node), grace-duration
                 // kills (dora's own timeout), and spawn failures are excluded:
@@ -2402,7 +3852,12 @@ impl Daemon {
                 // - GraceDuration: this is dora operational behaviour, not a user-code error.
                 // - FailedToSpawn: the node never ran, so its outputs were never open.
                 if let Err(node_error) = &node_result {
-                    if matches!(node_error.cause, NodeErrorCause::Other { .. }) {
+                    if matches!(
+                        node_error.cause,
+                        NodeErrorCause::Other { .. }
+                            | NodeErrorCause::Unresponsive { .. }
+                            | NodeErrorCause::MemoryLimitExceeded
+                    ) {
                         let error_message = format!("{node_error}");
                         if let Err(err) = self
                             .send_node_failed_events(dataflow_id, node_id.clone(), error_message)
@@ -2640,13 +4095,14 @@ async fn read_last_n_lines(file: &mut File, mut tail: usize) -> io::Result<Vec<u
 ///
 /// Returns `((daemon_id, coordinator_client), event_stream)`.
 async fn set_up_event_stream(
-    coordinator_addr: SocketAddr,
+    coordinator_addrs: &[SocketAddr],
     machine_id: &Option<String>,
     clock: &Arc<HLC>,
     state: Arc<state::DaemonState>,
     remote_daemon_events_rx: flume::Receiver<eyre::Result<Timestamped<InterDaemonEvent>>>,
     // used for dynamic nodes
     local_listen_port: u16,
+    placement: DaemonPlacement,
 ) -> eyre::Result<(
     (
         DaemonId,
@@ -2666,9 +4122,16 @@ async fn set_up_event_stream(
         },
     });
 
-    let register_result = coordinator::register(coordinator_addr, machine_id.clone(), clock, state)
-        .await
-        .wrap_err("failed to connect to dora-coordinator")?;
+    let register_result = coordinator::register(
+        coordinator_addrs,
+        machine_id.clone(),
+        None,
+        placement,
+        clock,
+        state,
+    )
+    .await
+    .wrap_err("failed to connect to dora-coordinator")?;
     // Monitor the RPC server task so panics are logged instead of silently lost.
     let rpc_server_handle = register_result.rpc_server_handle;
     let daemon_id = register_result.daemon_id;
@@ -2701,21 +4164,64 @@ async fn send_output_to_local_receivers(
 ) -> Result<Option<AVec<u8, ConstAlign<128>>>, eyre::ErrReport> {
     let timestamp = metadata.timestamp();
     let empty_set = BTreeSet::new();
-    let output_id = OutputId(node_id, output_id);
+    // outputs of replica instances are merged into the output of the replicated node
+    let output_id = OutputId(dataflow.replicas.node_id(&node_id).clone(), output_id);
     let local_receivers = dataflow.mappings.get(&output_id).unwrap_or(&empty_set);
-    let OutputId(node_id, _) = output_id;
+    // apply the `max_rate`, `every`, and `filter` options of the receiving inputs
+    let local_receivers = dataflow
+        .input_gates
+        .admit(local_receivers, metadata, &dataflow.replicas);
+    let subscribe_channels = &dataflow.subscribe_channels;
+    let local_receivers = dataflow
+        .replicas
+        .route(local_receivers, metadata, |receiver| {
+            subscribe_channels.contains_key(receiver)
+        });
     let mut closed = Vec::new();
+    let mut dropped_held_back = Vec::new();
     for (receiver_id, input_id) in local_receivers {
-        if let Some(channel) = dataflow.subscribe_channels.get(receiver_id) {
+        let mut metadata = metadata.clone();
+        // replies go to the node instance that sent the request, late replies are dropped
+        let receiver_id = if input_id.starts_with(REPLY_PREFIX) {
+            match dataflow
+                .service_requests
+                .complete(input_id, &mut metadata.parameters)
+            {
+                Some(client) => client,
+                None => continue,
+            }
+        } else if input_id.starts_with(REQUEST_PREFIX) {
+            // cancel requests go to the instance that executes the goal
+            match dataflow.service_requests.route_request(
+                receiver_id,
+                input_id,
+                &metadata.parameters,
+            ) {
+                Some(server) => server,
+                None => continue,
+            }
+        } else {
+            receiver_id.clone()
+        };
+        if let Some(channel) = dataflow.subscribe_channels.get(&receiver_id) {
             let item = NodeEvent::Input {
                 id: input_id.clone(),
-                metadata: metadata.clone(),
+                metadata,
                 data: data.clone(),
             };
-            match channel.send(Timestamped {
+            let event = Timestamped {
                 inner: item,
                 timestamp,
-            }) {
+            };
+            // messages to paused nodes are held back until `dora debug` releases them
+            let result = match dataflow.debugger.intercept(&receiver_id, &output_id, event) {
+                Intercepted::Deliver(event) => channel.send(event).map(|()| dataflow.event_sent()),
+                Intercepted::HeldBack { dropped } => {
+                    dropped_held_back.extend(dropped.map(|event| (receiver_id.clone(), event)));
+                    Ok(())
+                }
+            };
+            match result {
                 Ok(()) => {
                     if let Some(token) = data.as_ref().and_then(|d| d.drop_token()) {
                         dataflow
@@ -2736,7 +4242,10 @@ async fn send_output_to_local_receivers(
         }
     }
     for id in closed {
-        dataflow.subscribe_channels.remove(id);
+        dataflow.subscribe_channels.remove(&id);
+    }
+    for (receiver_id, event) in dropped_held_back {
+        discard_input(dataflow, &receiver_id, event, clock).await?;
     }
     let (data_bytes, drop_token) = match data {
         None => (None, None),
@@ -2766,7 +4275,87 @@ async fn send_output_to_local_receivers(
         // check if all local subscribers are finished with the token
         dataflow.check_drop_token(token, clock).await?;
     }
-    Ok(data_bytes)
+    Ok(data_bytes)
+}
+
+/// Compresses the data of an output that is sent to remote receivers if the
+/// output has a `compression` setting.
+fn compress_remote_output(
+    compression: Option<Compression>,
+    mut metadata: metadata::Metadata,
+    data: Option<AVec<u8, ConstAlign<128>>>,
+) -> eyre::Result<(metadata::Metadata, Option<AVec<u8, ConstAlign<128>>>)> {
+    let (Some(compression), Some(bytes)) = (compression, &data) else {
+        return Ok((metadata, data));
+    };
+    let data = match compression::compress(compression, &mut metadata, bytes)? {
+        Some(compressed) => Some(AVec::from_slice(128, &compressed)),
+        None => data,
+    };
+    Ok((metadata, data))
+}
+
+/// Reverts [`compress_remote_output`] before a remote output is delivered to
+/// local receivers.
+fn decompress_remote_output(
+    mut metadata: metadata::Metadata,
+    data: Option<AVec<u8, ConstAlign<128>>>,
+) -> eyre::Result<(metadata::Metadata, Option<AVec<u8, ConstAlign<128>>>)> {
+    let Some(bytes) = &data else {
+        return Ok((metadata, data));
+    };
+    let data = match compression::decompress(&mut metadata, bytes)? {
+        Some(decompressed) => Some(AVec::from_slice(128, &decompressed)),
+        None => data,
+    };
+    Ok((metadata, data))
+}
+
+/// Delivers events that were held back by the debugger.
+async fn deliver_held_back_messages(
+    dataflow: &mut RunningDataflow,
+    messages: Vec<(NodeId, Timestamped<NodeEvent>)>,
+    clock: &HLC,
+) -> eyre::Result<()> {
+    for (receiver_id, event) in messages {
+        let event = match dataflow.subscribe_channels.get(&receiver_id) {
+            Some(channel) => match channel.send(event) {
+                Ok(()) => {
+                    dataflow.event_sent();
+                    continue;
+                }
+                Err(err) => err.0,
+            },
+            None => event,
+        };
+        // the receiver is gone
+        dataflow.subscribe_channels.remove(&receiver_id);
+        discard_input(dataflow, &receiver_id, event, clock).await?;
+    }
+    Ok(())
+}
+
+/// Releases the drop token of an input message that is never delivered to `receiver_id`.
+async fn discard_input(
+    dataflow: &mut RunningDataflow,
+    receiver_id: &NodeId,
+    event: Timestamped<NodeEvent>,
+    clock: &HLC,
+) -> eyre::Result<()> {
+    let NodeEvent::Input {
+        data: Some(data), ..
+    } = &event.inner
+    else {
+        return Ok(());
+    };
+    if let Some(token) = data.drop_token() {
+        if let Some(info) = dataflow.pending_drop_tokens.get_mut(&token) {
+            if info.pending_nodes.remove(receiver_id) {
+                dataflow.check_drop_token(token, clock).await?;
+            }
+        }
+    }
+    Ok(())
 }
 
 fn node_inputs(node: &ResolvedNode) -> BTreeMap<DataId, Input> {
@@ -2787,20 +4376,66 @@ fn close_input(
             return;
         }
     }
-    if let Some(channel) = dataflow.subscribe_channels.get(receiver_id) {
-        let _ = send_with_timestamp(
-            channel,
-            NodeEvent::InputClosed {
-                id: input_id.clone(),
-            },
-            clock,
-        );
+    // the server stopped, so pending requests and goals won't be answered anymore
+    if input_id.starts_with(REPLY_PREFIX) {
+        for request in dataflow.service_requests.abandon(input_id) {
+            send_unanswered_reply(dataflow, request, "server node stopped", clock);
+        }
+    }
+    if dataflow.subscribe_channels.contains_key(receiver_id) {
+        let event = NodeEvent::InputClosed {
+            id: input_id.clone(),
+        };
+        dataflow.send_after_held_back(receiver_id, event, clock);
 
         if dataflow.open_inputs(receiver_id).is_empty() {
             if let Some(node) = dataflow.running_nodes.get_mut(receiver_id) {
                 node.disable_restart();
             }
-            let _ = send_with_timestamp(channel, NodeEvent::AllInputsClosed, clock);
+            dataflow.liveness.stop_monitoring(receiver_id);
+            dataflow.send_after_held_back(receiver_id, NodeEvent::AllInputsClosed, clock);
+        }
+    }
+}
+
+/// Sends an error reply for a request that won't be answered by the server.
+///
+/// Goals are reported as aborted.
+fn send_unanswered_reply(
+    dataflow: &mut RunningDataflow,
+    request: UnansweredRequest,
+    error: &str,
+    clock: &HLC,
+) {
+    let mut parameters = BTreeMap::new();
+    parameters.insert(
+        REQUEST_ID_PARAMETER.to_string(),
+        Parameter::Integer(request.client_request_id),
+    );
+    parameters.insert(
+        SERVICE_ERROR_PARAMETER.to_string(),
+        Parameter::String(error.into()),
+    );
+    if request.is_goal {
+        parameters.insert(
+            ACTION_MESSAGE_PARAMETER.to_string(),
+            Parameter::String(ActionMessage::Result.as_str().into()),
+        );
+        parameters.insert(
+            GOAL_STATUS_PARAMETER.to_string(),
+            Parameter::String(GoalStatus::Aborted.as_str().into()),
+        );
+    }
+    let metadata =
+        metadata::Metadata::from_parameters(clock.new_timestamp(), empty_type_info(), parameters);
+    let event = NodeEvent::Input {
+        id: request.reply_input,
+        metadata,
+        data: None,
+    };
+    if let Some(channel) = dataflow.subscribe_channels.get(&request.client) {
+        if send_with_timestamp(channel, event, clock, dataflow.sim_clock.as_ref()).is_err() {
+            dataflow.subscribe_channels.remove(&request.client);
         }
     }
 }
@@ -2819,6 +4454,10 @@ pub struct RunningNode {
     /// When set to true, the restart loop will restart the node regardless of
     /// the configured restart policy. Used for hot-reload.
     pending_hot_reload: Arc<AtomicBool>,
+    /// Hang detection settings of the node.
+    liveness: Option<Liveness>,
+    /// The cgroup that applies the resource limits of the node.
+    cgroup: Option<spawn::NodeCgroup>,
     /// Abort handle for this node's listener task, carried here until the dataflow
     /// collects it into `RunningDataflow::_listener_tasks`.
     listener_abort_handle: Option<tokio::task::AbortHandle>,
@@ -2838,6 +4477,8 @@ impl RunningNode {
 enum ProcessOperation {
     SoftKill,
     Kill,
+    /// Sends `SIGQUIT`, which makes many runtimes print the stack traces of all threads.
+    DumpStack,
 }
 
 impl ProcessOperation {
@@ -2876,6 +4517,20 @@ impl ProcessOperation {
                     warn!("failed to kill child process: {err}");
                 }
             }
+            Self::DumpStack => {
+                #[cfg(unix)]
+                {
+                    // Send SIGQUIT
+                    if let Err(err) = child.signal(3) {
+                        warn!("failed to send SIGQUIT to process {:?}: {err}", child.id());
+                    }
+                }
+
+                #[cfg(not(unix))]
+                {
+                    warn!("stack dumps are only supported on unix");
+                }
+            }
         }
     }
 }
@@ -2932,7 +4587,7 @@ pub struct RunningDataflow {
     subscribe_channels: HashMap<NodeId, UnboundedSender<Timestamped<NodeEvent>>>,
     drop_channels: HashMap<NodeId, UnboundedSender<Timestamped<NodeDropEvent>>>,
     mappings: HashMap<OutputId, BTreeSet<InputId>>,
-    timers: BTreeMap<Duration, BTreeSet<InputId>>,
+    timers: BTreeMap<Timer, BTreeSet<InputId>>,
     open_inputs: BTreeMap<NodeId, BTreeSet<DataId>>,
     running_nodes: BTreeMap<NodeId, RunningNode>,
 
@@ -2943,11 +4598,24 @@ pub struct RunningDataflow {
     dynamic_nodes: BTreeSet<NodeId>,
 
     open_external_mappings: BTreeSet<OutputId>,
+    /// Filters of remote inputs, used to skip messages that no remote node accepts.
+    ///
+    /// Contains `None` for remote inputs without a filter.
+    external_input_filters: HashMap<OutputId, Vec<Option<InputFilter>>>,
+
+    /// Replicated local nodes and their instances.
+    replicas: ReplicaSets,
+    /// Edge options (`max_rate`, `every`, `filter`) of local inputs.
+    input_gates: InputGates,
+    /// Pause and breakpoint state of `dora debug`.
+    debugger: Debugger,
+    /// Service requests of local nodes that wait for a reply.
+    service_requests: PendingRequests,
 
     pending_drop_tokens: HashMap<DropToken, DropTokenInformation>,
 
     /// Keep handles to all timer tasks of this dataflow to cancel them on drop.
-    _timer_handles: BTreeMap<Duration, futures::future::RemoteHandle<()>>,
+    _timer_handles: BTreeMap<Timer, futures::future::RemoteHandle<()>>,
     /// Keep abort handles for all node listener tasks so they are cancelled when
     /// the dataflow finishes and this struct is dropped.
     _listener_tasks: Vec<ListenerTask>,
@@ -2969,11 +4637,37 @@ pub struct RunningDataflow {
     node_stderr_most_recent: BTreeMap<NodeId, Arc<ArrayQueue<String>>>,
 
     publishers: BTreeMap<OutputId, zenoh::pubsub::Publisher<'static>>,
+    /// Dedicated zenoh session if the dataflow has custom zenoh settings.
+    zenoh_session: Option<zenoh::Session>,
 
     finished_tx: broadcast::Sender<()>,
 
     publish_all_messages_to_zenoh: bool,
 
+    /// Nodes that were stopped by `dora apply` and are waiting to exit.
+    ///
+    /// Their routes are already removed, so their exit needs no further cleanup.
+    stopped_by_apply: BTreeSet<NodeId>,
+    /// Restarted nodes that are spawned again once their old instance exited.
+    pending_respawns: BTreeMap<NodeId, ResolvedNode>,
+    /// Remote outputs that this daemon subscribed to via zenoh.
+    remote_subscriptions: BTreeSet<OutputId>,
+    /// Local nodes that wait for their `depends_on` conditions.
+    dependencies: StartupDependencies,
+    /// Ordered shutdown of the local nodes, set once the dataflow is stopped.
+    shutdown: Option<Shutdown>,
+    /// Hang detection of local nodes with a `liveness` configuration.
+    liveness: LivenessMonitors,
+    /// Working directories of nodes that were prepared by `dora build`, e.g. git clones.
+    build_working_dirs: BTreeMap<NodeId, PathBuf>,
+    uv: bool,
+    write_events_to: Option<PathBuf>,
+    /// Simulated clock of the dataflow, set for `dora run --clock sim`.
+    sim_clock: Option<SimClock>,
+
+    /// Rebuilds of watched nodes that are currently running (`dora run --watch`).
+    rebuilds: BTreeMap<NodeId, watch::Rebuild>,
+
     /// Tracks nodes that were stopped via hot-reload (`stop_single_node`).
     /// Their exit is expected, so normal cleanup is skipped.
     hot_reload_stopped_nodes: BTreeSet<NodeId>,
@@ -3000,6 +4694,7 @@ impl RunningDataflow {
         clock: Arc<HLC>,
     ) -> RunningDataflow {
         let (finished_tx, _) = broadcast::channel(1);
+        let service_requests = PendingRequests::new(&daemon_id);
         Self {
             id: dataflow_id,
             pending_nodes: PendingNodes::new(dataflow_id, daemon_id, events_tx, clock),
@@ -3012,6 +4707,11 @@ impl RunningDataflow {
             running_nodes: BTreeMap::new(),
             dynamic_nodes: BTreeSet::new(),
             open_external_mappings: Default::default(),
+            external_input_filters: HashMap::new(),
+            replicas: Default::default(),
+            input_gates: Default::default(),
+            debugger: Default::default(),
+            service_requests,
             pending_drop_tokens: HashMap::new(),
             _timer_handles: BTreeMap::new(),
             _listener_tasks: Vec::new(),
@@ -3021,31 +4721,80 @@ impl RunningDataflow {
             handled_node_failed: BTreeSet::new(),
             node_stderr_most_recent: BTreeMap::new(),
             publishers: Default::default(),
+            zenoh_session: None,
             finished_tx,
             publish_all_messages_to_zenoh: dataflow_descriptor.debug.publish_all_messages_to_zenoh,
             descriptor: dataflow_descriptor,
+            stopped_by_apply: BTreeSet::new(),
+            pending_respawns: BTreeMap::new(),
+            remote_subscriptions: BTreeSet::new(),
+            dependencies: Default::default(),
+            shutdown: None,
+            liveness: Default::default(),
+            build_working_dirs: BTreeMap::new(),
+            uv: false,
+            write_events_to: None,
+            sim_clock: None,
+            rebuilds: BTreeMap::new(),
             hot_reload_stopped_nodes: BTreeSet::new(),
             _hot_reload_watcher: None,
         }
     }
 
+    /// Records an event that was sent to a node listener for the simulated clock.
+    fn event_sent(&self) {
+        if let Some(sim_clock) = &self.sim_clock {
+            sim_clock.event_sent();
+        }
+    }
+
+    /// Sends a non-input event to a local node.
+    ///
+    /// If `dora debug` holds back inputs for the node, the event is queued behind them.
+    fn send_after_held_back(&mut self, node_id: &NodeId, event: NodeEvent, clock: &HLC) {
+        let event = Timestamped {
+            inner: event,
+            timestamp: clock.new_timestamp(),
+        };
+        let Some(event) = self.debugger.hold_back(node_id, event) else {
+            return;
+        };
+        if let Some(channel) = self.subscribe_channels.get(node_id) {
+            if channel.send(event).is_ok() {
+                self.event_sent();
+            }
+        }
+    }
+
     async fn start(
         &mut self,
         events_tx: &mpsc::Sender<Timestamped<Event>>,
         clock: &Arc<HLC>,
     ) -> eyre::Result<()> {
-        for interval in self.timers.keys().copied() {
-            if self._timer_handles.contains_key(&interval) {
+        if let Some(sim_clock) = &self.sim_clock {
+            // timer ticks are triggered by the simulated clock
+            for timer in self.timers.keys().copied() {
+                sim_clock.add_timer(timer);
+            }
+            sim_clock.start();
+            return Ok(());
+        }
+        for timer in self.timers.keys().copied() {
+            if self._timer_handles.contains_key(&timer) {
                 continue;
             }
             let events_tx = events_tx.clone();
             let dataflow_id = self.id;
             let clock = clock.clone();
             let task = async move {
-                let mut interval_stream = tokio::time::interval(interval);
+                let start = tokio::time::Instant::now();
+                let start_time = SystemTime::now();
+                let first_tick = start + timer::first_tick_delay(&timer, start_time);
+                let mut interval_stream = tokio::time::interval_at(first_tick, timer.interval);
                 let hlc = HLC::default();
                 loop {
-                    interval_stream.tick().await;
+                    let scheduled = interval_stream.tick().await;
+                    let jitter = scheduled.elapsed();
 
                     let span = tracing::span!(tracing::Level::TRACE, "tick");
                     let _ = span.enter();
@@ -3058,6 +4807,11 @@ impl RunningDataflow {
                         #[cfg(not(feature = "telemetry"))]
                         Parameter::String("".into()),
                     );
+                    timer::add_tick_parameters(
+                        &mut parameters,
+                        start_time + (scheduled - start),
+                        jitter,
+                    );
 
                     let metadata = metadata::Metadata::from_parameters(
                         hlc.new_timestamp(),
@@ -3068,20 +4822,20 @@ impl RunningDataflow {
                     let event = Timestamped {
                         inner: DoraEvent::Timer {
                             dataflow_id,
-                            interval,
+                            timer,
                             metadata,
                         }
                         .into(),
                         timestamp: clock.new_timestamp(),
                     };
-                    if events_tx.send(event).await.is_err() {
+                    if events_tx.send(event).await.is_err() || timer.once {
                         break;
                     }
                 }
             };
             let (task, handle) = task.remote_handle();
             tokio::spawn(task);
-            self._timer_handles.insert(interval, handle);
+            self._timer_handles.insert(timer, handle);
         }
 
         Ok(())
@@ -3096,7 +4850,7 @@ impl RunningDataflow {
     ) -> eyre::Result<FinishDataflowWhen> {
         self.pending_nodes
             .handle_dataflow_stop(
-                &state.coordinator_client().cloned(),
+                &state.coordinator_client(),
                 &state.clock,
                 &mut self.cascading_error_causes,
                 &self.dynamic_nodes,
@@ -3104,68 +4858,15 @@ impl RunningDataflow {
             )
             .await?;
 
-        for node in self.running_nodes.values_mut() {
-            node.disable_restart();
-        }
-
-        for (_node_id, channel) in self.subscribe_channels.drain() {
-            let _ = send_with_timestamp(
-                &channel,
-                NodeEvent::Stop {
-                    reason: Some(dora_message::daemon_to_node::StopCause::Manual),
-                },
-                &state.clock,
-            );
-        }
-
-        let running_processes: Vec<_> = self
-            .running_nodes
-            .iter_mut()
-            .map(|(id, n)| (id.clone(), n.process.take()))
-            .collect();
-        if force {
-            for (_, proc) in &running_processes {
-                if let Some(proc) = proc {
-                    proc.submit(crate::ProcessOperation::Kill);
-                }
-            }
-        } else {
-            let grace_duration_kills = self.grace_duration_kills.clone();
-            tokio::spawn(async move {
-                let duration = grace_duration.unwrap_or(Duration::from_millis(10000));
-                tokio::time::sleep(duration).await;
-
-                for (node, proc) in &running_processes {
-                    if let Some(proc) = proc {
-                        grace_duration_kills.insert(node.clone());
-                        proc.submit(crate::ProcessOperation::SoftKill);
-                    }
-                }
-
-                let kill_duration = duration / 2;
-                tokio::time::sleep(kill_duration).await;
-
-                for (node, proc) in &running_processes {
-                    if let Some(proc) = proc {
-                        grace_duration_kills.insert(node.clone());
-                        proc.submit(crate::ProcessOperation::Kill);
-                        warn!(
-                            "{node} was killed due to not stopping within the {:#?} grace period",
-                            duration + kill_duration
-                        );
-                    }
-                }
-            });
-        }
-        self.stop_sent = true;
-
-        // Determine if we should finish immediately or wait for nodes
-        Ok(self.should_finish_immediately())
+        self.stop_all_after_pending(state, grace_duration, force)
     }
 
-    /// Synchronous part of `stop_all` — sends stop signals to nodes and
-    /// schedules grace-period kills. Does NOT do any RPC or async I/O, so
-    /// it is safe to call while holding a DashMap guard.
+    /// Synchronous part of `stop_all` — sends stop signals to nodes or prepares
+    /// the ordered shutdown. Does NOT do any RPC or async I/O, so it is safe to
+    /// call while holding a DashMap guard.
+    ///
+    /// Unless `force` is set, the caller needs to start the ordered shutdown
+    /// through `Daemon::continue_shutdown` afterwards.
     fn stop_all_after_pending(
         &mut self,
         state: &state::DaemonState,
@@ -3175,61 +4876,111 @@ impl RunningDataflow {
         for node in self.running_nodes.values_mut() {
             node.disable_restart();
         }
+        self.dependencies.cancel();
 
-        for (_node_id, channel) in self.subscribe_channels.drain() {
-            let _ = send_with_timestamp(
-                &channel,
-                NodeEvent::Stop {
-                    reason: Some(dora_message::daemon_to_node::StopCause::Manual),
-                },
-                &state.clock,
-            );
-        }
-
-        let running_processes: Vec<_> = self
-            .running_nodes
-            .iter_mut()
-            .map(|(id, n)| (id.clone(), n.process.take()))
-            .collect();
         if force {
-            for (_, proc) in &running_processes {
-                if let Some(proc) = proc {
+            for (node_id, channel) in self.subscribe_channels.drain() {
+                self.liveness.stop_monitoring(&node_id);
+                send_stop(
+                    &channel,
+                    &node_id,
+                    StopCause::Manual,
+                    &mut self.debugger,
+                    &state.clock,
+                    self.sim_clock.as_ref(),
+                );
+            }
+            for node in self.running_nodes.values_mut() {
+                if let Some(proc) = node.process.take() {
                     proc.submit(crate::ProcessOperation::Kill);
                 }
             }
-        } else {
-            let grace_duration_kills = self.grace_duration_kills.clone();
-            tokio::spawn(async move {
-                let duration = grace_duration.unwrap_or(Duration::from_millis(10000));
-                tokio::time::sleep(duration).await;
-
-                for (node, proc) in &running_processes {
-                    if let Some(proc) = proc {
-                        grace_duration_kills.insert(node.clone());
-                        proc.submit(crate::ProcessOperation::SoftKill);
-                    }
-                }
-
-                let kill_duration = duration / 2;
-                tokio::time::sleep(kill_duration).await;
-
-                for (node, proc) in &running_processes {
-                    if let Some(proc) = proc {
-                        grace_duration_kills.insert(node.clone());
-                        proc.submit(crate::ProcessOperation::Kill);
-                        warn!(
-                            "{node} was killed due to not stopping within the {:#?} grace period",
-                            duration + kill_duration
-                        );
-                    }
-                }
-            });
+        } else if self.shutdown.is_none() {
+            self.shutdown = Some(Shutdown::new(
+                self.running_nodes.keys().cloned(),
+                grace_duration,
+            ));
         }
         self.stop_sent = true;
 
         Ok(self.should_finish_immediately())
     }
 
+    /// Sends stop events to the nodes of the next shutdown stage.
+    ///
+    /// Returns the stage number and the stopped nodes with their stop timeout.
+    fn stop_next_nodes(
+        &mut self,
+        state: &state::DaemonState,
+    ) -> Option<(usize, Vec<(NodeId, Duration)>)> {
+        let mut shutdown = self.shutdown.take()?;
+        let next = shutdown.next_stage(|node_id| self.upstream_nodes(node_id));
+        let mut stopped = Vec::new();
+        for node_id in next.iter().flat_map(|(_, nodes)| nodes) {
+            self.liveness.stop_monitoring(node_id);
+            if let Some(channel) = self.subscribe_channels.remove(node_id) {
+                send_stop(
+                    &channel,
+                    node_id,
+                    StopCause::Manual,
+                    &mut self.debugger,
+                    &state.clock,
+                    self.sim_clock.as_ref(),
+                );
+            }
+            let timeout = self
+                .stop_timeout(node_id)
+                .unwrap_or(shutdown.default_timeout());
+            if let Some(process) = self
+                .running_nodes
+                .get(node_id)
+                .and_then(|n| n.process.as_ref())
+            {
+                shutdown.start_kill_timer(
+                    node_id.clone(),
+                    timeout,
+                    process.clone_sender(),
+                    self.grace_duration_kills.clone(),
+                );
+            }
+            stopped.push((node_id.clone(), timeout));
+        }
+        if shutdown.waits_for_remote_nodes() {
+            shutdown.schedule_deadline(self.id, state.events_tx.clone(), state.clock.clone());
+        }
+        self.shutdown = Some(shutdown);
+        next.map(|(stage, _)| (stage, stopped))
+    }
+
+    /// Nodes that still send messages to open inputs of the given local node.
+    ///
+    /// Service replies are ignored because servers are stopped after their clients.
+    fn upstream_nodes(&self, node_id: &NodeId) -> BTreeSet<NodeId> {
+        let open_inputs = self.open_inputs(node_id);
+        let descriptor_id = self.replicas.node_id(node_id);
+        self.mappings
+            .iter()
+            .filter(|(output, _)| &output.0 != descriptor_id)
+            .filter(|(_, receivers)| {
+                receivers.iter().any(|(receiver, input)| {
+                    receiver == node_id
+                        && open_inputs.contains(input)
+                        && !input.starts_with(REPLY_PREFIX)
+                })
+            })
+            .map(|(output, _)| output.0.clone())
+            .collect()
+    }
+
+    fn stop_timeout(&self, node_id: &NodeId) -> Option<Duration> {
+        let descriptor_id = self.replicas.node_id(node_id);
+        self.descriptor
+            .nodes
+            .iter()
+            .find(|n| &n.id == descriptor_id)
+            .and_then(|n| n.stop_timeout)
+    }
+
     /// Check if dataflow should finish immediately after stop_all().
     /// Returns `Now` if all running nodes are dynamic (they won't send SpawnedNodeResult).
     /// Returns `WaitForNodes` if there are non-dynamic nodes to wait for.
@@ -3238,23 +4989,197 @@ impl RunningDataflow {
         // 1. No pending nodes
         // 2. All running nodes are dynamic (they won't send SpawnedNodeResult)
         // 3. Stop was sent (stop_all() was called)
-        if !self.pending_nodes.local_nodes_pending()
-            && self
-                .running_nodes
-                .iter()
-                .all(|(_id, n)| n.node_config.dynamic)
-            && self.stop_sent
-        {
+        if self.all_nodes_finished() && self.stop_sent {
             FinishDataflowWhen::Now
         } else {
             FinishDataflowWhen::WaitForNodes
         }
     }
 
+    /// Whether all local nodes finished, ignoring dynamic nodes.
+    ///
+    /// Dynamic nodes won't send SpawnedNodeResult events.
+    fn all_nodes_finished(&self) -> bool {
+        !self.pending_nodes.local_nodes_pending()
+            && !self.dependencies.pending()
+            && self
+                .running_nodes
+                .iter()
+                .all(|(_id, n)| n.node_config.dynamic)
+    }
+
     fn open_inputs(&self, node_id: &NodeId) -> &BTreeSet<DataId> {
         self.open_inputs.get(node_id).unwrap_or(&EMPTY_SET)
     }
 
+    /// Routes the messages of the given input mapping to a local node.
+    fn add_input_route(&mut self, node_id: &NodeId, input_id: DataId, input: Input) {
+        self.open_inputs
+            .entry(node_id.clone())
+            .or_default()
+            .insert(input_id.clone());
+        let gate_id = (self.replicas.node_id(node_id).clone(), input_id.clone());
+        self.input_gates.insert(gate_id, &input);
+        self.debugger
+            .set_queue_size(node_id.clone(), input_id.clone(), input.queue_size);
+        match input.mapping {
+            InputMapping::User(mapping) => {
+                self.mappings
+                    .entry(OutputId(mapping.source, mapping.output))
+                    .or_default()
+                    .insert((node_id.clone(), input_id));
+            }
+            InputMapping::Timer(timer) => {
+                self.timers
+                    .entry(timer)
+                    .or_default()
+                    .insert((node_id.clone(), input_id));
+            }
+        }
+    }
+
+    /// Removes the route of a local input again, stopping timers that have no
+    /// receivers left.
+    ///
+    /// The input stays in `open_inputs`, which is updated by the caller.
+    fn remove_input_route(&mut self, node_id: &NodeId, input_id: &DataId, input: &Input) {
+        let receiver = (node_id.clone(), input_id.clone());
+        self.input_gates
+            .remove(&(self.replicas.node_id(node_id).clone(), input_id.clone()));
+        self.debugger.remove_queue_size(node_id, input_id);
+        match &input.mapping {
+            InputMapping::User(mapping) => {
+                let output_id = OutputId(mapping.source.clone(), mapping.output.clone());
+                if let Some(receivers) = self.mappings.get_mut(&output_id) {
+                    receivers.remove(&receiver);
+                    if receivers.is_empty() {
+                        self.mappings.remove(&output_id);
+                    }
+                }
+            }
+            InputMapping::Timer(timer) => {
+                if let Some(receivers) = self.timers.get_mut(timer) {
+                    receivers.remove(&receiver);
+                    if receivers.is_empty() {
+                        self.timers.remove(timer);
+                        // dropping the handle cancels the timer task
+                        self._timer_handles.remove(timer);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Records that the given output is sent to a remote node.
+    fn add_remote_input(&mut self, output_id: OutputId, input: Input) {
+        self.external_input_filters
+            .entry(output_id.clone())
+            .or_default()
+            .push(input.filter);
+        self.open_external_mappings.insert(output_id);
+    }
+
+    /// Restarts a custom node by sending it a `Stop(HotReload)` event.
+    ///
+    /// The node can save its state and exit gracefully. A fallback SIGTERM is
+    /// sent after 2 seconds in case the node doesn't respond. Returns `false`
+    /// if the node is not running.
+    fn hot_reload_node(&mut self, node_id: &NodeId, clock: &HLC) -> bool {
+        let Some(running_node) = self.running_nodes.get(node_id) else {
+            return false;
+        };
+        tracing::info!(
+            "Hot-reload: sending Stop(HotReload) to custom node `{node_id}` for restart"
+        );
+
+        // Set pending_hot_reload flag before sending the stop event to avoid races
+        running_node
+            .pending_hot_reload
+            .store(true, atomic::Ordering::Release);
+        self.liveness.stop_monitoring(node_id);
+
+        if let Some(channel) = self.subscribe_channels.get(node_id) {
+            send_stop(
+                channel,
+                node_id,
+                StopCause::HotReload,
+                &mut self.debugger,
+                clock,
+                self.sim_clock.as_ref(),
+            );
+        }
+
+        // Spawn a fallback task: if the node doesn't exit within 2 seconds, send SIGTERM
+        if let Some(process) = &running_node.process {
+            let sender = process.clone_sender();
+            let pending_flag = running_node.pending_hot_reload.clone();
+            tokio::spawn(async move {
+                tokio::time::sleep(Duration::from_secs(2)).await;
+                if pending_flag.load(atomic::Ordering::Acquire) {
+                    let _ = sender.send(ProcessOperation::SoftKill);
+                }
+            });
+        }
+        true
+    }
+
+    /// Stops a single node for `dora apply`, killing it if it doesn't exit in time.
+    ///
+    /// Returns `false` if the node doesn't report its exit, i.e. if it is not
+    /// running or if it is a dynamic node.
+    fn stop_node_for_apply(&mut self, node_id: &NodeId, reason: StopCause, clock: &HLC) -> bool {
+        let Some(node) = self.running_nodes.get_mut(node_id) else {
+            return false;
+        };
+        node.disable_restart();
+        self.liveness.stop_monitoring(node_id);
+        if let Some(channel) = self.subscribe_channels.remove(node_id) {
+            send_stop(
+                &channel,
+                node_id,
+                reason,
+                &mut self.debugger,
+                clock,
+                self.sim_clock.as_ref(),
+            );
+        }
+        if node.node_config.dynamic {
+            self.running_nodes.remove(node_id);
+            self.dynamic_nodes.remove(node_id);
+            return false;
+        }
+        if let Some(process) = &node.process {
+            let sender = process.clone_sender();
+            let grace_duration_kills = self.grace_duration_kills.clone();
+            let node_id = node_id.clone();
+            tokio::spawn(async move {
+                let duration = Duration::from_millis(10000);
+                tokio::time::sleep(duration).await;
+                if sender.send(ProcessOperation::SoftKill).is_ok() {
+                    grace_duration_kills.insert(node_id.clone());
+                }
+                tokio::time::sleep(duration / 2).await;
+                if sender.send(ProcessOperation::Kill).is_ok() {
+                    warn!("{node_id} was killed due to not stopping within the grace period");
+                }
+            });
+        }
+        self.stopped_by_apply.insert(node_id.clone());
+        true
+    }
+
+    /// Returns the codec that the given output is compressed with before it is
+    /// sent to remote receivers.
+    fn output_compression(&self, output_id: &OutputId) -> Option<Compression> {
+        let OutputId(node_id, data_id) = output_id;
+        self.descriptor
+            .nodes
+            .iter()
+            .find(|node| &node.id == node_id)
+            .and_then(|node| node.compression.get(data_id))
+            .copied()
+    }
+
     async fn check_drop_token(&mut self, token: DropToken, clock: &HLC) -> eyre::Result<()> {
         match self.pending_drop_tokens.entry(token) {
             std::collections::hash_map::Entry::Occupied(entry) => {
@@ -3265,6 +5190,7 @@ impl RunningDataflow {
                             channel,
                             NodeDropEvent::OutputDropped { drop_token },
                             clock,
+                            self.sim_clock.as_ref(),
                         )
                         .wrap_err("send failed"),
                         None => Err(eyre!("no subscribe channel for node `{}`", &info.owner)),
@@ -3342,6 +5268,7 @@ pub enum Event {
         write_events_to: Option<PathBuf>,
         hot_reload: bool,
         dataflow_path: Option<PathBuf>,
+        clock: DataflowClock,
         reply_tx: oneshot::Sender<Result<(), String>>,
     },
     /// Coordinator requested stopping a dataflow (routed from RPC server).
@@ -3351,6 +5278,24 @@ pub enum Event {
         force: bool,
         reply_tx: oneshot::Sender<Result<(), String>>,
     },
+    /// Message injected by `dora topic pub` (routed from RPC server).
+    PublishRequest {
+        dataflow_id: DataflowId,
+        message: PublishMessage,
+        reply_tx: oneshot::Sender<Result<(), String>>,
+    },
+    /// `dora apply` changes for a running dataflow (routed from RPC server).
+    ApplyRequest {
+        dataflow_id: DataflowId,
+        request: ApplyDataflowChanges,
+        reply_tx: oneshot::Sender<Result<(), String>>,
+    },
+    /// `dora debug` command for a dataflow (routed from RPC server).
+    DebugRequest {
+        dataflow_id: DataflowId,
+        command: DebugCommand,
+        reply_tx: oneshot::Sender<Result<DebugStatus, String>>,
+    },
     SpawnNodeResult {
         dataflow_id: DataflowId,
         node_id: NodeId,
@@ -3365,6 +5310,53 @@ pub enum Event {
         dataflow_id: Uuid,
         node_id: NodeId,
     },
+    /// The simulated clock might be able to advance.
+    SimulatedClockIdle,
+    /// The timeout of a service request expired.
+    ServiceRequestTimeout {
+        dataflow_id: DataflowId,
+        request_id: i64,
+    },
+    /// A source file of a watched node changed (`dora run --watch`).
+    NodeSourceChanged {
+        dataflow_id: DataflowId,
+        node_id: NodeId,
+        node: WatchedNode,
+    },
+    /// The rebuild of a watched node finished.
+    NodeRebuilt {
+        dataflow_id: DataflowId,
+        node_id: NodeId,
+        node: WatchedNode,
+        /// Whether the node needs to be restarted.
+        result: eyre::Result<bool>,
+    },
+    /// A local node reached a `depends_on` condition of other nodes.
+    NodeReadiness {
+        dataflow_id: DataflowId,
+        node_id: NodeId,
+        readiness: Readiness,
+    },
+    /// Startup progress of a remote node that local nodes depend on (routed from RPC server).
+    DependencyStatus {
+        dataflow_id: DataflowId,
+        node_id: NodeId,
+        status: DependencyStatus,
+    },
+    /// The `startup_timeout` of a node that waits for its dependencies expired.
+    StartupTimeout {
+        dataflow_id: DataflowId,
+        node_id: NodeId,
+    },
+    /// Local nodes of a stopped dataflow waited too long for remote upstream nodes.
+    ShutdownDeadline {
+        dataflow_id: DataflowId,
+    },
+    /// The liveness timeout of a node could have passed since its last heartbeat.
+    LivenessCheck {
+        dataflow_id: DataflowId,
+        node_id: NodeId,
+    },
 }
 
 impl From<DoraEvent> for Event {
@@ -3389,9 +5381,21 @@ impl Event {
             Event::Destroy => "Destroy",
             Event::SpawnRequest { .. } => "SpawnRequest",
             Event::StopDataflowRequest { .. } => "StopDataflowRequest",
+            Event::DebugRequest { .. } => "DebugRequest",
+            Event::PublishRequest { .. } => "PublishRequest",
+            Event::ApplyRequest { .. } => "ApplyRequest",
             Event::SpawnNodeResult { .. } => "SpawnNodeResult",
             Event::SpawnDataflowResult { .. } => "SpawnDataflowResult",
             Event::NodeStopped { .. } => "NodeStopped",
+            Event::SimulatedClockIdle => "SimulatedClockIdle",
+            Event::ServiceRequestTimeout { .. } => "ServiceRequestTimeout",
+            Event::NodeSourceChanged { .. } => "NodeSourceChanged",
+            Event::NodeRebuilt { .. } => "NodeRebuilt",
+            Event::NodeReadiness { .. } => "NodeReadiness",
+            Event::DependencyStatus { .. } => "DependencyStatus",
+            Event::StartupTimeout { .. } => "StartupTimeout",
+            Event::ShutdownDeadline { .. } => "ShutdownDeadline",
+            Event::LivenessCheck { .. } => "LivenessCheck",
         }
     }
 }
@@ -3425,13 +5429,21 @@ pub enum DaemonNodeEvent {
     EventStreamDropped {
         reply_sender: oneshot::Sender<DaemonReply>,
     },
+    MarkReady {
+        reply_sender: oneshot::Sender<DaemonReply>,
+    },
+    ExtendStopTimeout {
+        duration: Duration,
+        reply_sender: oneshot::Sender<DaemonReply>,
+    },
+    Heartbeat,
 }
 
 #[derive(Debug)]
 pub enum DoraEvent {
     Timer {
         dataflow_id: DataflowId,
-        interval: Duration,
+        timer: Timer,
         metadata: metadata::Metadata,
     },
     Logs {
@@ -3445,20 +5457,53 @@ pub enum DoraEvent {
         node_id: NodeId,
         dynamic_node: bool,
         exit_status: NodeExitStatus,
+        /// Whether a process of the node was killed because it exceeded its memory limit
+        memory_limit_exceeded: bool,
         /// Whether the node will be restarted
         restart: bool,
     },
 }
 
+/// Sends a `Stop` event to a node that is stopped.
+///
+/// Events that `dora debug` still holds back for the node are delivered first,
+/// so that the node sees all inputs that were sent before the stop.
+fn send_stop(
+    channel: &UnboundedSender<Timestamped<NodeEvent>>,
+    node_id: &NodeId,
+    reason: StopCause,
+    debugger: &mut Debugger,
+    clock: &HLC,
+    sim_clock: Option<&SimClock>,
+) {
+    for event in debugger.take_node(node_id) {
+        if channel.send(event).is_err() {
+            return;
+        }
+        if let Some(sim_clock) = sim_clock {
+            sim_clock.event_sent();
+        }
+    }
+    let event = NodeEvent::Stop {
+        reason: Some(reason),
+    };
+    let _ = send_with_timestamp(channel, event, clock, sim_clock);
+}
+
 fn send_with_timestamp<T>(
     sender: &UnboundedSender<Timestamped<T>>,
     event: T,
     clock: &HLC,
+    sim_clock: Option<&SimClock>,
 ) -> Result<(), mpsc::error::SendError<Timestamped<T>>> {
     sender.send(Timestamped {
         inner: event,
         timestamp: clock.new_timestamp(),
-    })
+    })?;
+    if let Some(sim_clock) = sim_clock {
+        sim_clock.event_sent();
+    }
+    Ok(())
 }
 
 fn set_up_ctrlc_handler(
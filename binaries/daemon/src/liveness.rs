@@ -0,0 +1,233 @@
+//! Hang detection through the `liveness` field of the dataflow.
+//!
+//! Nodes with a liveness configuration send heartbeats from their event loop.
+//! Monitoring starts with the first heartbeat of a node, so that slow node
+//! initialization is not mistaken for a hang. Whenever the liveness timeout of
+//! a node could have passed, the daemon checks the time of its last heartbeat.
+//! Unresponsive nodes are killed, optionally after sending them `SIGQUIT` to
+//! get a stack dump, and reported as failed.
+
+use std::{
+    collections::{BTreeMap, BTreeSet, btree_map},
+    sync::{
+        Arc,
+        atomic::{self, AtomicU32},
+    },
+    time::Duration,
+};
+
+use dora_core::uhlc::HLC;
+use dora_message::{DataflowId, common::Timestamped, descriptor::Liveness, id::NodeId};
+use futures::{FutureExt, future::RemoteHandle};
+use tokio::{sync::mpsc, time::Instant};
+
+use crate::{Event, ProcessOperation};
+
+/// Time that an unresponsive node gets to print its stack dump before it is killed.
+const STACK_DUMP_DURATION: Duration = Duration::from_secs(2);
+
+/// Liveness monitoring of the local nodes of a dataflow.
+#[derive(Default)]
+pub struct LivenessMonitors {
+    monitors: BTreeMap<NodeId, Monitor>,
+    /// Nodes that were asked to stop or exited.
+    ///
+    /// Their heartbeats are ignored until they subscribe again, e.g. after a restart.
+    stopped: BTreeSet<NodeId>,
+    /// Nodes that were killed because they were unresponsive, with their liveness timeout.
+    unresponsive: BTreeMap<NodeId, Duration>,
+}
+
+struct Monitor {
+    liveness: Liveness,
+    last_heartbeat: Instant,
+    /// Sends the next [`Event::LivenessCheck`], canceled when dropped.
+    _check: RemoteHandle<()>,
+}
+
+impl LivenessMonitors {
+    /// Resets the monitoring state of a (re)started node.
+    pub fn node_subscribed(&mut self, node_id: &NodeId) {
+        self.monitors.remove(node_id);
+        self.stopped.remove(node_id);
+    }
+
+    /// Records a heartbeat of the given node, starting its monitoring if needed.
+    pub fn heartbeat(
+        &mut self,
+        dataflow_id: DataflowId,
+        node_id: NodeId,
+        liveness: &Liveness,
+        events_tx: &mpsc::Sender<Timestamped<Event>>,
+        clock: &Arc<HLC>,
+    ) {
+        if self.stopped.contains(&node_id) {
+            return;
+        }
+        match self.monitors.entry(node_id) {
+            btree_map::Entry::Occupied(mut entry) => {
+                entry.get_mut().last_heartbeat = Instant::now();
+            }
+            btree_map::Entry::Vacant(entry) => {
+                let check = schedule_check(
+                    dataflow_id,
+                    entry.key().clone(),
+                    liveness.timeout,
+                    events_tx.clone(),
+                    clock.clone(),
+                );
+                entry.insert(Monitor {
+                    liveness: liveness.clone(),
+                    last_heartbeat: Instant::now(),
+                    _check: check,
+                });
+            }
+        }
+    }
+
+    /// Stops monitoring the given node, e.g. because it was asked to stop.
+    ///
+    /// Nodes might stop calling `recv` once they received a stop event, so their
+    /// missing heartbeats don't indicate a hang.
+    pub fn stop_monitoring(&mut self, node_id: &NodeId) {
+        self.monitors.remove(node_id);
+        self.stopped.insert(node_id.clone());
+    }
+
+    /// Checks whether the given node missed its liveness deadline.
+    ///
+    /// Returns the liveness configuration of the node if it is unresponsive. The
+    /// node is not monitored anymore afterwards. Otherwise, the next check is
+    /// scheduled for the deadline of the last heartbeat.
+    pub fn check(
+        &mut self,
+        dataflow_id: DataflowId,
+        node_id: &NodeId,
+        events_tx: &mpsc::Sender<Timestamped<Event>>,
+        clock: &Arc<HLC>,
+    ) -> Option<Liveness> {
+        let monitor = self.monitors.get_mut(node_id)?;
+        let deadline = monitor.last_heartbeat + monitor.liveness.timeout;
+        let now = Instant::now();
+        if now < deadline {
+            monitor._check = schedule_check(
+                dataflow_id,
+                node_id.clone(),
+                deadline - now,
+                events_tx.clone(),
+                clock.clone(),
+            );
+            return None;
+        }
+        let monitor = self.monitors.remove(node_id)?;
+        self.stopped.insert(node_id.clone());
+        self.unresponsive
+            .insert(node_id.clone(), monitor.liveness.timeout);
+        Some(monitor.liveness)
+    }
+
+    /// Returns the liveness timeout if the node was killed because it was unresponsive.
+    pub fn take_unresponsive(&mut self, node_id: &NodeId) -> Option<Duration> {
+        self.unresponsive.remove(node_id)
+    }
+}
+
+/// Kills an unresponsive node, after sending `SIGQUIT` to it if a stack dump is requested.
+///
+/// The process might exit on `SIGQUIT` and be restarted before the stack dump
+/// duration passed, so the kill is skipped if the `pid` of the node changed.
+pub fn kill_unresponsive(
+    process: flume::Sender<ProcessOperation>,
+    pid: Arc<AtomicU32>,
+    stack_dump: bool,
+) {
+    let unresponsive_pid = pid.load(atomic::Ordering::Acquire);
+    tokio::spawn(async move {
+        if stack_dump && process.send(ProcessOperation::DumpStack).is_ok() {
+            tokio::time::sleep(STACK_DUMP_DURATION).await;
+        }
+        if pid.load(atomic::Ordering::Acquire) == unresponsive_pid {
+            let _ = process.send(ProcessOperation::Kill);
+        }
+    });
+}
+
+/// Sends an [`Event::LivenessCheck`] for the given node after the delay.
+fn schedule_check(
+    dataflow_id: DataflowId,
+    node_id: NodeId,
+    delay: Duration,
+    events_tx: mpsc::Sender<Timestamped<Event>>,
+    clock: Arc<HLC>,
+) -> RemoteHandle<()> {
+    let (task, handle) = async move {
+        tokio::time::sleep(delay).await;
+        let event = Timestamped {
+            inner: Event::LivenessCheck {
+                dataflow_id,
+                node_id,
+            },
+            timestamp: clock.new_timestamp(),
+        };
+        let _ = events_tx.send(event).await;
+    }
+    .remote_handle();
+    tokio::spawn(task);
+    handle
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, time::Duration};
+
+    use dora_core::uhlc::HLC;
+    use dora_message::{common::Timestamped, descriptor::Liveness, id::NodeId};
+    use tokio::sync::mpsc;
+    use uuid::Uuid;
+
+    use super::LivenessMonitors;
+    use crate::Event;
+
+    #[tokio::test]
+    async fn unresponsive_nodes_are_detected() {
+        let (events_tx, mut events_rx) = mpsc::channel::<Timestamped<Event>>(10);
+        let clock = Arc::new(HLC::default());
+        let dataflow_id = Uuid::new_v4();
+        let node_id: NodeId = "planner".to_owned().into();
+        let liveness = Liveness {
+            timeout: Duration::from_millis(300),
+            stack_dump: false,
+        };
+        let mut monitors = LivenessMonitors::default();
+        let mut next_check = async || match events_rx.recv().await.unwrap().inner {
+            Event::LivenessCheck { node_id, .. } => node_id,
+            _ => panic!("unexpected event"),
+        };
+
+        monitors.heartbeat(dataflow_id, node_id.clone(), &liveness, &events_tx, &clock);
+        tokio::time::sleep(Duration::from_millis(150)).await;
+        monitors.heartbeat(dataflow_id, node_id.clone(), &liveness, &events_tx, &clock);
+
+        // the first check happens one timeout after the first heartbeat
+        assert_eq!(next_check().await, node_id);
+        assert_eq!(
+            monitors.check(dataflow_id, &node_id, &events_tx, &clock),
+            None
+        );
+
+        // the second check happens one timeout after the last heartbeat
+        assert_eq!(next_check().await, node_id);
+        assert_eq!(
+            monitors.check(dataflow_id, &node_id, &events_tx, &clock),
+            Some(liveness.clone())
+        );
+        assert_eq!(monitors.take_unresponsive(&node_id), Some(liveness.timeout));
+
+        // heartbeats are ignored until the restarted node subscribes again
+        monitors.heartbeat(dataflow_id, node_id.clone(), &liveness, &events_tx, &clock);
+        assert!(monitors.monitors.is_empty());
+        monitors.node_subscribed(&node_id);
+        monitors.heartbeat(dataflow_id, node_id.clone(), &liveness, &events_tx, &clock);
+        assert!(monitors.monitors.contains_key(&node_id));
+    }
+}
@@ -1,4 +1,4 @@
-use crate::{DaemonNodeEvent, Event};
+use crate::{DaemonNodeEvent, Event, sim_clock::SimClock};
 use dora_core::{
     config::{DataId, NodeId},
     topics::LOCALHOST,
@@ -28,7 +28,29 @@ use tokio::{
     },
 };
 
+#[cfg(target_os = "linux")]
+pub mod shmem;
 pub mod tcp;
+#[cfg(unix)]
+pub mod unix_domain;
+
+/// Transport that nodes use to connect to their daemon.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NodeCommunication {
+    /// Loopback TCP connections, available on all platforms.
+    #[default]
+    Tcp,
+    /// Unix domain sockets, which have a lower latency than TCP.
+    ///
+    /// Falls back to TCP on platforms without Unix domain sockets and for
+    /// nodes that run in a `sandbox`.
+    UnixDomain,
+    /// Shared memory queues, which avoid a system call per message.
+    ///
+    /// Falls back to TCP on platforms other than Linux and for nodes that
+    /// run in a `sandbox`.
+    SharedMemory,
+}
 
 pub async fn spawn_listener_loop(
     dataflow_id: &DataflowId,
@@ -36,7 +58,64 @@ pub async fn spawn_listener_loop(
     daemon_tx: &mpsc::Sender<Timestamped<Event>>,
     queue_sizes: BTreeMap<DataId, usize>,
     clock: Arc<uhlc::HLC>,
+    sim_clock: Option<SimClock>,
+    communication: NodeCommunication,
 ) -> eyre::Result<(DaemonCommunication, Option<tokio::task::AbortHandle>)> {
+    match communication {
+        NodeCommunication::Tcp => {}
+        #[cfg(unix)]
+        NodeCommunication::UnixDomain => {
+            match unix_domain::spawn_listener_loop(
+                dataflow_id,
+                node_id,
+                daemon_tx,
+                queue_sizes.clone(),
+                clock.clone(),
+                sim_clock.clone(),
+            )
+            .await
+            {
+                Ok(result) => return Ok(result),
+                Err(err) => tracing::warn!(
+                    "failed to listen on Unix domain socket for node `{node_id}`, \
+                    falling back to TCP: {err:?}"
+                ),
+            }
+        }
+        #[cfg(not(unix))]
+        NodeCommunication::UnixDomain => {
+            tracing::warn!(
+                "Unix domain sockets are not supported on this platform, \
+                falling back to TCP for node `{node_id}`"
+            );
+        }
+        #[cfg(target_os = "linux")]
+        NodeCommunication::SharedMemory => {
+            match shmem::spawn_listener_loop(
+                dataflow_id,
+                node_id,
+                daemon_tx,
+                clock.clone(),
+                sim_clock.clone(),
+            )
+            .await
+            {
+                Ok(result) => return Ok(result),
+                Err(err) => tracing::warn!(
+                    "failed to create shared memory channels for node `{node_id}`, \
+                    falling back to TCP: {err:?}"
+                ),
+            }
+        }
+        #[cfg(not(target_os = "linux"))]
+        NodeCommunication::SharedMemory => {
+            tracing::warn!(
+                "shared memory channels are not supported on this platform, \
+                falling back to TCP for node `{node_id}`"
+            );
+        }
+    }
+
     let socket = match TcpListener::bind((LOCALHOST, 0)).await {
         Ok(socket) => socket,
         Err(err) => {
@@ -50,7 +129,7 @@ pub async fn spawn_listener_loop(
     let event_loop_node_id = format!("{dataflow_id}/{node_id}");
     let daemon_tx = daemon_tx.clone();
     let handle = tokio::spawn(async move {
-        tcp::listener_loop(socket, daemon_tx, queue_sizes, clock).await;
+        tcp::listener_loop(socket, daemon_tx, queue_sizes, clock, sim_clock).await;
         tracing::debug!("event listener loop finished for `{event_loop_node_id}`");
     });
     let abort_handle = handle.abort_handle();
@@ -66,6 +145,7 @@ struct Listener {
     subscribed_drop_events: Option<UnboundedReceiver<Timestamped<NodeDropEvent>>>,
     queue: VecDeque<Box<Option<Timestamped<NodeEventOrUnknown>>>>,
     clock: Arc<uhlc::HLC>,
+    sim_clock: Option<SimClock>,
 }
 
 impl Listener {
@@ -73,6 +153,7 @@ impl Listener {
         mut connection: C,
         daemon_tx: mpsc::Sender<Timestamped<Event>>,
         hlc: Arc<uhlc::HLC>,
+        sim_clock: Option<SimClock>,
     ) {
         // receive the first message
         let message = match connection
@@ -114,6 +195,7 @@ impl Listener {
                             subscribed_drop_events: None,
                             queue: VecDeque::new(),
                             clock: hlc.clone(),
+                            sim_clock,
                         };
                         match listener
                             .run_inner(connection)
@@ -162,6 +244,7 @@ impl Listener {
                 };
 
                 self.queue.push_back(Box::new(Some(event.into())));
+                self.record_received_events(1, true);
                 self.handle_events().await?;
             };
 
@@ -183,14 +266,37 @@ impl Listener {
     }
 
     async fn handle_events(&mut self) -> eyre::Result<()> {
+        let mut received = 0;
         if let Some(events) = &mut self.subscribed_events {
             while let Ok(event) = events.try_recv() {
                 self.queue.push_back(Box::new(Some(event.into())));
+                received += 1;
             }
         }
+        self.record_received_events(received, true);
         Ok(())
     }
 
+    /// Updates the simulated clock state after receiving events from the daemon.
+    fn record_received_events(&self, count: u64, event_stream: bool) {
+        let Some(sim_clock) = &self.sim_clock else {
+            return;
+        };
+        if sim_clock.events_received(event_stream.then_some(&self.node_id), count) {
+            self.notify_simulated_clock();
+        }
+    }
+
+    /// Tells the daemon main loop that the simulated clock might be able to advance.
+    fn notify_simulated_clock(&self) {
+        let event = Timestamped {
+            inner: Event::SimulatedClockIdle,
+            timestamp: self.clock.new_timestamp(),
+        };
+        // if the channel is full, the main loop checks the clock anyway
+        let _ = self.daemon_tx.try_send(event);
+    }
+
     #[tracing::instrument(skip(self, connection), fields(%self.dataflow_id, %self.node_id), level = "trace")]
     async fn handle_message<C: Connection>(
         &mut self,
@@ -287,7 +393,10 @@ impl Listener {
                     match self.subscribed_events.as_mut() {
                         // wait for next event
                         Some(events) => match events.recv().await {
-                            Some(event) => DaemonReply::NextEvents(vec![event.into()]),
+                            Some(event) => {
+                                self.record_received_events(1, true);
+                                DaemonReply::NextEvents(vec![event.into()])
+                            }
                             None => DaemonReply::NextEvents(vec![]),
                         },
                         None => {
@@ -311,11 +420,32 @@ impl Listener {
                     .await
                     .wrap_err("failed to send ReportDropTokens reply")?;
             }
+            DaemonRequest::ReportIdle {
+                sent_outputs,
+                wake_at,
+            } => {
+                self.handle_events().await?;
+                // the node is only idle if there are no queued events for it
+                if !self.queue.iter().any(|e| e.is_some())
+                    && self.sim_clock.as_ref().is_some_and(|sim_clock| {
+                        sim_clock.node_idle(&self.node_id, sent_outputs, wake_at)
+                    })
+                {
+                    self.notify_simulated_clock();
+                }
+
+                self.send_reply(DaemonReply::Empty, connection)
+                    .await
+                    .wrap_err("failed to send ReportIdle reply")?;
+            }
             DaemonRequest::NextFinishedDropTokens => {
                 let reply = match self.subscribed_drop_events.as_mut() {
                     // wait for next event
                     Some(events) => match events.recv().await {
-                        Some(event) => DaemonReply::NextDropEvents(vec![event]),
+                        Some(event) => {
+                            self.record_received_events(1, false);
+                            DaemonReply::NextDropEvents(vec![event])
+                        }
                         None => DaemonReply::NextDropEvents(vec![]),
                     },
                     None => DaemonReply::Result(Err("Ignoring event request because no drop \
@@ -329,6 +459,31 @@ impl Listener {
                         format!("failed to send NextFinishedDropTokens reply: {reply:?}")
                     })?;
             }
+            DaemonRequest::MarkReady => {
+                let (reply_sender, reply) = oneshot::channel();
+                self.process_daemon_event(
+                    DaemonNodeEvent::MarkReady { reply_sender },
+                    Some(reply),
+                    connection,
+                )
+                .await?;
+            }
+            DaemonRequest::ExtendStopTimeout { duration } => {
+                let (reply_sender, reply) = oneshot::channel();
+                self.process_daemon_event(
+                    DaemonNodeEvent::ExtendStopTimeout {
+                        duration,
+                        reply_sender,
+                    },
+                    Some(reply),
+                    connection,
+                )
+                .await?;
+            }
+            DaemonRequest::Heartbeat => {
+                self.process_daemon_event(DaemonNodeEvent::Heartbeat, None, connection)
+                    .await?;
+            }
             DaemonRequest::EventStreamDropped => {
                 let (reply_sender, reply) = oneshot::channel();
                 self.process_daemon_event(
@@ -425,6 +580,35 @@ impl Listener {
     }
 }
 
+impl Drop for Listener {
+    fn drop(&mut self) {
+        let Some(sim_clock) = self.sim_clock.clone() else {
+            return;
+        };
+        // events that are still in the channels will never be received
+        let mut unreceived = 0;
+        if let Some(events) = &mut self.subscribed_events {
+            events.close();
+            while events.try_recv().is_ok() {
+                unreceived += 1;
+            }
+        }
+        if let Some(events) = &mut self.subscribed_drop_events {
+            events.close();
+            while events.try_recv().is_ok() {
+                unreceived += 1;
+            }
+        }
+        let mut may_advance = sim_clock.events_received(None, unreceived);
+        if self.subscribed_events.is_some() {
+            may_advance |= sim_clock.remove_node(&self.node_id);
+        }
+        if may_advance {
+            self.notify_simulated_clock();
+        }
+    }
+}
+
 #[async_trait::async_trait]
 trait Connection {
     async fn receive_message(&mut self) -> eyre::Result<Option<Timestamped<DaemonRequest>>>;
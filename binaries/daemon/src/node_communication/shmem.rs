@@ -0,0 +1,145 @@
+use std::sync::Arc;
+
+use super::{Connection, Listener};
+use crate::{Event, sim_clock::SimClock};
+use communication_layer_shared_memory::{DisconnectHandle, ShmemChannel, ShmemSender};
+use dora_core::{config::NodeId, uhlc::HLC};
+use dora_message::{
+    DataflowId,
+    common::Timestamped,
+    daemon_to_node::{DaemonCommunication, DaemonReply},
+    node_to_daemon::DaemonRequest,
+};
+use eyre::{Context, eyre};
+use tokio::sync::mpsc;
+
+/// Number of connections that a node opens to the daemon: the event stream,
+/// its close channel, the control channel, and the drop stream.
+const CHANNELS_PER_NODE: usize = 4;
+
+/// Size of the queue for each direction of a channel.
+///
+/// Larger messages are transferred in multiple steps.
+const CHANNEL_CAPACITY: usize = 64 * 1024;
+
+/// Creates the shared memory channels for the given node and spawns a task
+/// that listens on them.
+///
+/// The channels are closed and their regions removed when the task is
+/// aborted.
+pub async fn spawn_listener_loop(
+    dataflow_id: &DataflowId,
+    node_id: &NodeId,
+    daemon_tx: &mpsc::Sender<Timestamped<Event>>,
+    clock: Arc<HLC>,
+    sim_clock: Option<SimClock>,
+) -> eyre::Result<(DaemonCommunication, Option<tokio::task::AbortHandle>)> {
+    let channels = (0..CHANNELS_PER_NODE)
+        .map(|_| ShmemChannel::create(CHANNEL_CAPACITY))
+        .collect::<eyre::Result<Vec<_>>>()?;
+    let region_ids = channels
+        .iter()
+        .map(|channel| channel.os_id().to_owned())
+        .collect();
+
+    let event_loop_node_id = format!("{dataflow_id}/{node_id}");
+    let daemon_tx = daemon_tx.clone();
+    let handle = tokio::spawn(async move {
+        let listeners = channels.into_iter().map(|channel| {
+            Listener::run(
+                ShmemConnection::new(channel),
+                daemon_tx.clone(),
+                clock.clone(),
+                sim_clock.clone(),
+            )
+        });
+        futures::future::join_all(listeners).await;
+        tracing::debug!("event listener loop finished for `{event_loop_node_id}`");
+    });
+
+    Ok((
+        DaemonCommunication::SharedMemory { region_ids },
+        Some(handle.abort_handle()),
+    ))
+}
+
+struct ShmemConnection {
+    requests: mpsc::Receiver<eyre::Result<Option<Vec<u8>>>>,
+    /// Temporarily taken while a large reply is sent from a blocking task.
+    sender: Option<ShmemSender>,
+    disconnect: DisconnectHandle,
+}
+
+impl ShmemConnection {
+    fn new(channel: ShmemChannel) -> Self {
+        let disconnect = channel.disconnect_handle();
+        let (sender, mut receiver) = channel.split();
+        let (tx, requests) = mpsc::channel(1);
+        // receiving blocks, so we use a separate thread for it
+        std::thread::spawn(move || {
+            loop {
+                let message = receiver.receive();
+                let finished = !matches!(message, Ok(Some(_)));
+                if tx.blocking_send(message).is_err() || finished {
+                    break;
+                }
+            }
+        });
+        Self {
+            requests,
+            sender: Some(sender),
+            disconnect,
+        }
+    }
+}
+
+impl Drop for ShmemConnection {
+    fn drop(&mut self) {
+        // stops the receive thread
+        self.disconnect.disconnect();
+    }
+}
+
+#[async_trait::async_trait]
+impl Connection for ShmemConnection {
+    async fn receive_message(&mut self) -> eyre::Result<Option<Timestamped<DaemonRequest>>> {
+        let raw = match self.requests.recv().await {
+            Some(Ok(Some(raw))) => raw,
+            Some(Ok(None)) | None => return Ok(None),
+            Some(Err(err)) => return Err(err.wrap_err("failed to receive DaemonRequest")),
+        };
+        bincode::deserialize(&raw)
+            .wrap_err("failed to deserialize DaemonRequest")
+            .map(Some)
+    }
+
+    async fn send_reply(&mut self, message: DaemonReply) -> eyre::Result<()> {
+        if matches!(message, DaemonReply::Empty) {
+            // don't send empty replies
+            return Ok(());
+        }
+        let serialized =
+            bincode::serialize(&message).wrap_err("failed to serialize DaemonReply")?;
+        let mut sender = self
+            .sender
+            .take()
+            .ok_or_else(|| eyre!("previous reply was not sent completely"))?;
+        let result = match sender.try_send(&serialized) {
+            Ok(true) => Ok(()),
+            Ok(false) => {
+                // the queue is full, so we need to wait until the node reads
+                let (returned, result) = tokio::task::spawn_blocking(move || {
+                    let result = sender.send(&serialized);
+                    (sender, result)
+                })
+                .await
+                .wrap_err("failed to join reply task")?;
+                sender = returned;
+                result
+            }
+            Err(err) => Err(err),
+        };
+        self.sender = Some(sender);
+        result.wrap_err("failed to send DaemonReply")
+    }
+}
@@ -3,6 +3,7 @@ use std::{collections::BTreeMap, io::ErrorKind, sync::Arc};
 use super::{Connection, Listener};
 use crate::{
     Event,
+    sim_clock::SimClock,
     socket_stream_utils::{socket_stream_receive, socket_stream_send},
 };
 use dora_core::{config::DataId, uhlc::HLC};
@@ -15,12 +16,13 @@ use tokio::{
     sync::mpsc,
 };
 
-#[tracing::instrument(skip(listener, daemon_tx, clock), level = "trace")]
+#[tracing::instrument(skip(listener, daemon_tx, clock, sim_clock), level = "trace")]
 pub async fn listener_loop(
     listener: TcpListener,
     daemon_tx: mpsc::Sender<Timestamped<Event>>,
     queue_sizes: BTreeMap<DataId, usize>,
     clock: Arc<HLC>,
+    sim_clock: Option<SimClock>,
 ) {
     loop {
         match listener
@@ -37,24 +39,26 @@ pub async fn listener_loop(
                     daemon_tx.clone(),
                     queue_sizes.clone(),
                     clock.clone(),
+                    sim_clock.clone(),
                 ));
             }
         }
     }
 }
 
-#[tracing::instrument(skip(connection, daemon_tx, clock), level = "trace")]
+#[tracing::instrument(skip(connection, daemon_tx, clock, sim_clock), level = "trace")]
 async fn handle_connection_loop(
     connection: TcpStream,
     daemon_tx: mpsc::Sender<Timestamped<Event>>,
     queue_sizes: BTreeMap<DataId, usize>,
     clock: Arc<HLC>,
+    sim_clock: Option<SimClock>,
 ) {
     if let Err(err) = connection.set_nodelay(true) {
         tracing::warn!("failed to set nodelay for connection: {err}");
     }
 
-    Listener::run(TcpConnection(connection), daemon_tx, clock).await
+    Listener::run(TcpConnection(connection), daemon_tx, clock, sim_clock).await
 }
 
 struct TcpConnection(TcpStream);
@@ -0,0 +1,209 @@
+use std::{
+    collections::BTreeMap,
+    io::ErrorKind,
+    os::unix::fs::{DirBuilderExt, MetadataExt, PermissionsExt},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use super::{Connection, Listener};
+use crate::{
+    Event,
+    sim_clock::SimClock,
+    socket_stream_utils::{socket_stream_receive, socket_stream_send},
+};
+use dora_core::{
+    config::{DataId, NodeId},
+    uhlc::HLC,
+};
+use dora_message::{
+    DataflowId,
+    common::Timestamped,
+    daemon_to_node::{DaemonCommunication, DaemonReply},
+    node_to_daemon::DaemonRequest,
+};
+use eyre::{Context, bail};
+use tokio::{
+    net::{UnixListener, UnixStream},
+    sync::mpsc,
+};
+
+/// Creates a Unix domain socket for the given node and spawns a task that
+/// accepts its connections.
+///
+/// The socket is placed in a directory that only the user of the daemon can
+/// access. It is removed again when the listener task is aborted, together
+/// with the directory if no other sockets are left.
+pub async fn spawn_listener_loop(
+    dataflow_id: &DataflowId,
+    node_id: &NodeId,
+    daemon_tx: &mpsc::Sender<Timestamped<Event>>,
+    queue_sizes: BTreeMap<DataId, usize>,
+    clock: Arc<HLC>,
+    sim_clock: Option<SimClock>,
+) -> eyre::Result<(DaemonCommunication, Option<tokio::task::AbortHandle>)> {
+    // keep the path short, socket paths are limited to about 100 bytes
+    let name = uuid::Uuid::new_v4().simple().to_string();
+    let path = socket_dir()?.join(format!("{}.sock", &name[..16]));
+    let listener = match UnixListener::bind(&path) {
+        // the directory was removed concurrently by the last socket in it
+        Err(err) if err.kind() == ErrorKind::NotFound => {
+            socket_dir()?;
+            UnixListener::bind(&path)
+        }
+        other => other,
+    }
+    .wrap_err_with(|| format!("failed to bind Unix domain socket `{}`", path.display()))?;
+    let socket = SocketFile(path.clone());
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))
+        .wrap_err("failed to set permissions of Unix domain socket")?;
+
+    let event_loop_node_id = format!("{dataflow_id}/{node_id}");
+    let daemon_tx = daemon_tx.clone();
+    let handle = tokio::spawn(async move {
+        // removes the socket file when the task finishes or is aborted
+        let _socket = socket;
+        listener_loop(listener, daemon_tx, queue_sizes, clock, sim_clock).await;
+        tracing::debug!("event listener loop finished for `{event_loop_node_id}`");
+    });
+
+    Ok((
+        DaemonCommunication::UnixDomain { path },
+        Some(handle.abort_handle()),
+    ))
+}
+
+/// Returns the directory for the node sockets of this daemon, creating it if
+/// necessary.
+fn socket_dir() -> eyre::Result<PathBuf> {
+    let dir = std::env::temp_dir().join(format!("dora-daemon-{}", std::process::id()));
+    match std::fs::DirBuilder::new().mode(0o700).create(&dir) {
+        Ok(()) => {}
+        Err(err) if err.kind() == ErrorKind::AlreadyExists => check_socket_dir(&dir)?,
+        Err(err) => {
+            return Err(err).wrap_err_with(|| {
+                format!("failed to create socket directory `{}`", dir.display())
+            });
+        }
+    }
+    Ok(dir)
+}
+
+/// Ensures that an existing socket directory, e.g. from an earlier process
+/// with the same ID, is not accessible to other users.
+fn check_socket_dir(dir: &Path) -> eyre::Result<()> {
+    let metadata = std::fs::symlink_metadata(dir)
+        .wrap_err_with(|| format!("failed to read metadata of `{}`", dir.display()))?;
+    // SAFETY: plain system call without arguments
+    let uid = unsafe { libc::geteuid() };
+    if !metadata.is_dir() || metadata.uid() != uid {
+        bail!(
+            "socket directory `{}` is not a directory owned by the daemon user",
+            dir.display()
+        );
+    }
+    if metadata.mode() & 0o077 != 0 {
+        std::fs::set_permissions(dir, std::fs::Permissions::from_mode(0o700))
+            .wrap_err("failed to restrict permissions of socket directory")?;
+    }
+    Ok(())
+}
+
+struct SocketFile(PathBuf);
+
+impl Drop for SocketFile {
+    fn drop(&mut self) {
+        if let Err(err) = std::fs::remove_file(&self.0) {
+            tracing::debug!("failed to remove socket `{}`: {err}", self.0.display());
+        }
+        // fails if the directory still contains the sockets of other nodes
+        if let Some(dir) = self.0.parent() {
+            let _ = std::fs::remove_dir(dir);
+        }
+    }
+}
+
+#[tracing::instrument(skip(listener, daemon_tx, clock, sim_clock), level = "trace")]
+async fn listener_loop(
+    listener: UnixListener,
+    daemon_tx: mpsc::Sender<Timestamped<Event>>,
+    queue_sizes: BTreeMap<DataId, usize>,
+    clock: Arc<HLC>,
+    sim_clock: Option<SimClock>,
+) {
+    loop {
+        match listener
+            .accept()
+            .await
+            .wrap_err("failed to accept new connection")
+        {
+            Err(err) => {
+                tracing::info!("{err}");
+            }
+            Ok((connection, _)) => {
+                tokio::spawn(Listener::run(
+                    UnixConnection(connection),
+                    daemon_tx.clone(),
+                    clock.clone(),
+                    sim_clock.clone(),
+                ));
+            }
+        }
+    }
+}
+
+struct UnixConnection(UnixStream);
+
+#[async_trait::async_trait]
+impl Connection for UnixConnection {
+    async fn receive_message(&mut self) -> eyre::Result<Option<Timestamped<DaemonRequest>>> {
+        let raw = match socket_stream_receive(&mut self.0).await {
+            Ok(raw) => raw,
+            Err(err) => match err.kind() {
+                ErrorKind::UnexpectedEof
+                | ErrorKind::ConnectionAborted
+                | ErrorKind::ConnectionReset => return Ok(None),
+                _other => {
+                    return Err(err)
+                        .context("unexpected I/O error while trying to receive DaemonRequest");
+                }
+            },
+        };
+        bincode::deserialize(&raw)
+            .wrap_err("failed to deserialize DaemonRequest")
+            .map(Some)
+    }
+
+    async fn send_reply(&mut self, message: DaemonReply) -> eyre::Result<()> {
+        if matches!(message, DaemonReply::Empty) {
+            // don't send empty replies
+            return Ok(());
+        }
+        let serialized =
+            bincode::serialize(&message).wrap_err("failed to serialize DaemonReply")?;
+        socket_stream_send(&mut self.0, &serialized)
+            .await
+            .wrap_err("failed to send DaemonReply")?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::os::unix::fs::PermissionsExt;
+
+    use super::socket_dir;
+
+    #[test]
+    fn socket_dir_is_private() {
+        let dir = socket_dir().unwrap();
+        let mode = std::fs::metadata(&dir).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o700);
+        // reusing the directory keeps it private
+        std::fs::set_permissions(&dir, std::fs::Permissions::from_mode(0o755)).unwrap();
+        let dir = socket_dir().unwrap();
+        let mode = std::fs::metadata(&dir).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o700);
+        std::fs::remove_dir(&dir).unwrap();
+    }
+}
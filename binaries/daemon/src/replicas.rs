@@ -0,0 +1,301 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, hash_map::DefaultHasher},
+    hash::{Hash, Hasher},
+};
+
+use dora_core::config::{DataId, NodeId};
+use dora_message::{
+    descriptor::{Distribution, Replicas},
+    metadata::{Metadata, Parameter},
+};
+
+use crate::InputId;
+
+/// Keeps track of the replicated nodes of a dataflow and distributes their inputs.
+#[derive(Default)]
+pub struct ReplicaSets {
+    sets: BTreeMap<NodeId, ReplicaSet>,
+    /// Maps replica instance IDs to the ID of the replicated node and the replica index.
+    instances: BTreeMap<NodeId, (NodeId, usize)>,
+}
+
+struct ReplicaSet {
+    distribution: Distribution,
+    /// Index of the replica that should be tried first for the next input.
+    next: usize,
+    /// Number of inputs per replica that were not followed by an output yet.
+    unanswered: Vec<usize>,
+    /// Replicas that closed the given output already.
+    closed_outputs: BTreeMap<DataId, BTreeSet<usize>>,
+}
+
+impl ReplicaSets {
+    /// Registers a replicated node and returns the IDs of its instances.
+    pub fn insert(&mut self, node_id: NodeId, replicas: &Replicas) -> Vec<NodeId> {
+        let instance_ids = replicas.instance_ids(&node_id);
+        for (index, instance_id) in instance_ids.iter().enumerate() {
+            self.instances
+                .insert(instance_id.clone(), (node_id.clone(), index));
+        }
+        self.sets.insert(
+            node_id,
+            ReplicaSet {
+                distribution: replicas.distribution.clone(),
+                next: 0,
+                unanswered: vec![0; replicas.count],
+                closed_outputs: BTreeMap::new(),
+            },
+        );
+        instance_ids
+    }
+
+    /// Returns the ID of the replicated node if `node_id` is a replica instance.
+    ///
+    /// Other node IDs are returned unchanged.
+    pub fn node_id<'a>(&'a self, node_id: &'a NodeId) -> &'a NodeId {
+        self.instances
+            .get(node_id)
+            .map(|(node_id, _)| node_id)
+            .unwrap_or(node_id)
+    }
+
+    /// Returns the IDs of all other instances of the same replicated node.
+    pub fn siblings<'a>(&'a self, node_id: &'a NodeId) -> impl Iterator<Item = &'a NodeId> {
+        let replicated = self.instances.get(node_id).map(|(id, _)| id);
+        self.instances
+            .iter()
+            .filter(move |(instance, (id, _))| Some(id) == replicated && *instance != node_id)
+            .map(|(instance, _)| instance)
+    }
+
+    /// Selects the receivers of an output message.
+    ///
+    /// For each input of a replicated node, only one of the replicas that are
+    /// `connected` is selected. All other receivers are returned unchanged.
+    pub fn route<'a>(
+        &mut self,
+        receivers: impl IntoIterator<Item = &'a InputId>,
+        metadata: &Metadata,
+        connected: impl Fn(&NodeId) -> bool,
+    ) -> Vec<&'a InputId> {
+        if self.sets.is_empty() {
+            return receivers.into_iter().collect();
+        }
+
+        let mut selected = Vec::new();
+        let mut candidates: BTreeMap<(&NodeId, &DataId), Vec<(usize, &'a InputId)>> =
+            BTreeMap::new();
+        for receiver in receivers {
+            match self.instances.get(&receiver.0) {
+                Some((node_id, index)) => {
+                    if connected(&receiver.0) {
+                        candidates
+                            .entry((node_id, &receiver.1))
+                            .or_default()
+                            .push((*index, receiver));
+                    }
+                }
+                None => selected.push(receiver),
+            }
+        }
+        let candidates: Vec<_> = candidates
+            .into_iter()
+            .map(|((node_id, _), candidates)| (node_id.clone(), candidates))
+            .collect();
+        for (node_id, candidates) in candidates {
+            if let Some(set) = self.sets.get_mut(&node_id) {
+                let indices: Vec<_> = candidates.iter().map(|(index, _)| *index).collect();
+                let index = set.select(&indices, metadata);
+                selected.extend(
+                    candidates
+                        .iter()
+                        .filter(|(i, _)| *i == index)
+                        .map(|(_, receiver)| *receiver),
+                );
+            }
+        }
+        selected
+    }
+
+    /// Records that the given node sent an output.
+    pub fn output_sent(&mut self, node_id: &NodeId) {
+        if let Some((replicated, index)) = self.instances.get(node_id) {
+            if let Some(set) = self.sets.get_mut(replicated) {
+                set.unanswered[*index] = set.unanswered[*index].saturating_sub(1);
+            }
+        }
+    }
+
+    /// Records that the given node closed the given outputs.
+    ///
+    /// Returns the outputs that are now closed by all replicas. For nodes that
+    /// are not replicated, all `outputs` are returned.
+    pub fn close_outputs(&mut self, node_id: &NodeId, outputs: Vec<DataId>) -> Vec<DataId> {
+        let Some((replicated, index)) = self.instances.get(node_id) else {
+            return outputs;
+        };
+        let Some(set) = self.sets.get_mut(replicated) else {
+            return outputs;
+        };
+        let count = set.unanswered.len();
+        outputs
+            .into_iter()
+            .filter(|output| {
+                let closed = set.closed_outputs.entry(output.clone()).or_default();
+                closed.insert(*index);
+                closed.len() == count
+            })
+            .collect()
+    }
+}
+
+impl ReplicaSet {
+    /// Picks one of the given replica indices (which must not be empty).
+    fn select(&mut self, indices: &[usize], metadata: &Metadata) -> usize {
+        let count = self.unanswered.len();
+        // distance from the round-robin position, used for fair tie-breaking
+        let distance = |index: usize| (index + count - self.next % count) % count;
+        let index = match &self.distribution {
+            Distribution::Key(key) => match metadata.parameters.get(key) {
+                Some(value) => {
+                    let hash = hash_parameter(value);
+                    let preferred = (hash % count as u64) as usize;
+                    if indices.contains(&preferred) {
+                        return preferred;
+                    }
+                    // the preferred replica is not available -> pick another one deterministically
+                    return indices[(hash % indices.len() as u64) as usize];
+                }
+                None => indices.iter().copied().min_by_key(|&i| distance(i)),
+            },
+            Distribution::RoundRobin => indices.iter().copied().min_by_key(|&i| distance(i)),
+            Distribution::LeastLoaded => indices
+                .iter()
+                .copied()
+                .min_by_key(|&i| (self.unanswered[i], distance(i))),
+        }
+        .expect("no replica candidates");
+        if let Distribution::LeastLoaded = self.distribution {
+            self.unanswered[index] += 1;
+        }
+        self.next = index + 1;
+        index
+    }
+}
+
+fn hash_parameter(value: &Parameter) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    match value {
+        Parameter::Bool(v) => v.hash(&mut hasher),
+        Parameter::Integer(v) => v.hash(&mut hasher),
+        Parameter::String(v) => v.hash(&mut hasher),
+        Parameter::ListInt(v) => v.hash(&mut hasher),
+        Parameter::Float(v) => v.to_bits().hash(&mut hasher),
+        Parameter::ListFloat(v) => v.iter().for_each(|f| f.to_bits().hash(&mut hasher)),
+        Parameter::ListString(v) => v.hash(&mut hasher),
+        Parameter::Timestamp(v) => v.hash(&mut hasher),
+    }
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use dora_core::{config::DataId, uhlc::HLC};
+    use dora_message::{
+        descriptor::{Distribution, Replicas},
+        metadata::{Metadata, MetadataParameters, Parameter},
+    };
+
+    use super::ReplicaSets;
+    use crate::InputId;
+
+    fn replica_sets(count: usize, distribution: Distribution) -> (ReplicaSets, BTreeSet<InputId>) {
+        let mut sets = ReplicaSets::default();
+        let instances = sets.insert(
+            "detector".to_string().into(),
+            &Replicas {
+                count,
+                distribution,
+            },
+        );
+        let receivers = instances
+            .into_iter()
+            .map(|id| (id, DataId::from("image".to_string())))
+            .chain([("plot".to_string().into(), "image".to_string().into())])
+            .collect();
+        (sets, receivers)
+    }
+
+    fn metadata(parameters: MetadataParameters) -> Metadata {
+        Metadata::from_parameters(
+            HLC::default().new_timestamp(),
+            crate::empty_type_info(),
+            parameters,
+        )
+    }
+
+    fn route(sets: &mut ReplicaSets, receivers: &BTreeSet<InputId>, metadata: &Metadata) -> String {
+        let selected = sets.route(receivers, metadata, |_| true);
+        assert_eq!(selected.len(), 2, "expected one replica plus `plot`");
+        assert!(selected.iter().any(|(node, _)| node.as_ref() == "plot"));
+        selected
+            .into_iter()
+            .map(|(node, _)| node.to_string())
+            .find(|node| node != "plot")
+            .unwrap()
+    }
+
+    #[test]
+    fn round_robin_cycles_through_replicas() {
+        let (mut sets, receivers) = replica_sets(3, Distribution::RoundRobin);
+        let metadata = metadata(Default::default());
+        let selected: Vec<_> = (0..4)
+            .map(|_| route(&mut sets, &receivers, &metadata))
+            .collect();
+        assert_eq!(
+            selected,
+            ["detector.0", "detector.1", "detector.2", "detector.0"]
+        );
+    }
+
+    #[test]
+    fn key_distribution_is_sticky() {
+        let (mut sets, receivers) = replica_sets(4, Distribution::Key("camera_id".into()));
+        let camera = |id: &str| {
+            metadata(
+                [("camera_id".to_string(), Parameter::String(id.into()))]
+                    .into_iter()
+                    .collect(),
+            )
+        };
+        let front = route(&mut sets, &receivers, &camera("front"));
+        let _ = route(&mut sets, &receivers, &camera("back"));
+        assert_eq!(route(&mut sets, &receivers, &camera("front")), front);
+    }
+
+    #[test]
+    fn least_loaded_prefers_replicas_that_answered() {
+        let (mut sets, receivers) = replica_sets(2, Distribution::LeastLoaded);
+        let metadata = metadata(Default::default());
+        assert_eq!(route(&mut sets, &receivers, &metadata), "detector.0");
+        assert_eq!(route(&mut sets, &receivers, &metadata), "detector.1");
+        sets.output_sent(&"detector.1".to_string().into());
+        assert_eq!(route(&mut sets, &receivers, &metadata), "detector.1");
+    }
+
+    #[test]
+    fn outputs_close_after_all_replicas() {
+        let (mut sets, _) = replica_sets(2, Distribution::RoundRobin);
+        let outputs = vec![DataId::from("boxes".to_string())];
+        assert!(
+            sets.close_outputs(&"detector.0".to_string().into(), outputs.clone())
+                .is_empty()
+        );
+        assert_eq!(
+            sets.close_outputs(&"detector.1".to_string().into(), outputs.clone()),
+            outputs
+        );
+    }
+}
@@ -0,0 +1,410 @@
+use std::{
+    collections::{HashMap, hash_map::DefaultHasher},
+    hash::{Hash, Hasher},
+    time::Duration,
+};
+
+use dora_core::config::{DataId, NodeId};
+use dora_message::{
+    common::DaemonId,
+    metadata::{MetadataParameters, Parameter},
+    service::{
+        ACTION_MESSAGE_PARAMETER, ActionMessage, REPLY_PREFIX, REQUEST_ID_PARAMETER,
+        REQUEST_PREFIX, REQUEST_TIMEOUT_PARAMETER, ServiceCall, parse_hidden_input,
+    },
+};
+
+/// Correlates the service requests and action goals of local client nodes with
+/// their replies.
+///
+/// Request IDs are chosen by the nodes, so they are only unique per node
+/// instance. The daemon replaces them with IDs that are unique for the
+/// dataflow and restores the original ID when the reply arrives. Replies are
+/// sent to the clients on all daemons, so the upper bits of the IDs are derived
+/// from the daemon ID to keep the requests of different daemons apart.
+///
+/// For local server nodes, it remembers which instance executes each goal so
+/// that cancel requests reach the same instance.
+pub struct PendingRequests {
+    /// Upper 31 bits of all request IDs of this daemon.
+    id_prefix: i64,
+    next_id: u32,
+    pending: HashMap<i64, PendingRequest>,
+    /// Goals executed by local server instances, by client, action, and goal ID.
+    active_goals: HashMap<(NodeId, DataId, i64), NodeId>,
+}
+
+struct PendingRequest {
+    /// The node instance that sent the request.
+    client: NodeId,
+    /// The request ID chosen by the client.
+    client_request_id: i64,
+    reply_input: DataId,
+    /// The state of the goal, if the request is an action goal.
+    goal: Option<GoalState>,
+}
+
+/// State of an action goal that was not finished yet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum GoalState {
+    /// The goal was sent to the server.
+    Accepted,
+    /// The server sent feedback for the goal.
+    Executing,
+    /// The client requested to cancel the goal.
+    Canceling,
+}
+
+/// A request or goal that will not receive a reply anymore.
+pub struct UnansweredRequest {
+    pub client: NodeId,
+    pub reply_input: DataId,
+    pub client_request_id: i64,
+    pub is_goal: bool,
+}
+
+fn action_message(parameters: &MetadataParameters) -> eyre::Result<Option<ActionMessage>> {
+    match parameters.get(ACTION_MESSAGE_PARAMETER) {
+        Some(Parameter::String(message)) => message
+            .parse()
+            .map(Some)
+            .map_err(|err: String| eyre::eyre!(err)),
+        Some(other) => eyre::bail!("invalid `{ACTION_MESSAGE_PARAMETER}` parameter {other:?}"),
+        None => Ok(None),
+    }
+}
+
+impl PendingRequests {
+    pub fn new(daemon_id: &DaemonId) -> Self {
+        let mut hasher = DefaultHasher::new();
+        daemon_id.to_string().hash(&mut hasher);
+        Self {
+            id_prefix: ((hasher.finish() >> 33) as i64) << 32,
+            next_id: 0,
+            pending: HashMap::new(),
+            active_goals: HashMap::new(),
+        }
+    }
+
+    /// Registers a request or action message sent by `client` on the given
+    /// request output.
+    ///
+    /// Replaces the request ID in the parameters and returns the new ID and
+    /// the request timeout. Cancel requests refer to an already registered goal
+    /// and don't time out.
+    pub fn register(
+        &mut self,
+        client: &NodeId,
+        output_id: &DataId,
+        parameters: &mut MetadataParameters,
+    ) -> eyre::Result<(i64, Option<Duration>)> {
+        let (server, service) = parse_hidden_input(REQUEST_PREFIX, output_id)
+            .ok_or_else(|| eyre::eyre!("invalid request output `{output_id}`"))?;
+        let Some(Parameter::Integer(client_request_id)) = parameters.get(REQUEST_ID_PARAMETER)
+        else {
+            eyre::bail!("request on `{output_id}` has no `{REQUEST_ID_PARAMETER}` parameter");
+        };
+        let client_request_id = *client_request_id;
+        let reply_input = ServiceCall { server, service }.reply_input();
+        let goal = match action_message(parameters)? {
+            None => None,
+            Some(ActionMessage::Goal) => Some(GoalState::Accepted),
+            Some(ActionMessage::Cancel) => {
+                let (&id, goal) = self
+                    .pending
+                    .iter_mut()
+                    .find(|(_, r)| {
+                        r.goal.is_some()
+                            && &r.client == client
+                            && r.client_request_id == client_request_id
+                            && r.reply_input == reply_input
+                    })
+                    .ok_or_else(|| eyre::eyre!("goal {client_request_id} is not active"))?;
+                goal.goal = Some(GoalState::Canceling);
+                parameters.insert(REQUEST_ID_PARAMETER.into(), Parameter::Integer(id));
+                return Ok((id, None));
+            }
+            Some(other) => eyre::bail!("clients cannot send `{}` messages", other.as_str()),
+        };
+        let timeout = match parameters.get(REQUEST_TIMEOUT_PARAMETER) {
+            Some(Parameter::Integer(ms)) if goal.is_none() => {
+                Some(Duration::from_millis((*ms).max(0) as u64))
+            }
+            _ => None,
+        };
+
+        let id = self.id_prefix | i64::from(self.next_id);
+        self.next_id = self.next_id.wrapping_add(1);
+        self.pending.insert(
+            id,
+            PendingRequest {
+                client: client.clone(),
+                client_request_id,
+                reply_input,
+                goal,
+            },
+        );
+        parameters.insert(REQUEST_ID_PARAMETER.into(), Parameter::Integer(id));
+        Ok((id, timeout))
+    }
+
+    /// Chooses the server instance for a request or action message that
+    /// arrived on the given request input.
+    ///
+    /// Goals stay with the instance that `receiver` selects, cancel requests
+    /// go to the instance that executes the goal. Returns `None` for cancel
+    /// requests of goals that are already finished.
+    pub fn route_request(
+        &mut self,
+        receiver: &NodeId,
+        request_input: &DataId,
+        parameters: &MetadataParameters,
+    ) -> Option<NodeId> {
+        let message = action_message(parameters).ok().flatten();
+        let (Some(message), Some(Parameter::Integer(goal_id))) =
+            (message, parameters.get(REQUEST_ID_PARAMETER))
+        else {
+            return Some(receiver.clone());
+        };
+        let (client, action) = parse_hidden_input(REQUEST_PREFIX, request_input)?;
+        let key = (client, action, *goal_id);
+        match message {
+            ActionMessage::Goal => {
+                self.active_goals.insert(key, receiver.clone());
+                Some(receiver.clone())
+            }
+            ActionMessage::Cancel => self.active_goals.get(&key).cloned(),
+            ActionMessage::Feedback | ActionMessage::Result => None,
+        }
+    }
+
+    /// Forgets the server instance of a goal once the server sent its result
+    /// on the given reply output.
+    pub fn goal_finished(&mut self, reply_output: &DataId, parameters: &MetadataParameters) {
+        if let (Ok(Some(ActionMessage::Result)), Some(Parameter::Integer(goal_id))) = (
+            action_message(parameters),
+            parameters.get(REQUEST_ID_PARAMETER),
+        ) {
+            if let Some((client, action)) = parse_hidden_input(REPLY_PREFIX, reply_output) {
+                self.active_goals.remove(&(client, action, *goal_id));
+            }
+        }
+    }
+
+    /// Matches a reply on the given input to its request.
+    ///
+    /// Returns the node instance that should receive the reply and restores
+    /// the request ID of the client. Returns `None` for replies to unknown or
+    /// timed out requests.
+    ///
+    /// Goals stay registered until their result arrives, so that all feedback
+    /// is delivered too.
+    pub fn complete(
+        &mut self,
+        reply_input: &DataId,
+        parameters: &mut MetadataParameters,
+    ) -> Option<NodeId> {
+        let Some(Parameter::Integer(id)) = parameters.get(REQUEST_ID_PARAMETER) else {
+            return None;
+        };
+        let id = *id;
+        let request = self.pending.get_mut(&id)?;
+        if request.reply_input != *reply_input {
+            return None;
+        }
+        if let Some(state) = &mut request.goal {
+            match action_message(parameters) {
+                Ok(Some(ActionMessage::Feedback)) => {
+                    if *state == GoalState::Accepted {
+                        *state = GoalState::Executing;
+                    }
+                    let client = request.client.clone();
+                    parameters.insert(
+                        REQUEST_ID_PARAMETER.into(),
+                        Parameter::Integer(request.client_request_id),
+                    );
+                    return Some(client);
+                }
+                Ok(Some(ActionMessage::Result)) => {}
+                _ => return None,
+            }
+        }
+        let request = self.pending.remove(&id)?;
+        parameters.insert(
+            REQUEST_ID_PARAMETER.into(),
+            Parameter::Integer(request.client_request_id),
+        );
+        Some(request.client)
+    }
+
+    /// Removes the given request if it is still waiting for a reply.
+    pub fn time_out(&mut self, id: i64) -> Option<UnansweredRequest> {
+        self.pending.remove(&id).map(UnansweredRequest::from)
+    }
+
+    /// Removes all requests and goals that wait for replies on the given input.
+    ///
+    /// Called when the server closed the reply input, e.g. because it exited.
+    pub fn abandon(&mut self, reply_input: &DataId) -> Vec<UnansweredRequest> {
+        let ids: Vec<_> = self
+            .pending
+            .iter()
+            .filter(|(_, r)| r.reply_input == *reply_input)
+            .map(|(id, _)| *id)
+            .collect();
+        ids.into_iter()
+            .filter_map(|id| self.pending.remove(&id))
+            .map(UnansweredRequest::from)
+            .collect()
+    }
+}
+
+impl From<PendingRequest> for UnansweredRequest {
+    fn from(request: PendingRequest) -> Self {
+        Self {
+            client: request.client,
+            reply_input: request.reply_input,
+            client_request_id: request.client_request_id,
+            is_goal: request.goal.is_some(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use dora_core::config::{DataId, NodeId};
+    use dora_message::{
+        common::DaemonId,
+        metadata::{MetadataParameters, Parameter},
+        service::{ACTION_MESSAGE_PARAMETER, REQUEST_ID_PARAMETER, ServiceCall},
+    };
+
+    use super::PendingRequests;
+
+    fn request_id(parameters: &MetadataParameters) -> i64 {
+        match parameters.get(REQUEST_ID_PARAMETER) {
+            Some(Parameter::Integer(id)) => *id,
+            other => panic!("unexpected request ID {other:?}"),
+        }
+    }
+
+    #[test]
+    fn replies_are_routed_to_the_requesting_instance() {
+        let call: ServiceCall = "planner/plan_path".parse().unwrap();
+        let mut pending = PendingRequests::new(&DaemonId::new(None));
+
+        // two replicas of the client use the same request ID
+        let mut ids = Vec::new();
+        for instance in ["robot.0", "robot.1"] {
+            let mut parameters = MetadataParameters::default();
+            parameters.insert(REQUEST_ID_PARAMETER.into(), Parameter::Integer(0));
+            let instance = NodeId::from(instance.to_string());
+            pending
+                .register(&instance, &call.request_output(), &mut parameters)
+                .unwrap();
+            ids.push(request_id(&parameters));
+        }
+        assert_ne!(ids[0], ids[1]);
+
+        let mut reply = MetadataParameters::default();
+        reply.insert(REQUEST_ID_PARAMETER.into(), Parameter::Integer(ids[1]));
+        assert!(
+            pending
+                .complete(&DataId::from("__reply/other/plan_path"), &mut reply.clone())
+                .is_none()
+        );
+        let client = pending.complete(&call.reply_input(), &mut reply);
+        assert_eq!(client, Some(NodeId::from("robot.1".to_string())));
+        assert_eq!(request_id(&reply), 0);
+
+        // late replies are dropped
+        assert!(pending.time_out(ids[0]).is_some());
+        reply.insert(REQUEST_ID_PARAMETER.into(), Parameter::Integer(ids[0]));
+        assert!(pending.complete(&call.reply_input(), &mut reply).is_none());
+
+        // replies to clients on other daemons are ignored
+        let mut other_daemon = PendingRequests::new(&DaemonId::new(None));
+        let mut parameters = MetadataParameters::default();
+        parameters.insert(REQUEST_ID_PARAMETER.into(), Parameter::Integer(0));
+        other_daemon
+            .register(
+                &"robot.0".to_string().into(),
+                &call.request_output(),
+                &mut parameters,
+            )
+            .unwrap();
+        assert!(!ids.contains(&request_id(&parameters)));
+        assert!(
+            pending
+                .complete(&call.reply_input(), &mut parameters)
+                .is_none()
+        );
+    }
+
+    fn action_message(id: i64, message: &str) -> MetadataParameters {
+        let mut parameters = MetadataParameters::default();
+        parameters.insert(REQUEST_ID_PARAMETER.into(), Parameter::Integer(id));
+        parameters.insert(
+            ACTION_MESSAGE_PARAMETER.into(),
+            Parameter::String(message.into()),
+        );
+        parameters
+    }
+
+    #[test]
+    fn goals_receive_feedback_until_their_result() {
+        let action: ServiceCall = "navigator/navigate_to".parse().unwrap();
+        let client = NodeId::from("robot".to_string());
+        let server = NodeId::from("navigator.1".to_string());
+        let mut pending = PendingRequests::new(&DaemonId::new(None));
+
+        let mut goal = action_message(7, "goal");
+        let (_, timeout) = pending
+            .register(&client, &action.request_output(), &mut goal)
+            .unwrap();
+        assert!(timeout.is_none());
+        let goal_id = request_id(&goal);
+
+        // cancel requests reach the server instance that executes the goal
+        let request_input = action.request_input(&client);
+        let server_instance = pending.route_request(&server, &request_input, &goal);
+        assert_eq!(server_instance.as_ref(), Some(&server));
+        let mut cancel = action_message(7, "cancel");
+        pending
+            .register(&client, &action.request_output(), &mut cancel)
+            .unwrap();
+        assert_eq!(request_id(&cancel), goal_id);
+        let other = NodeId::from("navigator.0".to_string());
+        assert_eq!(
+            pending.route_request(&other, &request_input, &cancel),
+            Some(server)
+        );
+
+        for message in ["feedback", "feedback", "result"] {
+            let mut reply = action_message(goal_id, message);
+            let receiver = pending.complete(&action.reply_input(), &mut reply);
+            assert_eq!(receiver.as_ref(), Some(&client));
+            assert_eq!(request_id(&reply), 7);
+        }
+        let result = action_message(goal_id, "result");
+        pending.goal_finished(&action.reply_output(&client), &result);
+        assert_eq!(pending.route_request(&other, &request_input, &cancel), None);
+
+        // the goal is finished, so further messages are dropped
+        let mut feedback = action_message(goal_id, "feedback");
+        assert!(
+            pending
+                .complete(&action.reply_input(), &mut feedback)
+                .is_none()
+        );
+        assert!(
+            pending
+                .register(
+                    &client,
+                    &action.request_output(),
+                    &mut action_message(7, "cancel")
+                )
+                .is_err()
+        );
+    }
+}
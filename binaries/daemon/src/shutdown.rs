@@ -0,0 +1,319 @@
+//! Ordered shutdown of the local nodes of a stopped dataflow.
+//!
+//! Instead of stopping all nodes at once, the daemon first sends the stop event
+//! to the nodes that don't receive messages from other nodes, i.e. the sources.
+//! Every other node is stopped once all of its upstream nodes exited, so that
+//! sinks still receive the last messages and can flush them. Nodes that are
+//! part of a cycle are stopped together.
+//!
+//! Every stopped node gets its `stop_timeout` to exit before it is terminated.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    sync::Arc,
+    time::Duration,
+};
+
+use crossbeam_skiplist::SkipSet;
+use dora_core::uhlc::HLC;
+use dora_message::{DataflowId, common::Timestamped, id::NodeId};
+use futures::{FutureExt, future::RemoteHandle};
+use tokio::sync::mpsc;
+use tracing::warn;
+
+use crate::{Event, ProcessOperation};
+
+/// Stop timeout of nodes that don't set a `stop_timeout` if `dora stop` gives no
+/// grace duration.
+pub const DEFAULT_STOP_TIMEOUT: Duration = Duration::from_secs(10);
+
+pub struct Shutdown {
+    /// Local nodes that didn't receive a stop event yet.
+    waiting: BTreeSet<NodeId>,
+    /// Local nodes that received a stop event, but didn't exit yet.
+    stopping: BTreeMap<NodeId, StoppingNode>,
+    /// Stop timeout of nodes that don't set a `stop_timeout`.
+    default_timeout: Duration,
+    /// Number of groups of nodes that were stopped so far.
+    stage: usize,
+    /// Stops the nodes that wait for remote nodes after the default timeout.
+    deadline: Option<RemoteHandle<()>>,
+    deadline_passed: bool,
+}
+
+struct StoppingNode {
+    process: flume::Sender<ProcessOperation>,
+    grace_duration_kills: Arc<SkipSet<NodeId>>,
+    _kill_timer: RemoteHandle<()>,
+}
+
+impl Shutdown {
+    pub fn new(nodes: impl IntoIterator<Item = NodeId>, default_timeout: Option<Duration>) -> Self {
+        Self {
+            waiting: nodes.into_iter().collect(),
+            stopping: BTreeMap::new(),
+            default_timeout: default_timeout.unwrap_or(DEFAULT_STOP_TIMEOUT),
+            stage: 0,
+            deadline: None,
+            deadline_passed: false,
+        }
+    }
+
+    pub fn default_timeout(&self) -> Duration {
+        self.default_timeout
+    }
+
+    /// Whether the given node was already asked to stop.
+    pub fn stop_sent(&self, node_id: &NodeId) -> bool {
+        !self.waiting.contains(node_id)
+    }
+
+    /// Number of local nodes that didn't exit yet.
+    pub fn remaining(&self) -> usize {
+        self.waiting.len() + self.stopping.len()
+    }
+
+    /// Selects the nodes that should be stopped next and returns them together
+    /// with the stage number.
+    ///
+    /// The `upstream_of` function returns the nodes that still send messages to
+    /// the given node. The selected nodes are considered as stopped afterwards.
+    pub fn next_stage(
+        &mut self,
+        upstream_of: impl Fn(&NodeId) -> BTreeSet<NodeId>,
+    ) -> Option<(usize, Vec<NodeId>)> {
+        let upstream: BTreeMap<_, _> = self
+            .waiting
+            .iter()
+            .map(|node_id| (node_id, upstream_of(node_id)))
+            .collect();
+        let mut next: Vec<_> = if self.deadline_passed {
+            self.waiting.iter().cloned().collect()
+        } else {
+            upstream
+                .iter()
+                .filter(|(_, upstream)| upstream.is_empty())
+                .map(|(node_id, _)| (*node_id).clone())
+                .collect()
+        };
+        if next.is_empty() && self.stopping.is_empty() {
+            // the remaining nodes wait for remote nodes or for each other -> stop
+            // the cycles that don't wait for any other node
+            next = upstream
+                .keys()
+                .filter(|node_id| {
+                    let reachable = reachable_upstream(node_id, &upstream);
+                    reachable.contains(**node_id)
+                        && reachable.iter().all(|other| {
+                            upstream.contains_key(other)
+                                && reachable_upstream(other, &upstream).contains(**node_id)
+                        })
+                })
+                .map(|node_id| (*node_id).clone())
+                .collect();
+        }
+        if next.is_empty() {
+            return None;
+        }
+        for node_id in &next {
+            self.waiting.remove(node_id);
+        }
+        self.stage += 1;
+        Some((self.stage, next))
+    }
+
+    /// Whether the remaining nodes only wait for nodes of other daemons.
+    ///
+    /// In this case, [`Self::schedule_deadline`] should be called to ensure that
+    /// these nodes are stopped eventually.
+    pub fn waits_for_remote_nodes(&self) -> bool {
+        !self.waiting.is_empty()
+            && self.stopping.is_empty()
+            && self.deadline.is_none()
+            && !self.deadline_passed
+    }
+
+    /// Sends an [`Event::ShutdownDeadline`] after the default stop timeout.
+    pub fn schedule_deadline(
+        &mut self,
+        dataflow_id: DataflowId,
+        events_tx: mpsc::Sender<Timestamped<Event>>,
+        clock: Arc<HLC>,
+    ) {
+        let timeout = self.default_timeout;
+        let (task, handle) = async move {
+            tokio::time::sleep(timeout).await;
+            let event = Timestamped {
+                inner: Event::ShutdownDeadline { dataflow_id },
+                timestamp: clock.new_timestamp(),
+            };
+            let _ = events_tx.send(event).await;
+        }
+        .remote_handle();
+        tokio::spawn(task);
+        self.deadline = Some(handle);
+    }
+
+    /// Stops all remaining nodes on the next [`Self::next_stage`] call.
+    pub fn deadline_passed(&mut self) {
+        self.deadline_passed = true;
+    }
+
+    /// Terminates the given node if it doesn't exit within the timeout.
+    pub fn start_kill_timer(
+        &mut self,
+        node_id: NodeId,
+        timeout: Duration,
+        process: flume::Sender<ProcessOperation>,
+        grace_duration_kills: Arc<SkipSet<NodeId>>,
+    ) {
+        let kill_timer = kill_timer(
+            node_id.clone(),
+            timeout,
+            process.clone(),
+            grace_duration_kills.clone(),
+        );
+        self.stopping.insert(
+            node_id,
+            StoppingNode {
+                process,
+                grace_duration_kills,
+                _kill_timer: kill_timer,
+            },
+        );
+    }
+
+    /// Restarts the kill timer of a stopping node with the given duration.
+    pub fn extend(&mut self, node_id: &NodeId, duration: Duration) -> eyre::Result<()> {
+        let Some(node) = self.stopping.get_mut(node_id) else {
+            eyre::bail!("node was not asked to stop");
+        };
+        node._kill_timer = kill_timer(
+            node_id.clone(),
+            duration,
+            node.process.clone(),
+            node.grace_duration_kills.clone(),
+        );
+        Ok(())
+    }
+
+    pub fn node_exited(&mut self, node_id: &NodeId) {
+        self.waiting.remove(node_id);
+        self.stopping.remove(node_id);
+    }
+}
+
+/// Collects the nodes that send messages to the given node, directly or indirectly.
+fn reachable_upstream<'a>(
+    node_id: &'a NodeId,
+    upstream: &'a BTreeMap<&NodeId, BTreeSet<NodeId>>,
+) -> BTreeSet<&'a NodeId> {
+    let mut reachable = BTreeSet::new();
+    let mut stack = vec![node_id];
+    while let Some(next) = stack.pop() {
+        for source in upstream.get(next).into_iter().flatten() {
+            if reachable.insert(source) {
+                stack.push(source);
+            }
+        }
+    }
+    reachable
+}
+
+/// Sends a soft kill after the timeout and a hard kill after another half of it.
+fn kill_timer(
+    node_id: NodeId,
+    timeout: Duration,
+    process: flume::Sender<ProcessOperation>,
+    grace_duration_kills: Arc<SkipSet<NodeId>>,
+) -> RemoteHandle<()> {
+    let (task, handle) = async move {
+        tokio::time::sleep(timeout).await;
+        if process.send(ProcessOperation::SoftKill).is_ok() {
+            grace_duration_kills.insert(node_id.clone());
+        }
+        let kill_duration = timeout / 2;
+        tokio::time::sleep(kill_duration).await;
+        if process.send(ProcessOperation::Kill).is_ok() {
+            warn!(
+                "{node_id} was killed due to not stopping within the {:#?} stop timeout",
+                timeout + kill_duration
+            );
+        }
+    }
+    .remote_handle();
+    tokio::spawn(task);
+    handle
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeMap, BTreeSet};
+
+    use dora_message::id::NodeId;
+
+    use super::Shutdown;
+
+    fn id(id: &str) -> NodeId {
+        id.to_owned().into()
+    }
+
+    #[tokio::test]
+    async fn nodes_are_stopped_in_data_flow_order() {
+        // camera -> detector -> recorder, detector <-> tracker, remote -> uploader
+        let upstream: BTreeMap<NodeId, BTreeSet<NodeId>> = [
+            ("camera", vec![]),
+            ("detector", vec!["camera", "tracker"]),
+            ("tracker", vec!["detector"]),
+            ("recorder", vec!["detector"]),
+            ("uploader", vec!["remote"]),
+        ]
+        .into_iter()
+        .map(|(node, upstream)| (id(node), upstream.into_iter().map(id).collect()))
+        .collect();
+        let mut exited = BTreeSet::new();
+        let mut shutdown = Shutdown::new(upstream.keys().cloned(), None);
+        let next_stage = |shutdown: &mut Shutdown, exited: &BTreeSet<NodeId>| {
+            shutdown.next_stage(|node_id| {
+                upstream[node_id]
+                    .iter()
+                    .filter(|n| !exited.contains(*n))
+                    .cloned()
+                    .collect()
+            })
+        };
+
+        let (stage, nodes) = next_stage(&mut shutdown, &exited).unwrap();
+        assert_eq!((stage, nodes), (1, vec![id("camera")]));
+        assert!(shutdown.stop_sent(&id("camera")));
+        assert!(!shutdown.stop_sent(&id("recorder")));
+
+        // `camera` is still running, so nothing else can be stopped
+        let (tx, _) = flume::unbounded();
+        shutdown.start_kill_timer(
+            id("camera"),
+            shutdown.default_timeout(),
+            tx,
+            Default::default(),
+        );
+        assert!(next_stage(&mut shutdown, &exited).is_none());
+
+        // the cycle of `detector` and `tracker` is stopped together
+        shutdown.node_exited(&id("camera"));
+        exited.insert(id("camera"));
+        let (stage, nodes) = next_stage(&mut shutdown, &exited).unwrap();
+        assert_eq!((stage, nodes), (2, vec![id("detector"), id("tracker")]));
+
+        exited.extend([id("detector"), id("tracker")]);
+        let (stage, nodes) = next_stage(&mut shutdown, &exited).unwrap();
+        assert_eq!((stage, nodes), (3, vec![id("recorder")]));
+
+        // `uploader` waits for its remote upstream node until the deadline passed
+        assert!(next_stage(&mut shutdown, &exited).is_none());
+        assert!(shutdown.waits_for_remote_nodes());
+        shutdown.deadline_passed();
+        let (stage, nodes) = next_stage(&mut shutdown, &exited).unwrap();
+        assert_eq!((stage, nodes), (4, vec![id("uploader")]));
+        assert_eq!(shutdown.remaining(), 0);
+    }
+}
@@ -0,0 +1,355 @@
+//! Simulated clock for `dora run --clock sim`.
+//!
+//! In simulation mode, the daemon HLC does not follow the system clock, but is
+//! moved forward to the simulated time of the dataflow. The simulated time
+//! only moves forward when all nodes that have inputs are idle, i.e. when
+//! they handled all their events and wait for new ones. It then jumps directly to the next timer tick or
+//! `recv_timeout` deadline. This makes runs deterministic and allows them to
+//! run faster (or slower) than real time.
+//!
+//! Alternatively, a node output can be configured as clock source. In this
+//! case, the time is set to the `sim_time_ns` metadata parameter of each
+//! message on this output.
+
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex, MutexGuard},
+    time::{Duration, UNIX_EPOCH},
+};
+
+use dora_core::{
+    config::{DataId, NodeId, Timer},
+    uhlc::NTP64,
+};
+use dora_message::metadata::Parameter;
+
+use crate::timer;
+
+/// Metadata parameter that sets the simulated time on the clock source output.
+pub const SIM_TIME_PARAMETER: &str = "sim_time_ns";
+
+/// The clock that a dataflow started through [`Daemon::run_dataflow`](crate::Daemon::run_dataflow) uses.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum DataflowClock {
+    /// Use the system clock.
+    #[default]
+    Real,
+    /// Use a simulated clock that starts at zero.
+    Simulated {
+        /// Output that drives the clock through its `sim_time_ns` parameter.
+        ///
+        /// If `None`, the clock advances automatically whenever all nodes are idle.
+        source: Option<(NodeId, DataId)>,
+    },
+}
+
+/// Simulated clock of a single dataflow.
+///
+/// Stored in the [`RunningDataflow`](crate::RunningDataflow) and shared with the
+/// listeners of its nodes, which report when the nodes become idle.
+#[derive(Clone)]
+pub struct SimClock {
+    state: Arc<Mutex<State>>,
+}
+
+struct State {
+    time: NTP64,
+    /// Number of node events that were sent to node listeners.
+    sent: u64,
+    /// Number of node events that node listeners took out of their channel.
+    received: u64,
+    /// Nodes with inputs, which need to be idle before the clock can advance.
+    nodes: BTreeMap<NodeId, NodeState>,
+    /// Set once all nodes are ready.
+    started: bool,
+    /// Next tick of all registered timers.
+    timers: BTreeMap<Timer, NTP64>,
+    source: Option<(NodeId, DataId)>,
+}
+
+#[derive(Default)]
+struct NodeState {
+    idle: Option<Idle>,
+    outputs_received: u64,
+}
+
+struct Idle {
+    sent_outputs: u64,
+    wake_at: Option<NTP64>,
+}
+
+/// Timer ticks and wake-ups that are due at a new simulated time.
+pub struct Step {
+    pub time: NTP64,
+    pub timers: Vec<Timer>,
+    /// Nodes whose `recv_timeout` deadline was reached.
+    pub wake: Vec<(NodeId, NTP64)>,
+}
+
+impl State {
+    fn new(source: Option<(NodeId, DataId)>) -> Self {
+        Self {
+            time: NTP64::default(),
+            sent: 0,
+            received: 0,
+            nodes: BTreeMap::new(),
+            started: false,
+            timers: BTreeMap::new(),
+            source,
+        }
+    }
+
+    fn is_quiescent(&self) -> bool {
+        self.started
+            && self.sent == self.received
+            && self.nodes.values().all(|node| {
+                node.idle
+                    .as_ref()
+                    .is_some_and(|idle| node.outputs_received >= idle.sent_outputs)
+            })
+    }
+
+    /// Collects the timers and wake-ups that are due at the given time.
+    fn step(&mut self, time: NTP64) -> Step {
+        self.time = time;
+
+        let mut timers = Vec::new();
+        self.timers.retain(|timer, deadline| {
+            if *deadline > time {
+                return true;
+            }
+            timers.push(*timer);
+            *deadline = *deadline + NTP64::from(timer.interval);
+            !timer.once
+        });
+        let mut wake = Vec::new();
+        for (node_id, node) in &mut self.nodes {
+            if let Some(idle) = &mut node.idle {
+                if idle.wake_at.is_some_and(|wake_at| wake_at <= time) {
+                    wake.push((node_id.clone(), idle.wake_at.take().unwrap()));
+                }
+            }
+        }
+        Step { time, timers, wake }
+    }
+}
+
+impl SimClock {
+    /// Creates a clock that starts at zero.
+    ///
+    /// If `source` is set, the clock is driven by the `sim_time_ns` parameter
+    /// of the messages on this output.
+    pub fn new(source: Option<(NodeId, DataId)>) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(State::new(source))),
+        }
+    }
+
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    /// Returns the output that drives the clock, if any.
+    pub fn source(&self) -> Option<(NodeId, DataId)> {
+        self.state().source.clone()
+    }
+
+    /// Records that an event was sent to a node listener.
+    pub fn event_sent(&self) {
+        self.state().sent += 1;
+    }
+
+    /// Returns the number of events that were sent to node listeners so far.
+    pub fn events_sent(&self) -> u64 {
+        self.state().sent
+    }
+
+    /// Records that a node listener received `count` events from its channel.
+    ///
+    /// For events of the node's event stream, `node` marks the node as busy.
+    /// Returns `true` if the clock might be able to advance now.
+    pub fn events_received(&self, node: Option<&NodeId>, count: u64) -> bool {
+        if count == 0 {
+            return false;
+        }
+        let mut state = self.state();
+        state.received += count;
+        if let Some(node) = node.and_then(|node_id| state.nodes.get_mut(node_id)) {
+            node.idle = None;
+        }
+        state.is_quiescent()
+    }
+
+    /// Records that a node waits for new events.
+    ///
+    /// Returns `true` if the clock might be able to advance now.
+    pub fn node_idle(&self, node_id: &NodeId, sent_outputs: u64, wake_at: Option<NTP64>) -> bool {
+        let mut state = self.state();
+        if let Some(node) = state.nodes.get_mut(node_id) {
+            node.idle = Some(Idle {
+                sent_outputs,
+                wake_at,
+            });
+        }
+        state.is_quiescent()
+    }
+
+    /// Registers a node that needs to be idle before the clock can advance.
+    pub fn add_node(&self, node_id: NodeId) {
+        self.state().nodes.entry(node_id).or_default();
+    }
+
+    /// Removes a node, e.g. because its event stream was closed.
+    ///
+    /// Returns `true` if the clock might be able to advance now.
+    pub fn remove_node(&self, node_id: &NodeId) -> bool {
+        let mut state = self.state();
+        state.nodes.remove(node_id);
+        state.is_quiescent()
+    }
+
+    /// Records that the daemon received an output message from the given node.
+    pub fn output_received(&self, node_id: &NodeId) {
+        if let Some(node) = self.state().nodes.get_mut(node_id) {
+            node.outputs_received += 1;
+        }
+    }
+
+    /// Schedules the ticks of the given timer, starting from the current simulated time.
+    pub fn add_timer(&self, timer: Timer) {
+        let mut state = self.state();
+        let now = state.time;
+        let first_tick = timer::first_tick_delay(&timer, UNIX_EPOCH + now.to_duration());
+        state
+            .timers
+            .entry(timer)
+            .or_insert(now + NTP64::from(first_tick));
+    }
+
+    /// Allows the clock to advance, called once all nodes are ready.
+    pub fn start(&self) {
+        self.state().started = true;
+    }
+
+    /// Advances the clock to the next timer tick or wake-up if all nodes are idle.
+    ///
+    /// Always returns `None` if the clock is driven by a source output.
+    pub fn advance(&self) -> Option<Step> {
+        let mut state = self.state();
+        if state.source.is_some() || state.nodes.is_empty() || !state.is_quiescent() {
+            // no node is waiting for the clock or some nodes are still busy
+            return None;
+        }
+        let next_timer = state.timers.values().min().copied();
+        let next_wake = state
+            .nodes
+            .values()
+            .filter_map(|node| node.idle.as_ref()?.wake_at)
+            .min();
+        let time = match (next_timer, next_wake) {
+            (Some(a), Some(b)) => a.min(b),
+            (Some(time), None) | (None, Some(time)) => time,
+            (None, None) => return None,
+        };
+        let time = time.max(state.time);
+        Some(state.step(time))
+    }
+
+    /// Sets the clock to the `sim_time_ns` parameter of a message on the source output.
+    ///
+    /// The clock never goes backwards, so earlier times are ignored.
+    pub fn set_time(&self, parameters: &BTreeMap<String, Parameter>) -> Option<Step> {
+        let time = match parameters.get(SIM_TIME_PARAMETER) {
+            Some(Parameter::Integer(nanos)) if *nanos >= 0 => {
+                NTP64::from(Duration::from_nanos(*nanos as u64))
+            }
+            Some(other) => {
+                tracing::warn!("ignoring invalid `{SIM_TIME_PARAMETER}` parameter: {other:?}");
+                return None;
+            }
+            None => {
+                tracing::warn!(
+                    "message on clock source output has no `{SIM_TIME_PARAMETER}` parameter"
+                );
+                return None;
+            }
+        };
+        let mut state = self.state();
+        if !state.started || time <= state.time {
+            return None;
+        }
+        Some(state.step(time))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use dora_core::{config::Timer, uhlc::NTP64};
+
+    use super::State;
+
+    #[test]
+    fn step_fires_due_timers_and_wakes() {
+        let mut state = State::new(None);
+        let timer = Timer::periodic(Duration::from_millis(100));
+        state.timers.insert(timer, NTP64::default());
+        state.nodes.insert(
+            "sink".to_string().into(),
+            super::NodeState {
+                idle: Some(super::Idle {
+                    sent_outputs: 0,
+                    wake_at: Some(NTP64::from(Duration::from_millis(150))),
+                }),
+                outputs_received: 0,
+            },
+        );
+        state.started = true;
+        assert!(state.is_quiescent());
+
+        let step = state.step(NTP64::default());
+        assert_eq!(step.timers, [timer]);
+        assert!(step.wake.is_empty());
+        assert_eq!(
+            state.timers[&timer],
+            NTP64::from(Duration::from_millis(100))
+        );
+
+        let step = state.step(NTP64::from(Duration::from_millis(200)));
+        assert_eq!(step.timers.len(), 1);
+        assert_eq!(step.wake.len(), 1);
+        // the wake-up is only delivered once
+        assert!(
+            state
+                .nodes
+                .values()
+                .all(|n| n.idle.as_ref().unwrap().wake_at.is_none())
+        );
+    }
+
+    #[test]
+    fn busy_nodes_block_the_clock() {
+        let mut state = State::new(None);
+        state.started = true;
+        state
+            .nodes
+            .insert("sink".to_string().into(), Default::default());
+        assert!(!state.is_quiescent());
+        state.nodes.values_mut().for_each(|node| {
+            node.idle = Some(super::Idle {
+                sent_outputs: 1,
+                wake_at: None,
+            })
+        });
+        // the output sent before becoming idle was not processed yet
+        assert!(!state.is_quiescent());
+        state
+            .nodes
+            .values_mut()
+            .for_each(|node| node.outputs_received = 1);
+        assert!(state.is_quiescent());
+        state.sent += 1;
+        assert!(!state.is_quiescent());
+    }
+}
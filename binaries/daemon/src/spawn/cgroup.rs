@@ -0,0 +1,224 @@
+//! Resource limits of nodes through Linux cgroups (v2).
+//!
+//! Every node with a `resources` configuration gets its own cgroup at
+//! `<root>/<dataflow_id>/<node_id>`, where `<root>` defaults to
+//! `/sys/fs/cgroup/dora`. The node process is moved into its cgroup before it
+//! executes the node, so that all of its child processes are limited too.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use dora_message::{DataflowId, descriptor::Resources, id::NodeId};
+use eyre::{Context, bail};
+
+/// Environment variable to override the base directory of the cgroups created by the daemon.
+pub const CGROUP_ROOT_ENV: &str = "DORA_CGROUP_ROOT";
+const DEFAULT_CGROUP_ROOT: &str = "/sys/fs/cgroup/dora";
+
+/// Period of the CPU bandwidth limit, in microseconds.
+const CPU_PERIOD: u64 = 100_000;
+
+/// The cgroup of a node with resource limits.
+#[derive(Debug, Clone)]
+pub struct NodeCgroup {
+    path: PathBuf,
+    resources: Resources,
+}
+
+/// Current resource usage of a node cgroup.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CgroupUsage {
+    pub memory_bytes: Option<u64>,
+    pub pids: Option<u64>,
+}
+
+impl NodeCgroup {
+    /// Creates the cgroup of the given node and applies its resource limits.
+    ///
+    /// An existing cgroup, e.g. of a previous run of the node, is reused.
+    pub fn create(
+        dataflow_id: DataflowId,
+        node_id: &NodeId,
+        resources: &Resources,
+    ) -> eyre::Result<Self> {
+        let root = std::env::var_os(CGROUP_ROOT_ENV)
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from(DEFAULT_CGROUP_ROOT));
+        let dataflow_path = root.join(dataflow_id.to_string());
+        let path = dataflow_path.join(node_id.to_string());
+
+        let controllers = required_controllers(resources);
+        for dir in [&root, &dataflow_path] {
+            fs::create_dir_all(dir)
+                .wrap_err_with(|| format!("failed to create cgroup `{}`", dir.display()))?;
+            enable_controllers(dir, &controllers)?;
+        }
+        fs::create_dir_all(&path)
+            .wrap_err_with(|| format!("failed to create cgroup `{}`", path.display()))?;
+
+        for (file, value) in limit_files(resources) {
+            let file_path = path.join(file);
+            fs::write(&file_path, &value).wrap_err_with(|| {
+                format!("failed to write `{value}` to `{}`", file_path.display())
+            })?;
+        }
+
+        Ok(Self {
+            path,
+            resources: resources.clone(),
+        })
+    }
+
+    pub fn resources(&self) -> &Resources {
+        &self.resources
+    }
+
+    /// Opens the `cgroup.procs` file of the cgroup.
+    ///
+    /// Writing `0` to the file moves the writing process into the cgroup.
+    #[cfg(target_os = "linux")]
+    pub fn procs_file(&self) -> eyre::Result<fs::File> {
+        let path = self.path.join("cgroup.procs");
+        fs::OpenOptions::new()
+            .write(true)
+            .open(&path)
+            .wrap_err_with(|| format!("failed to open `{}`", path.display()))
+    }
+
+    /// Number of processes of the cgroup that were killed by the OOM killer so far.
+    pub fn oom_kills(&self) -> u64 {
+        fs::read_to_string(self.path.join("memory.events"))
+            .ok()
+            .and_then(|events| {
+                events.lines().find_map(|line| {
+                    line.strip_prefix("oom_kill ")
+                        .and_then(|count| count.trim().parse().ok())
+                })
+            })
+            .unwrap_or_default()
+    }
+
+    pub fn usage(&self) -> CgroupUsage {
+        let read = |file: &str| {
+            fs::read_to_string(self.path.join(file))
+                .ok()
+                .and_then(|value| value.trim().parse().ok())
+        };
+        CgroupUsage {
+            memory_bytes: read("memory.current"),
+            pids: read("pids.current"),
+        }
+    }
+
+    /// Removes the cgroup and the cgroup of the dataflow if it's empty.
+    ///
+    /// Fails silently if processes of the node are still alive.
+    pub fn remove(&self) {
+        if let Err(err) = fs::remove_dir(&self.path) {
+            tracing::debug!("failed to remove cgroup `{}`: {err}", self.path.display());
+            return;
+        }
+        if let Some(dataflow_path) = self.path.parent() {
+            let _ = fs::remove_dir(dataflow_path);
+        }
+    }
+}
+
+/// The cgroup controllers that are needed to apply the given limits.
+fn required_controllers(resources: &Resources) -> Vec<&'static str> {
+    // the memory and pids controllers are needed for OOM kill detection and metrics
+    let mut controllers = vec!["memory", "pids"];
+    if resources.cpu.is_some() {
+        controllers.push("cpu");
+    }
+    if resources.io_weight.is_some() {
+        controllers.push("io");
+    }
+    controllers
+}
+
+/// Enables the given controllers for the child cgroups of `dir`.
+fn enable_controllers(dir: &Path, controllers: &[&str]) -> eyre::Result<()> {
+    let available = fs::read_to_string(dir.join("cgroup.controllers")).wrap_err_with(|| {
+        format!(
+            "`{}` is not a cgroup v2 directory (set `{CGROUP_ROOT_ENV}` to a writable cgroup)",
+            dir.display()
+        )
+    })?;
+    let enabled = fs::read_to_string(dir.join("cgroup.subtree_control")).unwrap_or_default();
+    for controller in controllers {
+        if enabled.split_whitespace().any(|c| c == *controller) {
+            continue;
+        }
+        if !available.split_whitespace().any(|c| c == *controller) {
+            bail!(
+                "cgroup controller `{controller}` is not available in `{}`",
+                dir.display()
+            );
+        }
+        fs::write(dir.join("cgroup.subtree_control"), format!("+{controller}")).wrap_err_with(
+            || {
+                format!(
+                    "failed to enable cgroup controller `{controller}` in `{}`",
+                    dir.display()
+                )
+            },
+        )?;
+    }
+    Ok(())
+}
+
+/// The cgroup interface files and values for the given limits.
+fn limit_files(resources: &Resources) -> Vec<(&'static str, String)> {
+    let mut files = Vec::new();
+    if let Some(cpu) = resources.cpu {
+        let quota = ((cpu * CPU_PERIOD as f64).round() as u64).max(1_000);
+        files.push(("cpu.max", format!("{quota} {CPU_PERIOD}")));
+    }
+    if let Some(memory) = resources.memory {
+        files.push(("memory.max", memory.to_string()));
+    }
+    if let Some(pids) = resources.pids {
+        files.push(("pids.max", pids.to_string()));
+    }
+    if let Some(weight) = resources.io_weight {
+        files.push(("io.weight", format!("default {weight}")));
+    }
+    files
+}
+
+#[cfg(test)]
+mod tests {
+    use dora_message::descriptor::Resources;
+
+    use super::limit_files;
+
+    #[test]
+    fn limits_are_written_in_cgroup_format() {
+        let resources = Resources {
+            cpu: Some(1.5),
+            memory: Some(512 << 20),
+            pids: Some(64),
+            io_weight: Some(50),
+        };
+        assert_eq!(
+            limit_files(&resources),
+            [
+                ("cpu.max", "150000 100000".to_owned()),
+                ("memory.max", "536870912".to_owned()),
+                ("pids.max", "64".to_owned()),
+                ("io.weight", "default 50".to_owned()),
+            ]
+        );
+        let tiny_cpu = Resources {
+            cpu: Some(0.001),
+            ..Default::default()
+        };
+        assert_eq!(
+            limit_files(&tiny_cpu),
+            [("cpu.max", "1000 100000".to_owned())]
+        );
+    }
+}
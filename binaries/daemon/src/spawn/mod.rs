@@ -1,6 +1,11 @@
+pub use cgroup::NodeCgroup;
 pub use prepared::PreparedNode;
+pub use scheduling::realtime_scheduling_allowed;
 pub use spawner::Spawner;
 
+mod cgroup;
 mod command;
 mod prepared;
+mod sandbox;
+mod scheduling;
 mod spawner;
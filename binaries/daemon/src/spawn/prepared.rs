@@ -1,3 +1,6 @@
+use super::NodeCgroup;
+#[cfg(target_os = "linux")]
+use super::{sandbox, scheduling};
 use crate::{
     CoreNodeKindExt, DoraEvent, Event, OutputId, ProcessOperation, RunningNode,
     log::{self, NodeLogger},
@@ -14,7 +17,7 @@ use dora_message::{
     DataflowId,
     common::{LogLevel, LogMessage, LogMessageHelper},
     daemon_to_coordinator::{DataMessage, NodeExitStatus, Timestamped},
-    daemon_to_node::NodeConfig,
+    daemon_to_node::{DaemonCommunication, NodeConfig},
     descriptor::RestartPolicy,
     id::NodeId,
 };
@@ -56,6 +59,8 @@ pub struct PreparedNode {
     /// the dataflow can cancel the listener when it finishes.
     /// `AbortHandle` is `Clone`, so `#[derive(Clone)]` continues to work.
     pub(super) listener_abort_handle: Option<tokio::task::AbortHandle>,
+    /// The cgroup that applies the `resources` limits of the node, created on spawn.
+    pub(super) cgroup: Option<NodeCgroup>,
 }
 
 impl PreparedNode {
@@ -67,7 +72,40 @@ impl PreparedNode {
         self.node.kind.dynamic()
     }
 
-    pub async fn spawn(self, mut logger: NodeLogger<'static>) -> eyre::Result<RunningNode> {
+    pub async fn spawn(mut self, mut logger: NodeLogger<'static>) -> eyre::Result<RunningNode> {
+        if let Some(resources) = &self.node.resources {
+            if self.dynamic() {
+                // dynamic nodes are not spawned by the daemon
+            } else if cfg!(target_os = "linux") {
+                let cgroup = NodeCgroup::create(self.dataflow_id, &self.node.id, resources)
+                    .wrap_err("failed to set up cgroup for node resource limits")?;
+                self.cgroup = Some(cgroup);
+            } else {
+                logger
+                    .log(
+                        LogLevel::Warn,
+                        Some("spawner".into()),
+                        "ignoring `resources` limits because they are only supported on Linux"
+                            .to_string(),
+                    )
+                    .await;
+            }
+        }
+        if self.node.sandbox.is_some() && !self.dynamic() && !cfg!(target_os = "linux") {
+            // running the node without its sandbox would defeat the purpose
+            eyre::bail!("`sandbox` is only supported on Linux");
+        }
+        if self.node.scheduling.is_some() && !cfg!(target_os = "linux") {
+            logger
+                .log(
+                    LogLevel::Warn,
+                    Some("spawner".into()),
+                    "ignoring `scheduling` settings because they are only supported on Linux"
+                        .to_string(),
+                )
+                .await;
+        }
+
         let (op_tx, op_rx) = flume::bounded(2);
         let (finished_tx, finished_rx) = oneshot::channel();
         let kind = self
@@ -86,6 +124,8 @@ impl PreparedNode {
             restart_policy: self.restart_policy(),
             disable_restart: disable_restart.clone(),
             pending_hot_reload: self.pending_hot_reload.clone(),
+            liveness: self.node.liveness.clone(),
+            cgroup: self.cgroup.clone(),
             pid: match kind {
                 NodeKind::Dynamic => None,
                 NodeKind::Spawned { pid: new_pid } => {
@@ -117,7 +157,12 @@ impl PreparedNode {
     ) {
         let mut last_spawn = std::time::Instant::now();
         loop {
-            let Ok(NodeProcessFinished { exit_status, op_rx }) = finished_rx.await else {
+            let Ok(NodeProcessFinished {
+                exit_status,
+                memory_limit_exceeded,
+                op_rx,
+            }) = finished_rx.await
+            else {
                 logger
                     .log(
                         LogLevel::Error,
@@ -169,6 +214,7 @@ impl PreparedNode {
                 dataflow_id: self.dataflow_id,
                 node_id: self.node.id.clone(),
                 exit_status,
+                memory_limit_exceeded,
                 dynamic_node: self.node.kind.dynamic(),
                 restart,
             }
@@ -254,6 +300,9 @@ impl PreparedNode {
                 break;
             }
         }
+        if let Some(cgroup) = &self.cgroup {
+            cgroup.remove();
+        }
     }
 
     async fn spawn_inner(
@@ -262,9 +311,21 @@ impl PreparedNode {
         op_rx: flume::Receiver<ProcessOperation>,
         finished_tx: oneshot::Sender<NodeProcessFinished>,
     ) -> eyre::Result<NodeKind> {
+        // OOM kills are only caused by the node if it has a memory limit
+        let oom_kills_before_spawn = self
+            .cgroup
+            .clone()
+            .filter(|cgroup| cgroup.resources().memory.is_some())
+            .map(|cgroup| {
+                let oom_kills = cgroup.oom_kills();
+                (cgroup, oom_kills)
+            });
+        #[allow(unused_mut)]
+        let mut sandbox_forwarder: Option<tokio::task::AbortHandle> = None;
         let mut child = match &mut self.command {
             Some(command) => {
-                let std_command = command.to_std();
+                #[allow(unused_mut)]
+                let mut std_command = command.to_std();
                 logger
                     .log(
                         LogLevel::Info,
@@ -279,6 +340,54 @@ impl PreparedNode {
                         ),
                     )
                     .await;
+                #[cfg(target_os = "linux")]
+                if let Some(cgroup) = &self.cgroup {
+                    use std::{io::Write, os::unix::process::CommandExt};
+
+                    // move the node into its cgroup before it executes, so that
+                    // all of its child processes are limited too
+                    let mut procs = cgroup.procs_file()?;
+                    // SAFETY: the closure only writes to an already opened file,
+                    // which is async-signal-safe
+                    unsafe {
+                        std_command.pre_exec(move || procs.write_all(b"0"));
+                    }
+                }
+                #[cfg(target_os = "linux")]
+                if let Some(scheduling) = &self.node.scheduling {
+                    use std::os::unix::process::CommandExt;
+
+                    let params = scheduling::SchedulingParams::new(scheduling)?;
+                    // SAFETY: `apply` only calls async-signal-safe functions
+                    unsafe {
+                        std_command.pre_exec(move || params.apply());
+                    }
+                }
+                #[cfg(target_os = "linux")]
+                let sandbox_channel = match &self.node.sandbox {
+                    Some(sandbox) => {
+                        use std::os::unix::process::CommandExt;
+
+                        let working_dir = std_command
+                            .get_current_dir()
+                            .unwrap_or(&self.node_working_dir);
+                        let daemon_addr = match &self.node_config.daemon_communication {
+                            Some(DaemonCommunication::Tcp { socket_addr }) => Some(*socket_addr),
+                            _ => None,
+                        };
+                        let (params, channel) =
+                            sandbox::SandboxParams::new(sandbox, working_dir, daemon_addr)
+                                .wrap_err("failed to prepare node sandbox")?;
+                        // SAFETY: `enter` only calls async-signal-safe functions;
+                        // this closure is registered last so that the other
+                        // settings are applied before the file system is read-only
+                        unsafe {
+                            std_command.pre_exec(move || params.enter());
+                        }
+                        Some((channel, daemon_addr))
+                    }
+                    None => None,
+                };
                 let mut command =
                     TokioCommandWrap::from(tokio::process::Command::from(std_command));
 
@@ -298,7 +407,25 @@ impl PreparedNode {
                         .wrap(process_wrap::tokio::JobObject);
                 }
 
-                command.spawn().wrap_err(self.spawn_error_msg)?
+                let spawn_result = command.spawn();
+                #[cfg(target_os = "linux")]
+                let spawn_result =
+                    spawn_result.map_err(|err| match (&sandbox_channel, &self.node.scheduling) {
+                        (Some((channel, _)), _) => channel.explain_spawn_error(err),
+                        (None, Some(_)) => scheduling::explain_spawn_error(err),
+                        (None, None) => err.into(),
+                    });
+                let child = spawn_result.wrap_err(self.spawn_error_msg)?;
+                #[cfg(target_os = "linux")]
+                if let Some((channel, Some(daemon_addr))) = &sandbox_channel {
+                    // connections of the node arrive in its own network namespace
+                    if let Some(listener) = channel.receive_listener()? {
+                        let forwarder =
+                            tokio::spawn(sandbox::forward_connections(listener, *daemon_addr));
+                        sandbox_forwarder = Some(forwarder.abort_handle());
+                    }
+                }
+                child
             }
             None => {
                 return Ok(NodeKind::Dynamic);
@@ -457,8 +584,20 @@ impl PreparedNode {
                 }
             };
 
+            if let Some(forwarder) = sandbox_forwarder {
+                forwarder.abort();
+            }
+
+            let memory_limit_exceeded = !exit_status.is_success()
+                && oom_kills_before_spawn
+                    .is_some_and(|(cgroup, oom_kills)| cgroup.oom_kills() > oom_kills);
+
             let _ = log_finish_rx.await;
-            let _ = finished_tx.send(NodeProcessFinished { exit_status, op_rx });
+            let _ = finished_tx.send(NodeProcessFinished {
+                exit_status,
+                memory_limit_exceeded,
+                op_rx,
+            });
         });
 
         let node_id = self.node.id.clone();
@@ -594,5 +733,7 @@ enum NodeKind {
 
 struct NodeProcessFinished {
     exit_status: NodeExitStatus,
+    /// Whether a process of the node was killed because it exceeded its memory limit.
+    memory_limit_exceeded: bool,
     op_rx: flume::Receiver<ProcessOperation>,
 }
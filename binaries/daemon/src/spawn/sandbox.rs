@@ -0,0 +1,687 @@
+//! Sandboxed execution of nodes through Linux namespaces, see the `sandbox`
+//! field of the dataflow.
+//!
+//! The sandbox is set up between `fork` and `exec` of the node process:
+//!
+//! 1. The process unshares its mount, PID, and network namespaces. If the
+//!    daemon doesn't run as root, it unshares its user namespace too, which
+//!    gives it the privileges to set up the other namespaces.
+//! 2. It makes the whole file system read-only, except for the writable paths.
+//! 3. It brings up the loopback interface of the new network namespace and
+//!    listens on the port of the daemon there. The listener is sent back to
+//!    the daemon, which forwards the connections to the actual node listener.
+//! 4. It forks the init process of the new PID namespace, which forks the
+//!    process that executes the node. The original process waits for the node
+//!    and exits in the same way, so that the daemon can treat it like a normal
+//!    node process.
+//!
+//! Each step reports failures to the daemon through a socket pair, so that
+//! spawn errors can be explained.
+
+#[cfg(target_os = "linux")]
+pub use linux::{SandboxParams, forward_connections};
+
+/// Looks up the user and group ID of the given user name or numeric user ID
+/// in the given contents of `/etc/passwd`.
+///
+/// Numeric user IDs without an entry use the group ID of the same value.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn lookup_user(passwd: &str, user: &str) -> Option<(u32, u32)> {
+    let numeric_id = user.parse::<u32>().ok();
+    let entry = passwd.lines().find_map(|line| {
+        let mut fields = line.split(':');
+        let name = fields.next()?;
+        let uid = fields.nth(1)?.parse::<u32>().ok()?;
+        let gid = fields.next()?.parse::<u32>().ok()?;
+        let matches = match numeric_id {
+            Some(id) => uid == id,
+            None => name == user,
+        };
+        matches.then_some((uid, gid))
+    });
+    entry.or(numeric_id.map(|id| (id, id)))
+}
+
+// SAFETY: unless noted otherwise, the `unsafe` blocks of this module are plain
+// system calls whose pointer arguments point to valid values that outlive the call
+#[cfg(target_os = "linux")]
+mod linux {
+    use std::{
+        ffi::{CString, c_int},
+        io,
+        net::{SocketAddr, TcpListener},
+        os::{
+            fd::{AsRawFd, FromRawFd, OwnedFd, RawFd},
+            unix::ffi::OsStrExt,
+        },
+        path::Path,
+    };
+
+    use dora_message::descriptor::{Sandbox, SandboxNetwork};
+    use eyre::{Context, bail, eyre};
+
+    use super::lookup_user;
+
+    /// User that sandboxed nodes run as if the daemon runs as root.
+    const DEFAULT_USER: &str = "nobody";
+
+    /// The steps of the sandbox setup, reported to the daemon.
+    #[derive(Clone, Copy)]
+    #[repr(u8)]
+    enum Stage {
+        Done = 0,
+        Namespaces,
+        UserNamespace,
+        FileSystem,
+        Network,
+        Processes,
+        Proc,
+        User,
+    }
+
+    /// Sandbox settings of a node, prepared for [`SandboxParams::enter`].
+    pub struct SandboxParams {
+        unshare_flags: c_int,
+        /// Contents of `uid_map` and `gid_map` if a user namespace is created.
+        id_maps: Option<(Vec<u8>, Vec<u8>)>,
+        writable_paths: Vec<CString>,
+        user: Option<(libc::uid_t, libc::gid_t)>,
+        /// Loopback address of the daemon that the node connects to.
+        daemon_addr: Option<libc::sockaddr_in>,
+        channel: OwnedFd,
+    }
+
+    /// The daemon side of the channel that the sandbox setup reports to.
+    pub struct SandboxChannel {
+        socket: OwnedFd,
+        user_namespace: bool,
+    }
+
+    impl SandboxParams {
+        /// Prepares the sandbox of a node.
+        ///
+        /// Relative `writable_paths` are resolved against the `working_dir` of
+        /// the node. The `daemon_addr` is the address of the node listener.
+        pub fn new(
+            sandbox: &Sandbox,
+            working_dir: &Path,
+            daemon_addr: Option<SocketAddr>,
+        ) -> eyre::Result<(Self, SandboxChannel)> {
+            // SAFETY: plain system calls without arguments
+            let (uid, gid) = unsafe { (libc::geteuid(), libc::getegid()) };
+            let root = uid == 0;
+
+            let mut unshare_flags = libc::CLONE_NEWNS | libc::CLONE_NEWPID;
+            let id_maps = if root {
+                None
+            } else {
+                if let Some(reason) = unprivileged_user_namespaces_disabled() {
+                    bail!(
+                        "sandboxing nodes requires unprivileged user namespaces when the \
+                        daemon doesn't run as root, but they are disabled ({reason})"
+                    );
+                }
+                unshare_flags |= libc::CLONE_NEWUSER;
+                // keep the IDs of the daemon inside of the user namespace
+                Some((
+                    format!("{uid} {uid} 1\n").into_bytes(),
+                    format!("{gid} {gid} 1\n").into_bytes(),
+                ))
+            };
+
+            let user = match (&sandbox.user, root) {
+                (Some(user), false) => {
+                    bail!("`sandbox.user` ({user}) requires a daemon that runs as root")
+                }
+                (user, true) => {
+                    let user = user.as_deref().unwrap_or(DEFAULT_USER);
+                    let passwd = std::fs::read_to_string("/etc/passwd").unwrap_or_default();
+                    let ids = lookup_user(&passwd, user)
+                        .ok_or_else(|| eyre!("unknown `sandbox.user` `{user}`"))?;
+                    Some(ids)
+                }
+                (None, false) => None,
+            };
+
+            let mut writable_paths = Vec::new();
+            for path in &sandbox.writable_paths {
+                let path = std::fs::canonicalize(working_dir.join(path)).wrap_err_with(|| {
+                    format!("writable sandbox path `{}` does not exist", path.display())
+                })?;
+                writable_paths.push(CString::new(path.as_os_str().as_bytes())?);
+            }
+            // the node exchanges large messages with the daemon through shared memory
+            if Path::new("/dev/shm").exists() {
+                writable_paths.push(CString::new("/dev/shm")?);
+            }
+
+            let daemon_addr = match sandbox.network {
+                SandboxNetwork::Host => None,
+                SandboxNetwork::Loopback => {
+                    unshare_flags |= libc::CLONE_NEWNET;
+                    match daemon_addr {
+                        Some(SocketAddr::V4(addr)) if addr.ip().is_loopback() => {
+                            Some(libc::sockaddr_in {
+                                sin_family: libc::AF_INET as libc::sa_family_t,
+                                sin_port: addr.port().to_be(),
+                                sin_addr: libc::in_addr {
+                                    s_addr: u32::from(*addr.ip()).to_be(),
+                                },
+                                sin_zero: [0; 8],
+                            })
+                        }
+                        Some(addr) => {
+                            bail!("daemon address `{addr}` is not reachable from a sandbox network")
+                        }
+                        None => None,
+                    }
+                }
+            };
+
+            let mut sockets = [0; 2];
+            // SAFETY: `sockets` has room for the two created file descriptors
+            let result = unsafe {
+                libc::socketpair(
+                    libc::AF_UNIX,
+                    libc::SOCK_SEQPACKET | libc::SOCK_CLOEXEC,
+                    0,
+                    sockets.as_mut_ptr(),
+                )
+            };
+            if result != 0 {
+                return Err(io::Error::last_os_error()).wrap_err("failed to create socket pair");
+            }
+            // SAFETY: both file descriptors were just created and are owned by nobody else
+            let (daemon_end, node_end) = unsafe {
+                (
+                    OwnedFd::from_raw_fd(sockets[0]),
+                    OwnedFd::from_raw_fd(sockets[1]),
+                )
+            };
+
+            let params = Self {
+                unshare_flags,
+                id_maps,
+                writable_paths,
+                user,
+                daemon_addr,
+                channel: node_end,
+            };
+            let channel = SandboxChannel {
+                socket: daemon_end,
+                user_namespace: !root,
+            };
+            Ok((params, channel))
+        }
+
+        /// Moves the calling process into the sandbox.
+        ///
+        /// Returns only in the process that should execute the node. The calling
+        /// process waits for that process and exits with its exit status.
+        ///
+        /// Only calls async-signal-safe functions and doesn't allocate, so it can
+        /// be called between `fork` and `exec`.
+        pub fn enter(&self) -> io::Result<()> {
+            let report = |stage: Stage| move |err| self.report(stage, err);
+
+            check(unsafe { libc::unshare(self.unshare_flags) })
+                .map_err(report(Stage::Namespaces))?;
+            if let Some((uid_map, gid_map)) = &self.id_maps {
+                write_file(c"/proc/self/setgroups", b"deny")
+                    .and_then(|()| write_file(c"/proc/self/uid_map", uid_map))
+                    .and_then(|()| write_file(c"/proc/self/gid_map", gid_map))
+                    .map_err(report(Stage::UserNamespace))?;
+            }
+            self.set_up_file_system()
+                .map_err(report(Stage::FileSystem))?;
+            if self.unshare_flags & libc::CLONE_NEWNET != 0 {
+                bring_up_loopback().map_err(report(Stage::Network))?;
+            }
+            let listener = match &self.daemon_addr {
+                Some(addr) => Some(listen(addr).map_err(report(Stage::Network))?),
+                None => None,
+            };
+            send_message(
+                self.channel.as_raw_fd(),
+                Stage::Done,
+                listener.as_ref().map(AsRawFd::as_raw_fd),
+            )?;
+            drop(listener);
+
+            self.fork_node()
+        }
+
+        fn set_up_file_system(&self) -> io::Result<()> {
+            // don't propagate any of the following mounts to the parent namespace
+            check(unsafe {
+                libc::mount(
+                    std::ptr::null(),
+                    c"/".as_ptr(),
+                    std::ptr::null(),
+                    libc::MS_REC | libc::MS_PRIVATE,
+                    std::ptr::null(),
+                )
+            })?;
+            // bind mount the writable paths onto themselves, so that they become
+            // separate mounts whose read-only flag can be cleared again
+            for path in &self.writable_paths {
+                check(unsafe {
+                    libc::mount(
+                        path.as_ptr(),
+                        path.as_ptr(),
+                        std::ptr::null(),
+                        libc::MS_BIND | libc::MS_REC,
+                        std::ptr::null(),
+                    )
+                })?;
+            }
+            set_read_only(c"/".as_ptr(), true)?;
+            for path in &self.writable_paths {
+                set_read_only(path.as_ptr(), false)?;
+            }
+            Ok(())
+        }
+
+        /// Forks the init process of the new PID namespace, which forks the
+        /// process that executes the node.
+        fn fork_node(&self) -> io::Result<()> {
+            const SIGNALS: [c_int; 4] = [libc::SIGINT, libc::SIGTERM, libc::SIGQUIT, libc::SIGHUP];
+
+            // signals sent to the process group are meant for the node only
+            for signal in SIGNALS {
+                unsafe { libc::signal(signal, libc::SIG_IGN) };
+            }
+            let mut status_pipe = [0; 2];
+            check(unsafe { libc::pipe2(status_pipe.as_mut_ptr(), libc::O_CLOEXEC) })
+                .map_err(|err| self.report(Stage::Processes, err))?;
+            let [status_rx, status_tx] = status_pipe;
+
+            let init =
+                check(unsafe { libc::fork() }).map_err(|err| self.report(Stage::Processes, err))?;
+            if init != 0 {
+                // wait for the init process, which reports the exit status of the node
+                unsafe { libc::close(status_tx) };
+                close_all_except(status_rx);
+                let init_status = wait_for(init);
+                let mut node_status = [0; 4];
+                let read = unsafe { libc::read(status_rx, node_status.as_mut_ptr().cast(), 4) };
+                match read {
+                    4 => exit_like(c_int::from_ne_bytes(node_status)),
+                    _ => exit_like(init_status),
+                }
+            }
+
+            unsafe { libc::prctl(libc::PR_SET_PDEATHSIG, libc::SIGKILL) };
+            let node =
+                check(unsafe { libc::fork() }).map_err(|err| self.report(Stage::Processes, err))?;
+            if node != 0 {
+                // init process of the PID namespace, which reaps orphaned processes
+                unsafe { libc::close(status_rx) };
+                close_all_except(status_tx);
+                loop {
+                    let mut status = 0;
+                    let pid = unsafe { libc::waitpid(-1, &mut status, 0) };
+                    if pid == node {
+                        let status = status.to_ne_bytes();
+                        unsafe { libc::write(status_tx, status.as_ptr().cast(), 4) };
+                        unsafe { libc::_exit(0) };
+                    } else if pid < 0
+                        && io::Error::last_os_error().raw_os_error() != Some(libc::EINTR)
+                    {
+                        unsafe { libc::_exit(1) };
+                    }
+                }
+            }
+
+            unsafe { libc::prctl(libc::PR_SET_PDEATHSIG, libc::SIGKILL) };
+            for signal in SIGNALS {
+                unsafe { libc::signal(signal, libc::SIG_DFL) };
+            }
+            check(unsafe {
+                libc::mount(
+                    c"proc".as_ptr(),
+                    c"/proc".as_ptr(),
+                    c"proc".as_ptr(),
+                    libc::MS_NOSUID | libc::MS_NODEV | libc::MS_NOEXEC,
+                    std::ptr::null(),
+                )
+            })
+            .map_err(|err| self.report(Stage::Proc, err))?;
+            if let Some((uid, gid)) = self.user {
+                check(unsafe { libc::setgroups(0, std::ptr::null()) })
+                    .and_then(|_| check(unsafe { libc::setgid(gid) }))
+                    .and_then(|_| check(unsafe { libc::setuid(uid) }))
+                    .map_err(|err| self.report(Stage::User, err))?;
+            }
+            // prevent the node from gaining privileges through setuid executables
+            check(unsafe { libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0) })
+                .map_err(|err| self.report(Stage::User, err))?;
+            Ok(())
+        }
+
+        fn report(&self, stage: Stage, err: io::Error) -> io::Error {
+            let _ = send_message(self.channel.as_raw_fd(), stage, None);
+            err
+        }
+    }
+
+    impl SandboxChannel {
+        /// Receives the listener that the sandboxed node process created on its
+        /// loopback interface, if any.
+        pub fn receive_listener(&self) -> eyre::Result<Option<TcpListener>> {
+            match receive_message(self.socket.as_raw_fd())? {
+                Some((0, listener)) => Ok(listener.map(TcpListener::from)),
+                _ => bail!("sandboxed node process didn't report its setup"),
+            }
+        }
+
+        /// Explains spawn errors of nodes that were caused by the sandbox setup.
+        pub fn explain_spawn_error(&self, err: io::Error) -> eyre::Report {
+            let mut stage = None;
+            while let Ok(Some((reported, _))) = receive_message(self.socket.as_raw_fd()) {
+                if reported != Stage::Done as u8 {
+                    stage = Some(reported);
+                }
+            }
+            let explanation = match stage {
+                Some(s) if s == Stage::Namespaces as u8 && self.user_namespace => {
+                    "failed to create the namespaces of the sandbox: the kernel doesn't \
+                    permit unprivileged user namespaces (see the `user.max_user_namespaces`, \
+                    `kernel.unprivileged_userns_clone`, and \
+                    `kernel.apparmor_restrict_unprivileged_userns` sysctls), consider \
+                    running the daemon as root"
+                }
+                Some(s) if s == Stage::Namespaces as u8 => {
+                    "failed to create the namespaces of the sandbox"
+                }
+                Some(s) if s == Stage::UserNamespace as u8 => {
+                    "failed to set up the user namespace of the sandbox"
+                }
+                Some(s)
+                    if s == Stage::FileSystem as u8 && err.raw_os_error() == Some(libc::ENOSYS) =>
+                {
+                    "failed to make the file system of the sandbox read-only: sandboxes \
+                    require Linux 5.12 or newer"
+                }
+                Some(s) if s == Stage::FileSystem as u8 => {
+                    "failed to make the file system of the sandbox read-only"
+                }
+                Some(s) if s == Stage::Network as u8 => {
+                    "failed to set up the loopback network of the sandbox"
+                }
+                Some(s) if s == Stage::Processes as u8 => {
+                    "failed to start the processes of the sandbox"
+                }
+                Some(s) if s == Stage::Proc as u8 => "failed to mount `/proc` in the sandbox",
+                Some(s) if s == Stage::User as u8 => "failed to switch to the `sandbox.user`",
+                _ => return err.into(),
+            };
+            eyre!(err).wrap_err(explanation)
+        }
+    }
+
+    /// Accepts the connections of a sandboxed node on the given listener and
+    /// forwards them to the node listener of the daemon.
+    pub async fn forward_connections(listener: TcpListener, daemon_addr: SocketAddr) {
+        let listener = match listener
+            .set_nonblocking(true)
+            .and_then(|()| tokio::net::TcpListener::from_std(listener))
+        {
+            Ok(listener) => listener,
+            Err(err) => {
+                tracing::warn!("failed to listen for connections of sandboxed node: {err}");
+                return;
+            }
+        };
+        loop {
+            let mut incoming = match listener.accept().await {
+                Ok((connection, _)) => connection,
+                Err(err) => {
+                    tracing::warn!("failed to accept connection of sandboxed node: {err}");
+                    continue;
+                }
+            };
+            tokio::spawn(async move {
+                let mut outgoing = match tokio::net::TcpStream::connect(daemon_addr).await {
+                    Ok(connection) => connection,
+                    Err(err) => {
+                        tracing::warn!("failed to forward connection of sandboxed node: {err}");
+                        return;
+                    }
+                };
+                let _ = incoming.set_nodelay(true);
+                let _ = outgoing.set_nodelay(true);
+                let _ = tokio::io::copy_bidirectional(&mut incoming, &mut outgoing).await;
+            });
+        }
+    }
+
+    /// Returns the reason if the kernel doesn't permit unprivileged user namespaces.
+    fn unprivileged_user_namespaces_disabled() -> Option<&'static str> {
+        let sysctl = |path: &str| std::fs::read_to_string(path).ok();
+        if sysctl("/proc/sys/user/max_user_namespaces").is_some_and(|max| max.trim() == "0") {
+            Some("`user.max_user_namespaces` is 0")
+        } else if sysctl("/proc/sys/kernel/unprivileged_userns_clone")
+            .is_some_and(|enabled| enabled.trim() == "0")
+        {
+            Some("`kernel.unprivileged_userns_clone` is 0")
+        } else {
+            None
+        }
+    }
+
+    fn check(result: c_int) -> io::Result<c_int> {
+        if result == -1 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(result)
+        }
+    }
+
+    fn write_file(path: &std::ffi::CStr, contents: &[u8]) -> io::Result<()> {
+        let fd = check(unsafe { libc::open(path.as_ptr(), libc::O_WRONLY | libc::O_CLOEXEC) })?;
+        let written = unsafe { libc::write(fd, contents.as_ptr().cast(), contents.len()) };
+        let result = if written == contents.len() as isize {
+            Ok(())
+        } else {
+            Err(io::Error::last_os_error())
+        };
+        unsafe { libc::close(fd) };
+        result
+    }
+
+    /// Sets or clears the read-only flag of the mount at `path` and all mounts below it.
+    fn set_read_only(path: *const libc::c_char, read_only: bool) -> io::Result<()> {
+        let mut attr: libc::mount_attr = unsafe { std::mem::zeroed() };
+        if read_only {
+            attr.attr_set = libc::MOUNT_ATTR_RDONLY;
+        } else {
+            attr.attr_clr = libc::MOUNT_ATTR_RDONLY;
+        }
+        let result = unsafe {
+            libc::syscall(
+                libc::SYS_mount_setattr,
+                libc::AT_FDCWD,
+                path,
+                libc::AT_RECURSIVE,
+                &attr,
+                std::mem::size_of::<libc::mount_attr>(),
+            )
+        };
+        check(result as c_int).map(|_| ())
+    }
+
+    /// Brings up the loopback interface of the network namespace.
+    fn bring_up_loopback() -> io::Result<()> {
+        let socket = new_socket(libc::SOCK_DGRAM)?;
+        let mut request: libc::ifreq = unsafe { std::mem::zeroed() };
+        for (dst, src) in request.ifr_name.iter_mut().zip(b"lo") {
+            *dst = *src as libc::c_char;
+        }
+        request.ifr_ifru.ifru_flags = (libc::IFF_UP | libc::IFF_RUNNING) as libc::c_short;
+        check(unsafe { libc::ioctl(socket.as_raw_fd(), libc::SIOCSIFFLAGS as _, &request) })?;
+        Ok(())
+    }
+
+    fn listen(addr: &libc::sockaddr_in) -> io::Result<OwnedFd> {
+        let listener = new_socket(libc::SOCK_STREAM)?;
+        check(unsafe {
+            libc::bind(
+                listener.as_raw_fd(),
+                (addr as *const libc::sockaddr_in).cast(),
+                std::mem::size_of::<libc::sockaddr_in>() as libc::socklen_t,
+            )
+        })?;
+        check(unsafe { libc::listen(listener.as_raw_fd(), 128) })?;
+        Ok(listener)
+    }
+
+    fn new_socket(kind: c_int) -> io::Result<OwnedFd> {
+        let fd = check(unsafe { libc::socket(libc::AF_INET, kind | libc::SOCK_CLOEXEC, 0) })?;
+        Ok(unsafe { OwnedFd::from_raw_fd(fd) })
+    }
+
+    /// Space for a control message with one file descriptor, aligned like `cmsghdr`.
+    #[repr(C)]
+    union ControlBuffer {
+        buffer: [u8; 64],
+        _align: libc::cmsghdr,
+    }
+
+    fn send_message(socket: RawFd, stage: Stage, fd: Option<RawFd>) -> io::Result<()> {
+        let mut data = [stage as u8];
+        let mut iov = libc::iovec {
+            iov_base: data.as_mut_ptr().cast(),
+            iov_len: data.len(),
+        };
+        let mut control = ControlBuffer { buffer: [0; 64] };
+        let mut message: libc::msghdr = unsafe { std::mem::zeroed() };
+        message.msg_iov = &mut iov;
+        message.msg_iovlen = 1;
+        if let Some(fd) = fd {
+            unsafe {
+                let fd_len = std::mem::size_of::<RawFd>() as u32;
+                message.msg_control = control.buffer.as_mut_ptr().cast();
+                message.msg_controllen = libc::CMSG_SPACE(fd_len) as _;
+                let header = libc::CMSG_FIRSTHDR(&message);
+                (*header).cmsg_level = libc::SOL_SOCKET;
+                (*header).cmsg_type = libc::SCM_RIGHTS;
+                (*header).cmsg_len = libc::CMSG_LEN(fd_len) as _;
+                std::ptr::write_unaligned(libc::CMSG_DATA(header).cast::<RawFd>(), fd);
+            }
+        }
+        let sent = unsafe { libc::sendmsg(socket, &message, libc::MSG_NOSIGNAL) };
+        if sent < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    /// Receives a pending message of the sandbox setup without blocking.
+    fn receive_message(socket: RawFd) -> io::Result<Option<(u8, Option<OwnedFd>)>> {
+        let mut data = [0u8];
+        let mut iov = libc::iovec {
+            iov_base: data.as_mut_ptr().cast(),
+            iov_len: data.len(),
+        };
+        let mut control = ControlBuffer { buffer: [0; 64] };
+        let mut message: libc::msghdr = unsafe { std::mem::zeroed() };
+        message.msg_iov = &mut iov;
+        message.msg_iovlen = 1;
+        message.msg_control = unsafe { control.buffer.as_mut_ptr().cast() };
+        message.msg_controllen = std::mem::size_of::<ControlBuffer>() as _;
+        let received = unsafe {
+            libc::recvmsg(
+                socket,
+                &mut message,
+                libc::MSG_DONTWAIT | libc::MSG_CMSG_CLOEXEC,
+            )
+        };
+        if received < 0 {
+            let err = io::Error::last_os_error();
+            return match err.kind() {
+                io::ErrorKind::WouldBlock => Ok(None),
+                _ => Err(err),
+            };
+        }
+        if received == 0 {
+            return Ok(None);
+        }
+        let mut fd = None;
+        unsafe {
+            let header = libc::CMSG_FIRSTHDR(&message);
+            if !header.is_null()
+                && (*header).cmsg_level == libc::SOL_SOCKET
+                && (*header).cmsg_type == libc::SCM_RIGHTS
+            {
+                let raw = std::ptr::read_unaligned(libc::CMSG_DATA(header).cast::<RawFd>());
+                fd = Some(OwnedFd::from_raw_fd(raw));
+            }
+        }
+        Ok(Some((data[0], fd)))
+    }
+
+    /// Closes all file descriptors except for the standard streams and `keep`.
+    ///
+    /// This closes the file descriptors inherited from the daemon, most
+    /// importantly the pipe through which the daemon detects that the node
+    /// process executed the node.
+    fn close_all_except(keep: RawFd) {
+        let keep = keep as libc::c_uint;
+        unsafe {
+            libc::syscall(libc::SYS_close_range, 3, keep - 1, 0);
+            libc::syscall(libc::SYS_close_range, keep + 1, libc::c_uint::MAX, 0);
+        }
+    }
+
+    /// Waits for the given child process and returns its wait status.
+    fn wait_for(pid: libc::pid_t) -> c_int {
+        loop {
+            let mut status = 0;
+            if unsafe { libc::waitpid(pid, &mut status, 0) } == pid {
+                return status;
+            }
+            if io::Error::last_os_error().raw_os_error() != Some(libc::EINTR) {
+                return libc::EXIT_FAILURE << 8;
+            }
+        }
+    }
+
+    /// Exits the calling process in the same way as described by the given wait status.
+    fn exit_like(status: c_int) -> ! {
+        if libc::WIFSIGNALED(status) {
+            let signal = libc::WTERMSIG(status);
+            unsafe {
+                // the node already dumped its core if it should have
+                let no_core = libc::rlimit {
+                    rlim_cur: 0,
+                    rlim_max: 0,
+                };
+                libc::setrlimit(libc::RLIMIT_CORE, &no_core);
+                libc::signal(signal, libc::SIG_DFL);
+                let mut signals: libc::sigset_t = std::mem::zeroed();
+                libc::sigemptyset(&mut signals);
+                libc::sigprocmask(libc::SIG_SETMASK, &signals, std::ptr::null_mut());
+                libc::kill(libc::getpid(), signal);
+                libc::_exit(128 + signal)
+            }
+        }
+        unsafe { libc::_exit(libc::WEXITSTATUS(status)) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::lookup_user;
+
+    #[test]
+    fn users_are_looked_up_by_name_or_id() {
+        let passwd = "root:x:0:0:root:/root:/bin/bash\n\
+            nobody:x:65534:65534:nobody:/nonexistent:/usr/sbin/nologin\n\
+            dora:x:1000:100::/home/dora:/bin/sh\n";
+        assert_eq!(lookup_user(passwd, "nobody"), Some((65534, 65534)));
+        assert_eq!(lookup_user(passwd, "1000"), Some((1000, 100)));
+        assert_eq!(lookup_user(passwd, "2000"), Some((2000, 2000)));
+        assert_eq!(lookup_user(passwd, "unknown"), None);
+    }
+}
@@ -0,0 +1,149 @@
+//! CPU scheduling settings of nodes, see the `scheduling` field of the dataflow.
+//!
+//! The settings are applied between `fork` and `exec` of the node process, so
+//! that all threads and child processes of the node inherit them.
+
+#[cfg(target_os = "linux")]
+pub use linux::{SchedulingParams, explain_spawn_error};
+
+/// Capability that is required for real-time policies and raised priorities.
+const CAP_SYS_NICE: u32 = 23;
+
+/// Whether the daemon is allowed to use real-time scheduling policies and to
+/// raise the priority of nodes, i.e. whether it has the `CAP_SYS_NICE` capability.
+pub fn realtime_scheduling_allowed() -> bool {
+    cfg!(target_os = "linux")
+        && std::fs::read_to_string("/proc/self/status")
+            .is_ok_and(|status| has_effective_capability(&status, CAP_SYS_NICE))
+}
+
+/// Checks the `CapEff` bit mask in the given contents of `/proc/<pid>/status`.
+fn has_effective_capability(status: &str, capability: u32) -> bool {
+    status
+        .lines()
+        .find_map(|line| line.strip_prefix("CapEff:"))
+        .and_then(|caps| u64::from_str_radix(caps.trim(), 16).ok())
+        .is_some_and(|caps| caps & (1 << capability) != 0)
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use std::io;
+
+    use dora_message::descriptor::{Scheduling, SchedulingPolicy};
+    use eyre::{bail, eyre};
+
+    /// Scheduling settings of a node, prepared for [`SchedulingParams::apply`].
+    #[derive(Clone, Copy)]
+    pub struct SchedulingParams {
+        policy: Option<(libc::c_int, libc::c_int)>,
+        cpu_set: Option<libc::cpu_set_t>,
+        nice: Option<libc::c_int>,
+    }
+
+    impl SchedulingParams {
+        pub fn new(scheduling: &Scheduling) -> eyre::Result<Self> {
+            let policy = scheduling.policy.map(|policy| {
+                let policy = match policy {
+                    SchedulingPolicy::Other => libc::SCHED_OTHER,
+                    SchedulingPolicy::Fifo => libc::SCHED_FIFO,
+                    SchedulingPolicy::Rr => libc::SCHED_RR,
+                    SchedulingPolicy::Batch => libc::SCHED_BATCH,
+                    SchedulingPolicy::Idle => libc::SCHED_IDLE,
+                };
+                (policy, scheduling.priority.unwrap_or(0).into())
+            });
+            let cpu_set = if scheduling.cpu_affinity.is_empty() {
+                None
+            } else {
+                // SAFETY: `cpu_set_t` is a plain bit mask, so all zeros is a valid (empty) set
+                let mut cpu_set: libc::cpu_set_t = unsafe { std::mem::zeroed() };
+                for &cpu in &scheduling.cpu_affinity {
+                    if cpu >= libc::CPU_SETSIZE as usize {
+                        bail!("CPU index {cpu} in `scheduling.cpu_affinity` is out of range");
+                    }
+                    // SAFETY: the index was checked against the size of the set above
+                    unsafe { libc::CPU_SET(cpu, &mut cpu_set) };
+                }
+                Some(cpu_set)
+            };
+            Ok(Self {
+                policy,
+                cpu_set,
+                nice: scheduling.nice.map(Into::into),
+            })
+        }
+
+        /// Applies the settings to the calling process.
+        ///
+        /// Only calls async-signal-safe functions and doesn't allocate, so it can
+        /// be called between `fork` and `exec`.
+        pub fn apply(&self) -> io::Result<()> {
+            if let Some(cpu_set) = &self.cpu_set {
+                // SAFETY: the set is a valid `cpu_set_t` of the given size
+                let result = unsafe {
+                    libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), cpu_set)
+                };
+                if result != 0 {
+                    return Err(io::Error::last_os_error());
+                }
+            }
+            if let Some(nice) = self.nice {
+                // SAFETY: plain system call without pointer arguments
+                if unsafe { libc::setpriority(libc::PRIO_PROCESS, 0, nice) } != 0 {
+                    return Err(io::Error::last_os_error());
+                }
+            }
+            if let Some((policy, priority)) = self.policy {
+                let param = libc::sched_param {
+                    sched_priority: priority,
+                };
+                // SAFETY: `param` is a valid `sched_param` that outlives the call
+                if unsafe { libc::sched_setscheduler(0, policy, &param) } != 0 {
+                    return Err(io::Error::last_os_error());
+                }
+            }
+            Ok(())
+        }
+    }
+
+    /// Explains spawn errors of nodes that are caused by their scheduling settings.
+    pub fn explain_spawn_error(err: io::Error) -> eyre::Report {
+        let explanation = match err.raw_os_error() {
+            Some(libc::EPERM) => {
+                "failed to apply `scheduling` settings: real-time policies and negative \
+                `nice` values require the `CAP_SYS_NICE` capability (see `dora check`)"
+            }
+            Some(libc::EINVAL) => {
+                "failed to apply `scheduling` settings: invalid settings, e.g. \
+                `cpu_affinity` contains no online CPU"
+            }
+            _ => return err.into(),
+        };
+        eyre!(err).wrap_err(explanation)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CAP_SYS_NICE, has_effective_capability};
+
+    #[test]
+    fn effective_capabilities_are_parsed() {
+        let status =
+            |caps: &str| format!("Name:\tdora\nCapInh:\t0000000000000000\nCapEff:\t{caps}\n");
+        assert!(has_effective_capability(
+            &status("000001ffffffffff"),
+            CAP_SYS_NICE
+        ));
+        assert!(has_effective_capability(
+            &status("0000000000800000"),
+            CAP_SYS_NICE
+        ));
+        assert!(!has_effective_capability(
+            &status("0000000000000000"),
+            CAP_SYS_NICE
+        ));
+        assert!(!has_effective_capability("Name:\tdora\n", CAP_SYS_NICE));
+    }
+}
@@ -1,8 +1,9 @@
 use crate::{
     CoreNodeKindExt, Event,
     log::NodeLogger,
-    node_communication::spawn_listener_loop,
+    node_communication::{NodeCommunication, spawn_listener_loop},
     node_inputs,
+    sim_clock::SimClock,
     spawn::{
         command::{path_spawn_command, uv_python_interpreter_from_env},
         prepared::PreparedNode,
@@ -11,6 +12,7 @@ use crate::{
 use clonable_command::{Command, Stdio};
 use crossbeam::queue::ArrayQueue;
 use dora_core::{
+    config::NodeId,
     descriptor::{Descriptor, OperatorDefinition, OperatorSource, PythonSource, ResolvedNode},
     get_python_path,
     uhlc::HLC,
@@ -20,6 +22,7 @@ use dora_message::{
     common::LogLevel,
     daemon_to_coordinator::Timestamped,
     daemon_to_node::{NodeConfig, RuntimeConfig},
+    descriptor::EnvValue,
 };
 use eyre::{ContextCompat, WrapErr, bail};
 use std::{
@@ -37,9 +40,34 @@ pub struct Spawner {
     /// clock is required for generating timestamps when dropping messages early because queue is full
     pub clock: Arc<HLC>,
     pub uv: bool,
+    pub node_communication: NodeCommunication,
+    pub sim_clock: Option<SimClock>,
 }
 
 impl Spawner {
+    /// Creates one node per replica instance, which can then be spawned through
+    /// [`spawn_node`](Self::spawn_node).
+    ///
+    /// Each instance gets the `DORA_REPLICA_INDEX` and `DORA_REPLICA_COUNT`
+    /// environment variables.
+    pub fn replica_instances(node: ResolvedNode, instance_ids: &[NodeId]) -> Vec<ResolvedNode> {
+        instance_ids
+            .iter()
+            .enumerate()
+            .map(|(index, instance_id)| {
+                let mut instance = node.clone();
+                instance.id = instance_id.clone();
+                let env = instance.env.get_or_insert_with(Default::default);
+                env.insert("DORA_REPLICA_INDEX".into(), EnvValue::Integer(index as i64));
+                env.insert(
+                    "DORA_REPLICA_COUNT".into(),
+                    EnvValue::Integer(instance_ids.len() as i64),
+                );
+                instance
+            })
+            .collect()
+    }
+
     pub async fn spawn_node(
         self,
         node: ResolvedNode,
@@ -68,6 +96,14 @@ impl Spawner {
             &self.daemon_tx,
             queue_sizes,
             self.clock.clone(),
+            self.sim_clock.clone(),
+            // sandboxed nodes may run as a different user, which can't access
+            // the socket directory or shared memory regions, so they always
+            // connect through TCP
+            match node.sandbox {
+                Some(_) => NodeCommunication::Tcp,
+                None => self.node_communication,
+            },
         )
         .await?;
 
@@ -80,6 +116,8 @@ impl Spawner {
                 .context("failed to serialize dataflow descriptor to YAML")?,
             dynamic: node.kind.dynamic(),
             write_events_to,
+            simulated_clock: self.sim_clock.is_some(),
+            liveness_timeout: node.liveness.as_ref().map(|liveness| liveness.timeout),
         };
 
         let mut logger = logger
@@ -344,6 +382,7 @@ impl Spawner {
             node_stderr_most_recent,
             pending_hot_reload: Arc::new(AtomicBool::new(false)),
             listener_abort_handle: None,
+            cgroup: None,
         })
     }
 }
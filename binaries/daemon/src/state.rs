@@ -15,7 +15,7 @@ use dora_message::{
 };
 use tokio::sync::{Mutex, mpsc};
 
-use crate::{Event, InterDaemonEvent, RunningDataflow};
+use crate::{Event, InterDaemonEvent, NodeCommunication, RunningDataflow};
 
 /// Shared daemon state accessible from both the event loop and the RPC server.
 ///
@@ -35,8 +35,18 @@ pub(crate) struct DaemonState {
     pub(crate) builds: DashMap<BuildId, BuildInfo>,
 
     /// tarpc client for daemon→coordinator RPC (replaces raw TCP `coordinator_connection`).
-    /// Set once during registration via [`set_coordinator_client`].
-    coordinator_client: std::sync::OnceLock<CoordinatorNotifyClient>,
+    /// Set during registration via [`set_coordinator_client`], replaced when
+    /// reconnecting to a new coordinator leader.
+    coordinator_client: std::sync::RwLock<Option<CoordinatorNotifyClient>>,
+    /// Settings for reconnecting to a new leader of a high-availability
+    /// coordinator cluster, only set if multiple coordinators are configured.
+    pub(crate) reconnect: std::sync::OnceLock<crate::coordinator::ReconnectSettings>,
+    /// Set while a reconnect to the coordinator is in progress.
+    pub(crate) reconnecting: std::sync::atomic::AtomicBool,
+    /// Dataflow results that were not received by the coordinator yet.
+    ///
+    /// They are sent again after reconnecting to a new coordinator leader.
+    pub(crate) unreported_results: Arc<DashMap<DataflowId, DataflowDaemonResult>>,
     /// Last time we received a heartbeat from the coordinator.
     pub(crate) last_coordinator_heartbeat: Mutex<Instant>,
     /// Git clone management for builds.
@@ -46,6 +56,8 @@ pub(crate) struct DaemonState {
     /// Channel to send remote daemon events into the event loop.
     pub(crate) remote_daemon_events_tx:
         Option<flume::Sender<eyre::Result<Timestamped<InterDaemonEvent>>>>,
+    /// Transport that spawned nodes use to connect to the daemon.
+    pub(crate) node_communication: NodeCommunication,
 }
 
 impl DaemonState {
@@ -54,6 +66,7 @@ impl DaemonState {
         events_tx: mpsc::Sender<Timestamped<Event>>,
         zenoh_session: Option<zenoh::Session>,
         remote_daemon_events_tx: Option<flume::Sender<eyre::Result<Timestamped<InterDaemonEvent>>>>,
+        node_communication: NodeCommunication,
     ) -> Self {
         Self {
             clock,
@@ -64,11 +77,15 @@ impl DaemonState {
             dataflow_node_results: Default::default(),
             sessions: Default::default(),
             builds: Default::default(),
-            coordinator_client: std::sync::OnceLock::new(),
+            coordinator_client: Default::default(),
+            reconnect: Default::default(),
+            reconnecting: Default::default(),
+            unreported_results: Default::default(),
             last_coordinator_heartbeat: Mutex::new(Instant::now()),
             git_manager: Mutex::new(Default::default()),
             zenoh_session,
             remote_daemon_events_tx,
+            node_communication,
         }
     }
 
@@ -79,6 +96,7 @@ impl DaemonState {
         events_tx: mpsc::Sender<Timestamped<Event>>,
         zenoh_session: zenoh::Session,
         builds: BTreeMap<BuildId, BuildInfo>,
+        node_communication: NodeCommunication,
     ) -> Self {
         let state = Self {
             clock,
@@ -95,11 +113,15 @@ impl DaemonState {
                 }
                 map
             },
-            coordinator_client: std::sync::OnceLock::new(),
+            coordinator_client: Default::default(),
+            reconnect: Default::default(),
+            reconnecting: Default::default(),
+            unreported_results: Default::default(),
             last_coordinator_heartbeat: Mutex::new(Instant::now()),
             git_manager: Mutex::new(Default::default()),
             zenoh_session: Some(zenoh_session),
             remote_daemon_events_tx: None,
+            node_communication,
         };
         let _ = state.daemon_id.set(daemon_id);
         state
@@ -122,14 +144,53 @@ impl DaemonState {
         self.daemon_id.get()
     }
 
-    /// Set the coordinator client after registration. Can only be called once.
+    /// Set the coordinator client after registration, replacing the client
+    /// of a previous coordinator.
     pub(crate) fn set_coordinator_client(&self, client: CoordinatorNotifyClient) {
-        let _ = self.coordinator_client.set(client);
+        *self
+            .coordinator_client
+            .write()
+            .unwrap_or_else(|e| e.into_inner()) = Some(client);
     }
 
     /// Get the coordinator client, if set.
-    pub(crate) fn coordinator_client(&self) -> Option<&CoordinatorNotifyClient> {
-        self.coordinator_client.get()
+    pub(crate) fn coordinator_client(&self) -> Option<CoordinatorNotifyClient> {
+        self.coordinator_client
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Reports the result of a finished dataflow to the coordinator.
+    ///
+    /// The result is kept until the coordinator received it, so that it can be
+    /// sent again after reconnecting to a new coordinator leader.
+    pub(crate) fn report_dataflow_result(
+        &self,
+        dataflow_id: DataflowId,
+        result: DataflowDaemonResult,
+    ) {
+        self.unreported_results.insert(dataflow_id, result.clone());
+        let Some(client) = self.coordinator_client() else {
+            return;
+        };
+        let unreported_results = self.unreported_results.clone();
+        tokio::spawn(async move {
+            match client
+                .all_nodes_finished(tarpc::context::current(), dataflow_id, result)
+                .await
+            {
+                Ok(()) => {
+                    unreported_results.remove(&dataflow_id);
+                }
+                Err(err) => {
+                    tracing::error!(
+                        ?err,
+                        "failed to send all_nodes_finished notification to coordinator"
+                    );
+                }
+            }
+        });
     }
 
     /// Finish a dataflow: report to coordinator and clean up state.
@@ -157,14 +218,7 @@ impl DaemonState {
                 });
         }
 
-        if let Some(client) = self.coordinator_client.get() {
-            let client = client.clone();
-            tokio::spawn(async move {
-                let _ = client
-                    .all_nodes_finished(tarpc::context::current(), dataflow_id, result)
-                    .await;
-            });
-        }
+        self.report_dataflow_result(dataflow_id, result);
         self.running.remove(&dataflow_id);
 
         Ok(())
@@ -0,0 +1,82 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use dora_core::config::Timer;
+use dora_message::metadata::{MetadataParameters, Parameter};
+
+/// Metadata parameter that contains the intended fire time of a timer tick.
+pub const SCHEDULED_TIME_PARAMETER: &str = "timer_scheduled_time";
+/// Metadata parameter that contains the delay of a timer tick relative to its
+/// intended fire time, in microseconds.
+pub const JITTER_PARAMETER: &str = "timer_jitter_us";
+
+/// Returns the delay between the timer start and the first tick.
+pub fn first_tick_delay(timer: &Timer, now: SystemTime) -> Duration {
+    if timer.once {
+        return timer.interval;
+    }
+    if !timer.align_to_wall_clock {
+        return timer.phase;
+    }
+    let interval = timer.interval.as_nanos();
+    let phase = timer.phase.as_nanos() % interval;
+    let since_epoch = now
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    let since_boundary = (since_epoch + interval - phase) % interval;
+    let remaining = (interval - since_boundary) % interval;
+    Duration::from_nanos(remaining as u64)
+}
+
+/// Adds the intended fire time and the jitter of a timer tick to the given
+/// metadata parameters.
+pub fn add_tick_parameters(
+    parameters: &mut MetadataParameters,
+    scheduled: SystemTime,
+    jitter: Duration,
+) {
+    parameters.insert(
+        SCHEDULED_TIME_PARAMETER.to_owned(),
+        Parameter::Timestamp(scheduled.into()),
+    );
+    parameters.insert(
+        JITTER_PARAMETER.to_owned(),
+        Parameter::Integer(jitter.as_micros().try_into().unwrap_or(i64::MAX)),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use dora_core::config::Timer;
+
+    use super::first_tick_delay;
+
+    #[test]
+    fn periodic_timers_start_after_phase() {
+        let timer = Timer {
+            phase: Duration::from_millis(50),
+            ..Timer::periodic(Duration::from_millis(100))
+        };
+        let now = UNIX_EPOCH + Duration::from_millis(1234);
+        assert_eq!(first_tick_delay(&timer, now), Duration::from_millis(50));
+    }
+
+    #[test]
+    fn aligned_timers_wait_for_next_boundary() {
+        let mut timer = Timer {
+            align_to_wall_clock: true,
+            ..Timer::periodic(Duration::from_secs(1))
+        };
+        let now = UNIX_EPOCH + Duration::from_millis(10_300);
+        assert_eq!(first_tick_delay(&timer, now), Duration::from_millis(700));
+        let on_boundary = UNIX_EPOCH + Duration::from_secs(10);
+        assert_eq!(first_tick_delay(&timer, on_boundary), Duration::ZERO);
+
+        timer.phase = Duration::from_millis(250);
+        assert_eq!(first_tick_delay(&timer, now), Duration::from_millis(950));
+        timer.phase = Duration::from_millis(400);
+        assert_eq!(first_tick_delay(&timer, now), Duration::from_millis(100));
+    }
+}
@@ -0,0 +1,259 @@
+//! Rebuild-on-change support for `dora run --watch`.
+//!
+//! Watches the source tree of every node that has a `build` command. When a
+//! file changes, the daemon reruns the build command and restarts the node
+//! on success. Nodes whose executable was not modified by the build keep
+//! running, which avoids restarting all nodes that share a source tree. If
+//! the build fails, the old process keeps running.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    env::consts::EXE_EXTENSION,
+    path::{Component, Path, PathBuf},
+    sync::Arc,
+    time::{Duration, Instant, SystemTime},
+};
+
+use dora_core::{
+    build::{BuildInfo, run_build_command},
+    descriptor::{CoreNodeKind, ResolvedNode},
+    uhlc::HLC,
+};
+use dora_message::{
+    DataflowId,
+    common::{LogLevel, Timestamped},
+    descriptor::EnvValue,
+    id::NodeId,
+};
+use eyre::Context;
+use notify::{Config, Event as NotifyEvent, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use tokio_stream::wrappers::ReceiverStream;
+
+use crate::{Event, log::NodeLogger};
+
+/// Time to wait after a change before starting the build.
+///
+/// Saving a file often results in several file events, which should only
+/// trigger a single rebuild.
+const DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// Directories that contain build outputs or tool state, not sources.
+const IGNORED_DIRS: &[&str] = &["target", "build", "out", "node_modules", "__pycache__"];
+
+/// Build configuration of a watched node.
+#[derive(Debug, Clone)]
+pub struct WatchedNode {
+    build: String,
+    working_dir: PathBuf,
+    env: Option<BTreeMap<String, EnvValue>>,
+    /// Build output that is spawned as node, if it lies outside of the sources.
+    ///
+    /// The node is only restarted if the build modified this file.
+    executable: Option<PathBuf>,
+}
+
+/// An in-progress rebuild of a node.
+pub struct Rebuild {
+    started: Instant,
+    /// Sources changed again after the build started, so it needs to rerun.
+    rerun: bool,
+}
+
+impl Rebuild {
+    pub fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            rerun: false,
+        }
+    }
+
+    /// Records a source change that happened while this rebuild was running.
+    pub fn source_changed(&mut self) {
+        // changes during the debounce period are picked up by the pending build
+        if self.started.elapsed() >= DEBOUNCE {
+            self.rerun = true;
+        }
+    }
+
+    pub fn needs_rerun(&self) -> bool {
+        self.rerun
+    }
+}
+
+/// Collects the nodes that have a `build` command, together with the
+/// directory that their build command runs in.
+pub fn watched_nodes(
+    nodes: &BTreeMap<NodeId, ResolvedNode>,
+    base_working_dir: &Path,
+    local_build: Option<&BuildInfo>,
+) -> BTreeMap<NodeId, WatchedNode> {
+    nodes
+        .values()
+        .filter_map(|node| {
+            let CoreNodeKind::Custom(custom) = &node.kind else {
+                return None;
+            };
+            let build = custom.build.clone()?;
+            let working_dir = local_build
+                .and_then(|b| b.node_working_dirs.get(&node.id).cloned())
+                .unwrap_or_else(|| base_working_dir.to_owned());
+            let working_dir = dunce::canonicalize(&working_dir).unwrap_or(working_dir);
+            let executable = build_output(&custom.path, &working_dir);
+            Some((
+                node.id.clone(),
+                WatchedNode {
+                    build,
+                    working_dir,
+                    env: node.env.clone(),
+                    executable,
+                },
+            ))
+        })
+        .collect()
+}
+
+/// Starts watching the source trees of the given nodes.
+///
+/// Every change results in an [`Event::NodeSourceChanged`] on the returned
+/// stream. The returned watcher must be kept alive to keep watching.
+pub fn watch_node_sources(
+    dataflow_id: DataflowId,
+    nodes: BTreeMap<NodeId, WatchedNode>,
+    clock: Arc<HLC>,
+) -> eyre::Result<(RecommendedWatcher, ReceiverStream<Timestamped<Event>>)> {
+    let (tx, rx) = tokio::sync::mpsc::channel(100);
+    let roots: BTreeSet<PathBuf> = nodes.values().map(|n| n.working_dir.clone()).collect();
+
+    let notifier = move |event: notify::Result<NotifyEvent>| {
+        let Ok(NotifyEvent { paths, kind, .. }) = event else {
+            return;
+        };
+        if !matches!(
+            kind,
+            EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)
+        ) {
+            return;
+        }
+        for (node_id, node) in &nodes {
+            let changed = paths.iter().any(|path| {
+                path.strip_prefix(&node.working_dir)
+                    .is_ok_and(|relative| !is_ignored(relative))
+            });
+            if changed {
+                let event = Event::NodeSourceChanged {
+                    dataflow_id,
+                    node_id: node_id.clone(),
+                    node: node.clone(),
+                };
+                // a full channel means that a rebuild is already queued
+                let _ = tx.try_send(Timestamped {
+                    inner: event,
+                    timestamp: clock.new_timestamp(),
+                });
+            }
+        }
+    };
+
+    let mut watcher = RecommendedWatcher::new(notifier, Config::default())
+        .context("failed to create file watcher")?;
+    for root in &roots {
+        tracing::info!("watching `{}` for source changes", root.display());
+        watcher
+            .watch(root, RecursiveMode::Recursive)
+            .with_context(|| format!("failed to watch `{}`", root.display()))?;
+    }
+
+    Ok((watcher, ReceiverStream::new(rx)))
+}
+
+/// Reruns the build command of the given node.
+///
+/// The build output is forwarded to the node log. Returns whether the node
+/// needs to be restarted.
+pub async fn rebuild_node(
+    node: WatchedNode,
+    uv: bool,
+    mut logger: NodeLogger<'static>,
+) -> eyre::Result<bool> {
+    tokio::time::sleep(DEBOUNCE).await;
+    logger
+        .log(
+            LogLevel::Info,
+            Some("watch".into()),
+            format!("sources changed, running build command `{}`", node.build),
+        )
+        .await;
+
+    let executable = node.executable.clone();
+    let modified_before = executable.as_deref().and_then(modified);
+    let (stdout_tx, mut stdout) = tokio::sync::mpsc::channel(10);
+    let build = tokio::spawn(async move {
+        run_build_command(&node.build, &node.working_dir, uv, &node.env, stdout_tx).await
+    });
+    while let Some(line) = stdout.recv().await {
+        let line = line.unwrap_or_else(|err| format!("io err: {}", err.kind()));
+        logger.log(LogLevel::Info, Some("build".into()), line).await;
+    }
+    build.await.context("build task panicked")??;
+
+    let changed = match &executable {
+        Some(executable) => modified_before.is_none() || modified(executable) != modified_before,
+        None => true,
+    };
+    Ok(changed)
+}
+
+/// Resolves the node path to a file that is produced by the build command.
+///
+/// Returns `None` if the path is not a local file or if it is part of the
+/// watched sources, e.g. a Python script.
+fn build_output(path: &str, working_dir: &Path) -> Option<PathBuf> {
+    let path = Path::new(path);
+    let path = if path.extension().is_none() {
+        path.with_extension(EXE_EXTENSION)
+    } else {
+        path.to_owned()
+    };
+    let path = dunce::canonicalize(working_dir.join(path)).ok()?;
+    match path.strip_prefix(working_dir) {
+        Ok(relative) if !is_ignored(relative) => None,
+        _ => Some(path),
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    path.metadata().and_then(|m| m.modified()).ok()
+}
+
+fn is_ignored(relative: &Path) -> bool {
+    // log files, e.g. the redirected output of `dora run` itself
+    if relative.extension().is_some_and(|ext| ext == "log") {
+        return true;
+    }
+    relative.components().any(|component| match component {
+        Component::Normal(name) => {
+            let name = name.to_string_lossy();
+            name.starts_with('.') || IGNORED_DIRS.contains(&name.as_ref())
+        }
+        _ => false,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::is_ignored;
+
+    #[test]
+    fn ignores_build_outputs_and_hidden_files() {
+        assert!(!is_ignored(Path::new("src/main.rs")));
+        assert!(!is_ignored(Path::new("node/CMakeLists.txt")));
+        assert!(is_ignored(Path::new("target/debug/node")));
+        assert!(is_ignored(Path::new("node/build/node.o")));
+        assert!(is_ignored(Path::new("out/0192/log_node.txt")));
+        assert!(is_ignored(Path::new(".git/index")));
+        assert!(is_ignored(Path::new("src/.main.rs.swp")));
+        assert!(is_ignored(Path::new("run.log")));
+    }
+}
@@ -1,15 +1,53 @@
-use dora_cli::{build, run};
-use eyre::Context;
-use std::path::Path;
+use dora_cli::{Executable, NodeCommunicationKind, RunCommand, build};
+use eyre::{Context, bail};
+use std::{path::Path, process::Command};
 
 fn main() -> eyre::Result<()> {
+    // the dataflow is run once per transport, each in a separate process
+    if let Some(transport) = std::env::args().nth(1) {
+        let transport = match transport.as_str() {
+            "tcp" => NodeCommunicationKind::Tcp,
+            "unix" => NodeCommunicationKind::Unix,
+            "shmem" => NodeCommunicationKind::Shmem,
+            other => bail!("unknown node communication `{other}`"),
+        };
+        return run(transport);
+    }
+
     let root = Path::new(env!("CARGO_MANIFEST_DIR"));
     std::env::set_current_dir(root.join(file!()).parent().unwrap())
         .wrap_err("failed to set working dir")?;
 
     build("dataflow.yml".to_string(), None, None, false, true)?;
 
-    run("dataflow.yml".to_string(), false)?;
+    let transports = [
+        "tcp",
+        #[cfg(unix)]
+        "unix",
+        #[cfg(target_os = "linux")]
+        "shmem",
+    ];
+    let exe = std::env::current_exe().wrap_err("failed to get current executable")?;
+    for transport in transports {
+        println!("\n=== Node communication: {transport} ===\n");
+        let status = Command::new(&exe)
+            .arg(transport)
+            .status()
+            .wrap_err("failed to run benchmark")?;
+        if !status.success() {
+            bail!("benchmark with `{transport}` node communication failed");
+        }
+    }
 
     Ok(())
 }
+
+fn run(transport: NodeCommunicationKind) -> eyre::Result<()> {
+    let mut run = RunCommand::new("dataflow.yml".to_string());
+    run.node_communication = transport;
+    let rt = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .context("tokio runtime failed")?;
+    rt.block_on(run.execute())
+}
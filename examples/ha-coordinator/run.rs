@@ -0,0 +1,262 @@
+use dora_cli::session::DataflowSession;
+use dora_core::{
+    descriptor::{DescriptorExt, read_as_descriptor},
+    topics::dora_coordinator_port_rpc,
+};
+use dora_message::{
+    cli_to_coordinator::{CoordinatorControlClient, StartRequest},
+    tarpc::{self, client},
+};
+use dora_tracing::TracingBuilder;
+use eyre::{Context, bail};
+
+use std::{
+    net::{Ipv4Addr, SocketAddr},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+use tokio::process::{Child, Command};
+use uuid::Uuid;
+
+/// Daemon, control, and Raft ports of the three coordinators.
+const COORDINATORS: [(u16, u16, u16); 3] = [
+    (53300, 6100, 53400),
+    (53310, 6110, 53410),
+    (53320, 6120, 53420),
+];
+
+#[tokio::main]
+async fn main() -> eyre::Result<()> {
+    TracingBuilder::new("ha-coordinator-runner")
+        .with_stdout("debug", false)
+        .build()?;
+
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    std::env::set_current_dir(root.join(file!()).parent().unwrap())
+        .wrap_err("failed to set working dir")?;
+
+    let dora = build_cli(root).await?;
+    let dataflow = Path::new("dataflow.yml");
+    build_dataflow(&dora, dataflow).await?;
+
+    tracing::info!("spawning coordinator cluster");
+    let mut coordinators = Vec::new();
+    for index in 0..COORDINATORS.len() {
+        coordinators.push(Some(run_coordinator(&dora, index)?));
+    }
+
+    let daemon_addrs = COORDINATORS
+        .iter()
+        .map(|(port, _, _)| SocketAddr::from((Ipv4Addr::LOCALHOST, *port)).to_string())
+        .collect::<Vec<_>>()
+        .join(",");
+    let mut daemon = run_daemon(&dora, &daemon_addrs)?;
+
+    tracing::info!("waiting until the daemon is connected to the leader");
+    let (leader, client) = wait_for_leader(&coordinators).await?;
+    tracing::info!("coordinator {leader} is the leader");
+
+    tracing::info!("starting dataflow");
+    let uuid = start_dataflow(dataflow, &client).await?;
+    tracing::info!("started dataflow under ID `{uuid}`");
+    if !running_dataflows(&client).await?.contains(&uuid) {
+        bail!("dataflow `{uuid}` is not running");
+    }
+
+    // give the leader time to replicate its state
+    tokio::time::sleep(Duration::from_secs(2)).await;
+
+    tracing::info!("killing leader coordinator {leader}");
+    drop(client);
+    if let Some(mut coordinator) = coordinators[leader].take() {
+        coordinator.kill().await?;
+    }
+
+    tracing::info!("waiting for a new leader");
+    let (new_leader, client) = wait_for_leader(&coordinators).await?;
+    tracing::info!("coordinator {new_leader} is the new leader");
+    if !running_dataflows(&client).await?.contains(&uuid) {
+        bail!("dataflow `{uuid}` is not running after the leader change");
+    }
+
+    tracing::info!("stopping dataflow `{uuid}` through the new leader");
+    client
+        .stop(long_context(), uuid, None, false)
+        .await
+        .context("RPC transport error")?
+        .map_err(|e| eyre::eyre!(e))?;
+    let mut retries = 0;
+    while running_dataflows(&client).await?.contains(&uuid) {
+        if retries > 40 {
+            bail!("dataflow not stopped after {retries} retries");
+        }
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        retries += 1;
+    }
+
+    tracing::info!("destroying daemon and coordinator cluster");
+    client
+        .destroy(long_context())
+        .await
+        .context("RPC transport error")?
+        .map_err(|e| eyre::eyre!(e))?;
+    if !daemon.wait().await?.success() {
+        bail!("daemon failed");
+    }
+    for mut coordinator in coordinators.into_iter().flatten() {
+        coordinator.kill().await?;
+    }
+
+    tracing::info!("done");
+    Ok(())
+}
+
+fn long_context() -> tarpc::context::Context {
+    let mut ctx = tarpc::context::current();
+    ctx.deadline = std::time::Instant::now() + Duration::from_secs(600);
+    ctx
+}
+
+/// Waits until one of the remaining coordinators accepts control connections
+/// and has a connected daemon.
+async fn wait_for_leader(
+    coordinators: &[Option<Child>],
+) -> eyre::Result<(usize, CoordinatorControlClient)> {
+    for _ in 0..120 {
+        for (index, (_, control_port, _)) in COORDINATORS.iter().enumerate() {
+            if coordinators[index].is_none() {
+                continue;
+            }
+            let Ok(client) = connect(*control_port).await else {
+                continue;
+            };
+            let connected = client
+                .connected_machines(tarpc::context::current())
+                .await
+                .ok()
+                .and_then(|machines| machines.ok());
+            if connected.is_some_and(|machines| !machines.is_empty()) {
+                return Ok((index, client));
+            }
+        }
+        tokio::time::sleep(Duration::from_millis(500)).await;
+    }
+    bail!("no coordinator became leader with a connected daemon")
+}
+
+async fn connect(control_port: u16) -> eyre::Result<CoordinatorControlClient> {
+    let addr = (Ipv4Addr::LOCALHOST, dora_coordinator_port_rpc(control_port));
+    let transport =
+        tarpc::serde_transport::tcp::connect(addr, tarpc::tokio_serde::formats::Json::default)
+            .await?;
+    Ok(CoordinatorControlClient::new(client::Config::default(), transport).spawn())
+}
+
+async fn start_dataflow(dataflow: &Path, client: &CoordinatorControlClient) -> eyre::Result<Uuid> {
+    let dataflow_descriptor = read_as_descriptor(dataflow)
+        .await
+        .wrap_err("failed to read yaml dataflow")?;
+    let working_dir = dataflow
+        .canonicalize()
+        .context("failed to canonicalize dataflow path")?
+        .parent()
+        .ok_or_else(|| eyre::eyre!("dataflow path has no parent dir"))?
+        .to_owned();
+    dataflow_descriptor
+        .check(&working_dir)
+        .wrap_err("could not validate yaml")?;
+
+    let dataflow_session =
+        DataflowSession::read_session(dataflow).context("failed to read DataflowSession")?;
+
+    let uuid = client
+        .start(
+            tarpc::context::current(),
+            StartRequest {
+                dataflow_id: None,
+                build_id: dataflow_session.build_id,
+                session_id: dataflow_session.session_id,
+                dataflow: dataflow_descriptor,
+                local_working_dir: Some(working_dir),
+                name: None,
+                uv: false,
+                write_events_to: None,
+                hot_reload: false,
+            },
+        )
+        .await
+        .context("RPC transport error")?
+        .map_err(|e| eyre::eyre!(e))?;
+
+    client
+        .wait_for_spawn(long_context(), uuid)
+        .await
+        .context("RPC transport error")?
+        .map_err(|e| eyre::eyre!(e))?;
+
+    Ok(uuid)
+}
+
+async fn running_dataflows(client: &CoordinatorControlClient) -> eyre::Result<Vec<Uuid>> {
+    let list = client
+        .list(tarpc::context::current())
+        .await
+        .context("RPC transport error")?
+        .map_err(|e| eyre::eyre!(e))?;
+    Ok(list.get_active().into_iter().map(|d| d.uuid).collect())
+}
+
+/// Builds the CLI up front so that the spawned processes can be killed
+/// directly instead of through `cargo run`.
+async fn build_cli(root: &Path) -> eyre::Result<PathBuf> {
+    let cargo = std::env::var("CARGO").unwrap();
+    let mut cmd = Command::new(&cargo);
+    cmd.arg("build");
+    cmd.arg("--package").arg("dora-cli");
+    cmd.arg("--release");
+    if !cmd.status().await?.success() {
+        bail!("failed to build dora-cli");
+    };
+    Ok(root
+        .join("target")
+        .join("release")
+        .join(format!("dora{}", std::env::consts::EXE_SUFFIX)))
+}
+
+async fn build_dataflow(dora: &Path, dataflow: &Path) -> eyre::Result<()> {
+    let mut cmd = Command::new(dora);
+    cmd.arg("build").arg(dataflow);
+    if !cmd.status().await?.success() {
+        bail!("failed to build dataflow");
+    };
+    Ok(())
+}
+
+fn run_coordinator(dora: &Path, index: usize) -> eyre::Result<Child> {
+    let (port, control_port, raft_port) = COORDINATORS[index];
+    let mut cmd = Command::new(dora);
+    cmd.arg("coordinator")
+        .arg("--port")
+        .arg(port.to_string())
+        .arg("--control-port")
+        .arg(control_port.to_string())
+        .arg("--ha-addr")
+        .arg(SocketAddr::from((Ipv4Addr::LOCALHOST, raft_port)).to_string());
+    for (_, _, peer_port) in COORDINATORS {
+        if peer_port != raft_port {
+            cmd.arg("--ha-peer")
+                .arg(SocketAddr::from((Ipv4Addr::LOCALHOST, peer_port)).to_string());
+        }
+    }
+    cmd.kill_on_drop(true);
+    cmd.spawn().wrap_err("failed to spawn coordinator")
+}
+
+fn run_daemon(dora: &Path, coordinator_addrs: &str) -> eyre::Result<Child> {
+    let mut cmd = Command::new(dora);
+    cmd.arg("daemon")
+        .arg("--coordinator-addr")
+        .arg(coordinator_addrs);
+    cmd.kill_on_drop(true);
+    cmd.spawn().wrap_err("failed to spawn daemon")
+}
@@ -0,0 +1,520 @@
+use crate::futex;
+use eyre::{Context, bail};
+use shared_memory_extended::{Shmem, ShmemConf};
+use std::{
+    mem,
+    sync::{
+        Arc, LazyLock,
+        atomic::{AtomicU32, AtomicU64, Ordering, fence},
+    },
+    time::{Duration, Instant},
+};
+
+/// Marks an initialized region, also used as a version number of the layout.
+const MAGIC: u64 = u64::from_le_bytes(*b"dorashm1");
+
+/// How long a waiting receiver spins before it goes to sleep.
+///
+/// Spinning avoids the wake-up latency of the futex for messages that
+/// arrive shortly after each other, e.g. the reply to a request. It only
+/// helps if the other side can run in parallel, so we don't spin on
+/// single-core machines.
+static SPIN_DURATION: LazyLock<Duration> =
+    LazyLock::new(|| match std::thread::available_parallelism() {
+        Ok(cores) if cores.get() > 1 => Duration::from_micros(50),
+        _ => Duration::ZERO,
+    });
+
+/// Interval in which sleeping threads check whether the other side is still
+/// alive.
+const WAIT_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// Header at the start of the shared memory region.
+///
+/// The two data buffers follow directly after the header.
+#[repr(C, align(64))]
+struct Header {
+    magic: AtomicU64,
+    capacity: AtomicU64,
+    server_pid: AtomicU32,
+    client_pid: AtomicU32,
+    claimed: AtomicU32,
+    disconnected: AtomicU32,
+    /// Messages from client to server.
+    requests: Queue,
+    /// Messages from server to client.
+    replies: Queue,
+}
+
+/// Lock-free single-producer single-consumer byte queue.
+#[repr(C, align(64))]
+struct Queue {
+    /// Total number of bytes written, only modified by the writer.
+    write: CachePadded<AtomicU64>,
+    /// Total number of bytes read, only modified by the reader.
+    read: CachePadded<AtomicU64>,
+    /// Incremented after each write, readers wait on this futex.
+    written_seq: AtomicU32,
+    /// Incremented after each read, writers wait on this futex.
+    read_seq: AtomicU32,
+    readers_waiting: AtomicU32,
+    writers_waiting: AtomicU32,
+}
+
+#[repr(C, align(64))]
+struct CachePadded<T>(T);
+
+#[derive(Clone, Copy)]
+enum Direction {
+    Requests,
+    Replies,
+}
+
+struct Region {
+    memory: Shmem,
+    is_server: bool,
+    /// Whether this side takes part in the channel, i.e. whether dropping
+    /// the region disconnects the channel.
+    connected: bool,
+}
+
+// SAFETY: all shared state in the header is atomic and each data buffer is
+// only accessed by its single sender and single receiver
+unsafe impl Send for Region {}
+unsafe impl Sync for Region {}
+
+impl Region {
+    fn header(&self) -> &Header {
+        // SAFETY: the region is larger than the header and page-aligned
+        unsafe { &*(self.memory.as_ptr() as *const Header) }
+    }
+
+    fn capacity(&self) -> u64 {
+        self.header().capacity.load(Ordering::Relaxed)
+    }
+
+    fn queue(&self, direction: Direction) -> &Queue {
+        match direction {
+            Direction::Requests => &self.header().requests,
+            Direction::Replies => &self.header().replies,
+        }
+    }
+
+    fn buffer(&self, direction: Direction) -> *mut u8 {
+        let offset = match direction {
+            Direction::Requests => mem::size_of::<Header>(),
+            Direction::Replies => mem::size_of::<Header>() + self.capacity() as usize,
+        };
+        // SAFETY: the region has space for the header and both buffers
+        unsafe { self.memory.as_ptr().add(offset) }
+    }
+
+    fn is_disconnected(&self) -> bool {
+        self.header().disconnected.load(Ordering::Acquire) != 0
+    }
+
+    fn disconnect(&self) {
+        let header = self.header();
+        header.disconnected.store(1, Ordering::Release);
+        for queue in [&header.requests, &header.replies] {
+            for seq in [&queue.written_seq, &queue.read_seq] {
+                seq.fetch_add(1, Ordering::Release);
+                futex::wake_all(seq);
+            }
+        }
+    }
+
+    /// Checks whether the process on the other side of the channel still
+    /// exists.
+    fn peer_alive(&self) -> bool {
+        let header = self.header();
+        let pid = if self.is_server {
+            header.client_pid.load(Ordering::Acquire)
+        } else {
+            header.server_pid.load(Ordering::Acquire)
+        };
+        if pid == 0 {
+            // no client connected yet
+            return true;
+        }
+        // SAFETY: signal 0 only checks whether the process exists
+        let result = unsafe { libc::kill(pid as libc::pid_t, 0) };
+        result == 0 || std::io::Error::last_os_error().raw_os_error() != Some(libc::ESRCH)
+    }
+
+    /// Waits until `ready` returns `true`.
+    ///
+    /// Returns `false` if the channel was disconnected before.
+    fn wait(&self, seq: &AtomicU32, waiting: &AtomicU32, ready: impl Fn() -> bool) -> bool {
+        let start = Instant::now();
+        while start.elapsed() < *SPIN_DURATION {
+            if ready() {
+                return true;
+            }
+            for _ in 0..64 {
+                std::hint::spin_loop();
+            }
+        }
+        loop {
+            let current = seq.load(Ordering::Acquire);
+            waiting.fetch_add(1, Ordering::SeqCst);
+            // pairs with the fence in `notify`, so that either we see the
+            // new data or the other side sees that we're waiting
+            fence(Ordering::SeqCst);
+            if !ready() && !self.is_disconnected() {
+                futex::wait(seq, current, WAIT_TIMEOUT);
+            }
+            waiting.fetch_sub(1, Ordering::SeqCst);
+            if ready() {
+                return true;
+            }
+            if self.is_disconnected() || !self.peer_alive() {
+                return false;
+            }
+        }
+    }
+
+    fn notify(&self, seq: &AtomicU32, waiting: &AtomicU32) {
+        seq.fetch_add(1, Ordering::Release);
+        fence(Ordering::SeqCst);
+        if waiting.load(Ordering::Relaxed) > 0 {
+            futex::wake_all(seq);
+        }
+    }
+}
+
+impl Drop for Region {
+    fn drop(&mut self) {
+        if self.connected {
+            self.disconnect();
+        }
+    }
+}
+
+/// Bidirectional shared memory channel, see the [crate-level docs](crate).
+pub struct ShmemChannel {
+    sender: ShmemSender,
+    receiver: ShmemReceiver,
+}
+
+impl ShmemChannel {
+    /// Creates a new shared memory region as the server side of a channel.
+    ///
+    /// The `capacity` is the size of the queue for each direction. Larger
+    /// messages are supported, but the sender has to wait until the receiver
+    /// reads them.
+    pub fn create(capacity: usize) -> eyre::Result<Self> {
+        if capacity == 0 {
+            bail!("shared memory channel capacity must not be zero");
+        }
+        let memory = ShmemConf::new()
+            .size(mem::size_of::<Header>() + 2 * capacity)
+            .create()
+            .wrap_err("failed to create shared memory region")?;
+        let region = Region {
+            memory,
+            is_server: true,
+            connected: true,
+        };
+        let header = region.header();
+        header.capacity.store(capacity as u64, Ordering::Relaxed);
+        header
+            .server_pid
+            .store(std::process::id(), Ordering::Relaxed);
+        header.magic.store(MAGIC, Ordering::Release);
+
+        Ok(Self::new(region, Direction::Replies, Direction::Requests))
+    }
+
+    /// Opens the region with the given ID as the client side of a channel.
+    ///
+    /// Returns `None` if another client already opened the region.
+    pub fn connect(os_id: &str) -> eyre::Result<Option<Self>> {
+        let memory = ShmemConf::new()
+            .os_id(os_id)
+            .open()
+            .wrap_err_with(|| format!("failed to open shared memory region `{os_id}`"))?;
+        let mut region = Region {
+            memory,
+            is_server: false,
+            connected: false,
+        };
+        let header = region.header();
+        if region.memory.len() < mem::size_of::<Header>()
+            || header.magic.load(Ordering::Acquire) != MAGIC
+        {
+            bail!("shared memory region `{os_id}` is not a dora channel");
+        }
+        let capacity = region.capacity() as usize;
+        if region.memory.len() < mem::size_of::<Header>() + 2 * capacity {
+            bail!("shared memory region `{os_id}` is too small");
+        }
+        if header
+            .claimed
+            .compare_exchange(0, 1, Ordering::AcqRel, Ordering::Acquire)
+            .is_err()
+        {
+            return Ok(None);
+        }
+        header
+            .client_pid
+            .store(std::process::id(), Ordering::Release);
+        region.connected = true;
+
+        Ok(Some(Self::new(
+            region,
+            Direction::Requests,
+            Direction::Replies,
+        )))
+    }
+
+    fn new(region: Region, send: Direction, receive: Direction) -> Self {
+        let region = Arc::new(region);
+        Self {
+            sender: ShmemSender {
+                region: region.clone(),
+                direction: send,
+            },
+            receiver: ShmemReceiver {
+                region,
+                direction: receive,
+            },
+        }
+    }
+
+    /// The ID that clients use to open the region.
+    pub fn os_id(&self) -> &str {
+        self.sender.region.memory.get_os_id()
+    }
+
+    /// Sends a message, waiting for free space if the queue is full.
+    pub fn send(&mut self, message: &[u8]) -> eyre::Result<()> {
+        self.sender.send(message)
+    }
+
+    /// Receives the next message.
+    ///
+    /// Returns `None` if the other side disconnected.
+    pub fn receive(&mut self) -> eyre::Result<Option<Vec<u8>>> {
+        self.receiver.receive()
+    }
+
+    /// Splits the channel into its two directions, e.g. to use them from
+    /// different threads.
+    pub fn split(self) -> (ShmemSender, ShmemReceiver) {
+        (self.sender, self.receiver)
+    }
+
+    /// Returns a handle that can be used to close the channel from another
+    /// thread.
+    pub fn disconnect_handle(&self) -> DisconnectHandle {
+        DisconnectHandle(self.sender.region.clone())
+    }
+}
+
+/// Sending half of a [`ShmemChannel`].
+pub struct ShmemSender {
+    region: Arc<Region>,
+    direction: Direction,
+}
+
+impl ShmemSender {
+    /// Sends a message, waiting for free space if the queue is full.
+    pub fn send(&mut self, message: &[u8]) -> eyre::Result<()> {
+        let len = (message.len() as u64).to_le_bytes();
+        let mut parts = [&len[..], message];
+        let mut part = 0;
+        let region = &*self.region;
+        let queue = region.queue(self.direction);
+        let capacity = region.capacity();
+        while part < parts.len() {
+            if region.is_disconnected() {
+                bail!("shared memory channel disconnected");
+            }
+            let write = queue.write.0.load(Ordering::Relaxed);
+            let read = queue.read.0.load(Ordering::Acquire);
+            let free = capacity - (write - read);
+            if free == 0 {
+                let space_available = || queue.read.0.load(Ordering::Acquire) != read;
+                if !region.wait(&queue.read_seq, &queue.writers_waiting, space_available) {
+                    bail!("shared memory channel disconnected");
+                }
+                continue;
+            }
+            let mut written = 0;
+            while part < parts.len() && written < free {
+                let n = parts[part].len().min((free - written) as usize);
+                self.copy_in(write + written, &parts[part][..n]);
+                written += n as u64;
+                parts[part] = &parts[part][n..];
+                if parts[part].is_empty() {
+                    part += 1;
+                }
+            }
+            queue.write.0.store(write + written, Ordering::Release);
+            region.notify(&queue.written_seq, &queue.readers_waiting);
+        }
+        Ok(())
+    }
+
+    /// Sends the message only if it fits into the free space of the queue.
+    ///
+    /// Returns `false` without sending anything otherwise.
+    pub fn try_send(&mut self, message: &[u8]) -> eyre::Result<bool> {
+        let region = &*self.region;
+        if region.is_disconnected() {
+            bail!("shared memory channel disconnected");
+        }
+        let queue = region.queue(self.direction);
+        let write = queue.write.0.load(Ordering::Relaxed);
+        let read = queue.read.0.load(Ordering::Acquire);
+        let free = region.capacity() - (write - read);
+        let len = (message.len() as u64).to_le_bytes();
+        if free < (len.len() + message.len()) as u64 {
+            return Ok(false);
+        }
+        self.copy_in(write, &len);
+        self.copy_in(write + len.len() as u64, message);
+        queue.write.0.store(
+            write + (len.len() + message.len()) as u64,
+            Ordering::Release,
+        );
+        region.notify(&queue.written_seq, &queue.readers_waiting);
+        Ok(true)
+    }
+
+    /// Copies the data into the ring buffer, wrapping around at the end.
+    fn copy_in(&self, position: u64, data: &[u8]) {
+        let capacity = self.region.capacity();
+        let buffer = self.region.buffer(self.direction);
+        let start = (position % capacity) as usize;
+        let first = data.len().min(capacity as usize - start);
+        // SAFETY: the caller made sure that the target range is free, so the
+        // receiver doesn't access it concurrently
+        unsafe {
+            std::ptr::copy_nonoverlapping(data.as_ptr(), buffer.add(start), first);
+            std::ptr::copy_nonoverlapping(data.as_ptr().add(first), buffer, data.len() - first);
+        }
+    }
+}
+
+/// Receiving half of a [`ShmemChannel`].
+pub struct ShmemReceiver {
+    region: Arc<Region>,
+    direction: Direction,
+}
+
+impl ShmemReceiver {
+    /// Receives the next message.
+    ///
+    /// Returns `None` if the other side disconnected.
+    pub fn receive(&mut self) -> eyre::Result<Option<Vec<u8>>> {
+        let mut len = [0; 8];
+        if !self.read_exact(&mut len) {
+            return Ok(None);
+        }
+        let mut message = vec![0; u64::from_le_bytes(len) as usize];
+        if !self.read_exact(&mut message) {
+            bail!("shared memory channel disconnected while receiving a message");
+        }
+        Ok(Some(message))
+    }
+
+    /// Returns `false` if the channel was disconnected before the buffer was
+    /// filled.
+    fn read_exact(&mut self, buf: &mut [u8]) -> bool {
+        let region = &*self.region;
+        let queue = region.queue(self.direction);
+        let mut filled = 0;
+        while filled < buf.len() {
+            let read = queue.read.0.load(Ordering::Relaxed);
+            let write = queue.write.0.load(Ordering::Acquire);
+            let available = write - read;
+            if available == 0 {
+                let data_available = || queue.write.0.load(Ordering::Acquire) != write;
+                if !region.wait(&queue.written_seq, &queue.readers_waiting, data_available) {
+                    return false;
+                }
+                continue;
+            }
+            let n = (buf.len() - filled).min(available as usize);
+            self.copy_out(read, &mut buf[filled..][..n]);
+            filled += n;
+            queue.read.0.store(read + n as u64, Ordering::Release);
+            region.notify(&queue.read_seq, &queue.writers_waiting);
+        }
+        true
+    }
+
+    /// Copies data out of the ring buffer, wrapping around at the end.
+    fn copy_out(&self, position: u64, data: &mut [u8]) {
+        let capacity = self.region.capacity();
+        let buffer = self.region.buffer(self.direction);
+        let start = (position % capacity) as usize;
+        let first = data.len().min(capacity as usize - start);
+        // SAFETY: the caller made sure that the source range was written, so
+        // the sender doesn't access it concurrently
+        unsafe {
+            std::ptr::copy_nonoverlapping(buffer.add(start), data.as_mut_ptr(), first);
+            std::ptr::copy_nonoverlapping(buffer, data.as_mut_ptr().add(first), data.len() - first);
+        }
+    }
+}
+
+/// Closes a [`ShmemChannel`] from another thread.
+///
+/// Waiting senders and receivers on both sides return with an error or
+/// `None` respectively.
+pub struct DisconnectHandle(Arc<Region>);
+
+impl DisconnectHandle {
+    pub fn disconnect(&self) {
+        self.0.disconnect();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ShmemChannel;
+
+    #[test]
+    fn messages_larger_than_capacity() {
+        let mut server = ShmemChannel::create(64).unwrap();
+        let mut client = ShmemChannel::connect(server.os_id()).unwrap().unwrap();
+
+        let messages: Vec<Vec<u8>> = (0..100usize)
+            .map(|i| (0..i * 7).map(|b| b as u8).collect())
+            .collect();
+        let expected = messages.clone();
+        let sender = std::thread::spawn(move || {
+            for message in &messages {
+                client.send(message).unwrap();
+            }
+            client
+        });
+        for message in expected {
+            assert_eq!(server.receive().unwrap(), Some(message));
+        }
+        let mut client = sender.join().unwrap();
+
+        server.send(b"reply").unwrap();
+        assert_eq!(client.receive().unwrap(), Some(b"reply".to_vec()));
+    }
+
+    #[test]
+    fn only_one_client() {
+        let server = ShmemChannel::create(64).unwrap();
+        let _client = ShmemChannel::connect(server.os_id()).unwrap().unwrap();
+        assert!(ShmemChannel::connect(server.os_id()).unwrap().is_none());
+    }
+
+    #[test]
+    fn disconnect_wakes_receiver() {
+        let server = ShmemChannel::create(64).unwrap();
+        let mut client = ShmemChannel::connect(server.os_id()).unwrap().unwrap();
+        let receiver = std::thread::spawn(move || client.receive().unwrap());
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        server.disconnect_handle().disconnect();
+        assert_eq!(receiver.join().unwrap(), None);
+    }
+}
@@ -0,0 +1,37 @@
+//! Minimal wrappers around the Linux futex system call.
+//!
+//! The futexes live in memory that is shared between processes, so the
+//! `FUTEX_PRIVATE_FLAG` must not be used.
+
+use std::{sync::atomic::AtomicU32, time::Duration};
+
+/// Blocks until the futex is woken, as long as it still has the `expected`
+/// value.
+///
+/// Returns early on timeouts, signals, and if the value changed in the
+/// meantime, so callers need to check their wake-up condition again.
+pub fn wait(futex: &AtomicU32, expected: u32, timeout: Duration) {
+    let timeout = libc::timespec {
+        tv_sec: timeout.as_secs() as libc::time_t,
+        tv_nsec: timeout.subsec_nanos() as libc::c_long,
+    };
+    // SAFETY: the futex points to a valid `u32` and the timeout is a valid
+    // relative `timespec`
+    unsafe {
+        libc::syscall(
+            libc::SYS_futex,
+            futex.as_ptr(),
+            libc::FUTEX_WAIT,
+            expected,
+            &timeout as *const libc::timespec,
+        );
+    }
+}
+
+/// Wakes all threads that wait on the given futex.
+pub fn wake_all(futex: &AtomicU32) {
+    // SAFETY: the futex points to a valid `u32`
+    unsafe {
+        libc::syscall(libc::SYS_futex, futex.as_ptr(), libc::FUTEX_WAKE, i32::MAX);
+    }
+}
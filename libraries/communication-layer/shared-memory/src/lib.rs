@@ -0,0 +1,26 @@
+#![cfg_attr(docsrs, feature(doc_auto_cfg))]
+
+//! Request/reply channel between two processes of the same machine, based on
+//! shared memory.
+//!
+//! A [`ShmemChannel`] consists of a single shared memory region that contains
+//! two lock-free single-producer single-consumer byte queues, one for each
+//! direction. Messages are written directly into the queue of the receiver,
+//! so sending a message requires no system call as long as the receiver is
+//! awake. Waiting receivers spin for a short time before they go to sleep on
+//! a futex.
+//!
+//! The region is created by the server side of the channel. The client opens
+//! it by its [`os_id`](ShmemChannel::os_id). Each region can only be opened by
+//! a single client.
+//!
+//! Futexes are only available on Linux, so this crate is empty on other
+//! platforms.
+
+#[cfg(target_os = "linux")]
+pub use channel::{DisconnectHandle, ShmemChannel, ShmemReceiver, ShmemSender};
+
+#[cfg(target_os = "linux")]
+mod channel;
+#[cfg(target_os = "linux")]
+mod futex;
@@ -1,3 +1,4 @@
+pub use build_command::run_build_command;
 pub use git::GitManager;
 pub use logger::{BuildLogger, LogLevelOrStdout, TracingBuildLogger};
 
@@ -12,7 +13,6 @@ use dora_message::{
 };
 use eyre::Context;
 
-use build_command::run_build_command;
 use git::GitFolder;
 
 mod build_command;
@@ -0,0 +1,125 @@
+//! Compression of output messages that are sent to other machines.
+//!
+//! The sending daemon compresses the data of outputs that have a
+//! [`compression`](dora_message::descriptor::Node::compression) setting and
+//! records the codec in the [`COMPRESSION_PARAMETER`] of the message metadata.
+//! Receivers decompress the data and remove the parameter again.
+
+use dora_message::{
+    config::Compression,
+    metadata::{COMPRESSION_PARAMETER, Metadata, Parameter},
+};
+use eyre::{Context, eyre};
+
+/// Compresses the given message data and marks the metadata accordingly.
+///
+/// Returns `None` if the data is left uncompressed because compression
+/// doesn't reduce its size. The metadata is only modified if the data is
+/// compressed.
+pub fn compress(
+    compression: Compression,
+    metadata: &mut Metadata,
+    data: &[u8],
+) -> eyre::Result<Option<Vec<u8>>> {
+    if data.is_empty() {
+        return Ok(None);
+    }
+    let compressed = match compression {
+        Compression::Lz4 => lz4_flex::compress_prepend_size(data),
+        Compression::Zstd(level) => {
+            zstd::bulk::compress(data, level).wrap_err("failed to compress message with zstd")?
+        }
+    };
+    if compressed.len() >= data.len() {
+        return Ok(None);
+    }
+    metadata.parameters.insert(
+        COMPRESSION_PARAMETER.to_owned(),
+        Parameter::String(compression.to_string()),
+    );
+    Ok(Some(compressed))
+}
+
+/// Returns the codec that the message data was compressed with, if any.
+pub fn compression_of(metadata: &Metadata) -> eyre::Result<Option<Compression>> {
+    match metadata.get(COMPRESSION_PARAMETER) {
+        None => Ok(None),
+        Some(Parameter::String(codec)) => codec
+            .parse::<Compression>()
+            .map(Some)
+            .map_err(|err| eyre!(err)),
+        Some(other) => Err(eyre!(
+            "invalid `{COMPRESSION_PARAMETER}` metadata parameter: {other:?}"
+        )),
+    }
+}
+
+/// Decompresses the data of a message that was compressed through [`compress`].
+///
+/// Returns `None` if the message is not compressed. Otherwise, the
+/// compression parameter is removed from the metadata.
+pub fn decompress(metadata: &mut Metadata, data: &[u8]) -> eyre::Result<Option<Vec<u8>>> {
+    let Some(compression) = compression_of(metadata)? else {
+        return Ok(None);
+    };
+    let decompressed = match compression {
+        Compression::Lz4 => lz4_flex::decompress_size_prepended(data)
+            .wrap_err("failed to decompress lz4 message")?,
+        Compression::Zstd(_) => {
+            zstd::decode_all(data).wrap_err("failed to decompress zstd message")?
+        }
+    };
+    metadata.parameters.remove(COMPRESSION_PARAMETER);
+    Ok(Some(decompressed))
+}
+
+#[cfg(test)]
+mod tests {
+    use dora_message::{
+        config::Compression,
+        metadata::{COMPRESSION_PARAMETER, Metadata},
+    };
+
+    use super::{compress, compression_of, decompress};
+    use crate::metadata::ArrowTypeInfoExt;
+
+    fn metadata() -> Metadata {
+        let clock = crate::uhlc::HLC::default();
+        Metadata::new(
+            clock.new_timestamp(),
+            dora_message::metadata::ArrowTypeInfo::empty(),
+        )
+    }
+
+    #[test]
+    fn roundtrip() {
+        let data: Vec<u8> = (0..4096u32).map(|i| (i % 16) as u8).collect();
+        for compression in [Compression::Lz4, Compression::Zstd(3)] {
+            let mut metadata = metadata();
+            let compressed = compress(compression, &mut metadata, &data)
+                .unwrap()
+                .expect("repetitive data should be compressed");
+            assert!(compressed.len() < data.len());
+            assert_eq!(compression_of(&metadata).unwrap(), Some(compression));
+
+            let decompressed = decompress(&mut metadata, &compressed).unwrap().unwrap();
+            assert_eq!(decompressed, data);
+            assert!(metadata.get(COMPRESSION_PARAMETER).is_none());
+        }
+    }
+
+    #[test]
+    fn incompressible_data_is_sent_as_is() {
+        let mut metadata = metadata();
+        assert_eq!(
+            compress(Compression::Lz4, &mut metadata, &[1, 2, 3]).unwrap(),
+            None
+        );
+        assert_eq!(
+            compress(Compression::Zstd(3), &mut metadata, &[]).unwrap(),
+            None
+        );
+        assert!(metadata.get(COMPRESSION_PARAMETER).is_none());
+        assert_eq!(decompress(&mut metadata, &[1, 2, 3]).unwrap(), None);
+    }
+}
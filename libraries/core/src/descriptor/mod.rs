@@ -1,7 +1,8 @@
 use dora_message::{
-    config::{Input, InputMapping, NodeRunConfig},
+    config::{Input, InputMapping, NodeRunConfig, UserInputMapping},
     descriptor::{GitRepoRev, NodeSource},
     id::{DataId, NodeId, OperatorId},
+    service::ServiceCall,
 };
 use eyre::{Context, OptionExt, Result, bail};
 use std::{
@@ -48,8 +49,22 @@ impl DescriptorExt for Descriptor {
             })
             .collect();
 
+        // service calls, grouped by the server node
+        let mut service_clients: HashMap<NodeId, Vec<(NodeId, ServiceCall)>> = HashMap::new();
+        for node in &self.nodes {
+            for call in &node.calls {
+                service_clients
+                    .entry(call.server.clone())
+                    .or_default()
+                    .push((node.id.clone(), call.clone()));
+            }
+        }
+
         let mut resolved = BTreeMap::new();
         for mut node in self.nodes.clone() {
+            let clients = service_clients.remove(&node.id).unwrap_or_default();
+            add_service_edges(&mut node, &clients);
+
             // adjust input mappings
             let mut node_kind = node_kind_mut(&mut node)?;
             let input_mappings: Vec<_> = match &mut node_kind {
@@ -119,6 +134,15 @@ impl DescriptorExt for Descriptor {
                     description: node.description,
                     env,
                     deploy: node.deploy,
+                    replicas: node.replicas,
+                    depends_on: node.depends_on,
+                    startup_timeout: node.startup_timeout,
+                    stop_timeout: node.stop_timeout,
+                    liveness: node.liveness,
+                    resources: node.resources,
+                    scheduling: node.scheduling,
+                    sandbox: node.sandbox,
+                    failover: node.failover,
                     kind,
                 },
             );
@@ -161,6 +185,34 @@ pub async fn read_as_descriptor(path: &Path) -> eyre::Result<Descriptor> {
     Descriptor::parse(buf)
 }
 
+/// Adds the hidden inputs and outputs that carry the requests and replies of services.
+fn add_service_edges(node: &mut Node, clients: &[(NodeId, ServiceCall)]) {
+    let hidden_input = |source: &NodeId, output: DataId| Input {
+        mapping: InputMapping::User(UserInputMapping {
+            source: source.clone(),
+            output,
+        }),
+        queue_size: None,
+        max_rate: None,
+        every: None,
+        filter: None,
+    };
+    for call in &node.calls {
+        node.outputs.insert(call.request_output());
+        node.inputs.insert(
+            call.reply_input(),
+            hidden_input(&call.server, call.reply_output(&node.id)),
+        );
+    }
+    for (client, call) in clients {
+        node.outputs.insert(call.reply_output(client));
+        node.inputs.insert(
+            call.request_input(client),
+            hidden_input(client, call.request_output()),
+        );
+    }
+}
+
 fn node_kind_mut(node: &mut Node) -> eyre::Result<NodeKindMut<'_>> {
     match node.kind()? {
         NodeKind::Standard(_) => {
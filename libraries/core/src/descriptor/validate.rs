@@ -6,14 +6,23 @@ use crate::{
 
 use dora_message::{
     config::{Input, InputMapping, UserInputMapping},
-    descriptor::{CoreNodeKind, DYNAMIC_SOURCE, OperatorSource, ResolvedNode, SHELL_SOURCE},
+    descriptor::{
+        CoreNodeKind, DYNAMIC_SOURCE, Distribution, OperatorSource, Readiness, ResolvedNode,
+        SHELL_SOURCE,
+    },
     id::{DataId, NodeId, OperatorId},
+    service,
 };
 use eyre::{Context, bail, eyre};
-use std::{collections::BTreeMap, path::Path, process::Command, time::Duration};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::Path,
+    process::Command,
+    time::Duration,
+};
 use tracing::info;
 
-use super::{Descriptor, DescriptorExt, resolve_path};
+use super::{Descriptor, DescriptorExt, Node, resolve_path};
 const VERSION: &str = env!("CARGO_PKG_VERSION");
 
 pub fn check_dataflow(
@@ -121,6 +130,10 @@ pub fn check_dataflow(
         match &node.kind {
             descriptor::CoreNodeKind::Custom(custom_node) => {
                 for (input_id, input) in &custom_node.run_config.inputs {
+                    if service::is_service_id(input_id) {
+                        // checked by `check_services`
+                        continue;
+                    }
                     if let Err(err) = check_input(input, &nodes, &format!("{}/{input_id}", node.id))
                     {
                         errors.push(format!("{err}"));
@@ -153,6 +166,80 @@ pub fn check_dataflow(
         }
     }
 
+    // Check that replicated nodes can be spawned multiple times
+    for node in nodes.values() {
+        if let Err(err) = check_replicas(node, &nodes) {
+            errors.push(format!("node `{}`: {err}", node.id));
+        }
+    }
+
+    // Check that startup dependencies exist and can be reached
+    for node in nodes.values() {
+        if let Err(err) = check_dependencies(node, &nodes) {
+            errors.push(format!("node `{}`: {err}", node.id));
+        }
+    }
+    if let Err(err) = check_dependency_cycles(&nodes) {
+        errors.push(format!("{err}"));
+    }
+    if let Err(err) = check_zenoh(dataflow, &nodes) {
+        errors.push(format!("{err}"));
+    }
+    for node in &dataflow.nodes {
+        for output in node.compression.keys() {
+            let exists = nodes
+                .get(&node.id)
+                .is_some_and(|resolved| has_output(resolved, output));
+            if !exists {
+                errors.push(format!(
+                    "node `{}`: `compression` refers to unknown output `{output}`",
+                    node.id
+                ));
+            }
+        }
+    }
+
+    for node in nodes.values() {
+        if node.stop_timeout.is_some_and(|timeout| timeout.is_zero()) {
+            errors.push(format!(
+                "node `{}`: `stop_timeout` must not be zero",
+                node.id
+            ));
+        }
+        if node
+            .liveness
+            .as_ref()
+            .is_some_and(|liveness| liveness.timeout.is_zero())
+        {
+            errors.push(format!(
+                "node `{}`: liveness `timeout` must not be zero",
+                node.id
+            ));
+        }
+        if let Err(err) = check_resources(node) {
+            errors.push(format!("node `{}`: {err}", node.id));
+        }
+        if let Err(err) = check_scheduling(node) {
+            errors.push(format!("node `{}`: {err}", node.id));
+        }
+        if let Err(err) = check_sandbox(node) {
+            errors.push(format!("node `{}`: {err}", node.id));
+        }
+        if let Err(err) = check_deploy(node) {
+            errors.push(format!("node `{}`: {err}", node.id));
+        }
+        if let Err(err) = check_failover(node) {
+            errors.push(format!("node `{}`: {err}", node.id));
+        }
+    }
+
+    // Check that called services exist
+    for node in &dataflow.nodes {
+        if let Err(err) = check_services(node, dataflow) {
+            errors.push(format!("node `{}`: {err}", node.id));
+        }
+    }
+
     if has_python_operator {
         if let Err(err) = check_python_runtime() {
             errors.push(format!("{err}"));
@@ -210,13 +297,375 @@ impl ResolvedNodeExt for ResolvedNode {
     }
 }
 
+fn check_replicas(node: &ResolvedNode, nodes: &BTreeMap<NodeId, ResolvedNode>) -> eyre::Result<()> {
+    let Some(replicas) = &node.replicas else {
+        return Ok(());
+    };
+    if replicas.count == 0 {
+        bail!("`replicas` must be at least 1");
+    }
+    if let CoreNodeKind::Custom(custom) = &node.kind {
+        if custom.path == DYNAMIC_SOURCE {
+            bail!("dynamic nodes cannot be replicated");
+        }
+    }
+    if let Distribution::Key(key) = &replicas.distribution {
+        if key.is_empty() {
+            bail!("`replicas.distribution.key` must not be empty");
+        }
+    }
+    for instance_id in replicas.instance_ids(&node.id) {
+        if nodes.contains_key(&instance_id) {
+            bail!("replica ID `{instance_id}` conflicts with an existing node ID");
+        }
+    }
+    Ok(())
+}
+
+fn check_resources(node: &ResolvedNode) -> eyre::Result<()> {
+    let Some(resources) = &node.resources else {
+        return Ok(());
+    };
+    if let CoreNodeKind::Custom(custom) = &node.kind {
+        if custom.path == DYNAMIC_SOURCE {
+            bail!("resource limits are not supported for dynamic nodes");
+        }
+    }
+    if resources
+        .cpu
+        .is_some_and(|cpu| !cpu.is_finite() || cpu <= 0.0)
+    {
+        bail!("`resources.cpu` must be a positive number of CPU cores");
+    }
+    if resources.memory == Some(0) {
+        bail!("`resources.memory` must not be zero");
+    }
+    if resources.pids == Some(0) {
+        bail!("`resources.pids` must not be zero");
+    }
+    if resources
+        .io_weight
+        .is_some_and(|weight| !(1..=10000).contains(&weight))
+    {
+        bail!("`resources.io_weight` must be between 1 and 10000");
+    }
+    Ok(())
+}
+
+fn check_scheduling(node: &ResolvedNode) -> eyre::Result<()> {
+    let Some(scheduling) = &node.scheduling else {
+        return Ok(());
+    };
+    if let CoreNodeKind::Custom(custom) = &node.kind {
+        if custom.path == DYNAMIC_SOURCE {
+            bail!("`scheduling` is not supported for dynamic nodes");
+        }
+    }
+    let realtime = scheduling.policy.is_some_and(|policy| policy.is_realtime());
+    match scheduling.priority {
+        None if realtime => bail!("`scheduling.priority` is required for real-time policies"),
+        Some(priority) if !realtime => {
+            bail!(
+                "`scheduling.priority` ({priority}) is only supported for the `fifo` and `rr` policies"
+            )
+        }
+        Some(priority) if !(1..=99).contains(&priority) => {
+            bail!("`scheduling.priority` must be between 1 and 99")
+        }
+        _ => {}
+    }
+    if let Some(nice) = scheduling.nice {
+        if realtime {
+            bail!("`scheduling.nice` has no effect with real-time policies");
+        }
+        if !(-20..=19).contains(&nice) {
+            bail!("`scheduling.nice` must be between -20 and 19");
+        }
+    }
+    Ok(())
+}
+
+fn check_sandbox(node: &ResolvedNode) -> eyre::Result<()> {
+    let Some(sandbox) = &node.sandbox else {
+        return Ok(());
+    };
+    if let CoreNodeKind::Custom(custom) = &node.kind {
+        if custom.path == DYNAMIC_SOURCE {
+            bail!("`sandbox` is not supported for dynamic nodes");
+        }
+    }
+    if sandbox
+        .writable_paths
+        .iter()
+        .any(|path| path.as_os_str().is_empty())
+    {
+        bail!("`sandbox.writable_paths` must not contain empty paths");
+    }
+    if sandbox
+        .user
+        .as_deref()
+        .is_some_and(|user| user.trim().is_empty())
+    {
+        bail!("`sandbox.user` must not be empty");
+    }
+    Ok(())
+}
+
+fn check_deploy(node: &ResolvedNode) -> eyre::Result<()> {
+    let Some(deploy) = &node.deploy else {
+        return Ok(());
+    };
+    if deploy.selector.keys().any(|label| label.trim().is_empty()) {
+        bail!("`_unstable_deploy.selector` must not contain empty labels");
+    }
+    if let Some(requires) = &deploy.requires {
+        if requires
+            .cpus
+            .is_some_and(|cpus| !(cpus.is_finite() && cpus > 0.0))
+        {
+            bail!("`_unstable_deploy.requires.cpus` must be a positive number of CPU cores");
+        }
+        if requires.memory == Some(0) {
+            bail!("`_unstable_deploy.requires.memory` must not be zero");
+        }
+    }
+    Ok(())
+}
+
+fn check_failover(node: &ResolvedNode) -> eyre::Result<()> {
+    if !node.failover {
+        return Ok(());
+    }
+    match &node.deploy {
+        Some(deploy) if deploy.machine.is_some() => {
+            bail!("`failover` nodes must not be pinned to a `machine`")
+        }
+        Some(deploy) if deploy.label_based() => {}
+        _ => bail!(
+            "`failover` nodes must be placed by `_unstable_deploy.selector` or \
+            `_unstable_deploy.requires`"
+        ),
+    }
+    if node.replicas.is_some() {
+        bail!("`failover` is not supported for replicated nodes");
+    }
+    if node.has_git_source() {
+        bail!("`failover` is not supported for nodes with a git source");
+    }
+    if let CoreNodeKind::Custom(custom) = &node.kind {
+        if custom.path == DYNAMIC_SOURCE {
+            bail!("`failover` is not supported for dynamic nodes");
+        }
+    }
+    Ok(())
+}
+
+fn check_dependencies(
+    node: &ResolvedNode,
+    nodes: &BTreeMap<NodeId, ResolvedNode>,
+) -> eyre::Result<()> {
+    if node.depends_on.is_empty() {
+        return Ok(());
+    }
+    if is_dynamic(node) {
+        bail!("`depends_on` is not supported for dynamic nodes");
+    }
+    if node.replicas.is_some() {
+        bail!("`depends_on` is not supported for replicated nodes");
+    }
+    for (dependency_id, readiness) in &node.depends_on {
+        if dependency_id == &node.id {
+            bail!("node cannot depend on itself");
+        }
+        let dependency = nodes
+            .get(dependency_id)
+            .ok_or_else(|| eyre!("dependency `{dependency_id}` does not exist"))?;
+        if dependency.replicas.is_some() {
+            bail!("dependency `{dependency_id}` is replicated, which is not supported");
+        }
+        match (readiness, &dependency.kind) {
+            (Readiness::Subscribed, _) => {}
+            (Readiness::Ready, CoreNodeKind::Custom(_)) => {}
+            (Readiness::Ready, CoreNodeKind::Runtime(_)) => {
+                bail!(
+                    "dependency `{dependency_id}` runs operators, which cannot be marked as ready"
+                )
+            }
+            (Readiness::Output(output), _) => {
+                if !has_output(dependency, output) {
+                    bail!("dependency `{dependency_id}` has no output `{output}`");
+                }
+            }
+            (Readiness::Exec(command), _) => {
+                if command.trim().is_empty() {
+                    bail!("exec probe for dependency `{dependency_id}` is empty");
+                }
+                if is_dynamic(dependency) {
+                    bail!("exec probes are not supported for dynamic node `{dependency_id}`");
+                }
+            }
+        }
+    }
+    if node
+        .startup_timeout
+        .is_some_and(|timeout| timeout.is_zero())
+    {
+        bail!("`startup_timeout` must not be zero");
+    }
+    Ok(())
+}
+
+/// Checks whether the node has the given output, which is prefixed with the
+/// operator ID for runtime nodes.
+fn has_output(node: &ResolvedNode, output: &str) -> bool {
+    match &node.kind {
+        CoreNodeKind::Custom(custom) => custom
+            .run_config
+            .outputs
+            .contains(&DataId::from(output.to_owned())),
+        CoreNodeKind::Runtime(runtime) => {
+            output.split_once('/').is_some_and(|(operator_id, output)| {
+                runtime.operators.iter().any(|o| {
+                    o.id.as_ref() == operator_id
+                        && o.config.outputs.contains(&DataId::from(output.to_owned()))
+                })
+            })
+        }
+    }
+}
+
+fn check_zenoh(dataflow: &Descriptor, nodes: &BTreeMap<NodeId, ResolvedNode>) -> eyre::Result<()> {
+    let Some(zenoh) = dataflow.communication.remote.zenoh() else {
+        return Ok(());
+    };
+    if let Some(prefix) = &zenoh.prefix {
+        if prefix.is_empty()
+            || prefix.starts_with('/')
+            || prefix.ends_with('/')
+            || prefix.contains(['*', '$', '?', '#'])
+        {
+            bail!(
+                "zenoh `prefix` must be a key expression without wildcards and without \
+                leading or trailing `/`, got `{prefix}`"
+            );
+        }
+    }
+    for output in zenoh.priorities.keys() {
+        let exists = output.split_once('/').is_some_and(|(node_id, output_id)| {
+            nodes
+                .get(&NodeId::from(node_id.to_owned()))
+                .is_some_and(|node| has_output(node, output_id))
+        });
+        if !exists {
+            bail!("zenoh `priorities` refer to unknown output `{output}`");
+        }
+    }
+    Ok(())
+}
+
+fn check_dependency_cycles(nodes: &BTreeMap<NodeId, ResolvedNode>) -> eyre::Result<()> {
+    fn visit<'a>(
+        node_id: &'a NodeId,
+        nodes: &'a BTreeMap<NodeId, ResolvedNode>,
+        path: &mut Vec<&'a NodeId>,
+        done: &mut BTreeSet<&'a NodeId>,
+    ) -> eyre::Result<()> {
+        if done.contains(node_id) {
+            return Ok(());
+        }
+        if let Some(start) = path.iter().position(|n| *n == node_id) {
+            let cycle: Vec<_> = path[start..].iter().map(|n| format!("`{n}`")).collect();
+            bail!(
+                "`depends_on` contains a cycle: {} -> `{node_id}`",
+                cycle.join(" -> ")
+            );
+        }
+        path.push(node_id);
+        for dependency in nodes
+            .get(node_id)
+            .into_iter()
+            .flat_map(|n| n.depends_on.keys())
+        {
+            visit(dependency, nodes, path, done)?;
+        }
+        path.pop();
+        done.insert(node_id);
+        Ok(())
+    }
+
+    let mut done = BTreeSet::new();
+    for node_id in nodes.keys() {
+        visit(node_id, nodes, &mut Vec::new(), &mut done)?;
+    }
+    Ok(())
+}
+
+fn is_dynamic(node: &ResolvedNode) -> bool {
+    node.kind
+        .as_custom()
+        .is_some_and(|custom| custom.path == DYNAMIC_SOURCE)
+}
+
+fn check_services(node: &Node, dataflow: &Descriptor) -> eyre::Result<()> {
+    if (!node.services.is_empty() || !node.actions.is_empty() || !node.calls.is_empty())
+        && node.path.is_none()
+    {
+        bail!("`services`, `actions` and `calls` are only supported for nodes with a `path`");
+    }
+    let reserved = node
+        .outputs
+        .iter()
+        .chain(node.inputs.keys())
+        .chain(&node.services)
+        .chain(&node.actions)
+        .find(|id| service::is_service_id(id));
+    if let Some(id) = reserved {
+        bail!(
+            "ID `{id}` is reserved, IDs must not start with `{}` or `{}`",
+            service::REQUEST_PREFIX,
+            service::REPLY_PREFIX
+        );
+    }
+    if let Some(name) = node.services.intersection(&node.actions).next() {
+        bail!("`{name}` is listed both as service and as action");
+    }
+    for call in &node.calls {
+        let server = dataflow
+            .nodes
+            .iter()
+            .find(|n| n.id == call.server)
+            .ok_or_else(|| eyre!("called node `{}` does not exist", call.server))?;
+        if !server.services.contains(&call.service) && !server.actions.contains(&call.service) {
+            bail!(
+                "node `{}` does not provide service or action `{}`",
+                call.server,
+                call.service
+            );
+        }
+        if call.server == node.id {
+            bail!(
+                "node cannot call its own service or action `{}`",
+                call.service
+            );
+        }
+    }
+    Ok(())
+}
+
 fn check_input(
     input: &Input,
     nodes: &BTreeMap<NodeId, super::ResolvedNode>,
     input_id_str: &str,
 ) -> Result<(), eyre::ErrReport> {
     match &input.mapping {
-        InputMapping::Timer { interval: _ } => {}
+        InputMapping::Timer(_) => {
+            if input.has_edge_options() {
+                bail!(
+                    "`max_rate`, `every`, and `filter` are not supported for \
+                    timer input `{input_id_str}`",
+                );
+            }
+        }
         InputMapping::User(UserInputMapping { source, output }) => {
             let source_node = nodes.values().find(|n| &n.id == source).ok_or_else(|| {
                 eyre!("source node `{source}` mapped to input `{input_id_str}` does not exist",)
@@ -398,4 +847,212 @@ mod tests {
             .expect("GET fallback should mark URL as reachable");
         handle.join().expect("server thread panicked");
     }
+
+    #[test]
+    fn services_are_resolved_into_hidden_edges() {
+        use crate::descriptor::{CoreNodeKind, Descriptor, DescriptorExt};
+        use dora_message::id::{DataId, NodeId};
+
+        let yaml = r#"
+nodes:
+  - id: planner
+    path: dynamic
+    services: [plan_path]
+  - id: robot
+    path: dynamic
+    calls: [planner/plan_path]
+"#;
+        let descriptor = Descriptor::parse(yaml.as_bytes().to_vec()).unwrap();
+        descriptor.check(std::path::Path::new(".")).unwrap();
+
+        let nodes = descriptor.resolve_aliases_and_set_defaults().unwrap();
+        let CoreNodeKind::Custom(planner) = &nodes[&NodeId::from("planner".to_string())].kind
+        else {
+            panic!("expected custom node");
+        };
+        let request = &planner.run_config.inputs[&DataId::from("__request/robot/plan_path")];
+        assert_eq!(
+            request.mapping.to_string(),
+            "robot/__request/planner/plan_path"
+        );
+        assert!(
+            planner
+                .run_config
+                .outputs
+                .contains(&DataId::from("__reply/robot/plan_path"))
+        );
+
+        let unknown = yaml.replace("planner/plan_path", "planner/plan_route");
+        let descriptor = Descriptor::parse(unknown.into_bytes()).unwrap();
+        let err = descriptor.check(std::path::Path::new(".")).unwrap_err();
+        assert!(format!("{err:?}").contains("does not provide service or action `plan_route`"));
+    }
+
+    #[test]
+    fn actions_can_be_called() {
+        use crate::descriptor::{Descriptor, DescriptorExt};
+
+        let yaml = r#"
+nodes:
+  - id: navigator
+    path: dynamic
+    actions: [navigate_to]
+  - id: robot
+    path: dynamic
+    calls: [navigator/navigate_to]
+"#;
+        let descriptor = Descriptor::parse(yaml.as_bytes().to_vec()).unwrap();
+        descriptor.check(std::path::Path::new(".")).unwrap();
+
+        let overlapping = yaml.replace(
+            "actions: [navigate_to]",
+            "actions: [navigate_to]\n    services: [navigate_to]",
+        );
+        let descriptor = Descriptor::parse(overlapping.into_bytes()).unwrap();
+        let err = descriptor.check(std::path::Path::new(".")).unwrap_err();
+        assert!(format!("{err:?}").contains("listed both as service and as action"));
+    }
+
+    #[test]
+    fn startup_dependencies_are_validated() {
+        use crate::descriptor::{Descriptor, DescriptorExt};
+        use dora_message::{
+            descriptor::Readiness,
+            id::{DataId, NodeId},
+        };
+
+        let yaml = r#"
+nodes:
+  - id: driver
+    path: dynamic
+    outputs: [status]
+  - id: camera
+    path: dynamic
+  - id: planner
+    path: shell
+    args: ./planner
+    depends_on:
+      driver:
+        output: status
+      camera: ready
+    startup_timeout: 500ms
+"#;
+        let descriptor = Descriptor::parse(yaml.as_bytes().to_vec()).unwrap();
+        descriptor.check(std::path::Path::new(".")).unwrap();
+        let nodes = descriptor.resolve_aliases_and_set_defaults().unwrap();
+        let planner = &nodes[&NodeId::from("planner".to_string())];
+        assert_eq!(
+            planner.depends_on[&NodeId::from("driver".to_string())],
+            Readiness::Output(DataId::from("status".to_string()))
+        );
+        assert_eq!(
+            planner.depends_on[&NodeId::from("camera".to_string())],
+            Readiness::Ready
+        );
+        assert_eq!(
+            planner.startup_timeout,
+            Some(std::time::Duration::from_millis(500))
+        );
+
+        let check = |yaml: String| {
+            let descriptor = Descriptor::parse(yaml.into_bytes()).unwrap();
+            let err = descriptor.check(std::path::Path::new(".")).unwrap_err();
+            format!("{err:?}")
+        };
+        let unknown_output = yaml.replace("output: status", "output: pose");
+        assert!(check(unknown_output).contains("dependency `driver` has no output `pose`"));
+        let cycle = yaml.replace(
+            "outputs: [status]",
+            "outputs: [status]\n    depends_on:\n      planner: subscribed",
+        );
+        assert!(check(cycle).contains("`depends_on` contains a cycle"));
+    }
+
+    #[test]
+    fn failover_nodes_are_validated() {
+        use crate::descriptor::{Descriptor, DescriptorExt};
+
+        let yaml = r#"
+nodes:
+  - id: detector
+    path: shell
+    args: ./detector
+    failover: true
+    _unstable_deploy:
+      selector:
+        gpu: "true"
+"#;
+        let descriptor = Descriptor::parse(yaml.as_bytes().to_vec()).unwrap();
+        descriptor.check(std::path::Path::new(".")).unwrap();
+
+        let check = |yaml: String| {
+            let descriptor = Descriptor::parse(yaml.into_bytes()).unwrap();
+            let err = descriptor.check(std::path::Path::new(".")).unwrap_err();
+            format!("{err:?}")
+        };
+        let pinned = yaml.replace("selector:", "machine: a\n      selector:");
+        assert!(check(pinned).contains("must not be pinned to a `machine`"));
+        let not_label_based = yaml.replace(
+            "    _unstable_deploy:\n      selector:\n        gpu: \"true\"\n",
+            "",
+        );
+        assert!(check(not_label_based).contains("must be placed by"));
+        let replicated = yaml.replace("failover: true", "failover: true\n    replicas: 2");
+        assert!(check(replicated).contains("not supported for replicated nodes"));
+        let dynamic = yaml.replace("path: shell\n    args: ./detector", "path: dynamic");
+        assert!(check(dynamic).contains("not supported for dynamic nodes"));
+    }
+
+    #[test]
+    fn zenoh_priorities_must_refer_to_outputs() {
+        use crate::descriptor::{Descriptor, DescriptorExt};
+
+        let yaml = r#"
+communication:
+  _unstable_remote:
+    zenoh:
+      prefix: robot-1/dora
+      priorities:
+        camera/image: real-time
+nodes:
+  - id: camera
+    path: dynamic
+    outputs: [image]
+"#;
+        let descriptor = Descriptor::parse(yaml.as_bytes().to_vec()).unwrap();
+        descriptor.check(std::path::Path::new(".")).unwrap();
+
+        let unknown = yaml.replace("camera/image:", "camera/depth:");
+        let descriptor = Descriptor::parse(unknown.into_bytes()).unwrap();
+        let err = descriptor.check(std::path::Path::new(".")).unwrap_err();
+        assert!(format!("{err:?}").contains("unknown output `camera/depth`"));
+
+        let wildcard = yaml.replace("robot-1/dora", "robot-*");
+        let descriptor = Descriptor::parse(wildcard.into_bytes()).unwrap();
+        assert!(descriptor.check(std::path::Path::new(".")).is_err());
+    }
+
+    #[test]
+    fn compression_must_refer_to_outputs() {
+        use crate::descriptor::{Descriptor, DescriptorExt};
+
+        let yaml = r#"
+nodes:
+  - id: camera
+    path: dynamic
+    outputs: [image]
+    compression:
+      image: zstd(5)
+"#;
+        let descriptor = Descriptor::parse(yaml.as_bytes().to_vec()).unwrap();
+        descriptor.check(std::path::Path::new(".")).unwrap();
+
+        let unknown = yaml.replace("image: zstd(5)", "depth: lz4");
+        let descriptor = Descriptor::parse(unknown.into_bytes()).unwrap();
+        let err = descriptor.check(std::path::Path::new(".")).unwrap_err();
+        assert!(format!("{err:?}").contains("unknown output `depth`"));
+
+        let invalid = yaml.replace("zstd(5)", "gzip");
+        assert!(Descriptor::parse(invalid.into_bytes()).is_err());
+    }
 }
@@ -1,5 +1,5 @@
 use dora_message::{
-    config::{Input, InputMapping, UserInputMapping, format_duration},
+    config::{Input, InputMapping, Timer, UserInputMapping},
     descriptor::{CoreNodeKind, OperatorDefinition},
     id::{DataId, NodeId},
 };
@@ -8,7 +8,6 @@ use super::{CustomNode, ResolvedNode, RuntimeNode};
 use std::{
     collections::{BTreeMap, BTreeSet, HashMap},
     fmt::Write as _,
-    time::Duration,
 };
 
 pub fn visualize_nodes(nodes: &BTreeMap<NodeId, ResolvedNode>) -> String {
@@ -24,9 +23,9 @@ pub fn visualize_nodes(nodes: &BTreeMap<NodeId, ResolvedNode>) -> String {
     if !dora_timers.is_empty() {
         writeln!(flowchart, "subgraph ___dora___ [dora]").unwrap();
         writeln!(flowchart, "  subgraph ___timer_timer___ [timer]").unwrap();
-        for interval in dora_timers {
-            let duration = format_duration(interval);
-            writeln!(flowchart, "    dora/timer/{duration}[\\{duration}/]").unwrap();
+        for timer in dora_timers {
+            let id = timer_node_id(&timer);
+            writeln!(flowchart, "    {id}[\\{timer}/]").unwrap();
         }
         flowchart.push_str("  end\n");
         flowchart.push_str("end\n");
@@ -39,7 +38,7 @@ pub fn visualize_nodes(nodes: &BTreeMap<NodeId, ResolvedNode>) -> String {
     flowchart
 }
 
-pub fn collect_dora_timers(nodes: &BTreeMap<NodeId, ResolvedNode>) -> BTreeSet<Duration> {
+pub fn collect_dora_timers(nodes: &BTreeMap<NodeId, ResolvedNode>) -> BTreeSet<Timer> {
     let mut dora_timers = BTreeSet::new();
     for node in nodes.values() {
         match &node.kind {
@@ -58,18 +57,23 @@ pub fn collect_dora_timers(nodes: &BTreeMap<NodeId, ResolvedNode>) -> BTreeSet<D
 
 fn collect_dora_nodes(
     values: std::collections::btree_map::Values<DataId, Input>,
-    dora_timers: &mut BTreeSet<Duration>,
+    dora_timers: &mut BTreeSet<Timer>,
 ) {
     for input in values {
         match &input.mapping {
             InputMapping::User(_) => {}
-            InputMapping::Timer { interval } => {
-                dora_timers.insert(*interval);
+            InputMapping::Timer(timer) => {
+                dora_timers.insert(*timer);
             }
         }
     }
 }
 
+/// Mermaid node ID of a timer, without the query characters of timer options.
+fn timer_node_id(timer: &Timer) -> String {
+    format!("dora/timer/{timer}").replace(['?', '&', '='], "_")
+}
+
 fn visualize_node(node: &ResolvedNode, flowchart: &mut String) {
     let node_id = &node.id;
     let description = if let Some(desc) = &node.description {
@@ -173,8 +177,9 @@ fn visualize_inputs(
 ) {
     for (input_id, input) in inputs {
         match &input.mapping {
-            mapping @ InputMapping::Timer { .. } => {
-                writeln!(flowchart, "  {mapping} -- {input_id} --> {target}").unwrap();
+            InputMapping::Timer(timer) => {
+                let id = timer_node_id(timer);
+                writeln!(flowchart, "  {id} -- {input_id} --> {target}").unwrap();
             }
             InputMapping::User(mapping) => {
                 visualize_user_mapping(mapping, target, nodes, input_id, flowchart)
@@ -9,6 +9,8 @@ pub use dora_message::{config, uhlc};
 
 #[cfg(feature = "build")]
 pub mod build;
+#[cfg(feature = "compression")]
+pub mod compression;
 pub mod descriptor;
 pub mod metadata;
 pub mod topics;
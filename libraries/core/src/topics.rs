@@ -156,14 +156,114 @@ pub async fn open_zenoh_session_as_daemon(
     Ok(zenoh_session)
 }
 
+/// Build the zenoh config of a dataflow that specifies custom zenoh settings.
+///
+/// Clients such as the CLI pass `listen: false` so that they don't try to
+/// bind the endpoints that the daemon on the same machine listens on.
+#[cfg(feature = "zenoh")]
+pub fn build_dataflow_zenoh_config(
+    settings: &dora_message::config::ZenohConfig,
+    listen: bool,
+) -> eyre::Result<zenoh::Config> {
+    use eyre::{Context, eyre};
+
+    let mut zenoh_config = match &settings.config {
+        Some(config) => {
+            let json = serde_json::to_string(config)
+                .context("failed to convert zenoh config to JSON")?;
+            zenoh::Config::from_json5(&json)
+                .map_err(|e| eyre!(e))
+                .context("invalid zenoh config")?
+        }
+        None => zenoh::Config::default(),
+    };
+    let mut insert = |key: &str, value: String| {
+        zenoh_config
+            .insert_json5(key, &value)
+            .map_err(|e| eyre!(e))
+            .wrap_err_with(|| format!("failed to set zenoh config `{key}` to `{value}`"))
+    };
+    if let Some(mode) = settings.mode {
+        insert("mode", format!(r#""{mode}""#))?;
+    }
+    if !settings.connect.is_empty() {
+        insert("connect/endpoints", serde_json::to_string(&settings.connect)?)?;
+    }
+    if !listen {
+        insert("listen/endpoints", "[]".to_owned())?;
+    } else if !settings.listen.is_empty() {
+        insert("listen/endpoints", serde_json::to_string(&settings.listen)?)?;
+    }
+    if let Some(enabled) = settings.multicast_scouting {
+        insert("scouting/multicast/enabled", enabled.to_string())?;
+    }
+    if let Some(enabled) = settings.shared_memory {
+        insert("transport/shared_memory/enabled", enabled.to_string())?;
+    }
+    Ok(zenoh_config)
+}
+
+/// Open a dedicated zenoh session for a dataflow with custom zenoh settings.
+#[cfg(feature = "zenoh")]
+pub async fn open_dataflow_zenoh_session(
+    settings: &dora_message::config::ZenohConfig,
+    listen: bool,
+) -> eyre::Result<zenoh::Session> {
+    use eyre::{Context, eyre};
+
+    let zenoh_config = build_dataflow_zenoh_config(settings, listen)?;
+    zenoh::open(zenoh_config)
+        .await
+        .map_err(|e| eyre!(e))
+        .context("failed to open zenoh session of dataflow")
+}
+
+/// Open a zenoh session for a client that subscribes to the outputs of a
+/// dataflow.
+///
+/// Uses the zenoh settings of the dataflow if it has any and falls back to
+/// [`open_zenoh_session`] otherwise.
+#[cfg(feature = "zenoh")]
+pub async fn open_zenoh_session_for_dataflow(
+    coordinator_addr: Option<IpAddr>,
+    remote: &dora_message::config::RemoteCommunicationConfig,
+) -> eyre::Result<zenoh::Session> {
+    match remote.zenoh_session_settings() {
+        Some(settings) => open_dataflow_zenoh_session(settings, false).await,
+        None => open_zenoh_session(coordinator_addr).await,
+    }
+}
+
+/// Zenoh topic that a daemon publishes the messages of the given output on.
+///
+/// The `prefix` is the [`RemoteCommunicationConfig::zenoh_topic_prefix`] of
+/// the dataflow.
+///
+/// [`RemoteCommunicationConfig::zenoh_topic_prefix`]: dora_message::config::RemoteCommunicationConfig::zenoh_topic_prefix
 #[cfg(feature = "zenoh")]
 pub fn zenoh_output_publish_topic(
+    prefix: &str,
     dataflow_id: uuid::Uuid,
     node_id: &dora_message::id::NodeId,
     output_id: &dora_message::id::DataId,
 ) -> String {
-    let network_id = "default";
-    format!("dora/{network_id}/{dataflow_id}/output/{node_id}/{output_id}")
+    format!("{prefix}/{dataflow_id}/output/{node_id}/{output_id}")
+}
+
+/// Convert the priority of a dataflow output to the zenoh priority type.
+#[cfg(feature = "zenoh")]
+pub fn zenoh_priority(priority: dora_message::config::ZenohPriority) -> zenoh::qos::Priority {
+    use dora_message::config::ZenohPriority;
+    use zenoh::qos::Priority;
+    match priority {
+        ZenohPriority::RealTime => Priority::RealTime,
+        ZenohPriority::InteractiveHigh => Priority::InteractiveHigh,
+        ZenohPriority::InteractiveLow => Priority::InteractiveLow,
+        ZenohPriority::DataHigh => Priority::DataHigh,
+        ZenohPriority::Data => Priority::Data,
+        ZenohPriority::DataLow => Priority::DataLow,
+        ZenohPriority::Background => Priority::Background,
+    }
 }
 
 /// Return the zenoh topic suffix for a given log level.
@@ -304,3 +404,31 @@ pub fn zenoh_log_topic_for_build_daemon(
     let suffix = log_level_topic_suffix(level);
     format!("dora/log/build/{build_id}/daemon/{daemon_id}/{suffix}")
 }
+
+#[cfg(all(test, feature = "zenoh"))]
+mod tests {
+    use dora_message::config::{ZenohConfig, ZenohMode};
+
+    use super::build_dataflow_zenoh_config;
+
+    #[test]
+    fn dataflow_zenoh_config() {
+        let settings = ZenohConfig {
+            mode: Some(ZenohMode::Client),
+            connect: vec!["tcp/10.0.0.1:7447".to_owned()],
+            listen: vec!["tcp/0.0.0.0:7448".to_owned()],
+            multicast_scouting: Some(false),
+            config: Some(serde_yaml::from_str("scouting: { delay: 100 }").unwrap()),
+            ..Default::default()
+        };
+        let config = build_dataflow_zenoh_config(&settings, true).unwrap();
+        assert_eq!(config.get_json("mode").unwrap(), r#""client""#);
+        assert_eq!(config.get_json("connect/endpoints").unwrap(), r#"["tcp/10.0.0.1:7447"]"#);
+        assert_eq!(config.get_json("listen/endpoints").unwrap(), r#"["tcp/0.0.0.0:7448"]"#);
+        assert_eq!(config.get_json("scouting/multicast/enabled").unwrap(), "false");
+        assert_eq!(config.get_json("scouting/delay").unwrap(), "100");
+
+        let client_config = build_dataflow_zenoh_config(&settings, false).unwrap();
+        assert_eq!(client_config.get_json("listen/endpoints").unwrap(), "[]");
+    }
+}
@@ -6,11 +6,12 @@ use std::collections::BTreeSet;
 
 use crate::{
     BuildId, SessionId,
-    common::{DaemonId, GitSource},
+    common::{DaemonId, DataflowChanges, GitSource, PublishMessage},
     coordinator_to_cli::{
         CheckDataflowReply, DaemonInfo, DataflowInfo, DataflowList, NodeInfo, StopDataflowReply,
         VersionInfo,
     },
+    debug::{DebugCommand, DebugStatus},
     descriptor::Descriptor,
     id::{NodeId, OperatorId},
 };
@@ -98,6 +99,9 @@ pub trait CoordinatorControl {
         node: String,
         tail: Option<usize>,
     ) -> Result<crate::common::LogsResponse>;
+    async fn debug(dataflow_uuid: Uuid, command: DebugCommand) -> Result<DebugStatus>;
+    async fn publish(dataflow_uuid: Uuid, message: PublishMessage) -> Result<()>;
+    async fn apply(dataflow_uuid: Uuid, dataflow: Descriptor) -> Result<DataflowChanges>;
     async fn destroy() -> Result<()>;
     async fn list() -> Result<DataflowList>;
     async fn info(dataflow_uuid: Uuid) -> Result<DataflowInfo>;
@@ -1,5 +1,8 @@
 use core::fmt;
-use std::{borrow::Cow, collections::BTreeMap};
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, BTreeSet},
+};
 
 use aligned_vec::{AVec, ConstAlign};
 use chrono::{DateTime, Utc};
@@ -7,7 +10,13 @@ use eyre::Context as _;
 use serde::Deserialize;
 use uuid::Uuid;
 
-use crate::{BuildId, DataflowId, daemon_to_daemon::InterDaemonEvent, id::NodeId};
+use crate::{
+    BuildId, DataflowId,
+    daemon_to_daemon::InterDaemonEvent,
+    descriptor::{CoreNodeKind, Readiness, ResolvedNode},
+    id::{DataId, NodeId},
+    metadata::{ArrowTypeInfo, MetadataParameters},
+};
 
 pub use log::Level as LogLevel;
 
@@ -126,6 +135,129 @@ pub struct LogsResponse {
     pub daemon_timestamp: DateTime<Utc>,
 }
 
+/// A message that is injected into a running dataflow by `dora topic pub`.
+///
+/// The message is delivered as if `node_id` had sent it on `output_id`.
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+pub struct PublishMessage {
+    pub node_id: NodeId,
+    pub output_id: DataId,
+    pub type_info: ArrowTypeInfo,
+    pub parameters: MetadataParameters,
+    pub data: Vec<u8>,
+}
+
+/// Startup progress of a node that other nodes list in their `depends_on` field.
+///
+/// Reported by the daemon of the node and forwarded to the daemons of the
+/// dependent nodes.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum DependencyStatus {
+    /// The node reached the given readiness condition.
+    Reached(Readiness),
+    /// The node stopped, so it won't reach any further conditions.
+    Stopped,
+}
+
+/// The node changes that `dora apply` performs on a running dataflow.
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct DataflowChanges {
+    /// Nodes that are new in the updated dataflow.
+    pub added: BTreeSet<NodeId>,
+    /// Nodes that are no longer part of the updated dataflow.
+    pub removed: BTreeSet<NodeId>,
+    /// Nodes whose configuration changed, so they need to be restarted.
+    pub restarted: BTreeSet<NodeId>,
+    /// Nodes of which only the input mappings changed.
+    ///
+    /// These nodes keep running, only their incoming edges are replaced.
+    pub rewired: BTreeSet<NodeId>,
+}
+
+impl DataflowChanges {
+    /// Compares the resolved nodes of a running dataflow with the nodes of an
+    /// updated descriptor.
+    pub fn between(
+        old: &BTreeMap<NodeId, ResolvedNode>,
+        new: &BTreeMap<NodeId, ResolvedNode>,
+    ) -> Self {
+        let mut changes = Self::default();
+        for (node_id, new_node) in new {
+            match old.get(node_id) {
+                None => {
+                    changes.added.insert(node_id.clone());
+                }
+                Some(old_node) if same_config(old_node, new_node) => {}
+                Some(old_node) if can_rewire(old_node, new_node) => {
+                    changes.rewired.insert(node_id.clone());
+                }
+                Some(_) => {
+                    changes.restarted.insert(node_id.clone());
+                }
+            }
+        }
+        changes.removed = old
+            .keys()
+            .filter(|id| !new.contains_key(*id))
+            .cloned()
+            .collect();
+        changes
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.restarted.is_empty()
+            && self.rewired.is_empty()
+    }
+
+    /// Returns all nodes that are affected by the changes.
+    pub fn nodes(&self) -> impl Iterator<Item = &NodeId> {
+        self.added
+            .iter()
+            .chain(&self.removed)
+            .chain(&self.restarted)
+            .chain(&self.rewired)
+    }
+}
+
+fn same_config(old: &ResolvedNode, new: &ResolvedNode) -> bool {
+    serde_json::to_value(old).ok() == serde_json::to_value(new).ok()
+}
+
+/// Checks whether the nodes only differ in input mappings that can be changed
+/// without restarting the node.
+///
+/// The queue sizes of inputs are set up when the node starts, so new inputs
+/// must use the default queue size and existing inputs must keep theirs.
+/// Operators are always restarted because the runtime dispatches inputs to
+/// operators based on its startup configuration.
+fn can_rewire(old: &ResolvedNode, new: &ResolvedNode) -> bool {
+    let (CoreNodeKind::Custom(old_custom), CoreNodeKind::Custom(new_custom)) =
+        (&old.kind, &new.kind)
+    else {
+        return false;
+    };
+    let old_inputs = &old_custom.run_config.inputs;
+    let queue_sizes_kept = new_custom
+        .run_config
+        .inputs
+        .iter()
+        .all(|(input_id, input)| match old_inputs.get(input_id) {
+            Some(old_input) => old_input.queue_size == input.queue_size,
+            None => input.queue_size.is_none(),
+        });
+
+    let without_inputs = |node: &ResolvedNode| {
+        let mut node = node.clone();
+        if let CoreNodeKind::Custom(custom) = &mut node.kind {
+            custom.run_config.inputs.clear();
+        }
+        node
+    };
+    queue_sizes_kept && same_config(&without_inputs(old), &without_inputs(new))
+}
+
 #[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
 pub struct NodeError {
     pub timestamp: uhlc::Timestamp,
@@ -138,6 +270,9 @@ impl std::fmt::Display for NodeError {
         if let NodeErrorCause::FailedToSpawn(err) = &self.cause {
             return write!(f, "failed to spawn node: {err}");
         }
+        if let NodeErrorCause::DaemonLost = &self.cause {
+            return write!(f, "the connection to the daemon of the node was lost");
+        }
         match &self.exit_status {
             NodeExitStatus::Success => write!(f, "<success>"),
             NodeExitStatus::IoError(err) => write!(f, "I/O error while reading exit status: {err}"),
@@ -159,13 +294,19 @@ impl std::fmt::Display for NodeError {
                     23 => "NSIG".into(),
                     other => other.to_string().into(),
                 };
-                if matches!(self.cause, NodeErrorCause::GraceDuration) {
-                    write!(
+                match &self.cause {
+                    NodeErrorCause::GraceDuration => write!(
                         f,
                         "node was killed by dora because it didn't react to a stop message in time ({signal_str})"
-                    )
-                } else {
-                    write!(f, "exited because of signal {signal_str}")
+                    ),
+                    NodeErrorCause::Unresponsive { timeout } => write!(
+                        f,
+                        "node was killed by dora because it was unresponsive for {timeout:?} ({signal_str})"
+                    ),
+                    NodeErrorCause::MemoryLimitExceeded => {
+                        write!(f, "killed: memory limit exceeded")
+                    }
+                    _ => write!(f, "exited because of signal {signal_str}"),
                 }
             }
             NodeExitStatus::Unknown => write!(f, "unknown exit status"),
@@ -177,7 +318,19 @@ impl std::fmt::Display for NodeError {
                 f,
                 ". This error occurred because node `{caused_by_node}` exited before connecting to dora."
             )?,
-            NodeErrorCause::FailedToSpawn(_) => unreachable!(), // handled above
+            NodeErrorCause::FailedToSpawn(_) | NodeErrorCause::DaemonLost => unreachable!(), // handled above
+            NodeErrorCause::Unresponsive { .. }
+                if matches!(self.exit_status, NodeExitStatus::Signal(_)) => {} // handled above
+            NodeErrorCause::Unresponsive { timeout } => write!(
+                f,
+                " after dora stopped it because it was unresponsive for {timeout:?}"
+            )?,
+            NodeErrorCause::MemoryLimitExceeded
+                if matches!(self.exit_status, NodeExitStatus::Signal(_)) => {} // handled above
+            NodeErrorCause::MemoryLimitExceeded => write!(
+                f,
+                " after one of its processes was killed: memory limit exceeded"
+            )?,
             NodeErrorCause::Other { stderr } if stderr.is_empty() => {}
             NodeErrorCause::Other { stderr } => {
                 let line: &str = "---------------------------------------------------------------------------------\n";
@@ -199,6 +352,15 @@ pub enum NodeErrorCause {
         caused_by_node: NodeId,
     },
     FailedToSpawn(String),
+    /// Node was killed because it didn't send a liveness heartbeat in time.
+    Unresponsive {
+        timeout: std::time::Duration,
+    },
+    /// A process of the node was killed because the node exceeded its memory limit.
+    MemoryLimitExceeded,
+    /// The coordinator lost the connection to the daemon of the node and the
+    /// node couldn't be moved to another daemon.
+    DaemonLost,
     Other {
         stderr: String,
     },
@@ -393,4 +555,70 @@ mod tests {
         let deserialized: LogMessageHelper = serde_yaml::from_str(&serialized).unwrap();
         assert_eq!(log_message, LogMessage::from(deserialized));
     }
+
+    fn resolved_nodes(yaml: &str) -> BTreeMap<NodeId, ResolvedNode> {
+        let nodes: Vec<ResolvedNode> = serde_yaml::from_str(yaml).unwrap();
+        nodes.into_iter().map(|n| (n.id.clone(), n)).collect()
+    }
+
+    #[test]
+    fn dataflow_changes_between() {
+        let old = resolved_nodes(
+            r#"
+- id: camera
+  custom: { path: camera, source: Local, outputs: [image] }
+- id: detector
+  custom:
+    path: detector
+    source: Local
+    inputs: { image: camera/image }
+    outputs: [boxes]
+- id: plot
+  custom: { path: plot, source: Local, inputs: { boxes: detector/boxes } }
+- id: recorder
+  custom: { path: recorder, source: Local, inputs: { image: camera/image } }
+"#,
+        );
+        let new = resolved_nodes(
+            r#"
+- id: camera
+  custom: { path: camera, source: Local, outputs: [image] }
+- id: detector
+  custom:
+    path: detector-v2
+    source: Local
+    inputs: { image: camera/image }
+    outputs: [boxes]
+- id: plot
+  custom:
+    path: plot
+    source: Local
+    inputs: { boxes: detector/boxes, image: camera/image }
+- id: logger
+  custom: { path: logger, source: Local, inputs: { boxes: detector/boxes } }
+"#,
+        );
+        let changes = DataflowChanges::between(&old, &new);
+        let ids = |ids: &[&str]| ids.iter().map(|id| NodeId(id.to_string())).collect();
+        assert_eq!(changes.added, ids(&["logger"]));
+        assert_eq!(changes.removed, ids(&["recorder"]));
+        assert_eq!(changes.restarted, ids(&["detector"]));
+        assert_eq!(changes.rewired, ids(&["plot"]));
+
+        // a custom queue size on a new input requires a restart
+        let new = resolved_nodes(
+            r#"
+- id: plot
+  custom:
+    path: plot
+    source: Local
+    inputs:
+      boxes: detector/boxes
+      image: { source: camera/image, queue_size: 5 }
+"#,
+        );
+        let changes = DataflowChanges::between(&old, &new);
+        assert_eq!(changes.restarted, ids(&["plot"]));
+        assert!(changes.rewired.is_empty());
+    }
 }
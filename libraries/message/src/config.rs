@@ -1,6 +1,7 @@
 use core::fmt;
 use std::{
     collections::{BTreeMap, BTreeSet},
+    num::NonZeroU64,
     str::FromStr,
     time::Duration,
 };
@@ -9,7 +10,10 @@ use once_cell::sync::OnceCell;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-pub use crate::id::{DataId, NodeId, OperatorId};
+pub use crate::{
+    filter::{InputFilter, Rate},
+    id::{DataId, NodeId, OperatorId},
+};
 
 /// Contains the input and output configuration of the node.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
@@ -42,6 +46,21 @@ pub struct NodeRunConfig {
 pub struct Input {
     pub mapping: InputMapping,
     pub queue_size: Option<usize>,
+    /// Deliver at most this many messages per second, e.g. `5Hz`.
+    #[schemars(with = "Option<String>")]
+    pub max_rate: Option<Rate>,
+    /// Only deliver every n-th message.
+    pub every: Option<NonZeroU64>,
+    /// Only deliver messages whose metadata matches this predicate.
+    #[schemars(with = "Option<String>")]
+    pub filter: Option<InputFilter>,
+}
+
+impl Input {
+    /// Returns `true` if any of the `max_rate`, `every`, or `filter` options are set.
+    pub fn has_edge_options(&self) -> bool {
+        self.max_rate.is_some() || self.every.is_some() || self.filter.is_some()
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
@@ -51,6 +70,14 @@ pub enum InputDef {
     WithOptions {
         source: InputMapping,
         queue_size: Option<usize>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        #[schemars(with = "Option<String>")]
+        max_rate: Option<Rate>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        every: Option<NonZeroU64>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        #[schemars(with = "Option<String>")]
+        filter: Option<InputFilter>,
     },
 }
 
@@ -60,13 +87,22 @@ impl From<Input> for InputDef {
             Input {
                 mapping,
                 queue_size: None,
+                max_rate: None,
+                every: None,
+                filter: None,
             } => Self::MappingOnly(mapping),
             Input {
                 mapping,
                 queue_size,
+                max_rate,
+                every,
+                filter,
             } => Self::WithOptions {
                 source: mapping,
                 queue_size,
+                max_rate,
+                every,
+                filter,
             },
         }
     }
@@ -78,10 +114,22 @@ impl From<InputDef> for Input {
             InputDef::MappingOnly(mapping) => Self {
                 mapping,
                 queue_size: None,
+                max_rate: None,
+                every: None,
+                filter: None,
             },
-            InputDef::WithOptions { source, queue_size } => Self {
+            InputDef::WithOptions {
+                source,
+                queue_size,
+                max_rate,
+                every,
+                filter,
+            } => Self {
                 mapping: source,
                 queue_size,
+                max_rate,
+                every,
+                filter,
             },
         }
     }
@@ -89,7 +137,7 @@ impl From<InputDef> for Input {
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, JsonSchema)]
 pub enum InputMapping {
-    Timer { interval: Duration },
+    Timer(Timer),
     User(UserInputMapping),
 }
 
@@ -107,10 +155,7 @@ impl InputMapping {
 impl fmt::Display for InputMapping {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            InputMapping::Timer { interval } => {
-                let duration = format_duration(*interval);
-                write!(f, "dora/timer/{duration}")
-            }
+            InputMapping::Timer(timer) => write!(f, "dora/timer/{timer}"),
             InputMapping::User(mapping) => {
                 write!(f, "{}/{}", mapping.source, mapping.output)
             }
@@ -128,31 +173,7 @@ impl FromStr for InputMapping {
 
         let mapping = match source {
             "dora" => match output.split_once('/') {
-                Some(("timer", output)) => {
-                    let (unit, value) = output.split_once('/').ok_or(
-                        "timer input must specify unit and value (e.g. `secs/5` or `millis/100`)",
-                    )?;
-                    let interval = match unit {
-                        "secs" => {
-                            let value = value
-                                .parse()
-                                .map_err(|_| format!("secs must be an integer (got `{value}`)"))?;
-                            Duration::from_secs(value)
-                        }
-                        "millis" => {
-                            let value = value.parse().map_err(|_| {
-                                format!("millis must be an integer (got `{value}`)")
-                            })?;
-                            Duration::from_millis(value)
-                        }
-                        other => {
-                            return Err(format!(
-                                "timer unit must be either secs or millis (got `{other}`"
-                            ));
-                        }
-                    };
-                    Self::Timer { interval }
-                }
+                Some(("timer", output)) => Self::Timer(output.parse()?),
                 Some((other, _)) => {
                     return Err(format!("unknown dora input `{other}`"));
                 }
@@ -168,6 +189,281 @@ impl FromStr for InputMapping {
     }
 }
 
+/// A built-in timer input, e.g. `dora/timer/millis/100`.
+///
+/// Periodic timers are specified as `secs/<value>` or `millis/<value>`. One-shot
+/// timers are specified as `once/<duration>` (e.g. `once/2s`) and fire a single
+/// time after the given delay.
+///
+/// Periodic timers support the following options, given as a query string:
+///
+/// - `phase=<duration>`: delays all ticks by the given offset, e.g.
+///   `dora/timer/millis/100?phase=50ms`
+/// - `align=wall`: fires at multiples of the interval since the UNIX epoch (plus
+///   the phase), e.g. `dora/timer/secs/1?align=wall`
+///
+/// Durations are given as a number followed by `us`, `ms`, or `s`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, JsonSchema)]
+pub struct Timer {
+    /// Interval between two ticks, or the delay of a one-shot timer.
+    pub interval: Duration,
+    /// Fire only a single time, after `interval`.
+    pub once: bool,
+    /// Offset of the ticks relative to the timer start or to the wall-clock
+    /// boundaries.
+    pub phase: Duration,
+    /// Align the ticks to multiples of `interval` since the UNIX epoch.
+    pub align_to_wall_clock: bool,
+}
+
+impl Timer {
+    /// Creates a periodic timer without phase offset or alignment.
+    pub fn periodic(interval: Duration) -> Self {
+        Self {
+            interval,
+            once: false,
+            phase: Duration::ZERO,
+            align_to_wall_clock: false,
+        }
+    }
+}
+
+impl fmt::Display for Timer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.once {
+            return write!(f, "once/{}", CompactDuration(self.interval));
+        }
+        write!(f, "{}", format_duration(self.interval))?;
+        let mut separator = '?';
+        if !self.phase.is_zero() {
+            write!(f, "{separator}phase={}", CompactDuration(self.phase))?;
+            separator = '&';
+        }
+        if self.align_to_wall_clock {
+            write!(f, "{separator}align=wall")?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Timer {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (spec, options) = match s.split_once('?') {
+            Some((spec, options)) => (spec, Some(options)),
+            None => (s, None),
+        };
+        let (unit, value) = spec.split_once('/').ok_or(
+            "timer input must specify unit and value (e.g. `secs/5`, `millis/100`, or `once/2s`)",
+        )?;
+        let mut timer = match unit {
+            "secs" => {
+                let value = value
+                    .parse()
+                    .map_err(|_| format!("secs must be an integer (got `{value}`)"))?;
+                Self::periodic(Duration::from_secs(value))
+            }
+            "millis" => {
+                let value = value
+                    .parse()
+                    .map_err(|_| format!("millis must be an integer (got `{value}`)"))?;
+                Self::periodic(Duration::from_millis(value))
+            }
+            "once" => {
+                if options.is_some() {
+                    return Err(format!(
+                        "one-shot timers do not support options (got `{s}`)"
+                    ));
+                }
+                Self {
+                    once: true,
+                    ..Self::periodic(parse_compact_duration(value)?)
+                }
+            }
+            other => {
+                return Err(format!(
+                    "timer unit must be either secs, millis, or once (got `{other}`)"
+                ));
+            }
+        };
+        if timer.interval.is_zero() {
+            return Err(format!("timer interval must not be zero (got `{s}`)"));
+        }
+        for option in options.into_iter().flat_map(|o| o.split('&')) {
+            match option.split_once('=') {
+                Some(("phase", value)) => timer.phase = parse_compact_duration(value)?,
+                Some(("align", "wall")) => timer.align_to_wall_clock = true,
+                Some(("align", other)) => {
+                    return Err(format!("timer alignment must be `wall` (got `{other}`)"));
+                }
+                _ => {
+                    return Err(format!(
+                        "unknown timer option `{option}` (expected `phase=<duration>` or `align=wall`)"
+                    ));
+                }
+            }
+        }
+        Ok(timer)
+    }
+}
+
+/// Formats a duration as `<value><unit>`, e.g. `50ms`.
+struct CompactDuration(Duration);
+
+impl fmt::Display for CompactDuration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.0.subsec_nanos() == 0 {
+            write!(f, "{}s", self.0.as_secs())
+        } else if self.0.subsec_nanos() % 1_000_000 == 0 {
+            write!(f, "{}ms", self.0.as_millis())
+        } else {
+            write!(f, "{}us", self.0.as_micros())
+        }
+    }
+}
+
+fn parse_compact_duration(s: &str) -> Result<Duration, String> {
+    let (value, nanos_per_unit) = if let Some(value) = s.strip_suffix("us") {
+        (value, 1_000)
+    } else if let Some(value) = s.strip_suffix("ms") {
+        (value, 1_000_000)
+    } else if let Some(value) = s.strip_suffix('s') {
+        (value, 1_000_000_000)
+    } else {
+        return Err(format!(
+            "duration must have a unit of `us`, `ms`, or `s`, e.g. `2s` (got `{s}`)"
+        ));
+    };
+    // parse integers exactly to avoid float rounding errors
+    if let Ok(value) = value.parse::<u64>() {
+        return Ok(Duration::from_nanos(value.saturating_mul(nanos_per_unit)));
+    }
+    let value: f64 = value
+        .parse()
+        .map_err(|_| format!("duration must be a number followed by a unit (got `{s}`)"))?;
+    Duration::try_from_secs_f64(value * nanos_per_unit as f64 / 1e9)
+        .map_err(|_| format!("duration must be non-negative (got `{s}`)"))
+}
+
+/// Serializes durations in compact form, e.g. `30s` or `500ms`.
+pub(crate) mod compact_duration {
+    use std::time::Duration;
+
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    use super::{CompactDuration, parse_compact_duration};
+
+    pub fn serialize<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(&CompactDuration(*duration))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+        parse_compact_duration(&String::deserialize(deserializer)?)
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+/// Serializes optional durations in compact form, e.g. `30s` or `500ms`.
+pub(crate) mod compact_duration_option {
+    use std::time::Duration;
+
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    use super::{CompactDuration, parse_compact_duration};
+
+    pub fn serialize<S: Serializer>(
+        duration: &Option<Duration>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match duration {
+            Some(duration) => serializer.collect_str(&CompactDuration(*duration)),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Duration>, D::Error> {
+        Option::<String>::deserialize(deserializer)?
+            .map(|s| parse_compact_duration(&s).map_err(serde::de::Error::custom))
+            .transpose()
+    }
+}
+
+/// Formats a number of bytes with the largest binary unit that divides it, e.g. `512M`.
+struct MemorySize(u64);
+
+impl fmt::Display for MemorySize {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (unit, factor) in [
+            ("T", 1 << 40),
+            ("G", 1 << 30),
+            ("M", 1 << 20),
+            ("K", 1 << 10),
+        ] {
+            if self.0 >= factor && self.0 % factor == 0 {
+                return write!(f, "{}{unit}", self.0 / factor);
+            }
+        }
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Parses a memory size with an optional unit of `K`, `M`, `G`, or `T` (powers of 1024).
+pub fn parse_memory_size(s: &str) -> Result<u64, String> {
+    let (value, factor) = match s.char_indices().last() {
+        Some((i, 'K' | 'k')) => (&s[..i], 1 << 10),
+        Some((i, 'M' | 'm')) => (&s[..i], 1 << 20),
+        Some((i, 'G' | 'g')) => (&s[..i], 1 << 30),
+        Some((i, 'T' | 't')) => (&s[..i], 1 << 40),
+        _ => (s, 1),
+    };
+    let value: u64 = value.trim().parse().map_err(|_| {
+        format!(
+            "memory size must be a number of bytes with an optional unit of `K`, `M`, `G`, or `T`, e.g. `512M` (got `{s}`)"
+        )
+    })?;
+    value
+        .checked_mul(factor)
+        .ok_or_else(|| format!("memory size is too large (got `{s}`)"))
+}
+
+/// Serializes optional memory sizes in compact form, e.g. `512M` or `2G`.
+///
+/// Plain numbers of bytes are accepted too.
+pub(crate) mod memory_size_option {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    use super::{MemorySize, parse_memory_size};
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Raw {
+        Bytes(u64),
+        String(String),
+    }
+
+    pub fn serialize<S: Serializer>(size: &Option<u64>, serializer: S) -> Result<S::Ok, S::Error> {
+        match size {
+            Some(size) => serializer.collect_str(&MemorySize(*size)),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<u64>, D::Error> {
+        match Option::<Raw>::deserialize(deserializer)? {
+            None => Ok(None),
+            Some(Raw::Bytes(bytes)) => Ok(Some(bytes)),
+            Some(Raw::String(s)) => parse_memory_size(&s)
+                .map(Some)
+                .map_err(serde::de::Error::custom),
+        }
+    }
+}
+
 pub struct FormattedDuration(pub Duration);
 
 impl fmt::Display for FormattedDuration {
@@ -222,19 +518,363 @@ pub struct CommunicationConfig {
     pub remote: RemoteCommunicationConfig,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq)]
 #[serde(deny_unknown_fields, rename_all = "lowercase")]
 pub enum RemoteCommunicationConfig {
+    /// Use the zenoh session of the daemons with the default topic prefix.
+    #[default]
     Tcp,
-    // TODO:a
-    // Zenoh {
-    //     config: Option<serde_yaml::Value>,
-    //     prefix: String,
-    // },
+    /// Use custom zenoh settings for the dataflow.
+    Zenoh(ZenohConfig),
+}
+
+/// Prefix of the zenoh topics that daemons publish outputs on, unless the
+/// dataflow specifies a different one.
+pub const DEFAULT_ZENOH_TOPIC_PREFIX: &str = "dora/default";
+
+impl RemoteCommunicationConfig {
+    /// Zenoh settings of the dataflow, if any.
+    pub fn zenoh(&self) -> Option<&ZenohConfig> {
+        match self {
+            Self::Tcp => None,
+            Self::Zenoh(config) => Some(config),
+        }
+    }
+
+    /// Prefix of the zenoh topics of all outputs of the dataflow.
+    pub fn zenoh_topic_prefix(&self) -> &str {
+        self.zenoh()
+            .and_then(|config| config.prefix.as_deref())
+            .unwrap_or(DEFAULT_ZENOH_TOPIC_PREFIX)
+    }
+
+    /// Zenoh settings that require a dedicated zenoh session for the dataflow.
+    pub fn zenoh_session_settings(&self) -> Option<&ZenohConfig> {
+        self.zenoh().filter(|config| config.has_session_settings())
+    }
+}
+
+/// Zenoh settings for the communication between the daemons of a dataflow.
+///
+/// Daemons open a dedicated zenoh session for the dataflow if any of the
+/// session settings (`mode`, `connect`, `listen`, `multicast_scouting`,
+/// `shared_memory`, or `config`) is set. Otherwise, they use their default
+/// session, which can be configured through the `ZENOH_CONFIG` environment
+/// variable.
+///
+/// e.g.
+///
+/// ```yaml
+/// communication:
+///   _unstable_remote:
+///     zenoh:
+///       prefix: robot-1/dora
+///       mode: client
+///       connect: ["tcp/10.0.0.1:7447"]
+///       multicast_scouting: false
+///       priorities:
+///         camera/image: real-time
+/// ```
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct ZenohConfig {
+    /// Key expression that is prepended to the zenoh topics of all outputs,
+    /// defaults to `dora/default`.
+    ///
+    /// Dataflows that are deployed on the same network should use different
+    /// prefixes to keep their traffic apart.
+    #[serde(default)]
+    pub prefix: Option<String>,
+    /// Zenoh priorities of outputs as a map from `node_id/output_id` to
+    /// priority.
+    #[serde(default)]
+    pub priorities: BTreeMap<String, ZenohPriority>,
+    /// Mode of the zenoh session.
+    #[serde(default)]
+    pub mode: Option<ZenohMode>,
+    /// Zenoh endpoints to connect to, e.g. `tcp/10.0.0.1:7447`.
+    #[serde(default)]
+    pub connect: Vec<String>,
+    /// Zenoh endpoints to listen on, e.g. `tcp/0.0.0.0:7447`.
+    #[serde(default)]
+    pub listen: Vec<String>,
+    /// Enables or disables the discovery of other zenoh peers through
+    /// multicast scouting.
+    #[serde(default)]
+    pub multicast_scouting: Option<bool>,
+    /// Enables or disables the zenoh shared-memory transport between peers
+    /// on the same machine.
+    ///
+    /// Only has an effect if zenoh is compiled with its `shared-memory`
+    /// feature.
+    #[serde(default)]
+    pub shared_memory: Option<bool>,
+    /// Full zenoh configuration that the settings above are applied to.
+    ///
+    /// See the [zenoh documentation](https://zenoh.io/docs/manual/configuration/)
+    /// for the available options.
+    #[serde(default)]
+    pub config: Option<serde_yaml::Value>,
+}
+
+impl ZenohConfig {
+    /// Whether the settings require a dedicated zenoh session.
+    pub fn has_session_settings(&self) -> bool {
+        self.mode.is_some()
+            || !self.connect.is_empty()
+            || !self.listen.is_empty()
+            || self.multicast_scouting.is_some()
+            || self.shared_memory.is_some()
+            || self.config.is_some()
+    }
+
+    /// Priority of the given output, if configured.
+    pub fn priority(&self, node_id: &NodeId, output_id: &DataId) -> Option<ZenohPriority> {
+        self.priorities
+            .get(&format!("{node_id}/{output_id}"))
+            .copied()
+    }
+}
+
+/// Mode of a zenoh session.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ZenohMode {
+    Peer,
+    Client,
+    Router,
+}
+
+impl fmt::Display for ZenohMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ZenohMode::Peer => "peer",
+            ZenohMode::Client => "client",
+            ZenohMode::Router => "router",
+        })
+    }
+}
+
+/// Zenoh priority of the messages of an output, from highest to lowest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ZenohPriority {
+    RealTime,
+    InteractiveHigh,
+    InteractiveLow,
+    DataHigh,
+    Data,
+    DataLow,
+    Background,
+}
+
+/// Codec that a daemon uses to compress the messages of an output before
+/// sending them to remote receivers.
+///
+/// Written as `lz4`, `zstd`, or `zstd(<level>)` in the dataflow YAML. The
+/// default zstd level is [`Compression::DEFAULT_ZSTD_LEVEL`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, JsonSchema)]
+#[schemars(with = "String")]
+pub enum Compression {
+    Lz4,
+    Zstd(i32),
+}
+
+impl Compression {
+    pub const DEFAULT_ZSTD_LEVEL: i32 = 3;
+}
+
+impl FromStr for Compression {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let trimmed = s.trim();
+        if trimmed == "lz4" {
+            return Ok(Compression::Lz4);
+        }
+        if trimmed == "zstd" {
+            return Ok(Compression::Zstd(Self::DEFAULT_ZSTD_LEVEL));
+        }
+        let level = trimmed
+            .strip_prefix("zstd(")
+            .and_then(|rest| rest.strip_suffix(')'))
+            .ok_or_else(|| {
+                format!("compression must be `lz4`, `zstd`, or `zstd(<level>)` (got `{s}`)")
+            })?;
+        let level: i32 = level
+            .trim()
+            .parse()
+            .map_err(|_| format!("zstd level must be an integer (got `{s}`)"))?;
+        if !(1..=22).contains(&level) {
+            return Err(format!("zstd level must be between 1 and 22 (got `{s}`)"));
+        }
+        Ok(Compression::Zstd(level))
+    }
 }
 
-impl Default for RemoteCommunicationConfig {
-    fn default() -> Self {
-        Self::Tcp
+impl fmt::Display for Compression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Compression::Lz4 => f.write_str("lz4"),
+            Compression::Zstd(level) => write!(f, "zstd({level})"),
+        }
+    }
+}
+
+impl Serialize for Compression {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Compression {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let string = String::deserialize(deserializer)?;
+        string.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{
+        CommunicationConfig, Compression, DEFAULT_ZENOH_TOPIC_PREFIX, InputMapping, MemorySize,
+        RemoteCommunicationConfig, Timer, ZenohMode, ZenohPriority, parse_memory_size,
+    };
+    use crate::id::{DataId, NodeId};
+
+    fn timer(s: &str) -> Timer {
+        match s.parse::<InputMapping>().unwrap() {
+            InputMapping::Timer(timer) => timer,
+            InputMapping::User(_) => panic!("expected timer input"),
+        }
+    }
+
+    #[test]
+    fn parse_timers() {
+        assert_eq!(
+            timer("dora/timer/millis/100"),
+            Timer::periodic(Duration::from_millis(100))
+        );
+        let once = timer("dora/timer/once/2s");
+        assert!(once.once);
+        assert_eq!(once.interval, Duration::from_secs(2));
+        let phased = timer("dora/timer/millis/100?phase=50ms&align=wall");
+        assert_eq!(phased.phase, Duration::from_millis(50));
+        assert!(phased.align_to_wall_clock);
+        assert_eq!(
+            timer("dora/timer/once/1.5s").interval,
+            Duration::from_millis(1500)
+        );
+    }
+
+    #[test]
+    fn timers_roundtrip() {
+        for s in [
+            "dora/timer/secs/1",
+            "dora/timer/millis/100",
+            "dora/timer/once/2s",
+            "dora/timer/once/250ms",
+            "dora/timer/millis/100?phase=50ms",
+            "dora/timer/secs/1?align=wall",
+            "dora/timer/millis/100?phase=20ms&align=wall",
+        ] {
+            assert_eq!(timer(s).to_string(), s.strip_prefix("dora/timer/").unwrap());
+        }
+    }
+
+    #[test]
+    fn invalid_timers_are_rejected() {
+        for s in [
+            "dora/timer/millis/0",
+            "dora/timer/once/2",
+            "dora/timer/once/0s",
+            "dora/timer/once/2s?phase=1s",
+            "dora/timer/millis/100?phase=50",
+            "dora/timer/millis/100?align=monotonic",
+            "dora/timer/millis/100?offset=5ms",
+            "dora/timer/hours/1",
+        ] {
+            assert!(
+                s.parse::<InputMapping>().is_err(),
+                "`{s}` should be rejected"
+            );
+        }
+    }
+
+    #[test]
+    fn memory_sizes() {
+        assert_eq!(parse_memory_size("512M"), Ok(512 << 20));
+        assert_eq!(parse_memory_size("2G"), Ok(2 << 30));
+        assert_eq!(parse_memory_size("4096"), Ok(4096));
+        assert!(parse_memory_size("2GB").is_err());
+        assert!(parse_memory_size("-1M").is_err());
+        assert!(parse_memory_size("99999999T").is_err());
+        for s in ["512M", "2G", "1536K", "1000"] {
+            assert_eq!(MemorySize(parse_memory_size(s).unwrap()).to_string(), s);
+        }
+    }
+
+    #[test]
+    fn zenoh_communication_config() {
+        let config: CommunicationConfig = serde_yaml::from_str(
+            r#"
+_unstable_remote:
+  zenoh:
+    prefix: robot-1/dora
+    mode: client
+    connect: ["tcp/10.0.0.1:7447"]
+    priorities:
+      camera/image: real-time
+"#,
+        )
+        .unwrap();
+        let remote = &config.remote;
+        assert_eq!(remote.zenoh_topic_prefix(), "robot-1/dora");
+        let zenoh = remote.zenoh_session_settings().unwrap();
+        assert_eq!(zenoh.mode, Some(ZenohMode::Client));
+        assert_eq!(zenoh.connect, ["tcp/10.0.0.1:7447"]);
+        assert_eq!(
+            zenoh.priority(
+                &NodeId::from("camera".to_owned()),
+                &DataId::from("image".to_owned())
+            ),
+            Some(ZenohPriority::RealTime)
+        );
+
+        // a prefix alone doesn't need a dedicated session
+        let config: CommunicationConfig =
+            serde_yaml::from_str("_unstable_remote:\n  zenoh:\n    prefix: robot-2\n").unwrap();
+        assert!(config.remote.zenoh_session_settings().is_none());
+
+        let config: CommunicationConfig = serde_yaml::from_str("_unstable_remote: tcp").unwrap();
+        assert_eq!(config.remote, RemoteCommunicationConfig::Tcp);
+        assert_eq!(
+            config.remote.zenoh_topic_prefix(),
+            DEFAULT_ZENOH_TOPIC_PREFIX
+        );
+    }
+
+    #[test]
+    fn compression() {
+        assert_eq!("lz4".parse(), Ok(Compression::Lz4));
+        assert_eq!("zstd".parse(), Ok(Compression::Zstd(3)));
+        assert_eq!("zstd(19)".parse(), Ok(Compression::Zstd(19)));
+        for s in ["gzip", "zstd(0)", "zstd(23)", "zstd(fast)", "lz4(1)"] {
+            assert!(
+                s.parse::<Compression>().is_err(),
+                "`{s}` should be rejected"
+            );
+        }
+        for s in ["lz4", "zstd(7)"] {
+            assert_eq!(s.parse::<Compression>().unwrap().to_string(), s);
+        }
     }
 }
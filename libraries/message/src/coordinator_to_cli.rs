@@ -3,7 +3,11 @@ use std::collections::BTreeMap;
 use uuid::Uuid;
 
 pub use crate::common::{LogLevel, LogMessage, NodeError, NodeErrorCause, NodeExitStatus};
-use crate::{common::DaemonId, descriptor::Descriptor, id::NodeId};
+use crate::{
+    common::DaemonId,
+    descriptor::{Capacity, Descriptor},
+    id::NodeId,
+};
 
 #[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
 pub struct NodeInfo {
@@ -12,6 +16,20 @@ pub struct NodeInfo {
     pub node_id: NodeId,
     pub daemon_id: DaemonId,
     pub metrics: Option<NodeMetricsInfo>,
+    /// Set if this entry is an instance of a replicated node.
+    #[serde(default)]
+    pub replica: Option<ReplicaInfo>,
+}
+
+/// Identifies an instance of a replicated node.
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+pub struct ReplicaInfo {
+    /// ID of the replicated node, as declared in the dataflow descriptor.
+    pub node_id: NodeId,
+    /// Index of this instance.
+    pub index: usize,
+    /// Total number of replicas of the node.
+    pub count: usize,
 }
 
 /// Resource metrics for a node (from daemon)
@@ -27,6 +45,18 @@ pub struct NodeMetricsInfo {
     pub disk_read_mb_s: Option<f64>,
     /// Disk write MB/s (if available)
     pub disk_write_mb_s: Option<f64>,
+    /// CPU limit in percent (100 per core), if the node has one
+    #[serde(default)]
+    pub cpu_limit: Option<f32>,
+    /// Memory limit in megabytes, if the node has one
+    #[serde(default)]
+    pub memory_limit_mb: Option<f64>,
+    /// Number of processes and threads, if the node runs in a cgroup
+    #[serde(default)]
+    pub pids: Option<u64>,
+    /// Maximum number of processes and threads, if the node has a limit
+    #[serde(default)]
+    pub pids_limit: Option<u64>,
 }
 
 #[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
@@ -130,4 +160,17 @@ pub struct DaemonInfo {
     pub zenoh_ready: bool,
     /// The Zenoh ZID of the daemon's session, if open.
     pub zenoh_peer_id: Option<String>,
+    /// Whether the daemon may apply real-time scheduling policies and raised
+    /// priorities to nodes (`CAP_SYS_NICE` on Linux).
+    #[serde(default)]
+    pub realtime_scheduling: bool,
+    /// Labels of the daemon for label-based node placement.
+    #[serde(default)]
+    pub labels: BTreeMap<String, String>,
+    /// Capacity that nodes can allocate on the daemon.
+    #[serde(default)]
+    pub capacity: Capacity,
+    /// Capacity that is allocated by the nodes of running dataflows.
+    #[serde(default)]
+    pub allocated: Capacity,
 }
@@ -6,7 +6,7 @@ use std::{
 
 use crate::{
     BuildId, DataflowId, SessionId,
-    common::{DaemonId, GitSource},
+    common::{DaemonId, DataflowChanges, GitSource},
     descriptor::{Descriptor, ResolvedNode},
     id::{NodeId, OperatorId},
 };
@@ -50,7 +50,7 @@ pub struct BuildDataflowNodes {
     pub uv: bool,
 }
 
-#[derive(Debug, serde::Deserialize, serde::Serialize)]
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
 pub struct SpawnDataflowNodes {
     pub build_id: Option<BuildId>,
     pub session_id: SessionId,
@@ -76,6 +76,22 @@ pub struct SpawnDataflowNodes {
     pub dataflow_path: Option<PathBuf>,
 }
 
+/// Changes to a running dataflow, sent by `dora apply`.
+#[derive(Debug, serde::Deserialize, serde::Serialize)]
+pub struct ApplyDataflowChanges {
+    /// All nodes of the updated dataflow, including the nodes of other daemons.
+    pub nodes: BTreeMap<NodeId, ResolvedNode>,
+    pub dataflow_descriptor: Descriptor,
+    /// The nodes of the updated dataflow that run on this daemon.
+    pub local_nodes: BTreeSet<NodeId>,
+    pub changes: DataflowChanges,
+    /// Only check whether the changes can be applied, without applying them.
+    ///
+    /// The coordinator checks all daemons first, so that the changes are not
+    /// applied partially.
+    pub dry_run: bool,
+}
+
 type DaemonResult<T> = std::result::Result<T, String>;
 
 #[tarpc::service]
@@ -104,6 +120,24 @@ pub trait DaemonControl {
         node_id: NodeId,
         tail: Option<usize>,
     ) -> DaemonResult<crate::common::LogsResponse>;
+    /// Apply a step-through debugging command to the local nodes of a dataflow.
+    async fn debug(
+        dataflow_id: DataflowId,
+        command: crate::debug::DebugCommand,
+    ) -> DaemonResult<crate::debug::DebugStatus>;
+    /// Send a message on behalf of a local node, for `dora topic pub`.
+    async fn publish(
+        dataflow_id: DataflowId,
+        message: crate::common::PublishMessage,
+    ) -> DaemonResult<()>;
+    /// Add, remove, restart, and rewire nodes of a running dataflow.
+    async fn apply(dataflow_id: DataflowId, request: ApplyDataflowChanges) -> DaemonResult<()>;
+    /// Forward the startup progress of a remote node that local nodes depend on.
+    async fn dependency_status(
+        dataflow_id: DataflowId,
+        node_id: NodeId,
+        status: crate::common::DependencyStatus,
+    );
     /// Stop a single node within a running dataflow (for hot-reload).
     async fn stop_node(dataflow_id: DataflowId, node_id: NodeId) -> DaemonResult<()>;
     /// Dynamically spawn a node into a running dataflow (for hot-reload).
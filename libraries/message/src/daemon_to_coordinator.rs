@@ -4,7 +4,12 @@ pub use crate::common::{
     DataMessage, LogLevel, NodeError, NodeErrorCause, NodeExitStatus, Timestamped,
 };
 use crate::{
-    BuildId, DataflowId, common::DaemonId, current_crate_version, id::NodeId, versions_compatible,
+    BuildId, DataflowId,
+    common::{DaemonId, DependencyStatus},
+    current_crate_version,
+    descriptor::Capacity,
+    id::NodeId,
+    versions_compatible,
 };
 
 #[derive(Debug, serde::Serialize, serde::Deserialize)]
@@ -20,6 +25,18 @@ pub enum CoordinatorRequest {
     },
 }
 
+/// Labels and capacity that a daemon offers for the placement of nodes, see
+/// [`Deploy`](crate::descriptor::Deploy).
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct DaemonPlacement {
+    /// Labels of the daemon, e.g. `arch=arm64`.
+    #[serde(default)]
+    pub labels: BTreeMap<String, String>,
+    /// Capacity that nodes can allocate on the daemon.
+    #[serde(default)]
+    pub capacity: Capacity,
+}
+
 #[derive(Debug, serde::Serialize, serde::Deserialize)]
 pub struct DaemonRegisterRequest {
     dora_version: semver::Version,
@@ -32,15 +49,34 @@ pub struct DaemonRegisterRequest {
     /// Zenoh ZID reported by the daemon's local zenoh::Session, if one was opened.
     #[serde(default)]
     pub zenoh_peer_id: Option<String>,
+    /// Whether the daemon may apply real-time scheduling policies and raised
+    /// priorities to nodes (`CAP_SYS_NICE` on Linux).
+    #[serde(default)]
+    pub realtime_scheduling: bool,
+    /// Labels and capacity of the daemon for label-based node placement.
+    #[serde(default)]
+    pub placement: DaemonPlacement,
+    /// ID assigned by a previous coordinator, set when reconnecting to a new
+    /// leader of a high-availability coordinator cluster.
+    #[serde(default)]
+    pub daemon_id: Option<DaemonId>,
 }
 
 impl DaemonRegisterRequest {
-    pub fn new(machine_id: Option<String>, zenoh_peer_id: Option<String>) -> Self {
+    pub fn new(
+        machine_id: Option<String>,
+        zenoh_peer_id: Option<String>,
+        realtime_scheduling: bool,
+        placement: DaemonPlacement,
+    ) -> Self {
         Self {
             dora_version: current_crate_version(),
             machine_id,
             machine_uid: crate::common::machine_uid(),
             zenoh_peer_id,
+            realtime_scheduling,
+            placement,
+            daemon_id: None,
         }
     }
 
@@ -68,11 +104,25 @@ pub struct NodeMetrics {
     /// CPU usage percentage (0-100 per core)
     pub cpu_usage: f32,
     /// Memory usage in bytes
+    ///
+    /// Includes all processes of the node if it runs in a cgroup with resource limits.
     pub memory_bytes: u64,
     /// Disk read bytes per second (if available)
     pub disk_read_bytes: Option<u64>,
     /// Disk write bytes per second (if available)
     pub disk_write_bytes: Option<u64>,
+    /// CPU limit in percent (100 per core), if the node has one
+    #[serde(default)]
+    pub cpu_limit: Option<f32>,
+    /// Memory limit in bytes, if the node has one
+    #[serde(default)]
+    pub memory_limit_bytes: Option<u64>,
+    /// Number of processes and threads, if the node runs in a cgroup
+    #[serde(default)]
+    pub pids: Option<u64>,
+    /// Maximum number of processes and threads, if the node has a limit
+    #[serde(default)]
+    pub pids_limit: Option<u64>,
 }
 
 #[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
@@ -108,4 +158,6 @@ pub trait CoordinatorNotify {
     async fn build_result(build_id: BuildId, result: Result<(), String>);
     /// Report that a dataflow spawn has completed (or failed) on this daemon.
     async fn spawn_result(dataflow_id: DataflowId, result: Result<(), String>);
+    /// Report the startup progress of a local node that remote nodes depend on.
+    async fn dependency_status(dataflow_id: DataflowId, node_id: NodeId, status: DependencyStatus);
 }
@@ -1,4 +1,4 @@
-use std::{net::SocketAddr, path::PathBuf};
+use std::{net::SocketAddr, path::PathBuf, time::Duration};
 
 use crate::{
     DataflowId,
@@ -26,11 +26,31 @@ pub struct NodeConfig {
     pub dataflow_descriptor: serde_yaml::Value,
     pub dynamic: bool,
     pub write_events_to: Option<PathBuf>,
+    /// Whether the node should follow the simulated clock of the daemon instead
+    /// of the system clock (see `dora run --clock sim`).
+    #[serde(default)]
+    pub simulated_clock: bool,
+    /// Maximum time between two heartbeats of the node, if its `liveness` is
+    /// monitored by the daemon.
+    #[serde(default)]
+    pub liveness_timeout: Option<Duration>,
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub enum DaemonCommunication {
-    Tcp { socket_addr: SocketAddr },
+    Tcp {
+        socket_addr: SocketAddr,
+    },
+    /// Unix domain socket at the given path, only available on Unix platforms.
+    UnixDomain {
+        path: PathBuf,
+    },
+    /// Shared memory regions with the given IDs, only available on Linux.
+    ///
+    /// Each connection of the node claims one of the regions.
+    SharedMemory {
+        region_ids: Vec<String>,
+    },
     Interactive,
 }
 
@@ -118,6 +138,13 @@ pub enum NodeEvent {
         error: String,
         source_node_id: NodeId,
     },
+    /// Wakes up a node that waits for events with a timeout once the deadline
+    /// is reached in simulated time.
+    ///
+    /// Only sent when the simulated clock is enabled.
+    SimulatedTimeout {
+        deadline: uhlc::NTP64,
+    },
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -0,0 +1,84 @@
+//! Step-through debugging of message delivery, used by `dora debug`.
+
+use crate::{
+    filter::InputFilter,
+    id::{DataId, NodeId},
+    metadata::MetadataParameters,
+};
+
+/// A debugging command for a running dataflow.
+///
+/// Commands with a `node` field apply to the whole dataflow if no node is given.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub enum DebugCommand {
+    /// Hold back all messages that would be delivered to the node.
+    Pause { node: Option<NodeId> },
+    /// Deliver all held back messages and stop pausing.
+    Resume { node: Option<NodeId> },
+    /// Deliver the next `count` held back messages, but stay paused.
+    Step { node: Option<NodeId>, count: usize },
+    /// Pause the dataflow when a message on the given output matches the condition.
+    SetBreakpoint(Breakpoint),
+    /// Remove all breakpoints.
+    ClearBreakpoints,
+    /// Report the current debugging state without changing it.
+    Status,
+}
+
+/// Pauses a dataflow when a matching message is sent.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Breakpoint {
+    pub node: NodeId,
+    pub output: DataId,
+    /// Metadata condition, see [`InputFilter`]. Matches all messages if `None`.
+    pub condition: Option<InputFilter>,
+}
+
+/// The debugging state of a dataflow, returned for every [`DebugCommand`].
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct DebugStatus {
+    /// Set if delivery to all nodes is paused.
+    pub dataflow_paused: bool,
+    /// Nodes that were paused individually.
+    pub paused_nodes: Vec<NodeId>,
+    pub breakpoints: Vec<Breakpoint>,
+    /// Held back messages in delivery order.
+    pub queued: Vec<QueuedMessage>,
+    /// Number of messages that were delivered because of this command.
+    pub released: usize,
+}
+
+/// A message that is held back by the debugger.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct QueuedMessage {
+    pub receiver: NodeId,
+    pub input: DataId,
+    pub source_node: NodeId,
+    pub source_output: DataId,
+    pub timestamp: uhlc::Timestamp,
+    pub parameters: MetadataParameters,
+    /// Size of the message data in bytes.
+    pub len: Option<usize>,
+    /// Set if the message hit a breakpoint.
+    pub breakpoint: bool,
+}
+
+impl DebugStatus {
+    /// Merges the status reported by another daemon into this status.
+    pub fn merge(&mut self, other: DebugStatus) {
+        self.dataflow_paused |= other.dataflow_paused;
+        for node in other.paused_nodes {
+            if !self.paused_nodes.contains(&node) {
+                self.paused_nodes.push(node);
+            }
+        }
+        for breakpoint in other.breakpoints {
+            if !self.breakpoints.contains(&breakpoint) {
+                self.breakpoints.push(breakpoint);
+            }
+        }
+        self.queued.extend(other.queued);
+        self.queued.sort_by_key(|message| message.timestamp);
+        self.released += other.released;
+    }
+}
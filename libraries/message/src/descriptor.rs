@@ -1,8 +1,9 @@
 #![warn(missing_docs)]
 
 use crate::{
-    config::{CommunicationConfig, Input, InputMapping, NodeRunConfig},
+    config::{CommunicationConfig, Compression, Input, InputMapping, NodeRunConfig},
     id::{DataId, NodeId, OperatorId},
+    service::ServiceCall,
 };
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
@@ -11,6 +12,7 @@ use std::{
     collections::{BTreeMap, BTreeSet},
     fmt,
     path::PathBuf,
+    time::Duration,
 };
 
 /// Node path value for executing commands directly in the shell.
@@ -147,7 +149,8 @@ pub enum RestartPolicy {
 ///
 /// This struct is part of the unstable deployment configuration, prefixed with
 /// `_unstable_deploy` in YAML files. It allows specifying which machine a node
-/// should run on in a multi-machine setup.
+/// should run on in a multi-machine setup, either directly through a `machine`
+/// ID or through the labels and capacity of the daemons.
 ///
 /// ## YAML Example
 ///
@@ -157,6 +160,20 @@ pub enum RestartPolicy {
 ///   working_dir: "/home/dora/projects"
 /// ```
 ///
+/// Label-based placement on any daemon that was started with the `camera=front`
+/// label (e.g. `dora daemon --label camera=front`) and has two unallocated CPUs:
+///
+/// ```yaml
+/// _unstable_deploy:
+///   selector:
+///     camera: front
+///   requires:
+///     cpus: 2
+/// ```
+///
+/// If multiple daemons match, the coordinator picks the one with the largest
+/// share of unallocated capacity.
+///
 /// ## Stability
 ///
 /// ⚠️ **Unstable**: This API may change in future versions.
@@ -171,6 +188,41 @@ pub struct Deploy {
     ///
     /// If not specified, defaults to the daemon's working directory.
     pub working_dir: Option<PathBuf>,
+    /// Labels that the daemon must have to run the node.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub selector: BTreeMap<String, String>,
+    /// Capacity that the node allocates on its daemon.
+    ///
+    /// The node is only placed on daemons with enough unallocated capacity.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub requires: Option<Capacity>,
+}
+
+impl Deploy {
+    /// Whether the node is placed based on the labels or capacity of the daemons.
+    pub fn label_based(&self) -> bool {
+        !self.selector.is_empty() || self.requires.is_some()
+    }
+}
+
+/// Capacity of a daemon or capacity requirements of a node.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Capacity {
+    /// Number of CPU cores, e.g. `2` or `0.5`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cpus: Option<f64>,
+    /// Memory in bytes.
+    ///
+    /// Given as a number with an optional unit of `K`, `M`, `G`, or `T` (powers
+    /// of 1024), e.g. `512M`.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "crate::config::memory_size_option"
+    )]
+    #[schemars(with = "Option<String>")]
+    pub memory: Option<u64>,
 }
 
 /// Debug and development options for dataflow execution.
@@ -386,6 +438,32 @@ pub struct Node {
     #[serde(default)]
     pub outputs: BTreeSet<DataId>,
 
+    /// Compression of outputs that are sent to other machines.
+    ///
+    /// Maps output identifiers to a codec: `lz4`, `zstd`, or `zstd(<level>)`.
+    /// The daemon compresses the messages of these outputs before sending them to
+    /// remote daemons or publishing them through zenoh. Receiving daemons
+    /// decompress them before they are delivered, so nodes always see the original
+    /// data. Receivers on the same machine are not affected.
+    ///
+    /// Outputs of operators are given as `<operator-id>/<output-id>`. Messages that
+    /// don't get smaller through compression are sent uncompressed.
+    ///
+    /// ## Example
+    ///
+    /// ```yaml
+    /// nodes:
+    ///   - id: camera
+    ///     outputs:
+    ///       - image
+    ///       - depth
+    ///     compression:
+    ///       image: lz4
+    ///       depth: zstd(5)
+    /// ```
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub compression: BTreeMap<DataId, Compression>,
+
     /// Input data connections from other nodes.
     ///
     /// Defines the inputs that this node is subscribing to.
@@ -418,6 +496,51 @@ pub struct Node {
     ///     inputs:
     ///         my_input: example-node/two
     /// ```
+    ///
+    /// ## Edge Options
+    ///
+    /// Inputs can also be given as a map with a `source` field and additional options
+    /// that reduce the number of delivered messages:
+    ///
+    /// - `queue_size`: maximum number of buffered messages for this input
+    /// - `max_rate`: deliver at most this many messages per second, e.g. `5Hz`
+    /// - `every`: deliver only every n-th message
+    /// - `filter`: deliver only messages whose metadata matches a predicate such as
+    ///   `parameters.label == 'person'` (see [`InputFilter`](crate::filter::InputFilter))
+    ///
+    /// Messages are dropped by the daemon before they are sent to the node.
+    ///
+    /// ```yaml
+    /// nodes:
+    ///   - id: receiver
+    ///     inputs:
+    ///       image:
+    ///         source: camera/image
+    ///         max_rate: 5Hz
+    ///       detections:
+    ///         source: detector/boxes
+    ///         filter: "parameters.label == 'person'"
+    /// ```
+    ///
+    /// ## Timers
+    ///
+    /// The built-in `dora/timer` source sends periodic ticks without data. Ticks carry
+    /// the intended fire time (`timer_scheduled_time`) and the delay of the actual
+    /// tick (`timer_jitter_us`) as metadata parameters. See
+    /// [`Timer`](crate::config::Timer) for details.
+    ///
+    /// ```yaml
+    /// nodes:
+    ///   - id: controller
+    ///     inputs:
+    ///       tick: dora/timer/millis/100
+    ///       # same interval, but shifted by 50ms
+    ///       shifted_tick: dora/timer/millis/100?phase=50ms
+    ///       # fires at every full second of the system clock
+    ///       second: dora/timer/secs/1?align=wall
+    ///       # fires once, two seconds after the dataflow started
+    ///       delayed_start: dora/timer/once/2s
+    /// ```
     #[serde(default)]
     pub inputs: BTreeMap<DataId, Input>,
 
@@ -564,12 +687,687 @@ pub struct Node {
     #[serde(default)]
     pub restart_policy: RestartPolicy,
 
+    /// Number of instances of this node that the daemon should spawn.
+    ///
+    /// Each input message is delivered to exactly one replica, selected according
+    /// to the configured [`distribution`](Distribution). Outputs of all replicas are
+    /// merged into the outputs declared by this node, so downstream nodes keep using
+    /// the plain `<node-id>/<output>` mapping. Timer inputs are delivered to every
+    /// replica.
+    ///
+    /// Replica instances are named `<node-id>.<index>` (e.g. in `dora node list`)
+    /// and can read their index from the `DORA_REPLICA_INDEX` environment variable.
+    ///
+    /// ## Example
+    ///
+    /// ```yaml
+    /// nodes:
+    ///   - id: detector
+    ///     path: detector.py
+    ///     replicas: 4
+    ///
+    ///   - id: tracker
+    ///     path: tracker.py
+    ///     replicas:
+    ///       count: 2
+    ///       distribution:
+    ///         key: camera_id
+    /// ```
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub replicas: Option<Replicas>,
+
+    /// Services that this node answers requests for.
+    ///
+    /// Other nodes call a service by listing it in their [`calls`](Self::calls) field.
+    /// Requests arrive as
+    /// [`Event::Request`](https://docs.rs/dora-node-api/latest/dora_node_api/enum.Event.html#variant.Request)
+    /// events and are answered through the `reply` function of the node API.
+    ///
+    /// Requests to replicated nodes are distributed across the replicas like inputs.
+    ///
+    /// ## Example
+    ///
+    /// ```yaml
+    /// nodes:
+    ///   - id: planner
+    ///     path: planner.py
+    ///     services:
+    ///       - plan_path
+    /// ```
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub services: BTreeSet<DataId>,
+
+    /// Long-running actions that this node executes on request of other nodes.
+    ///
+    /// Other nodes send goals to an action by listing it in their [`calls`](Self::calls)
+    /// field. Goals arrive as
+    /// [`Event::Goal`](https://docs.rs/dora-node-api/latest/dora_node_api/enum.Event.html#variant.Goal)
+    /// events. While working on a goal, the node can report progress through the
+    /// `send_feedback` function of the node API and finishes the goal through `finish_goal`.
+    /// Cancel requests of the client arrive as
+    /// [`Event::CancelGoal`](https://docs.rs/dora-node-api/latest/dora_node_api/enum.Event.html#variant.CancelGoal)
+    /// events.
+    ///
+    /// Action names must not overlap with the [`services`](Self::services) of the node.
+    ///
+    /// ## Example
+    ///
+    /// ```yaml
+    /// nodes:
+    ///   - id: navigator
+    ///     path: navigator.py
+    ///     actions:
+    ///       - navigate_to
+    /// ```
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub actions: BTreeSet<DataId>,
+
+    /// Services and actions of other nodes that this node calls, given as `<node>/<name>`.
+    ///
+    /// Requests are sent through the `call` function of the node API, which takes a
+    /// timeout. The reply, or a timeout error, is delivered as
+    /// [`Event::Reply`](https://docs.rs/dora-node-api/latest/dora_node_api/enum.Event.html#variant.Reply)
+    /// event. Replies are matched to their request by the daemon of the calling node, so
+    /// late replies are dropped.
+    ///
+    /// Action goals are sent through `send_goal` and canceled through `cancel_goal`.
+    /// Feedback and the final result arrive as `Event::Feedback` and `Event::GoalResult`
+    /// events. The daemon of the calling node tracks the status of each goal and reports
+    /// goals as aborted if the server stops before finishing them.
+    ///
+    /// ## Example
+    ///
+    /// ```yaml
+    /// nodes:
+    ///   - id: robot
+    ///     path: robot.py
+    ///     calls:
+    ///       - planner/plan_path
+    /// ```
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    #[schemars(with = "BTreeSet<String>")]
+    pub calls: BTreeSet<ServiceCall>,
+
+    /// Nodes that must be ready before this node is spawned.
+    ///
+    /// Maps the ID of each dependency to the [`Readiness`] condition that it has to
+    /// reach. The daemon delays spawning this node until all conditions hold, also
+    /// for dependencies that run on other machines. If a dependency exits before
+    /// reaching its condition, or if the conditions don't hold within the
+    /// [`startup_timeout`](Self::startup_timeout), this node fails to spawn.
+    ///
+    /// ## Example
+    ///
+    /// ```yaml
+    /// nodes:
+    ///   - id: planner
+    ///     path: planner.py
+    ///     depends_on:
+    ///       motor-driver: ready         # driver called `node.mark_ready()`
+    ///       camera:
+    ///         output: image             # camera sent its first `image` message
+    ///       lidar:
+    ///         exec: ./check-lidar.sh    # probe command exited successfully
+    ///       logger: subscribed          # logger initialized its dora connection
+    ///     startup_timeout: 30s
+    /// ```
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub depends_on: BTreeMap<NodeId, Readiness>,
+
+    /// Maximum time to wait for the [`depends_on`](Self::depends_on) conditions.
+    ///
+    /// Given as a number with a unit of `us`, `ms`, or `s`. Defaults to 60 seconds.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "crate::config::compact_duration_option"
+    )]
+    #[schemars(with = "Option<String>")]
+    pub startup_timeout: Option<Duration>,
+
+    /// Time that this node gets to exit after it received a stop event.
+    ///
+    /// When a dataflow is stopped, the nodes are stopped in the order of the data
+    /// flow: sources first, and every other node once all of its upstream nodes
+    /// exited. Nodes that don't exit within their stop timeout are terminated.
+    /// Nodes can request more time through `extend_stop_timeout`, e.g. while they
+    /// are flushing files.
+    ///
+    /// Given as a number with a unit of `us`, `ms`, or `s`. Defaults to the grace
+    /// duration of `dora stop`, or 10 seconds if none is given.
+    ///
+    /// ## Example
+    ///
+    /// ```yaml
+    /// nodes:
+    ///   - id: recorder
+    ///     path: recorder.py
+    ///     inputs:
+    ///       image: camera/image
+    ///     stop_timeout: 30s
+    /// ```
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "crate::config::compact_duration_option"
+    )]
+    #[schemars(with = "Option<String>")]
+    pub stop_timeout: Option<Duration>,
+
+    /// Hang detection for this node.
+    ///
+    /// The node API sends heartbeats to the daemon from the event loop of the
+    /// node: whenever the node receives the next event and periodically while it
+    /// waits for events. A node that doesn't ask for its next event within the
+    /// liveness `timeout`, e.g. because it is stuck in a deadlock, is considered
+    /// unresponsive. The daemon then logs the event, kills the node, and reports
+    /// it as failed, which restarts it according to its
+    /// [`restart_policy`](Self::restart_policy).
+    ///
+    /// With `stack_dump: true`, the daemon first sends `SIGQUIT` to the node to
+    /// make runtimes like Python (with `faulthandler`), Java, or Go print the
+    /// stack traces of all threads. Only supported on Unix.
+    ///
+    /// ## Example
+    ///
+    /// ```yaml
+    /// nodes:
+    ///   - id: planner
+    ///     path: planner.py
+    ///     restart_policy: on-failure
+    ///     liveness:
+    ///       timeout: 5s
+    ///       stack_dump: true
+    /// ```
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub liveness: Option<Liveness>,
+
+    /// Resource limits for this node.
+    ///
+    /// On Linux, the daemon places every node with resource limits into its own
+    /// cgroup (v2) below `/sys/fs/cgroup/dora/<dataflow>/<node>`, which limits
+    /// the node process and all of its child processes. A different base
+    /// directory can be set through the `DORA_CGROUP_ROOT` environment variable
+    /// of the daemon. The daemon needs write access to the base directory, e.g.
+    /// through a delegated cgroup.
+    ///
+    /// Nodes that exceed their `memory` limit are killed by the kernel and
+    /// reported as failed because of the exceeded memory limit. The limits are
+    /// ignored with a warning on other platforms.
+    ///
+    /// ## Example
+    ///
+    /// ```yaml
+    /// nodes:
+    ///   - id: detector
+    ///     path: detector.py
+    ///     resources:
+    ///       cpu: 1.5
+    ///       memory: 512M
+    ///       pids: 64
+    ///       io_weight: 50
+    /// ```
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resources: Option<Resources>,
+
+    /// CPU scheduling settings for this node.
+    ///
+    /// The daemon applies the settings to the node process before it starts
+    /// executing, so they apply to all threads and child processes of the node.
+    /// Settings that can't be applied, e.g. because of missing permissions, make
+    /// the node fail to spawn. Real-time policies and negative `nice` values
+    /// usually require the `CAP_SYS_NICE` capability, which `dora check` checks
+    /// for. Only supported on Linux, the settings are ignored with a warning on
+    /// other platforms.
+    ///
+    /// ## Example
+    ///
+    /// ```yaml
+    /// nodes:
+    ///   - id: controller
+    ///     path: controller
+    ///     scheduling:
+    ///       policy: fifo
+    ///       priority: 80
+    ///       cpu_affinity: [2, 3]
+    /// ```
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scheduling: Option<Scheduling>,
+
+    /// Runs the node in an isolated sandbox.
+    ///
+    /// Sandboxed nodes run in their own mount, PID, and network namespaces:
+    ///
+    /// - The whole file system is read-only, except for the `writable_paths`
+    ///   and `/dev/shm`, which is needed for shared memory with the daemon.
+    /// - The node only sees its own processes.
+    /// - The node has no network access by default. It can only reach the
+    ///   daemon, through a private loopback interface.
+    /// - The node runs as the given unprivileged `user`.
+    ///
+    /// If the daemon doesn't run as root, the sandbox is created in a new user
+    /// namespace, which requires a kernel that permits unprivileged user
+    /// namespaces. Nodes whose sandbox can't be set up fail to spawn. Only
+    /// supported on Linux 5.12 or newer, sandboxed nodes fail to spawn on
+    /// other platforms.
+    ///
+    /// ## Example
+    ///
+    /// ```yaml
+    /// nodes:
+    ///   - id: plugin
+    ///     path: plugin
+    ///     sandbox:
+    ///       writable_paths: [./output, /tmp]
+    ///       user: nobody
+    /// ```
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sandbox: Option<Sandbox>,
+
+    /// Moves the node to another daemon when its daemon is lost.
+    ///
+    /// If the coordinator loses the connection to a daemon, it places the
+    /// failover nodes of that daemon on other daemons that match their
+    /// `_unstable_deploy` settings and spawns them there. Upstream and
+    /// downstream nodes are reconnected to the new instances automatically.
+    ///
+    /// The new instance starts from scratch, so this is only suitable for
+    /// stateless nodes. Failover nodes must be placed by a `selector` or
+    /// `requires` field instead of a fixed `machine`, and they must not be
+    /// replicated or have a git source.
+    ///
+    /// ## Example
+    ///
+    /// ```yaml
+    /// nodes:
+    ///   - id: detector
+    ///     path: /opt/nodes/detector
+    ///     failover: true
+    ///     _unstable_deploy:
+    ///       selector:
+    ///         gpu: "true"
+    /// ```
+    #[serde(default)]
+    pub failover: bool,
+
     /// Unstable machine deployment configuration
     #[schemars(skip)]
     #[serde(rename = "_unstable_deploy")]
     pub deploy: Option<Deploy>,
 }
 
+/// Replication settings of a node.
+///
+/// Can be given either as a plain replica count or as a map with `count` and
+/// `distribution` fields.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(from = "ReplicasDef", into = "ReplicasDef")]
+pub struct Replicas {
+    /// Number of node instances to spawn.
+    pub count: usize,
+    /// How inputs are distributed across the instances.
+    pub distribution: Distribution,
+}
+
+impl Replicas {
+    /// Returns the ID of the replica instance with the given index.
+    pub fn instance_id(node_id: &NodeId, index: usize) -> NodeId {
+        NodeId(format!("{node_id}.{index}"))
+    }
+
+    /// Returns the IDs of all replica instances of the given node.
+    pub fn instance_ids(&self, node_id: &NodeId) -> Vec<NodeId> {
+        (0..self.count)
+            .map(|index| Self::instance_id(node_id, index))
+            .collect()
+    }
+}
+
+/// Internal representation for replication settings.
+///
+/// This enum is used for serde serialization/deserialization and allows
+/// the replicas to be specified as either a plain count or an object with options.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(untagged)]
+pub enum ReplicasDef {
+    /// Simple form: just the number of replicas.
+    CountOnly(usize),
+    /// Extended form: an object with count and distribution strategy.
+    WithOptions {
+        /// Number of node instances to spawn.
+        count: usize,
+        /// How inputs are distributed across the instances.
+        #[serde(default)]
+        distribution: Distribution,
+    },
+}
+
+impl From<Replicas> for ReplicasDef {
+    fn from(replicas: Replicas) -> Self {
+        match replicas {
+            Replicas {
+                count,
+                distribution: Distribution::RoundRobin,
+            } => Self::CountOnly(count),
+            Replicas {
+                count,
+                distribution,
+            } => Self::WithOptions {
+                count,
+                distribution,
+            },
+        }
+    }
+}
+
+impl From<ReplicasDef> for Replicas {
+    fn from(value: ReplicasDef) -> Self {
+        match value {
+            ReplicasDef::CountOnly(count) => Self {
+                count,
+                distribution: Distribution::default(),
+            },
+            ReplicasDef::WithOptions {
+                count,
+                distribution,
+            } => Self {
+                count,
+                distribution,
+            },
+        }
+    }
+}
+
+/// Strategy for distributing inputs across the replicas of a node.
+///
+/// ## YAML Example
+///
+/// ```yaml
+/// distribution: round-robin   # default
+/// distribution: least-loaded
+/// distribution:
+///   key: camera_id            # metadata parameter to partition by
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(from = "DistributionDef", into = "DistributionDef")]
+pub enum Distribution {
+    /// Send each input to the next replica in turn.
+    #[default]
+    RoundRobin,
+    /// Send each input to the replica with the fewest unanswered inputs.
+    ///
+    /// An input counts as unanswered until the replica sends its next output.
+    LeastLoaded,
+    /// Send all inputs with the same value of the given metadata parameter to
+    /// the same replica.
+    ///
+    /// Inputs without this parameter are distributed round-robin.
+    Key(String),
+}
+
+/// Internal representation for the input distribution of replicas.
+///
+/// This enum is used for serde serialization/deserialization and allows
+/// the distribution to be specified as either a strategy name or a
+/// `key` object.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(untagged)]
+pub enum DistributionDef {
+    /// A named strategy, e.g. `round-robin`.
+    Strategy(DistributionStrategy),
+    /// Key-based distribution.
+    Key {
+        /// Name of the metadata parameter to partition by.
+        key: String,
+    },
+}
+
+/// Named input distribution strategies.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum DistributionStrategy {
+    /// See [`Distribution::RoundRobin`].
+    RoundRobin,
+    /// See [`Distribution::LeastLoaded`].
+    LeastLoaded,
+}
+
+impl From<Distribution> for DistributionDef {
+    fn from(distribution: Distribution) -> Self {
+        match distribution {
+            Distribution::RoundRobin => Self::Strategy(DistributionStrategy::RoundRobin),
+            Distribution::LeastLoaded => Self::Strategy(DistributionStrategy::LeastLoaded),
+            Distribution::Key(key) => Self::Key { key },
+        }
+    }
+}
+
+impl From<DistributionDef> for Distribution {
+    fn from(value: DistributionDef) -> Self {
+        match value {
+            DistributionDef::Strategy(DistributionStrategy::RoundRobin) => Self::RoundRobin,
+            DistributionDef::Strategy(DistributionStrategy::LeastLoaded) => Self::LeastLoaded,
+            DistributionDef::Key { key } => Self::Key(key),
+        }
+    }
+}
+
+/// Condition that a dependency listed in [`depends_on`](Node::depends_on) has to reach.
+///
+/// ## YAML Example
+///
+/// ```yaml
+/// depends_on:
+///   logger: subscribed
+///   motor-driver: ready
+///   camera:
+///     output: image
+///   lidar:
+///     exec: ./check-lidar.sh --port /dev/ttyUSB0
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, JsonSchema)]
+#[serde(from = "ReadinessDef", into = "ReadinessDef")]
+pub enum Readiness {
+    /// The dependency initialized its connection to the dora daemon.
+    Subscribed,
+    /// The dependency called `mark_ready` on its node API.
+    Ready,
+    /// The dependency sent its first message on the given output.
+    Output(DataId),
+    /// The given probe command exited successfully.
+    ///
+    /// The command runs through the shell (`sh -c`, or `cmd /C` on Windows) in
+    /// the working directory of the dependency once the dependency was spawned,
+    /// and is retried until it succeeds.
+    Exec(String),
+}
+
+impl fmt::Display for Readiness {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Readiness::Subscribed => write!(f, "subscribed"),
+            Readiness::Ready => write!(f, "ready"),
+            Readiness::Output(output) => write!(f, "output `{output}`"),
+            Readiness::Exec(command) => write!(f, "exec `{command}`"),
+        }
+    }
+}
+
+/// Internal representation for readiness conditions.
+///
+/// This enum is used for serde serialization/deserialization and allows
+/// the condition to be specified as either a name or an `output`/`exec` object.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(untagged)]
+pub enum ReadinessDef {
+    /// A named condition, e.g. `ready`.
+    Condition(ReadinessCondition),
+    /// Wait for the first message on an output.
+    Output {
+        /// ID of the output of the dependency.
+        output: DataId,
+    },
+    /// Wait until a probe command succeeds.
+    Exec {
+        /// The probe command, run through the shell.
+        exec: String,
+    },
+}
+
+/// Named readiness conditions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum ReadinessCondition {
+    /// See [`Readiness::Subscribed`].
+    Subscribed,
+    /// See [`Readiness::Ready`].
+    Ready,
+}
+
+impl From<Readiness> for ReadinessDef {
+    fn from(readiness: Readiness) -> Self {
+        match readiness {
+            Readiness::Subscribed => Self::Condition(ReadinessCondition::Subscribed),
+            Readiness::Ready => Self::Condition(ReadinessCondition::Ready),
+            Readiness::Output(output) => Self::Output { output },
+            Readiness::Exec(exec) => Self::Exec { exec },
+        }
+    }
+}
+
+impl From<ReadinessDef> for Readiness {
+    fn from(value: ReadinessDef) -> Self {
+        match value {
+            ReadinessDef::Condition(ReadinessCondition::Subscribed) => Self::Subscribed,
+            ReadinessDef::Condition(ReadinessCondition::Ready) => Self::Ready,
+            ReadinessDef::Output { output } => Self::Output(output),
+            ReadinessDef::Exec { exec } => Self::Exec(exec),
+        }
+    }
+}
+
+/// Hang detection settings of a node, see [`liveness`](Node::liveness).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Liveness {
+    /// Maximum time between two heartbeats of the node.
+    ///
+    /// Given as a number with a unit of `us`, `ms`, or `s`.
+    #[serde(with = "crate::config::compact_duration")]
+    #[schemars(with = "String")]
+    pub timeout: Duration,
+    /// Send `SIGQUIT` to an unresponsive node before killing it.
+    #[serde(default)]
+    pub stack_dump: bool,
+}
+
+/// Resource limits of a node, see [`resources`](Node::resources).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Resources {
+    /// Maximum CPU time, given as a number of CPU cores, e.g. `0.5`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cpu: Option<f64>,
+    /// Maximum memory usage in bytes.
+    ///
+    /// Given as a number with an optional unit of `K`, `M`, `G`, or `T` (powers
+    /// of 1024), e.g. `512M`.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "crate::config::memory_size_option"
+    )]
+    #[schemars(with = "Option<String>")]
+    pub memory: Option<u64>,
+    /// Maximum number of processes and threads.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pids: Option<u64>,
+    /// Relative IO weight of the node between 1 and 10000, the default is 100.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub io_weight: Option<u16>,
+}
+
+/// CPU scheduling settings of a node, see [`scheduling`](Node::scheduling).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Scheduling {
+    /// Scheduling policy of the node, defaults to the policy of the daemon.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub policy: Option<SchedulingPolicy>,
+    /// Static priority between 1 and 99, required for the `fifo` and `rr` policies.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub priority: Option<u8>,
+    /// Indices of the CPU cores that the node is allowed to run on.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cpu_affinity: Vec<usize>,
+    /// Niceness between -20 (highest priority) and 19 (lowest priority).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nice: Option<i8>,
+}
+
+/// CPU scheduling policy of a node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum SchedulingPolicy {
+    /// Default time-sharing scheduling (`SCHED_OTHER`).
+    Other,
+    /// Real-time first-in, first-out scheduling (`SCHED_FIFO`).
+    Fifo,
+    /// Real-time round-robin scheduling (`SCHED_RR`).
+    Rr,
+    /// Time-sharing scheduling for CPU-intensive batch jobs (`SCHED_BATCH`).
+    Batch,
+    /// Scheduling for very low priority background jobs (`SCHED_IDLE`).
+    Idle,
+}
+
+impl SchedulingPolicy {
+    /// Whether this is a real-time policy, which requires a `priority`.
+    pub fn is_realtime(&self) -> bool {
+        matches!(self, Self::Fifo | Self::Rr)
+    }
+}
+
+impl Scheduling {
+    /// Whether applying these settings usually requires the `CAP_SYS_NICE` capability.
+    pub fn requires_privileges(&self) -> bool {
+        self.policy.is_some_and(|policy| policy.is_realtime())
+            || self.nice.is_some_and(|nice| nice < 0)
+    }
+}
+
+/// Sandbox settings of a node, see [`sandbox`](Node::sandbox).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Sandbox {
+    /// Paths that stay writable for the node, relative to its working directory.
+    ///
+    /// The paths must exist when the node is spawned.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub writable_paths: Vec<PathBuf>,
+    /// Network access of the node.
+    #[serde(default)]
+    pub network: SandboxNetwork,
+    /// Name or numeric ID of the user that the node runs as.
+    ///
+    /// Switching users requires a daemon that runs as root. Defaults to `nobody`
+    /// if the daemon runs as root, and to the user of the daemon otherwise.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+}
+
+/// Network access of a sandboxed node.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum SandboxNetwork {
+    /// No network access, the node can only reach the daemon through a private
+    /// loopback interface.
+    #[default]
+    Loopback,
+    /// No network isolation, the node uses the network of the daemon.
+    Host,
+}
+
 /// A fully resolved node with all aliases expanded and defaults applied.
 ///
 /// This type represents a node after the [`Descriptor`] has been processed
@@ -598,6 +1396,42 @@ pub struct ResolvedNode {
     #[serde(default)]
     pub deploy: Option<Deploy>,
 
+    /// Replication settings (if specified).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub replicas: Option<Replicas>,
+
+    /// Nodes that must be ready before this node is spawned.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub depends_on: BTreeMap<NodeId, Readiness>,
+
+    /// Maximum time to wait for the `depends_on` conditions.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub startup_timeout: Option<Duration>,
+
+    /// Time that the node gets to exit after it received a stop event.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stop_timeout: Option<Duration>,
+
+    /// Hang detection settings of the node.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub liveness: Option<Liveness>,
+
+    /// Resource limits of the node.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resources: Option<Resources>,
+
+    /// CPU scheduling settings of the node.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scheduling: Option<Scheduling>,
+
+    /// Sandbox settings of the node.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sandbox: Option<Sandbox>,
+
+    /// Whether the node is moved to another daemon when its daemon is lost.
+    #[serde(default)]
+    pub failover: bool,
+
     /// The kind of this node, determining its execution model.
     #[serde(flatten)]
     pub kind: CoreNodeKind,
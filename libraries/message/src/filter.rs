@@ -0,0 +1,358 @@
+//! Edge options for limiting the messages that are delivered on an input.
+
+use core::fmt;
+use std::{cmp::Ordering, str::FromStr, time::Duration};
+
+use crate::metadata::{MetadataParameters, Parameter};
+
+/// Maximum delivery rate of an input, e.g. `5Hz`.
+///
+/// Stored as the minimum interval between two delivered messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rate {
+    pub min_interval: Duration,
+}
+
+impl FromStr for Rate {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let trimmed = s.trim();
+        let (value, factor) = if let Some(value) = trimmed.strip_suffix("kHz") {
+            (value, 1000.0)
+        } else if let Some(value) = trimmed.strip_suffix("Hz") {
+            (value, 1.0)
+        } else {
+            return Err(format!(
+                "rate must be given in `Hz` or `kHz`, e.g. `5Hz` (got `{s}`)"
+            ));
+        };
+        let hz: f64 = value
+            .trim()
+            .parse()
+            .map_err(|_| format!("rate must be a number followed by `Hz` (got `{s}`)"))?;
+        let hz = hz * factor;
+        if !hz.is_finite() || hz <= 0.0 {
+            return Err(format!("rate must be positive (got `{s}`)"));
+        }
+        let min_interval = Duration::try_from_secs_f64(1.0 / hz)
+            .map_err(|_| format!("rate is too small (got `{s}`)"))?;
+        if min_interval.is_zero() {
+            return Err(format!("rate is too large (got `{s}`)"));
+        }
+        Ok(Self { min_interval })
+    }
+}
+
+impl fmt::Display for Rate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let hz = 1.0 / self.min_interval.as_secs_f64();
+        // round to avoid printing float artifacts such as `4.999999999Hz`
+        let rounded = (hz * 1e6).round() / 1e6;
+        write!(f, "{rounded}Hz")
+    }
+}
+
+impl serde::Serialize for Rate {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for Rate {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let string = String::deserialize(deserializer)?;
+        string.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// A predicate on the metadata parameters of a message.
+///
+/// The predicate consists of one or more comparisons joined by `&&`. Each
+/// comparison has the form `parameters.<key> <op> <value>`, where `<op>` is
+/// one of `==`, `!=`, `<`, `<=`, `>`, `>=` and `<value>` is a quoted string,
+/// a number, or `true`/`false`. For example:
+///
+/// ```text
+/// parameters.label == 'person' && parameters.score >= 0.5
+/// ```
+///
+/// Comparisons on missing parameters or parameters of a different type
+/// evaluate to `false`.
+#[derive(Debug, Clone)]
+pub struct InputFilter {
+    expression: String,
+    conditions: Vec<Condition>,
+}
+
+#[derive(Debug, Clone)]
+struct Condition {
+    key: String,
+    op: Op,
+    value: Literal,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Debug, Clone)]
+enum Literal {
+    String(String),
+    Number(f64),
+    Bool(bool),
+}
+
+impl InputFilter {
+    /// Returns `true` if the given metadata parameters satisfy the predicate.
+    pub fn matches(&self, parameters: &MetadataParameters) -> bool {
+        self.conditions.iter().all(|c| c.matches(parameters))
+    }
+}
+
+impl Condition {
+    fn matches(&self, parameters: &MetadataParameters) -> bool {
+        let Some(parameter) = parameters.get(&self.key) else {
+            return false;
+        };
+        let ordering = match (parameter, &self.value) {
+            (Parameter::String(a), Literal::String(b)) => a.as_str().cmp(b.as_str()),
+            (Parameter::Integer(a), Literal::Number(b)) => match (*a as f64).partial_cmp(b) {
+                Some(ordering) => ordering,
+                None => return false,
+            },
+            (Parameter::Float(a), Literal::Number(b)) => match a.partial_cmp(b) {
+                Some(ordering) => ordering,
+                None => return false,
+            },
+            (Parameter::Bool(a), Literal::Bool(b)) => match self.op {
+                Op::Eq | Op::Ne => a.cmp(b),
+                _ => return false,
+            },
+            _ => return false,
+        };
+        match self.op {
+            Op::Eq => ordering == Ordering::Equal,
+            Op::Ne => ordering != Ordering::Equal,
+            Op::Lt => ordering == Ordering::Less,
+            Op::Le => ordering != Ordering::Greater,
+            Op::Gt => ordering == Ordering::Greater,
+            Op::Ge => ordering != Ordering::Less,
+        }
+    }
+}
+
+impl FromStr for InputFilter {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let conditions = split_conditions(s)
+            .and_then(|conditions| {
+                conditions
+                    .into_iter()
+                    .map(|c| parse_condition(c.trim()))
+                    .collect()
+            })
+            .map_err(|err| format!("invalid filter `{s}`: {err}"))?;
+        Ok(Self {
+            expression: s.trim().to_owned(),
+            conditions,
+        })
+    }
+}
+
+/// Splits the expression at the `&&` operators that are not part of a quoted string.
+fn split_conditions(s: &str) -> Result<Vec<&str>, String> {
+    let mut conditions = Vec::new();
+    let mut start = 0;
+    let mut quote = None;
+    let mut chars = s.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '\'' | '"') => quote = Some(c),
+            (None, '&') if chars.next_if(|(_, c)| *c == '&').is_some() => {
+                conditions.push(&s[start..i]);
+                start = i + 2;
+            }
+            (None, _) => {}
+        }
+    }
+    if let Some(q) = quote {
+        return Err(format!("unterminated string, missing closing `{q}`"));
+    }
+    conditions.push(&s[start..]);
+    Ok(conditions)
+}
+
+fn parse_condition(s: &str) -> Result<Condition, String> {
+    let rest = s
+        .strip_prefix("parameters.")
+        .ok_or_else(|| format!("condition must start with `parameters.` (got `{s}`)"))?;
+    let key_len = rest
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '-'))
+        .unwrap_or(rest.len());
+    let (key, rest) = rest.split_at(key_len);
+    if key.is_empty() {
+        return Err(format!("missing parameter name in `{s}`"));
+    }
+    let rest = rest.trim_start();
+    let (op, value) = [
+        ("==", Op::Eq),
+        ("!=", Op::Ne),
+        ("<=", Op::Le),
+        (">=", Op::Ge),
+        ("<", Op::Lt),
+        (">", Op::Gt),
+    ]
+    .into_iter()
+    .find_map(|(token, op)| rest.strip_prefix(token).map(|value| (op, value)))
+    .ok_or_else(|| format!("expected comparison operator after `parameters.{key}`"))?;
+    Ok(Condition {
+        key: key.to_owned(),
+        op,
+        value: parse_literal(value.trim())?,
+    })
+}
+
+fn parse_literal(s: &str) -> Result<Literal, String> {
+    let quoted = ['\'', '"']
+        .into_iter()
+        .find_map(|q| s.strip_prefix(q).and_then(|s| s.strip_suffix(q)));
+    if let Some(string) = quoted {
+        return Ok(Literal::String(string.to_owned()));
+    }
+    match s {
+        "true" => Ok(Literal::Bool(true)),
+        "false" => Ok(Literal::Bool(false)),
+        other => other.parse().map(Literal::Number).map_err(|_| {
+            format!("expected quoted string, number, or boolean value (got `{other}`)")
+        }),
+    }
+}
+
+impl PartialEq for InputFilter {
+    fn eq(&self, other: &Self) -> bool {
+        self.expression == other.expression
+    }
+}
+
+impl Eq for InputFilter {}
+
+impl fmt::Display for InputFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.expression)
+    }
+}
+
+impl serde::Serialize for InputFilter {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for InputFilter {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let string = String::deserialize(deserializer)?;
+        string.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{InputFilter, Rate};
+    use crate::metadata::{MetadataParameters, Parameter};
+
+    fn parameters(values: &[(&str, Parameter)]) -> MetadataParameters {
+        values
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn parse_rate() {
+        let rate: Rate = "5Hz".parse().unwrap();
+        assert_eq!(rate.min_interval, Duration::from_millis(200));
+        assert_eq!(rate.to_string(), "5Hz");
+        let rate: Rate = "0.5 Hz".parse().unwrap();
+        assert_eq!(rate.min_interval, Duration::from_secs(2));
+        assert!("5".parse::<Rate>().is_err());
+        assert!("0Hz".parse::<Rate>().is_err());
+        assert!("1e-300Hz".parse::<Rate>().is_err());
+        assert!("1e300Hz".parse::<Rate>().is_err());
+    }
+
+    #[test]
+    fn filter_matches_parameters() {
+        let filter: InputFilter = "parameters.label == 'person' && parameters.score >= 0.5"
+            .parse()
+            .unwrap();
+        let person = parameters(&[
+            ("label", Parameter::String("person".into())),
+            ("score", Parameter::Float(0.9)),
+        ]);
+        let car = parameters(&[
+            ("label", Parameter::String("car".into())),
+            ("score", Parameter::Float(0.9)),
+        ]);
+        let unsure = parameters(&[
+            ("label", Parameter::String("person".into())),
+            ("score", Parameter::Integer(0)),
+        ]);
+        assert!(filter.matches(&person));
+        assert!(!filter.matches(&car));
+        assert!(!filter.matches(&unsure));
+        assert!(!filter.matches(&Default::default()));
+    }
+
+    #[test]
+    fn quoted_strings_may_contain_operators() {
+        let filter: InputFilter = "parameters.label == 'a && b' && parameters.id != \"&&\""
+            .parse()
+            .unwrap();
+        let matching = parameters(&[
+            ("label", Parameter::String("a && b".into())),
+            ("id", Parameter::String("x".into())),
+        ]);
+        assert!(filter.matches(&matching));
+        assert!(!filter.matches(&parameters(&[("label", Parameter::String("a".into()))])));
+        assert!(
+            "parameters.label == 'a && b"
+                .parse::<InputFilter>()
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn invalid_filters_are_rejected() {
+        assert!("label == 'person'".parse::<InputFilter>().is_err());
+        assert!(
+            "parameters.label ~ 'person'"
+                .parse::<InputFilter>()
+                .is_err()
+        );
+        assert!("parameters.label == person".parse::<InputFilter>().is_err());
+    }
+}
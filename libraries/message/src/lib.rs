@@ -11,9 +11,12 @@ pub use uhlc;
 
 pub mod common;
 pub mod config;
+pub mod debug;
 pub mod descriptor;
+pub mod filter;
 pub mod id;
 pub mod metadata;
+pub mod service;
 
 pub mod coordinator_to_daemon;
 pub mod daemon_to_coordinator;
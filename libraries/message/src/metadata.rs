@@ -68,6 +68,13 @@ impl Metadata {
 /// Additional metadata that can be sent as part of output messages.
 pub type MetadataParameters = BTreeMap<String, Parameter>;
 
+/// Metadata parameter that names the codec of a compressed message.
+///
+/// Set by the sending daemon for outputs with a
+/// [`compression`](crate::descriptor::Node::compression) setting and removed
+/// again when the message is decompressed, so nodes never observe it.
+pub const COMPRESSION_PARAMETER: &str = "__dora_compression";
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ArrowTypeInfo {
     pub data_type: DataType,
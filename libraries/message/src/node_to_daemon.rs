@@ -33,6 +33,35 @@ pub enum DaemonRequest {
     NodeConfig {
         node_id: NodeId,
     },
+    /// Reports that the node finished its initialization.
+    ///
+    /// Nodes that wait for this node to be `ready` through their `depends_on`
+    /// field are spawned afterwards.
+    MarkReady,
+    /// Requests more time to exit after the node received a stop event.
+    ///
+    /// The daemon restarts the stop timeout of the node with the given duration,
+    /// e.g. to allow the node to finish flushing its files.
+    ExtendStopTimeout {
+        duration: std::time::Duration,
+    },
+    /// Signals that the event loop of the node is still running.
+    ///
+    /// Only sent if the node has a `liveness` configuration. The daemon considers
+    /// the node unresponsive if no heartbeat arrives within the liveness timeout.
+    Heartbeat,
+    /// Reports that the node handled all of its events and waits for new ones.
+    ///
+    /// Only sent when the simulated clock is enabled. The daemon advances the
+    /// simulated time once all nodes are idle.
+    ReportIdle {
+        /// Number of outputs that the node sent so far.
+        sent_outputs: u64,
+        /// Simulated time at which the node should be woken up through a
+        /// [`SimulatedTimeout`](crate::daemon_to_node::NodeEvent::SimulatedTimeout)
+        /// event, if any.
+        wake_at: Option<uhlc::NTP64>,
+    },
 }
 
 impl DaemonRequest {
@@ -41,7 +70,9 @@ impl DaemonRequest {
         match self {
             DaemonRequest::SendMessage { .. }
             | DaemonRequest::NodeConfig { .. }
-            | DaemonRequest::ReportDropTokens { .. } => false,
+            | DaemonRequest::ReportDropTokens { .. }
+            | DaemonRequest::ReportIdle { .. }
+            | DaemonRequest::Heartbeat => false,
             DaemonRequest::Register(NodeRegisterRequest { .. })
             | DaemonRequest::Subscribe
             | DaemonRequest::CloseOutputs(_)
@@ -49,7 +80,9 @@ impl DaemonRequest {
             | DaemonRequest::NextEvent { .. }
             | DaemonRequest::SubscribeDrop
             | DaemonRequest::NextFinishedDropTokens
-            | DaemonRequest::EventStreamDropped => true,
+            | DaemonRequest::EventStreamDropped
+            | DaemonRequest::MarkReady
+            | DaemonRequest::ExtendStopTimeout { .. } => true,
         }
     }
 
@@ -66,7 +99,11 @@ impl DaemonRequest {
             | DaemonRequest::NextFinishedDropTokens
             | DaemonRequest::ReportDropTokens { .. }
             | DaemonRequest::SendMessage { .. }
-            | DaemonRequest::EventStreamDropped => false,
+            | DaemonRequest::EventStreamDropped
+            | DaemonRequest::ReportIdle { .. }
+            | DaemonRequest::Heartbeat
+            | DaemonRequest::MarkReady
+            | DaemonRequest::ExtendStopTimeout { .. } => false,
         }
     }
 }
@@ -0,0 +1,228 @@
+//! Request/reply communication and long-running actions between nodes.
+//!
+//! A node provides services by listing them in its `services` field and
+//! actions by listing them in its `actions` field. Clients list the services
+//! and actions they use as `<server>/<name>` in their `calls` field.
+//!
+//! Services are implemented on top of normal inputs and outputs: for each call,
+//! the dataflow gets a hidden request edge from the client to the server and a
+//! hidden reply edge back. This way, requests and replies are routed like any
+//! other message, also across machines. The IDs of these hidden inputs and
+//! outputs start with [`REQUEST_PREFIX`] or [`REPLY_PREFIX`].
+//!
+//! Actions use the same edges: goals and cancel requests travel on the request
+//! edge, feedback and results on the reply edge. The kind of each action message
+//! is stored in the [`ACTION_MESSAGE_PARAMETER`] and the goal ID in the
+//! [`REQUEST_ID_PARAMETER`].
+
+use core::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::id::{DataId, NodeId};
+
+/// Prefix of the hidden inputs and outputs that carry service requests.
+pub const REQUEST_PREFIX: &str = "__request/";
+/// Prefix of the hidden inputs and outputs that carry service replies.
+pub const REPLY_PREFIX: &str = "__reply/";
+
+/// Metadata parameter that correlates a reply with its request.
+pub const REQUEST_ID_PARAMETER: &str = "request_id";
+/// Metadata parameter of requests that sets the timeout in milliseconds.
+pub const REQUEST_TIMEOUT_PARAMETER: &str = "request_timeout_ms";
+/// Metadata parameter of replies that failed, e.g. because of a timeout.
+pub const SERVICE_ERROR_PARAMETER: &str = "service_error";
+/// Metadata parameter that holds the [`ActionMessage`] kind of action messages.
+pub const ACTION_MESSAGE_PARAMETER: &str = "action_message";
+/// Metadata parameter of action results that holds the final [`GoalStatus`].
+pub const GOAL_STATUS_PARAMETER: &str = "goal_status";
+
+/// Kind of a message sent as part of an action.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ActionMessage {
+    /// A new goal, sent from the client to the server.
+    Goal,
+    /// A request to cancel a goal, sent from the client to the server.
+    Cancel,
+    /// Intermediate feedback on a goal, sent from the server to the client.
+    Feedback,
+    /// The final result of a goal, sent from the server to the client.
+    Result,
+}
+
+impl ActionMessage {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ActionMessage::Goal => "goal",
+            ActionMessage::Cancel => "cancel",
+            ActionMessage::Feedback => "feedback",
+            ActionMessage::Result => "result",
+        }
+    }
+}
+
+impl FromStr for ActionMessage {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "goal" => Ok(Self::Goal),
+            "cancel" => Ok(Self::Cancel),
+            "feedback" => Ok(Self::Feedback),
+            "result" => Ok(Self::Result),
+            other => Err(format!("unknown action message `{other}`")),
+        }
+    }
+}
+
+/// Final status of an action goal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GoalStatus {
+    /// The server reached the goal.
+    Succeeded,
+    /// The server gave up on the goal, or the server stopped before finishing it.
+    Aborted,
+    /// The goal was canceled on request of the client.
+    Canceled,
+}
+
+impl GoalStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            GoalStatus::Succeeded => "succeeded",
+            GoalStatus::Aborted => "aborted",
+            GoalStatus::Canceled => "canceled",
+        }
+    }
+}
+
+impl FromStr for GoalStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "succeeded" => Ok(Self::Succeeded),
+            "aborted" => Ok(Self::Aborted),
+            "canceled" => Ok(Self::Canceled),
+            other => Err(format!("unknown goal status `{other}`")),
+        }
+    }
+}
+
+impl fmt::Display for GoalStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A service or action used by a client node, given as `<server>/<service>`.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ServiceCall {
+    pub server: NodeId,
+    pub service: DataId,
+}
+
+impl ServiceCall {
+    /// The hidden output of the client that sends requests to the server.
+    pub fn request_output(&self) -> DataId {
+        hidden_id(REQUEST_PREFIX, &self.server, &self.service)
+    }
+
+    /// The hidden input of the server that receives requests from `client`.
+    pub fn request_input(&self, client: &NodeId) -> DataId {
+        hidden_id(REQUEST_PREFIX, client, &self.service)
+    }
+
+    /// The hidden output of the server that sends replies to `client`.
+    pub fn reply_output(&self, client: &NodeId) -> DataId {
+        reply_output(client, &self.service)
+    }
+
+    /// The hidden input of the client that receives replies from the server.
+    pub fn reply_input(&self) -> DataId {
+        hidden_id(REPLY_PREFIX, &self.server, &self.service)
+    }
+}
+
+fn hidden_id(prefix: &str, node: &NodeId, service: &DataId) -> DataId {
+    DataId::from(format!("{prefix}{node}/{service}"))
+}
+
+/// The hidden output of a server that sends replies for `service` to `client`.
+pub fn reply_output(client: &NodeId, service: &DataId) -> DataId {
+    hidden_id(REPLY_PREFIX, client, service)
+}
+
+/// Splits a hidden input ID with the given prefix into node and service.
+///
+/// For request inputs, the node is the client. For reply inputs, it is the server.
+pub fn parse_hidden_input(prefix: &str, input_id: &DataId) -> Option<(NodeId, DataId)> {
+    let (node, service) = input_id.strip_prefix(prefix)?.split_once('/')?;
+    Some((NodeId::from(node.to_owned()), DataId::from(service)))
+}
+
+/// Returns `true` if the given input or output ID belongs to a service or action.
+pub fn is_service_id(id: &DataId) -> bool {
+    id.starts_with(REQUEST_PREFIX) || id.starts_with(REPLY_PREFIX)
+}
+
+impl fmt::Display for ServiceCall {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.server, self.service)
+    }
+}
+
+impl FromStr for ServiceCall {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once('/') {
+            Some((server, service)) if !server.is_empty() && !service.is_empty() => Ok(Self {
+                server: server.to_owned().into(),
+                service: service.into(),
+            }),
+            _ => Err(format!(
+                "service call must be `<node>/<service>` (got `{s}`)"
+            )),
+        }
+    }
+}
+
+impl Serialize for ServiceCall {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for ServiceCall {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let string = String::deserialize(deserializer)?;
+        string.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{REPLY_PREFIX, REQUEST_PREFIX, ServiceCall, parse_hidden_input};
+
+    #[test]
+    fn hidden_ids_match_on_both_sides() {
+        let call: ServiceCall = "planner/plan_path".parse().unwrap();
+        let client = "robot".to_string().into();
+
+        assert_eq!(
+            call.request_output().as_str(),
+            "__request/planner/plan_path"
+        );
+        assert_eq!(
+            parse_hidden_input(REQUEST_PREFIX, &call.request_input(&client)),
+            Some((client.clone(), "plan_path".into()))
+        );
+        assert_eq!(
+            parse_hidden_input(REPLY_PREFIX, &call.reply_input()),
+            Some(("planner".to_string().into(), "plan_path".into()))
+        );
+        assert!("plan_path".parse::<ServiceCall>().is_err());
+    }
+}